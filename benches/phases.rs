@@ -0,0 +1,116 @@
+//! Criterion benchmarks of tokenizing, parsing, and executing each
+//! checked-in workload, executing on both backends, so a slowdown can be
+//! pinned on one phase. Run with `cargo bench`; Criterion keeps each run's
+//! results in `target/criterion` and reports how the next run compares.
+//! `cargo bench -- parse/` runs one group, and `--save-baseline` and
+//! `--baseline` compare against a named run. For a single script,
+//! `slimescript bench` times the same phases. The `large` group parses and
+//! drops a generated script of about 5 MB, where how the tree is allocated
+//! shows.
+
+use std::hint::black_box;
+use std::io;
+
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use slimescript::{Backend, Interpreter};
+
+const WORKLOADS: [(&str, &str); 6] = [
+    ("tokens", include_str!("tokens.slime")),
+    ("nested", include_str!("nested.slime")),
+    ("sort", include_str!("sort.slime")),
+    ("concat", include_str!("concat.slime")),
+    ("append", include_str!("append.slime")),
+    ("variables", include_str!("variables.slime")),
+];
+
+fn lex(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lex");
+    for (name, source) in WORKLOADS {
+        group.bench_function(name, |b| b.iter(|| slimescript::tokenize(black_box(source)).expect("workloads lex")));
+    }
+    group.finish();
+}
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    for (name, source) in WORKLOADS {
+        let tokens = slimescript::tokenize(source).expect("workloads lex");
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || tokens.clone(),
+                |tokens| slimescript::parse_tokens(tokens).expect("workloads parse"),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+/// A script of about `size` bytes: functions full of nested arithmetic,
+/// calls, indexing, and closures, like generated code tends to be.
+fn generated(size: usize) -> String {
+    let mut source = String::with_capacity(size + 1024);
+    let mut n = 0;
+    while source.len() < size {
+        source.push_str(&format!(
+            "function f{n}(a, b) {{\n    let xs = [a, b, a * b, (a + {n}) % 7];\n    \
+             if a > b && xs[2] != {n} {{ return map(xs, function(x) {{ return x * 2 + a; }}); }}\n    \
+             let m = {{ \"k\": xs[0] + xs[1] * (b - 3), \"v\": f{n}_helper(a, b, xs) }};\n    \
+             return m.k + len(xs) - (a / (b + 1)) * {n};\n}}\n"
+        ));
+        n += 1;
+    }
+    source
+}
+
+fn large(c: &mut Criterion) {
+    let mut group = c.benchmark_group("large");
+    group.sample_size(10);
+    let source = generated(5 << 20);
+    let tokens = slimescript::tokenize(&source).expect("the generated script lexes");
+    group.bench_function("parse", |b| {
+        b.iter_batched(
+            || tokens.clone(),
+            |tokens| slimescript::parse_tokens(tokens).expect("the generated script parses"),
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("drop", |b| {
+        b.iter_batched(
+            || slimescript::parse_tokens(tokens.clone()).expect("the generated script parses"),
+            drop,
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+/// Times running each workload's statements on `backend`, each run in a
+/// fresh interpreter whose output is thrown away, so one run's globals
+/// don't carry over into the next.
+fn execute(c: &mut Criterion, group: &str, backend: Backend) {
+    let mut group = c.benchmark_group(group);
+    group.sample_size(20);
+    for (name, source) in WORKLOADS {
+        let program = slimescript::parse(source).expect("workloads parse");
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || Interpreter::with_io(io::empty(), io::sink()).with_backend(backend),
+                |mut interpreter| interpreter.eval_statements(&program).expect("workloads run without errors"),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn interpret(c: &mut Criterion) {
+    execute(c, "interpret", Backend::TreeWalker);
+}
+
+fn vm(c: &mut Criterion) {
+    execute(c, "vm", Backend::Vm);
+}
+
+criterion_group!(phases, lex, parse, large, interpret, vm);
+criterion_main!(phases);
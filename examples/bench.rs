@@ -0,0 +1,70 @@
+//! Compares the tree-walking and bytecode backends on a recursive, a
+//! loop-heavy, and a variable-heavy program. Run with `cargo run --release --example bench`.
+
+use std::time::{Duration, Instant};
+
+use slimescript::{Backend, Interpreter};
+
+const FIB: &str = "
+function fib(n) {
+    if n < 2 { return n; }
+    return fib(n - 1) + fib(n - 2);
+}
+fib(25);
+";
+
+const LOOP: &str = "
+let total = 0;
+for (let i = 0; i < 1000000; i = i + 1) {
+    total = total + i * 2 % 7;
+}
+total;
+";
+
+const VARIABLES: &str = "
+let a = 1;
+let b = 2;
+let c = 3;
+function sum(x, y, z) {
+    let s = x + y;
+    s = s + z;
+    return s;
+}
+let total = 0;
+for (let i = 0; i < 200000; i = i + 1) {
+    let d = a * b - c;
+    total = total + sum(a, b, c) + d;
+}
+total;
+";
+
+const RUNS: usize = 5;
+
+/// The fastest of several runs, each in a fresh interpreter.
+fn time(backend: Backend, source: &str) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let mut interpreter = Interpreter::new().with_backend(backend);
+            let start = Instant::now();
+            let result = interpreter.eval(source).expect("benchmark programs run without errors");
+            let elapsed = start.elapsed();
+            std::hint::black_box(result);
+            elapsed
+        })
+        .min()
+        .expect("RUNS is not zero")
+}
+
+fn main() {
+    for (name, source) in [("fib(25)", FIB), ("arithmetic loop", LOOP), ("variable access", VARIABLES)] {
+        let tree = time(Backend::TreeWalker, source);
+        let vm = time(Backend::Vm, source);
+        println!(
+            "{:<16} tree-walker {:>8.1?}   vm {:>8.1?}   speedup {:.2}x",
+            name,
+            tree,
+            vm,
+            tree.as_secs_f64() / vm.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,23 @@
+//! Checks that `complete` takes any source, finished or not, at any offset,
+//! and only offers words that start with the part of the word typed before
+//! the offset.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use slimescript::complete;
+
+fuzz_target!(|input: (&str, u16)| {
+    let (source, offset) = input;
+    let offset = offset as usize % (source.len() + 1);
+    let completions = complete(source, offset);
+    if !source.is_char_boundary(offset) {
+        assert!(completions.is_empty());
+        return;
+    }
+    let typed = &source[..offset];
+    let prefix = &typed[typed.trim_end_matches(|c: char| c.is_alphanumeric() || c == '_').len()..];
+    for completion in &completions {
+        assert!(completion.label.starts_with(prefix), "{:?} for {:?}", completion, prefix);
+    }
+});
@@ -0,0 +1,36 @@
+//! Checks that `escape_string` and `unescape_string` agree with the lexer:
+//! any text escaped lexes back to a string of exactly that text, and any
+//! input `unescape_string` reads is a string literal the lexer reads to
+//! the same value.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use slimescript::lexer::{Lexer, TokenType, escape_string, unescape_string};
+
+// the value of `source` lexed as a single string literal and nothing else
+fn lex_literal(source: &str) -> Option<String> {
+    let tokens = Lexer::new(source).tokenize().ok()?;
+    match &tokens[..] {
+        [string, eof] if string.token_type == TokenType::String && eof.token_type == TokenType::EOF => {
+            (string.span.start == 0 && string.span.end == source.len()).then(|| string.value.clone())
+        }
+        _ => None,
+    }
+}
+
+fuzz_target!(|text: &str| {
+    let literal = escape_string(text);
+    assert!(literal.chars().all(|c| c == ' ' || c.is_ascii_graphic()), "{}", literal);
+    assert_eq!(lex_literal(&literal).as_deref(), Some(text));
+    assert_eq!(unescape_string(&literal).as_deref(), Ok(text));
+
+    match unescape_string(text) {
+        Ok(value) => assert_eq!(lex_literal(text), Some(value)),
+        Err(error) => {
+            assert!(lex_literal(text).is_none(), "{}", error);
+            assert!((error.range.start < error.range.end || text.is_empty()) && error.range.end <= text.len(), "{}", error);
+            assert!(text.is_char_boundary(error.range.start) && text.is_char_boundary(error.range.end));
+        }
+    }
+});
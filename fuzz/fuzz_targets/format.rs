@@ -0,0 +1,23 @@
+//! Checks that formatting a program keeps its tokens, comments included,
+//! and that formatting the result again changes nothing. Giving it the
+//! programs in `benches/` as a starting corpus covers real code:
+//! `cargo +nightly fuzz run format fuzz/corpus/format benches`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use slimescript::formatter::format_source;
+use slimescript::lexer::{Lexer, TokenType};
+
+fuzz_target!(|source: &str| {
+    let Ok(formatted) = format_source(source) else {
+        return;
+    };
+    assert_eq!(tokens(&formatted), tokens(source));
+    assert_eq!(format_source(&formatted).unwrap(), formatted);
+});
+
+fn tokens(source: &str) -> Vec<(TokenType, String)> {
+    let tokens = Lexer::with_comments(source).tokenize().expect("the formatter lexed it");
+    tokens.into_iter().map(|token| (token.token_type, token.value)).collect()
+}
@@ -0,0 +1,35 @@
+//! Checks that `LineIndex` converts offsets to positions and back to the
+//! same offsets, counting columns either way, and that it agrees with the
+//! lines and columns the lexer gives its tokens.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use slimescript::lexer::Lexer;
+use slimescript::source::{Columns, LineIndex};
+
+fuzz_target!(|source: &str| {
+    for columns in [Columns::Chars, Columns::Utf16] {
+        let lines = LineIndex::new(source).with_columns(columns);
+        for offset in 0..=source.len() {
+            if !source.is_char_boundary(offset) {
+                continue;
+            }
+            let (line, column) = lines.offset_to_position(offset);
+            let back = lines.position_to_offset(line, column);
+            // the `\n` of a `\r\n` is at the same position as its `\r`
+            let crlf = source[offset..].starts_with('\n') && source[..offset].ends_with('\r');
+            let expected = if crlf { offset - 1 } else { offset };
+            assert_eq!(back, Some(expected), "offset {} is at {}:{}", offset, line, column);
+        }
+        assert_eq!(lines.position_to_offset(0, 1), None);
+        assert_eq!(lines.position_to_offset(lines.line_count() + 1, 1), None);
+    }
+
+    let lines = LineIndex::new(source);
+    let (tokens, _) = Lexer::with_comments(source).tokenize_all();
+    for token in tokens {
+        let position = lines.offset_to_position(token.span.start);
+        assert_eq!(position, (token.span.line, token.span.column), "{:?}", token);
+    }
+});
@@ -0,0 +1,25 @@
+//! Checks that every number reads back from the text it's printed as: with
+//! `parse_number` for finite ones, after the sign, and that the lexer takes
+//! that text as a single number token, so it also works as a literal.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use slimescript::lexer::{Lexer, TokenType, parse_number};
+use slimescript::value::format_number;
+
+fuzz_target!(|bits: u64| {
+    let n = f64::from_bits(bits);
+    let text = format_number(n);
+    if !n.is_finite() {
+        assert!(["nan", "inf", "-inf"].contains(&text.as_str()), "{} printed as {}", n, text);
+        return;
+    }
+    let magnitude = text.strip_prefix('-').unwrap_or(&text);
+    assert_eq!(parse_number(magnitude), Some(n.abs()), "{} printed as {}", n, text);
+
+    let tokens = Lexer::new(magnitude).tokenize().expect("printed numbers lex");
+    assert_eq!(tokens.len(), 2, "{} lexes as more than one token", magnitude);
+    assert_eq!(tokens[0].token_type, TokenType::Number);
+    assert_eq!(tokens[0].value, magnitude);
+});
@@ -0,0 +1,41 @@
+//! Checks that relexing after an edit gives the same tokens as lexing the
+//! edited text from scratch, with and without comments.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use slimescript::lexer::{Lexer, TextEdit, Token};
+
+fuzz_target!(|input: (&str, usize, usize, &str)| {
+    let (old, start, end, new_text) = input;
+    let mut start = start % (old.len() + 1);
+    let mut end = end % (old.len() + 1);
+    if start > end {
+        (start, end) = (end, start);
+    }
+    if !old.is_char_boundary(start) || !old.is_char_boundary(end) {
+        return;
+    }
+    let new = format!("{}{}{}", &old[..start], new_text, &old[end..]);
+    let edit = TextEdit {
+        range: start..end,
+        new_text: new_text.to_string(),
+    };
+
+    let old_tokens = Lexer::new(old).tokenize_all().0;
+    let relexed = Lexer::new(&new).relex(&old_tokens, edit.clone());
+    assert_eq!(describe(&relexed), describe(&Lexer::new(&new).tokenize_all().0));
+
+    let old_tokens = Lexer::with_comments(old).tokenize_all().0;
+    let relexed = Lexer::with_comments(&new).relex(&old_tokens, edit);
+    assert_eq!(describe(&relexed), describe(&Lexer::with_comments(&new).tokenize_all().0));
+});
+
+/// The tokens' types, values, and positions, where they end included,
+/// which `Token` can't compare itself.
+fn describe(tokens: &[Token]) -> Vec<String> {
+    tokens
+        .iter()
+        .map(|token| format!("{} at {:?} to {:?}", token, token.span, token.end()))
+        .collect()
+}
@@ -0,0 +1,37 @@
+//! Checks that the tokens `Lexer::with_trivia` makes of a program join back
+//! into exactly that program, and that apart from whitespace and comments
+//! they're the tokens the parser is given, with `#` comments allowed or
+//! not. Giving it the programs in `benches/` as a starting corpus covers
+//! real code:
+//! `cargo +nightly fuzz run round_trip fuzz/corpus/round_trip benches`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use slimescript::lexer::{Lexer, LexerConfig, TokenType, tokens_to_source};
+
+fuzz_target!(|source: &str| {
+    check(source, LexerConfig::default());
+    check(source, LexerConfig { hash_comments: true, ..LexerConfig::default() });
+});
+
+fn check(source: &str, config: LexerConfig) {
+    let Ok(tokens) = Lexer::with_trivia(source).with_config(config).tokenize() else {
+        assert!(Lexer::new(source).with_config(config).tokenize().is_err());
+        return;
+    };
+    assert_eq!(tokens_to_source(&tokens), source);
+    for token in &tokens {
+        assert_eq!(token.raw(), &source[token.span.start..token.span.end], "{}", token);
+    }
+
+    let plain = Lexer::new(source).with_config(config).tokenize().expect("the lexer keeping trivia took it");
+    let kept: Vec<_> = tokens
+        .iter()
+        .filter(|token| !matches!(token.token_type, TokenType::Whitespace | TokenType::Comment))
+        .collect();
+    assert_eq!(kept.len(), plain.len());
+    for (kept, plain) in kept.iter().zip(&plain) {
+        assert_eq!((&kept.token_type, &kept.value, kept.span), (&plain.token_type, &plain.value, plain.span));
+    }
+}
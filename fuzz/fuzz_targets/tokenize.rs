@@ -0,0 +1,58 @@
+//! Checks that lexing never panics, and that every span it reports, for a
+//! token, an error, or a warning, lies inside the input on character
+//! boundaries, with token spans in order and never overlapping. Each
+//! token's line and column, and those of its end, must agree with its byte
+//! offsets, with `EOF` at the end of the input whatever trails the last
+//! token. Also checks that highlighting covers every byte of the input
+//! exactly once.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use slimescript::highlight::highlight;
+use slimescript::lexer::{Lexer, Span, Token, TokenType};
+
+fuzz_target!(|source: &str| {
+    match Lexer::new(source).tokenize() {
+        Ok(tokens) => {
+            assert!(tokens.last().is_some_and(|token| token.token_type == TokenType::EOF));
+            check_tokens(source, &tokens);
+        }
+        Err(error) => check_span(source, error.span),
+    }
+
+    let mut lexer = Lexer::with_comments(source);
+    let (tokens, errors) = lexer.tokenize_all();
+    check_tokens(source, &tokens);
+    for error in errors.iter().chain(lexer.warnings()) {
+        check_span(source, error.span);
+    }
+
+    let mut previous_end = 0;
+    for span in highlight(source) {
+        assert!(span.start == previous_end && span.start < span.end, "{:?} leaves a gap or overlaps", span);
+        assert!(source.is_char_boundary(span.end));
+        previous_end = span.end;
+    }
+    assert_eq!(previous_end, source.len());
+});
+
+fn check_tokens(source: &str, tokens: &[Token]) {
+    let mut previous_end = 0;
+    for token in tokens {
+        check_span(source, token.span);
+        assert!(token.span.start >= previous_end, "{:?} overlaps the token before it", token);
+        let start = Span { end: token.span.start, ..token.span };
+        assert_eq!((token.line, token.column), start.end_position(source), "{:?} starts elsewhere", token);
+        assert_eq!((token.end_line, token.end_column), token.span.end_position(source), "{:?} ends elsewhere", token);
+        if token.token_type == TokenType::EOF {
+            assert_eq!(token.span.start..token.span.end, source.len()..source.len());
+        }
+        previous_end = token.span.end;
+    }
+}
+
+fn check_span(source: &str, span: Span) {
+    assert!(span.start <= span.end && span.end <= source.len(), "{:?} is outside the input", span);
+    assert!(source.is_char_boundary(span.start) && source.is_char_boundary(span.end));
+}
@@ -0,0 +1,100 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::{Rc, Weak};
+
+use indexmap::IndexMap;
+
+use crate::value::Value;
+
+/// The arrays and maps a program has made, for
+/// `InterpreterConfig::max_total_values`, each counting as one value plus
+/// one per element or entry. The running total only goes up, as containers
+/// are made or grow. Freed ones are taken off only when it passes the
+/// limit, by recounting what the containers still alive hold, so checks
+/// stay cheap while a program is well under the limit.
+#[derive(Debug, Default)]
+pub(crate) struct Allocations {
+    // every container made since the last recount, freed or not
+    containers: Vec<Container>,
+    total: usize,
+}
+
+#[derive(Debug)]
+enum Container {
+    Array(Weak<RefCell<Vec<Value>>>),
+    Map(Weak<RefCell<IndexMap<String, Value>>>),
+}
+
+impl Container {
+    // how many values it counts as, or `None` once it has been freed; one
+    // being changed right now counts as just itself
+    fn size(&self) -> Option<usize> {
+        let length = match self {
+            Container::Array(array) => array.upgrade()?.try_borrow().map_or(0, |elements| elements.len()),
+            Container::Map(map) => map.upgrade()?.try_borrow().map_or(0, |entries| entries.len()),
+        };
+        Some(length + 1)
+    }
+}
+
+impl Allocations {
+    /// Counts `value` if it's an array or map that was just made. One held
+    /// anywhere else already existed, like an array a builtin sorted in
+    /// place and returned, and isn't counted again.
+    pub(crate) fn made(&mut self, value: &Value) {
+        let container = match value {
+            Value::Array(array) if Rc::strong_count(array) == 1 => Container::Array(Rc::downgrade(array)),
+            Value::Map(map) if Rc::strong_count(map) == 1 => Container::Map(Rc::downgrade(map)),
+            _ => return,
+        };
+        self.total += container.size().unwrap_or(0);
+        self.containers.push(container);
+    }
+
+    /// Counts the arrays and maps anywhere inside `value` that was just
+    /// made along with everything in it, like a deep copy. `value` itself is
+    /// left to `made`, and one found more than once is counted once.
+    pub(crate) fn made_inside(&mut self, value: &Value) {
+        let mut seen = HashSet::new();
+        let mut pending = vec![value.clone()];
+        while let Some(value) = pending.pop() {
+            let (address, container) = match &value {
+                Value::Array(array) => (Rc::as_ptr(array) as *const (), Container::Array(Rc::downgrade(array))),
+                Value::Map(map) => (Rc::as_ptr(map) as *const (), Container::Map(Rc::downgrade(map))),
+                _ => continue,
+            };
+            if !seen.insert(address) {
+                continue;
+            }
+            match &value {
+                Value::Array(array) => pending.extend(array.borrow().iter().cloned()),
+                Value::Map(map) => pending.extend(map.borrow().values().cloned()),
+                _ => {}
+            }
+            if seen.len() > 1 {
+                self.total += container.size().unwrap_or(0);
+                self.containers.push(container);
+            }
+        }
+    }
+
+    /// Counts `added` values put into a container already counted.
+    pub(crate) fn grew(&mut self, added: usize) {
+        self.total += added;
+    }
+
+    /// Whether the values alive are within `limit`.
+    pub(crate) fn within(&mut self, limit: usize) -> bool {
+        if self.total > limit {
+            self.total = 0;
+            self.containers.retain(|container| match container.size() {
+                Some(size) => {
+                    self.total += size;
+                    true
+                }
+                None => false,
+            });
+        }
+        self.total <= limit
+    }
+}
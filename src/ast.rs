@@ -0,0 +1,569 @@
+use std::fmt;
+use std::ops::{Index, IndexMut};
+use std::rc::Rc;
+
+use crate::lexer::Span;
+use crate::symbol::Symbol;
+use crate::value::Arity;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnaryOp {
+    Negate,
+    Not,
+    // `typeof value`, the name of the value's type as a string
+    TypeOf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    // membership: an element of an array, a key of a map, or a substring
+    In,
+    NotIn,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogicalOp {
+    And,
+    Or,
+}
+
+impl UnaryOp {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            UnaryOp::Negate => "-",
+            UnaryOp::Not => "!",
+            UnaryOp::TypeOf => "typeof",
+        }
+    }
+}
+
+impl BinaryOp {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Subtract => "-",
+            BinaryOp::Multiply => "*",
+            BinaryOp::Divide => "/",
+            BinaryOp::Modulo => "%",
+            BinaryOp::Equal => "==",
+            BinaryOp::NotEqual => "!=",
+            BinaryOp::Less => "<",
+            BinaryOp::LessEqual => "<=",
+            BinaryOp::Greater => ">",
+            BinaryOp::GreaterEqual => ">=",
+            BinaryOp::In => "in",
+            BinaryOp::NotIn => "not in",
+        }
+    }
+}
+
+impl LogicalOp {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            LogicalOp::And => "&&",
+            LogicalOp::Or => "||",
+        }
+    }
+}
+
+/// A type written in an annotation, like `number` in `let x: number = 3;`.
+/// `Any` matches every value, and is what unannotated names have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Type {
+    Number,
+    String,
+    Bool,
+    Null,
+    Array,
+    Map,
+    Function,
+    Any,
+}
+
+impl Type {
+    /// The type's name as written in annotations, which matches
+    /// `Value::type_name` for the types values can have.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Type::Number => "number",
+            Type::String => "string",
+            Type::Bool => "bool",
+            Type::Null => "null",
+            Type::Array => "array",
+            Type::Map => "map",
+            Type::Function => "function",
+            Type::Any => "any",
+        }
+    }
+
+    /// Whether every value of type `other` is also a value of this type.
+    pub fn accepts(&self, other: Type) -> bool {
+        *self == Type::Any || other == Type::Any || *self == other
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A parsed program: its top-level statements and the expressions they're
+/// made of.
+#[derive(Debug, Clone, Default)]
+pub struct Program {
+    pub statements: Vec<Stmt>,
+    pub exprs: Exprs,
+}
+
+/// Where an expression is in the `Exprs` of the program or function it's
+/// written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ExprId(u32);
+
+/// The arena the expressions of a program or a function body are
+/// allocated in. An expression's operands are `ExprId`s in the same arena
+/// rather than boxes of their own, so a tree sits in a few large
+/// allocations and is freed in one go. Each function has its own arena,
+/// so a closure returned from a program keeps only its function's
+/// expressions alive, not the whole program's.
+#[derive(Debug, Clone, Default)]
+pub struct Exprs {
+    nodes: Vec<Expr>,
+}
+
+impl Exprs {
+    pub fn new() -> Self {
+        Exprs::default()
+    }
+
+    /// Moves `expr` into the arena, returning where it is.
+    pub fn add(&mut self, expr: Expr) -> ExprId {
+        let id = ExprId(u32::try_from(self.nodes.len()).expect("fewer than 2^32 expressions"));
+        self.nodes.push(expr);
+        id
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+impl Index<ExprId> for Exprs {
+    type Output = Expr;
+
+    fn index(&self, id: ExprId) -> &Expr {
+        &self.nodes[id.0 as usize]
+    }
+}
+
+impl IndexMut<ExprId> for Exprs {
+    fn index_mut(&mut self, id: ExprId) -> &mut Expr {
+        &mut self.nodes[id.0 as usize]
+    }
+}
+
+/// An expression. The top-level expression of a statement, an argument, or
+/// an element is held inline; the operands of the others are `ExprId`s in
+/// the arena of the program or function it's written in.
+#[derive(Debug, Clone)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum ExprKind {
+    // literals
+    Number(f64),
+    // a number literal without a fraction or exponent, in range
+    Int(i64),
+    String(String),
+    Bool(bool),
+    Null,
+
+    Variable(Symbol),
+    // `name = value`, or with an operator `name += value` and the like,
+    // which reads the variable, applies the operator, and assigns the result
+    Assign {
+        name: Symbol,
+        operator: Option<BinaryOp>,
+        value: ExprId,
+    },
+    Unary {
+        operator: UnaryOp,
+        operand: ExprId,
+    },
+    Binary {
+        left: ExprId,
+        operator: BinaryOp,
+        right: ExprId,
+    },
+    // kept apart from Binary because the right side is only evaluated when
+    // the left side doesn't already decide the result
+    Logical {
+        left: ExprId,
+        operator: LogicalOp,
+        right: ExprId,
+    },
+    Call {
+        callee: ExprId,
+        arguments: Vec<Expr>,
+    },
+    Array(Vec<Expr>),
+    Index {
+        object: ExprId,
+        index: ExprId,
+    },
+    // with an operator, the object and index are evaluated once for both
+    // the read and the write
+    SetIndex {
+        object: ExprId,
+        index: ExprId,
+        operator: Option<BinaryOp>,
+        value: ExprId,
+    },
+    // `object[start..end]`, a copy of the part of an array or string from
+    // start up to but not including end, or up to and including it with
+    // `..=`; either bound may be left out to slice from the start or to
+    // the end
+    Slice {
+        object: ExprId,
+        start: Option<ExprId>,
+        end: Option<ExprId>,
+        inclusive: bool,
+    },
+    // entries in source order; keys are written either as identifiers or
+    // string literals
+    Map(Vec<(String, Expr)>),
+    // `object.name`, a shorthand for `object["name"]` on maps
+    Member {
+        object: ExprId,
+        name: String,
+    },
+    SetMember {
+        object: ExprId,
+        name: String,
+        operator: Option<BinaryOp>,
+        value: ExprId,
+    },
+    // `start..end`, the numbers from start up to but not including end, or
+    // `start..=end`, up to and including it; only written as the iterable
+    // of a for-in loop
+    Range {
+        start: ExprId,
+        end: ExprId,
+        inclusive: bool,
+    },
+    // `function(params) { body }`, an anonymous function value
+    Function(Rc<FunctionDecl>),
+    // the instance a method was called on
+    This,
+}
+
+#[derive(Debug, Clone)]
+pub struct Parameter {
+    pub name: Symbol,
+    pub annotation: Option<Type>,
+    // `name = value`: evaluated when a call leaves the parameter out, in
+    // the function's scope once the parameters before it are bound
+    pub default: Option<Expr>,
+    // `...name`, the last parameter, bound to an array of the arguments
+    // left over after the others
+    pub rest: bool,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct FunctionDecl {
+    pub name: Symbol,
+    // where the name is written; for an anonymous function, the `function`
+    // keyword
+    pub name_span: Span,
+    pub params: Vec<Parameter>,
+    // the type after `):`, if the function declares what it returns
+    pub return_type: Option<Type>,
+    pub body: Vec<Stmt>,
+    // the arena of the expressions in the parameters' defaults and the
+    // body, but not in functions nested inside it, which have their own
+    pub exprs: Exprs,
+    pub span: Span,
+    // the `///` comment just above a function or method declaration, or on
+    // the `export` before it, without the slashes
+    pub doc: Option<String>,
+}
+
+impl FunctionDecl {
+    /// How many arguments calls must pass: at least one per parameter
+    /// without a default, and no more than there are parameters unless the
+    /// last is a rest parameter.
+    pub fn arity(&self) -> Arity {
+        let required = self.params.iter().filter(|param| param.default.is_none() && !param.rest).count();
+        match self.params.last() {
+            Some(param) if param.rest => Arity::AtLeast(required),
+            _ if required == self.params.len() => Arity::Exact(required),
+            _ => Arity::Range(required, self.params.len()),
+        }
+    }
+}
+
+/// `class Name { method(params) { body } ... }`. Calling the class makes an
+/// instance and passes the arguments to its `init` method, if it has one.
+#[derive(Debug, Clone)]
+pub struct ClassDecl {
+    pub name: Symbol,
+    pub methods: Vec<Rc<FunctionDecl>>,
+    pub span: Span,
+}
+
+impl ClassDecl {
+    pub fn method(&self, name: &str) -> Option<&Rc<FunctionDecl>> {
+        self.methods.iter().find(|method| method.name == name)
+    }
+}
+
+/// The left side of a destructuring `let`, naming the parts of a value to
+/// bind.
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    // binds the whole value
+    Name { name: Symbol, span: Span },
+    // `[first, [a, b], ...rest]`: the first elements of an array, in order;
+    // elements past them are ignored unless `rest` collects them into an
+    // array of their own
+    Array {
+        elements: Vec<Pattern>,
+        rest: Option<(Symbol, Span)>,
+    },
+    // `{ key, other: pattern }`: the values of a map's keys, where `key` on
+    // its own binds a variable with the key's name
+    Map(Vec<(String, Pattern)>),
+}
+
+impl Pattern {
+    /// Every name the pattern binds, with where it is written.
+    pub fn names(&self) -> Vec<(&str, Span)> {
+        match self {
+            Pattern::Name { name, span } => vec![(name, *span)],
+            Pattern::Array { elements, rest } => elements
+                .iter()
+                .flat_map(Pattern::names)
+                .chain(rest.iter().map(|(name, span)| (name.as_str(), *span)))
+                .collect(),
+            Pattern::Map(entries) => entries.iter().flat_map(|(_, pattern)| pattern.names()).collect(),
+        }
+    }
+}
+
+/// One arm of a `match`: the values it matches and the block it runs.
+#[derive(Debug, Clone)]
+pub struct MatchArm {
+    pub values: Vec<Expr>,
+    pub body: Vec<Stmt>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct Stmt {
+    pub kind: StmtKind,
+    pub span: Span,
+}
+
+impl Stmt {
+    /// The declaration an `export` wraps, or the statement itself if it
+    /// isn't exported.
+    pub fn without_export(&self) -> &Stmt {
+        match &self.kind {
+            StmtKind::Export(declaration) => declaration,
+            _ => self,
+        }
+    }
+
+    /// The names a `let`, `const`, function, or class declaration binds.
+    pub fn declared_names(&self) -> Vec<&str> {
+        match &self.kind {
+            StmtKind::Let { name, .. } => vec![name],
+            StmtKind::Destructure { pattern, .. } => pattern.names().into_iter().map(|(name, _)| name).collect(),
+            StmtKind::Function(declaration) => vec![&declaration.name],
+            StmtKind::Class(declaration) => vec![&declaration.name],
+            _ => Vec::new(),
+        }
+    }
+
+    /// The keyword the statement starts with, or the label of a labelled
+    /// loop, for diagnostics about a whole statement that shouldn't
+    /// underline all of it. An expression statement has no keyword, so this
+    /// is its expression.
+    pub fn keyword_span(&self) -> Span {
+        let keyword: &str = match &self.kind {
+            StmtKind::Expression(expr) => return expr.span,
+            StmtKind::Let { constant, .. } | StmtKind::Destructure { constant, .. } => match constant {
+                true => "const",
+                false => "let",
+            },
+            StmtKind::Block(_) => "{",
+            StmtKind::If { .. } => "if",
+            StmtKind::While { label: Some(label), .. }
+            | StmtKind::DoWhile { label: Some(label), .. }
+            | StmtKind::For { label: Some(label), .. }
+            | StmtKind::ForIn { label: Some(label), .. } => label,
+            StmtKind::While { .. } => "while",
+            StmtKind::DoWhile { .. } => "do",
+            StmtKind::Match { .. } => "match",
+            StmtKind::For { .. } | StmtKind::ForIn { .. } => "for",
+            StmtKind::Function(_) => "function",
+            StmtKind::Class(_) => "class",
+            StmtKind::Return(_) => "return",
+            StmtKind::Try { .. } => "try",
+            StmtKind::Throw(_) => "throw",
+            StmtKind::Break(_) => "break",
+            StmtKind::Continue(_) => "continue",
+            StmtKind::Import { .. } => "import",
+            StmtKind::Export(_) => "export",
+            StmtKind::Test { .. } => "test",
+        };
+        Span {
+            end: self.span.start + keyword.len(),
+            ..self.span
+        }
+    }
+
+    /// Whether running this statement never carries on to the one after
+    /// it: it's a `return`, `break`, `continue`, or `throw`, or an `if`,
+    /// `match`, `try`, or block that ends in one whichever way it goes.
+    /// Loops never do, since a `break` inside one can leave the loop.
+    pub fn diverges(&self) -> bool {
+        let any = |statements: &[Stmt]| statements.iter().any(Stmt::diverges);
+        match &self.kind {
+            StmtKind::Return(_) | StmtKind::Break(_) | StmtKind::Continue(_) | StmtKind::Throw(_) => true,
+            StmtKind::Block(statements) => any(statements),
+            StmtKind::If { then_branch, else_branch: Some(else_branch), .. } => any(then_branch) && any(else_branch),
+            StmtKind::Match { arms, default: Some(default), .. } => {
+                arms.iter().all(|arm| any(&arm.body)) && any(default)
+            }
+            // an error partway through the body goes to the handler
+            StmtKind::Try { body, handler, .. } => any(body) && any(handler),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum StmtKind {
+    Expression(Expr),
+    // `let name = value;` or `const name = value;`, where a const always
+    // has an initializer and can't be assigned to afterwards; either may be
+    // annotated as `let name: type = value;`
+    Let {
+        name: Symbol,
+        name_span: Span,
+        annotation: Option<Type>,
+        initializer: Option<Expr>,
+        constant: bool,
+    },
+    // `let pattern = value;` or `const pattern = value;`, binding the
+    // names in an array or map pattern to the parts of the value
+    Destructure {
+        pattern: Pattern,
+        initializer: Expr,
+        constant: bool,
+    },
+    Block(Vec<Stmt>),
+    If {
+        condition: Expr,
+        then_branch: Vec<Stmt>,
+        else_branch: Option<Vec<Stmt>>,
+    },
+    // each loop has the label written before it as `label:`, if any, for
+    // `break label;` and `continue label;` in loops nested inside it
+    While {
+        condition: Expr,
+        body: Vec<Stmt>,
+        label: Option<Symbol>,
+    },
+    // `do { ... } while condition;`, which runs the body once before
+    // checking the condition
+    DoWhile {
+        body: Vec<Stmt>,
+        condition: Expr,
+        label: Option<Symbol>,
+    },
+    // `match subject { a, b => { ... } _ => { ... } }`, which runs the
+    // block of the first arm with a value equal to the subject, or the `_`
+    // arm if none is; arms never fall through into the next one
+    Match {
+        subject: Expr,
+        arms: Vec<MatchArm>,
+        default: Option<Vec<Stmt>>,
+    },
+    // C-style `for (initializer; condition; increment) { ... }`
+    For {
+        initializer: Option<Box<Stmt>>,
+        condition: Option<Expr>,
+        increment: Option<Expr>,
+        body: Vec<Stmt>,
+        label: Option<Symbol>,
+    },
+    // `for variable in iterable { ... }` over an array, map, string, or range
+    ForIn {
+        variable: Symbol,
+        iterable: Expr,
+        body: Vec<Stmt>,
+        label: Option<Symbol>,
+    },
+    // shared with the function values created when the declaration runs, so
+    // the body isn't cloned every time a closure is made
+    Function(Rc<FunctionDecl>),
+    // shared with the class values created when the declaration runs, like
+    // a function's
+    Class(Rc<ClassDecl>),
+    Return(Option<Expr>),
+    // `try { ... } catch (name) { ... }`, which runs the catch block with
+    // `name` bound to the error if the try block raises one; `return`,
+    // `break`, and `continue` pass through it like any other block
+    Try {
+        body: Vec<Stmt>,
+        name: Symbol,
+        handler: Vec<Stmt>,
+    },
+    // `throw value;`, raising an error that `catch` binds to the value
+    Throw(Expr),
+    // `break;` and `continue;` are for the innermost loop, and `break
+    // label;` and `continue label;` for the enclosing loop with that label
+    Break(Option<Symbol>),
+    Continue(Option<Symbol>),
+    // `import "path";`, which runs another file's top-level statements in
+    // the global scope the first time it is reached, or `import "path" as
+    // alias;`, which runs them in a scope of their own and binds `alias` to
+    // a module of the names the file exports
+    Import {
+        path: String,
+        alias: Option<String>,
+    },
+    // `export` before a top-level `let`, `const`, function, or class
+    // declaration, making the name available to files importing this one
+    // as a module
+    Export(Box<Stmt>),
+    // `test "name" { ... }` at the top level, which a normal run skips and
+    // `Interpreter::run_tests` runs in a scope of its own once the rest of
+    // the file has
+    Test {
+        name: String,
+        body: Vec<Stmt>,
+    },
+}
@@ -0,0 +1,63 @@
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use crate::error::Error;
+use crate::interpreter::Interpreter;
+
+/// How long each step of running a program once took, so a slowdown can be
+/// pinned on the lexer, the parser, or the interpreter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Phases {
+    pub tokenize: Duration,
+    pub parse: Duration,
+    // loading imports included
+    pub execute: Duration,
+}
+
+impl Phases {
+    pub fn total(&self) -> Duration {
+        self.tokenize + self.parse + self.execute
+    }
+}
+
+/// Tokenizes, parses, and runs `source` in `interpreter`, timing each step
+/// on its own. Each run should get a fresh interpreter, so that one run's
+/// globals and loaded imports don't carry over into the next.
+pub fn time_phases(interpreter: &mut Interpreter, source: &str) -> Result<Phases, Error> {
+    let start = Instant::now();
+    let tokens = crate::tokenize(source)?;
+    let tokenize = start.elapsed();
+    let start = Instant::now();
+    let program = crate::parse_tokens(tokens)?;
+    let parse = start.elapsed();
+    let start = Instant::now();
+    let value = interpreter.eval_statements(&program)?;
+    let execute = start.elapsed();
+    black_box(value);
+    Ok(Phases { tokenize, parse, execute })
+}
+
+/// The fastest, middle, and average of several timings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Summary {
+    pub min: Duration,
+    // the mean of the middle two for an even number of timings
+    pub median: Duration,
+    pub mean: Duration,
+}
+
+impl Summary {
+    /// Summarizes `samples`, or `None` if there are none.
+    pub fn of(samples: &[Duration]) -> Option<Summary> {
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+        let count = sorted.len();
+        let min = *sorted.first()?;
+        let median = match count % 2 {
+            0 => (sorted[count / 2 - 1] + sorted[count / 2]) / 2,
+            _ => sorted[count / 2],
+        };
+        let mean = sorted.iter().sum::<Duration>() / count as u32;
+        Some(Summary { min, median, mean })
+    }
+}
@@ -0,0 +1,1283 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use std::fs;
+use std::rc::Rc;
+use std::time::Duration;
+
+use regex::Regex;
+
+use crate::clock;
+use crate::codes;
+use crate::doc;
+use crate::environment::Environment;
+use crate::interpreter::{Interpreter, RuntimeError};
+use crate::json::Json;
+use crate::lexer::{Span, parse_number};
+use crate::value::{Arity, Value, compare_int};
+
+/// A function implemented in Rust and available to every script.
+#[derive(Debug)]
+pub struct Builtin {
+    pub name: &'static str,
+    pub arity: Arity,
+    pub function: fn(&mut Interpreter, &[Value], Span) -> Result<Value, RuntimeError>,
+}
+
+const BUILTINS: &[Builtin] = &[
+    Builtin { name: "len", arity: Arity::Exact(1), function: len },
+    Builtin { name: "keys", arity: Arity::Exact(1), function: keys },
+    Builtin { name: "has", arity: Arity::Exact(2), function: has },
+    Builtin { name: "type", arity: Arity::Exact(1), function: type_of },
+    Builtin { name: "copy", arity: Arity::Exact(1), function: copy },
+    Builtin { name: "is_same", arity: Arity::Exact(2), function: is_same },
+    Builtin { name: "print", arity: Arity::AtLeast(0), function: print },
+    Builtin { name: "help", arity: Arity::Exact(1), function: help },
+    Builtin { name: "str", arity: Arity::Exact(1), function: str },
+    Builtin { name: "pretty", arity: Arity::Exact(1), function: pretty },
+    Builtin { name: "num", arity: Arity::Exact(1), function: num },
+    Builtin { name: "input", arity: Arity::Exact(1), function: input },
+    Builtin { name: "args", arity: Arity::Exact(0), function: args },
+    Builtin { name: "assert", arity: Arity::Range(1, 2), function: assert },
+    Builtin { name: "panic", arity: Arity::Exact(1), function: panic },
+    // math
+    Builtin { name: "abs", arity: Arity::Exact(1), function: abs },
+    Builtin { name: "floor", arity: Arity::Exact(1), function: floor },
+    Builtin { name: "ceil", arity: Arity::Exact(1), function: ceil },
+    Builtin { name: "round", arity: Arity::Exact(1), function: round },
+    Builtin { name: "sqrt", arity: Arity::Exact(1), function: sqrt },
+    Builtin { name: "min", arity: Arity::AtLeast(2), function: min },
+    Builtin { name: "max", arity: Arity::AtLeast(2), function: max },
+    Builtin { name: "random", arity: Arity::Range(0, 2), function: random },
+    Builtin { name: "seed", arity: Arity::Exact(1), function: seed },
+    // strings
+    Builtin { name: "upper", arity: Arity::Exact(1), function: upper },
+    Builtin { name: "lower", arity: Arity::Exact(1), function: lower },
+    Builtin { name: "trim", arity: Arity::Exact(1), function: trim },
+    Builtin { name: "split", arity: Arity::Exact(2), function: split },
+    Builtin { name: "join", arity: Arity::Exact(2), function: join },
+    Builtin { name: "concat", arity: Arity::Exact(1), function: concat },
+    Builtin { name: "contains", arity: Arity::Exact(2), function: contains },
+    Builtin { name: "replace", arity: Arity::Exact(3), function: replace },
+    Builtin { name: "substring", arity: Arity::Exact(3), function: substring },
+    Builtin { name: "chars", arity: Arity::Exact(1), function: chars },
+    Builtin { name: "format", arity: Arity::AtLeast(1), function: format },
+    Builtin { name: "regex_match", arity: Arity::Exact(2), function: regex_match },
+    Builtin { name: "regex_find", arity: Arity::Exact(2), function: regex_find },
+    Builtin { name: "regex_find_all", arity: Arity::Exact(2), function: regex_find_all },
+    Builtin { name: "regex_captures", arity: Arity::Exact(2), function: regex_captures },
+    Builtin { name: "regex_replace", arity: Arity::Exact(3), function: regex_replace },
+    Builtin { name: "json_parse", arity: Arity::Exact(1), function: json_parse },
+    Builtin { name: "json_stringify", arity: Arity::Range(1, 2), function: json_stringify },
+    // arrays
+    Builtin { name: "push", arity: Arity::Exact(2), function: push },
+    Builtin { name: "pop", arity: Arity::Exact(1), function: pop },
+    Builtin { name: "insert", arity: Arity::Exact(3), function: insert },
+    Builtin { name: "remove", arity: Arity::Exact(2), function: remove },
+    Builtin { name: "slice", arity: Arity::Exact(3), function: slice },
+    Builtin { name: "sort", arity: Arity::Range(1, 2), function: sort },
+    Builtin { name: "sort_by_key", arity: Arity::Exact(2), function: sort_by_key },
+    Builtin { name: "reverse", arity: Arity::Exact(1), function: reverse },
+    Builtin { name: "map", arity: Arity::Exact(2), function: map },
+    Builtin { name: "filter", arity: Arity::Exact(2), function: filter },
+    Builtin { name: "reduce", arity: Arity::Range(2, 3), function: reduce },
+    // system
+    Builtin { name: "env", arity: Arity::Exact(1), function: env },
+    Builtin { name: "read_file", arity: Arity::Exact(1), function: read_file },
+    Builtin { name: "write_file", arity: Arity::Exact(2), function: write_file },
+    Builtin { name: "file_exists", arity: Arity::Exact(1), function: file_exists },
+    Builtin { name: "exit", arity: Arity::Range(0, 1), function: exit },
+    // time
+    Builtin { name: "clock", arity: Arity::Exact(0), function: clock },
+    Builtin { name: "now", arity: Arity::Exact(0), function: now },
+    Builtin { name: "sleep", arity: Arity::Exact(1), function: sleep },
+    Builtin { name: "format_time", arity: Arity::Exact(2), function: format_time },
+];
+
+// the builtins `BuiltinSet::allow_only_pure_math` keeps, which only compute
+// a result from their arguments
+const PURE_MATH: &[&str] = &["abs", "floor", "ceil", "round", "sqrt", "min", "max"];
+
+/// Which builtins scripts may call, for `InterpreterConfig::builtins`. The
+/// default allows all of them. A denied builtin is still a global, but
+/// calling it fails with a `builtin-unavailable` error, which a script can
+/// catch to find out what it may use. This is finer than `allow_fs` and
+/// `allow_env`, which still apply to the builtins it allows.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BuiltinSet {
+    denied: BTreeSet<String>,
+}
+
+impl BuiltinSet {
+    /// Denies the builtin `name`.
+    ///
+    /// # Panics
+    ///
+    /// If there is no builtin called `name`, since denying a misspelled
+    /// name would leave the sandbox open without a word.
+    pub fn deny(mut self, name: &str) -> Self {
+        if find(name).is_none() {
+            panic!("can't deny '{}', which isn't a builtin", name);
+        }
+        self.denied.insert(name.to_string());
+        self
+    }
+
+    /// Allows the builtin `name` again after it was denied.
+    pub fn allow(mut self, name: &str) -> Self {
+        self.denied.remove(name);
+        self
+    }
+
+    /// Denies every builtin but `abs`, `floor`, `ceil`, `round`, `sqrt`,
+    /// `min`, and `max`, for scripts that should only compute things.
+    /// Builtins already denied stay denied.
+    pub fn allow_only_pure_math(mut self) -> Self {
+        let impure = names().filter(|name| !PURE_MATH.contains(name));
+        self.denied.extend(impure.map(str::to_string));
+        self
+    }
+
+    pub fn allows(&self, name: &str) -> bool {
+        !self.denied.contains(name)
+    }
+
+    /// The names denied, in alphabetical order.
+    pub fn denied(&self) -> impl Iterator<Item = &str> {
+        self.denied.iter().map(String::as_str)
+    }
+}
+
+/// Defines every builtin in `environment`, normally the global scope.
+pub fn define_builtins(environment: &mut Environment) {
+    for builtin in BUILTINS {
+        environment.define(builtin.name, Value::Builtin(builtin));
+    }
+}
+
+/// Defines the builtins `BuiltinSet::allow_only_pure_math` keeps in
+/// `environment`, and no others.
+pub(crate) fn define_pure_math(environment: &mut Environment) {
+    for builtin in BUILTINS.iter().filter(|builtin| PURE_MATH.contains(&builtin.name)) {
+        environment.define(builtin.name, Value::Builtin(builtin));
+    }
+}
+
+/// The builtin called `name`, if there is one.
+pub fn find(name: &str) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|builtin| builtin.name == name)
+}
+
+/// The names of every builtin, e.g. for static checks that need to know
+/// which globals exist before a program runs.
+pub fn names() -> impl Iterator<Item = &'static str> {
+    BUILTINS.iter().map(|builtin| builtin.name)
+}
+
+fn len(_: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let length = match &arguments[0] {
+        Value::String(s) => s.chars().count(),
+        Value::Array(elements) => elements.borrow().len(),
+        Value::Map(entries) => entries.borrow().len(),
+        other => {
+            return Err(RuntimeError::with_code(
+                &codes::WRONG_TYPE,
+                format!("len() expects a string, array, or map, got {}", other.type_name()),
+                span,
+            ));
+        }
+    };
+    Ok(Value::Int(length as i64))
+}
+
+/// The keys of a map as an array of strings, in insertion order.
+fn keys(_: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    match &arguments[0] {
+        Value::Map(entries) => Ok(Value::array(
+            entries.borrow().keys().map(|key| Value::String(key.as_str().into())).collect(),
+        )),
+        other => Err(RuntimeError::with_code(
+            &codes::WRONG_TYPE,
+            format!("keys() expects a map, got {}", other.type_name()),
+            span,
+        )),
+    }
+}
+
+fn has(_: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    match (&arguments[0], &arguments[1]) {
+        (Value::Map(entries), Value::String(key)) => Ok(Value::Bool(entries.borrow().contains_key(&**key))),
+        (Value::Map(_), other) => Err(RuntimeError::with_code(
+            &codes::WRONG_TYPE,
+            format!("has() expects a string key, got {}", other.type_name()),
+            span,
+        )),
+        (other, _) => Err(RuntimeError::with_code(
+            &codes::WRONG_TYPE,
+            format!("has() expects a map, got {}", other.type_name()),
+            span,
+        )),
+    }
+}
+
+fn type_of(_: &mut Interpreter, arguments: &[Value], _: Span) -> Result<Value, RuntimeError> {
+    Ok(Value::String(arguments[0].type_name().into()))
+}
+
+/// A deep copy of arrays and maps; see `Value::deep_copy`.
+fn copy(interpreter: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let copy = arguments[0].deep_copy();
+    interpreter.allocated_inside(&copy, span)?;
+    Ok(copy)
+}
+
+/// Whether both arguments are the same array or map rather than equal
+/// ones; see `Value::is_same`.
+fn is_same(_: &mut Interpreter, arguments: &[Value], _: Span) -> Result<Value, RuntimeError> {
+    Ok(Value::Bool(arguments[0].is_same(&arguments[1])))
+}
+
+/// Writes its arguments, rendered as `str()` would and separated by single
+/// spaces, followed by a newline. `print()` writes an empty line.
+fn print(interpreter: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let line = arguments
+        .iter()
+        .map(Value::to_string)
+        .collect::<Vec<_>>()
+        .join(" ");
+    interpreter
+        .write_line(&line)
+        .map_err(|error| RuntimeError::with_code(&codes::IO_FAILED, format!("print() failed to write: {}", error), span))?;
+    Ok(Value::Null)
+}
+
+/// Prints how a function is called, followed by its `///` doc comment if
+/// it has one.
+fn help(interpreter: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let text = match &arguments[0] {
+        Value::Function(function) => {
+            let declaration = &function.declaration;
+            let source = interpreter
+                .sources()
+                .get(declaration.span.file)
+                .map_or("", |file| file.contents.as_str());
+            let signature = doc::signature(declaration, source);
+            match &declaration.doc {
+                Some(doc) => format!("{}\n{}", signature, doc),
+                None => signature,
+            }
+        }
+        Value::Builtin(builtin) => format!("{}, a builtin taking {}", builtin.name, builtin.arity.describe()),
+        Value::NativeFunction(function) => {
+            format!("{}, a native function taking {}", function.name, function.arity.describe())
+        }
+        other => {
+            return Err(RuntimeError::with_code(
+                &codes::WRONG_TYPE,
+                format!("help() expects a function, got {}", other.type_name()),
+                span,
+            ));
+        }
+    };
+    interpreter
+        .write_line(&text)
+        .map_err(|error| RuntimeError::with_code(&codes::IO_FAILED, format!("help() failed to write: {}", error), span))?;
+    Ok(Value::Null)
+}
+
+/// Converts any value to the text `print` would show for it.
+fn str(_: &mut Interpreter, arguments: &[Value], _: Span) -> Result<Value, RuntimeError> {
+    Ok(Value::String(arguments[0].to_string().into()))
+}
+
+/// Like `str()`, but laid out over several lines with nesting indented.
+fn pretty(_: &mut Interpreter, arguments: &[Value], _: Span) -> Result<Value, RuntimeError> {
+    Ok(string_value(arguments[0].pretty()))
+}
+
+/// Parses a string as a number; numbers are passed through unchanged. The
+/// string is written as a number literal would be, like `3.14`, `1e5`, or
+/// `0x1F`, with an optional sign and surrounding whitespace, or is `nan`,
+/// `inf`, or `-inf`, so that `num(str(n))` is always `n` again.
+fn num(_: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    match &arguments[0] {
+        Value::Number(_) | Value::Int(_) => Ok(arguments[0].clone()),
+        Value::String(s) => {
+            let text = s.trim();
+            let number = match text {
+                "nan" => Some(f64::NAN),
+                "inf" => Some(f64::INFINITY),
+                "-inf" => Some(f64::NEG_INFINITY),
+                _ => match text.strip_prefix('-') {
+                    Some(magnitude) => parse_number(magnitude).map(|n| -n),
+                    None => parse_number(text.strip_prefix('+').unwrap_or(text)),
+                },
+            };
+            number
+                .map(Value::Number)
+                .ok_or_else(|| RuntimeError::with_code(&codes::INVALID_ARGUMENT, format!("num() could not parse '{}' as a number", s), span))
+        }
+        other => Err(RuntimeError::with_code(
+            &codes::WRONG_TYPE,
+            format!("num() expects a string, got {}", other.type_name()),
+            span,
+        )),
+    }
+}
+
+/// Prints the prompt and reads a line from the interpreter's input,
+/// returning `null` once the input is exhausted.
+fn input(interpreter: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let prompt = match &arguments[0] {
+        Value::String(s) => s,
+        other => {
+            return Err(RuntimeError::with_code(
+                &codes::WRONG_TYPE,
+                format!("input() expects a string prompt, got {}", other.type_name()),
+                span,
+            ));
+        }
+    };
+    match interpreter.read_line(prompt) {
+        Ok(Some(line)) => Ok(Value::String(line.into())),
+        Ok(None) => Ok(Value::Null),
+        Err(error) => Err(RuntimeError::with_code(&codes::IO_FAILED, format!("input() failed to read: {}", error), span)),
+    }
+}
+
+/// The arguments the script was started with, as a new array of strings.
+fn args(interpreter: &mut Interpreter, _: &[Value], _: Span) -> Result<Value, RuntimeError> {
+    let args = interpreter.args.iter().map(|arg| Value::String(arg.as_str().into())).collect();
+    Ok(Value::array(args))
+}
+
+/// Fails unless the condition is truthy. Without a message, the error quotes
+/// the condition's source text when it's available.
+fn assert(interpreter: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    if arguments[0].is_truthy() {
+        return Ok(Value::Null);
+    }
+    let message = match arguments.get(1) {
+        Some(message) => format!("Assertion failed: {}", message),
+        None => match interpreter.source_text(span).and_then(call_arguments) {
+            Some(condition) => format!("Assertion failed: {}", condition),
+            None => "Assertion failed".to_string(),
+        },
+    };
+    Err(RuntimeError::with_code(&codes::ASSERTION_FAILED, message, span))
+}
+
+/// The text between the parentheses of a call's source, e.g. `x > 1` for
+/// `assert(x > 1)`.
+fn call_arguments(call: &str) -> Option<&str> {
+    let open = call.find('(')?;
+    let inner = call[open + 1..].strip_suffix(')')?;
+    Some(inner.trim())
+}
+
+/// Fails unconditionally with the given message.
+fn panic(_: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    Err(RuntimeError::with_code(&codes::ASSERTION_FAILED, format!("Panic: {}", arguments[0]), span))
+}
+
+/// A small splitmix64 generator behind `random()`, so scripts get
+/// reproducible sequences after `seed(n)` without pulling in a crate.
+#[derive(Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    /// Seeded from the system clock, for a different sequence each run.
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    pub fn from_time() -> Self {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.as_nanos() as u64)
+            .unwrap_or(0);
+        Rng::new(nanos)
+    }
+
+    /// wasm32-unknown-unknown has no system clock to read, and asking for
+    /// the time panics, so there every run starts from the same seed.
+    #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+    pub fn from_time() -> Self {
+        Rng::new(0)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// A float in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Extracts a number argument, or reports which builtin wanted one.
+fn number_argument(name: &str, value: &Value, span: Span) -> Result<f64, RuntimeError> {
+    value.as_number().ok_or_else(|| {
+        RuntimeError::with_code(
+            &codes::WRONG_TYPE,
+            format!("{}() expects a number, got {}", name, value.type_name()),
+            span,
+        )
+    })
+}
+
+fn abs(_: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    match &arguments[0] {
+        // the most negative integer's magnitude is only a fractional number
+        Value::Int(n) if let Some(n) = n.checked_abs() => Ok(Value::Int(n)),
+        argument => Ok(Value::Number(number_argument("abs", argument, span)?.abs())),
+    }
+}
+
+fn floor(_: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    rounded("floor", &arguments[0], f64::floor, span)
+}
+
+fn ceil(_: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    rounded("ceil", &arguments[0], f64::ceil, span)
+}
+
+/// Rounds half away from zero, so `round(2.5)` is 3 and `round(-2.5)` is -3.
+fn round(_: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    rounded("round", &arguments[0], f64::round, span)
+}
+
+// `argument` rounded to a whole number by `round`, where an integer is one
+// already
+fn rounded(name: &str, argument: &Value, round: fn(f64) -> f64, span: Span) -> Result<Value, RuntimeError> {
+    match argument {
+        Value::Int(_) => Ok(argument.clone()),
+        argument => Ok(Value::Number(round(number_argument(name, argument, span)?))),
+    }
+}
+
+/// Like division by zero, the square root of a negative number is an error
+/// rather than NaN.
+fn sqrt(_: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let n = number_argument("sqrt", &arguments[0], span)?;
+    if n < 0.0 {
+        return Err(RuntimeError::with_code(
+            &codes::INVALID_ARGUMENT,
+            format!("sqrt() of negative number {}", arguments[0]),
+            span,
+        ));
+    }
+    Ok(Value::Number(n.sqrt()))
+}
+
+fn min(_: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    if let Some(n) = integers(arguments).and_then(|integers| integers.into_iter().min()) {
+        return Ok(Value::Int(n));
+    }
+    let mut result = f64::INFINITY;
+    for argument in arguments {
+        result = result.min(number_argument("min", argument, span)?);
+    }
+    Ok(Value::Number(result))
+}
+
+fn max(_: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    if let Some(n) = integers(arguments).and_then(|integers| integers.into_iter().max()) {
+        return Ok(Value::Int(n));
+    }
+    let mut result = f64::NEG_INFINITY;
+    for argument in arguments {
+        result = result.max(number_argument("max", argument, span)?);
+    }
+    Ok(Value::Number(result))
+}
+
+// `arguments`, if they are all integers, which `min` and `max` compare
+// exactly
+fn integers(arguments: &[Value]) -> Option<Vec<i64>> {
+    arguments
+        .iter()
+        .map(|argument| match argument {
+            Value::Int(n) => Some(*n),
+            _ => None,
+        })
+        .collect()
+}
+
+/// `random()` is a float in `[0, 1)`; `random(a, b)` is an integer in
+/// `[a, b]`, inclusive at both ends.
+fn random(interpreter: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    match arguments {
+        [] => Ok(Value::Number(interpreter.rng.next_f64())),
+        [low, high] => {
+            let low = number_argument("random", low, span)?;
+            let high = number_argument("random", high, span)?;
+            if low.fract() != 0.0 || high.fract() != 0.0 {
+                return Err(RuntimeError::with_code(&codes::INVALID_ARGUMENT, "random() bounds must be whole numbers", span));
+            }
+            if low > high {
+                return Err(RuntimeError::with_code(
+                    &codes::INVALID_ARGUMENT,
+                    format!("random() lower bound {} is greater than upper bound {}", low, high),
+                    span,
+                ));
+            }
+            let offset = (interpreter.rng.next_f64() * (high - low + 1.0)).floor();
+            Ok(Value::whole(low + offset))
+        }
+        _ => Err(RuntimeError::with_code(&codes::WRONG_ARGUMENT_COUNT, "random() expects either no arguments or two bounds", span)),
+    }
+}
+
+/// Restarts the `random()` sequence from a fixed seed.
+fn seed(interpreter: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let n = number_argument("seed", &arguments[0], span)?;
+    if n.fract() != 0.0 {
+        return Err(RuntimeError::with_code(
+            &codes::INVALID_ARGUMENT,
+            format!("seed() expects a whole number, got {}", arguments[0]),
+            span,
+        ));
+    }
+    interpreter.rng = Rng::new(n.to_bits());
+    Ok(Value::Null)
+}
+
+/// Extracts a string argument, or reports which builtin wanted one.
+fn string_argument<'a>(name: &str, value: &'a Value, span: Span) -> Result<&'a str, RuntimeError> {
+    match value {
+        Value::String(s) => Ok(s),
+        other => Err(RuntimeError::with_code(
+            &codes::WRONG_TYPE,
+            format!("{}() expects a string, got {}", name, other.type_name()),
+            span,
+        )),
+    }
+}
+
+/// Extracts an array argument, or reports which builtin wanted one.
+fn array_argument<'a>(name: &str, value: &'a Value, span: Span) -> Result<&'a RefCell<Vec<Value>>, RuntimeError> {
+    match value {
+        Value::Array(elements) => Ok(elements),
+        other => Err(RuntimeError::with_code(
+            &codes::WRONG_TYPE,
+            format!("{}() expects an array, got {}", name, other.type_name()),
+            span,
+        )),
+    }
+}
+
+/// Extracts a non-negative whole number used as a character or element
+/// position.
+fn position_argument(name: &str, value: &Value, span: Span) -> Result<usize, RuntimeError> {
+    let n = number_argument(name, value, span)?;
+    if n < 0.0 || n.fract() != 0.0 {
+        return Err(RuntimeError::with_code(
+            &codes::INVALID_ARGUMENT,
+            format!("{}() expects a non-negative whole number, got {}", name, value),
+            span,
+        ));
+    }
+    Ok(n as usize)
+}
+
+/// Extracts a whole number used as an index into an array, or `None` if
+/// it's negative, which is out of bounds like an index past the end rather
+/// than a position counted from it.
+fn index_argument(name: &str, value: &Value, span: Span) -> Result<Option<usize>, RuntimeError> {
+    let n = number_argument(name, value, span)?;
+    if n.fract() != 0.0 {
+        return Err(RuntimeError::with_code(
+            &codes::INVALID_ARGUMENT,
+            format!("{}() expects a whole number, got {}", name, value),
+            span,
+        ));
+    }
+    Ok((n >= 0.0).then_some(n as usize))
+}
+
+fn string_value(s: impl Into<Rc<str>>) -> Value {
+    Value::String(s.into())
+}
+
+fn upper(_: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    Ok(string_value(string_argument("upper", &arguments[0], span)?.to_uppercase()))
+}
+
+fn lower(_: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    Ok(string_value(string_argument("lower", &arguments[0], span)?.to_lowercase()))
+}
+
+fn trim(_: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    Ok(string_value(string_argument("trim", &arguments[0], span)?.trim()))
+}
+
+fn split(_: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let s = string_argument("split", &arguments[0], span)?;
+    let separator = string_argument("split", &arguments[1], span)?;
+    if separator.is_empty() {
+        return Err(RuntimeError::with_code(&codes::INVALID_ARGUMENT, "split() separator must not be empty; use chars() instead", span));
+    }
+    Ok(Value::array(s.split(separator).map(string_value).collect()))
+}
+
+/// Joins the elements of an array, converting each one as `str()` would.
+fn join(interpreter: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let elements = array_argument("join", &arguments[0], span)?;
+    let separator = string_argument("join", &arguments[1], span)?;
+    let parts: Vec<String> = elements.borrow().iter().map(Value::to_string).collect();
+    let length = parts.iter().map(String::len).sum::<usize>() + separator.len() * parts.len().saturating_sub(1);
+    interpreter.check_string_len(length, span)?;
+    Ok(string_value(parts.join(separator)))
+}
+
+/// Joins the elements of an array with nothing between them, converting
+/// each as `str()` would. Pushing pieces onto an array and concatenating
+/// them once at the end takes time in proportion to the length of the
+/// result, where adding each piece to a string with `+` copies everything
+/// built so far every time.
+fn concat(interpreter: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let elements = array_argument("concat", &arguments[0], span)?;
+    let mut result = String::new();
+    for element in elements.borrow().iter() {
+        match element {
+            Value::String(s) => result.push_str(s),
+            other => result.push_str(&other.to_string()),
+        }
+        interpreter.check_string_len(result.len(), span)?;
+    }
+    Ok(string_value(result))
+}
+
+fn contains(_: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let s = string_argument("contains", &arguments[0], span)?;
+    let needle = string_argument("contains", &arguments[1], span)?;
+    Ok(Value::Bool(s.contains(needle)))
+}
+
+fn replace(interpreter: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let s = string_argument("replace", &arguments[0], span)?;
+    let from = string_argument("replace", &arguments[1], span)?;
+    let to = string_argument("replace", &arguments[2], span)?;
+    if from.is_empty() {
+        return Err(RuntimeError::with_code(&codes::INVALID_ARGUMENT, "replace() pattern must not be empty", span));
+    }
+    // the result can be far longer than `s`, so its length is checked
+    // before it is built
+    if to.len() > from.len() {
+        let length = s.len() + s.matches(from).count() * (to.len() - from.len());
+        interpreter.check_string_len(length, span)?;
+    }
+    Ok(string_value(s.replace(from, to)))
+}
+
+/// The characters from `start` up to (not including) `end`, counted in
+/// characters rather than bytes.
+fn substring(_: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let s = string_argument("substring", &arguments[0], span)?;
+    let start = position_argument("substring", &arguments[1], span)?;
+    let end = position_argument("substring", &arguments[2], span)?;
+    let length = s.chars().count();
+    if end > length {
+        return Err(RuntimeError::with_code(
+            &codes::INDEX_OUT_OF_BOUNDS,
+            format!("substring() end {} is out of range for string of length {}", end, length),
+            span,
+        ));
+    }
+    if start > end {
+        return Err(RuntimeError::with_code(
+            &codes::INDEX_OUT_OF_BOUNDS,
+            format!("substring() start {} is after end {}", start, end),
+            span,
+        ));
+    }
+    Ok(string_value(s.chars().skip(start).take(end - start).collect::<String>()))
+}
+
+fn chars(_: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let s = string_argument("chars", &arguments[0], span)?;
+    Ok(Value::array(s.chars().map(|ch| string_value(ch.to_string())).collect()))
+}
+
+/// Replaces each `{}` in the template with the next argument, rendered as
+/// `str()` would. `{{` and `}}` stand for literal braces.
+fn format(_: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let template = string_argument("format", &arguments[0], span)?;
+    let values = &arguments[1..];
+    let mut result = String::new();
+    let mut placeholders = 0;
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('{', Some('{')) | ('}', Some('}')) => {
+                chars.next();
+                result.push(c);
+            }
+            ('{', Some('}')) => {
+                chars.next();
+                if let Some(value) = values.get(placeholders) {
+                    result.push_str(&value.to_string());
+                }
+                placeholders += 1;
+            }
+            ('{', _) | ('}', _) => {
+                return Err(RuntimeError::with_code(
+                    &codes::INVALID_ARGUMENT,
+                    format!("format() found an unmatched '{}'; use '{}{}' for a literal brace", c, c, c),
+                    span,
+                ));
+            }
+            _ => result.push(c),
+        }
+    }
+    if placeholders != values.len() {
+        return Err(RuntimeError::with_code(
+            &codes::INVALID_ARGUMENT,
+            format!(
+                "format() has {} placeholder{} but got {} value{}",
+                placeholders,
+                if placeholders == 1 { "" } else { "s" },
+                values.len(),
+                if values.len() == 1 { "" } else { "s" }
+            ),
+            span,
+        ));
+    }
+    Ok(string_value(result))
+}
+
+// how many compiled patterns a `RegexCache` keeps
+const REGEX_CACHE_CAPACITY: usize = 32;
+
+/// The patterns the regex builtins compiled most recently, so matching in
+/// a loop compiles the pattern once rather than on every iteration. Only
+/// the last `REGEX_CACHE_CAPACITY` are kept, so a script building patterns
+/// on the fly can't make it grow without bound.
+#[derive(Debug, Default)]
+pub struct RegexCache {
+    // least recently used first
+    entries: Vec<(Rc<str>, Rc<Regex>)>,
+}
+
+impl RegexCache {
+    /// The compiled `pattern`, compiling it if it isn't cached.
+    pub fn get(&mut self, pattern: &str) -> Result<Rc<Regex>, regex::Error> {
+        if let Some(i) = self.entries.iter().position(|(cached, _)| **cached == *pattern) {
+            let entry = self.entries.remove(i);
+            let regex = Rc::clone(&entry.1);
+            self.entries.push(entry);
+            return Ok(regex);
+        }
+        let regex = Rc::new(Regex::new(pattern)?);
+        if self.entries.len() == REGEX_CACHE_CAPACITY {
+            self.entries.remove(0);
+        }
+        self.entries.push((pattern.into(), Rc::clone(&regex)));
+        Ok(regex)
+    }
+}
+
+/// Compiles the pattern argument, through the interpreter's cache, or
+/// reports why it isn't a valid pattern.
+fn regex_argument(interpreter: &mut Interpreter, name: &str, value: &Value, span: Span) -> Result<Rc<Regex>, RuntimeError> {
+    let pattern = string_argument(name, value, span)?;
+    interpreter
+        .regexes
+        .get(pattern)
+        .map_err(|error| RuntimeError::with_code(&codes::INVALID_ARGUMENT, format!("{}() was given an invalid pattern: {}", name, error), span))
+}
+
+/// Whether the pattern matches anywhere in the string; `^` and `$` anchor
+/// it to the whole string.
+fn regex_match(interpreter: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let regex = regex_argument(interpreter, "regex_match", &arguments[0], span)?;
+    let s = string_argument("regex_match", &arguments[1], span)?;
+    Ok(Value::Bool(regex.is_match(s)))
+}
+
+/// The first match of the pattern in the string, or null.
+fn regex_find(interpreter: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let regex = regex_argument(interpreter, "regex_find", &arguments[0], span)?;
+    let s = string_argument("regex_find", &arguments[1], span)?;
+    Ok(regex.find(s).map_or(Value::Null, |found| string_value(found.as_str())))
+}
+
+/// Every match of the pattern in the string, left to right and not
+/// overlapping.
+fn regex_find_all(interpreter: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let regex = regex_argument(interpreter, "regex_find_all", &arguments[0], span)?;
+    let s = string_argument("regex_find_all", &arguments[1], span)?;
+    Ok(Value::array(regex.find_iter(s).map(|found| string_value(found.as_str())).collect()))
+}
+
+/// The groups of the first match of the pattern in the string: the whole
+/// match, then each group in order, with null for a group that took no
+/// part in the match. Null if there's no match at all.
+fn regex_captures(interpreter: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let regex = regex_argument(interpreter, "regex_captures", &arguments[0], span)?;
+    let s = string_argument("regex_captures", &arguments[1], span)?;
+    let Some(captures) = regex.captures(s) else {
+        return Ok(Value::Null);
+    };
+    let groups = captures
+        .iter()
+        .map(|group| group.map_or(Value::Null, |group| string_value(group.as_str())))
+        .collect();
+    Ok(Value::array(groups))
+}
+
+/// Replaces every match of the pattern in the string. In the replacement,
+/// `$1` or `${1}` stands for a group by number, `${name}` for a named
+/// group, `$0` for the whole match, and `$$` for a literal `$`.
+fn regex_replace(interpreter: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let regex = regex_argument(interpreter, "regex_replace", &arguments[0], span)?;
+    let s = string_argument("regex_replace", &arguments[1], span)?;
+    let replacement = string_argument("regex_replace", &arguments[2], span)?;
+    let mut result = String::new();
+    let mut last = 0;
+    for captures in regex.captures_iter(s) {
+        let found = captures.get(0).expect("group 0 is the whole match");
+        result.push_str(&s[last..found.start()]);
+        captures.expand(replacement, &mut result);
+        last = found.end();
+        // group references can make the result far longer than `s`, so
+        // it's checked as it grows
+        interpreter.check_string_len(result.len(), span)?;
+    }
+    result.push_str(&s[last..]);
+    Ok(string_value(result))
+}
+
+/// Reads a JSON document into maps, arrays, strings, numbers, bools, and
+/// null. Objects keep their key order, and a repeated key keeps its last
+/// value.
+fn json_parse(interpreter: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let text = string_argument("json_parse", &arguments[0], span)?;
+    let json = Json::parse(text).map_err(|message| RuntimeError::with_code(&codes::INVALID_ARGUMENT, format!("json_parse(): {}", message), span))?;
+    let value = json.into_value();
+    interpreter.allocated_inside(&value, span)?;
+    Ok(value)
+}
+
+/// Writes a value as JSON, compactly or, if the second argument is true,
+/// indented. Anything `json_parse()` couldn't read back the same is an
+/// error rather than being written some other way.
+fn json_stringify(interpreter: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let pretty = match arguments.get(1) {
+        None => false,
+        Some(Value::Bool(pretty)) => *pretty,
+        Some(other) => {
+            return Err(RuntimeError::with_code(
+                &codes::WRONG_TYPE,
+                format!("json_stringify() expects pretty to be a bool, got {}", other.type_name()),
+                span,
+            ));
+        }
+    };
+    let json = Json::from_value(&arguments[0])
+        .map_err(|message| RuntimeError::with_code(&codes::INVALID_ARGUMENT, format!("json_stringify(): {}", message), span))?;
+    let text = if pretty { json.pretty() } else { json.to_string() };
+    interpreter.check_string_len(text.len(), span)?;
+    Ok(string_value(text))
+}
+
+/// The error for an element position past the end of an array.
+fn index_error(name: &str, index: &Value, length: usize, span: Span) -> RuntimeError {
+    RuntimeError::with_code(
+        &codes::INDEX_OUT_OF_BOUNDS,
+        format!("{}() index {} out of bounds for array of length {}", name, index, length),
+        span,
+    )
+}
+
+/// Appends to the end of the array, in place.
+fn push(interpreter: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let elements = array_argument("push", &arguments[0], span)?;
+    interpreter.check_growth(&arguments[0], 1, span)?;
+    elements.borrow_mut().push(arguments[1].clone());
+    Ok(Value::Null)
+}
+
+/// Removes and returns the last element.
+fn pop(_: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let elements = array_argument("pop", &arguments[0], span)?;
+    elements
+        .borrow_mut()
+        .pop()
+        .ok_or_else(|| RuntimeError::with_code(&codes::EMPTY_ARRAY, "pop() called on an empty array", span))
+}
+
+/// Inserts before the element at the index, shifting later elements up.
+fn insert(interpreter: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let elements = array_argument("insert", &arguments[0], span)?;
+    let index = index_argument("insert", &arguments[1], span)?;
+    interpreter.check_growth(&arguments[0], 1, span)?;
+    let mut elements = elements.borrow_mut();
+    // inserting at the length appends
+    let Some(index) = index.filter(|&index| index <= elements.len()) else {
+        return Err(index_error("insert", &arguments[1], elements.len(), span));
+    };
+    elements.insert(index, arguments[2].clone());
+    Ok(Value::Null)
+}
+
+/// Removes and returns the element at the index, shifting later elements
+/// down.
+fn remove(_: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let elements = array_argument("remove", &arguments[0], span)?;
+    let index = index_argument("remove", &arguments[1], span)?;
+    let mut elements = elements.borrow_mut();
+    let Some(index) = index.filter(|&index| index < elements.len()) else {
+        return Err(index_error("remove", &arguments[1], elements.len(), span));
+    };
+    Ok(elements.remove(index))
+}
+
+/// A new array of the elements from `start` up to (not including) `end`.
+fn slice(_: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let elements = array_argument("slice", &arguments[0], span)?.borrow();
+    let bound = |which, value| match index_argument("slice", value, span)? {
+        Some(bound) if bound <= elements.len() => Ok(bound),
+        _ => Err(RuntimeError::with_code(
+            &codes::INDEX_OUT_OF_BOUNDS,
+            format!("slice() {} {} is out of range for array of length {}", which, value, elements.len()),
+            span,
+        )),
+    };
+    let start = bound("start", &arguments[1])?;
+    let end = bound("end", &arguments[2])?;
+    if start > end {
+        return Err(RuntimeError::with_code(
+            &codes::INDEX_OUT_OF_BOUNDS,
+            format!("slice() start {} is after end {}", start, end),
+            span,
+        ));
+    }
+    Ok(Value::array(elements[start..end].to_vec()))
+}
+
+/// Sorts an array in place. Without a comparator the elements must be only
+/// numbers or only strings, sorted ascending; with one, `compare(a, b)`
+/// returns a negative number, zero, or a positive number when `a` sorts
+/// before, with, or after `b`. Equal elements keep their order.
+fn sort(interpreter: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let array = array_argument("sort", &arguments[0], span)?;
+    let Some(comparator) = arguments.get(1) else {
+        let mut elements = array.borrow_mut();
+        check_sortable("sort() expects only numbers or only strings", &elements, span)?;
+        elements.sort_by(compare_sortable);
+        return Ok(Value::Null);
+    };
+    let comparator = function_argument("sort", comparator, span)?;
+    let elements = array_elements("sort", &arguments[0], span)?;
+    let sorted = merge_sort(elements, &mut |a, b| {
+        match interpreter.call(comparator.clone(), vec![a.clone(), b.clone()], span)? {
+            other if let Some(order) = other.as_number() => Ok(order.partial_cmp(&0.0).unwrap_or(Ordering::Equal)),
+            other => Err(RuntimeError::with_code(
+                &codes::WRONG_TYPE,
+                format!("sort() comparator must return a number, got {}", other.type_name()),
+                span,
+            )),
+        }
+    })?;
+    *array.borrow_mut() = sorted;
+    Ok(Value::Null)
+}
+
+/// Sorts an array in place by the key the function returns for each
+/// element, which must be only numbers or only strings. Each key is computed
+/// once, and elements with equal keys keep their order.
+fn sort_by_key(interpreter: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let array = array_argument("sort_by_key", &arguments[0], span)?;
+    let elements = array_elements("sort_by_key", &arguments[0], span)?;
+    let function = function_argument("sort_by_key", &arguments[1], span)?;
+    let mut keys = Vec::with_capacity(elements.len());
+    for element in &elements {
+        keys.push(interpreter.call(function.clone(), vec![element.clone()], span)?);
+    }
+    check_sortable("sort_by_key() expects keys that are only numbers or only strings", &keys, span)?;
+    let mut keyed: Vec<(Value, Value)> = keys.into_iter().zip(elements).collect();
+    keyed.sort_by(|(a, _), (b, _)| compare_sortable(a, b));
+    *array.borrow_mut() = keyed.into_iter().map(|(_, element)| element).collect();
+    Ok(Value::Null)
+}
+
+/// Fails unless the values are only numbers or only strings, naming the
+/// first two types that differ.
+fn check_sortable(expects: &str, values: &[Value], span: Span) -> Result<(), RuntimeError> {
+    let all_numbers = values.iter().all(|value| value.as_number().is_some());
+    let all_strings = values.iter().all(|value| matches!(value, Value::String(_)));
+    if all_numbers || all_strings {
+        return Ok(());
+    }
+    let first = &values[0];
+    let other = values
+        .iter()
+        .find(|value| value.type_name() != first.type_name())
+        .unwrap_or(first);
+    Err(RuntimeError::with_code(
+        &codes::WRONG_TYPE,
+        format!("{}, got {} and {}", expects, first.type_name(), other.type_name()),
+        span,
+    ))
+}
+
+/// The ascending order of two values that passed `check_sortable`.
+fn compare_sortable(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Int(a), Value::Int(b)) => a.cmp(b),
+        (Value::Int(a), Value::Number(b)) if let Some(ordering) = compare_int(*a, *b) => ordering,
+        (Value::Number(a), Value::Int(b)) if let Some(ordering) = compare_int(*b, *a) => ordering.reverse(),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (a, b) if let (Some(a), Some(b)) = (a.as_number(), b.as_number()) => a.total_cmp(&b),
+        _ => unreachable!("element types were checked by check_sortable"),
+    }
+}
+
+/// A stable merge sort whose comparison can fail, since a script comparator
+/// can raise an error (which `sort_by` has no way to carry) and need not be
+/// consistent (which `sort_by` may panic on).
+fn merge_sort(
+    mut values: Vec<Value>,
+    compare: &mut dyn FnMut(&Value, &Value) -> Result<Ordering, RuntimeError>,
+) -> Result<Vec<Value>, RuntimeError> {
+    if values.len() < 2 {
+        return Ok(values);
+    }
+    let right = values.split_off(values.len() / 2);
+    let left = merge_sort(values, compare)?;
+    let right = merge_sort(right, compare)?;
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let mut left = left.into_iter().peekable();
+    let mut right = right.into_iter().peekable();
+    while let (Some(a), Some(b)) = (left.peek(), right.peek()) {
+        // Taking from the left on ties is what keeps the sort stable.
+        if compare(a, b)? == Ordering::Greater {
+            merged.extend(right.next());
+        } else {
+            merged.extend(left.next());
+        }
+    }
+    merged.extend(left);
+    merged.extend(right);
+    Ok(merged)
+}
+
+/// Reverses the order of the elements, in place.
+fn reverse(_: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let elements = array_argument("reverse", &arguments[0], span)?;
+    elements.borrow_mut().reverse();
+    Ok(Value::Null)
+}
+
+/// Extracts a function argument (script-defined, builtin, or native), or
+/// reports which builtin wanted one.
+fn function_argument(name: &str, value: &Value, span: Span) -> Result<Value, RuntimeError> {
+    match value {
+        Value::Function(_) | Value::Builtin(_) | Value::NativeFunction(_) => Ok(value.clone()),
+        other => Err(RuntimeError::with_code(
+            &codes::WRONG_TYPE,
+            format!("{}() expects a function, got {}", name, other.type_name()),
+            span,
+        )),
+    }
+}
+
+/// A copy of an array argument's elements, so a callback that changes the
+/// array doesn't disturb the iteration.
+fn array_elements(name: &str, value: &Value, span: Span) -> Result<Vec<Value>, RuntimeError> {
+    Ok(array_argument(name, value, span)?.borrow().clone())
+}
+
+/// A new array of the function's result for each element.
+fn map(interpreter: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let elements = array_elements("map", &arguments[0], span)?;
+    let function = function_argument("map", &arguments[1], span)?;
+    let mut results = Vec::with_capacity(elements.len());
+    for element in elements {
+        results.push(interpreter.call(function.clone(), vec![element], span)?);
+    }
+    Ok(Value::array(results))
+}
+
+/// A new array of the elements for which the predicate returns a truthy
+/// value.
+fn filter(interpreter: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let elements = array_elements("filter", &arguments[0], span)?;
+    let predicate = function_argument("filter", &arguments[1], span)?;
+    let mut results = Vec::new();
+    for element in elements {
+        if interpreter.call(predicate.clone(), vec![element.clone()], span)?.is_truthy() {
+            results.push(element);
+        }
+    }
+    Ok(Value::array(results))
+}
+
+/// Combines the elements from left to right as `f(accumulator, element)`,
+/// starting from `init`, or from the first element when there is no `init`.
+fn reduce(interpreter: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let mut elements = array_elements("reduce", &arguments[0], span)?.into_iter();
+    let function = function_argument("reduce", &arguments[1], span)?;
+    let mut accumulator = match arguments.get(2) {
+        Some(init) => init.clone(),
+        None => elements
+            .next()
+            .ok_or_else(|| RuntimeError::with_code(&codes::EMPTY_ARRAY, "reduce() of an empty array needs an initial value", span))?,
+    };
+    for element in elements {
+        accumulator = interpreter.call(function.clone(), vec![accumulator, element], span)?;
+    }
+    Ok(accumulator)
+}
+
+/// The value of an environment variable, or `null` if it isn't set.
+fn env(interpreter: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    if !interpreter.config.allow_env {
+        return Err(RuntimeError::with_code(&codes::BUILTIN_UNAVAILABLE, "environment access disabled", span));
+    }
+    let name = string_argument("env", &arguments[0], span)?;
+    Ok(std::env::var(name).map_or(Value::Null, string_value))
+}
+
+/// Fails unless the interpreter may touch the filesystem.
+fn check_fs_allowed(interpreter: &Interpreter, span: Span) -> Result<(), RuntimeError> {
+    if interpreter.config.allow_fs {
+        Ok(())
+    } else {
+        Err(RuntimeError::with_code(&codes::BUILTIN_UNAVAILABLE, "filesystem access disabled", span))
+    }
+}
+
+/// The whole contents of a UTF-8 text file.
+fn read_file(interpreter: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    check_fs_allowed(interpreter, span)?;
+    let path = string_argument("read_file", &arguments[0], span)?;
+    fs::read_to_string(path)
+        .map(string_value)
+        .map_err(|error| RuntimeError::with_code(&codes::IO_FAILED, format!("read_file() could not read '{}': {}", path, error), span))
+}
+
+/// Replaces the file's contents with the text, creating the file if needed.
+fn write_file(interpreter: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    check_fs_allowed(interpreter, span)?;
+    let path = string_argument("write_file", &arguments[0], span)?;
+    let text = string_argument("write_file", &arguments[1], span)?;
+    fs::write(path, text)
+        .map_err(|error| RuntimeError::with_code(&codes::IO_FAILED, format!("write_file() could not write '{}': {}", path, error), span))?;
+    Ok(Value::Null)
+}
+
+fn file_exists(interpreter: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    check_fs_allowed(interpreter, span)?;
+    let path = string_argument("file_exists", &arguments[0], span)?;
+    Ok(Value::Bool(fs::exists(path).unwrap_or(false)))
+}
+
+/// Stops the program with an exit code, 0 if none is given. Nothing can
+/// catch it, and the program doesn't end with an error; see
+/// `Completion::Exit`.
+fn exit(_: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let code = match arguments.first() {
+        None => 0,
+        Some(Value::Int(n)) if (0..=255).contains(n) => *n as i32,
+        Some(Value::Number(n)) if n.fract() == 0.0 && (0.0..=255.0).contains(n) => *n as i32,
+        Some(other) => {
+            return Err(RuntimeError::with_code(
+                &codes::INVALID_ARGUMENT,
+                format!("exit() expects a whole number from 0 to 255, got {}", other),
+                span,
+            ));
+        }
+    };
+    Err(RuntimeError::exit(code, span))
+}
+
+/// Seconds on a clock that never goes backwards, for timing code by taking
+/// the difference between two calls.
+fn clock(interpreter: &mut Interpreter, _: &[Value], _: Span) -> Result<Value, RuntimeError> {
+    Ok(Value::Number(interpreter.clock().monotonic().as_secs_f64()))
+}
+
+/// Seconds since the Unix epoch, with a fractional part.
+fn now(interpreter: &mut Interpreter, _: &[Value], _: Span) -> Result<Value, RuntimeError> {
+    Ok(Value::Number(interpreter.clock().now().as_secs_f64()))
+}
+
+/// Pauses for a possibly fractional number of seconds. Sleeping counts
+/// against the interpreter's time limit like running does.
+fn sleep(interpreter: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let seconds = number_argument("sleep", &arguments[0], span)?;
+    let duration = Duration::try_from_secs_f64(seconds).map_err(|_| {
+        RuntimeError::with_code(
+            &codes::INVALID_ARGUMENT,
+            format!("sleep() expects a non-negative number of seconds, got {}", arguments[0]),
+            span,
+        )
+    })?;
+    interpreter.sleep(duration, span)?;
+    Ok(Value::Null)
+}
+
+/// Formats a time in seconds since the Unix epoch as a UTC date, replacing
+/// `%Y`, `%m`, `%d`, `%H`, `%M`, and `%S` in the template with the year,
+/// month, day, hour, minute, and second. `%%` stands for a literal `%`.
+// the first and last seconds `format_time` formats, of 0000-01-01 and
+// 9999-12-31, so that `%Y` is always four digits
+const FIRST_FORMATTED_TIME: f64 = -62_167_219_200.0;
+const LAST_FORMATTED_TIME: f64 = 253_402_300_799.0;
+
+fn format_time(_: &mut Interpreter, arguments: &[Value], span: Span) -> Result<Value, RuntimeError> {
+    let seconds = number_argument("format_time", &arguments[0], span)?;
+    let template = string_argument("format_time", &arguments[1], span)?;
+    if !seconds.is_finite() {
+        return Err(RuntimeError::with_code(
+            &codes::INVALID_ARGUMENT,
+            format!("format_time() expects a finite time, got {}", arguments[0]),
+            span,
+        ));
+    }
+    if !(FIRST_FORMATTED_TIME..=LAST_FORMATTED_TIME).contains(&seconds.floor()) {
+        return Err(RuntimeError::with_code(
+            &codes::INVALID_ARGUMENT,
+            format!("format_time() expects a time from the year 0 to 9999, got {}", arguments[0]),
+            span,
+        ));
+    }
+    let (year, month, day, hour, minute, second) = clock::utc_date_time(seconds.floor() as i64);
+    let mut result = String::new();
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('Y') => result.push_str(&format!("{:04}", year)),
+            Some('m') => result.push_str(&format!("{:02}", month)),
+            Some('d') => result.push_str(&format!("{:02}", day)),
+            Some('H') => result.push_str(&format!("{:02}", hour)),
+            Some('M') => result.push_str(&format!("{:02}", minute)),
+            Some('S') => result.push_str(&format!("{:02}", second)),
+            Some('%') => result.push('%'),
+            Some(other) => {
+                return Err(RuntimeError::with_code(
+                    &codes::INVALID_ARGUMENT,
+                    format!("format_time() doesn't know '%{}'", other),
+                    span,
+                ));
+            }
+            None => return Err(RuntimeError::with_code(&codes::INVALID_ARGUMENT, "format_time() template ends with a lone '%'", span)),
+        }
+    }
+    Ok(string_value(result))
+}
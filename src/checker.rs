@@ -0,0 +1,722 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+
+use crate::ast::{Expr, ExprId, ExprKind, Exprs, FunctionDecl, Program, Stmt, StmtKind, UnaryOp};
+use crate::codes::{self, Code};
+use crate::builtins::{self, BuiltinSet};
+use crate::{lint, optimizer, typechecker};
+use crate::lexer::{LexError, Lexer, Span};
+use crate::parser::Parser;
+
+/// How serious a diagnostic is. Errors are problems that would fail at
+/// runtime; warnings point at code that is probably a mistake but runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A problem found in a program without running it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    // what kind of problem this is
+    pub code: &'static Code,
+    pub message: String,
+    pub span: Span,
+    // how to fix it, when there's an obvious way
+    pub suggestion: Option<String>,
+}
+
+impl Diagnostic {
+    /// The longer description of the code `code`, like "E0200", with an
+    /// example of the problem and its fix. See `codes::lookup`.
+    pub fn explanation(code: &str) -> Option<&'static str> {
+        codes::lookup(code).map(|code| code.explanation)
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at line {}, column {}", self.message, self.span.line, self.span.column)
+    }
+}
+
+/// Lexes, parses, and checks `source` without running it, going on past
+/// lexer and parse errors so that all of them are found, along with the
+/// lexer's warnings, what `lint::lint_tokens` finds in the tokens, and
+/// what `check` finds in the statements that did parse. A parse error in a
+/// statement after a lexer error in it isn't reported, since the lexer
+/// error likely caused it. Names declared by a statement that didn't parse
+/// may be reported as undefined. Imports are found as `check` finds them.
+pub fn check_source(source: &str) -> Vec<Diagnostic> {
+    check_source_in(source, None)
+}
+
+/// Like `check_source`, for the source of the file at `path`, whose
+/// imports are found relative to it as `check_file` finds them.
+pub fn check_source_file(source: &str, path: &Path) -> Vec<Diagnostic> {
+    check_source_in(source, Some(path))
+}
+
+fn check_source_in(source: &str, path: Option<&Path>) -> Vec<Diagnostic> {
+    let mut lexer = Lexer::new(source);
+    let (tokens, lex_errors) = lexer.tokenize_all();
+    let mut lints = lint::lint_tokens(&tokens);
+    lints.extend(lint::lint_lexer_warnings(lexer.warnings()));
+    let (program, parse_errors) = Parser::new(tokens).parse_all_with_starts();
+    // the tokens a lexer error left out, like a stray character or the
+    // rest of an unterminated string, would usually make the statement it's
+    // in fail to parse too, which says nothing new
+    let parse_errors = parse_errors.into_iter().filter_map(|(start, parse_error)| {
+        let caused = |lex_error: &LexError| (start..=parse_error.span.start).contains(&lex_error.span.start);
+        (!lex_errors.iter().any(caused)).then_some(parse_error)
+    });
+    let error = |code, message: &str, span| Diagnostic {
+        severity: Severity::Error,
+        code,
+        message: message.to_string(),
+        span,
+        suggestion: None,
+    };
+    let mut diagnostics: Vec<Diagnostic> = lex_errors
+        .iter()
+        .map(|lex_error| error(lex_error.code, &lex_error.message, lex_error.span))
+        .chain(parse_errors.map(|parse_error| error(parse_error.code, &parse_error.message, parse_error.span)))
+        .collect();
+    diagnostics.extend(lints);
+    diagnostics.extend(check_in(&program, &BuiltinSet::default(), path));
+    diagnostics.sort_by_key(|diagnostic| (diagnostic.span.line, diagnostic.span.column));
+    diagnostics
+}
+
+/// How `check_files_with` shares out the files and when it gives up early.
+#[derive(Debug, Clone, Default)]
+pub struct CheckOptions {
+    // how many files to check at once; 0 means one per available core
+    pub jobs: usize,
+    // once a file has an error, skip the files not yet started
+    pub stop_on_error: bool,
+    // the warnings not to report, which `--allow` names
+    pub allow: Vec<&'static Code>,
+}
+
+/// What checking one of the files passed to `check_files` found.
+#[derive(Debug)]
+pub struct FileReport {
+    pub path: PathBuf,
+    pub status: FileStatus,
+}
+
+#[derive(Debug)]
+pub enum FileStatus {
+    // the file's source, to render the diagnostics with
+    Checked { source: String, diagnostics: Vec<Diagnostic> },
+    Unreadable(io::Error),
+    // not started because an earlier file had an error and
+    // `stop_on_error` was set
+    Skipped,
+}
+
+impl FileReport {
+    /// Whether the file couldn't be read or has an error diagnostic.
+    pub fn failed(&self) -> bool {
+        match &self.status {
+            FileStatus::Checked { diagnostics, .. } => {
+                diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error)
+            }
+            FileStatus::Unreadable(_) => true,
+            FileStatus::Skipped => false,
+        }
+    }
+}
+
+/// Runs `check_source_file` on each file in `paths`, several at a time,
+/// with the default options.
+pub fn check_files(paths: &[PathBuf]) -> Vec<FileReport> {
+    check_files_with(paths, &CheckOptions::default())
+}
+
+/// Runs `check_source_file` on each file in `paths` on a pool of threads, each
+/// file wholly on one thread. The reports are in the order of `paths`
+/// whichever finishes first. With `stop_on_error`, which files after a
+/// failing one still get checked depends on what was already running.
+pub fn check_files_with(paths: &[PathBuf], options: &CheckOptions) -> Vec<FileReport> {
+    let jobs = match options.jobs {
+        0 => thread::available_parallelism().map_or(1, usize::from),
+        jobs => jobs,
+    };
+    let next = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let work = || {
+        let mut reports = Vec::new();
+        loop {
+            let index = next.fetch_add(1, Ordering::Relaxed);
+            let Some(path) = paths.get(index) else {
+                return reports;
+            };
+            let status = if options.stop_on_error && failed.load(Ordering::Relaxed) {
+                FileStatus::Skipped
+            } else {
+                match fs::read_to_string(path) {
+                    Ok(source) => {
+                        let mut diagnostics = check_source_file(&source, path);
+                        remove_allowed(&mut diagnostics, &options.allow);
+                        FileStatus::Checked { source, diagnostics }
+                    }
+                    Err(error) => FileStatus::Unreadable(error),
+                }
+            };
+            let report = FileReport { path: path.clone(), status };
+            if report.failed() {
+                failed.store(true, Ordering::Relaxed);
+            }
+            reports.push((index, report));
+        }
+    };
+
+    let mut reports: Vec<(usize, FileReport)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs.min(paths.len())).map(|_| scope.spawn(work)).collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("checking a file doesn't panic"))
+            .collect()
+    });
+    reports.sort_by_key(|(index, _)| *index);
+    reports.into_iter().map(|(_, report)| report).collect()
+}
+
+/// Drops the warnings whose code is in `allowed`. Errors are kept
+/// whatever their code.
+pub fn remove_allowed(diagnostics: &mut Vec<Diagnostic>, allowed: &[&'static Code]) {
+    diagnostics.retain(|diagnostic| diagnostic.severity == Severity::Error || !allowed.contains(&diagnostic.code));
+}
+
+/// Finds variables that are read or assigned without being declared, and
+/// warns about `let` bindings and functions that are never read, in the
+/// order they appear. Scopes are walked the way the interpreter creates them
+/// at runtime, with the builtins as globals.
+///
+/// Code inside a function body may refer to names declared later in an
+/// enclosing scope, since the body only runs once the function is called.
+/// A function calling itself doesn't count as using it, and names starting
+/// with `_` are never reported as unused, nor are exported ones.
+///
+/// A program that imports other files into its global scope can use the
+/// names declared at their top level. Those files are read to find them,
+/// with paths relative to the current directory, as the interpreter finds
+/// them without a script path; `check_file` finds them relative to the
+/// program's file. If one can't be read or parsed, it could declare any
+/// name, so undefined names aren't reported in the program at all. Type
+/// mismatches found by `typechecker::check` are included.
+pub fn check(program: &Program) -> Vec<Diagnostic> {
+    check_with_builtins(program, &BuiltinSet::default())
+}
+
+/// Like `check`, for the program in the file at `path`, whose imports are
+/// found relative to the directory it is in.
+pub fn check_file(program: &Program, path: &Path) -> Vec<Diagnostic> {
+    check_in(program, &BuiltinSet::default(), Some(path))
+}
+
+/// Like `check`, for a program that will run with only the builtins
+/// `builtins` allows. Reading any of the others is warned about, since
+/// calling it would fail.
+pub fn check_with_builtins(program: &Program, builtins: &BuiltinSet) -> Vec<Diagnostic> {
+    check_in(program, builtins, None)
+}
+
+fn check_in(program: &Program, builtins: &BuiltinSet, path: Option<&Path>) -> Vec<Diagnostic> {
+    let statements = &program.statements;
+    let imported = imported_names(statements, path, &mut Vec::new());
+    let mut checker = Checker {
+        exprs: &program.exprs,
+        scopes: Vec::new(),
+        function_depth: 0,
+        functions: Vec::new(),
+        unknown_imports: imported.is_none(),
+        unreachable: false,
+        diagnostics: Vec::new(),
+    };
+    checker.begin_scope(statements);
+    for name in builtins::names() {
+        let kind = if builtins.allows(name) { Kind::Other } else { Kind::DisabledBuiltin };
+        checker.declare(name, kind, Span::default());
+    }
+    for name in imported.iter().flatten() {
+        checker.declare(name, Kind::Other, Span::default());
+    }
+    checker.statements(statements);
+    checker.end_scope();
+    checker.diagnostics.extend(typechecker::check(program));
+    // unused bindings are only known once their scope ends
+    checker.diagnostics.sort_by_key(|diagnostic| (diagnostic.span.line, diagnostic.span.column));
+    checker.diagnostics
+}
+
+/// The names declared at the top level of the files that `statements`, in
+/// the file at `path`, imports into the global scope, and of the files
+/// those import in turn, or `None` if one of them can't be read or parsed.
+/// `seen` holds the files already read, so a cycle of imports ends.
+fn imported_names(statements: &[Stmt], path: Option<&Path>, seen: &mut Vec<PathBuf>) -> Option<Vec<String>> {
+    let mut names = Vec::new();
+    for statement in statements {
+        let StmtKind::Import { path: imported, alias: None } = &statement.kind else {
+            continue;
+        };
+        let resolved = match path.and_then(Path::parent) {
+            Some(directory) => directory.join(imported),
+            None => PathBuf::from(imported),
+        };
+        let canonical = fs::canonicalize(resolved).ok()?;
+        if seen.contains(&canonical) {
+            continue;
+        }
+        seen.push(canonical.clone());
+        let statements = crate::parse(&fs::read_to_string(&canonical).ok()?).ok()?.statements;
+        let declared = statements.iter().flat_map(|statement| statement.without_export().declared_names());
+        names.extend(declared.map(str::to_string));
+        names.extend(imported_names(&statements, Some(&canonical), seen)?);
+    }
+    Some(names)
+}
+
+/// What declared a binding, which decides whether it is reported when
+/// nothing reads it.
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Variable,
+    Function,
+    // parameters, loop variables, and builtins
+    Other,
+    // a builtin the program isn't allowed to call
+    DisabledBuiltin,
+}
+
+/// A name declared somewhere in a scope, whether execution has reached its
+/// declaration yet, and how many times it is read.
+struct Binding {
+    kind: Kind,
+    declared_at: Span,
+    reached: bool,
+    reads: usize,
+}
+
+struct Scope {
+    bindings: HashMap<String, Binding>,
+    // how many function bodies enclose the scope
+    function_depth: usize,
+}
+
+enum Resolution {
+    Declared(Kind),
+    // declared later in a scope the code runs in, with no earlier binding
+    // further out to fall back on
+    NotYetDeclared(Span),
+    Undefined,
+}
+
+struct Checker<'a> {
+    // the arena of the expressions in the function or program being checked
+    exprs: &'a Exprs,
+    scopes: Vec<Scope>,
+    function_depth: usize,
+    // the named functions whose bodies are being checked, with the index of
+    // the scope they are declared in
+    functions: Vec<(String, usize)>,
+    // whether the program imports a file that couldn't be read, which may
+    // declare any name
+    unknown_imports: bool,
+    // whether the code being checked can never run, which has been warned
+    // about at its first statement and so isn't again inside it
+    unreachable: bool,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Checker<'a> {
+    /// Enters a scope whose declarations are `statements`' `let`s and
+    /// functions, none of them reached yet.
+    fn begin_scope(&mut self, statements: &[Stmt]) {
+        let mut bindings = HashMap::new();
+        for statement in statements {
+            let declared: Vec<(&str, Kind, Span)> = match &statement.without_export().kind {
+                StmtKind::Let { name, name_span, .. } => vec![(name, Kind::Variable, *name_span)],
+                StmtKind::Destructure { pattern, .. } => pattern
+                    .names()
+                    .into_iter()
+                    .map(|(name, span)| (name, Kind::Variable, span))
+                    .collect(),
+                StmtKind::Function(declaration) => vec![(&declaration.name, Kind::Function, declaration.name_span)],
+                StmtKind::Class(declaration) => vec![(&declaration.name, Kind::Other, statement.span)],
+                StmtKind::Import { alias: Some(alias), .. } => vec![(alias, Kind::Other, statement.span)],
+                _ => continue,
+            };
+            for (name, kind, declared_at) in declared {
+                bindings.insert(
+                    name.to_string(),
+                    Binding {
+                        kind,
+                        declared_at,
+                        reached: false,
+                        reads: 0,
+                    },
+                );
+            }
+        }
+        self.scopes.push(Scope {
+            bindings,
+            function_depth: self.function_depth,
+        });
+    }
+
+    /// Leaves the current scope, warning about its bindings nothing read.
+    fn end_scope(&mut self) {
+        let scope = self.scopes.pop().expect("scopes are begun and ended in pairs");
+        for (name, binding) in scope.bindings {
+            if binding.reads > 0 || !binding.reached || name.starts_with('_') {
+                continue;
+            }
+            let (code, message) = match binding.kind {
+                Kind::Variable => (&codes::UNUSED_VARIABLE, format!("Unused variable '{}'", name)),
+                Kind::Function => (&codes::UNUSED_FUNCTION, format!("Unused function '{}'", name)),
+                Kind::Other | Kind::DisabledBuiltin => continue,
+            };
+            self.report(Severity::Warning, code, message, binding.declared_at);
+        }
+    }
+
+    /// Marks `name` as reached in the current scope. Reads counted before
+    /// the declaration was reached, from function bodies, are kept.
+    fn declare(&mut self, name: &str, kind: Kind, span: Span) {
+        let scope = self.scopes.last_mut().expect("there is always a global scope");
+        let reads = scope.bindings.get(name).map_or(0, |binding| binding.reads);
+        scope.bindings.insert(
+            name.to_string(),
+            Binding {
+                kind,
+                declared_at: span,
+                reached: true,
+                reads,
+            },
+        );
+    }
+
+    /// Finds the binding `name` refers to from the current scope. `read`
+    /// says whether to count this as a read of it.
+    fn resolve(&mut self, name: &str, read: bool) -> Resolution {
+        let mut pending = None;
+        for (index, scope) in self.scopes.iter_mut().enumerate().rev() {
+            let Some(binding) = scope.bindings.get_mut(name) else {
+                continue;
+            };
+            // a function body runs after the code around it, so a later
+            // declaration outside the function will have been reached
+            if binding.reached || scope.function_depth < self.function_depth {
+                let recursive = self.functions.iter().any(|(function, at)| function == name && *at == index);
+                if read && !recursive {
+                    binding.reads += 1;
+                }
+                return Resolution::Declared(binding.kind);
+            }
+            pending = pending.or(Some(binding.declared_at));
+        }
+        match pending {
+            Some(declared_at) => Resolution::NotYetDeclared(declared_at),
+            None => Resolution::Undefined,
+        }
+    }
+
+    fn expr(&self, id: ExprId) -> &'a Expr {
+        &self.exprs[id]
+    }
+
+    fn report(&mut self, severity: Severity, code: &'static Code, message: String, span: Span) {
+        self.diagnostics.push(Diagnostic { severity, code, message, span, suggestion: None });
+    }
+
+    fn use_name(&mut self, name: &str, span: Span, read: bool) {
+        match self.resolve(name, read) {
+            Resolution::Declared(Kind::DisabledBuiltin) if read => self.report(
+                Severity::Warning,
+                &codes::DISABLED_BUILTIN,
+                format!("Builtin '{}' is not available in this environment", name),
+                span,
+            ),
+            Resolution::Declared(_) => {}
+            Resolution::NotYetDeclared(declared_at) => self.report(
+                Severity::Error,
+                &codes::USED_BEFORE_DECLARATION,
+                format!("'{}' is used before its declaration on line {}", name, declared_at.line),
+                span,
+            ),
+            Resolution::Undefined if self.unknown_imports => {}
+            Resolution::Undefined => {
+                let (code, message) = match read {
+                    true => (&codes::UNDEFINED_VARIABLE, format!("Undefined variable '{}'", name)),
+                    false => (&codes::UNDECLARED_ASSIGNMENT, format!("Assignment to undeclared variable '{}'", name)),
+                };
+                self.report(Severity::Error, code, message, span);
+            }
+        }
+    }
+
+    fn statements(&mut self, statements: &'a [Stmt]) {
+        let outer = self.unreachable;
+        let mut diverged = false;
+        for statement in statements {
+            // tests don't run in order with the code around them
+            let reached = !diverged || matches!(statement.kind, StmtKind::Test { .. });
+            if !reached && !self.unreachable {
+                self.report_unreachable(statement, "Unreachable code".to_string());
+            }
+            self.unreachable = outer || !reached;
+            self.statement(statement);
+            diverged |= statement.diverges();
+        }
+        self.unreachable = outer;
+    }
+
+    /// Checks `statements`, a branch taken when `condition` is truthy if
+    /// `taken_when` is true and falsy otherwise, in a new scope.
+    fn branch(&mut self, condition: &Expr, taken_when: bool, statements: &'a [Stmt]) {
+        let never = optimizer::constant_condition(condition, self.exprs) == Some(!taken_when);
+        if never
+            && !self.unreachable
+            && let Some(first) = statements.first()
+        {
+            let message = format!("Unreachable code: the condition is always {}", !taken_when);
+            self.report_unreachable(first, message);
+        }
+        let outer = self.unreachable;
+        self.unreachable |= never;
+        self.scoped(statements);
+        self.unreachable = outer;
+    }
+
+    fn report_unreachable(&mut self, stmt: &Stmt, message: String) {
+        self.report(Severity::Warning, &codes::UNREACHABLE_CODE, message, stmt.keyword_span());
+    }
+
+    /// Checks `statements` in a new scope of their own.
+    fn scoped(&mut self, statements: &'a [Stmt]) {
+        self.begin_scope(statements);
+        self.statements(statements);
+        self.end_scope();
+    }
+
+    fn statement(&mut self, stmt: &'a Stmt) {
+        match &stmt.kind {
+            StmtKind::Expression(expr) => self.expression(expr),
+            StmtKind::Let { name, name_span, initializer, .. } => {
+                if let Some(initializer) = initializer {
+                    self.expression(initializer);
+                }
+                self.declare(name, Kind::Variable, *name_span);
+            }
+            StmtKind::Destructure { pattern, initializer, .. } => {
+                self.expression(initializer);
+                for (name, span) in pattern.names() {
+                    self.declare(name, Kind::Variable, span);
+                }
+            }
+            StmtKind::Block(statements) => self.scoped(statements),
+            StmtKind::If { condition, then_branch, else_branch } => {
+                self.condition(condition);
+                self.branch(condition, true, then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.branch(condition, false, else_branch);
+                }
+            }
+            StmtKind::While { condition, body, .. } => {
+                self.condition(condition);
+                self.branch(condition, true, body);
+            }
+            StmtKind::DoWhile { body, condition, .. } => {
+                self.scoped(body);
+                self.condition(condition);
+            }
+            StmtKind::Match { subject, arms, default } => {
+                self.expression(subject);
+                for arm in arms {
+                    for value in &arm.values {
+                        self.expression(value);
+                    }
+                    self.scoped(&arm.body);
+                }
+                if let Some(default) = default {
+                    self.scoped(default);
+                }
+            }
+            StmtKind::For { initializer, condition, increment, body, .. } => {
+                // the initializer's variable lives in a scope of its own
+                let initializer: &[Stmt] = match initializer {
+                    Some(initializer) => std::slice::from_ref(initializer),
+                    None => &[],
+                };
+                self.begin_scope(initializer);
+                self.statements(initializer);
+                if let Some(condition) = condition {
+                    self.condition(condition);
+                }
+                self.scoped(body);
+                if let Some(increment) = increment {
+                    self.expression(increment);
+                }
+                self.end_scope();
+            }
+            StmtKind::ForIn { variable, iterable, body, .. } => {
+                self.expression(iterable);
+                // the loop variable shares a scope with the body
+                self.begin_scope(body);
+                self.declare(variable, Kind::Other, iterable.span);
+                self.statements(body);
+                self.end_scope();
+            }
+            StmtKind::Function(declaration) => {
+                // declared before its body is checked, since the body can
+                // only run once the declaration has been reached
+                self.declare(&declaration.name, Kind::Function, declaration.name_span);
+                self.functions.push((declaration.name.to_string(), self.scopes.len() - 1));
+                self.function(declaration);
+                self.functions.pop();
+            }
+            StmtKind::Class(declaration) => {
+                self.declare(&declaration.name, Kind::Other, stmt.span);
+                for method in &declaration.methods {
+                    self.function(method);
+                }
+            }
+            StmtKind::Return(value) => {
+                if let Some(value) = value {
+                    self.expression(value);
+                }
+            }
+            StmtKind::Try { body, name, handler } => {
+                self.scoped(body);
+                // the caught error shares a scope with the catch block
+                self.begin_scope(handler);
+                self.declare(name, Kind::Other, stmt.span);
+                self.statements(handler);
+                self.end_scope();
+            }
+            StmtKind::Throw(value) => self.expression(value),
+            StmtKind::Export(declaration) => {
+                self.statement(declaration);
+                // files importing this one as a module can read the name
+                for name in declaration.declared_names() {
+                    self.resolve(name, true);
+                }
+            }
+            StmtKind::Import { alias: Some(alias), .. } => self.declare(alias, Kind::Other, stmt.span),
+            StmtKind::Test { body, .. } => self.scoped(body),
+            StmtKind::Break(_) | StmtKind::Continue(_) | StmtKind::Import { alias: None, .. } => {}
+        }
+    }
+
+    /// Checks a function body, which shares a scope with the parameters.
+    fn function(&mut self, declaration: &'a FunctionDecl) {
+        let enclosing_exprs = std::mem::replace(&mut self.exprs, &declaration.exprs);
+        self.function_depth += 1;
+        self.begin_scope(&declaration.body);
+        for param in &declaration.params {
+            if let Some(default) = &param.default {
+                self.expression(default);
+            }
+            self.declare(&param.name, Kind::Other, param.span);
+        }
+        self.statements(&declaration.body);
+        self.end_scope();
+        self.function_depth -= 1;
+        self.exprs = enclosing_exprs;
+    }
+
+    /// Checks a loop's or `if`'s condition, warning if it assigns where it
+    /// probably meant to compare.
+    fn condition(&mut self, condition: &'a Expr) {
+        self.expression(condition);
+        let mut pending = vec![condition];
+        while let Some(expr) = pending.pop() {
+            match &expr.kind {
+                // a compound assignment like `-=` can't be a mistyped `==`
+                ExprKind::Assign { operator: None, .. }
+                | ExprKind::SetIndex { operator: None, .. }
+                | ExprKind::SetMember { operator: None, .. } => {
+                    let target = match &expr.kind {
+                        ExprKind::Assign { name, .. } => format!("'{}'", name),
+                        _ => "a value".to_string(),
+                    };
+                    self.diagnostics.push(Diagnostic {
+                        severity: Severity::Warning,
+                        code: &codes::ASSIGN_IN_CONDITION,
+                        message: format!("The condition assigns to {} instead of comparing", target),
+                        span: expr.span,
+                        suggestion: Some("use `==` to compare".to_string()),
+                    });
+                }
+                // `!` and `&&` and `||` test their operands as conditions too
+                ExprKind::Unary { operator: UnaryOp::Not, operand } => pending.push(self.expr(*operand)),
+                ExprKind::Logical { left, right, .. } => pending.extend([self.expr(*left), self.expr(*right)]),
+                _ => {}
+            }
+        }
+    }
+
+    fn expression(&mut self, expr: &'a Expr) {
+        match &expr.kind {
+            ExprKind::Number(_) | ExprKind::Int(_) | ExprKind::String(_) | ExprKind::Bool(_) | ExprKind::Null => {}
+            ExprKind::Variable(name) => self.use_name(name, expr.span, true),
+            ExprKind::Assign { name, operator, value } => {
+                self.expression(self.expr(*value));
+                // the assignment's span starts with the name being assigned
+                let span = Span {
+                    end: expr.span.start + name.len(),
+                    ..expr.span
+                };
+                // a compound assignment reads the variable before assigning it
+                self.use_name(name, span, operator.is_some());
+            }
+            ExprKind::Unary { operand, .. } => self.expression(self.expr(*operand)),
+            ExprKind::Binary { left, right, .. } | ExprKind::Logical { left, right, .. } => {
+                self.expression(self.expr(*left));
+                self.expression(self.expr(*right));
+            }
+            ExprKind::Call { callee, arguments } => {
+                self.expression(self.expr(*callee));
+                arguments.iter().for_each(|argument| self.expression(argument));
+            }
+            ExprKind::Array(elements) => elements.iter().for_each(|element| self.expression(element)),
+            ExprKind::Index { object, index } => {
+                self.expression(self.expr(*object));
+                self.expression(self.expr(*index));
+            }
+            ExprKind::Slice { object, start, end, .. } => {
+                self.expression(self.expr(*object));
+                for bound in [start, end].into_iter().flatten() {
+                    self.expression(self.expr(*bound));
+                }
+            }
+            ExprKind::SetIndex { object, index, value, .. } => {
+                self.expression(self.expr(*object));
+                self.expression(self.expr(*index));
+                self.expression(self.expr(*value));
+            }
+            ExprKind::Map(entries) => entries.iter().for_each(|(_, value)| self.expression(value)),
+            ExprKind::Member { object, .. } => self.expression(self.expr(*object)),
+            ExprKind::SetMember { object, value, .. } => {
+                self.expression(self.expr(*object));
+                self.expression(self.expr(*value));
+            }
+            ExprKind::Range { start, end, .. } => {
+                self.expression(self.expr(*start));
+                self.expression(self.expr(*end));
+            }
+            ExprKind::Function(declaration) => self.function(declaration),
+            ExprKind::This => {}
+        }
+    }
+}
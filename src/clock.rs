@@ -0,0 +1,91 @@
+use std::cell::Cell;
+use std::fmt;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Where `clock()`, `now()`, and `sleep()` get the time from. Interpreters
+/// use `SystemClock` unless `InterpreterConfig::clock` sets another, e.g. a
+/// `FakeClock` in tests.
+pub trait Clock: fmt::Debug {
+    /// Time since some fixed point, which never goes backwards.
+    fn monotonic(&self) -> Duration;
+    /// Time since the Unix epoch.
+    fn now(&self) -> Duration;
+    fn sleep(&self, duration: Duration);
+}
+
+/// The real time, measured from the first time any interpreter asked.
+#[derive(Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn monotonic(&self) -> Duration {
+        static START: OnceLock<Instant> = OnceLock::new();
+        START.get_or_init(Instant::now).elapsed()
+    }
+
+    fn now(&self) -> Duration {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+/// A clock that only moves when told to. Sleeping advances it instantly.
+#[derive(Debug, Default)]
+pub struct FakeClock {
+    monotonic: Cell<Duration>,
+    now: Cell<Duration>,
+}
+
+impl FakeClock {
+    /// A clock reading `now` since the Unix epoch and zero on the
+    /// monotonic clock.
+    pub fn starting_at(now: Duration) -> Self {
+        FakeClock {
+            monotonic: Cell::new(Duration::ZERO),
+            now: Cell::new(now),
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.monotonic.set(self.monotonic.get() + duration);
+        self.now.set(self.now.get() + duration);
+    }
+}
+
+impl Clock for FakeClock {
+    fn monotonic(&self) -> Duration {
+        self.monotonic.get()
+    }
+
+    fn now(&self) -> Duration {
+        self.now.get()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+    }
+}
+
+/// The UTC date and time `seconds` after the Unix epoch, which may be
+/// negative, as `(year, month, day, hour, minute, second)` with months and
+/// days counted from 1.
+pub fn utc_date_time(seconds: i64) -> (i64, u32, u32, u32, u32, u32) {
+    let days = seconds.div_euclid(86_400);
+    let time = seconds.rem_euclid(86_400) as u32;
+    // Howard Hinnant's civil_from_days, with eras of 400 years starting on
+    // March 1st so leap days fall at the end of a year
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day, time / 3600, time / 60 % 60, time % 60)
+}
@@ -0,0 +1,718 @@
+//! Every kind of problem slimescript reports, each with a stable code that
+//! is printed with the message and can be looked up with
+//! `slimescript explain`. Codes are grouped by where the problem is found:
+//! `E00xx` by the lexer, `E01xx` the parser, `E02xx` the checker, `E03xx`
+//! the type checker, `E04xx` while loading imports, and `E05xx` at runtime.
+//! Warnings are `W0xxx`. A code never changes meaning once released.
+
+/// A kind of problem, such as an undefined variable.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Code {
+    // the stable code, like "E0200"
+    pub id: &'static str,
+    // a readable name for tools, like "undefined-variable"
+    pub name: &'static str,
+    // what the problem means and how to fix it, with an example
+    pub explanation: &'static str,
+}
+
+impl Code {
+    /// Whether the code is for a warning, which `--allow` can turn off.
+    pub fn is_warning(&self) -> bool {
+        self.id.starts_with('W')
+    }
+}
+
+pub static UNEXPECTED_CHARACTER: Code = Code {
+    id: "E0001",
+    name: "unexpected-character",
+    explanation: r#"The source contains a character that can't start any token, such as `@` or
+`#`, or a lone `&` or `|`.
+
+    let total = price @ 2;   // wrong
+    let total = price * 2;   // fixed
+
+Logical operators are written twice: `&&` and `||`.
+
+Text pasted from chat apps, word processors, and PDFs often has characters
+that only look like the ones slimescript uses: curly quotes, dashes, a
+minus sign, or fullwidth forms. The message names such a character and
+says what to write instead.
+
+    let name = “Ada”;   // wrong: curly quotes
+    let name = "Ada";   // fixed"#,
+};
+
+pub static UNTERMINATED_STRING: Code = Code {
+    id: "E0002",
+    name: "unterminated-string",
+    explanation: r#"A string literal has no closing `"` before the end of the input, often
+because a `\"` escape was meant to end it.
+
+    let greeting = "hello;   // wrong
+    let greeting = "hello";  // fixed"#,
+};
+
+pub static INVALID_ESCAPE: Code = Code {
+    id: "E0003",
+    name: "invalid-escape",
+    explanation: r#"A backslash in a string is followed by a character that isn't a known
+escape, or by a malformed `\u{...}`. The escapes are `\n`, `\t`, `\r`, `\\`,
+`\"`, and `\u{...}` with one to six hex digits naming a Unicode character,
+like `\u{e9}` for é.
+
+    let path = "C:\data";    // wrong: `\d` isn't an escape
+    let path = "C:\\data";   // fixed"#,
+};
+
+pub static UNEXPECTED_TOKEN: Code = Code {
+    id: "E0100",
+    name: "unexpected-token",
+    explanation: r#"The parser found a token where the grammar doesn't allow one, like a
+missing bracket or an operator with nothing after it.
+
+    print(1 +);   // wrong
+    print(1 + 2); // fixed"#,
+};
+
+pub static MISSING_SEMICOLON: Code = Code {
+    id: "E0101",
+    name: "missing-semicolon",
+    explanation: r#"Statements other than blocks, `if`, `match`, `try`, `while` and `for` loops,
+and function declarations end with `;`, including `do { ... } while
+condition;` and `throw value;`.
+
+    let x = 1    // wrong
+    let x = 1;   // fixed"#,
+};
+
+pub static INVALID_ASSIGNMENT_TARGET: Code = Code {
+    id: "E0102",
+    name: "invalid-assignment-target",
+    explanation: r#"Only variables, indexes like `xs[0]`, and properties like `point.x` can be
+assigned to.
+
+    1 = x;   // wrong
+    x = 1;   // fixed"#,
+};
+
+pub static DUPLICATE_PARAMETER: Code = Code {
+    id: "E0103",
+    name: "duplicate-parameter",
+    explanation: r#"A function declares the same parameter name twice, so one of them could
+never be read.
+
+    function add(a, a) { return a + a; }   // wrong
+    function add(a, b) { return a + b; }   // fixed"#,
+};
+
+pub static MISPLACED_STATEMENT: Code = Code {
+    id: "E0104",
+    name: "misplaced-statement",
+    explanation: r#"A statement appears somewhere it can't run: `break` or `continue` outside a
+loop, `import` or `export` anywhere but the top level of a file, `this`
+outside a class's methods, or a label like `outer:` before something that
+isn't a loop.
+
+    if (done) { break; }                  // wrong, outside a loop
+    while (true) { if (done) { break; } } // fixed"#,
+};
+
+pub static INVALID_NUMBER: Code = Code {
+    id: "E0105",
+    name: "invalid-number",
+    explanation: r#"A number literal couldn't be read as a number, like one with two decimal
+points.
+
+    let version = 1.2.3;     // wrong
+    let version = "1.2.3";   // fixed
+
+It is also the error for an integer literal outside the range of a 64-bit
+integer when the interpreter treats integer overflow as an error (see
+`integer-overflow`, E0520). Write it with a fraction to make it a
+fractional number.
+
+    let big = 9223372036854775808;     // wrong
+    let big = 9223372036854775808.0;   // fixed"#,
+};
+
+pub static TOO_DEEPLY_NESTED: Code = Code {
+    id: "E0106",
+    name: "too-deeply-nested",
+    explanation: r#"Expressions, blocks or patterns nest more than 200 levels deep, which usually means
+the code was generated. A long chain of operators counts too, since
+`a + b + c` is `(a + b) + c`. Build the value up in steps instead.
+
+    let total = 1 + 2 + 3 + ... + 500;   // wrong
+
+    let total = 0;
+    for n in 1..=500 { total = total + n; }   // fixed"#,
+};
+
+pub static RESERVED_KEYWORD: Code = Code {
+    id: "E0107",
+    name: "reserved-keyword",
+    explanation: r#"A keyword was used where a name is declared: a variable, constant, function,
+class, method, parameter, or caught error. Keywords like `if`, `class`, and
+`true` can't be names; pick another one.
+
+    let class = "warrior";        // wrong
+    let class_name = "warrior";   // fixed"#,
+};
+
+pub static NOT_READ_ONLY: Code = Code {
+    id: "E0108",
+    name: "not-read-only",
+    explanation: r#"An expression given to `CompiledExpr::compile` or `eval_with_vars`
+assigns to something or defines a function. Those only evaluate formulas
+over the variables they're given, so their input can't change anything.
+
+    total = price * qty      // wrong
+    price * qty              // fixed"#,
+};
+
+pub static CHAINED_COMPARISON: Code = Code {
+    id: "E0109",
+    name: "chained-comparison",
+    explanation: r#"Two comparisons are chained, like `1 < x < 10` or `a == b == c`. That
+would compare the first comparison's true or false to the last operand
+rather than checking both, so it isn't allowed. Compare each pair and join
+them with `&&`, or put one comparison in parentheses to really compare its
+result.
+
+    if 1 < x < 10 { }          // wrong
+    if 1 < x && x < 10 { }     // fixed"#,
+};
+
+pub static PIPE_WITHOUT_CALL: Code = Code {
+    id: "E0110",
+    name: "pipe-without-call",
+    explanation: r#"The right side of `|>` isn't a call. `x |> f(a)` means `f(x, a)`: the value
+on the left becomes the first argument of the call on the right, so there
+has to be one, even with no other arguments.
+
+    lines |> trim           // wrong
+    lines |> trim()         // fixed"#,
+};
+
+pub static UNDEFINED_LABEL: Code = Code {
+    id: "E0111",
+    name: "undefined-label",
+    explanation: r#"A `break` or `continue` names a label that none of the loops around it in
+the same function has. A loop is labelled by writing `name:` before it, and
+`break name;` or `continue name;` inside it, however deeply nested, leaves
+that loop or goes on to its next iteration.
+
+    outer: for row in grid {
+        for cell in row {
+            if (cell == target) { break outter; }   // wrong, misspelled
+            if (cell == target) { break outer; }    // fixed
+        }
+    }"#,
+};
+
+pub static UNDEFINED_VARIABLE: Code = Code {
+    id: "E0200",
+    name: "undefined-variable",
+    explanation: r#"A name is read that no `let`, `const`, function, parameter, or builtin in
+scope declares. Check its spelling, and that it isn't declared in a block
+that has already ended.
+
+    if (ok) { let message = "yes"; }
+    print(message);                      // wrong
+
+    let message = "no";
+    if (ok) { message = "yes"; }
+    print(message);                      // fixed"#,
+};
+
+pub static UNDECLARED_ASSIGNMENT: Code = Code {
+    id: "E0201",
+    name: "undeclared-assignment",
+    explanation: r#"A variable is assigned to without ever being declared. Declare it with
+`let` first.
+
+    count = 0;       // wrong
+    let count = 0;   // fixed"#,
+};
+
+pub static USED_BEFORE_DECLARATION: Code = Code {
+    id: "E0202",
+    name: "used-before-declaration",
+    explanation: r#"A variable is read before the `let` that declares it has run.
+
+    print(x);
+    let x = 1;   // wrong
+
+    let x = 1;
+    print(x);    // fixed"#,
+};
+
+pub static TYPE_MISMATCH: Code = Code {
+    id: "E0300",
+    name: "type-mismatch",
+    explanation: r#"A value doesn't have the type its annotation asks for.
+
+    let n: number = "five";   // wrong
+    let n: number = 5;        // fixed"#,
+};
+
+pub static INVALID_OPERAND: Code = Code {
+    id: "E0301",
+    name: "invalid-operand",
+    explanation: r#"An operator is applied to a value of a type it doesn't work on, such as
+subtracting from a string.
+
+    let rest = "10" - 1;        // wrong
+    let rest = num("10") - 1;   // fixed"#,
+};
+
+pub static IMPORT_FAILED: Code = Code {
+    id: "E0400",
+    name: "import-failed",
+    explanation: r#"An imported file couldn't be read. Paths are relative to the file doing the
+importing.
+
+    import "utils.slime";       // wrong, if utils.slime is in lib/
+    import "lib/utils.slime";   // fixed"#,
+};
+
+pub static CIRCULAR_IMPORT: Code = Code {
+    id: "E0401",
+    name: "circular-import",
+    explanation: r#"A file ends up importing itself, directly or through other files, so its
+statements would never finish loading. Move what both files need into a
+third file they each import.
+
+    // a.slime: import "b.slime";
+    // b.slime: import "a.slime";   // wrong
+
+    // a.slime: import "shared.slime";
+    // b.slime: import "shared.slime";   // fixed"#,
+};
+
+pub static RUNTIME_ERROR: Code = Code {
+    id: "E0500",
+    name: "runtime-error",
+    explanation: r#"The program failed while running, for a reason without a code of its own,
+usually an error returned by a function the program embedding the
+interpreter registered. The message says what went wrong; the embedding
+program's documentation says what that function expects."#,
+};
+
+pub static UNDEFINED_NAME: Code = Code {
+    id: "E0501",
+    name: "undefined-name",
+    explanation: r#"A variable or function was looked up while running and doesn't exist, e.g.
+a function the host program was expected to register.
+
+    print(totl);    // wrong
+    print(total);   // fixed"#,
+};
+
+pub static DIVISION_BY_ZERO: Code = Code {
+    id: "E0502",
+    name: "division-by-zero",
+    explanation: r#"A number was divided by zero, or taken modulo zero. Check the divisor
+first.
+
+    let average = sum / count;   // wrong, if count is 0
+
+    let average = 0;
+    if (count != 0) { average = sum / count; }   // fixed"#,
+};
+
+pub static INDEX_OUT_OF_BOUNDS: Code = Code {
+    id: "E0503",
+    name: "index-out-of-bounds",
+    explanation: r#"An array or string was indexed or sliced past its end, or sliced with a
+start after its end. Indexes start at 0, so the last element is at
+`len(xs) - 1`, and a string's positions count characters, not bytes.
+
+    let last = xs[len(xs)];       // wrong
+    let last = xs[len(xs) - 1];   // fixed"#,
+};
+
+pub static MISSING_KEY: Code = Code {
+    id: "E0504",
+    name: "missing-key",
+    explanation: r#"A map was read at a key it doesn't have, or an instance at a field its
+class doesn't define as a method and nothing has set. Check maps with `has`
+first.
+
+    let port = config["port"];   // wrong, if unset
+
+    let port = 80;
+    if (has(config, "port")) { port = config["port"]; }   // fixed"#,
+};
+
+pub static WRONG_TYPE: Code = Code {
+    id: "E0505",
+    name: "wrong-type",
+    explanation: r#"A value was used in a way its type doesn't support, like calling a number,
+indexing a boolean, or adding a string to an array.
+
+    let n = 5; n();       // wrong
+    let f = len; f("a");  // fixed"#,
+};
+
+pub static RECURSION_LIMIT: Code = Code {
+    id: "E0506",
+    name: "recursion-limit",
+    explanation: r#"Function calls nested too deeply, usually because a recursive function has
+no base case. A call that is the whole value of a `return` doesn't count,
+since it replaces the function making it; stack traces leave out the frames
+such calls replaced, too.
+
+    function count(n) { return 1 + count(n + 1); }   // wrong
+
+    function count(n) {
+        if (n > 10) { return 0; }
+        return 1 + count(n + 1);
+    }                                                // fixed"#,
+};
+
+pub static BUDGET_EXCEEDED: Code = Code {
+    id: "E0507",
+    name: "budget-exceeded",
+    explanation: r#"The program ran more steps, or for longer, than the interpreter running it
+allows, often because of a loop that never ends. `catch` doesn't stop it, so
+a script can't outlast its limits by catching it.
+
+    let i = 0; while (i < 10) { print(i); }            // wrong
+    let i = 0; while (i < 10) { print(i); i = i + 1; } // fixed"#,
+};
+
+pub static ASSERTION_FAILED: Code = Code {
+    id: "E0508",
+    name: "assertion-failed",
+    explanation: r#"`assert` was given a falsy condition, or `panic` was called. The message is
+the one passed to them, or the condition's source.
+
+    assert(len(items) > 0, "no items");   // fails when items is empty"#,
+};
+
+pub static UNCAUGHT_THROW: Code = Code {
+    id: "E0509",
+    name: "uncaught-throw",
+    explanation: r#"A value was thrown with `throw` and no `try` around it caught it. The
+message is the thrown value.
+
+    throw "bad record";   // fails the program
+
+    try {
+        throw "bad record";
+    } catch (e) {
+        print(e);
+    }                     // fixed: prints `bad record`"#,
+};
+
+pub static BUILTIN_UNAVAILABLE: Code = Code {
+    id: "E0510",
+    name: "builtin-unavailable",
+    explanation: r#"The program called a builtin the program embedding the interpreter has
+turned off, e.g. `input` in a sandbox without a user to ask. The name is
+still defined, so a script can check what it may use by catching the
+error.
+
+    let name = "guest";
+    try {
+        name = input("Name: ");
+    } catch (e) {
+        print(e);   // builtin 'input' is not available in this environment
+    }"#,
+};
+
+pub static ALLOCATION_LIMIT: Code = Code {
+    id: "E0511",
+    name: "allocation-limit",
+    explanation: r#"The program made a string or array longer, or more arrays and maps, than
+the program embedding the interpreter allows, which it does to keep an
+untrusted script from using up all its memory. Build less data, or raise
+the limit with `max_string_len`, `max_array_len`, or `max_total_values`.
+
+    let s = "x";
+    while (true) { s = s + s; }   // fails once `s` is too long"#,
+};
+
+pub static EXIT: Code = Code {
+    id: "E0512",
+    name: "exit",
+    explanation: r#"Not a mistake: the program called `exit()`, which ends it wherever it's
+called with the code it was given. `catch` doesn't stop it. The command line
+uses the code as its exit status; a program embedding the interpreter gets
+it back from `run` as `Completion::Exit`.
+
+    if (len(args()) == 0) { print("usage: tool FILE"); exit(64); }"#,
+};
+
+pub static WRONG_ARGUMENT_COUNT: Code = Code {
+    id: "E0513",
+    name: "wrong-argument-count",
+    explanation: r#"A function was called with more or fewer arguments than it takes. The
+message says how many it expected; `help(f)` shows a builtin's parameters.
+
+    function area(w, h) { return w * h; }
+    print(area(3));      // wrong
+    print(area(3, 4));   // fixed"#,
+};
+
+pub static ASSIGN_TO_CONSTANT: Code = Code {
+    id: "E0514",
+    name: "assign-to-constant",
+    explanation: r#"A variable declared with `const` was assigned a new value. Declare it with
+`let` if it needs to change.
+
+    const limit = 10;
+    limit = 20;         // wrong
+
+    let limit = 10;
+    limit = 20;         // fixed"#,
+};
+
+pub static ALREADY_DECLARED: Code = Code {
+    id: "E0515",
+    name: "already-declared",
+    explanation: r#"A name was declared twice in the same scope. Assign to the existing
+variable instead, or declare the new one in an inner block to shadow it.
+
+    let total = 0;
+    let total = 5;   // wrong
+    total = 5;       // fixed"#,
+};
+
+pub static INVALID_RANGE: Code = Code {
+    id: "E0516",
+    name: "invalid-range",
+    explanation: r#"A range in a `for`-`in` loop had a bound that isn't a whole number.
+
+    let end = 2.5;
+    for i in 0..end { print(i); }          // wrong
+    for i in 0..floor(end) { print(i); }   // fixed"#,
+};
+
+pub static INVALID_ARGUMENT: Code = Code {
+    id: "E0517",
+    name: "invalid-argument",
+    explanation: r#"A builtin was given an argument of the right type but a value it can't
+use, like text `num` can't read as a number, a negative number for `sqrt`,
+an empty separator for `split`, or a `format` template whose placeholders
+don't match its values. Passing the wrong type is a wrong-type error
+instead, and taking from an empty array is an empty-array error.
+
+    let n = num("ten");   // wrong
+    let n = num("10");    // fixed"#,
+};
+
+pub static EMPTY_ARRAY: Code = Code {
+    id: "E0518",
+    name: "empty-array",
+    explanation: r#"`pop` was called on an empty array, or `reduce` on an empty array without
+an initial value. Check the length first, or give `reduce` a starting
+value.
+
+    let total = reduce([], function(a, b) { return a + b; });      // wrong
+    let total = reduce([], function(a, b) { return a + b; }, 0);   // fixed"#,
+};
+
+pub static IO_FAILED: Code = Code {
+    id: "E0519",
+    name: "io-failed",
+    explanation: r#"Reading or writing failed outside the program: a file `read_file` or
+`write_file` was given couldn't be opened, or `print` or `input` couldn't
+use the terminal, e.g. because output was closed. The message includes
+the operating system's reason.
+
+    let text = read_file("missing.txt");   // fails if there's no such file
+
+    if (file_exists("notes.txt")) { print(read_file("notes.txt")); }"#,
+};
+
+pub static INTEGER_OVERFLOW: Code = Code {
+    id: "E0520",
+    name: "integer-overflow",
+    explanation: r#"`+`, `-`, `*`, or negation on integers, numbers written without a fraction
+or exponent, gave a result outside the range of a 64-bit integer, from
+-9223372036854775808 to 9223372036854775807, and the interpreter was set
+to treat integer overflow as an error. The other settings wrap the result
+around, stop it at the nearest end of the range, or, by default, give it
+as a fractional number, which is exact only up to 2^53.
+
+    let next = 9223372036854775807 + 1;     // fails
+    let next = 9223372036854775807.0 + 1;   // fractional: 9223372036854775808"#,
+};
+
+pub static UNUSED_VARIABLE: Code = Code {
+    id: "W0001",
+    name: "unused-variable",
+    explanation: r#"A variable is declared but never read, which is often a typo or leftover.
+Remove it, or start its name with `_` to keep it on purpose.
+
+    let result = compute();    // warned
+    let _result = compute();   // fixed"#,
+};
+
+pub static UNUSED_FUNCTION: Code = Code {
+    id: "W0002",
+    name: "unused-function",
+    explanation: r#"A function is declared but never called or read. Remove it, start its name
+with `_`, or `export` it if other files use it.
+
+    function helper() { return 1; }          // warned
+    export function helper() { return 1; }   // fixed, when imported as a module"#,
+};
+
+pub static UNREACHABLE_CODE: Code = Code {
+    id: "W0003",
+    name: "unreachable-code",
+    explanation: r#"A statement can never run, because the one before it always returns,
+breaks, continues, or throws, or because it's in a branch whose condition
+is always false. Only the first such statement of a block is warned about.
+Remove the code, or fix the condition or the early exit.
+
+    return total;
+    print("done");      // warned
+
+    if (1 > 2) {
+        print("never"); // warned
+    }
+
+`--optimize` removes such code before running the program."#,
+};
+
+pub static DISABLED_BUILTIN: Code = Code {
+    id: "W0004",
+    name: "disabled-builtin",
+    explanation: r#"The program uses a builtin that the interpreter it will run in doesn't
+allow, so calling it fails with `builtin-unavailable` (E0510). This is only
+reported when checking against that interpreter's builtins. It's a warning
+rather than an error because a script may catch the failure on purpose.
+
+    let answer = input("Continue? ");   // warned where `input` is denied"#,
+};
+
+pub static ASSIGN_IN_CONDITION: Code = Code {
+    id: "W0005",
+    name: "assign-in-condition",
+    explanation: r#"The condition of an `if`, `while`, `do`-`while`, or `for` is an assignment,
+which is almost always `=` typed where `==` was meant. The assignment
+happens, and the branch is taken whenever the assigned value is truthy.
+
+    if (count = 0) { print("empty"); }    // warned
+    if (count == 0) { print("empty"); }   // fixed
+
+Assign before the condition to keep the assignment on purpose."#,
+};
+
+pub static INTERPOLATION_IN_STRING: Code = Code {
+    id: "W0006",
+    name: "interpolation-in-string",
+    explanation: r#"A string contains `${...}`, which looks like interpolation from another
+language. SlimeScript doesn't interpolate strings, so the text is printed as
+it is. Join the value in with `+`, or use `format()`.
+
+    print("total: ${total}");               // warned
+    print(format("total: {}", total));      // fixed"#,
+};
+
+pub static ADJACENT_STRINGS: Code = Code {
+    id: "W0007",
+    name: "adjacent-strings",
+    explanation: r#"Two string literals follow each other with nothing between them. Unlike in
+C or Python, they aren't joined, and the program doesn't parse. Join them
+with `+`, or make them one string.
+
+    let greeting = "Hello, " "world";     // warned
+    let greeting = "Hello, " + "world";   // fixed"#,
+};
+
+pub static NUMBER_BEFORE_NAME: Code = Code {
+    id: "W0008",
+    name: "number-before-name",
+    explanation: r#"A number is written right up against a name, as in `2x`. That isn't
+multiplication, so the program doesn't parse. Put the operator in, or a
+space if they are meant to be apart.
+
+    let area = 2pi * r;     // warned
+    let area = 2 * pi * r;  // fixed"#,
+};
+
+pub static NO_BREAK_SPACE: Code = Code {
+    id: "W0009",
+    name: "no-break-space",
+    explanation: r#"The program has a no-break space (U+00A0 or U+202F) outside a string or
+comment, usually from text pasted out of a chat app, word processor, or
+PDF. It looks like a space and is read as one, but other tools may not
+treat it that way. Replace it with an ordinary space. An embedder can make
+it an unexpected-character error instead with
+`LexerConfig::reject_no_break_spaces`."#,
+};
+
+static CODES: &[&Code] = &[
+    &UNEXPECTED_CHARACTER,
+    &UNTERMINATED_STRING,
+    &INVALID_ESCAPE,
+    &UNEXPECTED_TOKEN,
+    &MISSING_SEMICOLON,
+    &INVALID_ASSIGNMENT_TARGET,
+    &DUPLICATE_PARAMETER,
+    &MISPLACED_STATEMENT,
+    &INVALID_NUMBER,
+    &TOO_DEEPLY_NESTED,
+    &RESERVED_KEYWORD,
+    &NOT_READ_ONLY,
+    &CHAINED_COMPARISON,
+    &PIPE_WITHOUT_CALL,
+    &UNDEFINED_LABEL,
+    &UNDEFINED_VARIABLE,
+    &UNDECLARED_ASSIGNMENT,
+    &USED_BEFORE_DECLARATION,
+    &TYPE_MISMATCH,
+    &INVALID_OPERAND,
+    &IMPORT_FAILED,
+    &CIRCULAR_IMPORT,
+    &RUNTIME_ERROR,
+    &UNDEFINED_NAME,
+    &DIVISION_BY_ZERO,
+    &INDEX_OUT_OF_BOUNDS,
+    &MISSING_KEY,
+    &WRONG_TYPE,
+    &RECURSION_LIMIT,
+    &BUDGET_EXCEEDED,
+    &ASSERTION_FAILED,
+    &UNCAUGHT_THROW,
+    &BUILTIN_UNAVAILABLE,
+    &ALLOCATION_LIMIT,
+    &EXIT,
+    &WRONG_ARGUMENT_COUNT,
+    &ASSIGN_TO_CONSTANT,
+    &ALREADY_DECLARED,
+    &INVALID_RANGE,
+    &INVALID_ARGUMENT,
+    &EMPTY_ARRAY,
+    &IO_FAILED,
+    &INTEGER_OVERFLOW,
+    &UNUSED_VARIABLE,
+    &UNUSED_FUNCTION,
+    &UNREACHABLE_CODE,
+    &DISABLED_BUILTIN,
+    &ASSIGN_IN_CONDITION,
+    &INTERPOLATION_IN_STRING,
+    &ADJACENT_STRINGS,
+    &NUMBER_BEFORE_NAME,
+    &NO_BREAK_SPACE,
+];
+
+/// The code with the id `code`, like "E0200", or the name, like
+/// "undefined-variable". Ids are matched ignoring case.
+pub fn lookup(code: &str) -> Option<&'static Code> {
+    CODES
+        .iter()
+        .copied()
+        .find(|entry| entry.id.eq_ignore_ascii_case(code) || entry.name == code)
+}
+
+/// Every code, in order.
+pub fn all() -> impl Iterator<Item = &'static Code> {
+    CODES.iter().copied()
+}
@@ -0,0 +1,797 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::ast::{
+    BinaryOp, ClassDecl, Expr, ExprId, ExprKind, Exprs, FunctionDecl, LogicalOp, Pattern, Program, Stmt, StmtKind, UnaryOp,
+};
+use crate::codes::{self, Code};
+use crate::lexer::Span;
+use crate::symbol::Symbol;
+use crate::value::Value;
+
+/// One bytecode instruction. Operands index into the tables of the `Chunk`
+/// the instruction belongs to, or are instruction offsets for jumps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    // push `constants[i]`
+    Constant(usize),
+    Null,
+    Pop,
+    // push a copy of the value on top of the stack
+    Duplicate,
+    // push a copy of the top two values, keeping their order
+    DuplicateTwo,
+
+    // variables, named by `names[i]`
+    GetVariable(usize),
+    // assigns the value on top of the stack, leaving it there
+    SetVariable(usize),
+    // pops the value and declares it in the current scope
+    Declare(usize),
+    DeclareConst(usize),
+    // pops the value and declares the names in `patterns[i]` bound to its
+    // parts
+    Destructure(usize),
+    DestructureConst(usize),
+    // pops the value and binds it without the redeclaration check, for the
+    // variable of a for-in loop
+    Define(usize),
+    // push a function value for `functions[i]` closing over the current scope
+    Closure(usize),
+    // push a class value for `classes[i]` closing over the current scope
+    Class(usize),
+
+    Unary(UnaryOp),
+    Binary(BinaryOp),
+    // Binary for a compound assignment, whose errors name the assignment
+    // operator
+    Compound(BinaryOp),
+
+    Jump(usize),
+    // jump if the current scope binds `names[i]`, for a parameter the call
+    // passed an argument for instead of leaving it to its default
+    JumpIfBound(usize, usize),
+    // pops the condition
+    JumpIfFalse(usize),
+    // for `&&` and `||`: jump keeping the operand when it decides the
+    // result, otherwise pop it
+    JumpIfFalseOrPop(usize),
+    JumpIfTrueOrPop(usize),
+
+    // pops the arguments and then the callee
+    Call(usize),
+    // like Call then Return, but a script function is returned to the
+    // caller to call in place of this one
+    TailCall(usize),
+    // pops the elements
+    Array(usize),
+    // pops one value per key in `map_keys[i]`
+    Map(usize),
+    // pops the index, then the object
+    GetIndex,
+    // pops the value, index, and object, then pushes the value back
+    SetIndex,
+    // pops the end, start, and object, where a null bound was left out;
+    // true for a slice that includes its end
+    Slice(bool),
+    GetMember(usize),
+    // pops the value and object, then pushes the value back
+    SetMember(usize),
+
+    // enter a new scope nested in the current one, or leave it
+    PushScope,
+    PopScope,
+
+    // for-in loops: pop the iterable (or checked range bounds) and start
+    // iterating over it; push the next item or, once there are none left,
+    // finish the iteration and jump; finish the iteration early on `break`
+    Iterate,
+    CheckRangeBound,
+    IterateRange,
+    Next(usize),
+    EndIteration,
+
+    // `try`: until the matching EndTry, an error unwinds to the target with
+    // what `catch` binds pushed; EndTry also drops the handler on `break`
+    // and `continue`
+    Try(usize),
+    EndTry,
+
+    // run the file imported as `names[i]`, unless it has run already
+    Import(usize),
+    // push the module for the file imported as `names[i]`, running the
+    // file in a scope of its own the first time
+    ImportModule(usize),
+
+    // count a step against the execution budget
+    Tick,
+    // pop the value of a top-level expression statement as the program's
+    // result so far
+    SetResult,
+    // return the popped value from a function
+    Return,
+    // end the program with its result
+    Halt,
+    // fail with the code and `constants[i]` as the message
+    Error(&'static Code, usize),
+    // fail with the popped value as what `catch` binds
+    Throw,
+}
+
+/// Compiled bytecode for a program or one function body.
+#[derive(Debug, Default)]
+pub struct Chunk {
+    pub name: String,
+    pub code: Vec<Op>,
+    // the source span of each instruction, for runtime errors
+    pub spans: Vec<Span>,
+    pub constants: Vec<Value>,
+    pub names: Vec<Symbol>,
+    pub functions: Vec<Rc<FunctionDecl>>,
+    pub classes: Vec<Rc<ClassDecl>>,
+    pub map_keys: Vec<Vec<String>>,
+    pub patterns: Vec<Pattern>,
+}
+
+/// Compiles a whole program, which runs in the global scope and produces
+/// the value of its last statement if that is an expression statement.
+pub fn compile(program: &Program) -> Chunk {
+    compile_statements(&program.statements, &program.exprs)
+}
+
+/// Compiles top-level `statements`, with their expressions in `exprs`, as
+/// `compile` compiles a program.
+pub(crate) fn compile_statements(statements: &[Stmt], exprs: &Exprs) -> Chunk {
+    let mut compiler = Compiler::new("<script>", false, exprs);
+    for statement in statements {
+        compiler.statement(statement);
+    }
+    compiler.emit(Op::Halt, Span::default());
+    compiler.chunk
+}
+
+/// Compiles the body of a function. The caller binds its parameters in a
+/// fresh scope before running it, except those it has no argument for,
+/// which the function binds to their defaults itself.
+pub fn compile_function(declaration: &FunctionDecl) -> Chunk {
+    let mut compiler = Compiler::new(&declaration.name, true, &declaration.exprs);
+    for param in &declaration.params {
+        if let Some(default) = &param.default {
+            let name = compiler.name(&param.name);
+            let skip = compiler.emit(Op::JumpIfBound(name, 0), param.span);
+            compiler.expression(default);
+            compiler.emit(Op::Declare(name), param.span);
+            compiler.patch(skip);
+        }
+    }
+    for statement in &declaration.body {
+        compiler.statement(statement);
+    }
+    compiler.emit(Op::Null, declaration.span);
+    compiler.emit(Op::Return, declaration.span);
+    compiler.chunk
+}
+
+/// A loop being compiled, so `break` and `continue` know which scopes to
+/// leave and where to jump.
+struct Loop {
+    // the label written before the loop, if any
+    label: Option<Symbol>,
+    // scopes open outside the loop body
+    scope_depth: usize,
+    // `try` blocks open outside the loop body
+    handler_depth: usize,
+    // whether `break` has to finish a for-in iteration
+    iterates: bool,
+    breaks: Vec<usize>,
+    continues: Vec<usize>,
+}
+
+struct Compiler<'a> {
+    chunk: Chunk,
+    // the arena of the expressions in the function or program compiled
+    exprs: &'a Exprs,
+    in_function: bool,
+    // blocks entered, whether or not they needed a scope at runtime
+    block_depth: usize,
+    // scopes pushed at runtime
+    scope_depth: usize,
+    // `try` blocks the code being compiled is in
+    handler_depth: usize,
+    loops: Vec<Loop>,
+    name_indices: HashMap<Symbol, usize>,
+}
+
+impl<'a> Compiler<'a> {
+    fn new(name: &str, in_function: bool, exprs: &'a Exprs) -> Self {
+        Compiler {
+            chunk: Chunk {
+                name: name.to_string(),
+                ..Chunk::default()
+            },
+            exprs,
+            in_function,
+            block_depth: 0,
+            scope_depth: 0,
+            handler_depth: 0,
+            loops: Vec::new(),
+            name_indices: HashMap::new(),
+        }
+    }
+
+    fn expr(&self, id: ExprId) -> &'a Expr {
+        &self.exprs[id]
+    }
+
+    fn emit(&mut self, op: Op, span: Span) -> usize {
+        self.chunk.code.push(op);
+        self.chunk.spans.push(span);
+        self.chunk.code.len() - 1
+    }
+
+    /// Points the jump at `at` to the next instruction to be emitted.
+    fn patch(&mut self, at: usize) {
+        let target = self.chunk.code.len();
+        self.chunk.code[at] = match self.chunk.code[at] {
+            Op::Jump(_) => Op::Jump(target),
+            Op::JumpIfBound(name, _) => Op::JumpIfBound(name, target),
+            Op::JumpIfFalse(_) => Op::JumpIfFalse(target),
+            Op::JumpIfFalseOrPop(_) => Op::JumpIfFalseOrPop(target),
+            Op::JumpIfTrueOrPop(_) => Op::JumpIfTrueOrPop(target),
+            Op::Next(_) => Op::Next(target),
+            Op::Try(_) => Op::Try(target),
+            other => unreachable!("{:?} is not a jump", other),
+        };
+    }
+
+    fn constant(&mut self, value: Value) -> usize {
+        self.chunk.constants.push(value);
+        self.chunk.constants.len() - 1
+    }
+
+    fn name(&mut self, name: impl Into<Symbol>) -> usize {
+        let name = name.into();
+        if let Some(&index) = self.name_indices.get(&name) {
+            return index;
+        }
+        self.chunk.names.push(name.clone());
+        let index = self.chunk.names.len() - 1;
+        self.name_indices.insert(name, index);
+        index
+    }
+
+    fn function(&mut self, declaration: &Rc<FunctionDecl>) -> usize {
+        self.chunk.functions.push(Rc::clone(declaration));
+        self.chunk.functions.len() - 1
+    }
+
+    fn push_scope(&mut self, span: Span) {
+        self.block_depth += 1;
+        self.scope_depth += 1;
+        self.emit(Op::PushScope, span);
+    }
+
+    fn pop_scope(&mut self, span: Span) {
+        self.block_depth -= 1;
+        self.scope_depth -= 1;
+        self.emit(Op::PopScope, span);
+    }
+
+    /// Compiles `statements` in a scope of their own. A block that declares
+    /// nothing directly inside it gets no scope, since an empty scope can't
+    /// be told apart from the enclosing one.
+    fn scoped(&mut self, statements: &[Stmt], span: Span) {
+        let declares = statements
+            .iter()
+            .any(|statement| {
+                matches!(
+                    statement.kind,
+                    StmtKind::Let { .. } | StmtKind::Destructure { .. } | StmtKind::Function(_)
+                )
+            });
+        self.block_depth += 1;
+        if declares {
+            self.push_scope(span);
+        }
+        for statement in statements {
+            self.statement(statement);
+        }
+        if declares {
+            self.pop_scope(span);
+        }
+        self.block_depth -= 1;
+    }
+
+    /// Compiles the body of a loop with `label` in a fresh scope, with
+    /// `break` and `continue` jumps collected for the caller to patch.
+    fn loop_body(&mut self, body: &[Stmt], label: Option<Symbol>, span: Span) -> Loop {
+        self.loops.push(Loop {
+            label,
+            scope_depth: self.scope_depth,
+            handler_depth: self.handler_depth,
+            iterates: false,
+            breaks: Vec::new(),
+            continues: Vec::new(),
+        });
+        self.scoped(body, span);
+        self.loops.pop().expect("loop was pushed above")
+    }
+
+    /// The index in `loops` of the loop a `break` or `continue` naming
+    /// `label`, if anything, is for: the innermost loop, or the innermost
+    /// one with that label.
+    fn target_loop(&self, label: Option<Symbol>) -> usize {
+        let target = match label {
+            Some(_) => self.loops.iter().rposition(|enclosing| enclosing.label == label),
+            None => self.loops.len().checked_sub(1),
+        };
+        target.expect("the parser rejects break outside loops and unknown labels")
+    }
+
+    /// Leaves the scopes and `try` blocks opened inside the body of the loop
+    /// at `target` without changing the compiler's own depths, since the
+    /// code after the jump still runs inside them. Also finishes the
+    /// iterations of the for-in loops nested inside it, and its own too if
+    /// `leaving` it.
+    fn leave_loop_scopes(&mut self, target: usize, leaving: bool, span: Span) {
+        let (scope_depth, handler_depth) = (self.loops[target].scope_depth, self.loops[target].handler_depth);
+        for _ in scope_depth..self.scope_depth {
+            self.emit(Op::PopScope, span);
+        }
+        for _ in handler_depth..self.handler_depth {
+            self.emit(Op::EndTry, span);
+        }
+        let skipped = if leaving { target } else { target + 1 };
+        let iterations = self.loops[skipped..].iter().filter(|enclosing| enclosing.iterates).count();
+        for _ in 0..iterations {
+            self.emit(Op::EndIteration, span);
+        }
+    }
+
+    fn statement(&mut self, stmt: &Stmt) {
+        let span = stmt.span;
+        self.emit(Op::Tick, span);
+        let top_level = !self.in_function && self.block_depth == 0;
+        if top_level && !matches!(stmt.kind, StmtKind::Expression(_)) {
+            // any other top-level statement leaves the program without a
+            // result, even if it ends the program with `return`
+            self.emit(Op::Null, span);
+            self.emit(Op::SetResult, span);
+        }
+        match &stmt.kind {
+            StmtKind::Expression(expr) => {
+                self.expression(expr);
+                let op = if top_level { Op::SetResult } else { Op::Pop };
+                self.emit(op, span);
+            }
+            StmtKind::Let { name, initializer, constant, .. } => {
+                match initializer {
+                    Some(expr) => self.expression(expr),
+                    None => {
+                        self.emit(Op::Null, span);
+                    }
+                }
+                let name = self.name(name);
+                let op = if *constant { Op::DeclareConst(name) } else { Op::Declare(name) };
+                self.emit(op, span);
+            }
+            StmtKind::Destructure { pattern, initializer, constant } => {
+                self.expression(initializer);
+                self.chunk.patterns.push(pattern.clone());
+                let pattern = self.chunk.patterns.len() - 1;
+                let op = if *constant { Op::DestructureConst(pattern) } else { Op::Destructure(pattern) };
+                self.emit(op, span);
+            }
+            StmtKind::Block(statements) => self.scoped(statements, span),
+            StmtKind::If { .. } => {
+                // an `else if` chain is compiled arm by arm in a loop, as if
+                // each following if were a statement in its own block
+                let mut to_end = Vec::new();
+                let mut arm = stmt;
+                let block_depth = self.block_depth;
+                while let StmtKind::If { condition, then_branch, else_branch } = &arm.kind {
+                    let span = arm.span;
+                    self.expression(condition);
+                    let to_else = self.emit(Op::JumpIfFalse(0), span);
+                    self.scoped(then_branch, span);
+                    let Some(else_branch) = else_branch else {
+                        self.patch(to_else);
+                        break;
+                    };
+                    to_end.push(self.emit(Op::Jump(0), span));
+                    self.patch(to_else);
+                    match else_branch.as_slice() {
+                        [next @ Stmt { kind: StmtKind::If { .. }, .. }] => {
+                            self.block_depth += 1;
+                            self.emit(Op::Tick, next.span);
+                            arm = next;
+                        }
+                        else_branch => {
+                            self.scoped(else_branch, span);
+                            break;
+                        }
+                    }
+                }
+                self.block_depth = block_depth;
+                for jump in to_end {
+                    self.patch(jump);
+                }
+            }
+            StmtKind::DoWhile { body, condition, label } => {
+                let start = self.emit(Op::Tick, span);
+                let body = self.loop_body(body, label.clone(), span);
+                // `continue` still checks the condition
+                for continue_jump in body.continues {
+                    self.patch(continue_jump);
+                }
+                self.expression(condition);
+                let exit = self.emit(Op::JumpIfFalse(0), span);
+                self.emit(Op::Jump(start), span);
+                self.patch(exit);
+                for break_jump in body.breaks {
+                    self.patch(break_jump);
+                }
+            }
+            StmtKind::Match { subject, arms, default } => {
+                // the subject stays on the stack while the arms' values are
+                // compared with it, and is popped before any block runs
+                self.expression(subject);
+                let mut to_end = Vec::new();
+                for arm in arms {
+                    let mut to_body = Vec::new();
+                    for value in &arm.values {
+                        self.emit(Op::Duplicate, value.span);
+                        self.expression(value);
+                        self.emit(Op::Binary(BinaryOp::Equal), value.span);
+                        to_body.push(self.emit(Op::JumpIfTrueOrPop(0), value.span));
+                    }
+                    let to_next = self.emit(Op::Jump(0), arm.span);
+                    for jump in to_body {
+                        self.patch(jump);
+                    }
+                    self.emit(Op::Pop, arm.span);
+                    self.emit(Op::Pop, arm.span);
+                    self.scoped(&arm.body, arm.span);
+                    to_end.push(self.emit(Op::Jump(0), arm.span));
+                    self.patch(to_next);
+                }
+                self.emit(Op::Pop, span);
+                if let Some(default) = default {
+                    self.scoped(default, span);
+                }
+                for jump in to_end {
+                    self.patch(jump);
+                }
+            }
+            StmtKind::While { condition, body, label } => {
+                let start = self.chunk.code.len();
+                self.expression(condition);
+                let exit = self.emit(Op::JumpIfFalse(0), span);
+                self.emit(Op::Tick, span);
+                let body = self.loop_body(body, label.clone(), span);
+                for continue_jump in body.continues {
+                    self.chunk.code[continue_jump] = Op::Jump(start);
+                }
+                self.emit(Op::Jump(start), span);
+                self.patch(exit);
+                for break_jump in body.breaks {
+                    self.patch(break_jump);
+                }
+            }
+            StmtKind::For { initializer, condition, increment, body, label } => {
+                // the initializer's variable lives in a scope of its own
+                self.push_scope(span);
+                if let Some(initializer) = initializer {
+                    self.statement(initializer);
+                }
+                let start = self.chunk.code.len();
+                let exit = condition.as_ref().map(|condition| {
+                    self.expression(condition);
+                    self.emit(Op::JumpIfFalse(0), span)
+                });
+                self.emit(Op::Tick, span);
+                let body = self.loop_body(body, label.clone(), span);
+                // `continue` still runs the increment clause
+                for continue_jump in body.continues {
+                    self.patch(continue_jump);
+                }
+                if let Some(increment) = increment {
+                    self.expression(increment);
+                    self.emit(Op::Pop, span);
+                }
+                self.emit(Op::Jump(start), span);
+                if let Some(exit) = exit {
+                    self.patch(exit);
+                }
+                for break_jump in body.breaks {
+                    self.patch(break_jump);
+                }
+                self.pop_scope(span);
+            }
+            StmtKind::ForIn { variable, iterable, body, label } => {
+                match &iterable.kind {
+                    ExprKind::Range { start, end, inclusive } => {
+                        let (start, end) = (self.expr(*start), self.expr(*end));
+                        self.expression(start);
+                        self.emit(Op::CheckRangeBound, start.span);
+                        self.expression(end);
+                        self.emit(Op::CheckRangeBound, end.span);
+                        if *inclusive {
+                            let one = self.constant(Value::Number(1.0));
+                            self.emit(Op::Constant(one), end.span);
+                            self.emit(Op::Binary(BinaryOp::Add), end.span);
+                        }
+                        self.emit(Op::IterateRange, iterable.span);
+                    }
+                    _ => {
+                        self.expression(iterable);
+                        self.emit(Op::Iterate, iterable.span);
+                    }
+                }
+                let start = self.emit(Op::Next(0), iterable.span);
+                self.emit(Op::Tick, iterable.span);
+
+                // a fresh binding of the loop variable each iteration, so
+                // closures made in different iterations don't share it
+                self.loops.push(Loop {
+                    label: label.clone(),
+                    scope_depth: self.scope_depth,
+                    handler_depth: self.handler_depth,
+                    iterates: true,
+                    breaks: Vec::new(),
+                    continues: Vec::new(),
+                });
+                self.push_scope(span);
+                let variable = self.name(variable);
+                self.emit(Op::Define(variable), iterable.span);
+                for statement in body {
+                    self.statement(statement);
+                }
+                self.pop_scope(span);
+                let body = self.loops.pop().expect("loop was pushed above");
+
+                for continue_jump in body.continues {
+                    self.chunk.code[continue_jump] = Op::Jump(start);
+                }
+                self.emit(Op::Jump(start), span);
+                self.patch(start);
+                for break_jump in body.breaks {
+                    self.patch(break_jump);
+                }
+            }
+            StmtKind::Function(declaration) => {
+                let function = self.function(declaration);
+                self.emit(Op::Closure(function), span);
+                let name = self.name(&declaration.name);
+                self.emit(Op::Declare(name), span);
+            }
+            StmtKind::Class(declaration) => {
+                self.chunk.classes.push(Rc::clone(declaration));
+                self.emit(Op::Class(self.chunk.classes.len() - 1), span);
+                let name = self.name(&declaration.name);
+                self.emit(Op::Declare(name), span);
+            }
+            StmtKind::Return(value) => {
+                match value {
+                    // a try block's handler has to stay in place for the
+                    // call, so a call inside one isn't a tail call
+                    Some(Expr { kind: ExprKind::Call { callee, arguments }, span })
+                        if self.in_function && self.handler_depth == 0 =>
+                    {
+                        self.expression(self.expr(*callee));
+                        for argument in arguments {
+                            self.expression(argument);
+                        }
+                        self.emit(Op::TailCall(arguments.len()), *span);
+                        return;
+                    }
+                    Some(expr) => self.expression(expr),
+                    None => {
+                        self.emit(Op::Null, span);
+                    }
+                }
+                if self.in_function {
+                    self.emit(Op::Return, span);
+                } else {
+                    // a top-level return just ends the program
+                    self.emit(Op::Pop, span);
+                    self.emit(Op::Halt, span);
+                }
+            }
+            StmtKind::Try { body, name, handler } => {
+                let to_handler = self.emit(Op::Try(0), span);
+                self.handler_depth += 1;
+                self.scoped(body, span);
+                self.handler_depth -= 1;
+                self.emit(Op::EndTry, span);
+                let to_end = self.emit(Op::Jump(0), span);
+                // the caught error is on the stack, bound in a scope shared
+                // with the catch block
+                self.patch(to_handler);
+                self.push_scope(span);
+                let name = self.name(name);
+                self.emit(Op::Define(name), span);
+                for statement in handler {
+                    self.statement(statement);
+                }
+                self.pop_scope(span);
+                self.patch(to_end);
+            }
+            StmtKind::Throw(value) => {
+                self.expression(value);
+                self.emit(Op::Throw, span);
+            }
+            StmtKind::Break(label) => {
+                let target = self.target_loop(label.clone());
+                self.leave_loop_scopes(target, true, span);
+                let jump = self.emit(Op::Jump(0), span);
+                self.loops[target].breaks.push(jump);
+            }
+            StmtKind::Continue(label) => {
+                let target = self.target_loop(label.clone());
+                self.leave_loop_scopes(target, false, span);
+                let jump = self.emit(Op::Jump(0), span);
+                self.loops[target].continues.push(jump);
+            }
+            StmtKind::Import { path, alias: None } => {
+                let path = self.name(path);
+                self.emit(Op::Import(path), span);
+            }
+            StmtKind::Import { path, alias: Some(alias) } => {
+                let path = self.name(path);
+                self.emit(Op::ImportModule(path), span);
+                let alias = self.name(alias);
+                self.emit(Op::DeclareConst(alias), span);
+            }
+            StmtKind::Export(declaration) => self.statement(declaration),
+            // only `Interpreter::run_tests` runs tests, compiling each body
+            // on its own
+            StmtKind::Test { .. } => {}
+        }
+    }
+
+    fn expression(&mut self, expr: &Expr) {
+        let span = expr.span;
+        match &expr.kind {
+            ExprKind::Number(n) => {
+                let constant = self.constant(Value::Number(*n));
+                self.emit(Op::Constant(constant), span);
+            }
+            ExprKind::Int(n) => {
+                let constant = self.constant(Value::Int(*n));
+                self.emit(Op::Constant(constant), span);
+            }
+            ExprKind::String(s) => {
+                let constant = self.constant(Value::String(s.as_str().into()));
+                self.emit(Op::Constant(constant), span);
+            }
+            ExprKind::Bool(b) => {
+                let constant = self.constant(Value::Bool(*b));
+                self.emit(Op::Constant(constant), span);
+            }
+            ExprKind::Null => {
+                self.emit(Op::Null, span);
+            }
+            ExprKind::Variable(name) => {
+                let name = self.name(name);
+                self.emit(Op::GetVariable(name), span);
+            }
+            // a method's `this` is bound in the scope its closure adds
+            ExprKind::This => {
+                let name = self.name("this");
+                self.emit(Op::GetVariable(name), span);
+            }
+            ExprKind::Assign { name, operator, value } => {
+                let name = self.name(name);
+                if operator.is_some() {
+                    self.emit(Op::GetVariable(name), span);
+                }
+                self.expression(self.expr(*value));
+                if let Some(operator) = operator {
+                    self.emit(Op::Compound(*operator), span);
+                }
+                self.emit(Op::SetVariable(name), span);
+            }
+            ExprKind::Unary { operator, operand } => {
+                self.expression(self.expr(*operand));
+                self.emit(Op::Unary(*operator), span);
+            }
+            ExprKind::Binary { left, operator, right } => {
+                self.expression(self.expr(*left));
+                self.expression(self.expr(*right));
+                self.emit(Op::Binary(*operator), span);
+            }
+            ExprKind::Logical { left, operator, right } => {
+                self.expression(self.expr(*left));
+                let jump = match operator {
+                    LogicalOp::And => Op::JumpIfFalseOrPop(0),
+                    LogicalOp::Or => Op::JumpIfTrueOrPop(0),
+                };
+                let end = self.emit(jump, span);
+                self.expression(self.expr(*right));
+                self.patch(end);
+            }
+            ExprKind::Call { callee, arguments } => {
+                self.expression(self.expr(*callee));
+                for argument in arguments {
+                    self.expression(argument);
+                }
+                self.emit(Op::Call(arguments.len()), span);
+            }
+            ExprKind::Array(elements) => {
+                for element in elements {
+                    self.expression(element);
+                }
+                self.emit(Op::Array(elements.len()), span);
+            }
+            ExprKind::Index { object, index } => {
+                self.expression(self.expr(*object));
+                self.expression(self.expr(*index));
+                self.emit(Op::GetIndex, span);
+            }
+            ExprKind::Slice { object, start, end, inclusive } => {
+                self.expression(self.expr(*object));
+                for bound in [start, end] {
+                    match bound {
+                        Some(bound) => self.expression(self.expr(*bound)),
+                        None => {
+                            self.emit(Op::Null, span);
+                        }
+                    }
+                }
+                self.emit(Op::Slice(*inclusive), span);
+            }
+            ExprKind::SetIndex { object, index, operator, value } => {
+                self.expression(self.expr(*object));
+                self.expression(self.expr(*index));
+                if operator.is_some() {
+                    self.emit(Op::DuplicateTwo, span);
+                    self.emit(Op::GetIndex, span);
+                }
+                self.expression(self.expr(*value));
+                if let Some(operator) = operator {
+                    self.emit(Op::Compound(*operator), span);
+                }
+                self.emit(Op::SetIndex, span);
+            }
+            ExprKind::Map(entries) => {
+                let mut keys = Vec::with_capacity(entries.len());
+                for (key, value) in entries {
+                    self.expression(value);
+                    keys.push(key.clone());
+                }
+                self.chunk.map_keys.push(keys);
+                let keys = self.chunk.map_keys.len() - 1;
+                self.emit(Op::Map(keys), span);
+            }
+            ExprKind::Member { object, name } => {
+                self.expression(self.expr(*object));
+                let name = self.name(name);
+                self.emit(Op::GetMember(name), span);
+            }
+            ExprKind::SetMember { object, name, operator, value } => {
+                self.expression(self.expr(*object));
+                let name = self.name(name);
+                if operator.is_some() {
+                    self.emit(Op::Duplicate, span);
+                    self.emit(Op::GetMember(name), span);
+                }
+                self.expression(self.expr(*value));
+                if let Some(operator) = operator {
+                    self.emit(Op::Compound(*operator), span);
+                }
+                self.emit(Op::SetMember(name), span);
+            }
+            ExprKind::Function(declaration) => {
+                let function = self.function(declaration);
+                self.emit(Op::Closure(function), span);
+            }
+            ExprKind::Range { .. } => {
+                let message = self.constant(Value::String(
+                    "Ranges can only be used as the iterable of a for-in loop".into(),
+                ));
+                self.emit(Op::Error(&codes::INVALID_RANGE, message), span);
+            }
+        }
+    }
+}
@@ -0,0 +1,326 @@
+use crate::builtins;
+use crate::lexer::{Lexer, Token, TokenType, reserved_words};
+
+/// What a `Completion` names, for an editor to show beside it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionKind {
+    Keyword,
+    Builtin,
+    Variable,
+    Constant,
+    Function,
+    Class,
+    // a key of the map literal a variable was declared with, after `name.`
+    Key,
+}
+
+/// A word that could be written where the cursor is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    pub label: String,
+    pub kind: CompletionKind,
+}
+
+/// The completions for the word being typed at byte `offset` of `source`,
+/// in alphabetical order: the names declared and in scope there, the
+/// builtins, and the keywords, that start with the part of the word before
+/// `offset`. After `name.` they're the keys of the map literal `name` was
+/// declared with instead, or none if it wasn't. Inside a string or a
+/// comment there are none.
+///
+/// `source` needn't lex or parse. The names in scope are found from the
+/// tokens before `offset`, following braces, parameter lists, and loop and
+/// `catch` variables, so the unfinished statement being typed doesn't hide
+/// the declarations around it.
+pub fn complete(source: &str, offset: usize) -> Vec<Completion> {
+    complete_with_globals(source, offset, &[])
+}
+
+/// Like `complete`, with `globals` declared before `source` begins, such
+/// as what a REPL's earlier entries declared.
+pub fn complete_with_globals(source: &str, offset: usize, globals: &[Completion]) -> Vec<Completion> {
+    let offset = offset.min(source.len());
+    if !source.is_char_boundary(offset) {
+        return Vec::new();
+    }
+    let (tokens, _) = Lexer::with_comments(source).tokenize_all();
+    if tokens.iter().any(|token| contains(source, token, offset)) {
+        return Vec::new();
+    }
+    let start = source[..offset].trim_end_matches(|c: char| c.is_alphanumeric() || c == '_').len();
+    let prefix = &source[start..offset];
+    // a number being typed
+    if prefix.starts_with(|c: char| c.is_ascii_digit()) {
+        return Vec::new();
+    }
+    let before: Vec<&Token> = tokens
+        .iter()
+        .filter(|token| token.span.end <= start && !matches!(token.token_type, TokenType::Comment | TokenType::EOF))
+        .collect();
+    let scopes = resolve(&before);
+
+    let mut completions: Vec<Completion> = match before.split_last() {
+        Some((dot, rest)) if dot.token_type == TokenType::Dot => {
+            let keys = rest
+                .last()
+                .filter(|object| object.token_type == TokenType::Identifier)
+                .and_then(|object| lookup(&scopes, &object.value))
+                .map_or(&[][..], |declared| &declared.keys[..]);
+            keys.iter().map(|key| completion(key, CompletionKind::Key)).collect()
+        }
+        _ => {
+            let declared = scopes.iter().rev().flat_map(|scope| scope.names.iter().rev());
+            declared
+                .map(|declared| completion(&declared.name, declared.kind))
+                .chain(globals.iter().cloned())
+                .chain(builtins::names().map(|name| completion(name, CompletionKind::Builtin)))
+                .chain(reserved_words().iter().map(|word| completion(word, CompletionKind::Keyword)))
+                .collect()
+        }
+    };
+    completions.retain(|completion| completion.label.starts_with(prefix));
+    // the sort is stable, so of two with the same label, the one in the
+    // innermost scope is kept
+    completions.sort_by(|a, b| a.label.cmp(&b.label));
+    completions.dedup_by(|later, earlier| later.label == earlier.label);
+    completions
+}
+
+fn completion(label: &str, kind: CompletionKind) -> Completion {
+    Completion { label: label.to_string(), kind }
+}
+
+// whether `offset` is inside the string or comment `token`, where the end
+// of an unterminated string or of a comment still counts as inside
+fn contains(source: &str, token: &Token, offset: usize) -> bool {
+    let span = token.span;
+    match token.token_type {
+        TokenType::String => {
+            let text = &source[span.start..span.end];
+            let closed = text.len() >= 2 && text.ends_with('"');
+            span.start < offset && (offset < span.end || !closed)
+        }
+        TokenType::Comment => span.start < offset && offset <= span.end,
+        _ => false,
+    }
+}
+
+struct Declared {
+    name: String,
+    kind: CompletionKind,
+    // for a variable declared as a map literal, its keys
+    keys: Vec<String>,
+}
+
+#[derive(Default)]
+struct Scope {
+    names: Vec<Declared>,
+    // how many parentheses and brackets were open where the scope began
+    parens: usize,
+    // whether this is a class body, where `name(` starts a method
+    class: bool,
+}
+
+fn lookup<'a>(scopes: &'a [Scope], name: &str) -> Option<&'a Declared> {
+    scopes.iter().rev().flat_map(|scope| scope.names.iter().rev()).find(|declared| declared.name == name)
+}
+
+// the scopes open after `tokens`, outermost first, with what each declares
+fn resolve(tokens: &[&Token]) -> Vec<Scope> {
+    let mut scopes = vec![Scope::default()];
+    // names bound in the next block, like parameters and loop variables,
+    // which opens with the parentheses as deep as `pending_parens`
+    let mut pending: Vec<Declared> = Vec::new();
+    let mut pending_parens = 0;
+    let mut parens: usize = 0;
+    let mut class_body = false;
+    let token_type = |index: usize| tokens.get(index).map(|token| &token.token_type);
+    let mut index = 0;
+    while let Some(token) = tokens.get(index) {
+        let scope_parens = scopes.last().map_or(0, |scope| scope.parens);
+        match token.token_type {
+            TokenType::LeftParen | TokenType::LeftBracket => parens += 1,
+            TokenType::RightParen | TokenType::RightBracket => parens = parens.saturating_sub(1),
+            TokenType::LeftBrace => {
+                let names = if pending_parens == parens { std::mem::take(&mut pending) } else { Vec::new() };
+                scopes.push(Scope { names, parens, class: class_body });
+                class_body = false;
+            }
+            TokenType::RightBrace if scopes.len() > 1 => {
+                // a stray closer leaves the count where the scope began
+                parens = scopes.pop().map_or(0, |scope| scope.parens);
+            }
+            TokenType::Let | TokenType::Const => {
+                let kind = match token.token_type {
+                    TokenType::Let => CompletionKind::Variable,
+                    _ => CompletionKind::Constant,
+                };
+                let (names, end) = pattern_names(tokens, index + 1);
+                let keys = match (&names[..], token_type(end), token_type(end + 1)) {
+                    ([_], Some(TokenType::Assign), Some(TokenType::LeftBrace)) => map_keys(tokens, end + 1),
+                    _ => Vec::new(),
+                };
+                let declared = names.into_iter().map(|name| Declared { name, kind, keys: keys.clone() });
+                // `for (let i = 0; ...)` declares `i` in the loop's body
+                if parens > scope_parens {
+                    pending = declared.collect();
+                    pending_parens = scope_parens;
+                } else if let Some(scope) = scopes.last_mut() {
+                    scope.names.extend(declared);
+                }
+                index = end;
+                continue;
+            }
+            TokenType::Function => {
+                let mut open = index + 1;
+                if token_type(open) == Some(&TokenType::Identifier) {
+                    declare(&mut scopes, &tokens[open].value, CompletionKind::Function);
+                    open += 1;
+                }
+                if token_type(open) == Some(&TokenType::LeftParen) {
+                    pending = parameters(tokens, open);
+                    pending_parens = parens;
+                }
+            }
+            TokenType::Identifier
+                if scopes.last().is_some_and(|scope| scope.class && scope.parens == parens)
+                    && token_type(index + 1) == Some(&TokenType::LeftParen) =>
+            {
+                pending = parameters(tokens, index + 1);
+                pending_parens = parens;
+            }
+            TokenType::Class => {
+                if token_type(index + 1) == Some(&TokenType::Identifier) {
+                    declare(&mut scopes, &tokens[index + 1].value, CompletionKind::Class);
+                }
+                class_body = true;
+            }
+            // `for name in ...`
+            TokenType::For if token_type(index + 1) == Some(&TokenType::Identifier) => {
+                pending = vec![variable(&tokens[index + 1].value)];
+                pending_parens = parens;
+            }
+            // `catch (name)`
+            TokenType::Catch
+                if token_type(index + 1) == Some(&TokenType::LeftParen)
+                    && token_type(index + 2) == Some(&TokenType::Identifier) =>
+            {
+                pending = vec![variable(&tokens[index + 2].value)];
+                pending_parens = parens;
+            }
+            // `import "file" as name`
+            TokenType::As if token_type(index + 1) == Some(&TokenType::Identifier) => {
+                declare(&mut scopes, &tokens[index + 1].value, CompletionKind::Variable);
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+    scopes
+}
+
+fn declare(scopes: &mut [Scope], name: &str, kind: CompletionKind) {
+    if let Some(scope) = scopes.last_mut() {
+        scope.names.push(Declared { name: name.to_string(), kind, keys: Vec::new() });
+    }
+}
+
+fn variable(name: &str) -> Declared {
+    Declared { name: name.to_string(), kind: CompletionKind::Variable, keys: Vec::new() }
+}
+
+// the names a `let` or `const` pattern starting at `tokens[start]`
+// declares, and the index just past the pattern
+fn pattern_names(tokens: &[&Token], start: usize) -> (Vec<String>, usize) {
+    let Some(first) = tokens.get(start) else {
+        return (Vec::new(), start);
+    };
+    match first.token_type {
+        TokenType::Identifier => (vec![first.value.clone()], start + 1),
+        TokenType::LeftBracket | TokenType::LeftBrace => {
+            let mut names = Vec::new();
+            let mut depth = 0;
+            for (index, token) in tokens.iter().enumerate().skip(start) {
+                match token.token_type {
+                    TokenType::LeftBracket | TokenType::LeftBrace => depth += 1,
+                    TokenType::RightBracket | TokenType::RightBrace => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return (names, index + 1);
+                        }
+                    }
+                    // in `{key: pattern}` the key isn't declared
+                    TokenType::Identifier
+                        if tokens.get(index + 1).map(|next| &next.token_type) != Some(&TokenType::Colon) =>
+                    {
+                        names.push(token.value.clone());
+                    }
+                    _ => {}
+                }
+            }
+            (names, tokens.len())
+        }
+        _ => (Vec::new(), start),
+    }
+}
+
+// the parameters in the list opening at `tokens[open]`: the names that
+// start a parameter, leaving out annotations and default values
+fn parameters(tokens: &[&Token], open: usize) -> Vec<Declared> {
+    let mut names = Vec::new();
+    let mut depth = 0;
+    for (index, token) in tokens.iter().enumerate().skip(open) {
+        match token.token_type {
+            TokenType::LeftParen | TokenType::LeftBracket | TokenType::LeftBrace => depth += 1,
+            TokenType::RightParen | TokenType::RightBracket | TokenType::RightBrace => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            TokenType::Identifier
+                if depth == 1
+                    && matches!(
+                        tokens[index - 1].token_type,
+                        TokenType::LeftParen | TokenType::Comma | TokenType::Ellipsis
+                    ) =>
+            {
+                names.push(variable(&token.value));
+            }
+            _ => {}
+        }
+    }
+    names
+}
+
+// the keys written in the map literal opening at `tokens[open]` that can
+// be written after a `.`
+fn map_keys(tokens: &[&Token], open: usize) -> Vec<String> {
+    let mut keys = Vec::new();
+    let mut depth = 0;
+    for (index, token) in tokens.iter().enumerate().skip(open) {
+        match token.token_type {
+            TokenType::LeftParen | TokenType::LeftBracket | TokenType::LeftBrace => depth += 1,
+            TokenType::RightParen | TokenType::RightBracket | TokenType::RightBrace => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            TokenType::Identifier | TokenType::String
+                if depth == 1
+                    && matches!(tokens[index - 1].token_type, TokenType::LeftBrace | TokenType::Comma)
+                    && tokens.get(index + 1).map(|next| &next.token_type) == Some(&TokenType::Colon)
+                    && is_name(&token.value) =>
+            {
+                keys.push(token.value.clone());
+            }
+            _ => {}
+        }
+    }
+    keys
+}
+
+fn is_name(text: &str) -> bool {
+    text.starts_with(|c: char| c.is_alphabetic() || c == '_') && text.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
@@ -0,0 +1,159 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::ast::{Expr, ExprKind, Exprs, FunctionDecl, Program, Stmt, StmtKind};
+use crate::lexer::Span;
+
+/// The lines of each file a statement has started running on, recorded as
+/// programs run once `Interpreter::with_coverage` turns it on.
+#[derive(Debug, Clone, Default)]
+pub struct Coverage {
+    // a bit per line, by the file ids of spans
+    executed: HashMap<usize, Vec<u64>>,
+}
+
+impl Coverage {
+    pub(crate) fn record(&mut self, span: Span) {
+        let lines = self.executed.entry(span.file).or_default();
+        let (word, bit) = (span.line / 64, span.line % 64);
+        if lines.len() <= word {
+            lines.resize(word + 1, 0);
+        }
+        lines[word] |= 1 << bit;
+    }
+
+    /// Whether a statement on `line` of the file with id `file` has run.
+    pub fn executed(&self, file: usize, line: usize) -> bool {
+        self.executed
+            .get(&file)
+            .and_then(|lines| lines.get(line / 64))
+            .is_some_and(|word| word & (1 << (line % 64)) != 0)
+    }
+}
+
+/// How much of one file ran: each line a statement starts on, in order,
+/// with whether it ran.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileCoverage {
+    // the id of the file in the interpreter's `sources`
+    pub file: usize,
+    pub name: String,
+    pub lines: Vec<(usize, bool)>,
+}
+
+impl FileCoverage {
+    /// How many of the lines ran.
+    pub fn covered(&self) -> usize {
+        self.lines.iter().filter(|(_, executed)| *executed).count()
+    }
+}
+
+/// The lines of `program` coverage counts, which are those a statement
+/// starts on, in function bodies too. Lines of only comments or braces
+/// aren't among them. Neither are the bodies of `test` blocks, which only
+/// `Interpreter::run_tests` runs.
+pub fn coverable_lines(program: &Program) -> BTreeSet<usize> {
+    let mut lines = BTreeSet::new();
+    statements_lines(&program.statements, &program.exprs, &mut lines);
+    lines
+}
+
+fn statements_lines(statements: &[Stmt], exprs: &Exprs, lines: &mut BTreeSet<usize>) {
+    for statement in statements {
+        statement_lines(statement, exprs, lines);
+    }
+}
+
+fn statement_lines(stmt: &Stmt, exprs: &Exprs, lines: &mut BTreeSet<usize>) {
+    if let StmtKind::Test { .. } = stmt.kind {
+        return;
+    }
+    lines.insert(stmt.span.line);
+    match &stmt.kind {
+        StmtKind::Expression(expr) | StmtKind::Throw(expr) => expression_lines(expr, exprs, lines),
+        StmtKind::Let { initializer, .. } => initializer.iter().for_each(|expr| expression_lines(expr, exprs, lines)),
+        StmtKind::Destructure { initializer, .. } => expression_lines(initializer, exprs, lines),
+        StmtKind::Block(statements) => statements_lines(statements, exprs, lines),
+        StmtKind::If { condition, then_branch, else_branch } => {
+            expression_lines(condition, exprs, lines);
+            statements_lines(then_branch, exprs, lines);
+            else_branch.iter().for_each(|branch| statements_lines(branch, exprs, lines));
+        }
+        StmtKind::While { condition, body, .. } | StmtKind::DoWhile { body, condition, .. } => {
+            expression_lines(condition, exprs, lines);
+            statements_lines(body, exprs, lines);
+        }
+        StmtKind::Match { subject, arms, default } => {
+            expression_lines(subject, exprs, lines);
+            for arm in arms {
+                arm.values.iter().for_each(|value| expression_lines(value, exprs, lines));
+                statements_lines(&arm.body, exprs, lines);
+            }
+            default.iter().for_each(|default| statements_lines(default, exprs, lines));
+        }
+        StmtKind::For { initializer, condition, increment, body, .. } => {
+            initializer.iter().for_each(|initializer| statement_lines(initializer, exprs, lines));
+            condition.iter().chain(increment).for_each(|expr| expression_lines(expr, exprs, lines));
+            statements_lines(body, exprs, lines);
+        }
+        StmtKind::ForIn { iterable, body, .. } => {
+            expression_lines(iterable, exprs, lines);
+            statements_lines(body, exprs, lines);
+        }
+        StmtKind::Function(declaration) => function_lines(declaration, lines),
+        StmtKind::Class(declaration) => declaration.methods.iter().for_each(|method| function_lines(method, lines)),
+        StmtKind::Return(value) => value.iter().for_each(|value| expression_lines(value, exprs, lines)),
+        StmtKind::Try { body, handler, .. } => {
+            statements_lines(body, exprs, lines);
+            statements_lines(handler, exprs, lines);
+        }
+        StmtKind::Export(declaration) => statement_lines(declaration, exprs, lines),
+        StmtKind::Break(_) | StmtKind::Continue(_) | StmtKind::Import { .. } | StmtKind::Test { .. } => {}
+    }
+}
+
+fn function_lines(declaration: &FunctionDecl, lines: &mut BTreeSet<usize>) {
+    for param in &declaration.params {
+        param.default.iter().for_each(|default| expression_lines(default, &declaration.exprs, lines));
+    }
+    statements_lines(&declaration.body, &declaration.exprs, lines);
+}
+
+// only function expressions hold statements, but they can be anywhere
+fn expression_lines(expr: &Expr, exprs: &Exprs, lines: &mut BTreeSet<usize>) {
+    match &expr.kind {
+        ExprKind::Number(_)
+        | ExprKind::Int(_)
+        | ExprKind::String(_)
+        | ExprKind::Bool(_)
+        | ExprKind::Null
+        | ExprKind::Variable(_)
+        | ExprKind::This => {}
+        ExprKind::Assign { value, .. } => expression_lines(&exprs[*value], exprs, lines),
+        ExprKind::Unary { operand, .. } => expression_lines(&exprs[*operand], exprs, lines),
+        ExprKind::Binary { left, right, .. }
+        | ExprKind::Logical { left, right, .. }
+        | ExprKind::Index { object: left, index: right }
+        | ExprKind::SetMember { object: left, value: right, .. }
+        | ExprKind::Range { start: left, end: right, .. } => {
+            expression_lines(&exprs[*left], exprs, lines);
+            expression_lines(&exprs[*right], exprs, lines);
+        }
+        ExprKind::Call { callee, arguments } => {
+            expression_lines(&exprs[*callee], exprs, lines);
+            arguments.iter().for_each(|argument| expression_lines(argument, exprs, lines));
+        }
+        ExprKind::Array(elements) => elements.iter().for_each(|element| expression_lines(element, exprs, lines)),
+        ExprKind::Slice { object, start, end, .. } => {
+            expression_lines(&exprs[*object], exprs, lines);
+            start.iter().chain(end).for_each(|bound| expression_lines(&exprs[*bound], exprs, lines));
+        }
+        ExprKind::SetIndex { object, index, value, .. } => {
+            expression_lines(&exprs[*object], exprs, lines);
+            expression_lines(&exprs[*index], exprs, lines);
+            expression_lines(&exprs[*value], exprs, lines);
+        }
+        ExprKind::Map(entries) => entries.iter().for_each(|(_, value)| expression_lines(value, exprs, lines)),
+        ExprKind::Member { object, .. } => expression_lines(&exprs[*object], exprs, lines),
+        ExprKind::Function(declaration) => function_lines(declaration, lines),
+    }
+}
@@ -0,0 +1,324 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::mem;
+use std::rc::Rc;
+
+use corosensei::stack::DefaultStack;
+use corosensei::{Coroutine, CoroutineResult, Yielder};
+
+use crate::error::Error;
+use crate::interpreter::Interpreter;
+use crate::lexer::Span;
+use crate::source::SourceFile;
+use crate::value::Value;
+
+/// How much stack a debugged program gets unless its interpreter's
+/// `InterpreterConfig::stack_size` says otherwise. It's only reserved, and
+/// the pages are used as the program needs them.
+const STACK_SIZE: usize = 64 * 1024 * 1024;
+
+/// How a paused program goes on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resume {
+    // run until the next breakpoint
+    Continue,
+    // pause at the next statement, even inside a function it calls
+    StepInto,
+    // pause at the next statement of this function or its caller
+    StepOver,
+    // pause once this function has returned
+    StepOut,
+}
+
+/// Why a program paused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PauseReason {
+    Breakpoint,
+    Step,
+}
+
+/// Where a program stopped: just before running a statement, or before
+/// the next iteration of a loop.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Paused {
+    /// The name of the file it stopped in, as it was run or imported.
+    pub file: String,
+    pub span: Span,
+    /// How many calls deep it is: 0 at the top level.
+    pub depth: usize,
+    pub reason: PauseReason,
+}
+
+impl Paused {
+    pub fn line(&self) -> usize {
+        self.span.line
+    }
+}
+
+/// What a program being debugged did when it was last run on.
+#[derive(Debug)]
+pub enum Event {
+    Paused(Paused),
+    /// It ran to the end, with what `Interpreter::eval_file` would return.
+    Finished(Result<Value, Error>),
+}
+
+/// Runs programs so they can be paused at breakpoints and stepped through,
+/// e.g. for an editor. The program runs on a coroutine with a stack of its
+/// own, so pausing hands control back to the caller partway through a
+/// recursive interpreter, and every other method picks up where it left off.
+pub struct Debugger {
+    session: Session,
+    breakpoints: Rc<RefCell<HashSet<(String, usize)>>>,
+}
+
+enum Session {
+    Idle(Box<Interpreter>),
+    Paused(Program),
+    // only while `run` starts a program
+    Starting,
+}
+
+/// A program paused on its own stack. It takes what to do next and answers
+/// with where it paused again or what was asked for, until it returns the
+/// interpreter it ran on.
+type Program = Coroutine<Command, Reply, (Interpreter, Result<Value, Error>)>;
+
+enum Command {
+    Resume(Resume),
+    Inspect(String),
+    Locals,
+}
+
+enum Reply {
+    Paused(Paused),
+    Value(Option<Value>),
+    Locals(Vec<(String, Value)>),
+}
+
+impl Debugger {
+    pub fn new(interpreter: Interpreter) -> Self {
+        Debugger {
+            session: Session::Idle(Box::new(interpreter)),
+            breakpoints: Rc::default(),
+        }
+    }
+
+    /// Pauses before any statement that starts on `line` of the file named
+    /// `file`, the name a program was run under or an import's path. It can
+    /// be set while a program is paused.
+    pub fn set_breakpoint(&mut self, file: impl Into<String>, line: usize) {
+        self.breakpoints.borrow_mut().insert((file.into(), line));
+    }
+
+    /// Removes a breakpoint, returning whether there was one.
+    pub fn clear_breakpoint(&mut self, file: &str, line: usize) -> bool {
+        self.breakpoints.borrow_mut().remove(&(file.to_string(), line))
+    }
+
+    /// Starts running `file` like `Interpreter::eval_file`, until it pauses
+    /// at a breakpoint or finishes.
+    ///
+    /// # Panics
+    ///
+    /// If a program is already paused; it has to be continued to the end
+    /// first.
+    pub fn run(&mut self, file: SourceFile) -> Event {
+        let Session::Idle(mut interpreter) = mem::replace(&mut self.session, Session::Starting) else {
+            panic!("a program is already paused in this debugger");
+        };
+        let stack = DefaultStack::new(interpreter.config.stack_size.unwrap_or(STACK_SIZE))
+            .expect("couldn't reserve a stack for the program");
+        let breakpoints = Rc::clone(&self.breakpoints);
+        let program = Coroutine::with_stack(stack, move |yielder: &Yielder<Command, Reply>, _| {
+            interpreter.debugger = Some(Box::new(DebugHook {
+                breakpoints,
+                resume: Resume::Continue,
+                depth: 0,
+                yielder,
+            }));
+            let result = interpreter.eval_file(file);
+            interpreter.debugger = None;
+            (*interpreter, result)
+        });
+        self.session = Session::Paused(program);
+        self.resume(Resume::Continue)
+    }
+
+    /// Runs on to the next statement of this function or its caller,
+    /// stepping over any calls in between.
+    pub fn step_over(&mut self) -> Event {
+        self.resume(Resume::StepOver)
+    }
+
+    /// Runs on to the next statement, even inside a function called first.
+    pub fn step_into(&mut self) -> Event {
+        self.resume(Resume::StepInto)
+    }
+
+    /// Runs on until the function the program paused in has returned.
+    pub fn step_out(&mut self) -> Event {
+        self.resume(Resume::StepOut)
+    }
+
+    /// Runs on to the next breakpoint, or the end.
+    pub fn continue_(&mut self) -> Event {
+        self.resume(Resume::Continue)
+    }
+
+    /// Runs the paused program on as `resume` says.
+    ///
+    /// # Panics
+    ///
+    /// If no program is paused.
+    pub fn resume(&mut self, resume: Resume) -> Event {
+        match self.send(Command::Resume(resume)) {
+            CoroutineResult::Yield(Reply::Paused(paused)) => Event::Paused(paused),
+            CoroutineResult::Yield(_) => unreachable!("a resumed program answers by pausing"),
+            CoroutineResult::Return((interpreter, result)) => {
+                self.session = Session::Idle(Box::new(interpreter));
+                Event::Finished(result)
+            }
+        }
+    }
+
+    /// The value `name` has where the program paused, looked up through
+    /// every enclosing scope like a variable in the code would be. With no
+    /// program paused, it's looked up among the globals.
+    pub fn inspect(&mut self, name: &str) -> Option<Value> {
+        if let Session::Idle(interpreter) = &self.session {
+            return interpreter.environment.borrow().get(name);
+        }
+        match self.send(Command::Inspect(name.to_string())) {
+            CoroutineResult::Yield(Reply::Value(value)) => value,
+            _ => unreachable!("a paused program answers an inspection with a value"),
+        }
+    }
+
+    /// The variables in the scopes around where the program paused, up to
+    /// but not including the global one, sorted by name. A name bound in
+    /// more than one of them has its innermost value. With no program
+    /// paused, there are none.
+    pub fn locals(&mut self) -> Vec<(String, Value)> {
+        if let Session::Idle(interpreter) = &self.session {
+            return locals(interpreter);
+        }
+        match self.send(Command::Locals) {
+            CoroutineResult::Yield(Reply::Locals(locals)) => locals,
+            _ => unreachable!("a paused program answers with its locals"),
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        matches!(self.session, Session::Paused(_))
+    }
+
+    /// The interpreter programs run on, unless one is paused using it.
+    pub fn interpreter(&mut self) -> Option<&mut Interpreter> {
+        match &mut self.session {
+            Session::Idle(interpreter) => Some(interpreter),
+            _ => None,
+        }
+    }
+
+    /// The interpreter programs ran on, unless one is paused using it, in
+    /// which case that program is dropped along with it.
+    pub fn into_interpreter(self) -> Option<Interpreter> {
+        match self.session {
+            Session::Idle(interpreter) => Some(*interpreter),
+            _ => None,
+        }
+    }
+
+    fn send(&mut self, command: Command) -> CoroutineResult<Reply, (Interpreter, Result<Value, Error>)> {
+        let Session::Paused(program) = &mut self.session else {
+            panic!("no program is paused in this debugger");
+        };
+        program.resume(command)
+    }
+}
+
+/// The variables in the scopes from the interpreter's current one out to,
+/// but not including, the globals.
+fn locals(interpreter: &Interpreter) -> Vec<(String, Value)> {
+    let mut seen = HashSet::new();
+    let mut locals = Vec::new();
+    let mut scope = Rc::clone(&interpreter.environment);
+    while !Rc::ptr_eq(&scope, interpreter.globals()) {
+        let enclosing = {
+            let environment = scope.borrow();
+            for (name, value) in environment.bindings() {
+                if seen.insert(name.clone()) {
+                    locals.push((name.to_string(), value));
+                }
+            }
+            environment.enclosing().cloned()
+        };
+        match enclosing {
+            Some(enclosing) => scope = enclosing,
+            None => break,
+        }
+    }
+    locals.sort_by(|(a, _), (b, _)| a.cmp(b));
+    locals
+}
+
+/// What the interpreter consults before each statement while a `Debugger`
+/// is running it.
+pub(crate) struct DebugHook {
+    breakpoints: Rc<RefCell<HashSet<(String, usize)>>>,
+    // the last thing the debugger asked for, and the call depth it asked at
+    resume: Resume,
+    depth: usize,
+    // the way back to the debugger from the program's coroutine, which is
+    // the only place the hook is set
+    yielder: *const Yielder<Command, Reply>,
+}
+
+impl Interpreter {
+    /// Pauses before the statement at `span` if a breakpoint or step says
+    /// to, answering the debugger until it says to go on.
+    pub(crate) fn debug(&mut self, span: Span) {
+        let Some(mut hook) = self.debugger.take() else {
+            return;
+        };
+        let file = self.sources().get(span.file).map_or("<input>", |file| file.name.as_str());
+        let stepped = match hook.resume {
+            Resume::Continue => false,
+            Resume::StepInto => true,
+            Resume::StepOver => self.call_depth <= hook.depth,
+            Resume::StepOut => self.call_depth < hook.depth,
+        };
+        let reason = if stepped {
+            Some(PauseReason::Step)
+        } else {
+            hook.breakpoints
+                .borrow()
+                .contains(&(file.to_string(), span.line))
+                .then_some(PauseReason::Breakpoint)
+        };
+        if let Some(reason) = reason {
+            let mut reply = Reply::Paused(Paused {
+                file: file.to_string(),
+                span,
+                depth: self.call_depth,
+                reason,
+            });
+            // SAFETY: the hook is set and removed inside the coroutine the
+            // yielder belongs to, so it's alive whenever the hook runs.
+            let yielder = unsafe { &*hook.yielder };
+            loop {
+                match yielder.suspend(reply) {
+                    Command::Resume(resume) => {
+                        hook.resume = resume;
+                        hook.depth = self.call_depth;
+                        break;
+                    }
+                    Command::Inspect(name) => reply = Reply::Value(self.environment.borrow().get(&name)),
+                    Command::Locals => reply = Reply::Locals(locals(self)),
+                }
+            }
+        }
+        self.debugger = Some(hook);
+    }
+}
@@ -0,0 +1,102 @@
+use std::fmt::Write;
+
+use crate::compiler::{self, Chunk, Op};
+use crate::value::Value;
+
+/// Renders a chunk one instruction per line, followed by the chunks of the
+/// functions it defines. Each line has the instruction's offset, its source
+/// line (`|` when unchanged from the previous instruction, `-` when it has
+/// none), its name, and its operands with table entries resolved. Jump
+/// targets are instruction offsets.
+///
+/// ```text
+/// == <script> ==
+/// 0000    1 Tick
+/// 0001    | Constant 0 (60)
+/// ```
+pub fn disassemble(chunk: &Chunk) -> String {
+    let mut output = String::new();
+    disassemble_into(&mut output, chunk);
+    output
+}
+
+fn disassemble_into(output: &mut String, chunk: &Chunk) {
+    let _ = writeln!(output, "== {} ==", chunk.name);
+    let mut previous_line = None;
+    for (offset, (op, span)) in chunk.code.iter().zip(&chunk.spans).enumerate() {
+        let line = match span.line {
+            0 => "-".to_string(),
+            line if previous_line == Some(line) => "|".to_string(),
+            line => line.to_string(),
+        };
+        previous_line = Some(span.line);
+        let _ = writeln!(output, "{:04} {:>4} {}", offset, line, instruction(chunk, *op));
+    }
+    let methods = chunk.classes.iter().flat_map(|class| &class.methods);
+    for declaration in chunk.functions.iter().chain(methods) {
+        output.push('\n');
+        disassemble_into(output, &compiler::compile_function(declaration));
+    }
+}
+
+fn instruction(chunk: &Chunk, op: Op) -> String {
+    let name = |index: usize| format!("{} ({})", index, chunk.names[index]);
+    match op {
+        Op::Constant(index) => format!("Constant {} ({})", index, constant(&chunk.constants[index])),
+        Op::GetVariable(index) => format!("GetVariable {}", name(index)),
+        Op::SetVariable(index) => format!("SetVariable {}", name(index)),
+        Op::Declare(index) => format!("Declare {}", name(index)),
+        Op::DeclareConst(index) => format!("DeclareConst {}", name(index)),
+        Op::Define(index) => format!("Define {}", name(index)),
+        Op::Import(index) => format!("Import {}", name(index)),
+        Op::ImportModule(index) => format!("ImportModule {}", name(index)),
+        Op::GetMember(index) => format!("GetMember {}", name(index)),
+        Op::SetMember(index) => format!("SetMember {}", name(index)),
+        Op::Closure(index) => format!("Closure {} ({})", index, chunk.functions[index].name),
+        Op::Class(index) => format!("Class {} ({})", index, chunk.classes[index].name),
+        Op::Unary(operator) => format!("Unary {}", operator.symbol()),
+        Op::Binary(operator) => format!("Binary {}", operator.symbol()),
+        Op::Compound(operator) => format!("Compound {}=", operator.symbol()),
+        Op::Jump(target) => format!("Jump -> {:04}", target),
+        Op::JumpIfBound(index, target) => format!("JumpIfBound {} -> {:04}", name(index), target),
+        Op::JumpIfFalse(target) => format!("JumpIfFalse -> {:04}", target),
+        Op::JumpIfFalseOrPop(target) => format!("JumpIfFalseOrPop -> {:04}", target),
+        Op::JumpIfTrueOrPop(target) => format!("JumpIfTrueOrPop -> {:04}", target),
+        Op::Slice(inclusive) => format!("Slice {}", if inclusive { "..=" } else { ".." }),
+        Op::Destructure(index) => format!("Destructure {}", index),
+        Op::DestructureConst(index) => format!("DestructureConst {}", index),
+        Op::Next(target) => format!("Next -> {:04}", target),
+        Op::Try(target) => format!("Try -> {:04}", target),
+        Op::Call(count) => format!("Call {}", count),
+        Op::TailCall(count) => format!("TailCall {}", count),
+        Op::Array(count) => format!("Array {}", count),
+        Op::Map(index) => format!("Map {} ({})", index, chunk.map_keys[index].join(", ")),
+        Op::Error(code, index) => format!("Error {} {} ({})", code.id, index, constant(&chunk.constants[index])),
+        Op::Null
+        | Op::Pop
+        | Op::Duplicate
+        | Op::DuplicateTwo
+        | Op::GetIndex
+        | Op::SetIndex
+        | Op::PushScope
+        | Op::PopScope
+        | Op::Iterate
+        | Op::CheckRangeBound
+        | Op::IterateRange
+        | Op::EndIteration
+        | Op::EndTry
+        | Op::Tick
+        | Op::SetResult
+        | Op::Return
+        | Op::Halt
+        | Op::Throw => format!("{:?}", op),
+    }
+}
+
+/// Shows strings quoted and escaped, so `"1"` and `1` can be told apart.
+fn constant(value: &Value) -> String {
+    match value {
+        Value::String(s) => format!("{:?}", s),
+        other => other.to_string(),
+    }
+}
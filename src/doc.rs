@@ -0,0 +1,48 @@
+use crate::ast::{FunctionDecl, Stmt, StmtKind};
+
+/// How `declaration` is called, like `add(a: number, b = 1): number`, with
+/// its defaults quoted from `source`, the file it was declared in.
+pub fn signature(declaration: &FunctionDecl, source: &str) -> String {
+    let params: Vec<String> = declaration
+        .params
+        .iter()
+        .map(|param| {
+            let mut text = if param.rest { format!("...{}", param.name) } else { param.name.to_string() };
+            if let Some(annotation) = &param.annotation {
+                text.push_str(&format!(": {}", annotation));
+            }
+            if let Some(default) = &param.default {
+                let written = source.get(default.span.start..default.span.end).unwrap_or("...");
+                text.push_str(&format!(" = {}", written));
+            }
+            text
+        })
+        .collect();
+    let mut signature = format!("{}({})", declaration.name, params.join(", "));
+    if let Some(return_type) = &declaration.return_type {
+        signature.push_str(&format!(": {}", return_type));
+    }
+    signature
+}
+
+/// A Markdown page for the file `name`, parsed into `statements` from
+/// `source`: a section for each function declared at its top level,
+/// exported or not, in order, giving its signature and then its doc
+/// comment, if it has one.
+pub fn markdown(name: &str, statements: &[Stmt], source: &str) -> String {
+    let mut page = format!("# {}\n", name);
+    for statement in statements {
+        let StmtKind::Function(declaration) = &statement.without_export().kind else {
+            continue;
+        };
+        page.push_str(&format!(
+            "\n## {}\n\n```\nfunction {}\n```\n",
+            declaration.name,
+            signature(declaration, source)
+        ));
+        if let Some(doc) = &declaration.doc {
+            page.push_str(&format!("\n{}\n", doc));
+        }
+    }
+    page
+}
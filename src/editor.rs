@@ -0,0 +1,118 @@
+use std::env;
+use std::io;
+use std::path::PathBuf;
+
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::{ValidationContext, ValidationResult, Validator};
+use rustyline::{CompletionType, Config, Context, Editor};
+
+use crate::completion;
+use crate::interpreter::{Completion, Interpreter};
+use crate::repl::{self, Session};
+
+// the most entries the history file keeps, dropping the oldest
+const HISTORY_SIZE: usize = 1000;
+
+/// Runs an interactive session like `repl::run`, but reading from the
+/// terminal through a line editor. The arrow keys move through the entry
+/// being typed and back through earlier ones, which are kept in
+/// `~/.slimescript_history` for the next session. Enter on an entry with
+/// unclosed brackets or strings starts a new line of it, so an entry of
+/// several lines, typed or pasted, is edited, run, and saved to the
+/// history as one. Tab completes the word at the cursor, as
+/// `repl::completions` does. Ctrl-C drops the entry being typed, and
+/// Ctrl-D on an empty line ends the session.
+pub fn run(interpreter: &mut Interpreter, color: bool) -> io::Result<Completion> {
+    let config = Config::builder()
+        .max_history_size(HISTORY_SIZE)
+        .and_then(|config| config.history_ignore_dups(true))
+        .map_err(io::Error::other)?
+        .auto_add_history(false)
+        .completion_type(CompletionType::List)
+        .bracketed_paste(true)
+        .build();
+    let mut editor: Editor<EntryHelper, DefaultHistory> = Editor::with_config(config).map_err(io::Error::other)?;
+    editor.set_helper(Some(EntryHelper { globals: repl::global_completions(interpreter) }));
+    let history = history_path();
+    if let Some(path) = &history {
+        // there's none before the first session
+        let _ = editor.load_history(path);
+    }
+
+    let mut session = Session::new(color);
+    loop {
+        let source = match editor.readline(repl::PROMPT) {
+            Ok(source) => source,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => return Ok(Completion::Finished),
+            Err(error) => return Err(io::Error::other(error)),
+        };
+        if !source.trim().is_empty() {
+            let _ = editor.add_history_entry(source.as_str());
+            // saved as each entry is made, so a session ended by a signal
+            // keeps its history; the session goes on without it if the
+            // file can't be written
+            if let Some(path) = &history {
+                let _ = editor.append_history(path);
+            }
+        }
+        if let Some(completion) = session.enter(interpreter, &source)? {
+            return Ok(completion);
+        }
+        if let Some(helper) = editor.helper_mut() {
+            helper.globals = repl::global_completions(interpreter);
+        }
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    let home = env::var_os("HOME").filter(|home| !home.is_empty())?;
+    Some(PathBuf::from(home).join(".slimescript_history"))
+}
+
+// tells the editor when an entry is finished, and what can be written
+// at the cursor
+struct EntryHelper {
+    // the globals declared so far, updated after each entry
+    globals: Vec<completion::Completion>,
+}
+
+impl Validator for EntryHelper {
+    fn validate(&self, context: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        let input = context.input();
+        Ok(match !repl::is_command(input) && repl::is_incomplete(input) {
+            true => ValidationResult::Incomplete,
+            false => ValidationResult::Valid(None),
+        })
+    }
+}
+
+impl Completer for EntryHelper {
+    type Candidate = Pair;
+
+    fn complete(&self, line: &str, position: usize, _: &Context) -> rustyline::Result<(usize, Vec<Pair>)> {
+        // the completions all start with the part of the word before the
+        // cursor, which they replace
+        let start = line[..position].trim_end_matches(|c: char| c.is_alphanumeric() || c == '_').len();
+        let candidates = completion::complete_with_globals(line, position, &self.globals)
+            .into_iter()
+            .map(|completion| Pair {
+                display: completion.label.clone(),
+                replacement: completion.label,
+            })
+            .collect();
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for EntryHelper {
+    type Hint = String;
+}
+
+impl Highlighter for EntryHelper {}
+
+impl rustyline::Helper for EntryHelper {}
@@ -0,0 +1,145 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::lexer::Span;
+use crate::symbol::{LookupName, Symbol};
+use crate::value::Value;
+
+#[derive(Debug)]
+struct Binding {
+    value: Value,
+    constant: bool,
+    // where the binding was declared; builtins and parameters bound by the
+    // interpreter itself don't have one
+    declared_at: Option<Span>,
+}
+
+/// Why `Environment::assign` failed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AssignError {
+    Undefined,
+    // the binding is a `const`, declared at the given position
+    Constant(Option<Span>),
+}
+
+/// One scope of variable bindings, linked to the scope that encloses it.
+/// Names may be passed as strings, or as symbols to skip interning them.
+/// Only binding a name interns it; looking one up doesn't.
+#[derive(Debug, Default)]
+pub struct Environment {
+    values: HashMap<Symbol, Binding>,
+    enclosing: Option<Rc<RefCell<Environment>>>,
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Environment::default()
+    }
+
+    pub fn with_enclosing(enclosing: Rc<RefCell<Environment>>) -> Self {
+        Environment {
+            values: HashMap::new(),
+            enclosing: Some(enclosing),
+        }
+    }
+
+    /// Removes every binding in this scope. Functions declared in a scope
+    /// hold on to it, so a scope that outlives its program is cleared to let
+    /// them, and the names they intern, be freed.
+    pub(crate) fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    /// Binds `name` in this scope, replacing any existing binding here.
+    pub fn define(&mut self, name: impl Into<Symbol>, value: Value) {
+        self.values.insert(
+            name.into(),
+            Binding {
+                value,
+                constant: false,
+                declared_at: None,
+            },
+        );
+    }
+
+    /// Binds `name` in this scope to `value`, keeping whether a binding it
+    /// replaces was constant and where that was declared.
+    pub(crate) fn replace(&mut self, name: impl Into<Symbol>, value: Value) {
+        let name = name.into();
+        match self.values.get_mut(&name) {
+            Some(binding) => binding.value = value,
+            None => self.define(name, value),
+        }
+    }
+
+    /// Binds `name` for a declaration in the script at `span`. If this scope
+    /// already has a binding for `name`, nothing changes and the position of
+    /// the earlier declaration (if it has one) is returned as the error.
+    pub fn declare(
+        &mut self,
+        name: impl Into<Symbol>,
+        value: Value,
+        constant: bool,
+        span: Span,
+    ) -> Result<(), Option<Span>> {
+        let name = name.into();
+        if let Some(existing) = self.values.get(&name) {
+            return Err(existing.declared_at);
+        }
+        self.values.insert(
+            name,
+            Binding {
+                value,
+                constant,
+                declared_at: Some(span),
+            },
+        );
+        Ok(())
+    }
+
+    /// Whether this scope itself binds `name`, ignoring enclosing scopes.
+    pub fn binds(&self, name: impl LookupName) -> bool {
+        name.symbol().is_some_and(|name| self.values.contains_key(&name))
+    }
+
+    /// The names bound in this scope alone, with their values, in no
+    /// particular order.
+    pub fn bindings(&self) -> impl Iterator<Item = (Symbol, Value)> {
+        self.values.iter().map(|(name, binding)| (name.clone(), binding.value.clone()))
+    }
+
+    /// The scope this one is inside, or `None` for the global scope.
+    pub fn enclosing(&self) -> Option<&Rc<RefCell<Environment>>> {
+        self.enclosing.as_ref()
+    }
+
+    /// Looks `name` up through this scope and every enclosing one.
+    pub fn get(&self, name: impl LookupName) -> Option<Value> {
+        self.lookup(name.symbol()?)
+    }
+
+    fn lookup(&self, name: Symbol) -> Option<Value> {
+        match self.values.get(&name) {
+            Some(binding) => Some(binding.value.clone()),
+            None => self.enclosing.as_ref()?.borrow().lookup(name),
+        }
+    }
+
+    /// Updates the nearest existing binding of `name`, unless there isn't
+    /// one or it is a constant.
+    pub fn assign(&mut self, name: impl LookupName, value: Value) -> Result<(), AssignError> {
+        let name = name.symbol().ok_or(AssignError::Undefined)?;
+        if let Some(binding) = self.values.get_mut(&name) {
+            if binding.constant {
+                return Err(AssignError::Constant(binding.declared_at));
+            }
+            binding.value = value;
+            return Ok(());
+        }
+        match &self.enclosing {
+            Some(enclosing) => enclosing.borrow_mut().assign(name, value),
+            None => Err(AssignError::Undefined),
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use std::fmt;
+
+use crate::interpreter::{ImportError, RuntimeError};
+use crate::lexer::LexError;
+use crate::parser::ParseError;
+
+/// Anything that can go wrong running a program, from lexing through
+/// execution.
+#[derive(Debug, Clone)]
+pub enum Error {
+    Lex(LexError),
+    Parse(ParseError),
+    Import(ImportError),
+    Runtime(RuntimeError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Lex(error) => write!(f, "Lexer error: {}", error),
+            Error::Parse(error) => write!(f, "Parse error: {}", error),
+            Error::Import(error) => write!(f, "Import error: {}", error),
+            Error::Runtime(error) => write!(f, "Runtime error: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<LexError> for Error {
+    fn from(error: LexError) -> Self {
+        Error::Lex(error)
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(error: ParseError) -> Self {
+        Error::Parse(error)
+    }
+}
+
+impl From<ImportError> for Error {
+    fn from(error: ImportError) -> Self {
+        Error::Import(error)
+    }
+}
+
+impl From<RuntimeError> for Error {
+    fn from(error: RuntimeError) -> Self {
+        Error::Runtime(error)
+    }
+}
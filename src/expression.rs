@@ -0,0 +1,120 @@
+use std::cell::RefCell;
+use std::io;
+use std::rc::Rc;
+
+use crate::ast::{Expr, ExprKind, Exprs, Program, Stmt, StmtKind};
+use crate::builtins;
+use crate::codes;
+use crate::environment::Environment;
+use crate::error::Error;
+use crate::interpreter::Interpreter;
+use crate::lexer::Lexer;
+use crate::parser::{ParseError, Parser};
+use crate::value::Value;
+
+/// A single expression parsed once, to be evaluated any number of times
+/// with different variables, like a formula in a calculator or a
+/// spreadsheet. It can only read: its names are the variables it's given
+/// and the builtins `abs`, `floor`, `ceil`, `round`, `sqrt`, `min`, and
+/// `max`, which variables of the same name hide.
+pub struct CompiledExpr {
+    // the expression as a program of one statement, for `interpret`
+    program: Program,
+    // the builtins it can call, shared by every evaluation
+    builtins: Rc<RefCell<Environment>>,
+    interpreter: RefCell<Interpreter>,
+}
+
+impl CompiledExpr {
+    /// Lexes and parses `source`, which must be one expression and nothing
+    /// else. One that assigns or defines a function is rejected with a
+    /// `not-read-only` parse error, as nothing it's evaluated with may
+    /// change.
+    pub fn compile(source: &str) -> Result<CompiledExpr, Error> {
+        let tokens = Lexer::new(source).tokenize()?;
+        let (expr, exprs) = Parser::new(tokens).parse_expression()?;
+        read_only(&expr, &exprs)?;
+        let mut builtins = Environment::new();
+        builtins::define_pure_math(&mut builtins);
+        Ok(CompiledExpr {
+            program: Program {
+                statements: vec![Stmt {
+                    span: expr.span,
+                    kind: StmtKind::Expression(expr),
+                }],
+                exprs,
+            },
+            builtins: Rc::new(RefCell::new(builtins)),
+            interpreter: RefCell::new(Interpreter::with_io(io::empty(), io::sink())),
+        })
+    }
+
+    /// The expression's value with the names in `variables` bound to their
+    /// values. A name it reads that is neither one of them nor a math
+    /// builtin is an `undefined-name` runtime error.
+    pub fn eval(&self, variables: &[(&str, Value)]) -> Result<Value, Error> {
+        let mut scope = Environment::with_enclosing(Rc::clone(&self.builtins));
+        for (name, value) in variables {
+            scope.define(*name, value.clone());
+        }
+        let mut interpreter = self.interpreter.borrow_mut();
+        let previous = std::mem::replace(&mut interpreter.environment, Rc::new(RefCell::new(scope)));
+        let value = interpreter.interpret(&self.program);
+        interpreter.environment = previous;
+        Ok(value?)
+    }
+}
+
+/// Evaluates `source`, a single expression, with the names in `variables`
+/// bound to their values, as `CompiledExpr` does. Compiling the expression
+/// once is quicker for one evaluated many times.
+pub fn eval_with_vars(source: &str, variables: &[(&str, Value)]) -> Result<Value, Error> {
+    CompiledExpr::compile(source)?.eval(variables)
+}
+
+// rejects an expression that assigns or defines a function anywhere in it
+fn read_only(expr: &Expr, exprs: &Exprs) -> Result<(), ParseError> {
+    let child = |id| read_only(&exprs[id], exprs);
+    let error = |message: &str| ParseError {
+        code: &codes::NOT_READ_ONLY,
+        message: message.to_string(),
+        span: expr.span,
+    };
+    match &expr.kind {
+        ExprKind::Assign { .. } | ExprKind::SetIndex { .. } | ExprKind::SetMember { .. } => {
+            Err(error("The expression can't assign, only read"))
+        }
+        ExprKind::Function(_) => Err(error("The expression can't define a function")),
+        ExprKind::Number(_)
+        | ExprKind::Int(_)
+        | ExprKind::String(_)
+        | ExprKind::Bool(_)
+        | ExprKind::Null
+        | ExprKind::Variable(_)
+        | ExprKind::This => Ok(()),
+        ExprKind::Unary { operand, .. } => child(*operand),
+        ExprKind::Binary { left, right, .. } | ExprKind::Logical { left, right, .. } => {
+            child(*left)?;
+            child(*right)
+        }
+        ExprKind::Call { callee, arguments } => {
+            child(*callee)?;
+            arguments.iter().try_for_each(|argument| read_only(argument, exprs))
+        }
+        ExprKind::Array(elements) => elements.iter().try_for_each(|element| read_only(element, exprs)),
+        ExprKind::Index { object, index } => {
+            child(*object)?;
+            child(*index)
+        }
+        ExprKind::Slice { object, start, end, .. } => {
+            child(*object)?;
+            [start, end].into_iter().flatten().try_for_each(|bound| child(*bound))
+        }
+        ExprKind::Map(entries) => entries.iter().try_for_each(|(_, value)| read_only(value, exprs)),
+        ExprKind::Member { object, .. } => child(*object),
+        ExprKind::Range { start, end, .. } => {
+            child(*start)?;
+            child(*end)
+        }
+    }
+}
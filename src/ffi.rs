@@ -0,0 +1,267 @@
+//! A C interface for embedding the interpreter in programs written in
+//! other languages, built with the `ffi` feature. Generate a header with
+//! `cbindgen --config cbindgen.toml --output slimescript.h`, and a shared
+//! library with `cargo rustc --release --features ffi --crate-type cdylib`.
+//!
+//! Strings cross the boundary as NUL-terminated UTF-8. Strings passed in
+//! stay owned by the caller; every string returned is allocated by the
+//! library and must be released with `slime_string_free`. A panic inside
+//! the interpreter is caught at the boundary and reported as
+//! `SlimeStatus::Panic`.
+//!
+//! An interpreter belongs to the thread that created it: its values are
+//! reference counted without atomics, and names are interned per thread.
+//! Calls on it from any other thread don't touch it: they return
+//! `SlimeStatus::WrongThread`, or null from `slime_last_error_message`,
+//! and `slime_free` leaks it rather than freeing it there. Separate
+//! interpreters on separate threads are fine.
+
+use std::ffi::{CStr, CString, c_char};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::thread::{self, ThreadId};
+
+use crate::interpreter::Interpreter;
+use crate::value::Value;
+
+/// How a call into the library went.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlimeStatus {
+    Ok = 0,
+    // the program failed to lex, parse, load its imports, or run; see
+    // `slime_last_error_message`
+    Error = 1,
+    // the interpreter panicked, which is a bug in it
+    Panic = 2,
+    // a pointer was null or a string wasn't UTF-8
+    InvalidArgument = 3,
+    // the global doesn't exist or has a different type
+    NotFound = 4,
+    // the interpreter was created on another thread, which must make
+    // every call on it
+    WrongThread = 5,
+}
+
+/// What `slime_eval` produced.
+#[repr(C)]
+#[derive(Debug)]
+pub struct SlimeResult {
+    pub status: SlimeStatus,
+    // the value of the program's last expression statement as `str()`
+    // renders it, or null unless `status` is `Ok`; free with
+    // `slime_string_free`
+    pub value: *mut c_char,
+}
+
+/// An interpreter and the last error it reported, opaque to C.
+pub struct SlimeInterpreter {
+    interpreter: Interpreter,
+    last_error: Option<String>,
+    // the thread that created it, the only one that may use it
+    thread: ThreadId,
+}
+
+impl SlimeInterpreter {
+    fn on_own_thread(&self) -> bool {
+        self.thread == thread::current().id()
+    }
+
+    fn fail(&mut self, status: SlimeStatus, message: impl Into<String>) -> SlimeStatus {
+        self.last_error = Some(message.into());
+        status
+    }
+}
+
+/// Runs `f`, turning a panic into `SlimeStatus::Panic`.
+fn catch(f: impl FnOnce() -> SlimeStatus) -> SlimeStatus {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(SlimeStatus::Panic)
+}
+
+/// The panic's message, which is a `&str` or `String` for `panic!` calls.
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic");
+    format!("interpreter panicked: {}", message)
+}
+
+/// Copies `s` into a string for C to free with `slime_string_free`. C
+/// strings can't hold NUL, so it is cut short at the first one.
+fn c_string(s: &str) -> *mut c_char {
+    let end = s.find('\0').unwrap_or(s.len());
+    CString::new(&s[..end]).expect("NUL was cut off above").into_raw()
+}
+
+/// Borrows a string from C, or `None` if it is null or not UTF-8.
+///
+/// # Safety
+///
+/// `s` must be null or point to a NUL-terminated string that outlives the
+/// result.
+unsafe fn rust_str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    unsafe { CStr::from_ptr(s) }.to_str().ok()
+}
+
+/// A new interpreter with its own globals, printing to stdout. Returns null
+/// if creating it panicked. Release it with `slime_free`. Only the calling
+/// thread may use it.
+#[unsafe(no_mangle)]
+pub extern "C" fn slime_new() -> *mut SlimeInterpreter {
+    panic::catch_unwind(|| {
+        Box::into_raw(Box::new(SlimeInterpreter {
+            interpreter: Interpreter::new(),
+            last_error: None,
+            thread: thread::current().id(),
+        }))
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Releases an interpreter from `slime_new`. Null is ignored, and an
+/// interpreter created on another thread is leaked instead, since freeing
+/// its values here isn't safe.
+///
+/// # Safety
+///
+/// `handle` must be null or come from `slime_new`, and must not be used
+/// afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn slime_free(handle: *mut SlimeInterpreter) {
+    if let Some(interpreter) = unsafe { handle.as_ref() }
+        && interpreter.on_own_thread()
+    {
+        drop(unsafe { Box::from_raw(handle) });
+    }
+}
+
+/// Runs `source` against the interpreter's globals, as `Interpreter::eval`
+/// does, and fills in `out` if it isn't null.
+///
+/// # Safety
+///
+/// `handle` must come from `slime_new`, `source` must be null or a
+/// NUL-terminated string, and `out` must be null or point to a writable
+/// `SlimeResult`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn slime_eval(
+    handle: *mut SlimeInterpreter,
+    source: *const c_char,
+    out: *mut SlimeResult,
+) -> SlimeStatus {
+    let Some(handle) = (unsafe { handle.as_mut() }) else {
+        return SlimeStatus::InvalidArgument;
+    };
+    if !handle.on_own_thread() {
+        return SlimeStatus::WrongThread;
+    }
+    let mut value = ptr::null_mut();
+    let status = match unsafe { rust_str(source) } {
+        None => handle.fail(SlimeStatus::InvalidArgument, "source is null or not UTF-8"),
+        Some(source) => match panic::catch_unwind(AssertUnwindSafe(|| handle.interpreter.eval(source))) {
+            Ok(Ok(result)) => {
+                value = c_string(&result.to_string());
+                SlimeStatus::Ok
+            }
+            Ok(Err(error)) => handle.fail(SlimeStatus::Error, error.to_string()),
+            Err(payload) => handle.fail(SlimeStatus::Panic, panic_message(&*payload)),
+        },
+    };
+    if let Some(out) = unsafe { out.as_mut() } {
+        *out = SlimeResult { status, value };
+    } else if !value.is_null() {
+        drop(unsafe { CString::from_raw(value) });
+    }
+    status
+}
+
+/// Reads the global number `name` into `out`.
+///
+/// # Safety
+///
+/// `handle` must come from `slime_new`, `name` must be null or a
+/// NUL-terminated string, and `out` must point to a writable `double`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn slime_get_global_number(
+    handle: *mut SlimeInterpreter,
+    name: *const c_char,
+    out: *mut f64,
+) -> SlimeStatus {
+    let (Some(handle), Some(name), false) = (unsafe { handle.as_mut() }, unsafe { rust_str(name) }, out.is_null())
+    else {
+        return SlimeStatus::InvalidArgument;
+    };
+    if !handle.on_own_thread() {
+        return SlimeStatus::WrongThread;
+    }
+    catch(|| match handle.interpreter.get_global(name) {
+        Some(value) if let Some(n) = value.as_number() => {
+            unsafe { *out = n };
+            SlimeStatus::Ok
+        }
+        _ => handle.fail(SlimeStatus::NotFound, format!("no global number '{}'", name)),
+    })
+}
+
+/// Reads the global string `name` into `*out`, which the caller frees with
+/// `slime_string_free`.
+///
+/// # Safety
+///
+/// `handle` must come from `slime_new`, `name` must be null or a
+/// NUL-terminated string, and `out` must point to a writable `char *`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn slime_get_global_str(
+    handle: *mut SlimeInterpreter,
+    name: *const c_char,
+    out: *mut *mut c_char,
+) -> SlimeStatus {
+    let (Some(handle), Some(name), false) = (unsafe { handle.as_mut() }, unsafe { rust_str(name) }, out.is_null())
+    else {
+        return SlimeStatus::InvalidArgument;
+    };
+    if !handle.on_own_thread() {
+        return SlimeStatus::WrongThread;
+    }
+    catch(|| match handle.interpreter.get_global(name) {
+        Some(Value::String(s)) => {
+            unsafe { *out = c_string(&s) };
+            SlimeStatus::Ok
+        }
+        _ => handle.fail(SlimeStatus::NotFound, format!("no global string '{}'", name)),
+    })
+}
+
+/// The message of the last error reported by a call on `handle`, or null
+/// if none has failed or `handle` belongs to another thread. The caller
+/// frees it with `slime_string_free`.
+///
+/// # Safety
+///
+/// `handle` must be null or come from `slime_new`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn slime_last_error_message(handle: *const SlimeInterpreter) -> *mut c_char {
+    let handle = unsafe { handle.as_ref() }.filter(|handle| handle.on_own_thread());
+    match handle.and_then(|handle| handle.last_error.as_deref()) {
+        Some(message) => c_string(message),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Releases a string returned by the library. Null is ignored.
+///
+/// # Safety
+///
+/// `s` must be null or a string returned by this library that hasn't been
+/// freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn slime_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
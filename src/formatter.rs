@@ -0,0 +1,278 @@
+use crate::lexer::{LexError, Lexer, Token, TokenType};
+
+const INDENT: &str = "    ";
+
+/// Rewrites `source` in the canonical style: four-space indentation, one
+/// statement per line, braces on the same line as the code they belong to,
+/// one space around binary operators and after commas and colons, and none
+/// before semicolons. Comments stay where they are, either at the end of a
+/// line or on their own, and a run of blank lines becomes one.
+///
+/// Only whitespace changes, so the tokens of the result are the tokens of
+/// `source`, and formatting the result again changes nothing.
+pub fn format_source(source: &str) -> Result<String, LexError> {
+    let mut tokens = Lexer::with_comments(source).tokenize()?;
+    tokens.pop(); // EOF
+    let mut formatter = Formatter {
+        source,
+        output: String::new(),
+        open: Vec::new(),
+        newline_after: false,
+        unary_last: false,
+        do_bodies: Vec::new(),
+    };
+    for index in 0..tokens.len() {
+        formatter.token(&tokens, index);
+    }
+    if !formatter.output.is_empty() {
+        formatter.output.push('\n');
+    }
+    Ok(formatter.output)
+}
+
+/// A bracket that hasn't been closed yet.
+#[derive(Clone, Copy, PartialEq)]
+enum Open {
+    Block,
+    Map,
+    // parentheses or square brackets
+    Group,
+}
+
+struct Formatter<'a> {
+    source: &'a str,
+    output: String,
+    open: Vec<Open>,
+    // whether the next token starts a new line
+    newline_after: bool,
+    // whether the last token was a prefix `-` or `!`
+    unary_last: bool,
+    // how many brackets were open outside each `do` loop body still open,
+    // since the `while` after its closing brace stays on the same line
+    do_bodies: Vec<usize>,
+}
+
+impl Formatter<'_> {
+    fn token(&mut self, tokens: &[Token], index: usize) {
+        let token = &tokens[index];
+        let previous = index.checked_sub(1).map(|index| &tokens[index]);
+        // the nearest token that isn't a comment decides how code is spaced
+        let code_before = tokens[..index]
+            .iter()
+            .rev()
+            .find(|token| token.token_type != TokenType::Comment);
+        let next_code = tokens[index + 1..]
+            .iter()
+            .find(|token| token.token_type != TokenType::Comment);
+
+        // work out what the token opens or closes before placing it, since
+        // a closing brace is indented like the line that opened it
+        let opens = match token.token_type {
+            TokenType::LeftBrace if opens_map(code_before) => Some(Open::Map),
+            TokenType::LeftBrace => Some(Open::Block),
+            TokenType::LeftParen | TokenType::LeftBracket => Some(Open::Group),
+            _ => None,
+        };
+        let closes = match token.token_type {
+            TokenType::RightBrace | TokenType::RightParen | TokenType::RightBracket => self.open.pop(),
+            _ => None,
+        };
+        let empty_block = closes == Some(Open::Block)
+            && previous.is_some_and(|previous| previous.token_type == TokenType::LeftBrace);
+
+        let text = &self.source[token.span.start..token.span.end];
+        match previous {
+            None => {}
+            // a comment on the same line as the code before it stays there
+            Some(previous)
+                if token.token_type == TokenType::Comment
+                    && previous.token_type != TokenType::Comment
+                    && token.line == self.end_line(previous) =>
+            {
+                self.output.push(' ');
+            }
+            Some(previous)
+                if (self.newline_after || closes == Some(Open::Block) || token.token_type == TokenType::Comment)
+                    && !empty_block =>
+            {
+                self.output.push('\n');
+                let blank_line = token.line > self.end_line(previous) + 1;
+                if blank_line && previous.token_type != TokenType::LeftBrace && closes != Some(Open::Block) {
+                    self.output.push('\n');
+                }
+                self.indent(closes == Some(Open::Block));
+            }
+            Some(_) if self.space_before(token, code_before, closes) => self.output.push(' '),
+            // keep tokens apart that would lex as one, like `..` and `=>`
+            Some(previous) if merges(&self.source[previous.span.start..previous.span.end], text) => {
+                self.output.push(' ');
+            }
+            Some(_) => {}
+        }
+        self.output.push_str(text);
+
+        if let Some(open) = opens {
+            if open == Open::Block && code_before.is_some_and(|token| token.token_type == TokenType::Do) {
+                self.do_bodies.push(self.open.len());
+            }
+            self.open.push(open);
+        }
+        let closes_do_body = closes == Some(Open::Block) && self.do_bodies.last() == Some(&self.open.len());
+        if closes_do_body {
+            self.do_bodies.pop();
+        }
+        self.unary_last = matches!(token.token_type, TokenType::Minus | TokenType::Not) && !ends_operand(code_before);
+        let next = next_code.map(|token| &token.token_type);
+        self.newline_after = match token.token_type {
+            TokenType::Comment => true,
+            TokenType::Semicolon => !self.open.contains(&Open::Group),
+            TokenType::LeftBrace => opens == Some(Open::Block) && next != Some(&TokenType::RightBrace),
+            TokenType::RightBrace => {
+                closes == Some(Open::Block)
+                    && !(closes_do_body && next == Some(&TokenType::While))
+                    && !matches!(
+                        next,
+                        Some(
+                            TokenType::Else
+                                | TokenType::Catch
+                                | TokenType::RightParen
+                                | TokenType::RightBracket
+                                | TokenType::Comma
+                                | TokenType::Semicolon
+                                | TokenType::Dot
+                                | TokenType::LeftParen
+                                | TokenType::LeftBracket
+                        )
+                    )
+            }
+            _ => false,
+        };
+    }
+
+    /// Indents a new line by the blocks it is in. Code continued inside
+    /// brackets, after a comment, is indented once more, but a block's
+    /// closing brace lines up with the line that opened it.
+    fn indent(&mut self, closing_block: bool) {
+        let blocks = self.open.iter().filter(|open| **open == Open::Block).count();
+        let continued = !closing_block && self.open.last().is_some_and(|open| *open != Open::Block);
+        for _ in 0..blocks + usize::from(continued) {
+            self.output.push_str(INDENT);
+        }
+    }
+
+    /// The line `token` ends on, which is later than the one it starts on
+    /// for strings with newlines in them.
+    fn end_line(&self, token: &Token) -> usize {
+        token.line + self.source[token.span.start..token.span.end].matches('\n').count()
+    }
+
+    /// Whether a space goes between the code before `token` and `token`,
+    /// when they are on the same line.
+    fn space_before(&self, token: &Token, before: Option<&Token>, closes: Option<Open>) -> bool {
+        let Some(before) = before else {
+            return false;
+        };
+        if self.unary_last || closes.is_some() && closes != Some(Open::Block) {
+            return false;
+        }
+        match (&before.token_type, &token.token_type) {
+            (
+                _,
+                TokenType::Semicolon
+                | TokenType::Comma
+                | TokenType::Colon
+                | TokenType::Dot
+                | TokenType::DotDot
+                | TokenType::DotDotEqual
+            ) => false,
+            (
+                TokenType::LeftParen
+                | TokenType::LeftBracket
+                | TokenType::Dot
+                | TokenType::DotDot
+                | TokenType::DotDotEqual
+                | TokenType::Ellipsis,
+                _,
+            ) => false,
+            (TokenType::LeftBrace, TokenType::RightBrace) => false,
+            (TokenType::LeftBrace, _) if self.open.last() == Some(&Open::Map) => false,
+            // calls and indexing
+            (TokenType::Function, TokenType::LeftParen) => false,
+            (_, TokenType::LeftParen | TokenType::LeftBracket) if ends_operand(Some(before)) => false,
+            _ => true,
+        }
+    }
+}
+
+/// Whether `before` followed directly by `after` lexes as something other
+/// than those two tokens.
+fn merges(before: &str, after: &str) -> bool {
+    let joined = format!("{}{}", before, after);
+    match Lexer::new(&joined).tokenize() {
+        Ok(tokens) => tokens.len() != 3 || tokens[0].span.end != before.len(),
+        Err(_) => true,
+    }
+}
+
+/// Whether `token` can end an operand, so a `-` after it is subtraction and
+/// a `(` or `[` after it is a call or an index.
+fn ends_operand(token: Option<&Token>) -> bool {
+    token.is_some_and(|token| {
+        matches!(
+            token.token_type,
+            TokenType::Identifier
+                | TokenType::This
+                | TokenType::Number
+                | TokenType::String
+                | TokenType::True
+                | TokenType::False
+                | TokenType::Null
+                | TokenType::RightParen
+                | TokenType::RightBracket
+                | TokenType::RightBrace
+        )
+    })
+}
+
+/// Whether a `{` after `before` starts a map literal rather than a block,
+/// which is when an expression is expected there.
+fn opens_map(before: Option<&Token>) -> bool {
+    before.is_some_and(|token| {
+        matches!(
+            token.token_type,
+            TokenType::Assign
+                | TokenType::PlusAssign
+                | TokenType::MinusAssign
+                | TokenType::MultiplyAssign
+                | TokenType::DivideAssign
+                | TokenType::ModuloAssign
+                | TokenType::Let
+                | TokenType::Const
+                | TokenType::LeftParen
+                | TokenType::LeftBracket
+                | TokenType::Comma
+                | TokenType::Colon
+                | TokenType::Return
+                | TokenType::Throw
+                | TokenType::In
+                | TokenType::DotDot
+                | TokenType::DotDotEqual
+                | TokenType::Plus
+                | TokenType::Minus
+                | TokenType::Multiply
+                | TokenType::Divide
+                | TokenType::Modulo
+                | TokenType::Equal
+                | TokenType::NotEqual
+                | TokenType::Less
+                | TokenType::LessEqual
+                | TokenType::Greater
+                | TokenType::GreaterEqual
+                | TokenType::Not
+                | TokenType::TypeOf
+                | TokenType::And
+                | TokenType::Or
+                | TokenType::PipeGreater
+        )
+    })
+}
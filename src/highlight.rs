@@ -0,0 +1,175 @@
+use crate::lexer::{Lexer, TokenType};
+
+/// What a stretch of source is, for coloring it in an editor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HighlightKind {
+    Keyword,
+    Identifier,
+    Number,
+    String,
+    Comment,
+    Operator,
+    Punctuation,
+    Whitespace,
+    // characters no token starts with
+    Error,
+}
+
+impl HighlightKind {
+    /// The kind's name in lowercase, which `highlight_html` uses as the
+    /// class of its spans.
+    pub fn name(&self) -> &'static str {
+        match self {
+            HighlightKind::Keyword => "keyword",
+            HighlightKind::Identifier => "identifier",
+            HighlightKind::Number => "number",
+            HighlightKind::String => "string",
+            HighlightKind::Comment => "comment",
+            HighlightKind::Operator => "operator",
+            HighlightKind::Punctuation => "punctuation",
+            HighlightKind::Whitespace => "whitespace",
+            HighlightKind::Error => "error",
+        }
+    }
+
+    fn of(token_type: &TokenType) -> HighlightKind {
+        match token_type {
+            TokenType::Number => HighlightKind::Number,
+            TokenType::String => HighlightKind::String,
+            TokenType::Identifier => HighlightKind::Identifier,
+            TokenType::Comment => HighlightKind::Comment,
+            TokenType::Whitespace => HighlightKind::Whitespace,
+            TokenType::Plus
+            | TokenType::Minus
+            | TokenType::Multiply
+            | TokenType::Divide
+            | TokenType::Modulo
+            | TokenType::Assign
+            | TokenType::PlusAssign
+            | TokenType::MinusAssign
+            | TokenType::MultiplyAssign
+            | TokenType::DivideAssign
+            | TokenType::ModuloAssign
+            | TokenType::Equal
+            | TokenType::NotEqual
+            | TokenType::Less
+            | TokenType::LessEqual
+            | TokenType::Greater
+            | TokenType::GreaterEqual
+            | TokenType::Not
+            | TokenType::And
+            | TokenType::Or
+            | TokenType::PipeGreater
+            | TokenType::FatArrow
+            | TokenType::DotDot
+            | TokenType::DotDotEqual
+            | TokenType::Ellipsis => HighlightKind::Operator,
+            TokenType::Semicolon
+            | TokenType::Colon
+            | TokenType::Comma
+            | TokenType::Dot
+            | TokenType::LeftParen
+            | TokenType::RightParen
+            | TokenType::LeftBrace
+            | TokenType::RightBrace
+            | TokenType::LeftBracket
+            | TokenType::RightBracket => HighlightKind::Punctuation,
+            TokenType::Let
+            | TokenType::Const
+            | TokenType::If
+            | TokenType::Else
+            | TokenType::While
+            | TokenType::Do
+            | TokenType::For
+            | TokenType::Function
+            | TokenType::Return
+            | TokenType::Break
+            | TokenType::Continue
+            | TokenType::In
+            | TokenType::Import
+            | TokenType::As
+            | TokenType::Export
+            | TokenType::Match
+            | TokenType::Class
+            | TokenType::This
+            | TokenType::Try
+            | TokenType::Catch
+            | TokenType::Throw
+            | TokenType::TypeOf
+            | TokenType::True
+            | TokenType::False
+            | TokenType::Null => HighlightKind::Keyword,
+            // covers no source, so `highlight` leaves it out
+            TokenType::EOF => HighlightKind::Whitespace,
+        }
+    }
+}
+
+/// A stretch of source from byte `start` up to `end`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HighlightSpan {
+    pub start: usize,
+    pub end: usize,
+    pub kind: HighlightKind,
+}
+
+/// Classifies every byte of `source`, comments and whitespace included, in
+/// spans that are in order, don't overlap, and never split a character.
+/// This never fails: characters the lexer rejects become `Error` spans,
+/// and a string with a bad escape or no closing quote is still a string.
+pub fn highlight(source: &str) -> Vec<HighlightSpan> {
+    let (tokens, _) = Lexer::with_comments(source).tokenize_all();
+    let mut spans: Vec<HighlightSpan> = Vec::new();
+    let mut position = 0;
+    for token in &tokens {
+        // between tokens there is only whitespace and characters the lexer
+        // left out, each run of which becomes one span
+        for (offset, ch) in source[position..token.span.start].char_indices() {
+            let start = position + offset;
+            let kind = if ch.is_whitespace() { HighlightKind::Whitespace } else { HighlightKind::Error };
+            match spans.last_mut() {
+                Some(last) if last.end == start && last.kind == kind => last.end = start + ch.len_utf8(),
+                _ => spans.push(HighlightSpan { start, end: start + ch.len_utf8(), kind }),
+            }
+        }
+        if token.token_type != TokenType::EOF {
+            spans.push(HighlightSpan {
+                start: token.span.start,
+                end: token.span.end,
+                kind: HighlightKind::of(&token.token_type),
+            });
+        }
+        position = token.span.end;
+    }
+    spans
+}
+
+/// `source` as HTML, with each span but whitespace in a
+/// `<span class="kind">`, named by `HighlightKind::name`. Meant to go
+/// inside a `<pre>`.
+pub fn highlight_html(source: &str) -> String {
+    let mut html = String::with_capacity(source.len() * 2);
+    for span in highlight(source) {
+        let text = escape_html(&source[span.start..span.end]);
+        if span.kind == HighlightKind::Whitespace {
+            html.push_str(&text);
+        } else {
+            html.push_str(&format!("<span class=\"{}\">{}</span>", span.kind.name(), text));
+        }
+    }
+    html
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,2389 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use indexmap::IndexMap;
+
+use crate::allocation::Allocations;
+use crate::ast::{
+    BinaryOp, ClassDecl, Expr, ExprKind, Exprs, FunctionDecl, LogicalOp, Pattern, Program, Stmt, StmtKind, UnaryOp,
+};
+use crate::builtins::{self, BuiltinSet, RegexCache, Rng};
+use crate::clock::{Clock, SystemClock};
+use crate::codes::{self, Code};
+use crate::compiler;
+use crate::coverage::{Coverage, FileCoverage, coverable_lines};
+#[cfg(feature = "debugger")]
+use crate::debugger::DebugHook;
+use crate::environment::{AssignError, Environment};
+use crate::error::Error;
+use crate::lexer::{Lexer, LexerConfig, Span};
+use crate::optimizer;
+use crate::parser::Parser;
+use crate::profile::{FnStats, Profiler};
+use crate::printer::json_string;
+use crate::report::Report;
+use crate::source::{SourceFile, SourceMap};
+use crate::symbol::Symbol;
+use crate::value::{Arity, Class, Function, Instance, IntOverflow, Module, NativeFunction, Value, compare_int};
+use crate::vm::ChunkCache;
+
+/// How deep function calls may nest before the interpreter gives up, so
+/// runaway recursion is reported instead of overflowing the Rust stack,
+/// unless `InterpreterConfig::stack_size` says how much stack there is.
+/// Each call can take several kilobytes, and far more in a debug build.
+const MAX_CALL_DEPTH: usize = 200;
+
+/// How many steps pass between checks of the wall clock, which is far more
+/// expensive than bumping the step counter. Calls to builtins and native
+/// functions, which can take any amount of time in a single step, check
+/// it as soon as they return.
+const CLOCK_CHECK_INTERVAL: u64 = 1024;
+
+/// How the interpreter executes programs. Both backends produce the same
+/// output, results, and runtime errors.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Backend {
+    // evaluate the syntax tree directly
+    #[default]
+    TreeWalker,
+    // compile to bytecode and run it on a stack machine
+    Vm,
+}
+
+/// Limits for running untrusted scripts. A step is one executed statement
+/// or one loop iteration. The limits on time and memory are off by default,
+/// and scripts can't touch files or environment variables unless they are
+/// allowed to. Other builtins can be turned off one by one with `builtins`.
+#[derive(Debug, Clone, Default)]
+pub struct InterpreterConfig {
+    pub max_steps: Option<u64>,
+    pub max_duration: Option<Duration>,
+    // the longest string, in bytes, and the longest array a program can
+    // make
+    pub max_string_len: Option<usize>,
+    pub max_array_len: Option<usize>,
+    // roughly how many values can be held in arrays and maps at once, each
+    // array or map counting as one plus its length
+    pub max_total_values: Option<usize>,
+    // whether `read_file`, `write_file`, and `file_exists` work
+    pub allow_fs: bool,
+    // whether `env` works
+    pub allow_env: bool,
+    // which builtins can be called, all of them by default
+    pub builtins: BuiltinSet,
+    // where time builtins read the time from, `SystemClock` if unset
+    pub clock: Option<Rc<dyn Clock>>,
+    // where to write a line for each statement run, if anywhere; only the
+    // tree-walking backend traces
+    pub trace: Option<Trace>,
+    // optional syntax to accept in programs and the files they import
+    pub lexer: LexerConfig,
+    // what integer arithmetic and integer literals out of the range of an
+    // `i64` give
+    pub int_overflow: IntOverflow,
+    // how big the stack of the thread running programs is, if known, in
+    // bytes; calls may then nest until they've used half of it, leaving
+    // the rest for what the innermost call does, instead of stopping at
+    // `MAX_CALL_DEPTH`
+    pub stack_size: Option<usize>,
+}
+
+/// Where `InterpreterConfig::trace` sends its lines: for each statement,
+/// where it is, the first line of its source, and for a `let` or an
+/// assignment the value it stores, like `main.slime:3:1: let x = 5; -> 5`.
+#[derive(Clone)]
+pub struct Trace {
+    output: Rc<RefCell<dyn Write>>,
+    limit: Option<usize>,
+}
+
+impl Trace {
+    pub fn new(output: impl Write + 'static) -> Self {
+        Trace {
+            output: Rc::new(RefCell::new(output)),
+            limit: None,
+        }
+    }
+
+    /// Stops tracing after `limit` lines, though the program goes on.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+}
+
+impl fmt::Debug for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Trace").field("limit", &self.limit).finish_non_exhaustive()
+    }
+}
+
+/// Where a program's output goes: the lines `print` writes, and the prompt
+/// `input()` shows before reading. Any `Write` is one, getting the text as
+/// a terminal would show it. A host that wants each line apart, e.g. to
+/// send it on as a message, can implement it instead.
+pub trait Output {
+    /// Writes one line `print` made, without a line ending.
+    fn line(&mut self, line: &str) -> io::Result<()>;
+
+    /// Shows the prompt `input()` was given, just before it reads a line.
+    fn prompt(&mut self, prompt: &str) -> io::Result<()>;
+}
+
+impl<W: Write> Output for W {
+    fn line(&mut self, line: &str) -> io::Result<()> {
+        writeln!(self, "{}", line)
+    }
+
+    fn prompt(&mut self, prompt: &str) -> io::Result<()> {
+        write!(self, "{}", prompt)?;
+        self.flush()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RuntimeError {
+    pub code: &'static Code,
+    pub message: String,
+    pub span: Span,
+    // the calls the error unwound through, innermost first
+    pub trace: Vec<TraceFrame>,
+    // the value of a `throw`, which `catch` binds as it is, or for an
+    // `E0512` error, the code given to `exit()`
+    pub thrown: Option<Value>,
+}
+
+/// How one `test` block went in `Interpreter::run_tests`.
+#[derive(Debug, Clone)]
+pub struct TestResult {
+    pub name: String,
+    pub span: Span,
+    // what ended the test early, if it failed
+    pub error: Option<RuntimeError>,
+}
+
+impl TestResult {
+    pub fn passed(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// How a program that `Interpreter::run` ran without an error ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Completion {
+    /// It ran to its end.
+    Finished,
+    /// It called `exit(code)`. The host decides what that means; the
+    /// interpreter never ends the process itself.
+    Exit(i32),
+}
+
+/// One function call in a runtime error's stack trace.
+#[derive(Debug, Clone)]
+pub struct TraceFrame {
+    pub function: String,
+    // where the function was called from
+    pub span: Span,
+}
+
+impl RuntimeError {
+    /// An error with the catch-all `E0500` code, like one from a function
+    /// the host registered.
+    pub fn new(message: impl Into<String>, span: Span) -> Self {
+        RuntimeError::with_code(&codes::RUNTIME_ERROR, message, span)
+    }
+
+    pub fn with_code(code: &'static Code, message: impl Into<String>, span: Span) -> Self {
+        RuntimeError {
+            code,
+            message: message.into(),
+            span,
+            trace: Vec::new(),
+            thrown: None,
+        }
+    }
+
+    /// The error `throw value` raises, whose message is the value as
+    /// `print` shows it.
+    pub fn thrown(value: Value, span: Span) -> Self {
+        RuntimeError {
+            thrown: Some(value.clone()),
+            ..RuntimeError::with_code(&codes::UNCAUGHT_THROW, value.to_string(), span)
+        }
+    }
+
+    /// What `exit(code)` raises to stop the program, which `run` returns
+    /// as `Completion::Exit`.
+    pub(crate) fn exit(code: i32, span: Span) -> Self {
+        RuntimeError {
+            thrown: Some(Value::Int(code.into())),
+            ..RuntimeError::with_code(&codes::EXIT, format!("exit({})", code), span)
+        }
+    }
+
+    /// The code given to `exit()`, if that's what raised the error. It
+    /// isn't really an error, but it unwinds the program like one.
+    pub fn exit_code(&self) -> Option<i32> {
+        match &self.thrown {
+            Some(Value::Int(code)) if self.code == &codes::EXIT => Some(*code as i32),
+            _ => None,
+        }
+    }
+
+    /// Whether `catch` can stop the error. Running out of the execution
+    /// budget can't be caught, or a script could carry on past its limits,
+    /// and neither can `exit()`, which must end the program wherever it's
+    /// called.
+    pub(crate) fn catchable(&self) -> bool {
+        self.code != &codes::BUDGET_EXCEEDED && self.code != &codes::EXIT
+    }
+
+    /// What `catch` binds for the error: the thrown value, or for an error
+    /// raised by the interpreter, a map of its `message`, `code`, `line`,
+    /// and `column`.
+    pub(crate) fn caught_value(self) -> Value {
+        if let Some(value) = self.thrown {
+            return value;
+        }
+        let mut map = IndexMap::new();
+        map.insert("message".to_string(), Value::String(Rc::from(self.message.as_str())));
+        map.insert("code".to_string(), Value::String(Rc::from(self.code.id)));
+        map.insert("line".to_string(), Value::Int(self.span.line as i64));
+        map.insert("column".to_string(), Value::Int(self.span.column as i64));
+        Value::map(map)
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // errors raised by host code outside any script have no position
+        if self.span == Span::default() {
+            write!(f, "{}", self.message)?;
+        } else {
+            write!(f, "{} at line {}, column {}", self.message, self.span.line, self.span.column)?;
+        }
+        for line in self.trace_lines() {
+            write!(f, "\n    {}", line)?;
+        }
+        Ok(())
+    }
+}
+
+impl RuntimeError {
+    /// The calls in the trace as lines like `in f() called at line 3,
+    /// column 5`, innermost first. A call made again and again from the
+    /// same place, as by deep recursion, is shown once, with how many more
+    /// times it was repeated.
+    pub fn trace_lines(&self) -> Vec<String> {
+        self.trace_lines_in(&SourceMap::default())
+    }
+
+    /// Like `trace_lines`, but naming the file each call was made from when
+    /// it's in `sources`, as in `in f() called at line 3, column 5 of
+    /// lib.slime`.
+    pub fn trace_lines_in(&self, sources: &SourceMap) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut frames = self.trace.iter().peekable();
+        while let Some(frame) = frames.next() {
+            let position = format!("line {}, column {}", frame.span.line, frame.span.column);
+            lines.push(match (frame.span == Span::default(), sources.get(frame.span.file)) {
+                (true, _) => format!("in {}() called from the host", frame.function),
+                (false, Some(file)) => format!("in {}() called at {} of {}", frame.function, position, file.name),
+                (false, None) => format!("in {}() called at {}", frame.function, position),
+            });
+            let mut repeated = 0;
+            while frames.next_if(|next| next.function == frame.function && next.span == frame.span).is_some() {
+                repeated += 1;
+            }
+            if repeated > 0 {
+                lines.push(format!("(the call above repeated {} more time{})", repeated, if repeated == 1 { "" } else { "s" }));
+            }
+        }
+        lines
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+/// An `import` that can't be loaded: the file is missing or unreadable, or
+/// it ends up importing itself.
+#[derive(Debug, Clone)]
+pub struct ImportError {
+    pub code: &'static Code,
+    pub message: String,
+    pub span: Span,
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at line {}, column {}", self.message, self.span.line, self.span.column)
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+/// A file loaded by an `import`.
+struct ImportedFile {
+    // the canonical path, which identifies the file however it was imported
+    path: PathBuf,
+    // the file's id in the interpreter's sources, named with its path
+    // relative to the program's
+    file: usize,
+    // `None` until the file has been read and parsed without errors
+    program: Option<Rc<Program>>,
+    // whether the file's statements have run in the global scope, which
+    // they only do once
+    executed: bool,
+    // the file as a module, once it has been imported as one
+    module: Option<Rc<Module>>,
+}
+
+/// How a statement finished: normally, or by unwinding with `return`,
+/// `break`, or `continue`. Loops stop `Break` and `Continue` without a
+/// label or with their own, and function calls stop `Return` and
+/// `TailCall`; the parser guarantees `break`/`continue` only appear inside
+/// a loop in the same function, and only name the label of one of the
+/// loops around them, so they never reach a call.
+pub(crate) enum Flow {
+    Normal,
+    Return(Value),
+    TailCall(TailCall),
+    Break(Option<Symbol>),
+    Continue(Option<Symbol>),
+}
+
+// whether a `break` or `continue` naming `target`, if anything, is for the
+// loop labelled `label` rather than one around it
+fn is_for_loop(target: &Option<Symbol>, label: &Option<Symbol>) -> bool {
+    target.is_none() || target == label
+}
+
+/// A script function call that is the whole value of a `return`. The call
+/// that returns it makes it in place of the returning function, so tail
+/// recursion doesn't add to the call depth.
+pub(crate) struct TailCall {
+    pub(crate) function: Rc<Function>,
+    pub(crate) arguments: Vec<Value>,
+    pub(crate) span: Span,
+}
+
+pub struct Interpreter {
+    // the builtins and the host's functions, in a scope of their own
+    // around the globals, so a script can declare a name of its own that
+    // shadows one of them
+    prelude: Rc<RefCell<Environment>>,
+    globals: Rc<RefCell<Environment>>,
+    pub(crate) environment: Rc<RefCell<Environment>>,
+    pub(crate) call_depth: usize,
+    // the address of the stack where the outermost call began, to measure
+    // how much the calls inside it use
+    stack_base: usize,
+    // where `input()` reads lines from and where `print` writes to
+    input: Box<dyn BufRead>,
+    output: Box<dyn Output>,
+    pub(crate) rng: Rng,
+    pub(crate) regexes: RegexCache,
+    // what `args()` returns, e.g. the command line after the script's name
+    pub(crate) args: Vec<String>,
+    pub(crate) config: InterpreterConfig,
+    backend: Backend,
+    chunks: ChunkCache,
+    // whether `eval` folds constant expressions before running
+    fold_constants: bool,
+    // every program given to `eval` and every file they import, so errors
+    // can be shown with their source and builtins can quote it
+    sources: SourceMap,
+    // where the programs given to `eval` are, so their imports can be found
+    script_path: Option<PathBuf>,
+    // the files imported so far
+    imports: Vec<ImportedFile>,
+    // steps taken and the time limit for the current `interpret` call
+    steps: u64,
+    deadline: Option<Instant>,
+    // how many lines have been traced, counted against the trace's limit
+    traced: usize,
+    // the arrays and maps made so far, if their number is limited
+    allocations: Allocations,
+    // the lines run so far, if coverage is on
+    coverage: Option<Coverage>,
+    // the calls timed so far, if profiling is on
+    profiler: Option<Profiler>,
+    // set while a `Debugger` runs a program
+    #[cfg(feature = "debugger")]
+    pub(crate) debugger: Option<Box<DebugHook>>,
+}
+
+impl Drop for Interpreter {
+    // the script's functions hold the scopes they were declared in, so the
+    // scopes are emptied to break the cycles and let both go
+    fn drop(&mut self) {
+        for file in &self.imports {
+            if let Some(module) = &file.module {
+                module.environment.borrow_mut().clear();
+            }
+        }
+        self.environment.borrow_mut().clear();
+        self.globals.borrow_mut().clear();
+        self.prelude.borrow_mut().clear();
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Interpreter::new()
+    }
+}
+
+impl Interpreter {
+    /// An interpreter reading from stdin and printing to stdout.
+    pub fn new() -> Self {
+        Interpreter::with_io(io::BufReader::new(io::stdin()), io::stdout())
+    }
+
+    /// An interpreter whose `input()` reads from `input` and whose `print`
+    /// writes to `output`, e.g. in-memory buffers when testing.
+    pub fn with_io(input: impl BufRead + 'static, output: impl Output + 'static) -> Self {
+        let mut prelude = Environment::new();
+        builtins::define_builtins(&mut prelude);
+        let prelude = Rc::new(RefCell::new(prelude));
+        let globals = Rc::new(RefCell::new(Environment::with_enclosing(Rc::clone(&prelude))));
+        Interpreter {
+            environment: Rc::clone(&globals),
+            prelude,
+            globals,
+            call_depth: 0,
+            stack_base: 0,
+            input: Box::new(input),
+            output: Box::new(output),
+            rng: Rng::from_time(),
+            regexes: RegexCache::default(),
+            args: Vec::new(),
+            config: InterpreterConfig::default(),
+            backend: Backend::default(),
+            chunks: ChunkCache::default(),
+            fold_constants: false,
+            sources: SourceMap::default(),
+            script_path: None,
+            imports: Vec::new(),
+            steps: 0,
+            deadline: None,
+            traced: 0,
+            allocations: Allocations::default(),
+            coverage: None,
+            profiler: None,
+            #[cfg(feature = "debugger")]
+            debugger: None,
+        }
+    }
+
+    /// Makes a Rust closure callable from scripts as the function `name`,
+    /// which like a builtin a script can shadow with a declaration of its
+    /// own. Errors it returns without a position are reported at the call.
+    pub fn register_fn(
+        &mut self,
+        name: &str,
+        arity: Arity,
+        function: impl Fn(&[Value]) -> Result<Value, RuntimeError> + 'static,
+    ) {
+        let function = NativeFunction {
+            name: name.to_string(),
+            arity,
+            function: Box::new(function),
+        };
+        self.prelude
+            .borrow_mut()
+            .define(name, Value::NativeFunction(Rc::new(function)));
+    }
+
+    /// Sends what `print` writes, and `input()`'s prompts, to `output`
+    /// instead.
+    pub fn with_output(mut self, output: impl Output + 'static) -> Self {
+        self.output = Box::new(output);
+        self
+    }
+
+    pub fn with_config(mut self, config: InterpreterConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Sets the strings scripts get from `args()`, which is empty otherwise.
+    pub fn with_args(mut self, args: Vec<String>) -> Self {
+        self.args = args;
+        self
+    }
+
+    /// Sets the path of the programs given to `run` and `eval`, which their
+    /// imports are found relative to. Without one, they are found relative
+    /// to the working directory.
+    pub fn with_script_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.script_path = Some(path.into());
+        self
+    }
+
+    pub fn with_backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Folds operations on literals in programs given to `run` and `eval`,
+    /// then removes the code that can never run, before running them. See
+    /// `optimizer::fold_constants` and `optimizer::eliminate_dead_code`.
+    pub fn with_constant_folding(mut self, enabled: bool) -> Self {
+        self.fold_constants = enabled;
+        self
+    }
+
+    /// Records which lines run, in programs given to `run` and `eval` and
+    /// the files they import, for `coverage` to report.
+    pub fn with_coverage(mut self, enabled: bool) -> Self {
+        self.coverage = enabled.then(Coverage::default);
+        self
+    }
+
+    /// How much of each program and imported file has run so far, in the
+    /// order they were loaded, or `None` unless `with_coverage` turned
+    /// coverage on. A file that doesn't parse has no lines to cover.
+    pub fn coverage(&self) -> Option<Vec<FileCoverage>> {
+        let coverage = self.coverage.as_ref()?;
+        let files = self.sources.iter().map(|(id, file)| {
+            let (tokens, _) = Lexer::in_file(&file.contents, id).with_config(self.config.lexer).tokenize_all();
+            let (program, _) = Parser::new(tokens).parse_all();
+            FileCoverage {
+                file: id,
+                name: file.name.clone(),
+                lines: coverable_lines(&program)
+                    .into_iter()
+                    .map(|line| (line, coverage.executed(id, line)))
+                    .collect(),
+            }
+        });
+        Some(files.collect())
+    }
+
+    /// Times every call of a script function, builtin, or native function,
+    /// for `take_profile` to report.
+    pub fn with_profiling(mut self, enabled: bool) -> Self {
+        self.profiler = enabled.then(Profiler::default);
+        self
+    }
+
+    /// How many times each function was called since profiling started or
+    /// the last `take_profile`, and how long the calls took, most time
+    /// spent in the function itself first. Empty unless `with_profiling`
+    /// turned profiling on.
+    pub fn take_profile(&mut self) -> Vec<FnStats> {
+        self.profiler.as_mut().map_or_else(Vec::new, Profiler::take)
+    }
+
+    /// The scope a program's top-level declarations are made in. The
+    /// builtins and functions from `register_fn` are in the scope around
+    /// it, so they're found through it but aren't among its `bindings`.
+    pub fn globals(&self) -> &Rc<RefCell<Environment>> {
+        &self.globals
+    }
+
+    /// The current value of the global variable `name`, e.g. a setting read
+    /// from a config script after `run`.
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        self.globals.borrow().get(name)
+    }
+
+    /// Calls the global function `name` (script-defined, builtin, or
+    /// native) with `arguments`, under the same execution budget as `run`.
+    pub fn call_function(&mut self, name: &str, arguments: &[Value]) -> Result<Value, RuntimeError> {
+        let callee = self
+            .get_global(name)
+            .ok_or_else(|| {
+                RuntimeError::with_code(&codes::UNDEFINED_NAME, format!("Undefined function '{}'", name), Span::default())
+            })?;
+        self.steps = 0;
+        self.deadline = self.config.max_duration.map(|limit| Instant::now() + limit);
+        self.call(callee, arguments.to_vec(), Span::default())
+    }
+
+    /// Lexes, parses, and runs `source` against this interpreter's globals,
+    /// so variables and functions from earlier calls are still defined.
+    pub fn run(&mut self, source: &str) -> Result<Completion, Error> {
+        match self.eval(source) {
+            Ok(_) => Ok(Completion::Finished),
+            Err(Error::Runtime(error)) if let Some(code) = error.exit_code() => Ok(Completion::Exit(code)),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Like `run`, but returns the value of the program's last statement if
+    /// it is an expression statement (`null` otherwise), e.g. for a REPL to
+    /// echo. A program that calls `exit()` returns a runtime error with its
+    /// `exit_code`. The program is named after the script path, or `<input>`
+    /// without one.
+    pub fn eval(&mut self, source: &str) -> Result<Value, Error> {
+        let name = match &self.script_path {
+            Some(path) => path.display().to_string(),
+            None => "<input>".to_string(),
+        };
+        self.eval_file(SourceFile::new(name, source))
+    }
+
+    /// Like `eval`, but for a program with its own name, e.g. `<stdin>`.
+    /// The file is added to `sources`, so the spans of errors in it, or in
+    /// functions it declares, can be looked up there long after it has run.
+    pub fn eval_file(&mut self, file: SourceFile) -> Result<Value, Error> {
+        let program = self.load(file)?;
+        Ok(self.execute_program(&program.statements, &program.exprs)?)
+    }
+
+    /// Runs a program parsed apart from the interpreter, such as by
+    /// `slimescript::parse_tokens`, in the global scope, loading the files
+    /// it imports first as `eval` does. Errors point into file 0, which
+    /// isn't in `sources`, so the caller has to render them with the
+    /// source it parsed.
+    pub fn eval_statements(&mut self, program: &Program) -> Result<Value, Error> {
+        self.load_program_imports(&program.statements, 0)?;
+        Ok(self.execute_program(&program.statements, &program.exprs)?)
+    }
+
+    /// Runs a file of tests: first its statements like `eval_file`, which
+    /// skips the `test` blocks, then each block in order, in a fresh scope
+    /// inside the global one. Every test starts from the globals as the
+    /// file's top level left them: a `let` in one test can't be seen by the
+    /// next, and after each test the globals get back their values, with
+    /// the arrays and maps in them copied as `copy()` would. A test that
+    /// fails doesn't stop the ones after it; only an error outside the
+    /// tests, or a call to `exit()` anywhere, does, which is returned
+    /// instead.
+    pub fn run_tests(&mut self, file: SourceFile) -> Result<Vec<TestResult>, Error> {
+        let program = self.load(file)?;
+        self.execute_program(&program.statements, &program.exprs)?;
+        // copied all at once, so globals sharing an array still share one
+        let (names, values): (Vec<Symbol>, Vec<Value>) = self.globals.borrow().bindings().unzip();
+        let saved = Value::array(values).deep_copy();
+        let mut results = Vec::new();
+        for statement in &program.statements {
+            let StmtKind::Test { name, body } = &statement.kind else {
+                continue;
+            };
+            let scope = Rc::new(RefCell::new(Environment::with_enclosing(Rc::clone(&self.globals))));
+            let previous = std::mem::replace(&mut self.environment, scope);
+            let outcome = self.execute_program(body, &program.exprs);
+            self.environment = previous;
+            let Value::Array(values) = saved.deep_copy() else {
+                unreachable!("a copy of an array is an array");
+            };
+            for (name, value) in names.iter().zip(values.borrow().iter()) {
+                self.globals.borrow_mut().replace(name.clone(), value.clone());
+            }
+            if let Err(error) = &outcome
+                && error.exit_code().is_some()
+            {
+                return Err(error.clone().into());
+            }
+            results.push(TestResult {
+                name: name.clone(),
+                span: statement.span,
+                error: outcome.err(),
+            });
+        }
+        Ok(results)
+    }
+
+    /// Adds `file` to the sources, parses it, and loads its imports, ready
+    /// to run.
+    fn load(&mut self, file: SourceFile) -> Result<Program, Error> {
+        let file = self.sources.add(file);
+        let source = &self.sources.get(file).expect("the file was just added").contents;
+        let tokens = Lexer::in_file(source, file).with_config(self.config.lexer).tokenize()?;
+        let mut program = Parser::new(tokens).with_int_overflow(self.config.int_overflow).parse()?;
+        if self.fold_constants {
+            optimizer::fold_constants(&mut program);
+            optimizer::eliminate_dead_code(&mut program);
+        }
+        self.load_program_imports(&program.statements, file)?;
+        Ok(program)
+    }
+
+    /// Loads the imports of a program's `statements`, which are in `file`,
+    /// with the script itself as the start of the chain of importers.
+    fn load_program_imports(&mut self, statements: &[Stmt], file: usize) -> Result<(), Error> {
+        let mut chain = Vec::new();
+        if let Some(path) = &self.script_path
+            && let Ok(canonical) = fs::canonicalize(path)
+        {
+            chain.push(canonical);
+        }
+        self.load_imports(statements, file, &mut chain)
+    }
+
+    /// The programs given to `eval` and the files they import, by the ids
+    /// in their spans, e.g. to render an error with the file it is in.
+    pub fn sources(&self) -> &SourceMap {
+        &self.sources
+    }
+
+    /// `error` as a report, with each call in its trace naming the file it
+    /// was made from, which may not be the file the error is in.
+    pub fn report(&self, error: &Error) -> Report {
+        let mut report = error.report();
+        if let Error::Runtime(error) = error {
+            report.notes = error.trace_lines_in(&self.sources);
+        }
+        report
+    }
+
+    /// The canonical paths of the files imported so far, including any
+    /// that failed to parse, e.g. to watch them for changes.
+    pub fn imported_paths(&self) -> impl Iterator<Item = &Path> {
+        self.imports.iter().map(|import| import.path.as_path())
+    }
+
+    /// Runs an already-parsed program in the global scope, returning the
+    /// value of its last statement if that is an expression statement. Its
+    /// imports must have been loaded by an earlier `eval`.
+    pub fn interpret(&mut self, program: &Program) -> Result<Value, RuntimeError> {
+        self.execute_program(&program.statements, &program.exprs)
+    }
+
+    /// The source text covered by `span`, if it is in a program that came
+    /// through `eval` or a file one imported.
+    pub(crate) fn source_text(&self, span: Span) -> Option<&str> {
+        self.sources.get(span.file)?.contents.get(span.start..span.end)
+    }
+
+    /// Loads the files imported by `statements`, which are in `file`, and
+    /// the files those import, so that none of them has to be read or
+    /// parsed once the program is running. `chain` is the files whose
+    /// imports are being loaded, outermost first, to catch a file that ends
+    /// up importing itself.
+    fn load_imports(&mut self, statements: &[Stmt], file: usize, chain: &mut Vec<PathBuf>) -> Result<(), Error> {
+        for statement in statements {
+            let StmtKind::Import { path, .. } = &statement.kind else {
+                continue;
+            };
+            let import_error = |code, message: String| ImportError { code, message, span: statement.span };
+            let resolved = self.resolve_import(path, file);
+            let name = resolved.display().to_string();
+            let cannot_import =
+                |error: io::Error| import_error(&codes::IMPORT_FAILED, format!("Cannot import '{}': {}", name, error));
+            let canonical = fs::canonicalize(&resolved).map_err(&cannot_import)?;
+            if let Some(position) = chain.iter().position(|path| *path == canonical) {
+                let cycle: Vec<String> = chain[position..].iter().chain([&canonical]).map(|path| shown_path(path)).collect();
+                let message = format!("Circular import: {}", cycle.join(" -> "));
+                return Err(import_error(&codes::CIRCULAR_IMPORT, message).into());
+            }
+
+            // the file is added before it is parsed, so errors in it can be
+            // shown with its source
+            let index = match self.imports.iter().position(|import| import.path == canonical) {
+                Some(index) => index,
+                None => {
+                    let source = fs::read_to_string(&canonical).map_err(&cannot_import)?;
+                    self.imports.push(ImportedFile {
+                        path: canonical.clone(),
+                        file: self.sources.add(SourceFile::new(name.clone(), source)),
+                        program: None,
+                        executed: false,
+                        module: None,
+                    });
+                    self.imports.len() - 1
+                }
+            };
+            let file = self.imports[index].file;
+            let program = match &self.imports[index].program {
+                Some(program) => Rc::clone(program),
+                None => {
+                    let source = &self.sources.get(file).expect("imported files are in the sources").contents;
+                    let tokens = Lexer::in_file(source, file).with_config(self.config.lexer).tokenize()?;
+                    let mut program = Parser::new(tokens).with_int_overflow(self.config.int_overflow).parse()?;
+                    if self.fold_constants {
+                        optimizer::fold_constants(&mut program);
+                        optimizer::eliminate_dead_code(&mut program);
+                    }
+                    let program = Rc::new(program);
+                    self.imports[index].program = Some(Rc::clone(&program));
+                    program
+                }
+            };
+            // a file loaded before may have failed to load its own imports
+            chain.push(canonical);
+            let loaded = self.load_imports(&program.statements, file, chain);
+            chain.pop();
+            loaded?;
+        }
+        Ok(())
+    }
+
+    /// Where `import "path"` in `file` points: `path` relative to the
+    /// directory of the file it is in.
+    fn resolve_import(&self, path: &str, file: usize) -> PathBuf {
+        let importer = match self.imports.iter().find(|import| import.file == file) {
+            Some(import) => Some(import.path.as_path()),
+            None => self.script_path.as_deref(),
+        };
+        match importer.and_then(Path::parent) {
+            Some(directory) => directory.join(path),
+            None => PathBuf::from(path),
+        }
+    }
+
+    /// The index in `imports` of the file `import "path"` at `span` loads,
+    /// with the program to run.
+    fn loaded_import(&self, path: &str, span: Span) -> Result<(usize, Rc<Program>), RuntimeError> {
+        let resolved = self.resolve_import(path, span.file);
+        fs::canonicalize(&resolved)
+            .ok()
+            .and_then(|canonical| self.imports.iter().position(|import| import.path == canonical))
+            .and_then(|index| Some((index, Rc::clone(self.imports[index].program.as_ref()?))))
+            .ok_or_else(|| {
+                let message = format!("'{}' was not loaded before running", path);
+                RuntimeError::with_code(&codes::IMPORT_FAILED, message, span)
+            })
+    }
+
+    /// Runs the statements of the file `import "path"` at `span` loads in
+    /// the global scope, unless they have run already.
+    pub(crate) fn import(&mut self, path: &str, span: Span) -> Result<(), RuntimeError> {
+        let (index, program) = self.loaded_import(path, span)?;
+        if self.imports[index].executed {
+            return Ok(());
+        }
+        self.imports[index].executed = true;
+        let previous = std::mem::replace(&mut self.environment, Rc::clone(&self.globals));
+        let result = self.execute_statements(&program.statements, &program.exprs);
+        self.environment = previous;
+        result.map(|_| ())
+    }
+
+    /// The module for the file `import "path" as alias` at `span` loads.
+    /// The first import runs the file's statements in a scope of their own,
+    /// inside the global one, and later ones get the same module.
+    pub(crate) fn import_module(&mut self, path: &str, span: Span) -> Result<Value, RuntimeError> {
+        let (index, program) = self.loaded_import(path, span)?;
+        if let Some(module) = &self.imports[index].module {
+            return Ok(Value::Module(Rc::clone(module)));
+        }
+        let environment = Rc::new(RefCell::new(Environment::with_enclosing(Rc::clone(&self.globals))));
+        let previous = std::mem::replace(&mut self.environment, Rc::clone(&environment));
+        let result = self.execute_statements(&program.statements, &program.exprs);
+        self.environment = previous;
+        result?;
+
+        let module = Rc::new(Module {
+            name: self.sources.get(self.imports[index].file).expect("imported files are in the sources").name.clone(),
+            environment,
+            exports: program
+                .statements
+                .iter()
+                .filter(|statement| matches!(statement.kind, StmtKind::Export(_)))
+                .flat_map(|statement| statement.without_export().declared_names())
+                .map(str::to_string)
+                .collect(),
+        });
+        self.imports[index].module = Some(Rc::clone(&module));
+        Ok(Value::Module(module))
+    }
+
+    fn execute_program(&mut self, statements: &[Stmt], exprs: &Exprs) -> Result<Value, RuntimeError> {
+        self.steps = 0;
+        self.deadline = self.config.max_duration.map(|limit| Instant::now() + limit);
+        self.execute_statements(statements, exprs)
+    }
+
+    /// Runs top-level statements in the current scope with the chosen
+    /// backend, returning the value of the last one if it is an expression
+    /// statement.
+    fn execute_statements(&mut self, statements: &[Stmt], exprs: &Exprs) -> Result<Value, RuntimeError> {
+        if self.backend == Backend::Vm {
+            let chunk = compiler::compile_statements(statements, exprs);
+            return match self.run_chunk(&chunk, Rc::clone(&self.environment))? {
+                Flow::Return(value) => Ok(value),
+                _ => unreachable!("only function bodies make tail calls"),
+            };
+        }
+
+        let mut last = Value::Null;
+        for statement in statements {
+            if let StmtKind::Expression(expr) = &statement.kind {
+                self.tick(statement.span)?;
+                if !is_assignment(expr) {
+                    self.trace(statement, None);
+                }
+                last = self.evaluate(expr, exprs)?;
+                if is_assignment(expr) {
+                    self.trace(statement, Some(&last));
+                }
+                continue;
+            }
+            last = Value::Null;
+            let flow = self.execute(statement, exprs)?;
+            if let Flow::Return(_) = self.finish_tail_call(flow)? {
+                // a top-level return just ends the program
+                break;
+            }
+        }
+        Ok(last)
+    }
+
+    /// Writes `line` and a newline to the output.
+    pub(crate) fn write_line(&mut self, line: &str) -> io::Result<()> {
+        self.output.line(line)
+    }
+
+    /// Writes `prompt` to the output, then reads one line of input without
+    /// its line ending. Returns `None` at end of input.
+    pub(crate) fn read_line(&mut self, prompt: &str) -> io::Result<Option<String>> {
+        self.output.prompt(prompt)?;
+
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        if line.ends_with('\n') {
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
+        Ok(Some(line))
+    }
+
+    /// Counts one step against the execution budget, failing at `span` once
+    /// the step or time limit has been exceeded.
+    pub(crate) fn tick(&mut self, span: Span) -> Result<(), RuntimeError> {
+        self.steps += 1;
+        if let Some(coverage) = &mut self.coverage {
+            coverage.record(span);
+        }
+        #[cfg(feature = "debugger")]
+        if self.debugger.is_some() {
+            self.debug(span);
+        }
+        if let Some(max_steps) = self.config.max_steps
+            && self.steps > max_steps
+        {
+            return Err(RuntimeError::with_code(
+                &codes::BUDGET_EXCEEDED,
+                format!("Execution budget exceeded: more than {} steps", max_steps),
+                span,
+            ));
+        }
+        if self.steps.is_multiple_of(CLOCK_CHECK_INTERVAL) {
+            self.check_deadline(span)?;
+        }
+        Ok(())
+    }
+
+    /// Fails at `span` if the time limit has passed.
+    fn check_deadline(&self, span: Span) -> Result<(), RuntimeError> {
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => Err(self.time_limit_error(span)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks `value`, which the operation at `span` just made, against the
+    /// allocation limits, and counts it towards `max_total_values` if it's
+    /// an array or map.
+    pub(crate) fn allocated(&mut self, value: &Value, span: Span) -> Result<(), RuntimeError> {
+        if !self.limits_allocation() {
+            return Ok(());
+        }
+        match value {
+            Value::String(s) => self.check_string_len(s.len(), span)?,
+            Value::Array(elements) => {
+                let length = elements.borrow().len();
+                self.check_array_len(length, span)?;
+            }
+            _ => {}
+        }
+        if let Some(limit) = self.config.max_total_values {
+            self.allocations.made(value);
+            if !self.allocations.within(limit) {
+                return Err(allocation_error(format!("more than {} values alive at once", limit), span));
+            }
+        }
+        Ok(())
+    }
+
+    /// Counts the arrays and maps inside `value`, which were all just made
+    /// with it, against `max_total_values`. For builtins like `copy()` that
+    /// return new containers nested in the new one `call` counts.
+    pub(crate) fn allocated_inside(&mut self, value: &Value, span: Span) -> Result<(), RuntimeError> {
+        if let Some(limit) = self.config.max_total_values {
+            self.allocations.made_inside(value);
+            if !self.allocations.within(limit) {
+                return Err(allocation_error(format!("more than {} values alive at once", limit), span));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the array or map `container` can take `added` more
+    /// values, before the operation at `span` puts them in.
+    pub(crate) fn check_growth(&mut self, container: &Value, added: usize, span: Span) -> Result<(), RuntimeError> {
+        if !self.limits_allocation() || added == 0 {
+            return Ok(());
+        }
+        if let Value::Array(elements) = container {
+            let length = elements.borrow().len();
+            self.check_array_len(length + added, span)?;
+        }
+        if let Some(limit) = self.config.max_total_values {
+            self.allocations.grew(added);
+            if !self.allocations.within(limit) {
+                return Err(allocation_error(format!("more than {} values alive at once", limit), span));
+            }
+        }
+        Ok(())
+    }
+
+    /// Fails at `span` if a string of `length` bytes would be too long, for
+    /// builtins to check before building one.
+    pub(crate) fn check_string_len(&self, length: usize, span: Span) -> Result<(), RuntimeError> {
+        match self.config.max_string_len {
+            Some(limit) if length > limit => {
+                Err(allocation_error(format!("a string of {} bytes is longer than {}", length, limit), span))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn check_array_len(&self, length: usize, span: Span) -> Result<(), RuntimeError> {
+        match self.config.max_array_len {
+            Some(limit) if length > limit => {
+                Err(allocation_error(format!("an array of {} elements is longer than {}", length, limit), span))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // whether any allocation limit is on, which is checked first so that
+    // programs without limits pay almost nothing
+    fn limits_allocation(&self) -> bool {
+        let config = &self.config;
+        config.max_string_len.is_some() || config.max_array_len.is_some() || config.max_total_values.is_some()
+    }
+
+    /// Writes the trace line for `stmt`, if tracing, with the value it
+    /// stores if it is a `let` or an assignment. Once the trace's limit is
+    /// reached, one last line says so.
+    fn trace(&mut self, stmt: &Stmt, value: Option<&Value>) {
+        let Some(trace) = &self.config.trace else {
+            return;
+        };
+        let line = match trace.limit {
+            Some(limit) if self.traced > limit => return,
+            Some(limit) if self.traced == limit => format!("trace stopped after {} lines", limit),
+            _ => {
+                let file = self.sources.get(stmt.span.file).map_or("<input>", |file| file.name.as_str());
+                let text = self.source_text(stmt.span).and_then(|text| text.lines().next()).unwrap_or("");
+                let mut line = format!("{}:{}:{}: {}", file, stmt.span.line, stmt.span.column, text.trim_end());
+                match value {
+                    // quoted, so an empty string or spaces still show
+                    Some(Value::String(s)) => line.push_str(&format!(" -> {}", json_string(s))),
+                    Some(value) => line.push_str(&format!(" -> {}", value)),
+                    None => {}
+                }
+                line
+            }
+        };
+        self.traced += 1;
+        // a sink that can't take the trace doesn't stop the program
+        let _ = writeln!(trace.output.borrow_mut(), "{}", line);
+    }
+
+    fn time_limit_error(&self, span: Span) -> RuntimeError {
+        let limit = self.config.max_duration.unwrap_or_default();
+        RuntimeError::with_code(
+            &codes::BUDGET_EXCEEDED,
+            format!("Execution budget exceeded: ran longer than {:?}", limit),
+            span,
+        )
+    }
+
+    pub(crate) fn clock(&self) -> &dyn Clock {
+        self.config.clock.as_deref().unwrap_or(&SystemClock)
+    }
+
+    /// Waits for `duration`, or fails at `span` after waiting out the rest
+    /// of the time limit if `duration` would run past it.
+    pub(crate) fn sleep(&mut self, duration: Duration, span: Span) -> Result<(), RuntimeError> {
+        let remaining = self
+            .deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        match remaining {
+            Some(remaining) if duration > remaining => {
+                self.clock().sleep(remaining);
+                Err(self.time_limit_error(span))
+            }
+            _ => {
+                self.clock().sleep(duration);
+                Ok(())
+            }
+        }
+    }
+
+    fn execute(&mut self, stmt: &Stmt, exprs: &Exprs) -> Result<Flow, RuntimeError> {
+        self.tick(stmt.span)?;
+        // statements that store a value are traced once it is known
+        let stores = match &stmt.kind {
+            StmtKind::Expression(expr) => is_assignment(expr),
+            StmtKind::Let { .. } | StmtKind::Destructure { .. } => true,
+            _ => false,
+        };
+        if !stores {
+            self.trace(stmt, None);
+        }
+        match &stmt.kind {
+            StmtKind::Expression(expr) => {
+                let value = self.evaluate(expr, exprs)?;
+                if stores {
+                    self.trace(stmt, Some(&value));
+                }
+            }
+            StmtKind::Let { name, initializer, constant, .. } => {
+                let value = match initializer {
+                    Some(expr) => self.evaluate(expr, exprs)?,
+                    None => Value::Null,
+                };
+                self.trace(stmt, Some(&value));
+                self.declare(name, value, *constant, stmt.span)?;
+            }
+            StmtKind::Destructure { pattern, initializer, constant } => {
+                let value = self.evaluate(initializer, exprs)?;
+                self.trace(stmt, Some(&value));
+                self.destructure(pattern, value, *constant, stmt.span)?;
+            }
+            StmtKind::Block(statements) => {
+                return self.execute_scoped(statements, exprs);
+            }
+            StmtKind::If { .. } => {
+                // an `else if` is an else holding just the next if, which is
+                // run here in a loop, as `execute` would run it, rather than
+                // by recursing once per arm
+                let mut arm = stmt;
+                while let StmtKind::If { condition, then_branch, else_branch } = &arm.kind {
+                    if self.evaluate(condition, exprs)?.is_truthy() {
+                        return self.execute_scoped(then_branch, exprs);
+                    }
+                    match else_branch.as_deref() {
+                        Some([next @ Stmt { kind: StmtKind::If { .. }, .. }]) => {
+                            self.tick(next.span)?;
+                            self.trace(next, None);
+                            arm = next;
+                        }
+                        Some(else_branch) => return self.execute_scoped(else_branch, exprs),
+                        None => break,
+                    }
+                }
+            }
+            StmtKind::DoWhile { body, condition, label } => loop {
+                self.tick(stmt.span)?;
+                match self.execute_scoped(body, exprs)? {
+                    Flow::Break(target) if is_for_loop(&target, label) => break,
+                    Flow::Normal => {}
+                    Flow::Continue(target) if is_for_loop(&target, label) => {}
+                    flow => return Ok(flow),
+                }
+                if !self.evaluate(condition, exprs)?.is_truthy() {
+                    break;
+                }
+            },
+            StmtKind::Match { subject, arms, default } => {
+                let subject = self.evaluate(subject, exprs)?;
+                for arm in arms {
+                    for value in &arm.values {
+                        if self.evaluate(value, exprs)? == subject {
+                            return self.execute_scoped(&arm.body, exprs);
+                        }
+                    }
+                }
+                if let Some(default) = default {
+                    return self.execute_scoped(default, exprs);
+                }
+            }
+            StmtKind::While { condition, body, label } => {
+                while self.evaluate(condition, exprs)?.is_truthy() {
+                    // an empty body executes no statements, so count the
+                    // iteration itself
+                    self.tick(stmt.span)?;
+                    match self.execute_scoped(body, exprs)? {
+                        Flow::Break(target) if is_for_loop(&target, label) => break,
+                        Flow::Normal => {}
+                        Flow::Continue(target) if is_for_loop(&target, label) => {}
+                        flow => return Ok(flow),
+                    }
+                }
+            }
+            StmtKind::For { .. } => {
+                // the initializer's variable lives in a scope of its own
+                let scope = Environment::with_enclosing(Rc::clone(&self.environment));
+                let previous = std::mem::replace(&mut self.environment, Rc::new(RefCell::new(scope)));
+                let result = self.execute_for(stmt, exprs);
+                self.environment = previous;
+                return result;
+            }
+            StmtKind::ForIn { variable, iterable, body, label } => {
+                return self.execute_for_in(variable, iterable, body, label.clone(), exprs);
+            }
+            StmtKind::Function(declaration) => {
+                let function = self.closure(declaration);
+                self.declare(&declaration.name, function, false, stmt.span)?;
+            }
+            StmtKind::Class(declaration) => {
+                let class = self.class(declaration);
+                self.declare(&declaration.name, class, false, stmt.span)?;
+            }
+            StmtKind::Return(value) => {
+                let value = match value {
+                    Some(Expr { kind: ExprKind::Call { callee, arguments }, span }) => {
+                        let callee = self.evaluate(&exprs[*callee], exprs)?;
+                        let arguments = self.evaluate_arguments(arguments, exprs)?;
+                        if let Value::Function(function) = callee {
+                            return Ok(Flow::TailCall(TailCall { function, arguments, span: *span }));
+                        }
+                        self.call(callee, arguments, *span)?
+                    }
+                    Some(expr) => self.evaluate(expr, exprs)?,
+                    None => Value::Null,
+                };
+                return Ok(Flow::Return(value));
+            }
+            StmtKind::Try { body, name, handler } => {
+                // a tail call out of the try block is made here, so that
+                // catch sees its errors
+                let result = self.execute_scoped(body, exprs).and_then(|flow| self.finish_tail_call(flow));
+                match result {
+                    Err(error) if error.catchable() => {
+                        let mut scope = Environment::with_enclosing(Rc::clone(&self.environment));
+                        scope.define(name, error.caught_value());
+                        return self.execute_block(handler, Rc::new(RefCell::new(scope)), exprs);
+                    }
+                    result => return result,
+                }
+            }
+            StmtKind::Throw(value) => {
+                let value = self.evaluate(value, exprs)?;
+                return Err(RuntimeError::thrown(value, stmt.span));
+            }
+            StmtKind::Break(label) => return Ok(Flow::Break(label.clone())),
+            StmtKind::Continue(label) => return Ok(Flow::Continue(label.clone())),
+            StmtKind::Import { path, alias: None } => self.import(path, stmt.span)?,
+            StmtKind::Import { path, alias: Some(alias) } => {
+                let module = self.import_module(path, stmt.span)?;
+                self.declare(alias, module, true, stmt.span)?;
+            }
+            StmtKind::Export(declaration) => return self.execute(declaration, exprs),
+            // only `run_tests` runs them
+            StmtKind::Test { .. } => {}
+        }
+        Ok(Flow::Normal)
+    }
+
+    /// Whether a call made now would nest too deep: `MAX_CALL_DEPTH` calls
+    /// deep, or with a known `stack_size`, once the calls have used half of
+    /// it. The stack is taken to grow down, as it does on every target
+    /// supported.
+    fn call_too_deep(&mut self) -> bool {
+        let Some(size) = self.config.stack_size else {
+            return self.call_depth >= MAX_CALL_DEPTH;
+        };
+        let marker = 0u8;
+        let here = std::hint::black_box(&marker) as *const u8 as usize;
+        if self.call_depth == 0 {
+            self.stack_base = here;
+        }
+        self.stack_base.saturating_sub(here) > size / 2
+    }
+
+    /// Declares `name` in the current scope. Declaring the same name twice in
+    /// one scope is an error, since it's almost always a mistyped assignment.
+    pub(crate) fn declare(
+        &mut self,
+        name: impl Into<Symbol>,
+        value: Value,
+        constant: bool,
+        span: Span,
+    ) -> Result<(), RuntimeError> {
+        let name = name.into();
+        let result = self.environment.borrow_mut().declare(&name, value, constant, span);
+        result.map_err(|previous| {
+            let message = match previous {
+                Some(previous) => format!(
+                    "'{}' is already declared in this scope (previous declaration at line {}, column {})",
+                    name, previous.line, previous.column
+                ),
+                None => format!("'{}' is already declared in this scope", name),
+            };
+            RuntimeError::with_code(&codes::ALREADY_DECLARED, message, span)
+        })
+    }
+
+    /// Declares the names in `pattern` in the current scope, bound to the
+    /// parts of `value` they match.
+    pub(crate) fn destructure(
+        &mut self,
+        pattern: &Pattern,
+        value: Value,
+        constant: bool,
+        span: Span,
+    ) -> Result<(), RuntimeError> {
+        match pattern {
+            Pattern::Name { name, span } => self.declare(name, value, constant, *span),
+            Pattern::Array { elements, rest } => {
+                let Value::Array(items) = &value else {
+                    return Err(RuntimeError::with_code(
+                        &codes::WRONG_TYPE,
+                        format!("Cannot destructure {} as an array", value.type_name()),
+                        span,
+                    ));
+                };
+                let items = items.borrow().clone();
+                if items.len() < elements.len() {
+                    return Err(RuntimeError::with_code(
+                        &codes::INDEX_OUT_OF_BOUNDS,
+                        format!(
+                            "Cannot destructure array of length {} into {} elements",
+                            items.len(),
+                            elements.len()
+                        ),
+                        span,
+                    ));
+                }
+                let rest_items = items[elements.len()..].to_vec();
+                for (element, item) in elements.iter().zip(items) {
+                    self.destructure(element, item, constant, span)?;
+                }
+                match rest {
+                    Some((name, name_span)) => self.declare(name, Value::array(rest_items), constant, *name_span),
+                    None => Ok(()),
+                }
+            }
+            Pattern::Map(entries) => {
+                let Value::Map(map) = &value else {
+                    return Err(RuntimeError::with_code(
+                        &codes::WRONG_TYPE,
+                        format!("Cannot destructure {} as a map", value.type_name()),
+                        span,
+                    ));
+                };
+                for (key, entry) in entries {
+                    let item = map.borrow().get(key).cloned().ok_or_else(|| {
+                        RuntimeError::with_code(
+                            &codes::MISSING_KEY,
+                            format!("Cannot destructure missing key '{}'", key),
+                            span,
+                        )
+                    })?;
+                    self.destructure(entry, item, constant, span)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn execute_for(&mut self, stmt: &Stmt, exprs: &Exprs) -> Result<Flow, RuntimeError> {
+        let StmtKind::For { initializer, condition, increment, body, label } = &stmt.kind else {
+            unreachable!("only a for loop is run as one");
+        };
+        if let Some(initializer) = initializer {
+            self.execute(initializer, exprs)?;
+        }
+        loop {
+            if let Some(condition) = condition
+                && !self.evaluate(condition, exprs)?.is_truthy()
+            {
+                break;
+            }
+            self.tick(stmt.span)?;
+            match self.execute_scoped(body, exprs)? {
+                Flow::Break(target) if is_for_loop(&target, label) => break,
+                Flow::Normal => {}
+                // `continue` still runs the increment clause
+                Flow::Continue(target) if is_for_loop(&target, label) => {}
+                flow => return Ok(flow),
+            }
+            if let Some(increment) = increment {
+                self.evaluate(increment, exprs)?;
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    fn execute_for_in(
+        &mut self,
+        variable: &str,
+        iterable: &Expr,
+        body: &[Stmt],
+        label: Option<Symbol>,
+        exprs: &Exprs,
+    ) -> Result<Flow, RuntimeError> {
+        if let ExprKind::Range { start, end, inclusive } = &iterable.kind {
+            let (start, end) = (&exprs[*start], &exprs[*end]);
+            let start = range_bound(self.evaluate(start, exprs)?, start.span)?;
+            let end = range_bound(self.evaluate(end, exprs)?, end.span)? + if *inclusive { 1.0 } else { 0.0 };
+            let mut i = start;
+            while i < end {
+                self.tick(iterable.span)?;
+                match self.execute_iteration(variable, Value::whole(i), body, exprs)? {
+                    Flow::Break(target) if is_for_loop(&target, &label) => break,
+                    Flow::Normal => {}
+                    Flow::Continue(target) if is_for_loop(&target, &label) => {}
+                    flow => return Ok(flow),
+                }
+                i += 1.0;
+            }
+            return Ok(Flow::Normal);
+        }
+
+        let items = iteration_items(self.evaluate(iterable, exprs)?, iterable.span)?;
+        for item in items {
+            self.tick(iterable.span)?;
+            match self.execute_iteration(variable, item, body, exprs)? {
+                Flow::Break(target) if is_for_loop(&target, &label) => break,
+                Flow::Normal => {}
+                Flow::Continue(target) if is_for_loop(&target, &label) => {}
+                flow => return Ok(flow),
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    /// Runs one pass of a for-in body with a fresh binding of the loop
+    /// variable, so closures made in different iterations don't share it.
+    fn execute_iteration(
+        &mut self,
+        variable: &str,
+        item: Value,
+        body: &[Stmt],
+        exprs: &Exprs,
+    ) -> Result<Flow, RuntimeError> {
+        let mut scope = Environment::with_enclosing(Rc::clone(&self.environment));
+        scope.define(variable, item);
+        self.execute_block(body, Rc::new(RefCell::new(scope)), exprs)
+    }
+
+    /// Runs `statements` in a new scope nested inside the current one.
+    fn execute_scoped(&mut self, statements: &[Stmt], exprs: &Exprs) -> Result<Flow, RuntimeError> {
+        let scope = Environment::with_enclosing(Rc::clone(&self.environment));
+        self.execute_block(statements, Rc::new(RefCell::new(scope)), exprs)
+    }
+
+    /// Runs `statements` with `environment` as the current scope, restoring
+    /// the previous scope afterwards even if a statement fails.
+    fn execute_block(
+        &mut self,
+        statements: &[Stmt],
+        environment: Rc<RefCell<Environment>>,
+        exprs: &Exprs,
+    ) -> Result<Flow, RuntimeError> {
+        let previous = std::mem::replace(&mut self.environment, environment);
+        let mut result = Ok(Flow::Normal);
+        for statement in statements {
+            match self.execute(statement, exprs) {
+                Ok(Flow::Normal) => {}
+                other => {
+                    result = other;
+                    break;
+                }
+            }
+        }
+        self.environment = previous;
+        result
+    }
+
+    fn evaluate(&mut self, expr: &Expr, exprs: &Exprs) -> Result<Value, RuntimeError> {
+        match &expr.kind {
+            ExprKind::Number(n) => Ok(Value::Number(*n)),
+            ExprKind::Int(n) => Ok(Value::Int(*n)),
+            ExprKind::String(s) => Ok(Value::String(Rc::from(s.as_str()))),
+            ExprKind::Bool(b) => Ok(Value::Bool(*b)),
+            ExprKind::Null => Ok(Value::Null),
+            ExprKind::Variable(name) => self.get_variable(name, expr.span),
+            ExprKind::Assign { name, operator, value } => {
+                let value = match operator {
+                    Some(operator) => {
+                        let current = self.get_variable(name, expr.span)?;
+                        let value = self.evaluate(&exprs[*value], exprs)?;
+                        self.compound(*operator, current, value, expr.span)?
+                    }
+                    None => self.evaluate(&exprs[*value], exprs)?,
+                };
+                self.assign(name, value.clone(), expr.span)?;
+                Ok(value)
+            }
+            ExprKind::Unary { operator, operand } => {
+                let operand = self.evaluate(&exprs[*operand], exprs)?;
+                unary_operation(*operator, operand, self.config.int_overflow, expr.span)
+            }
+            ExprKind::Binary { .. }
+            | ExprKind::Logical { .. }
+            | ExprKind::Call { .. }
+            | ExprKind::Index { .. }
+            | ExprKind::Slice { .. }
+            | ExprKind::Member { .. } => self.evaluate_chain(expr, exprs),
+            ExprKind::Array(elements) => {
+                let mut values = Vec::with_capacity(elements.len());
+                for element in elements {
+                    values.push(self.evaluate(element, exprs)?);
+                }
+                let array = Value::array(values);
+                self.allocated(&array, expr.span)?;
+                Ok(array)
+            }
+            ExprKind::SetIndex { object, index, operator, value } => {
+                let object = self.evaluate(&exprs[*object], exprs)?;
+                let index = self.evaluate(&exprs[*index], exprs)?;
+                let value = match operator {
+                    Some(operator) => {
+                        let current = get_index(&object, &index, expr.span)?;
+                        let value = self.evaluate(&exprs[*value], exprs)?;
+                        self.compound(*operator, current, value, expr.span)?
+                    }
+                    None => self.evaluate(&exprs[*value], exprs)?,
+                };
+                let added = adds_entry(&object, &index) as usize;
+                self.check_growth(&object, added, expr.span)?;
+                set_index(&object, &index, value.clone(), expr.span)?;
+                Ok(value)
+            }
+            ExprKind::Map(entries) => {
+                let mut map = IndexMap::with_capacity(entries.len());
+                for (key, value) in entries {
+                    let value = self.evaluate(value, exprs)?;
+                    map.insert(key.clone(), value);
+                }
+                let map = Value::map(map);
+                self.allocated(&map, expr.span)?;
+                Ok(map)
+            }
+            ExprKind::Function(declaration) => Ok(self.closure(declaration)),
+            ExprKind::This => self.get_variable("this", expr.span),
+            ExprKind::Range { .. } => Err(RuntimeError::with_code(
+                &codes::INVALID_RANGE,
+                "Ranges can only be used as the iterable of a for-in loop",
+                expr.span,
+            )),
+            ExprKind::SetMember { object, name, operator, value } => {
+                let object = self.evaluate(&exprs[*object], exprs)?;
+                let value = match operator {
+                    Some(operator) => {
+                        let current = get_member(&object, name, expr.span)?;
+                        let value = self.evaluate(&exprs[*value], exprs)?;
+                        self.compound(*operator, current, value, expr.span)?
+                    }
+                    None => self.evaluate(&exprs[*value], exprs)?,
+                };
+                let added = adds_entry(&object, &Value::String(name.as_str().into())) as usize;
+                self.check_growth(&object, added, expr.span)?;
+                set_member(&object, name, value.clone(), expr.span)?;
+                Ok(value)
+            }
+        }
+    }
+
+    /// Evaluates an operation whose first operand can be another such
+    /// operation: a chain like `a + b + c`, `a && b`, or `f(x)[0].y`, which
+    /// leans left. The chain is walked down its left side to the operand
+    /// that starts it, then applied link by link in a loop rather than by
+    /// recursing, so a chain as long as the parser allows doesn't overflow
+    /// the stack.
+    fn evaluate_chain(&mut self, expr: &Expr, exprs: &Exprs) -> Result<Value, RuntimeError> {
+        let mut chain = Vec::new();
+        let mut leftmost = expr;
+        while let Some(operand) = chained_operand(leftmost, exprs) {
+            chain.push(leftmost);
+            leftmost = operand;
+        }
+        let mut value = self.evaluate(leftmost, exprs)?;
+        for link in chain.into_iter().rev() {
+            let span = link.span;
+            value = match &link.kind {
+                ExprKind::Binary { operator, right, .. } => {
+                    let right = self.evaluate(&exprs[*right], exprs)?;
+                    let result = binary_operation(*operator, value, right, self.config.int_overflow, span)?;
+                    self.allocated(&result, span)?;
+                    result
+                }
+                // `&&` and `||` short-circuit and produce whichever operand
+                // decided the result (Lua/JS style) rather than a strict
+                // bool: `a && b` is `a` when `a` is falsey, otherwise `b`;
+                // `a || b` is `a` when `a` is truthy, otherwise `b`. The
+                // right operand is never evaluated when the left one decides.
+                ExprKind::Logical { operator, right, .. } => {
+                    let decided = match operator {
+                        LogicalOp::And => !value.is_truthy(),
+                        LogicalOp::Or => value.is_truthy(),
+                    };
+                    if decided { value } else { self.evaluate(&exprs[*right], exprs)? }
+                }
+                ExprKind::Call { arguments, .. } => {
+                    let arguments = self.evaluate_arguments(arguments, exprs)?;
+                    self.call(value, arguments, span)?
+                }
+                ExprKind::Index { index, .. } => {
+                    let index = self.evaluate(&exprs[*index], exprs)?;
+                    get_index(&value, &index, span)?
+                }
+                ExprKind::Slice { start, end, inclusive, .. } => {
+                    let start = match start {
+                        Some(start) => self.evaluate(&exprs[*start], exprs)?,
+                        None => Value::Null,
+                    };
+                    let end = match end {
+                        Some(end) => self.evaluate(&exprs[*end], exprs)?,
+                        None => Value::Null,
+                    };
+                    get_slice(&value, &start, &end, *inclusive, span)?
+                }
+                ExprKind::Member { name, .. } => get_member(&value, name, span)?,
+                _ => unreachable!("only links of a chain were collected"),
+            };
+        }
+        Ok(value)
+    }
+
+    // the new value of a compound assignment's target, `current` being its
+    // value before
+    fn compound(&mut self, operator: BinaryOp, current: Value, value: Value, span: Span) -> Result<Value, RuntimeError> {
+        let result = compound_operation(operator, current, value, self.config.int_overflow, span)?;
+        self.allocated(&result, span)?;
+        Ok(result)
+    }
+
+    fn evaluate_arguments(&mut self, arguments: &[Expr], exprs: &Exprs) -> Result<Vec<Value>, RuntimeError> {
+        let mut values = Vec::with_capacity(arguments.len());
+        for argument in arguments {
+            values.push(self.evaluate(argument, exprs)?);
+        }
+        Ok(values)
+    }
+
+    /// Makes the tail call `flow` ended with, if it did, as an ordinary call
+    /// that returns from here instead of from the caller.
+    fn finish_tail_call(&mut self, flow: Flow) -> Result<Flow, RuntimeError> {
+        match flow {
+            Flow::TailCall(TailCall { function, arguments, span }) => {
+                Ok(Flow::Return(self.call(Value::Function(function), arguments, span)?))
+            }
+            flow => Ok(flow),
+        }
+    }
+
+    pub(crate) fn get_variable(&self, name: impl Into<Symbol>, span: Span) -> Result<Value, RuntimeError> {
+        let name = name.into();
+        self.environment
+            .borrow()
+            .get(&name)
+            .ok_or_else(|| {
+                RuntimeError::with_code(&codes::UNDEFINED_NAME, format!("Undefined variable '{}'", name), span)
+            })
+    }
+
+    /// Assigns to the nearest existing binding of `name`.
+    pub(crate) fn assign(&mut self, name: impl Into<Symbol>, value: Value, span: Span) -> Result<(), RuntimeError> {
+        let name = name.into();
+        let result = self.environment.borrow_mut().assign(&name, value);
+        result.map_err(|error| {
+            let message = match error {
+                AssignError::Undefined => {
+                    let message = format!("Undefined variable '{}'", name);
+                    return RuntimeError::with_code(&codes::UNDEFINED_NAME, message, span);
+                }
+                AssignError::Constant(Some(declared_at)) => format!(
+                    "Cannot assign to constant '{}' (declared at line {}, column {})",
+                    name, declared_at.line, declared_at.column
+                ),
+                AssignError::Constant(None) => format!("Cannot assign to constant '{}'", name),
+            };
+            RuntimeError::with_code(&codes::ASSIGN_TO_CONSTANT, message, span)
+        })
+    }
+
+    /// A function value for `declaration` that closes over the current scope.
+    pub(crate) fn closure(&self, declaration: &Rc<FunctionDecl>) -> Value {
+        Value::Function(Rc::new(Function {
+            declaration: Rc::clone(declaration),
+            closure: Rc::clone(&self.environment),
+        }))
+    }
+
+    /// A class value for `declaration` whose methods close over the current
+    /// scope.
+    pub(crate) fn class(&self, declaration: &Rc<ClassDecl>) -> Value {
+        Value::Class(Rc::new(Class {
+            declaration: Rc::clone(declaration),
+            closure: Rc::clone(&self.environment),
+        }))
+    }
+
+    /// Calls any kind of function value, checking its arity and recording
+    /// the call in the stack trace of any error it raises.
+    pub(crate) fn call(&mut self, callee: Value, arguments: Vec<Value>, span: Span) -> Result<Value, RuntimeError> {
+        let (name, arity) = match &callee {
+            Value::Function(function) => (function.declaration.name.as_str(), function.declaration.arity()),
+            Value::Builtin(builtin) => (builtin.name, builtin.arity),
+            Value::NativeFunction(function) => (function.name.as_str(), function.arity),
+            // a class takes the arguments of its `init` method
+            Value::Class(class) => {
+                let init = class.declaration.method("init");
+                (class.declaration.name.as_str(), init.map_or(Arity::Exact(0), |init| init.arity()))
+            }
+            other => {
+                return Err(RuntimeError::with_code(
+                    &codes::WRONG_TYPE,
+                    format!("Can only call functions, not {}", other.type_name()),
+                    span,
+                ));
+            }
+        };
+
+        if let Value::Builtin(builtin) = &callee
+            && !self.config.builtins.allows(builtin.name)
+        {
+            let message = format!("builtin '{}' is not available in this environment", builtin.name);
+            return Err(RuntimeError::with_code(&codes::BUILTIN_UNAVAILABLE, message, span));
+        }
+        check_arity(name, arity, arguments.len(), span)?;
+        if self.call_too_deep() {
+            return Err(RuntimeError::with_code(&codes::RECURSION_LIMIT, "Maximum recursion depth exceeded", span));
+        }
+
+        if let Some(profiler) = &mut self.profiler {
+            let declared = match &callee {
+                Value::Function(function) => Some(function.declaration.span),
+                Value::Class(class) => Some(class.declaration.span),
+                _ => None,
+            };
+            profiler.enter(name, declared);
+        }
+        self.call_depth += 1;
+        let result = match &callee {
+            Value::Function(function) => self.call_script(function, arguments),
+            Value::Builtin(builtin) => (builtin.function)(self, &arguments, span).and_then(|result| {
+                self.allocated(&result, span)?;
+                Ok(result)
+            }),
+            Value::Class(class) => self.instantiate(class, arguments),
+            Value::NativeFunction(function) => (function.function)(&arguments).map_err(|mut error| {
+                if error.span == Span::default() {
+                    error.span = span;
+                }
+                error
+            }),
+            _ => unreachable!("non-functions are rejected above"),
+        };
+        self.call_depth -= 1;
+        // one call to a builtin can take far longer than the steps between
+        // checks of the clock, e.g. splitting a string of megabytes
+        let result = match &callee {
+            Value::Builtin(_) | Value::NativeFunction(_) => result.and_then(|value| {
+                self.check_deadline(span)?;
+                Ok(value)
+            }),
+            _ => result,
+        };
+        if let Some(profiler) = &mut self.profiler {
+            profiler.exit();
+        }
+
+        result.map_err(|mut error| {
+            // a builtin failing at its own call site (e.g. a bad argument)
+            // adds nothing beyond the error's position
+            let raised_here = matches!(callee, Value::Builtin(_)) && error.span == span && error.trace.is_empty();
+            if !raised_here {
+                error.trace.push(TraceFrame {
+                    function: name.to_string(),
+                    span,
+                });
+            }
+            error
+        })
+    }
+
+    /// Runs a script function, then each tail call it ends with in turn.
+    /// Only the latest of a chain of tail calls shows in a stack trace,
+    /// since the frames of the functions making them are gone.
+    fn call_script(&mut self, function: &Function, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+        let mut flow = self.run_function(function, arguments)?;
+        while let Flow::TailCall(TailCall { function, arguments, span }) = flow {
+            let name = function.declaration.name.as_str();
+            check_arity(name, function.declaration.arity(), arguments.len(), span)?;
+            // the function tail calling is done, and its caller is now
+            // calling this one
+            if let Some(profiler) = &mut self.profiler {
+                profiler.exit();
+                profiler.enter(name, Some(function.declaration.span));
+            }
+            flow = self.run_function(&function, arguments).map_err(|mut error| {
+                error.trace.push(TraceFrame {
+                    function: name.to_string(),
+                    span,
+                });
+                error
+            })?;
+        }
+        match flow {
+            Flow::Return(value) => Ok(value),
+            _ => Ok(Value::Null),
+        }
+    }
+
+    /// Binds a script function's arguments and runs its body, which may end
+    /// with a tail call left for `call_script` to make.
+    fn run_function(&mut self, function: &Function, arguments: Vec<Value>) -> Result<Flow, RuntimeError> {
+        let declaration = &function.declaration;
+        let mut scope = Environment::with_enclosing(Rc::clone(&function.closure));
+        let provided = arguments.len();
+        let mut arguments = arguments.into_iter();
+        // parameters left out are bound to their defaults by the function
+        // body's own code, since defaults may read the parameters before them
+        for param in &declaration.params {
+            let argument = if param.rest {
+                Value::array(arguments.by_ref().collect())
+            } else if let Some(argument) = arguments.next() {
+                argument
+            } else {
+                continue;
+            };
+            // the parser rejects duplicate parameters, so this can't clash
+            let _ = scope.declare(&param.name, argument, false, param.span);
+        }
+
+        let scope = Rc::new(RefCell::new(scope));
+        match self.backend {
+            Backend::TreeWalker => {
+                let previous = std::mem::replace(&mut self.environment, Rc::clone(&scope));
+                let defaults = self.bind_defaults(declaration, provided);
+                self.environment = previous;
+                defaults?;
+                self.execute_block(&declaration.body, scope, &declaration.exprs)
+            }
+            Backend::Vm => {
+                let chunk = self.chunks.get(declaration);
+                self.run_chunk(&chunk, scope)
+            }
+        }
+    }
+
+    /// A new instance of `class`, with `init` run on it if the class has
+    /// one. Whatever `init` returns is ignored.
+    fn instantiate(&mut self, class: &Rc<Class>, arguments: Vec<Value>) -> Result<Value, RuntimeError> {
+        let instance = Rc::new(Instance {
+            class: Rc::clone(class),
+            fields: RefCell::new(IndexMap::new()),
+        });
+        if let Some(init) = class.bind("init", &instance) {
+            self.call_script(&init, arguments)?;
+        }
+        Ok(Value::Instance(instance))
+    }
+
+    /// Binds the parameters after the first `provided`, which a call left
+    /// out, to their defaults in the current scope, in order.
+    fn bind_defaults(&mut self, declaration: &FunctionDecl, provided: usize) -> Result<(), RuntimeError> {
+        for param in declaration.params.iter().skip(provided) {
+            if let Some(default) = &param.default {
+                let value = self.evaluate(default, &declaration.exprs)?;
+                self.declare(&param.name, value, false, param.span)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks that a function taking `arity` arguments can be called with
+/// `count` of them.
+fn check_arity(name: &str, arity: Arity, count: usize, span: Span) -> Result<(), RuntimeError> {
+    if !arity.accepts(count) {
+        return Err(RuntimeError::with_code(
+            &codes::WRONG_ARGUMENT_COUNT,
+            format!("{}() expected {} but got {}", name, arity.describe(), count),
+            span,
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that `index` is a whole number within `0..length` of an array
+/// or string, whose length counts characters. Negative indices are an
+/// error rather than counting from the end.
+fn element_index(index: &Value, length: usize, of: &str, span: Span) -> Result<usize, RuntimeError> {
+    let n = whole_number(index, "Index", span)?;
+    if n < 0.0 || n >= length as f64 {
+        return Err(RuntimeError::with_code(
+            &codes::INDEX_OUT_OF_BOUNDS,
+            format!("Index {} out of bounds for {} of length {}", index, of, length),
+            span,
+        ));
+    }
+    Ok(n as usize)
+}
+
+/// `index` as a number, if it is a whole one; `what` names it in errors.
+fn whole_number(index: &Value, what: &str, span: Span) -> Result<f64, RuntimeError> {
+    match index {
+        Value::Number(n) if n.fract() == 0.0 => Ok(*n),
+        Value::Int(n) => Ok(*n as f64),
+        Value::Number(n) => Err(RuntimeError::with_code(
+            &codes::WRONG_TYPE,
+            format!("{} must be a whole number, got {}", what, n),
+            span,
+        )),
+        other => Err(RuntimeError::with_code(
+            &codes::WRONG_TYPE,
+            format!("{} must be a number, got {}", what, other.type_name()),
+            span,
+        )),
+    }
+}
+
+pub(crate) fn unary_operation(
+    operator: UnaryOp,
+    operand: Value,
+    overflow: IntOverflow,
+    span: Span,
+) -> Result<Value, RuntimeError> {
+    match (operator, &operand) {
+        (UnaryOp::Negate, Value::Number(n)) => Ok(Value::Number(-n)),
+        // only the most negative integer has no opposite in range
+        (UnaryOp::Negate, Value::Int(n)) => match n.checked_neg() {
+            Some(n) => Ok(Value::Int(n)),
+            None => overflow
+                .overflowed(n.wrapping_neg(), -(*n as f64))
+                .ok_or_else(|| overflow_error(format!("-({})", n), span)),
+        },
+        (UnaryOp::Not, value) => Ok(Value::Bool(!value.is_truthy())),
+        (UnaryOp::TypeOf, value) => Ok(Value::String(value.type_name().into())),
+        _ => Err(RuntimeError::with_code(
+            &codes::WRONG_TYPE,
+            format!("Cannot apply '{}' to {}", operator.symbol(), operand.type_name()),
+            span,
+        )),
+    }
+}
+
+/// `object[index]` for an array, map, or string, where a string gives the
+/// character at that position as a string of its own.
+pub(crate) fn get_index(object: &Value, index: &Value, span: Span) -> Result<Value, RuntimeError> {
+    match object {
+        Value::Array(elements) => {
+            let elements = elements.borrow();
+            let i = element_index(index, elements.len(), "array", span)?;
+            Ok(elements[i].clone())
+        }
+        Value::String(s) => {
+            let i = element_index(index, s.chars().count(), "string", span)?;
+            let c = s.chars().nth(i).expect("the index was checked against the length");
+            Ok(Value::String(c.to_string().into()))
+        }
+        Value::Map(entries) => {
+            let key = map_key(index, span)?;
+            map_get(&entries.borrow(), key, span)
+        }
+        _ => Err(RuntimeError::with_code(
+            &codes::WRONG_TYPE,
+            format!("Cannot index into {}", object.type_name()),
+            span,
+        )),
+    }
+}
+
+/// `object[index] = value` for an array or map.
+pub(crate) fn set_index(object: &Value, index: &Value, value: Value, span: Span) -> Result<(), RuntimeError> {
+    match object {
+        Value::Array(elements) => {
+            let mut elements = elements.borrow_mut();
+            let i = element_index(index, elements.len(), "array", span)?;
+            elements[i] = value;
+            Ok(())
+        }
+        Value::Map(entries) => {
+            let key = map_key(index, span)?;
+            entries.borrow_mut().insert(key.to_string(), value);
+            Ok(())
+        }
+        Value::String(_) => Err(RuntimeError::with_code(
+            &codes::WRONG_TYPE,
+            "Cannot assign to an index of a string: strings are immutable",
+            span,
+        )),
+        _ => Err(RuntimeError::with_code(
+            &codes::WRONG_TYPE,
+            format!("Cannot assign to an index of {}", object.type_name()),
+            span,
+        )),
+    }
+}
+
+/// `object[start..end]`, or `object[start..=end]` if `inclusive`, for an
+/// array or string: a new array or string of the elements or characters
+/// from `start` up to `end`. A null bound stands for one left out, so the
+/// slice runs from the beginning or to the end. Bounds past either end, or
+/// a start after the end, are errors rather than being clamped.
+pub(crate) fn get_slice(
+    object: &Value,
+    start: &Value,
+    end: &Value,
+    inclusive: bool,
+    span: Span,
+) -> Result<Value, RuntimeError> {
+    let length = match object {
+        Value::Array(elements) => elements.borrow().len(),
+        Value::String(s) => s.chars().count(),
+        _ => {
+            return Err(RuntimeError::with_code(
+                &codes::WRONG_TYPE,
+                format!("Cannot slice {}", object.type_name()),
+                span,
+            ));
+        }
+    };
+    let written = format!(
+        "{}{}{}",
+        slice_bound(start),
+        if inclusive { "..=" } else { ".." },
+        slice_bound(end)
+    );
+    let from = match start {
+        Value::Null => 0.0,
+        start => whole_number(start, "Slice start", span)?,
+    };
+    let to = match end {
+        Value::Null => length as f64,
+        end => whole_number(end, "Slice end", span)? + if inclusive { 1.0 } else { 0.0 },
+    };
+    if from < 0.0 || to > length as f64 {
+        return Err(RuntimeError::with_code(
+            &codes::INDEX_OUT_OF_BOUNDS,
+            format!("Slice {} out of bounds for {} of length {}", written, object.type_name(), length),
+            span,
+        ));
+    }
+    if from > to {
+        return Err(RuntimeError::with_code(
+            &codes::INDEX_OUT_OF_BOUNDS,
+            format!("Slice {} of {} of length {} starts after it ends", written, object.type_name(), length),
+            span,
+        ));
+    }
+    let (from, to) = (from as usize, to as usize);
+    match object {
+        Value::Array(elements) => Ok(Value::array(elements.borrow()[from..to].to_vec())),
+        Value::String(s) => Ok(Value::String(s.chars().skip(from).take(to - from).collect::<String>().into())),
+        _ => unreachable!("other types were rejected above"),
+    }
+}
+
+/// The operand an operation in a chain starts from, which is evaluated
+/// before anything else in it, if `expr` is such an operation.
+fn chained_operand<'a>(expr: &Expr, exprs: &'a Exprs) -> Option<&'a Expr> {
+    match &expr.kind {
+        ExprKind::Binary { left, .. } | ExprKind::Logical { left, .. } => Some(&exprs[*left]),
+        ExprKind::Call { callee, .. } => Some(&exprs[*callee]),
+        ExprKind::Index { object, .. } | ExprKind::Slice { object, .. } | ExprKind::Member { object, .. } => {
+            Some(&exprs[*object])
+        }
+        _ => None,
+    }
+}
+
+/// A slice bound as it was written, which is nothing if it was left out.
+fn slice_bound(bound: &Value) -> String {
+    match bound {
+        Value::Null => String::new(),
+        bound => bound.to_string(),
+    }
+}
+
+pub(crate) fn get_member(object: &Value, name: &str, span: Span) -> Result<Value, RuntimeError> {
+    match object {
+        Value::Map(entries) => map_get(&entries.borrow(), name, span),
+        Value::Module(module) if module.exports.iter().any(|export| export == name) => {
+            Ok(module.environment.borrow().get(name).unwrap_or(Value::Null))
+        }
+        Value::Module(module) => Err(RuntimeError::with_code(
+            &codes::UNDEFINED_NAME,
+            format!("Module '{}' has no export '{}'", module.name, name),
+            span,
+        )),
+        // fields come before methods, so a field can shadow a method
+        Value::Instance(instance) => {
+            if let Some(value) = instance.fields.borrow().get(name) {
+                return Ok(value.clone());
+            }
+            match instance.class.bind(name, instance) {
+                Some(method) => Ok(Value::Function(method)),
+                None => Err(RuntimeError::with_code(
+                    &codes::MISSING_KEY,
+                    format!("{} instance has no field or method '{}'", instance.class.declaration.name, name),
+                    span,
+                )),
+            }
+        }
+        _ => Err(RuntimeError::with_code(
+            &codes::WRONG_TYPE,
+            format!("Cannot read property '{}' of {}", name, object.type_name()),
+            span,
+        )),
+    }
+}
+
+pub(crate) fn set_member(object: &Value, name: &str, value: Value, span: Span) -> Result<(), RuntimeError> {
+    match object {
+        Value::Map(entries) => {
+            entries.borrow_mut().insert(name.to_string(), value);
+            Ok(())
+        }
+        Value::Instance(instance) => {
+            instance.fields.borrow_mut().insert(name.to_string(), value);
+            Ok(())
+        }
+        _ => Err(RuntimeError::with_code(
+            &codes::WRONG_TYPE,
+            format!("Cannot set property '{}' of {}", name, object.type_name()),
+            span,
+        )),
+    }
+}
+
+/// The items a for-in loop visits. Arrays, maps, and strings are iterated
+/// over a snapshot taken when the loop starts, so changes the body makes to
+/// them don't affect which items are visited. Maps yield their keys and
+/// strings their characters.
+pub(crate) fn iteration_items(iterable: Value, span: Span) -> Result<Vec<Value>, RuntimeError> {
+    match iterable {
+        Value::Array(elements) => Ok(elements.borrow().clone()),
+        Value::Map(entries) => Ok(entries.borrow().keys().map(|key| Value::String(key.as_str().into())).collect()),
+        Value::String(s) => Ok(s.chars().map(|ch| Value::String(ch.to_string().into())).collect()),
+        other => Err(RuntimeError::with_code(
+            &codes::WRONG_TYPE,
+            format!("Cannot iterate over {}", other.type_name()),
+            span,
+        )),
+    }
+}
+
+pub(crate) fn range_bound(value: Value, span: Span) -> Result<f64, RuntimeError> {
+    match value {
+        Value::Number(n) if n.fract() == 0.0 => Ok(n),
+        Value::Int(n) => Ok(n as f64),
+        other => Err(RuntimeError::with_code(
+            &codes::INVALID_RANGE,
+            format!("Range bounds must be whole numbers, got {}", other),
+            span,
+        )),
+    }
+}
+
+fn map_key(key: &Value, span: Span) -> Result<&str, RuntimeError> {
+    match key {
+        Value::String(s) => Ok(s),
+        other => Err(RuntimeError::with_code(
+            &codes::WRONG_TYPE,
+            format!("Map key must be a string, got {}", other.type_name()),
+            span,
+        )),
+    }
+}
+
+/// Reads `key` from a map. A missing key is an error; scripts can check
+/// with `has(map, key)` first.
+fn map_get(entries: &IndexMap<String, Value>, key: &str, span: Span) -> Result<Value, RuntimeError> {
+    entries
+        .get(key)
+        .cloned()
+        .ok_or_else(|| RuntimeError::with_code(&codes::MISSING_KEY, format!("Key '{}' not found in map", key), span))
+}
+
+pub(crate) fn binary_operation(
+    operator: BinaryOp,
+    left: Value,
+    right: Value,
+    overflow: IntOverflow,
+    span: Span,
+) -> Result<Value, RuntimeError> {
+    match (operator, &left, &right) {
+        (BinaryOp::Equal, _, _) => Ok(Value::Bool(left == right)),
+        (BinaryOp::NotEqual, _, _) => Ok(Value::Bool(left != right)),
+
+        (BinaryOp::Add | BinaryOp::Subtract | BinaryOp::Multiply, Value::Int(a), Value::Int(b)) => {
+            integer_arithmetic(operator, *a, *b, overflow, span)
+        }
+        (BinaryOp::Divide | BinaryOp::Modulo, Value::Int(_), Value::Int(0)) => {
+            Err(RuntimeError::with_code(&codes::DIVISION_BY_ZERO, "Division by zero", span))
+        }
+        // `%` can't overflow, though Rust's checked remainder of the most
+        // negative integer by -1 says it does: the remainder is 0
+        (BinaryOp::Modulo, Value::Int(a), Value::Int(b)) => Ok(Value::Int(a.wrapping_rem(*b))),
+        (BinaryOp::Less, Value::Int(a), Value::Int(b)) => Ok(Value::Bool(a < b)),
+        (BinaryOp::LessEqual, Value::Int(a), Value::Int(b)) => Ok(Value::Bool(a <= b)),
+        (BinaryOp::Greater, Value::Int(a), Value::Int(b)) => Ok(Value::Bool(a > b)),
+        (BinaryOp::GreaterEqual, Value::Int(a), Value::Int(b)) => Ok(Value::Bool(a >= b)),
+
+        (BinaryOp::Add, Value::Number(a), Value::Number(b)) => Ok(Value::Number(a + b)),
+        (BinaryOp::Add, Value::String(a), Value::String(b)) => {
+            Ok(Value::String(Rc::from(format!("{}{}", a, b))))
+        }
+        (BinaryOp::Subtract, Value::Number(a), Value::Number(b)) => Ok(Value::Number(a - b)),
+        (BinaryOp::Multiply, Value::Number(a), Value::Number(b)) => Ok(Value::Number(a * b)),
+        (BinaryOp::Divide | BinaryOp::Modulo, Value::Number(_), Value::Number(b)) if *b == 0.0 => {
+            Err(RuntimeError::with_code(&codes::DIVISION_BY_ZERO, "Division by zero", span))
+        }
+        (BinaryOp::Divide, Value::Number(a), Value::Number(b)) => Ok(Value::Number(a / b)),
+        (BinaryOp::Modulo, Value::Number(a), Value::Number(b)) => Ok(Value::Number(a % b)),
+
+        (BinaryOp::Less, Value::Number(a), Value::Number(b)) => Ok(Value::Bool(a < b)),
+        (BinaryOp::LessEqual, Value::Number(a), Value::Number(b)) => Ok(Value::Bool(a <= b)),
+        (BinaryOp::Greater, Value::Number(a), Value::Number(b)) => Ok(Value::Bool(a > b)),
+        (BinaryOp::GreaterEqual, Value::Number(a), Value::Number(b)) => Ok(Value::Bool(a >= b)),
+        (BinaryOp::Less, Value::String(a), Value::String(b)) => Ok(Value::Bool(a < b)),
+        (BinaryOp::LessEqual, Value::String(a), Value::String(b)) => Ok(Value::Bool(a <= b)),
+        (BinaryOp::Greater, Value::String(a), Value::String(b)) => Ok(Value::Bool(a > b)),
+        (BinaryOp::GreaterEqual, Value::String(a), Value::String(b)) => Ok(Value::Bool(a >= b)),
+
+        (BinaryOp::In | BinaryOp::NotIn, _, Value::Array(elements)) => {
+            let found = elements.borrow().contains(&left);
+            Ok(Value::Bool(found == (operator == BinaryOp::In)))
+        }
+        (BinaryOp::In | BinaryOp::NotIn, Value::String(key), Value::Map(entries)) => {
+            let found = entries.borrow().contains_key(&**key);
+            Ok(Value::Bool(found == (operator == BinaryOp::In)))
+        }
+        (BinaryOp::In | BinaryOp::NotIn, Value::String(needle), Value::String(s)) => {
+            Ok(Value::Bool(s.contains(&**needle) == (operator == BinaryOp::In)))
+        }
+
+        // an integer and a fractional number compare exactly, since as
+        // floats the integer could round onto the other number
+        (BinaryOp::Less | BinaryOp::LessEqual | BinaryOp::Greater | BinaryOp::GreaterEqual, _, _)
+            if let Some(ordering) = mixed_ordering(&left, &right) =>
+        {
+            Ok(Value::Bool(match operator {
+                BinaryOp::Less => ordering.is_lt(),
+                BinaryOp::LessEqual => ordering.is_le(),
+                BinaryOp::Greater => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            }))
+        }
+        // dividing integers, or mixing one with a fractional number, works
+        // on them both as fractional numbers
+        (_, Value::Int(a), Value::Number(_) | Value::Int(_)) => {
+            binary_operation(operator, Value::Number(*a as f64), right, overflow, span)
+        }
+        (_, Value::Number(_), Value::Int(b)) => {
+            binary_operation(operator, left, Value::Number(*b as f64), overflow, span)
+        }
+
+        _ => Err(RuntimeError::with_code(
+            &codes::WRONG_TYPE,
+            format!(
+                "Cannot apply '{}' to {} and {}",
+                operator.symbol(),
+                left.type_name(),
+                right.type_name()
+            ),
+            span,
+        )),
+    }
+}
+
+// how an integer and a fractional number order, either way round, or
+// `None` for any other pair, or a NaN
+fn mixed_ordering(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Int(a), Value::Number(b)) => compare_int(*a, *b),
+        (Value::Number(a), Value::Int(b)) => compare_int(*b, *a).map(Ordering::reverse),
+        _ => None,
+    }
+}
+
+// `+`, `-`, or `*` on two integers, where a result out of range is what
+// `overflow` says
+fn integer_arithmetic(
+    operator: BinaryOp,
+    a: i64,
+    b: i64,
+    overflow: IntOverflow,
+    span: Span,
+) -> Result<Value, RuntimeError> {
+    let (exact, wrapped, promoted) = match operator {
+        BinaryOp::Add => (a.checked_add(b), a.wrapping_add(b), a as f64 + b as f64),
+        BinaryOp::Subtract => (a.checked_sub(b), a.wrapping_sub(b), a as f64 - b as f64),
+        BinaryOp::Multiply => (a.checked_mul(b), a.wrapping_mul(b), a as f64 * b as f64),
+        _ => unreachable!("only +, -, and * overflow"),
+    };
+    match exact {
+        Some(n) => Ok(Value::Int(n)),
+        None => overflow
+            .overflowed(wrapped, promoted)
+            .ok_or_else(|| overflow_error(format!("{} {} {}", a, operator.symbol(), b), span)),
+    }
+}
+
+fn overflow_error(operation: String, span: Span) -> RuntimeError {
+    RuntimeError::with_code(
+        &codes::INTEGER_OVERFLOW,
+        format!("Integer overflow: {} is out of range", operation),
+        span,
+    )
+}
+
+/// `binary_operation` for the compound assignment `target op= value`, whose
+/// type errors name the assignment's operator, such as `+=`.
+pub(crate) fn compound_operation(
+    operator: BinaryOp,
+    current: Value,
+    value: Value,
+    overflow: IntOverflow,
+    span: Span,
+) -> Result<Value, RuntimeError> {
+    let types = (current.type_name(), value.type_name());
+    binary_operation(operator, current, value, overflow, span).map_err(|error| {
+        if error.code == &codes::WRONG_TYPE {
+            RuntimeError::with_code(
+                &codes::WRONG_TYPE,
+                format!("Cannot apply '{}=' to {} and {}", operator.symbol(), types.0, types.1),
+                span,
+            )
+        } else {
+            error
+        }
+    })
+}
+
+fn allocation_error(detail: String, span: Span) -> RuntimeError {
+    RuntimeError::with_code(&codes::ALLOCATION_LIMIT, format!("allocation limit exceeded: {}", detail), span)
+}
+
+/// Whether a map `object` doesn't have the key `index` yet, so that setting
+/// it adds an entry.
+pub(crate) fn adds_entry(object: &Value, index: &Value) -> bool {
+    match (object, index) {
+        (Value::Map(entries), Value::String(key)) => !entries.borrow().contains_key(&**key),
+        _ => false,
+    }
+}
+
+/// Whether `expr`, as a statement of its own, stores a value, which
+/// tracing shows.
+fn is_assignment(expr: &Expr) -> bool {
+    matches!(expr.kind, ExprKind::Assign { .. } | ExprKind::SetIndex { .. } | ExprKind::SetMember { .. })
+}
+
+/// The canonical path `path` as an error shows it: relative to the current
+/// directory if it's inside it, or in full otherwise, so every file in a
+/// message is written the same way whatever path reached it.
+fn shown_path(path: &Path) -> String {
+    let relative = env::current_dir()
+        .and_then(fs::canonicalize)
+        .ok()
+        .and_then(|directory| path.strip_prefix(directory).ok().map(Path::to_path_buf));
+    relative.as_deref().unwrap_or(path).display().to_string()
+}
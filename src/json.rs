@@ -0,0 +1,458 @@
+use std::fmt;
+use std::rc::Rc;
+
+use indexmap::IndexMap;
+
+use crate::printer::json_string;
+use crate::value::{Value, format_number};
+
+// arrays and objects nested deeper than this are rejected rather than
+// parsed, since each level recurses
+const MAX_DEPTH: usize = 128;
+
+/// A JSON value, as read from or written to the language server's client,
+/// or by `json_parse()` and `json_stringify()`. Object members keep their
+/// order, and a repeated key keeps its last value, as in JavaScript's
+/// `JSON.parse`, at the place it first appeared.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    // a number written without a fraction or exponent that fits in an
+    // `i64`, kept exact
+    Int(i64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Parses `text` as a single JSON value, with nothing but whitespace
+    /// around it. The error says what was wrong and at which byte.
+    pub(crate) fn parse(text: &str) -> Result<Json, String> {
+        let mut parser = JsonParser { text, position: 0, depth: 0 };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.position < text.len() {
+            return Err(parser.error("Unexpected text after the value"));
+        }
+        Ok(value)
+    }
+
+    /// The JSON for `value`, or what makes it impossible: a function,
+    /// module, class, or instance, a number JSON can't hold, an array or
+    /// map that contains itself, or nesting deeper than can be parsed back.
+    pub(crate) fn from_value(value: &Value) -> Result<Json, String> {
+        Json::from_nested(value, &mut Vec::new())
+    }
+
+    // `open` holds the arrays and maps being converted, outermost first
+    fn from_nested(value: &Value, open: &mut Vec<*const ()>) -> Result<Json, String> {
+        let address = match value {
+            Value::Null => return Ok(Json::Null),
+            Value::Bool(b) => return Ok(Json::Bool(*b)),
+            Value::Number(n) if n.is_finite() => return Ok(Json::Number(*n)),
+            Value::Number(n) => return Err(format!("{} can't be written as JSON", n)),
+            Value::Int(n) => return Ok(Json::Int(*n)),
+            Value::String(s) => return Ok(Json::String(s.to_string())),
+            Value::Array(elements) => Rc::as_ptr(elements) as *const (),
+            Value::Map(entries) => Rc::as_ptr(entries) as *const (),
+            other => return Err(format!("{} can't be written as JSON", with_article(other.type_name()))),
+        };
+        if open.contains(&address) {
+            return Err(format!(
+                "{} that contains itself can't be written as JSON",
+                with_article(value.type_name())
+            ));
+        }
+        if open.len() == MAX_DEPTH {
+            return Err("a value nested this deeply can't be written as JSON".to_string());
+        }
+        open.push(address);
+        let json = match value {
+            Value::Array(elements) => Json::Array(
+                elements
+                    .borrow()
+                    .iter()
+                    .map(|element| Json::from_nested(element, open))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Map(entries) => Json::Object(
+                entries
+                    .borrow()
+                    .iter()
+                    .map(|(key, value)| Ok((key.clone(), Json::from_nested(value, open)?)))
+                    .collect::<Result<_, String>>()?,
+            ),
+            _ => unreachable!("only arrays and maps are opened"),
+        };
+        open.pop();
+        Ok(json)
+    }
+
+    /// The value a script sees: objects become maps, in the same order.
+    pub(crate) fn into_value(self) -> Value {
+        match self {
+            Json::Null => Value::Null,
+            Json::Bool(b) => Value::Bool(b),
+            Json::Number(n) => Value::Number(n),
+            Json::Int(n) => Value::Int(n),
+            Json::String(s) => Value::String(s.into()),
+            Json::Array(items) => Value::array(items.into_iter().map(Json::into_value).collect()),
+            Json::Object(members) => Value::map(
+                members
+                    .into_iter()
+                    .map(|(key, value)| (key, value.into_value()))
+                    .collect::<IndexMap<_, _>>(),
+            ),
+        }
+    }
+
+    /// Like `to_string`, but with each array element and object member on
+    /// its own line, indented two spaces per level. Empty arrays and
+    /// objects stay on one line.
+    pub(crate) fn pretty(&self) -> String {
+        let mut out = String::new();
+        self.write_pretty(&mut out, 0);
+        out
+    }
+
+    fn write_pretty(&self, out: &mut String, depth: usize) {
+        let (open, close, count) = match self {
+            Json::Array(items) if !items.is_empty() => ('[', ']', items.len()),
+            Json::Object(members) if !members.is_empty() => ('{', '}', members.len()),
+            other => return out.push_str(&other.to_string()),
+        };
+        out.push(open);
+        for i in 0..count {
+            if i > 0 {
+                out.push(',');
+            }
+            out.push('\n');
+            out.push_str(&"  ".repeat(depth + 1));
+            match self {
+                Json::Array(items) => items[i].write_pretty(out, depth + 1),
+                Json::Object(members) => {
+                    let (key, value) = &members[i];
+                    out.push_str(&json_string(key));
+                    out.push_str(": ");
+                    value.write_pretty(out, depth + 1);
+                }
+                _ => unreachable!(),
+            }
+        }
+        out.push('\n');
+        out.push_str(&"  ".repeat(depth));
+        out.push(close);
+    }
+
+    /// An object from its members, for building messages with fewer
+    /// `to_string` calls.
+    pub(crate) fn object<const N: usize>(members: [(&str, Json); N]) -> Json {
+        Json::Object(members.into_iter().map(|(key, value)| (key.to_string(), value)).collect())
+    }
+
+    /// The member `key` of an object, or `None` if this isn't an object or
+    /// has no such member.
+    pub(crate) fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members.iter().find(|(name, _)| name == key).map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_number(&self) -> Option<f64> {
+        match self {
+            Json::Number(n) => Some(*n),
+            Json::Int(n) => Some(*n as f64),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for Json {
+    fn from(s: &str) -> Self {
+        Json::String(s.to_string())
+    }
+}
+
+impl From<usize> for Json {
+    fn from(n: usize) -> Self {
+        Json::Number(n as f64)
+    }
+}
+
+// compact, with no whitespace between tokens
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            // JSON has no infinities or NaN
+            Json::Number(n) if !n.is_finite() => write!(f, "null"),
+            Json::Number(n) => write!(f, "{}", format_number(*n)),
+            Json::Int(n) => write!(f, "{}", n),
+            Json::String(s) => write!(f, "{}", json_string(s)),
+            Json::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(members) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}:{}", json_string(key), value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+// "an instance", "a map"
+fn with_article(noun: &str) -> String {
+    let article = if noun.starts_with(['a', 'e', 'i', 'o', 'u']) { "an" } else { "a" };
+    format!("{} {}", article, noun)
+}
+
+struct JsonParser<'a> {
+    text: &'a str,
+    position: usize,
+    depth: usize,
+}
+
+impl JsonParser<'_> {
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.nested(Self::object),
+            Some(b'[') => self.nested(Self::array),
+            Some(b'"') => Ok(Json::String(self.string()?)),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) if self.eat_word("true") => Ok(Json::Bool(true)),
+            Some(_) if self.eat_word("false") => Ok(Json::Bool(false)),
+            Some(_) if self.eat_word("null") => Ok(Json::Null),
+            Some(_) => Err(self.error("Expected a value")),
+            None => Err(self.error("Unexpected end of input")),
+        }
+    }
+
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Json, String>) -> Result<Json, String> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("Value nested too deeply"));
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.position += 1;
+        let mut members: Vec<(String, Json)> = Vec::new();
+        self.skip_whitespace();
+        if self.eat(b'}') {
+            return Ok(Json::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(self.error("Expected a string key"));
+            }
+            let key = self.string()?;
+            self.skip_whitespace();
+            if !self.eat(b':') {
+                return Err(self.error("Expected ':' after the key"));
+            }
+            let value = self.value()?;
+            match members.iter_mut().find(|(name, _)| *name == key) {
+                Some((_, earlier)) => *earlier = value,
+                None => members.push((key, value)),
+            }
+            self.skip_whitespace();
+            if self.eat(b'}') {
+                return Ok(Json::Object(members));
+            }
+            if !self.eat(b',') {
+                return Err(self.error("Expected ',' or '}' in the object"));
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.position += 1;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.eat(b']') {
+            return Ok(Json::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.skip_whitespace();
+            if self.eat(b']') {
+                return Ok(Json::Array(items));
+            }
+            if !self.eat(b',') {
+                return Err(self.error("Expected ',' or ']' in the array"));
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.position += 1;
+        let mut s = String::new();
+        loop {
+            let rest = &self.text[self.position..];
+            let Some(ch) = rest.chars().next() else {
+                return Err(self.error("Unterminated string"));
+            };
+            self.position += ch.len_utf8();
+            match ch {
+                '"' => return Ok(s),
+                '\\' => {
+                    let escape = self.peek().ok_or_else(|| self.error("Unterminated string"))?;
+                    self.position += 1;
+                    match escape {
+                        b'"' => s.push('"'),
+                        b'\\' => s.push('\\'),
+                        b'/' => s.push('/'),
+                        b'b' => s.push('\u{8}'),
+                        b'f' => s.push('\u{c}'),
+                        b'n' => s.push('\n'),
+                        b'r' => s.push('\r'),
+                        b't' => s.push('\t'),
+                        b'u' => s.push(self.unicode_escape()?),
+                        _ => return Err(self.error("Invalid escape in string")),
+                    }
+                }
+                ch if (ch as u32) < 0x20 => return Err(self.error("Control character in string")),
+                ch => s.push(ch),
+            }
+        }
+    }
+
+    // after `\u`: four hex digits, or a surrogate pair of two such escapes
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let first = self.hex4()?;
+        if !(0xD800..0xDC00).contains(&first) {
+            // a lone low surrogate can't be a char either
+            return char::from_u32(first).ok_or_else(|| self.error("Invalid unicode escape"));
+        }
+        if !self.text[self.position..].starts_with("\\u") {
+            return Err(self.error("Unpaired surrogate in unicode escape"));
+        }
+        self.position += 2;
+        let second = self.hex4()?;
+        if !(0xDC00..0xE000).contains(&second) {
+            return Err(self.error("Unpaired surrogate in unicode escape"));
+        }
+        let code = 0x10000 + ((first - 0xD800) << 10) + (second - 0xDC00);
+        char::from_u32(code).ok_or_else(|| self.error("Invalid unicode escape"))
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits = self.text.get(self.position..self.position + 4).filter(|digits| {
+            digits.bytes().all(|b| b.is_ascii_hexdigit())
+        });
+        let code = digits
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .ok_or_else(|| self.error("Expected four hex digits in unicode escape"))?;
+        self.position += 4;
+        Ok(code)
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.position;
+        self.eat(b'-');
+        if !self.eat(b'0') && !self.digits() {
+            return Err(self.error("Expected a digit"));
+        }
+        let fraction = self.eat(b'.');
+        if fraction && !self.digits() {
+            return Err(self.error("Expected a digit after '.'"));
+        }
+        let exponent = self.eat(b'e') || self.eat(b'E');
+        if exponent {
+            let _ = self.eat(b'+') || self.eat(b'-');
+            if !self.digits() {
+                return Err(self.error("Expected a digit in the exponent"));
+            }
+        }
+        let text = &self.text[start..self.position];
+        // an integer that fits stays exact, but `-0` is only a float
+        if !fraction && !exponent && text != "-0" && let Ok(n) = text.parse() {
+            return Ok(Json::Int(n));
+        }
+        text.parse()
+            .map(Json::Number)
+            .map_err(|_| self.error("Invalid number"))
+    }
+
+    // whether there were any
+    fn digits(&mut self) -> bool {
+        let start = self.position;
+        while matches!(self.peek(), Some(b'0'..=b'9')) {
+            self.position += 1;
+        }
+        self.position > start
+    }
+
+    fn eat_word(&mut self, word: &str) -> bool {
+        let found = self.text[self.position..].starts_with(word);
+        if found {
+            self.position += word.len();
+        }
+        found
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        let found = self.peek() == Some(byte);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.position += 1;
+        }
+    }
+
+    fn error(&self, message: &str) -> String {
+        // in bytes, since a bad escape can leave `position` inside a char
+        let before = &self.text.as_bytes()[..self.position];
+        let line = before.iter().filter(|&&b| b == b'\n').count() + 1;
+        let line_start = before.iter().rposition(|&b| b == b'\n').map_or(0, |newline| newline + 1);
+        // counting chars, not their continuation bytes
+        let column = before[line_start..].iter().filter(|&&b| b & 0xC0 != 0x80).count() + 1;
+        format!("{} at line {}, column {} (byte {})", message, line, column, self.position)
+    }
+}
@@ -0,0 +1,1183 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+
+use crate::codes::{self, Code};
+use crate::report::Label;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenType {
+    // literals
+    Number,
+    String,
+    Identifier,
+
+    // operators
+    Plus,
+    Minus,
+    Multiply,
+    Divide,
+    Modulo,
+    Assign,
+    // compound assignment: `+=`, `-=`, `*=`, `/=`, and `%=`
+    PlusAssign,
+    MinusAssign,
+    MultiplyAssign,
+    DivideAssign,
+    ModuloAssign,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Not,
+    And,
+    Or,
+    // `|>`, passing the value on its left to the call on its right
+    PipeGreater,
+    // `=>`, between a match arm's values and its block
+    FatArrow,
+
+    // delimiters
+    Semicolon,
+    Colon,
+    Comma,
+    Dot,
+    DotDot,
+    DotDotEqual,
+    // `...`, before the name collecting the rest of an array
+    Ellipsis,
+
+    // parentheses and brackets
+    LeftParen,
+    RightParen,
+    LeftBrace,
+    RightBrace,
+    LeftBracket,
+    RightBracket,
+
+    // keywords
+    Let,
+    Const,
+    If,
+    Else,
+    While,
+    Do,
+    For,
+    Function,
+    Return,
+    Break,
+    Continue,
+    In,
+    Import,
+    As,
+    Export,
+    Match,
+    Class,
+    This,
+    Try,
+    Catch,
+    Throw,
+    TypeOf,
+    True,
+    False,
+    Null,
+
+    // special
+    // only produced by `Lexer::with_comments`
+    Comment,
+    // a run of whitespace, only produced by `Lexer::with_trivia`
+    Whitespace,
+    EOF,
+}
+
+/// A region of the source: byte offsets `start..end`, plus the line and
+/// column where it begins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+    // which file the span is in: 0 for the program given to the lexer or
+    // interpreter, otherwise a file it imports
+    pub file: usize,
+}
+
+impl Span {
+    /// Returns a span covering from the start of `self` to the end of `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span {
+            start: self.start,
+            end: other.end,
+            line: self.line,
+            column: self.column,
+            file: self.file,
+        }
+    }
+
+    /// The line and column where the span ends in `source`, just past its
+    /// last character, counted like `line` and `column`.
+    pub fn end_position(&self, source: &str) -> (usize, usize) {
+        let before = &source[..self.end.min(source.len())];
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+        (before.matches('\n').count() + 1, before[line_start..].chars().count() + 1)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Token {
+    pub token_type: TokenType,
+    pub value: String,
+    pub line: usize,
+    pub column: usize,
+    pub span: Span,
+    // the line and column just past the token's last character, which for a
+    // string may be on a later line than it starts
+    pub end_line: usize,
+    pub end_column: usize,
+    // the literal as written, when that isn't `value`: kept by
+    // `Lexer::with_trivia` for a string, whose escapes `value` has
+    // already turned into the characters they stand for
+    pub raw: Option<String>,
+    // the text of the `///` comment lines just above the token, with no
+    // blank line or other comment between, from a lexer that skips
+    // comments; the parser keeps it for the declaration the token starts
+    pub doc: Option<String>,
+}
+
+impl Token {
+    /// The token's text as it appears in the source, for tokens from
+    /// `Lexer::with_trivia`. Strings from other lexers only have their
+    /// contents.
+    pub fn raw(&self) -> &str {
+        self.raw.as_deref().unwrap_or(&self.value)
+    }
+
+    /// A zero-width span just past the token's last character, where
+    /// something missing after it would have gone.
+    pub fn end(&self) -> Span {
+        Span {
+            start: self.span.end,
+            end: self.span.end,
+            line: self.end_line,
+            column: self.end_column,
+            file: self.span.file,
+        }
+    }
+}
+
+/// Joins the text of `tokens` back together. Given every token
+/// `Lexer::with_trivia` made of a program, it gives back the program
+/// exactly, byte for byte; `fuzz/fuzz_targets/round_trip.rs` checks that
+/// it does.
+pub fn tokens_to_source(tokens: &[Token]) -> String {
+    tokens.iter().map(Token::raw).collect()
+}
+
+impl fmt::Display for Token {
+    /// One line describing the token, e.g.
+    /// `Identifier "x" at line 1, column 5 (bytes 4..5)`, with the value
+    /// escaped so every token fits on one line.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:?} {:?} at line {}, column {} (bytes {}..{})",
+            self.token_type, self.value, self.line, self.column, self.span.start, self.span.end
+        )
+    }
+}
+
+/// A change to source text: the bytes in `range` replaced by `new_text`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub new_text: String,
+}
+
+/// A problem turning source text into tokens, such as a character no
+/// token starts with.
+#[derive(Debug, Clone)]
+pub struct LexError {
+    pub code: &'static Code,
+    pub message: String,
+    pub span: Span,
+    // another part of the source the error involves, like where an
+    // unterminated string starts, boxed to keep results small
+    pub secondary: Option<Box<Label>>,
+}
+
+impl LexError {
+    fn new(code: &'static Code, message: impl Into<String>, span: Span) -> Self {
+        LexError {
+            code,
+            message: message.into(),
+            span,
+            secondary: None,
+        }
+    }
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at line {}, column {}", self.message, self.span.line, self.span.column)
+    }
+}
+
+impl std::error::Error for LexError {}
+
+/// A string literal `unescape_string` couldn't read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EscapeError {
+    pub message: String,
+    // the bytes of the literal the problem is in
+    pub range: Range<usize>,
+}
+
+impl fmt::Display for EscapeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at bytes {}..{}", self.message, self.range.start, self.range.end)
+    }
+}
+
+impl std::error::Error for EscapeError {}
+
+/// `value` as a string literal that lexes back to exactly `value`, e.g.
+/// `he said "hi"` followed by a newline becomes `"he said \"hi\"\n"`.
+/// Printable ASCII is kept as it is, apart from `"` and `\`, which are
+/// escaped; newlines, tabs, and carriage returns become `\n`, `\t`, and
+/// `\r`; and every other character becomes `\u{...}` with its code point
+/// in hex. `fuzz/fuzz_targets/escape.rs` checks the lexer agrees.
+pub fn escape_string(value: &str) -> String {
+    let mut literal = String::with_capacity(value.len() + 2);
+    literal.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\t' => literal.push_str("\\t"),
+            '\r' => literal.push_str("\\r"),
+            ' '..='~' => literal.push(c),
+            c => literal.push_str(&format!("\\u{{{:x}}}", c as u32)),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+/// The value of the string literal `literal`, its quotes included, read
+/// the way the lexer reads one, so `unescape_string(&escape_string(s))` is
+/// `s`. The error's range is the bytes of `literal` at fault.
+pub fn unescape_string(literal: &str) -> Result<String, EscapeError> {
+    let error = |message: &str, range: Range<usize>| EscapeError { message: message.to_string(), range };
+    let Some(contents) = literal.strip_prefix('"').and_then(|rest| rest.strip_suffix('"')) else {
+        return Err(error("A string literal starts and ends with '\"'", 0..literal.len()));
+    };
+    let chars: Vec<char> = contents.chars().collect();
+    let width = |chars: &[char]| chars.iter().map(|c| c.len_utf8()).sum::<usize>();
+    let mut value = String::with_capacity(contents.len());
+    // the byte in `literal` of `chars[position]`
+    let mut offset = 1;
+    let mut position = 0;
+    while let Some(&c) = chars.get(position) {
+        let length = match c {
+            '"' => return Err(error("Unescaped '\"' inside the string", offset..offset + 1)),
+            '\\' if position + 1 == chars.len() => {
+                return Err(error("The string's closing quote is escaped", offset..literal.len()));
+            }
+            '\\' => match read_escape(&chars[position + 1..]) {
+                Ok((c, length)) => {
+                    value.push(c);
+                    1 + length
+                }
+                Err((message, length)) => {
+                    let end = offset + width(&chars[position..position + 1 + length]);
+                    return Err(error(&message, offset..end));
+                }
+            },
+            c => {
+                value.push(c);
+                1
+            }
+        };
+        offset += width(&chars[position..position + length]);
+        position += length;
+    }
+    Ok(value)
+}
+
+// reads the escape sequence `rest` starts with, just after its backslash,
+// as the character it stands for and how many characters of `rest` it
+// takes, or for an invalid one, what's wrong and how many characters to
+// skip; `rest` isn't empty
+fn read_escape(rest: &[char]) -> Result<(char, usize), (String, usize)> {
+    match rest[0] {
+        'n' => Ok(('\n', 1)),
+        't' => Ok(('\t', 1)),
+        'r' => Ok(('\r', 1)),
+        '\\' => Ok(('\\', 1)),
+        '"' => Ok(('"', 1)),
+        'u' => read_unicode_escape(rest),
+        other => Err((format!("Invalid escape sequence: \\{}", other), 1)),
+    }
+}
+
+// `u{...}` with one to six hex digits naming a Unicode scalar value
+fn read_unicode_escape(rest: &[char]) -> Result<(char, usize), (String, usize)> {
+    if rest.get(1) != Some(&'{') {
+        return Err(("Expected '{' after \\u".to_string(), 1));
+    }
+    let digits: String = rest[2..].iter().take_while(|c| c.is_ascii_hexdigit()).collect();
+    let close = 2 + digits.len();
+    if rest.get(close) != Some(&'}') {
+        return Err(("Expected '}' to close \\u{...}".to_string(), close));
+    }
+    if !(1..=6).contains(&digits.len()) {
+        return Err(("\\u{...} takes one to six hex digits".to_string(), close + 1));
+    }
+    let code = u32::from_str_radix(&digits, 16).expect("one to six hex digits fit in a u32");
+    match char::from_u32(code) {
+        Some(c) => Ok((c, close + 1)),
+        None => Err((format!("\\u{{{}}} is not a Unicode character", digits), close + 1)),
+    }
+}
+
+/// The value of a number literal: decimal digits with an optional
+/// fraction and exponent, like `12`, `3.5`, or `1e-7`, or hex digits after
+/// `0x`. `None` for text that isn't one, including text `read_number` takes
+/// in but the parser rejects, like `1.2.3`.
+pub fn parse_number(text: &str) -> Option<f64> {
+    if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        if hex.is_empty() || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        // too many digits for a u64 are added up one by one instead
+        return Some(u64::from_str_radix(hex, 16).map_or_else(
+            |_| hex.chars().fold(0.0, |n, digit| n * 16.0 + digit.to_digit(16).unwrap_or(0) as f64),
+            |n| n as f64,
+        ));
+    }
+    let bytes = text.as_bytes();
+    let digits_from = |start: usize| bytes[start.min(bytes.len())..].iter().take_while(|b| b.is_ascii_digit()).count();
+    let mut end = digits_from(0);
+    if end == 0 {
+        return None;
+    }
+    if bytes.get(end) == Some(&b'.') {
+        let fraction = digits_from(end + 1);
+        if fraction == 0 {
+            return None;
+        }
+        end += 1 + fraction;
+    }
+    if matches!(bytes.get(end), Some(b'e' | b'E')) {
+        end += 1;
+        if matches!(bytes.get(end), Some(b'+' | b'-')) {
+            end += 1;
+        }
+        let exponent = digits_from(end);
+        if exponent == 0 {
+            return None;
+        }
+        end += exponent;
+    }
+    if end != bytes.len() {
+        return None;
+    }
+    text.parse().ok()
+}
+
+/// The magnitude of an integer literal: decimal digits alone, like `12`,
+/// or hex digits after `0x`. A magnitude too big for a `u64` is an `Err`
+/// holding its low 64 bits, which are all that wrapping around keeps.
+/// `None` for text that isn't one, including a literal with a fraction or
+/// an exponent.
+pub fn parse_integer(text: &str) -> Option<Result<u64, u64>> {
+    let (digits, radix) = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => (hex, 16),
+        None => (text, 10),
+    };
+    if digits.is_empty() {
+        return None;
+    }
+    let mut magnitude = Ok(0u64);
+    for digit in digits.chars() {
+        let digit = u64::from(digit.to_digit(radix)?);
+        let (Ok(n) | Err(n)) = magnitude;
+        let wrapped = n.wrapping_mul(u64::from(radix)).wrapping_add(digit);
+        magnitude = match magnitude {
+            Ok(n) => n.checked_mul(u64::from(radix)).and_then(|n| n.checked_add(digit)).ok_or(wrapped),
+            Err(_) => Err(wrapped),
+        };
+    }
+    Some(magnitude)
+}
+
+// the words that lex as keywords rather than identifiers
+const KEYWORDS: [(&str, TokenType); 25] = [
+    ("let", TokenType::Let),
+    ("const", TokenType::Const),
+    ("if", TokenType::If),
+    ("else", TokenType::Else),
+    ("while", TokenType::While),
+    ("do", TokenType::Do),
+    ("for", TokenType::For),
+    ("function", TokenType::Function),
+    ("return", TokenType::Return),
+    ("break", TokenType::Break),
+    ("continue", TokenType::Continue),
+    ("in", TokenType::In),
+    ("import", TokenType::Import),
+    ("as", TokenType::As),
+    ("export", TokenType::Export),
+    ("match", TokenType::Match),
+    ("class", TokenType::Class),
+    ("this", TokenType::This),
+    ("try", TokenType::Try),
+    ("catch", TokenType::Catch),
+    ("throw", TokenType::Throw),
+    ("typeof", TokenType::TypeOf),
+    ("true", TokenType::True),
+    ("false", TokenType::False),
+    ("null", TokenType::Null),
+];
+
+static RESERVED_WORDS: [&str; KEYWORDS.len()] = {
+    let mut words = [""; KEYWORDS.len()];
+    let mut i = 0;
+    while i < KEYWORDS.len() {
+        words[i] = KEYWORDS[i].0;
+        i += 1;
+    }
+    words
+};
+
+/// The words that can't be used as names because they're keywords, like
+/// `if`, `let`, and `true`, for editors to warn about as they're typed.
+pub fn reserved_words() -> &'static [&'static str] {
+    &RESERVED_WORDS
+}
+
+/// Whether tokens of this type are keywords, which read like names but
+/// can't be used as one.
+pub fn is_keyword(token_type: &TokenType) -> bool {
+    KEYWORDS.iter().any(|(_, keyword)| keyword == token_type)
+}
+
+const STRING_QUOTE: &str = "string literals must use '\"'";
+
+/// Characters that look like ones slimescript uses but aren't, mostly from
+/// text pasted out of chat apps, word processors, and PDFs: each with its
+/// Unicode name and what to write instead. Fullwidth forms of ASCII
+/// characters are worked out in `unexpected_character` instead.
+const CONFUSABLES: [(char, &str, &str); 21] = [
+    ('\u{201C}', "LEFT DOUBLE QUOTATION MARK", STRING_QUOTE),
+    ('\u{201D}', "RIGHT DOUBLE QUOTATION MARK", STRING_QUOTE),
+    ('\u{201E}', "DOUBLE LOW-9 QUOTATION MARK", STRING_QUOTE),
+    ('\u{2018}', "LEFT SINGLE QUOTATION MARK", STRING_QUOTE),
+    ('\u{2019}', "RIGHT SINGLE QUOTATION MARK", STRING_QUOTE),
+    ('\u{00AB}', "LEFT-POINTING DOUBLE ANGLE QUOTATION MARK", STRING_QUOTE),
+    ('\u{00BB}', "RIGHT-POINTING DOUBLE ANGLE QUOTATION MARK", STRING_QUOTE),
+    ('\u{2212}', "MINUS SIGN", "write '-' instead"),
+    ('\u{2010}', "HYPHEN", "write '-' instead"),
+    ('\u{2011}', "NON-BREAKING HYPHEN", "write '-' instead"),
+    ('\u{2013}', "EN DASH", "write '-' instead"),
+    ('\u{2014}', "EM DASH", "write '-' instead"),
+    ('\u{00D7}', "MULTIPLICATION SIGN", "write '*' instead"),
+    ('\u{00F7}', "DIVISION SIGN", "write '/' instead"),
+    ('\u{037E}', "GREEK QUESTION MARK", "write ';' instead"),
+    ('\u{2026}', "HORIZONTAL ELLIPSIS", "write '...' instead"),
+    ('\u{00A0}', "NO-BREAK SPACE", "write an ordinary space instead"),
+    ('\u{202F}', "NARROW NO-BREAK SPACE", "write an ordinary space instead"),
+    ('\u{200B}', "ZERO WIDTH SPACE", "it's invisible, so delete it"),
+    ('\u{2060}', "WORD JOINER", "it's invisible, so delete it"),
+    ('\u{FEFF}', "ZERO WIDTH NO-BREAK SPACE", "it's invisible, so delete it"),
+];
+
+/// The message for `ch`, which no token starts with. A character from
+/// `CONFUSABLES` or the fullwidth form of an ASCII character is named with
+/// what to write instead, and any other character outside ASCII has its
+/// code point given, since it may not look like what it is.
+fn unexpected_character(ch: char) -> String {
+    let code_point = ch as u32;
+    if let Some((_, name, instead)) = CONFUSABLES.iter().find(|(confusable, ..)| *confusable == ch) {
+        return format!("Unexpected character '{}' (U+{:04X} {}); {}", ch.escape_debug(), code_point, name, instead);
+    }
+    // U+FF01 to U+FF5E are '!' to '~' as wide as CJK characters
+    if (0xFF01..=0xFF5E).contains(&code_point)
+        && let Some(ascii) = char::from_u32(code_point - 0xFEE0)
+    {
+        return format!(
+            "Unexpected character '{}' (U+{:04X}, a fullwidth '{}'); write '{}' instead",
+            ch, code_point, ascii, ascii
+        );
+    }
+    match ch {
+        '#' => "Unexpected character '#'; line comments start with '//'".to_string(),
+        _ if ch.is_ascii() => format!("Unexpected character '{}'", ch.escape_debug()),
+        _ => format!("Unexpected character '{}' (U+{:04X})", ch.escape_debug(), code_point),
+    }
+}
+
+// the spaces that keep words on one line, which pasted text often has
+fn is_no_break_space(ch: char) -> bool {
+    matches!(ch, '\u{00A0}' | '\u{202F}')
+}
+
+/// Optional syntax for the lexer to accept, all of it off by default. A
+/// `#!` line at the very start of the input is always skipped, so a script
+/// can be run directly whatever the configuration.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LexerConfig {
+    // whether `#` starts a line comment, like `//`, instead of being an
+    // unexpected character
+    pub hash_comments: bool,
+    // whether a no-break space is an unexpected character, instead of
+    // whitespace with a warning
+    pub reject_no_break_spaces: bool,
+}
+
+#[derive(Debug)]
+pub struct Lexer {
+    input: Vec<char>,
+    position: usize,
+    offset: usize,
+    line: usize,
+    column: usize,
+    keywords: HashMap<String, TokenType>,
+    // whether comments become tokens instead of being skipped
+    keep_comments: bool,
+    // whether whitespace becomes tokens too, and strings keep their
+    // quotes and escapes in `raw`
+    keep_whitespace: bool,
+    config: LexerConfig,
+    // the file spans are in
+    file: usize,
+    // what the input has that lexes but is probably a mistake
+    warnings: Vec<LexError>,
+    // errors in tokens that could still be made, like a string with a bad
+    // escape in it, waiting to be returned
+    errors: Vec<LexError>,
+}
+
+impl Lexer {
+    pub fn new(input: &str) -> Self {
+        let keywords = KEYWORDS
+            .iter()
+            .map(|(word, token_type)| (word.to_string(), token_type.clone()))
+            .collect();
+
+        Lexer {
+            input: input.chars().collect(),
+            position: 0,
+            offset: 0,
+            line: 1,
+            column: 1,
+            keywords,
+            keep_comments: false,
+            keep_whitespace: false,
+            config: LexerConfig::default(),
+            file: 0,
+            errors: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// A lexer that also produces a `Comment` token for each line comment,
+    /// for tools like the formatter that must keep them. The parser doesn't
+    /// accept these tokens.
+    pub fn with_comments(input: &str) -> Self {
+        Lexer {
+            keep_comments: true,
+            ..Lexer::new(input)
+        }
+    }
+
+    /// A lexer that keeps everything in the input: comments and runs of
+    /// whitespace become tokens, and each string token has the literal as
+    /// written in `raw`. `tokens_to_source` turns the tokens back into the
+    /// input, so a tool can rewrite some tokens and leave the rest of the
+    /// file untouched. Like `with_comments`, the parser doesn't accept
+    /// these tokens.
+    pub fn with_trivia(input: &str) -> Self {
+        Lexer {
+            keep_comments: true,
+            keep_whitespace: true,
+            ..Lexer::new(input)
+        }
+    }
+
+    /// A lexer whose spans are in `file`, for a file imported by the
+    /// program.
+    pub fn in_file(input: &str, file: usize) -> Self {
+        Lexer {
+            file,
+            ..Lexer::new(input)
+        }
+    }
+
+    /// Accepts the optional syntax `config` turns on.
+    pub fn with_config(mut self, config: LexerConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Warnings about what the input has that lexes but is probably a
+    /// mistake, like a no-break space, in the order they were found.
+    pub fn warnings(&self) -> &[LexError] {
+        &self.warnings
+    }
+
+    fn current_char(&self) -> Option<char> {
+        self.input.get(self.position).copied()
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        self.input.get(self.position + 1).copied()
+    }
+
+    fn advance(&mut self) {
+        if let Some(ch) = self.current_char() {
+            if ch == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+            self.offset += ch.len_utf8();
+        }
+        self.position += 1;
+    }
+
+    /// A zero-width span at the current position, used to remember where a
+    /// token starts.
+    fn mark(&self) -> Span {
+        Span {
+            start: self.offset,
+            end: self.offset,
+            line: self.line,
+            column: self.column,
+            file: self.file,
+        }
+    }
+
+    /// Builds a token running from `start` up to the current position.
+    fn make_token(&self, token_type: TokenType, value: String, start: Span) -> Token {
+        Token {
+            token_type,
+            value,
+            line: start.line,
+            column: start.column,
+            span: Span {
+                end: self.offset,
+                ..start
+            },
+            end_line: self.line,
+            end_column: self.column,
+            raw: None,
+            doc: None,
+        }
+    }
+
+    /// Consumes a single-character token.
+    fn single(&mut self, token_type: TokenType, value: &str) -> Token {
+        let start = self.mark();
+        self.advance();
+        self.make_token(token_type, value.to_string(), start)
+    }
+
+    /// Consumes `first`, then also `second` if it follows, choosing between
+    /// the one- and two-character token types.
+    fn single_or_double(
+        &mut self,
+        second: char,
+        single: TokenType,
+        double: TokenType,
+    ) -> Token {
+        let start = self.mark();
+        let first = self.current_char().unwrap_or_default();
+        self.advance();
+        if self.current_char() == Some(second) {
+            self.advance();
+            self.make_token(double, format!("{}{}", first, second), start)
+        } else {
+            self.make_token(single, first.to_string(), start)
+        }
+    }
+
+    /// Whether the current character is whitespace, which a no-break space
+    /// isn't if the config rejects them.
+    fn at_whitespace(&self) -> bool {
+        self.current_char().is_some_and(|ch| {
+            ch.is_whitespace() && !(self.config.reject_no_break_spaces && is_no_break_space(ch))
+        })
+    }
+
+    /// Consumes a whitespace character, warning if it's a no-break space.
+    fn advance_whitespace(&mut self) {
+        let start = self.mark();
+        let ch = self.current_char();
+        self.advance();
+        if let Some(ch) = ch.filter(|ch| is_no_break_space(*ch)) {
+            self.warnings.push(LexError::new(
+                &codes::NO_BREAK_SPACE,
+                format!("Found U+{:04X}, a no-break space, read as an ordinary space", ch as u32),
+                Span { end: self.offset, ..start },
+            ));
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.at_whitespace() {
+            self.advance_whitespace();
+        }
+    }
+
+    fn read_whitespace(&mut self) -> Token {
+        let start = self.mark();
+        let mut whitespace = String::new();
+        while let Some(ch) = self.current_char().filter(|_| self.at_whitespace()) {
+            whitespace.push(ch);
+            self.advance_whitespace();
+        }
+        self.make_token(TokenType::Whitespace, whitespace, start)
+    }
+
+    fn read_number(&mut self) -> Token {
+        let start = self.mark();
+        let mut number = String::new();
+
+        let hex_prefix = self.current_char() == Some('0') && matches!(self.peek_char(), Some('x' | 'X'));
+        if hex_prefix && self.input.get(self.position + 2).is_some_and(|digit| digit.is_ascii_hexdigit()) {
+            number.push('0');
+            self.advance();
+            number.extend(self.current_char());
+            self.advance();
+            while let Some(digit) = self.current_char().filter(char::is_ascii_hexdigit) {
+                number.push(digit);
+                self.advance();
+            }
+            return self.make_token(TokenType::Number, number, start);
+        }
+
+        while let Some(ch) = self.current_char() {
+            // a '.' only belongs to the number when a digit follows, so
+            // `0..10` lexes as a range rather than one malformed number
+            let is_decimal_point = ch == '.' && self.peek_char().is_some_and(|next| next.is_ascii_digit());
+            if ch.is_ascii_digit() || is_decimal_point {
+                number.push(ch);
+                self.advance();
+            } else {
+                break;
+            }
+        }
+
+        // likewise an exponent needs a digit, after its sign if it has one
+        if matches!(self.current_char(), Some('e' | 'E')) {
+            let digit_at = match self.peek_char() {
+                Some('+' | '-') => 2,
+                _ => 1,
+            };
+            if self.input.get(self.position + digit_at).is_some_and(char::is_ascii_digit) {
+                for _ in 0..digit_at {
+                    number.extend(self.current_char());
+                    self.advance();
+                }
+                while let Some(digit) = self.current_char().filter(char::is_ascii_digit) {
+                    number.push(digit);
+                    self.advance();
+                }
+            }
+        }
+
+        self.make_token(TokenType::Number, number, start)
+    }
+
+    /// Reads a string literal. A bad escape or a missing closing quote is
+    /// kept in `errors`, and the string still becomes a token so lexing can
+    /// go on past it. A lexer keeping trivia also keeps the literal as
+    /// written.
+    fn read_string(&mut self) -> Token {
+        let start_position = self.position;
+        let mut token = self.read_string_contents();
+        if self.keep_whitespace {
+            token.raw = Some(self.input[start_position..self.position].iter().collect());
+        }
+        token
+    }
+
+    fn read_string_contents(&mut self) -> Token {
+        let start = self.mark();
+        let string_starts_here = || {
+            Some(Box::new(Label {
+                span: Span { end: start.start + 1, ..start },
+                message: "string starts here".to_string(),
+            }))
+        };
+        let mut string = String::new();
+
+        // Skip opening quote
+        self.advance();
+
+        while let Some(ch) = self.current_char() {
+            if ch == '"' {
+                self.advance(); // Skip closing quote
+                return self.make_token(TokenType::String, string, start);
+            } else if ch == '\\' {
+                // Handle escape sequences
+                let backslash = self.mark();
+                self.advance();
+                if self.current_char().is_some() {
+                    let escape = read_escape(&self.input[self.position..]);
+                    let (Ok((_, length)) | Err((_, length))) = escape;
+                    for _ in 0..length {
+                        self.advance();
+                    }
+                    match escape {
+                        Ok((escaped, _)) => string.push(escaped),
+                        Err((message, _)) => {
+                            let span = Span { end: self.offset, ..backslash };
+                            self.errors.push(LexError::new(&codes::INVALID_ESCAPE, message, span));
+                        }
+                    }
+                } else {
+                    self.errors.push(LexError {
+                        secondary: string_starts_here(),
+                        ..LexError::new(
+                            &codes::UNTERMINATED_STRING,
+                            "Unexpected end of input in escape sequence",
+                            self.mark(),
+                        )
+                    });
+                    return self.make_token(TokenType::String, string, start);
+                }
+            } else {
+                string.push(ch);
+                self.advance();
+            }
+        }
+
+        self.errors.push(LexError {
+            secondary: string_starts_here(),
+            ..LexError::new(&codes::UNTERMINATED_STRING, "Unterminated string literal", self.mark())
+        });
+        self.make_token(TokenType::String, string, start)
+    }
+
+    fn read_identifier(&mut self) -> Token {
+        let start = self.mark();
+        let mut identifier = String::new();
+
+        while let Some(ch) = self.current_char() {
+            if ch.is_alphanumeric() || ch == '_' {
+                identifier.push(ch);
+                self.advance();
+            } else {
+                break;
+            }
+        }
+
+        // Check if it's a keyword
+        let token_type = self.keywords.get(&identifier)
+            .cloned()
+            .unwrap_or(TokenType::Identifier);
+
+        self.make_token(token_type, identifier, start)
+    }
+
+    /// Whether a line comment starts here: `//`, a `#` if the config allows
+    /// them, or a `#!` line at the start of the input.
+    fn at_comment(&self) -> bool {
+        match self.current_char() {
+            Some('/') => self.peek_char() == Some('/'),
+            Some('#') => self.config.hash_comments || (self.position == 0 && self.peek_char() == Some('!')),
+            _ => false,
+        }
+    }
+
+    fn read_comment(&mut self) -> Token {
+        // Skip the comment's opening and everything until end of line
+        let start = self.mark();
+        let mut comment = String::new();
+        while let Some(ch) = self.current_char() {
+            if ch == '\n' {
+                break;
+            }
+            comment.push(ch);
+            self.advance();
+        }
+        self.make_token(TokenType::Comment, comment, start)
+    }
+
+    pub fn next_token(&mut self) -> Result<Token, LexError> {
+        let token = self.scan_token();
+        match self.errors.drain(..).next() {
+            Some(error) => Err(error),
+            None => token,
+        }
+    }
+
+    /// Reads the next token, leaving errors in a token that could still be
+    /// made in `errors`.
+    fn scan_token(&mut self) -> Result<Token, LexError> {
+        if self.keep_whitespace && self.at_whitespace() {
+            return Ok(self.read_whitespace());
+        }
+        self.skip_whitespace();
+        // skipped comments are looped over rather than recursed past, so a
+        // long run of them can't overflow the stack
+        let mut doc: Option<String> = None;
+        while !self.keep_comments && self.at_comment() {
+            let comment = self.read_comment();
+            doc = match comment.value.strip_prefix("///") {
+                // `////` is an ordinary comment
+                Some(text) if !text.starts_with('/') => {
+                    let text = text.strip_prefix(' ').unwrap_or(text);
+                    Some(match doc {
+                        Some(doc) => format!("{}\n{}", doc, text),
+                        None => text.to_string(),
+                    })
+                }
+                _ => None,
+            };
+            let line = self.line;
+            self.skip_whitespace();
+            if self.line > line + 1 {
+                doc = None;
+            }
+        }
+
+        let current_char = match self.current_char() {
+            Some(ch) => ch,
+            None => {
+                return Ok(self.make_token(TokenType::EOF, "".to_string(), self.mark()));
+            }
+        };
+
+        let token = match current_char {
+            // Comments
+            _ if self.at_comment() => Ok(self.read_comment()),
+
+            // Numbers
+            '0'..='9' => Ok(self.read_number()),
+
+            // Strings
+            '"' => Ok(self.read_string()),
+
+            // Identifiers and keywords
+            'a'..='z' | 'A'..='Z' | '_' => Ok(self.read_identifier()),
+
+            // Operators
+            '+' => Ok(self.single_or_double('=', TokenType::Plus, TokenType::PlusAssign)),
+            '-' => Ok(self.single_or_double('=', TokenType::Minus, TokenType::MinusAssign)),
+            '*' => Ok(self.single_or_double('=', TokenType::Multiply, TokenType::MultiplyAssign)),
+            '/' => Ok(self.single_or_double('=', TokenType::Divide, TokenType::DivideAssign)),
+            '%' => Ok(self.single_or_double('=', TokenType::Modulo, TokenType::ModuloAssign)),
+            '=' if self.peek_char() == Some('>') => {
+                let start = self.mark();
+                self.advance();
+                self.advance();
+                Ok(self.make_token(TokenType::FatArrow, "=>".to_string(), start))
+            }
+            '=' => Ok(self.single_or_double('=', TokenType::Assign, TokenType::Equal)),
+            '!' => Ok(self.single_or_double('=', TokenType::Not, TokenType::NotEqual)),
+            '<' => Ok(self.single_or_double('=', TokenType::Less, TokenType::LessEqual)),
+            '>' => Ok(self.single_or_double('=', TokenType::Greater, TokenType::GreaterEqual)),
+            '&' if self.peek_char() == Some('&') => {
+                let start = self.mark();
+                self.advance();
+                self.advance();
+                Ok(self.make_token(TokenType::And, "&&".to_string(), start))
+            }
+            '|' if self.peek_char() == Some('|') => {
+                let start = self.mark();
+                self.advance();
+                self.advance();
+                Ok(self.make_token(TokenType::Or, "||".to_string(), start))
+            }
+            '|' if self.peek_char() == Some('>') => {
+                let start = self.mark();
+                self.advance();
+                self.advance();
+                Ok(self.make_token(TokenType::PipeGreater, "|>".to_string(), start))
+            }
+
+            // Delimiters
+            ';' => Ok(self.single(TokenType::Semicolon, ";")),
+            ':' => Ok(self.single(TokenType::Colon, ":")),
+            ',' => Ok(self.single(TokenType::Comma, ",")),
+            '.' if self.peek_char() == Some('.') => {
+                let start = self.mark();
+                self.advance();
+                self.advance();
+                if self.current_char() == Some('=') {
+                    self.advance();
+                    Ok(self.make_token(TokenType::DotDotEqual, "..=".to_string(), start))
+                } else if self.current_char() == Some('.') {
+                    self.advance();
+                    Ok(self.make_token(TokenType::Ellipsis, "...".to_string(), start))
+                } else {
+                    Ok(self.make_token(TokenType::DotDot, "..".to_string(), start))
+                }
+            }
+            '.' => Ok(self.single(TokenType::Dot, ".")),
+
+            // Parentheses and brackets
+            '(' => Ok(self.single(TokenType::LeftParen, "(")),
+            ')' => Ok(self.single(TokenType::RightParen, ")")),
+            '{' => Ok(self.single(TokenType::LeftBrace, "{")),
+            '}' => Ok(self.single(TokenType::RightBrace, "}")),
+            '[' => Ok(self.single(TokenType::LeftBracket, "[")),
+            ']' => Ok(self.single(TokenType::RightBracket, "]")),
+
+            // Invalid character
+            _ => {
+                let start = self.mark();
+                self.advance();
+                let span = Span { end: self.offset, ..start };
+                Err(LexError::new(
+                    &codes::UNEXPECTED_CHARACTER,
+                    unexpected_character(current_char),
+                    span,
+                ))
+            }
+        };
+        token.map(|token| Token { doc, ..token })
+    }
+
+    /// Lexes the whole input, stopping at the first error. This never
+    /// panics, whatever the input: it returns either tokens ending in `EOF`
+    /// or an error with a span inside the input. The tokens' spans are in
+    /// order and never overlap. `fuzz/fuzz_targets/tokenize.rs` checks
+    /// both.
+    pub fn tokenize(&mut self) -> Result<Vec<Token>, LexError> {
+        let mut tokens = Vec::new();
+
+        loop {
+            let token = self.next_token()?;
+            let is_eof = matches!(token.token_type, TokenType::EOF);
+
+            tokens.push(token);
+
+            if is_eof {
+                break;
+            }
+        }
+
+        Ok(tokens)
+    }
+
+    /// Lexes the whole input like `tokenize`, but skips past errors instead
+    /// of stopping at the first, so all of them can be reported at once. A
+    /// character that can't start a token is left out of the tokens, and a
+    /// string with an error in it is kept.
+    pub fn tokenize_all(&mut self) -> (Vec<Token>, Vec<LexError>) {
+        let mut tokens = Vec::new();
+        let mut errors = Vec::new();
+
+        loop {
+            let token = self.scan_token();
+            errors.append(&mut self.errors);
+            match token {
+                Ok(token) => {
+                    let is_eof = matches!(token.token_type, TokenType::EOF);
+                    tokens.push(token);
+                    if is_eof {
+                        break;
+                    }
+                }
+                Err(error) => errors.push(error),
+            }
+        }
+
+        (tokens, errors)
+    }
+
+    /// Lexes the input like `tokenize_all`, without the errors, reusing
+    /// `old_tokens`: what `tokenize_all` made of the text that `edit` turned
+    /// into this lexer's input, with the same handling of comments.
+    ///
+    /// Old tokens ending before the edit are kept, except the last, since
+    /// where a token ends can depend on the two characters after it. Lexing
+    /// restarts at that token. Between tokens the lexer carries no state, so
+    /// once a new token past the edit starts where an old one did, the rest
+    /// of the old tokens are reused with their positions shifted. An edit
+    /// that leaves a string unterminated relexes everything after it.
+    pub fn relex(&mut self, old_tokens: &[Token], edit: TextEdit) -> Vec<Token> {
+        let before = old_tokens.iter().take_while(|token| token.span.end < edit.range.start).count();
+        let kept = before.saturating_sub(1);
+        let mut tokens = old_tokens[..kept].to_vec();
+        match old_tokens.get(kept) {
+            Some(restart) if before > 0 => self.seek(restart.span),
+            _ => self.seek(Span { line: 1, column: 1, ..Span::default() }),
+        }
+
+        let shift = edit.new_text.len() as isize - edit.range.len() as isize;
+        let edit_end = edit.range.start + edit.new_text.len();
+        loop {
+            let token = self.scan_token();
+            self.errors.clear();
+            let Ok(token) = token else {
+                continue;
+            };
+            if token.span.start >= edit_end {
+                let old_start = token.span.start.wrapping_add_signed(-shift);
+                if let Ok(index) = old_tokens.binary_search_by_key(&old_start, |old| old.span.start)
+                    && old_tokens[index].token_type == token.token_type
+                {
+                    let first = &old_tokens[index];
+                    let line_shift = token.line as isize - first.line as isize;
+                    let column_shift = token.column as isize - first.column as isize;
+                    let reused = tokens.len();
+                    tokens.extend(old_tokens[index..].iter().map(|old| {
+                        let mut span = Span {
+                            start: old.span.start.wrapping_add_signed(shift),
+                            end: old.span.end.wrapping_add_signed(shift),
+                            line: old.span.line.wrapping_add_signed(line_shift),
+                            ..old.span
+                        };
+                        let mut end_column = old.end_column;
+                        // only tokens on the same line move sideways
+                        if old.span.line == first.span.line {
+                            span.column = old.span.column.wrapping_add_signed(column_shift);
+                        }
+                        if old.end_line == first.span.line {
+                            end_column = old.end_column.wrapping_add_signed(column_shift);
+                        }
+                        Token {
+                            line: span.line,
+                            column: span.column,
+                            span,
+                            end_line: old.end_line.wrapping_add_signed(line_shift),
+                            end_column,
+                            ..old.clone()
+                        }
+                    }));
+                    // the edit may have been to the doc comment above it
+                    tokens[reused].doc = token.doc;
+                    return tokens;
+                }
+            }
+            let is_eof = matches!(token.token_type, TokenType::EOF);
+            tokens.push(token);
+            if is_eof {
+                return tokens;
+            }
+        }
+    }
+
+    /// Moves to where `span` starts, which must be on a character boundary.
+    fn seek(&mut self, span: Span) {
+        self.position = 0;
+        self.offset = 0;
+        while self.offset < span.start {
+            self.offset += self.input[self.position].len_utf8();
+            self.position += 1;
+        }
+        self.line = span.line;
+        self.column = span.column;
+        self.errors.clear();
+    }
+}
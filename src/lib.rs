@@ -0,0 +1,76 @@
+mod allocation;
+pub mod ast;
+pub mod bench;
+pub mod checker;
+pub mod builtins;
+pub mod clock;
+pub mod codes;
+pub mod compiler;
+pub mod completion;
+pub mod coverage;
+#[cfg(feature = "debugger")]
+pub mod debugger;
+pub mod disassembler;
+pub mod doc;
+#[cfg(feature = "editor")]
+pub mod editor;
+pub mod environment;
+pub mod error;
+pub mod expression;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod formatter;
+pub mod highlight;
+pub mod interpreter;
+mod json;
+pub mod lexer;
+pub mod lint;
+pub mod lsp;
+pub mod optimizer;
+pub mod parser;
+pub mod printer;
+pub mod profile;
+pub mod repl;
+pub mod report;
+pub mod snapshot;
+pub mod source;
+pub mod symbol;
+pub mod typechecker;
+pub mod value;
+pub mod vm;
+pub mod watch;
+
+pub use completion::complete;
+pub use checker::{CheckOptions, FileReport, FileStatus, check_files, check_files_with};
+pub use error::Error;
+pub use expression::{CompiledExpr, eval_with_vars};
+pub use interpreter::{Backend, Completion, ImportError, Interpreter, InterpreterConfig, Output, RuntimeError, TestResult, Trace, TraceFrame};
+pub use lexer::{EscapeError, escape_string, reserved_words, unescape_string};
+pub use snapshot::SnapshotError;
+pub use source::{Columns, LineIndex, SourceFile, SourceMap};
+pub use value::{Arity, IntOverflow, Value};
+
+/// Lexes and parses `source` into a program, which owns its statements and
+/// the arena of their expressions. It's `tokenize` followed by
+/// `parse_tokens`, which can be called apart, e.g. to time them.
+pub fn parse(source: &str) -> Result<ast::Program, Error> {
+    parse_tokens(tokenize(source)?)
+}
+
+/// Lexes `source` into tokens, ending with an `EOF` token.
+pub fn tokenize(source: &str) -> Result<Vec<lexer::Token>, Error> {
+    Ok(lexer::Lexer::new(source).tokenize()?)
+}
+
+/// Lexes `source` with `Lexer::with_trivia`, keeping its whitespace and
+/// comments as tokens, so `lexer::tokens_to_source` can put it back
+/// together exactly.
+pub fn tokenize_with_trivia(source: &str) -> Result<Vec<lexer::Token>, Error> {
+    Ok(lexer::Lexer::with_trivia(source).tokenize()?)
+}
+
+/// Parses the tokens `tokenize` gave into a program, ready for
+/// `Interpreter::eval_statements`.
+pub fn parse_tokens(tokens: Vec<lexer::Token>) -> Result<ast::Program, Error> {
+    Ok(parser::Parser::new(tokens).parse()?)
+}
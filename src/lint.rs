@@ -0,0 +1,76 @@
+use crate::checker::{Diagnostic, Severity};
+use crate::codes::{self, Code};
+use crate::lexer::{LexError, Span, Token, TokenType, is_keyword};
+
+/// Warns about tokens that lex fine but are almost certainly a mistake:
+/// a string with `${...}` in it, two strings in a row, and a number
+/// written right up against a name. Each warning suggests a fix. The last
+/// two never parse, so the warning comes with a parse error, which it
+/// explains.
+pub fn lint_tokens(tokens: &[Token]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for (index, token) in tokens.iter().enumerate() {
+        if token.token_type == TokenType::String && looks_interpolated(&token.value) {
+            diagnostics.push(warning(
+                &codes::INTERPOLATION_IN_STRING,
+                "This string isn't interpolated; `${...}` in it is kept as it is".to_string(),
+                token.span,
+                "join the value in with `+`, or use `format(\"...{}...\", value)`".to_string(),
+            ));
+        }
+        let Some(next) = tokens.get(index + 1) else {
+            continue;
+        };
+        match (&token.token_type, &next.token_type) {
+            (TokenType::String, TokenType::String) => diagnostics.push(warning(
+                &codes::ADJACENT_STRINGS,
+                "Two strings in a row aren't joined".to_string(),
+                token.span.to(next.span),
+                "put `+` between them to join them".to_string(),
+            )),
+            (TokenType::Number, name)
+                if next.span.start == token.span.end && (*name == TokenType::Identifier || is_keyword(name)) =>
+            {
+                diagnostics.push(warning(
+                    &codes::NUMBER_BEFORE_NAME,
+                    format!("The number {} is written right against '{}'", token.value, next.value),
+                    token.span.to(next.span),
+                    format!("write `{} * {}` to multiply them, or put a space between them", token.value, next.value),
+                ))
+            }
+            _ => {}
+        }
+    }
+    diagnostics
+}
+
+/// The lexer's `warnings`, like one for each no-break space it read as a
+/// space, as diagnostics.
+pub fn lint_lexer_warnings(warnings: &[LexError]) -> Vec<Diagnostic> {
+    warnings
+        .iter()
+        .map(|lex_warning| Diagnostic {
+            severity: Severity::Warning,
+            code: lex_warning.code,
+            message: lex_warning.message.clone(),
+            span: lex_warning.span,
+            suggestion: (lex_warning.code == &codes::NO_BREAK_SPACE)
+                .then(|| "replace it with an ordinary space".to_string()),
+        })
+        .collect()
+}
+
+// whether `text` has a `${` closed by a later `}`
+fn looks_interpolated(text: &str) -> bool {
+    text.find("${").is_some_and(|start| text[start + 2..].contains('}'))
+}
+
+fn warning(code: &'static Code, message: String, span: Span, suggestion: String) -> Diagnostic {
+    Diagnostic {
+        severity: Severity::Warning,
+        code,
+        message,
+        span,
+        suggestion: Some(suggestion),
+    }
+}
@@ -0,0 +1,455 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+use crate::ast::{FunctionDecl, Stmt, StmtKind};
+use crate::builtins;
+use crate::checker::{Diagnostic, Severity, check_source, check_source_file};
+use crate::completion::{CompletionKind, complete};
+use crate::doc::signature;
+use crate::highlight::{HighlightKind, highlight};
+use crate::json::Json;
+use crate::lexer::{Lexer, Span, Token, TokenType};
+use crate::parser::Parser;
+use crate::source::{Columns, LineIndex};
+
+// JSON-RPC error codes
+const PARSE_ERROR: i32 = -32700;
+const INVALID_REQUEST: i32 = -32600;
+const METHOD_NOT_FOUND: i32 = -32601;
+
+// the semantic token types the server reports, in the order of the legend
+// it sends in `initialize`; a token's type is its index here
+const TOKEN_TYPES: [&str; 6] = ["keyword", "variable", "number", "string", "comment", "operator"];
+
+/// What kind of declaration a `DocumentSymbol` is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Function,
+    Class,
+    Method,
+    Variable,
+    Constant,
+}
+
+impl SymbolKind {
+    // the number LSP gives the kind
+    fn number(self) -> usize {
+        match self {
+            SymbolKind::Class => 5,
+            SymbolKind::Method => 6,
+            SymbolKind::Function => 12,
+            SymbolKind::Variable => 13,
+            SymbolKind::Constant => 14,
+        }
+    }
+}
+
+/// A declaration in the outline of a file: where all of it is, where its
+/// name is, and, for a class, its methods.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentSymbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub span: Span,
+    pub name_span: Span,
+    pub children: Vec<DocumentSymbol>,
+}
+
+/// The functions, classes, and variables declared at the top level of
+/// `source`, exported or not, in order. Like `check_source`, this goes on
+/// past errors, leaving out only the statements that didn't parse.
+pub fn document_symbols(source: &str) -> Vec<DocumentSymbol> {
+    let (tokens, _) = Lexer::new(source).tokenize_all();
+    let (program, _) = Parser::new(tokens.clone()).parse_all();
+    program.statements.iter().flat_map(|statement| statement_symbols(statement, &tokens)).collect()
+}
+
+fn statement_symbols(statement: &Stmt, tokens: &[Token]) -> Vec<DocumentSymbol> {
+    let symbol = |name: &str, kind, name_span, children| DocumentSymbol {
+        name: name.to_string(),
+        kind,
+        span: statement.span,
+        name_span,
+        children,
+    };
+    match &statement.without_export().kind {
+        StmtKind::Let { name, constant, .. } => {
+            let kind = if *constant { SymbolKind::Constant } else { SymbolKind::Variable };
+            vec![symbol(name, kind, name_span(tokens, name, statement.span), Vec::new())]
+        }
+        StmtKind::Destructure { pattern, constant, .. } => {
+            let kind = if *constant { SymbolKind::Constant } else { SymbolKind::Variable };
+            pattern.names().into_iter().map(|(name, span)| symbol(name, kind, span, Vec::new())).collect()
+        }
+        StmtKind::Function(declaration) => {
+            vec![symbol(&declaration.name, SymbolKind::Function, name_span(tokens, &declaration.name, statement.span), Vec::new())]
+        }
+        StmtKind::Class(declaration) => {
+            let methods = declaration.methods.iter().map(|method| method_symbol(method, tokens)).collect();
+            vec![symbol(&declaration.name, SymbolKind::Class, name_span(tokens, &declaration.name, statement.span), methods)]
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn method_symbol(method: &FunctionDecl, tokens: &[Token]) -> DocumentSymbol {
+    DocumentSymbol {
+        name: method.name.to_string(),
+        kind: SymbolKind::Method,
+        span: method.span,
+        name_span: name_span(tokens, &method.name, method.span),
+        children: Vec::new(),
+    }
+}
+
+// where `name` is first written in a declaration spanning `span`, which is
+// just after its keyword; the whole declaration if it can't be found
+fn name_span(tokens: &[Token], name: &str, span: Span) -> Span {
+    let first = tokens.partition_point(|token| token.span.start < span.start);
+    tokens[first..]
+        .iter()
+        .take_while(|token| token.span.end <= span.end)
+        .find(|token| token.token_type == TokenType::Identifier && token.value == name)
+        .map_or(span, |token| token.span)
+}
+
+/// A language server for slimescript files, which reports the same
+/// diagnostics as `slimescript --check` as files are opened and edited,
+/// and answers requests for their outline, highlighting, completions, and
+/// what the names in them are.
+/// It keeps the text of each open file, sent whole on every change.
+#[derive(Debug, Default)]
+pub struct Server {
+    documents: HashMap<String, String>,
+    shutting_down: bool,
+    exited: bool,
+}
+
+impl Server {
+    pub fn new() -> Server {
+        Server::default()
+    }
+
+    /// Handles one JSON-RPC message from the client, returning the messages
+    /// to send back in order: the response to a request, and notifications
+    /// like the diagnostics for a changed file.
+    pub fn handle(&mut self, message: &str) -> Vec<String> {
+        let message = match Json::parse(message) {
+            Ok(message) => message,
+            Err(error) => return vec![error_response(Json::Null, PARSE_ERROR, &error)],
+        };
+        let method = message.get("method").and_then(Json::as_str).unwrap_or("");
+        let params = message.get("params").unwrap_or(&Json::Null);
+        let Some(id) = message.get("id").cloned() else {
+            return self.notify(method, params);
+        };
+        if self.shutting_down {
+            return vec![error_response(id, INVALID_REQUEST, "The server is shutting down")];
+        }
+        let result = match method {
+            "initialize" => initialize_result(),
+            "shutdown" => {
+                self.shutting_down = true;
+                Json::Null
+            }
+            "textDocument/documentSymbol" => match self.document(params) {
+                Some(source) => symbols_json(source),
+                None => Json::Null,
+            },
+            "textDocument/semanticTokens/full" => match self.document(params) {
+                Some(source) => Json::object([("data", semantic_tokens_json(source))]),
+                None => Json::Null,
+            },
+            "textDocument/completion" => match self.document(params) {
+                Some(source) => completions_json(source, params.get("position")),
+                None => Json::Null,
+            },
+            "textDocument/hover" => match self.document(params) {
+                Some(source) => hover_json(source, params.get("position")),
+                None => Json::Null,
+            },
+            _ => return vec![error_response(id, METHOD_NOT_FOUND, &format!("Unknown method '{}'", method))],
+        };
+        vec![Json::object([("jsonrpc", "2.0".into()), ("id", id), ("result", result)]).to_string()]
+    }
+
+    /// Whether the client has sent `exit`, after which the server should
+    /// stop.
+    pub fn exited(&self) -> bool {
+        self.exited
+    }
+
+    // notifications get no response, and unknown ones are ignored
+    fn notify(&mut self, method: &str, params: &Json) -> Vec<String> {
+        let document = params.get("textDocument");
+        let Some(uri) = document.and_then(|document| document.get("uri")).and_then(Json::as_str) else {
+            if method == "exit" {
+                self.exited = true;
+            }
+            return Vec::new();
+        };
+        let text = match method {
+            "textDocument/didOpen" => document.and_then(|document| document.get("text")).and_then(Json::as_str),
+            // the server asks for whole files, so the last change holds all
+            // of the text
+            "textDocument/didChange" => params
+                .get("contentChanges")
+                .and_then(Json::as_array)
+                .and_then(|changes| changes.last())
+                .and_then(|change| change.get("text"))
+                .and_then(Json::as_str),
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                return vec![publish_diagnostics(uri, "", &[])];
+            }
+            _ => None,
+        };
+        match text {
+            Some(text) => {
+                // imports are found next to documents saved as files
+                let diagnostics = match uri.strip_prefix("file://") {
+                    Some(path) => check_source_file(text, Path::new(path)),
+                    None => check_source(text),
+                };
+                let notification = publish_diagnostics(uri, text, &diagnostics);
+                self.documents.insert(uri.to_string(), text.to_string());
+                vec![notification]
+            }
+            None => Vec::new(),
+        }
+    }
+
+    fn document(&self, params: &Json) -> Option<&str> {
+        let uri = params.get("textDocument")?.get("uri")?.as_str()?;
+        self.documents.get(uri).map(String::as_str)
+    }
+}
+
+/// Runs a `Server` on messages framed with `Content-Length` headers, as
+/// LSP sends them over stdio, until the client sends `exit` or closes
+/// `input`. Returns whether the client asked the server to shut down
+/// first, without which it should exit with a failure.
+pub fn serve(mut input: impl BufRead, mut output: impl Write) -> io::Result<bool> {
+    let mut server = Server::new();
+    while let Some(message) = read_message(&mut input)? {
+        for reply in server.handle(&message) {
+            write!(output, "Content-Length: {}\r\n\r\n{}", reply.len(), reply)?;
+        }
+        output.flush()?;
+        if server.exited() {
+            break;
+        }
+    }
+    Ok(server.shutting_down)
+}
+
+// `None` once the input has ended
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut length = None;
+    let mut header = String::new();
+    loop {
+        header.clear();
+        if input.read_line(&mut header)? == 0 {
+            return match length {
+                None => Ok(None),
+                Some(_) => Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Input ended in a message header")),
+            };
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("Content-Length")
+        {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+    let Some(length) = length else {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Message has no Content-Length header"));
+    };
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    Ok(Some(String::from_utf8_lossy(&body).into_owned()))
+}
+
+fn error_response(id: Json, code: i32, message: &str) -> String {
+    let error = Json::object([("code", Json::Number(code as f64)), ("message", message.into())]);
+    Json::object([("jsonrpc", "2.0".into()), ("id", id), ("error", error)]).to_string()
+}
+
+fn initialize_result() -> Json {
+    let legend = Json::object([
+        ("tokenTypes", Json::Array(TOKEN_TYPES.iter().map(|&name| name.into()).collect())),
+        ("tokenModifiers", Json::Array(Vec::new())),
+    ]);
+    let capabilities = Json::object([
+        // whole files on every change
+        ("textDocumentSync", Json::Number(1.0)),
+        ("documentSymbolProvider", Json::Bool(true)),
+        ("hoverProvider", Json::Bool(true)),
+        ("completionProvider", Json::object([("triggerCharacters", Json::Array(vec![".".into()]))])),
+        ("semanticTokensProvider", Json::object([("legend", legend), ("full", Json::Bool(true))])),
+    ]);
+    let server_info = Json::object([("name", "slimescript".into()), ("version", env!("CARGO_PKG_VERSION").into())]);
+    Json::object([("capabilities", capabilities), ("serverInfo", server_info)])
+}
+
+fn publish_diagnostics(uri: &str, source: &str, diagnostics: &[Diagnostic]) -> String {
+    let lines = line_index(source);
+    let diagnostics = diagnostics
+        .iter()
+        .map(|diagnostic| {
+            let severity = match diagnostic.severity {
+                Severity::Error => 1.0,
+                Severity::Warning => 2.0,
+            };
+            Json::object([
+                ("range", range(&lines, diagnostic.span)),
+                ("severity", Json::Number(severity)),
+                ("code", diagnostic.code.id.into()),
+                ("source", "slimescript".into()),
+                ("message", diagnostic.message.as_str().into()),
+            ])
+        })
+        .collect();
+    let params = Json::object([("uri", uri.into()), ("diagnostics", Json::Array(diagnostics))]);
+    Json::object([
+        ("jsonrpc", "2.0".into()),
+        ("method", "textDocument/publishDiagnostics".into()),
+        ("params", params),
+    ])
+    .to_string()
+}
+
+fn symbols_json(source: &str) -> Json {
+    fn symbol_json(symbol: &DocumentSymbol, lines: &LineIndex) -> Json {
+        Json::object([
+            ("name", symbol.name.as_str().into()),
+            ("kind", symbol.kind.number().into()),
+            ("range", range(lines, symbol.span)),
+            ("selectionRange", range(lines, symbol.name_span)),
+            ("children", Json::Array(symbol.children.iter().map(|child| symbol_json(child, lines)).collect())),
+        ])
+    }
+    let lines = line_index(source);
+    Json::Array(document_symbols(source).iter().map(|symbol| symbol_json(symbol, &lines)).collect())
+}
+
+// the highlighted spans as LSP's flat array of numbers: for each token, its
+// line and start relative to the token before, its length, its type, and
+// no modifiers; tokens spanning lines are split at each line break, since
+// not every client takes them whole
+fn semantic_tokens_json(source: &str) -> Json {
+    let lines = line_index(source);
+    let mut data = Vec::new();
+    let (mut previous_line, mut previous_start) = (0, 0);
+    for span in highlight(source) {
+        let token_type = match span.kind {
+            HighlightKind::Keyword => 0,
+            HighlightKind::Identifier => 1,
+            HighlightKind::Number => 2,
+            HighlightKind::String => 3,
+            HighlightKind::Comment => 4,
+            HighlightKind::Operator => 5,
+            HighlightKind::Punctuation | HighlightKind::Whitespace | HighlightKind::Error => continue,
+        };
+        let mut start = span.start;
+        for piece in source[span.start..span.end].split('\n') {
+            let piece = piece.strip_suffix('\r').unwrap_or(piece);
+            if !piece.is_empty() {
+                let (line, character) = position(&lines, start);
+                let delta_start = if line == previous_line { character - previous_start } else { character };
+                data.extend([line - previous_line, delta_start, utf16_len(piece), token_type, 0]);
+                (previous_line, previous_start) = (line, character);
+            }
+            start += piece.len() + 1;
+        }
+    }
+    Json::Array(data.into_iter().map(Json::from).collect())
+}
+
+// the completions at `position`, a zero-based line and UTF-16 character
+fn completions_json(source: &str, position: Option<&Json>) -> Json {
+    let Some(offset) = offset(&line_index(source), position) else {
+        return Json::Null;
+    };
+    let items = complete(source, offset)
+        .into_iter()
+        .map(|completion| {
+            // LSP's CompletionItemKind
+            let kind = match completion.kind {
+                CompletionKind::Function | CompletionKind::Builtin => 3,
+                CompletionKind::Key => 5,
+                CompletionKind::Variable => 6,
+                CompletionKind::Class => 7,
+                CompletionKind::Keyword => 14,
+                CompletionKind::Constant => 21,
+            };
+            Json::object([("label", completion.label.as_str().into()), ("kind", kind.into())])
+        })
+        .collect();
+    Json::Array(items)
+}
+
+// what the name at `position` is: the signature and doc comment of a
+// function declared at the top level, or how many arguments a builtin takes
+fn hover_json(source: &str, position: Option<&Json>) -> Json {
+    let lines = line_index(source);
+    let (tokens, _) = Lexer::new(source).tokenize_all();
+    let Some(name) = offset(&lines, position).and_then(|offset| {
+        tokens.iter().find(|token| {
+            token.token_type == TokenType::Identifier && token.span.start <= offset && offset <= token.span.end
+        })
+    }) else {
+        return Json::Null;
+    };
+    let (program, _) = Parser::new(tokens.clone()).parse_all();
+    let declared = program.statements.iter().find_map(|statement| match &statement.without_export().kind {
+        StmtKind::Function(declaration) if declaration.name.as_str() == name.value => Some(declaration),
+        _ => None,
+    });
+    let text = match (declared, builtins::find(&name.value)) {
+        (Some(declaration), _) => {
+            let mut text = format!("```\nfunction {}\n```", signature(declaration, source));
+            if let Some(doc) = &declaration.doc {
+                text.push_str(&format!("\n\n{}", doc));
+            }
+            text
+        }
+        (None, Some(builtin)) => format!("`{}`, a builtin taking {}", builtin.name, builtin.arity.describe()),
+        (None, None) => return Json::Null,
+    };
+    let contents = Json::object([("kind", "markdown".into()), ("value", text.as_str().into())]);
+    Json::object([("contents", contents), ("range", range(&lines, name.span))])
+}
+
+/// An index of `source` counting columns as LSP positions do.
+fn line_index(source: &str) -> LineIndex<'_> {
+    LineIndex::new(source).with_columns(Columns::Utf16)
+}
+
+// LSP positions are zero-based, where spans and `LineIndex` start at 1
+fn position(lines: &LineIndex, offset: usize) -> (usize, usize) {
+    let (line, column) = lines.offset_to_position(offset);
+    (line - 1, column - 1)
+}
+
+// the byte offset of `position`, a zero-based line and UTF-16 character
+fn offset(lines: &LineIndex, position: Option<&Json>) -> Option<usize> {
+    let number = |key| position.and_then(|position| position.get(key)).and_then(Json::as_number);
+    lines.position_to_offset(number("line")? as usize + 1, number("character")? as usize + 1)
+}
+
+fn range(lines: &LineIndex, span: Span) -> Json {
+    let position = |offset| {
+        let (line, character) = position(lines, offset);
+        Json::object([("line", line.into()), ("character", character.into())])
+    };
+    Json::object([("start", position(span.start)), ("end", position(span.end))])
+}
+
+fn utf16_len(text: &str) -> usize {
+    text.chars().map(char::len_utf16).sum()
+}
@@ -1,147 +1,1173 @@
-use core::str;
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use regex::Regex;
+use slimescript::{
+    Backend, CheckOptions, Completion, Error, FileReport, FileStatus, IntOverflow, Interpreter, InterpreterConfig,
+    Output, SourceFile, Trace,
+};
+use slimescript::checker::{self, Diagnostic, Severity};
+use slimescript::coverage::FileCoverage;
+use slimescript::profile::FnStats;
+use slimescript::bench::{self, Phases, Summary};
+use slimescript::{codes, compiler, formatter, lint, lsp};
+use slimescript::codes::Code;
+use slimescript::disassembler::disassemble;
+use slimescript::lexer::Lexer;
+use slimescript::printer::{self, json_string};
+use slimescript::report::{self, Report};
+use slimescript::watch::{self, Poller};
 
-fn is_opening_parenthesis(character: char) -> bool {
-    character == '('
-}
-fn is_closing_parenthesis(character: char) -> bool {
-    character == ')'
+// exit codes from sysexits.h, which other interpreters use too
+const EXIT_USAGE: u8 = 64;
+const EXIT_COMPILE_ERROR: u8 = 65;
+const EXIT_NO_INPUT: u8 = 66;
+const EXIT_RUNTIME_ERROR: u8 = 70;
+const EXIT_IO_ERROR: u8 = 74;
+
+// the parser and the call-depth limit keep recursion bounded, but each level
+// takes far more stack in a debug build than the main thread is given
+const STACK_SIZE: usize = 64 * 1024 * 1024;
+
+// set by --no-color, which can come anywhere in the arguments
+static NO_COLOR_FLAG: AtomicBool = AtomicBool::new(false);
+
+fn main() -> ExitCode {
+    match thread::Builder::new().stack_size(STACK_SIZE).spawn(dispatch) {
+        // a panic has already printed its message
+        Ok(handle) => handle.join().unwrap_or(ExitCode::FAILURE),
+        Err(error) => {
+            eprintln!("Could not start the interpreter: {}", error);
+            ExitCode::FAILURE
+        }
+    }
 }
-fn is_parenthesis(character: char) -> bool {
-    is_opening_parenthesis(character) || is_closing_parenthesis(character)
+
+fn dispatch() -> ExitCode {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    // everything after `--` belongs to the script
+    let script_args = args.iter().position(|arg| arg == "--").unwrap_or(args.len());
+    if let Some(position) = args[..script_args].iter().position(|arg| arg == "--no-color") {
+        args.remove(position);
+        NO_COLOR_FLAG.store(true, Ordering::Relaxed);
+    }
+    match args.split_first() {
+        Some((command, options)) if command == "run" => run(options),
+        Some((command, [path])) if command == "disasm" => disasm(path),
+        Some((command, options)) if command == "fmt" => fmt(options),
+        Some((command, options)) if command == "doc" => doc(options),
+        Some((command, options)) if command == "check" => check_paths(options),
+        Some((command, options)) if command == "test" => test_paths(options),
+        Some((command, options)) if command == "bench" => bench(options),
+        Some((command, [code])) if command == "explain" => explain(code),
+        Some((command, [])) if command == "lsp" => lsp(),
+        Some((command, [])) if command == "--help" || command == "-h" => {
+            println!("{}", USAGE);
+            ExitCode::SUCCESS
+        }
+        // `slimescript file.slime` is short for `slimescript run file.slime`
+        Some(_) => run(&args),
+        // piped input is a program to run
+        None if !io::stdin().is_terminal() => run(&["-".to_string()]),
+        None => match interactive(&mut Interpreter::new().with_config(cli_config()), color(io::stdout().is_terminal())) {
+            Ok(Completion::Finished) => ExitCode::SUCCESS,
+            Ok(Completion::Exit(code)) => ExitCode::from(exit_status(code)),
+            Err(error) => {
+                eprintln!("{}", error);
+                ExitCode::FAILURE
+            }
+        },
+    }
 }
-fn is_letter(character: char) -> bool {
-    let letter: Regex = Regex::new(r"[a-zA-Z]").unwrap();
-    letter.is_match(&character.to_string())
+
+/// Runs the REPL in the terminal, with a line editor unless the binary
+/// was built without one.
+#[cfg(feature = "editor")]
+fn interactive(interpreter: &mut Interpreter, color: bool) -> io::Result<Completion> {
+    slimescript::editor::run(interpreter, color)
 }
-fn is_whitespace(character: char) -> bool {
-    let whitespace: Regex = Regex::new(r"\s+").unwrap();
-    whitespace.is_match(&character.to_string())
+
+#[cfg(not(feature = "editor"))]
+fn interactive(interpreter: &mut Interpreter, color: bool) -> io::Result<Completion> {
+    slimescript::repl::run(interpreter, color)
 }
-fn is_number(character: char) -> bool {
-    let number: Regex = Regex::new(r"^[0-9]+$").unwrap();
-    number.is_match(&character.to_string())
+
+/// Scripts run from the command line may use files and environment
+/// variables, unlike ones run by a program embedding the interpreter, and
+/// recurse as deep as the stack `main` gives them allows.
+fn cli_config() -> InterpreterConfig {
+    InterpreterConfig {
+        stack_size: Some(STACK_SIZE),
+        allow_fs: true,
+        allow_env: true,
+        ..InterpreterConfig::default()
+    }
 }
-fn is_quote(character: char) -> bool {
-    character == '"'
+
+const USAGE: &str = "Usage: slimescript [--no-color]
+       slimescript --help
+       slimescript [run] [--vm] [--optimize] [--trace [--trace-limit N]]
+                         [--coverage | --coverage-json] [--profile] [--watch]
+                         [--quiet] [--allow <warning>]... [--output human|json]
+                         [--int-overflow promote|error|wrap|saturate]
+                         <file | -> [-- <args>...]
+       slimescript --check [--format human|json] [--allow <warning>]... <file | ->
+       slimescript check [--jobs N] [--stop-on-error] [--format human|json]
+                         [--allow <warning>]... <file | dir>...
+       slimescript --tokens [--json] <file | ->
+       slimescript --ast [--json] [--spans] <file | ->
+       slimescript test [--vm] <file | dir>...
+       slimescript bench [--vm] [--iterations N] [--json] <file | ->
+       slimescript disasm <file | ->
+       slimescript fmt [--check] <file | ->
+       slimescript doc [--format md] <file | ->
+       slimescript explain <code>
+       slimescript lsp
+
+--quiet hides warnings before running, and --allow hides one kind of
+warning, named like assign-in-condition or numbered like W0005.
+--output json puts everything on stdout as one JSON event per line: a
+\"print\" or \"prompt\" for the script's output, a \"diagnostic\" for each
+warning or error, including a script that can't be read, and a \"result\"
+with the exit code last.
+--int-overflow says what integer arithmetic past the range of a 64-bit
+integer gives: a fractional number, the default, an error, or the result
+wrapped around or stopped at the end of the range.
+
+Exit status: 0 on success, the script's code if it calls exit(code),
+64 for bad usage, 65 if the script doesn't lex, parse, or check, 66 if it
+can't be read, 70 for a runtime error, and 74 for other I/O errors.";
+
+fn run(options: &[String]) -> ExitCode {
+    run_script(options, &mut Vec::new())
 }
-fn is_operator(character: char) -> bool {
-    let operators: Vec<char> = vec!['+', '-', '*', '/', '%'];
-    return operators.contains(&character)
+
+/// Runs a script as `run` does, adding the paths of the files it imported
+/// to `imported`.
+fn run_script(arguments: &[String], imported: &mut Vec<PathBuf>) -> ExitCode {
+    let start = Instant::now();
+    let (options, script_args) = match arguments.iter().position(|option| option == "--") {
+        Some(position) => (&arguments[..position], &arguments[position + 1..]),
+        None => (arguments, &[][..]),
+    };
+    let mut interpreter = Interpreter::new()
+        .with_config(cli_config())
+        .with_args(script_args.to_vec());
+    let mut check_only = false;
+    let mut json_diagnostics = None;
+    let mut tokens = false;
+    let mut ast = false;
+    let mut json = false;
+    let mut spans = false;
+    let mut vm = false;
+    let mut trace = false;
+    let mut trace_limit = None;
+    let mut coverage = None;
+    let mut profile = false;
+    let mut watch = false;
+    let mut quiet = false;
+    let mut allow = Vec::new();
+    let mut events = Events::Human;
+    let mut int_overflow = IntOverflow::default();
+    let mut path = None;
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--vm" => {
+                vm = true;
+                interpreter = interpreter.with_backend(Backend::Vm);
+            }
+            "--optimize" => interpreter = interpreter.with_constant_folding(true),
+            "--check" => check_only = true,
+            "--format" => {
+                json_diagnostics = match options.next().map(String::as_str) {
+                    Some("human") => Some(false),
+                    Some("json") => Some(true),
+                    _ => {
+                        eprintln!("{}", USAGE);
+                        return ExitCode::from(EXIT_USAGE);
+                    }
+                }
+            }
+            "--tokens" => tokens = true,
+            "--ast" => ast = true,
+            "--json" => json = true,
+            "--spans" => spans = true,
+            "--trace" => trace = true,
+            "--coverage" => coverage = Some(false),
+            "--coverage-json" => coverage = Some(true),
+            "--profile" => profile = true,
+            "--watch" => watch = true,
+            "--quiet" => quiet = true,
+            "--allow" => match allowed_warning(options.next()) {
+                Some(code) => allow.push(code),
+                None => return ExitCode::from(EXIT_USAGE),
+            },
+            "--output" => {
+                events = match options.next().map(String::as_str) {
+                    Some("human") => Events::Human,
+                    Some("json") => Events::Json,
+                    _ => {
+                        eprintln!("{}", USAGE);
+                        return ExitCode::from(EXIT_USAGE);
+                    }
+                }
+            }
+            "--trace-limit" => match options.next().and_then(|limit| limit.parse().ok()) {
+                Some(limit) => trace_limit = Some(limit),
+                None => {
+                    eprintln!("{}", USAGE);
+                    return ExitCode::from(EXIT_USAGE);
+                }
+            },
+            "--int-overflow" => {
+                int_overflow = match options.next().map(String::as_str) {
+                    Some("promote") => IntOverflow::Promote,
+                    Some("error") => IntOverflow::Error,
+                    Some("wrap") => IntOverflow::Wrap,
+                    Some("saturate") => IntOverflow::Saturate,
+                    _ => {
+                        eprintln!("{}", USAGE);
+                        return ExitCode::from(EXIT_USAGE);
+                    }
+                }
+            }
+            _ if path.is_none() && !option.starts_with("--") => path = Some(option),
+            _ => {
+                eprintln!("{}", USAGE);
+                return ExitCode::from(EXIT_USAGE);
+            }
+        }
+    }
+    // --json and --spans only change how tokens or the tree are printed,
+    // --format how --check prints what it finds, and --output how a script
+    // that runs once does; only the tree-walker traces
+    let misplaced = (json && !(tokens || ast))
+        || (events == Events::Json && (tokens || ast || check_only || watch))
+        || (spans && !ast)
+        || (json_diagnostics.is_some() && !check_only)
+        || (quiet && check_only)
+        || (trace_limit.is_some() && !trace)
+        || (trace && vm)
+        || (watch && path.is_some_and(|path| path == "-"));
+    let (Some(path), false) = (path, misplaced) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(EXIT_USAGE);
+    };
+    if watch {
+        return watch_script(arguments, path);
+    }
+    let source = match read_to_string(path) {
+        Ok(source) => source,
+        Err(error) => {
+            events.unreadable(path, &error);
+            events.finish(EXIT_NO_INPUT, start.elapsed());
+            return ExitCode::from(EXIT_NO_INPUT);
+        }
+    };
+    if events == Events::Json {
+        interpreter = interpreter.with_output(PrintEvents);
+    }
+    // imports are found relative to the script, or the working directory
+    // for stdin
+    if path != "-" {
+        interpreter = interpreter.with_script_path(path);
+    }
+    let path = source_name(path);
+    let mut config = InterpreterConfig { int_overflow, ..cli_config() };
+    if trace {
+        // on stderr, apart from what the script prints
+        let trace = Trace::new(io::stderr());
+        config.trace = Some(match trace_limit {
+            Some(limit) => trace.with_limit(limit),
+            None => trace,
+        });
+    }
+    interpreter = interpreter.with_config(config);
+    if coverage.is_some() {
+        interpreter = interpreter.with_coverage(true);
+    }
+    if profile {
+        interpreter = interpreter.with_profiling(true);
+    }
+    if tokens {
+        return print_tokens(path, &source, json);
+    }
+    if check_only && !ast {
+        return check(path, &source, json_diagnostics == Some(true), &allow);
+    }
+
+    // the script only runs once the checker finds nothing wrong with it
+    let shown = |mut diagnostics: Vec<Diagnostic>| {
+        checker::remove_allowed(&mut diagnostics, &allow);
+        if quiet {
+            diagnostics.retain(|diagnostic| diagnostic.severity == Severity::Error);
+        }
+        diagnostics
+    };
+    let status = 'run: {
+        let mut lexer = Lexer::new(&source);
+        let tokens = match lexer.tokenize() {
+            Ok(tokens) => tokens,
+            Err(error) => {
+                events.report(Error::Lex(error).report(), &source, path);
+                break 'run EXIT_COMPILE_ERROR;
+            }
+        };
+        let mut lints = lint::lint_tokens(&tokens);
+        lints.extend(lint::lint_lexer_warnings(lexer.warnings()));
+        let program = match slimescript::parse_tokens(tokens) {
+            Ok(program) => program,
+            Err(error) => {
+                // a warning about the tokens may explain the error
+                for diagnostic in shown(lints) {
+                    events.report(diagnostic.report(), &source, path);
+                }
+                events.report(error.report(), &source, path);
+                break 'run EXIT_COMPILE_ERROR;
+            }
+        };
+        if ast {
+            return write_stdout(|out| match json {
+                true => writeln!(out, "{}", printer::ast_json(&program, spans)),
+                false => write!(out, "{}", printer::print_ast(&program, spans)),
+            });
+        }
+        let mut diagnostics = lints;
+        diagnostics.extend(match path {
+            "<stdin>" => checker::check(&program),
+            path => checker::check_file(&program, Path::new(path)),
+        });
+        diagnostics.sort_by_key(|diagnostic| (diagnostic.span.line, diagnostic.span.column));
+        let diagnostics = shown(diagnostics);
+        for diagnostic in &diagnostics {
+            events.report(diagnostic.report(), &source, path);
+        }
+        if diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error) {
+            break 'run EXIT_COMPILE_ERROR;
+        }
+
+        // a failed `assert` or any other runtime error exits non-zero
+        let result = interpreter.eval_file(SourceFile::new(path, source.as_str()));
+        imported.extend(interpreter.imported_paths().map(Path::to_path_buf));
+        if let (Some(json), Some(files)) = (coverage, interpreter.coverage()) {
+            print_coverage(&interpreter, &files, json);
+        }
+        if profile {
+            let stats = interpreter.take_profile();
+            print_profile(&interpreter, &stats);
+        }
+        match result {
+            Ok(_) => 0,
+            Err(Error::Runtime(error)) if let Some(code) = error.exit_code() => exit_status(code),
+            Err(error) => {
+                // the error may be in a file the script imports
+                let report = interpreter.report(&error);
+                let (path, source) = match interpreter.sources().get(report.span.file) {
+                    Some(file) => (file.name.as_str(), file.contents.as_str()),
+                    None => (path, source.as_str()),
+                };
+                events.report(report, source, path);
+                exit_code(&error)
+            }
+        }
+    };
+    events.finish(status, start.elapsed());
+    ExitCode::from(status)
 }
 
-struct Token {
-    token_type: String,
-    value: String,
+/// How `run` shows what it finds wrong with a script and how the script
+/// ended: rendered on stderr, or with `--output json` as JSON events on
+/// stdout, one per line, among the script's `PrintEvents`. Each is written
+/// as it happens.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Events {
+    Human,
+    Json,
 }
 
-fn tokenize(input: &str) -> Vec<Token> {
-    let mut tokens = Vec::new();
-    let length = input.len();
-    let mut cursor = 0;
-    while cursor < length {
-        let character = input.chars().nth(cursor).unwrap();
+impl Events {
+    fn report(self, report: Report, source: &str, path: &str) {
+        match self {
+            Events::Human => eprint!("{}", render(report, source, path)),
+            Events::Json => println!("{{\"type\": \"diagnostic\", {}}}", report_fields(&report, source, path)),
+        }
+    }
 
-        if is_parenthesis(character) {
-            tokens.push(Token {
-                token_type: "parenthesis".into(),
-                value: character.to_string(),
-            });
-            cursor += 1;
-            continue;
+    /// Says the script at `path` couldn't be read, which as a JSON event is
+    /// an `io-failed` diagnostic with no position.
+    fn unreadable(self, path: &str, error: &io::Error) {
+        let message = format!("Could not read {}: {}", source_name(path), error);
+        match self {
+            Events::Human => eprintln!("{}", message),
+            Events::Json => println!(
+                "{{\"type\": \"diagnostic\", \"file\": {}, \"line\": null, \"column\": null, \"end_line\": null, \
+                 \"end_column\": null, \"severity\": \"error\", \"code\": {}, \"name\": {}, \"message\": {}, \
+                 \"suggestion\": null}}",
+                json_string(source_name(path)),
+                json_string(codes::IO_FAILED.id),
+                json_string(codes::IO_FAILED.name),
+                json_string(&message)
+            ),
         }
+    }
 
-        if is_whitespace(character) {
-            // skip whitespace
-            cursor += 1;
-            continue;
+    /// Ends the events with the status the process exits with.
+    fn finish(self, status: u8, elapsed: Duration) {
+        if self == Events::Json {
+            println!(
+                "{{\"type\": \"result\", \"exit_code\": {}, \"duration_ms\": {:.3}}}",
+                status,
+                elapsed.as_secs_f64() * 1000.0
+            );
         }
+    }
+}
 
-        if is_number(character) {
-            let mut number = character.to_string();
+/// Sends what a script prints to stdout as `--output json` events: a
+/// `print` event for each line `print` writes and a `prompt` event for each
+/// prompt `input()` shows.
+struct PrintEvents;
 
-            // account for multi-digit numbers
-            cursor += 1;
-            while is_number(input.chars().nth(cursor).unwrap()) {
-                number.push(input.chars().nth(cursor).unwrap());
-                cursor += 1;
+impl Output for PrintEvents {
+    fn line(&mut self, line: &str) -> io::Result<()> {
+        writeln!(io::stdout(), "{{\"type\": \"print\", \"text\": {}}}", json_string(line))
+    }
+
+    fn prompt(&mut self, prompt: &str) -> io::Result<()> {
+        writeln!(io::stdout(), "{{\"type\": \"prompt\", \"text\": {}}}", json_string(prompt))
+    }
+}
+
+/// Runs the script at `path` with `arguments` but `--watch`, then again
+/// each time it or a file it imports changes, clearing the screen first
+/// when stdout is a terminal. Errors are shown and watching goes on; it
+/// only ends when interrupted.
+fn watch_script(arguments: &[String], path: &str) -> ExitCode {
+    let mut arguments = arguments.to_vec();
+    if let Some(position) = arguments.iter().position(|argument| argument == "--watch") {
+        arguments.remove(position);
+    }
+    let clear = io::stdout().is_terminal();
+    watch::watch(&mut Poller::default(), |changed| {
+        if clear {
+            print!("\x1b[2J\x1b[H");
+            let _ = io::stdout().flush();
+        }
+        match changed {
+            [] => eprintln!("[{}] Running {}", time_of_day(), path),
+            changed => {
+                let names: Vec<String> = changed.iter().map(|file| file.display().to_string()).collect();
+                eprintln!("[{}] Running {} after changes to {}", time_of_day(), path, names.join(", "));
             }
+        }
+        // the script is watched even when it can't be read, until it can
+        let mut files = vec![PathBuf::from(path)];
+        let status = match run_script(&arguments, &mut files) == ExitCode::SUCCESS {
+            true => "Finished",
+            false => "Failed",
+        };
+        eprintln!("[{}] {}; watching for changes (Ctrl-C to stop)", time_of_day(), status);
+        files
+    });
+    ExitCode::SUCCESS
+}
 
-            tokens.push(Token {
-                token_type: "number".into(),
-                value: number,
-            });
+// the time now in UTC, as HH:MM:SS
+fn time_of_day() -> String {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() % 86400;
+    format!("{:02}:{:02}:{:02} UTC", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+/// Prints how much of each file ran to stderr, apart from the script's
+/// output: a summary line and a listing marking the lines that never ran
+/// with `!`, or with `json`, one object per file per line with `file`,
+/// `covered`, `coverable`, `covered_lines`, and `uncovered_lines` keys.
+fn print_coverage(interpreter: &Interpreter, files: &[FileCoverage], json: bool) {
+    for coverage in files {
+        let covered = coverage.covered();
+        let coverable = coverage.lines.len();
+        if json {
+            let lines = |executed: bool| {
+                let lines: Vec<String> = coverage
+                    .lines
+                    .iter()
+                    .filter(|(_, ran)| *ran == executed)
+                    .map(|(line, _)| line.to_string())
+                    .collect();
+                format!("[{}]", lines.join(", "))
+            };
+            eprintln!(
+                "{{\"file\": {}, \"covered\": {}, \"coverable\": {}, \"covered_lines\": {}, \"uncovered_lines\": {}}}",
+                json_string(&coverage.name),
+                covered,
+                coverable,
+                lines(true),
+                lines(false)
+            );
             continue;
         }
+        let percent = if coverable == 0 { 100.0 } else { covered as f64 * 100.0 / coverable as f64 };
+        eprintln!("{}: {} of {} lines covered ({:.1}%)", coverage.name, covered, coverable, percent);
+        let Some(file) = interpreter.sources().get(coverage.file) else {
+            continue;
+        };
+        let width = file.contents.lines().count().to_string().len();
+        let mut lines = coverage.lines.iter().peekable();
+        for (index, text) in file.contents.lines().enumerate() {
+            let number = index + 1;
+            let marker = match lines.next_if(|(line, _)| *line == number) {
+                Some((_, false)) => '!',
+                _ => ' ',
+            };
+            eprintln!("{} {:>width$} | {}", marker, number, text);
+        }
+    }
+}
 
-        if is_letter(character) {
-            let mut identifier = character.to_string();
+/// Prints a table of the calls made to each function to stderr, apart from
+/// the script's output, in the order `take_profile` gives them. Script
+/// functions are shown with where they are declared.
+fn print_profile(interpreter: &Interpreter, stats: &[FnStats]) {
+    let names: Vec<String> = stats
+        .iter()
+        .map(|stats| match stats.span {
+            Some(span) => {
+                let file = interpreter.sources().get(span.file).map_or("<input>", |file| file.name.as_str());
+                format!("{} ({}:{})", stats.name, file, span.line)
+            }
+            None => stats.name.clone(),
+        })
+        .collect();
+    let width = names.iter().map(String::len).max().unwrap_or(0).max("function".len());
+    let milliseconds = |duration: Duration| format!("{:.3} ms", duration.as_secs_f64() * 1000.0);
+    eprintln!("{:<width$}  {:>8}  {:>12}  {:>12}", "function", "calls", "inclusive", "exclusive");
+    for (name, stats) in names.iter().zip(stats) {
+        eprintln!(
+            "{:<width$}  {:>8}  {:>12}  {:>12}",
+            name,
+            stats.calls,
+            milliseconds(stats.inclusive),
+            milliseconds(stats.exclusive)
+        );
+    }
+}
 
-            // account for multi-character identifiers
-            cursor += 1;
-            while cursor < length && is_letter(input.chars().nth(cursor).unwrap()) {
-                identifier.push(input.chars().nth(cursor).unwrap());
-                cursor += 1;
+/// Runs a script `--iterations` times, 10 by default, each in a fresh
+/// interpreter whose output is thrown away, and prints the fastest, median,
+/// and mean time taken to tokenize, parse, and execute it. With `--json`,
+/// prints one JSON object instead, with `file`, `backend`, `iterations`,
+/// and for each of `tokenize`, `parse`, `execute`, and `total`, an object
+/// of `min_ms`, `median_ms`, and `mean_ms`.
+fn bench(options: &[String]) -> ExitCode {
+    let mut backend = Backend::TreeWalker;
+    let mut iterations: usize = 10;
+    let mut json = false;
+    let mut path = None;
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--vm" => backend = Backend::Vm,
+            "--json" => json = true,
+            "--iterations" => match options.next().and_then(|count| count.parse().ok()) {
+                Some(count) if count > 0 => iterations = count,
+                _ => {
+                    eprintln!("{}", USAGE);
+                    return ExitCode::from(EXIT_USAGE);
+                }
+            },
+            _ if path.is_none() && !option.starts_with("--") => path = Some(option.as_str()),
+            _ => {
+                eprintln!("{}", USAGE);
+                return ExitCode::from(EXIT_USAGE);
             }
+        }
+    }
+    let Some(path) = path else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(EXIT_USAGE);
+    };
+    let Some(source) = read_source(path) else {
+        return ExitCode::from(EXIT_NO_INPUT);
+    };
+    let name = source_name(path);
 
-            tokens.push(Token {
-                token_type: "identifier".into(),
-                value: identifier,
-            });
-            continue;
+    let mut runs = Vec::with_capacity(iterations);
+    for _ in 0..iterations {
+        let mut interpreter = Interpreter::with_io(io::empty(), io::sink())
+            .with_config(cli_config())
+            .with_backend(backend);
+        if path != "-" {
+            interpreter = interpreter.with_script_path(path);
+        }
+        match bench::time_phases(&mut interpreter, &source) {
+            Ok(phases) => runs.push(phases),
+            Err(error) => {
+                // the error may be in a file the script imports
+                let report = interpreter.report(&error);
+                let (path, source) = match interpreter.sources().get(report.span.file) {
+                    Some(file) => (file.name.as_str(), file.contents.as_str()),
+                    None => (name, source.as_str()),
+                };
+                eprint!("{}", render(report, source, path));
+                return ExitCode::from(exit_code(&error));
+            }
         }
+    }
+
+    let summary = |time: fn(&Phases) -> Duration| {
+        let samples: Vec<Duration> = runs.iter().map(time).collect();
+        Summary::of(&samples).expect("there is at least one iteration")
+    };
+    let summaries = [
+        ("tokenize", summary(|phases| phases.tokenize)),
+        ("parse", summary(|phases| phases.parse)),
+        ("execute", summary(|phases| phases.execute)),
+        ("total", summary(Phases::total)),
+    ];
+    let backend = match backend {
+        Backend::TreeWalker => "tree-walker",
+        Backend::Vm => "vm",
+    };
+    let milliseconds = |duration: Duration| duration.as_secs_f64() * 1000.0;
+    if json {
+        let phases: Vec<String> = summaries
+            .iter()
+            .map(|(phase, summary)| {
+                format!(
+                    "\"{}\": {{\"min_ms\": {:.6}, \"median_ms\": {:.6}, \"mean_ms\": {:.6}}}",
+                    phase,
+                    milliseconds(summary.min),
+                    milliseconds(summary.median),
+                    milliseconds(summary.mean)
+                )
+            })
+            .collect();
+        println!(
+            "{{\"file\": {}, \"backend\": \"{}\", \"iterations\": {}, {}}}",
+            json_string(name),
+            backend,
+            iterations,
+            phases.join(", ")
+        );
+        return ExitCode::SUCCESS;
+    }
+    println!("{}: {} iteration{} with the {}", name, iterations, plural(iterations), backend);
+    println!("{:<8}  {:>12}  {:>12}  {:>12}", "phase", "min", "median", "mean");
+    for (phase, summary) in &summaries {
+        println!(
+            "{:<8}  {:>9.3} ms  {:>9.3} ms  {:>9.3} ms",
+            phase,
+            milliseconds(summary.min),
+            milliseconds(summary.median),
+            milliseconds(summary.mean)
+        );
+    }
+    ExitCode::SUCCESS
+}
 
-        if is_quote(character) {
-            let mut string = String::new();
+/// Reports everything wrong with `source` without running it, carrying on
+/// past lexer and parse errors, and fails if any of it is an error. With
+/// `json`, prints one JSON object per line for each diagnostic instead, with
+/// `file`, `line`, `column`, `end_line`, `end_column`, `severity`, `code`
+/// (like "E0200"), `name` (like "undefined-variable"), `message`, and
+/// `suggestion` (a string, or null) keys. Warnings in `allow` are left out.
+fn check(path: &str, source: &str, json: bool, allow: &[&'static Code]) -> ExitCode {
+    let mut diagnostics = match path {
+        "<stdin>" => checker::check_source(source),
+        path => checker::check_source_file(source, Path::new(path)),
+    };
+    checker::remove_allowed(&mut diagnostics, allow);
+    print_diagnostics(path, source, &diagnostics, json);
+    match diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error) {
+        true => ExitCode::FAILURE,
+        false => ExitCode::SUCCESS,
+    }
+}
 
-            cursor += 1;
-            while !is_quote(input.chars().nth(cursor).unwrap()) {
-                string.push(input.chars().nth(cursor).unwrap());
-                cursor += 1;
+/// Checks many files at once like `--check`, where directories stand for
+/// every `.slime` file under them. `--jobs` sets how many are checked at a
+/// time, one per core by default, and `--stop-on-error` skips the files not
+/// yet started once one fails. Output is in the order the files are given.
+fn check_paths(options: &[String]) -> ExitCode {
+    let mut check_options = CheckOptions::default();
+    let mut json = false;
+    let mut paths = Vec::new();
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        match option.as_str() {
+            "--jobs" => match options.next().and_then(|jobs| jobs.parse().ok()) {
+                Some(jobs) if jobs > 0 => check_options.jobs = jobs,
+                _ => {
+                    eprintln!("{}", USAGE);
+                    return ExitCode::from(EXIT_USAGE);
+                }
+            },
+            "--stop-on-error" => check_options.stop_on_error = true,
+            "--allow" => match allowed_warning(options.next()) {
+                Some(code) => check_options.allow.push(code),
+                None => return ExitCode::from(EXIT_USAGE),
+            },
+            "--format" => {
+                json = match options.next().map(String::as_str) {
+                    Some("human") => false,
+                    Some("json") => true,
+                    _ => {
+                        eprintln!("{}", USAGE);
+                        return ExitCode::from(EXIT_USAGE);
+                    }
+                }
+            }
+            _ if !option.starts_with("--") => paths.push(PathBuf::from(option)),
+            _ => {
+                eprintln!("{}", USAGE);
+                return ExitCode::from(EXIT_USAGE);
             }
+        }
+    }
+    if paths.is_empty() {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(EXIT_USAGE);
+    }
+    let mut files = Vec::new();
+    for path in &paths {
+        if let Err(error) = slime_files(path, &mut files) {
+            eprintln!("Could not read {}: {}", path.display(), error);
+            return ExitCode::from(EXIT_NO_INPUT);
+        }
+    }
 
-            tokens.push(Token {
-                token_type: "string".into(),
-                value: string,
-            });
-            cursor += 1; // skip the closing quote
+    let reports = slimescript::check_files_with(&files, &check_options);
+    let mut skipped = 0;
+    for report in &reports {
+        let path = report.path.display().to_string();
+        match &report.status {
+            FileStatus::Checked { source, diagnostics } => print_diagnostics(&path, source, diagnostics, json),
+            FileStatus::Unreadable(error) => eprintln!("Could not read {}: {}", path, error),
+            FileStatus::Skipped => skipped += 1,
+        }
+    }
+    if skipped > 0 {
+        eprintln!("Skipped {} of {} files after an error", skipped, reports.len());
+    }
+    match reports.iter().any(FileReport::failed) {
+        true => ExitCode::FAILURE,
+        false => ExitCode::SUCCESS,
+    }
+}
+
+/// Runs the `test` blocks of many files, where directories stand for every
+/// `.slime` file under them, printing each test's outcome, then what went
+/// wrong in the failures and a summary line like `cargo test`'s. Fails if a
+/// test does, or if a file doesn't get as far as its tests.
+fn test_paths(options: &[String]) -> ExitCode {
+    let mut backend = Backend::TreeWalker;
+    let mut paths = Vec::new();
+    for option in options {
+        match option.as_str() {
+            "--vm" => backend = Backend::Vm,
+            _ if !option.starts_with("--") => paths.push(PathBuf::from(option)),
+            _ => {
+                eprintln!("{}", USAGE);
+                return ExitCode::from(EXIT_USAGE);
+            }
+        }
+    }
+    if paths.is_empty() {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(EXIT_USAGE);
+    }
+    let mut files = Vec::new();
+    for path in &paths {
+        if let Err(error) = slime_files(path, &mut files) {
+            eprintln!("Could not read {}: {}", path.display(), error);
+            return ExitCode::from(EXIT_NO_INPUT);
+        }
+    }
+
+    let start = Instant::now();
+    let color = color(io::stdout().is_terminal());
+    let (mut passed, mut broken_files) = (0, 0);
+    // the name of each failed test and what it failed with
+    let mut failures = Vec::new();
+    for file in &files {
+        let path = file.display().to_string();
+        let source = match fs::read_to_string(file) {
+            Ok(source) => source,
+            Err(error) => {
+                eprintln!("Could not read {}: {}", path, error);
+                broken_files += 1;
+                continue;
+            }
+        };
+        // like `run`, nothing runs if the checker finds an error
+        let diagnostics = checker::check_source_file(&source, file);
+        print_diagnostics(&path, &source, &diagnostics, false);
+        if diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error) {
+            broken_files += 1;
             continue;
         }
+        let mut interpreter = Interpreter::new()
+            .with_config(cli_config())
+            .with_backend(backend)
+            .with_script_path(file);
+        match interpreter.run_tests(SourceFile::new(path.as_str(), source.as_str())) {
+            Ok(results) => {
+                println!("\nrunning {} test{} in {}", results.len(), plural(results.len()), path);
+                for result in results {
+                    let Some(error) = result.error else {
+                        println!("test {} ... ok", result.name);
+                        passed += 1;
+                        continue;
+                    };
+                    println!("test {} ... FAILED", result.name);
+                    let report = interpreter.report(&Error::Runtime(error));
+                    let rendered = match interpreter.sources().get(report.span.file) {
+                        Some(file) => report.render(&file.contents, &file.name, color),
+                        None => report.render(&source, &path, color),
+                    };
+                    failures.push((result.name, rendered));
+                }
+            }
+            Err(Error::Runtime(error)) if let Some(code) = error.exit_code() => {
+                eprintln!("{} called exit({}) instead of finishing its tests", path, code);
+                broken_files += 1;
+            }
+            Err(error) => {
+                let report = interpreter.report(&error);
+                let (path, source) = match interpreter.sources().get(report.span.file) {
+                    Some(file) => (file.name.as_str(), file.contents.as_str()),
+                    None => (path.as_str(), source.as_str()),
+                };
+                eprint!("{}", render(report, source, path));
+                broken_files += 1;
+            }
+        }
+    }
 
-        println!("Unexpected character: {character}");
-        cursor += 1;
-        continue;
+    if !failures.is_empty() {
+        println!("\nfailures:");
+        for (name, rendered) in &failures {
+            print!("\n---- {} ----\n{}", name, rendered);
+        }
+    }
+    if broken_files > 0 {
+        println!("\n{} file{} could not be tested", broken_files, plural(broken_files));
+    }
+    let ok = failures.is_empty() && broken_files == 0;
+    println!(
+        "\ntest result: {}. {} passed; {} failed; finished in {:.2}s",
+        if ok { "ok" } else { "FAILED" },
+        passed,
+        failures.len(),
+        start.elapsed().as_secs_f64()
+    );
+    match ok {
+        true => ExitCode::SUCCESS,
+        false => ExitCode::FAILURE,
     }
+}
 
-    tokens
+fn plural(count: usize) -> &'static str {
+    if count == 1 { "" } else { "s" }
 }
 
+/// Adds `path` to `files` if it's a file, or every `.slime` file under it
+/// in name order if it's a directory.
+fn slime_files(path: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    if !path.is_dir() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+    let mut entries = fs::read_dir(path)?.map(|entry| entry.map(|entry| entry.path())).collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+    for entry in entries {
+        if entry.is_dir() {
+            slime_files(&entry, files)?;
+        } else if entry.extension().is_some_and(|extension| extension == "slime") {
+            files.push(entry);
+        }
+    }
+    Ok(())
+}
 
-fn main() {
-    let input = r#"
-    // this is a comment
-    let numx = 3;
-    let numy = 5;
-    let numz = numx + numy;
-    print(hey);
-    let message = "Hello, World!";
-    print(message);
-    "#;
+/// Prints what `--check` found in `source`, rendered or as JSON.
+fn print_diagnostics(path: &str, source: &str, diagnostics: &[Diagnostic], json: bool) {
+    for diagnostic in diagnostics {
+        match json {
+            true => println!("{{{}}}", report_fields(&diagnostic.report(), source, path)),
+            false => eprint!("{}", render(diagnostic.report(), source, path)),
+        }
+    }
+}
 
-    let mut lexer = Lexer::new(input);
-    
-    match lexer.tokenize() {
-        Ok(tokens) => {
-            println!("Tokens:");
-            for token in tokens {
-                println!("  {:?} '{}' at line {}, column {}", 
-                        token.token_type, token.value, token.line, token.column);
-            }
+/// The keys `--check --format json` and `--output json` give a problem
+/// with a program, for `report` in `source`, without the braces.
+fn report_fields(report: &Report, source: &str, path: &str) -> String {
+    let (end_line, end_column) = report.span.end_position(source);
+    format!(
+        "\"file\": {}, \"line\": {}, \"column\": {}, \"end_line\": {}, \"end_column\": {}, \"severity\": {}, \"code\": {}, \"name\": {}, \"message\": {}, \"suggestion\": {}",
+        json_string(path),
+        report.span.line,
+        report.span.column,
+        end_line,
+        end_column,
+        json_string(match report.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }),
+        json_string(report.code.id),
+        json_string(report.code.name),
+        json_string(&report.message),
+        report.help.as_deref().map_or("null".to_string(), json_string)
+    )
+}
+
+/// Runs the language server over stdin and stdout until the editor tells it
+/// to exit.
+fn lsp() -> ExitCode {
+    match lsp::serve(io::stdin().lock(), io::stdout().lock()) {
+        Ok(true) => ExitCode::SUCCESS,
+        // exiting without being asked to shut down first is an error
+        Ok(false) => ExitCode::FAILURE,
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::from(EXIT_IO_ERROR)
+        }
+    }
+}
+
+/// Prints the explanation of an error code like E0200, which is shown in
+/// brackets after the kind of every error.
+fn explain(code: &str) -> ExitCode {
+    match codes::lookup(code) {
+        Some(code) => {
+            println!("{} ({})\n\n{}", code.id, code.name, code.explanation);
+            ExitCode::SUCCESS
+        }
+        None => {
+            eprintln!("Unknown error code '{}'", code);
+            ExitCode::from(EXIT_USAGE)
         }
+    }
+}
+
+/// The warning `--allow` names, like assign-in-condition or W0005. Prints
+/// why when there isn't one.
+fn allowed_warning(name: Option<&String>) -> Option<&'static Code> {
+    let Some(name) = name else {
+        eprintln!("{}", USAGE);
+        return None;
+    };
+    let code = codes::lookup(name).filter(|code| code.is_warning());
+    if code.is_none() {
+        eprintln!("Unknown warning '{}'; --allow takes a warning's name, like unused-variable, or its code, like W0001", name);
+    }
+    code
+}
+
+// the status a script chose with `exit()`, which only takes 0 to 255
+fn exit_status(code: i32) -> u8 {
+    u8::try_from(code).unwrap_or(EXIT_RUNTIME_ERROR)
+}
+
+fn exit_code(error: &Error) -> u8 {
+    match error {
+        Error::Lex(_) | Error::Parse(_) | Error::Import(_) => EXIT_COMPILE_ERROR,
+        Error::Runtime(_) => EXIT_RUNTIME_ERROR,
+    }
+}
+
+/// Renders `report` with the source it points at, colored when stderr is a
+/// terminal unless color is turned off.
+fn render(report: Report, source: &str, path: &str) -> String {
+    report.render(source, path, color(io::stderr().is_terminal()))
+}
+
+fn color(is_terminal: bool) -> bool {
+    !NO_COLOR_FLAG.load(Ordering::Relaxed) && report::color_enabled(is_terminal)
+}
+
+/// Prints the tokens of `source` one per line, or as a JSON array of
+/// objects with `type`, `value`, `line`, `column`, `start`, and `end` keys.
+fn print_tokens(path: &str, source: &str, json: bool) -> ExitCode {
+    let tokens = match Lexer::new(source).tokenize() {
+        Ok(tokens) => tokens,
+        Err(error) => {
+            eprint!("{}", render(Error::Lex(error).report(), source, path));
+            return ExitCode::from(EXIT_COMPILE_ERROR);
+        }
+    };
+    write_stdout(|out| match json {
+        true => writeln!(out, "{}", printer::tokens_json(&tokens)),
+        false => tokens.iter().try_for_each(|token| writeln!(out, "{}", token)),
+    })
+}
+
+/// Writes what a command prints through one lock on stdout. A reader that
+/// stops early, like `head`, closes the pipe, which ends the output
+/// quietly instead of panicking as `println!` would.
+fn write_stdout(write: impl FnOnce(&mut io::StdoutLock) -> io::Result<()>) -> ExitCode {
+    let mut out = io::stdout().lock();
+    match write(&mut out).and_then(|()| out.flush()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) if error.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("Could not write the output: {}", error);
+            ExitCode::from(EXIT_IO_ERROR)
+        }
+    }
+}
+
+/// Formats the script at `path` in place, or prints it formatted if it
+/// comes from stdin. With `--check`, leaves it alone and instead prints how
+/// formatting would change it, failing if it would.
+fn fmt(options: &[String]) -> ExitCode {
+    let (check, path) = match options {
+        [path] => (false, path),
+        [flag, path] if flag == "--check" => (true, path),
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+    let Some(source) = read_source(path) else {
+        return ExitCode::from(EXIT_NO_INPUT);
+    };
+    let name = source_name(path);
+    // formatting a script that doesn't parse could bake in a mistake
+    if let Err(error) = slimescript::parse(&source) {
+        eprint!("{}", render(error.report(), &source, name));
+        return ExitCode::from(EXIT_COMPILE_ERROR);
+    }
+    let formatted = formatter::format_source(&source).expect("a script that parses also lexes");
+
+    if check {
+        if formatted == source {
+            return ExitCode::SUCCESS;
+        }
+        print!("{}", diff(name, &source, &formatted));
+        return ExitCode::FAILURE;
+    }
+    if path == "-" {
+        return write_stdout(|out| out.write_all(formatted.as_bytes()));
+    }
+    if formatted != source
+        && let Err(error) = fs::write(path, &formatted)
+    {
+        eprintln!("Could not write {}: {}", path, error);
+        return ExitCode::from(EXIT_IO_ERROR);
+    }
+    ExitCode::SUCCESS
+}
+
+/// Prints a Markdown page of the functions declared at the top of a
+/// script, with their `///` doc comments. Markdown is the only format.
+fn doc(options: &[String]) -> ExitCode {
+    let path = match options {
+        [path] => path,
+        [flag, format, path] if flag == "--format" && format == "md" => path,
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+    let Some(source) = read_source(path) else {
+        return ExitCode::from(EXIT_NO_INPUT);
+    };
+    let name = source_name(path);
+    match slimescript::parse(&source) {
+        Ok(program) => {
+            write_stdout(|out| write!(out, "{}", slimescript::doc::markdown(name, &program.statements, &source)))
+        }
+        Err(error) => {
+            eprint!("{}", render(error.report(), &source, name));
+            ExitCode::from(exit_code(&error))
+        }
+    }
+}
+
+/// The lines that differ between `old` and `new`, each group of changes
+/// headed by the line numbers where it starts.
+fn diff(name: &str, old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    // common[i][j] is the length of the longest common subsequence of
+    // old[i..] and new[j..]
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut output = format!("--- {}\n+++ {} (formatted)\n", name, name);
+    let (mut i, mut j) = (0, 0);
+    let mut in_change = false;
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            in_change = false;
+            i += 1;
+            j += 1;
+            continue;
+        }
+        if !in_change {
+            output.push_str(&format!("@@ -{} +{} @@\n", i + 1, j + 1));
+            in_change = true;
+        }
+        if j == new.len() || (i < old.len() && common[i + 1][j] >= common[i][j + 1]) {
+            output.push_str(&format!("-{}\n", old[i]));
+            i += 1;
+        } else {
+            output.push_str(&format!("+{}\n", new[j]));
+            j += 1;
+        }
+    }
+    output
+}
+
+/// Prints the bytecode the script at `path` compiles to, without running it.
+fn disasm(path: &str) -> ExitCode {
+    let Some(source) = read_source(path) else {
+        return ExitCode::from(EXIT_NO_INPUT);
+    };
+    let path = source_name(path);
+    match slimescript::parse(&source) {
+        Ok(program) => write_stdout(|out| write!(out, "{}", disassemble(&compiler::compile(&program)))),
+        Err(error) => {
+            eprint!("{}", render(error.report(), &source, path));
+            ExitCode::from(exit_code(&error))
+        }
+    }
+}
+
+/// Reads the program at `path`, or stdin if `path` is `-`, saying on
+/// stderr why it couldn't.
+fn read_source(path: &str) -> Option<String> {
+    match read_to_string(path) {
+        Ok(source) => Some(source),
         Err(error) => {
-            eprintln!("Lexer error: {}", error);
+            eprintln!("Could not read {}: {}", source_name(path), error);
+            None
         }
     }
+}
+
+/// Reads the program at `path`, or stdin if `path` is `-`.
+fn read_to_string(path: &str) -> io::Result<String> {
+    if path == "-" { io::read_to_string(io::stdin()) } else { fs::read_to_string(path) }
+}
 
+/// The name diagnostics give the program read from `path`.
+fn source_name(path: &str) -> &str {
+    if path == "-" { "<stdin>" } else { path }
 }
@@ -0,0 +1,406 @@
+use std::rc::Rc;
+
+use crate::ast::{Expr, ExprId, ExprKind, Exprs, FunctionDecl, LogicalOp, Program, Stmt, StmtKind};
+use crate::lexer::Span;
+use crate::interpreter::{binary_operation, unary_operation};
+use crate::value::{IntOverflow, Value};
+
+/// Replaces operations on literals with their results, e.g. `60 * 60 * 24`
+/// with `86400` and `"a" + "b"` with `"ab"`, including inside function
+/// bodies. Operations that would fail at runtime, like `1 / 0`, are left as
+/// they are so they still fail at runtime, with the same error, and so is
+/// integer arithmetic that overflows, which the interpreter's `IntOverflow`
+/// decides. Running the pass again changes nothing.
+pub fn fold_constants(program: &mut Program) {
+    fold_statements(&mut program.statements, &mut program.exprs);
+}
+
+/// Whether `condition`, with its operands in `exprs`, is always truthy or
+/// always falsy, going by its value once folded, or `None` if that depends
+/// on the running program.
+pub fn constant_condition(condition: &Expr, exprs: &Exprs) -> Option<bool> {
+    constant_value(condition, exprs).map(|value| value.is_truthy())
+}
+
+/// The literal `fold_expression` would turn `expr` into, if any, worked out
+/// without changing it.
+fn constant_value(expr: &Expr, exprs: &Exprs) -> Option<Value> {
+    match &expr.kind {
+        ExprKind::Unary { operator, operand } => {
+            let operand = constant_value(&exprs[*operand], exprs)?;
+            unary_operation(*operator, operand, IntOverflow::Error, expr.span).ok()
+        }
+        ExprKind::Binary { left, operator, right } => {
+            let left = constant_value(&exprs[*left], exprs)?;
+            let right = constant_value(&exprs[*right], exprs)?;
+            binary_operation(*operator, left, right, IntOverflow::Error, expr.span).ok()
+        }
+        ExprKind::Logical { left, operator, right } => {
+            let value = constant_value(&exprs[*left], exprs)?;
+            let decided = match operator {
+                LogicalOp::And => !value.is_truthy(),
+                LogicalOp::Or => value.is_truthy(),
+            };
+            if decided { Some(value) } else { constant_value(&exprs[*right], exprs) }
+        }
+        _ => literal(expr),
+    }
+}
+
+/// Removes code that can never run, including inside function bodies: the
+/// statements after one that always returns, breaks, continues, or throws
+/// (see `Stmt::diverges`), the branches of an `if` ruled out by a literal
+/// condition, and `while` loops whose condition is a falsy literal. Run it
+/// after `fold_constants`, which turns conditions like `1 > 2` into
+/// literals. Unreachable declarations and `test` blocks are kept, since
+/// removing a declaration could change what a name refers to, and tests
+/// don't run in order with the code around them. Running the pass again
+/// changes nothing.
+pub fn eliminate_dead_code(program: &mut Program) {
+    eliminate_in_statements(&mut program.statements, &mut program.exprs);
+}
+
+fn eliminate_in_statements(statements: &mut Vec<Stmt>, exprs: &mut Exprs) {
+    let mut reachable = true;
+    statements.retain_mut(|statement| {
+        let keep = reachable || is_kept_unreachable(statement);
+        if keep {
+            eliminate_in_statement(statement, exprs);
+            reachable = reachable && !statement.diverges();
+        }
+        // an `if` or `while` that can't run anything became an empty block
+        keep && !matches!(&statement.kind, StmtKind::Block(statements) if statements.is_empty())
+    });
+}
+
+fn is_kept_unreachable(stmt: &Stmt) -> bool {
+    !stmt.declared_names().is_empty()
+        || matches!(stmt.kind, StmtKind::Export(_) | StmtKind::Import { .. } | StmtKind::Test { .. })
+}
+
+fn eliminate_in_statement(stmt: &mut Stmt, exprs: &mut Exprs) {
+    match &mut stmt.kind {
+        StmtKind::Expression(expr) | StmtKind::Throw(expr) => eliminate_in_expression(expr, exprs),
+        StmtKind::Let { initializer, .. } => {
+            initializer.iter_mut().for_each(|initializer| eliminate_in_expression(initializer, exprs))
+        }
+        StmtKind::Destructure { initializer, .. } => eliminate_in_expression(initializer, exprs),
+        StmtKind::Block(statements) | StmtKind::Test { body: statements, .. } => {
+            eliminate_in_statements(statements, exprs)
+        }
+        StmtKind::If { condition, then_branch, else_branch } => {
+            eliminate_in_expression(condition, exprs);
+            eliminate_in_statements(then_branch, exprs);
+            if let Some(else_branch) = else_branch {
+                eliminate_in_statements(else_branch, exprs);
+            }
+            // the branch taken keeps a scope of its own as a block
+            if let Some(value) = literal(condition) {
+                let taken = match value.is_truthy() {
+                    true => std::mem::take(then_branch),
+                    false => else_branch.take().unwrap_or_default(),
+                };
+                stmt.kind = StmtKind::Block(taken);
+            }
+        }
+        StmtKind::While { condition, body, .. } => {
+            eliminate_in_expression(condition, exprs);
+            eliminate_in_statements(body, exprs);
+            if literal(condition).is_some_and(|value| !value.is_truthy()) {
+                stmt.kind = StmtKind::Block(Vec::new());
+            }
+        }
+        StmtKind::DoWhile { body, condition, .. } => {
+            eliminate_in_statements(body, exprs);
+            eliminate_in_expression(condition, exprs);
+        }
+        StmtKind::Match { subject, arms, default } => {
+            eliminate_in_expression(subject, exprs);
+            for arm in arms {
+                arm.values.iter_mut().for_each(|value| eliminate_in_expression(value, exprs));
+                eliminate_in_statements(&mut arm.body, exprs);
+            }
+            if let Some(default) = default {
+                eliminate_in_statements(default, exprs);
+            }
+        }
+        StmtKind::For { initializer, condition, increment, body, .. } => {
+            if let Some(initializer) = initializer {
+                eliminate_in_statement(initializer, exprs);
+            }
+            condition.iter_mut().chain(increment).for_each(|expr| eliminate_in_expression(expr, exprs));
+            eliminate_in_statements(body, exprs);
+        }
+        StmtKind::ForIn { iterable, body, .. } => {
+            eliminate_in_expression(iterable, exprs);
+            eliminate_in_statements(body, exprs);
+        }
+        StmtKind::Function(declaration) => eliminate_in_function(Rc::make_mut(declaration)),
+        StmtKind::Class(declaration) => {
+            for method in &mut Rc::make_mut(declaration).methods {
+                eliminate_in_function(Rc::make_mut(method));
+            }
+        }
+        StmtKind::Return(value) => value.iter_mut().for_each(|value| eliminate_in_expression(value, exprs)),
+        StmtKind::Try { body, handler, .. } => {
+            eliminate_in_statements(body, exprs);
+            eliminate_in_statements(handler, exprs);
+        }
+        StmtKind::Export(declaration) => eliminate_in_statement(declaration, exprs),
+        StmtKind::Break(_) | StmtKind::Continue(_) | StmtKind::Import { .. } => {}
+    }
+}
+
+// only function expressions hold statements, but they can be anywhere
+fn eliminate_in_expression(expr: &mut Expr, exprs: &mut Exprs) {
+    match &mut expr.kind {
+        ExprKind::Number(_)
+        | ExprKind::Int(_)
+        | ExprKind::String(_)
+        | ExprKind::Bool(_)
+        | ExprKind::Null
+        | ExprKind::Variable(_)
+        | ExprKind::This => {}
+        ExprKind::Assign { value, .. } => eliminate_in_operand(*value, exprs),
+        ExprKind::Unary { operand, .. } => eliminate_in_operand(*operand, exprs),
+        ExprKind::Binary { left, right, .. }
+        | ExprKind::Logical { left, right, .. }
+        | ExprKind::Index { object: left, index: right }
+        | ExprKind::SetMember { object: left, value: right, .. }
+        | ExprKind::Range { start: left, end: right, .. } => {
+            eliminate_in_operand(*left, exprs);
+            eliminate_in_operand(*right, exprs);
+        }
+        ExprKind::Call { callee, arguments } => {
+            eliminate_in_operand(*callee, exprs);
+            arguments.iter_mut().for_each(|argument| eliminate_in_expression(argument, exprs));
+        }
+        ExprKind::Array(elements) => elements.iter_mut().for_each(|element| eliminate_in_expression(element, exprs)),
+        ExprKind::Slice { object, start, end, .. } => {
+            eliminate_in_operand(*object, exprs);
+            [start, end].into_iter().flatten().for_each(|bound| eliminate_in_operand(*bound, exprs));
+        }
+        ExprKind::SetIndex { object, index, value, .. } => {
+            eliminate_in_operand(*object, exprs);
+            eliminate_in_operand(*index, exprs);
+            eliminate_in_operand(*value, exprs);
+        }
+        ExprKind::Map(entries) => entries.iter_mut().for_each(|(_, value)| eliminate_in_expression(value, exprs)),
+        ExprKind::Member { object, .. } => eliminate_in_operand(*object, exprs),
+        ExprKind::Function(declaration) => eliminate_in_function(Rc::make_mut(declaration)),
+    }
+}
+
+fn eliminate_in_operand(id: ExprId, exprs: &mut Exprs) {
+    let mut operand = take(exprs, id);
+    eliminate_in_expression(&mut operand, exprs);
+    exprs[id] = operand;
+}
+
+fn eliminate_in_function(declaration: &mut FunctionDecl) {
+    for param in &mut declaration.params {
+        if let Some(default) = &mut param.default {
+            eliminate_in_expression(default, &mut declaration.exprs);
+        }
+    }
+    eliminate_in_statements(&mut declaration.body, &mut declaration.exprs);
+}
+
+fn fold_statements(statements: &mut [Stmt], exprs: &mut Exprs) {
+    for statement in statements {
+        fold_statement(statement, exprs);
+    }
+}
+
+fn fold_statement(stmt: &mut Stmt, exprs: &mut Exprs) {
+    match &mut stmt.kind {
+        StmtKind::Expression(expr) => fold_expression(expr, exprs),
+        StmtKind::Let { initializer, .. } => {
+            if let Some(initializer) = initializer {
+                fold_expression(initializer, exprs);
+            }
+        }
+        StmtKind::Destructure { initializer, .. } => fold_expression(initializer, exprs),
+        StmtKind::Block(statements) => fold_statements(statements, exprs),
+        StmtKind::If { condition, then_branch, else_branch } => {
+            fold_expression(condition, exprs);
+            fold_statements(then_branch, exprs);
+            if let Some(else_branch) = else_branch {
+                fold_statements(else_branch, exprs);
+            }
+        }
+        StmtKind::While { condition, body, .. } => {
+            fold_expression(condition, exprs);
+            fold_statements(body, exprs);
+        }
+        StmtKind::DoWhile { body, condition, .. } => {
+            fold_statements(body, exprs);
+            fold_expression(condition, exprs);
+        }
+        StmtKind::Match { subject, arms, default } => {
+            fold_expression(subject, exprs);
+            for arm in arms {
+                arm.values.iter_mut().for_each(|value| fold_expression(value, exprs));
+                fold_statements(&mut arm.body, exprs);
+            }
+            if let Some(default) = default {
+                fold_statements(default, exprs);
+            }
+        }
+        StmtKind::For { initializer, condition, increment, body, .. } => {
+            if let Some(initializer) = initializer {
+                fold_statement(initializer, exprs);
+            }
+            if let Some(condition) = condition {
+                fold_expression(condition, exprs);
+            }
+            if let Some(increment) = increment {
+                fold_expression(increment, exprs);
+            }
+            fold_statements(body, exprs);
+        }
+        StmtKind::ForIn { iterable, body, .. } => {
+            fold_expression(iterable, exprs);
+            fold_statements(body, exprs);
+        }
+        StmtKind::Function(declaration) => fold_function(Rc::make_mut(declaration)),
+        StmtKind::Class(declaration) => {
+            for method in &mut Rc::make_mut(declaration).methods {
+                fold_function(Rc::make_mut(method));
+            }
+        }
+        StmtKind::Return(value) => {
+            if let Some(value) = value {
+                fold_expression(value, exprs);
+            }
+        }
+        StmtKind::Try { body, handler, .. } => {
+            fold_statements(body, exprs);
+            fold_statements(handler, exprs);
+        }
+        StmtKind::Throw(value) => fold_expression(value, exprs),
+        StmtKind::Export(declaration) => fold_statement(declaration, exprs),
+        StmtKind::Test { body, .. } => fold_statements(body, exprs),
+        StmtKind::Break(_) | StmtKind::Continue(_) | StmtKind::Import { .. } => {}
+    }
+}
+
+fn fold_expression(expr: &mut Expr, exprs: &mut Exprs) {
+    match &mut expr.kind {
+        ExprKind::Number(_)
+        | ExprKind::Int(_)
+        | ExprKind::String(_)
+        | ExprKind::Bool(_)
+        | ExprKind::Null
+        | ExprKind::Variable(_)
+        | ExprKind::This => {}
+        ExprKind::Assign { value, .. } => fold_operand(*value, exprs),
+        ExprKind::Unary { operator, operand } => {
+            fold_operand(*operand, exprs);
+            if let Some(operand) = literal(&exprs[*operand])
+                && let Ok(result) = unary_operation(*operator, operand, IntOverflow::Error, expr.span)
+            {
+                replace_with_literal(expr, result);
+            }
+        }
+        ExprKind::Binary { left, operator, right } => {
+            fold_operand(*left, exprs);
+            fold_operand(*right, exprs);
+            if let (Some(left), Some(right)) = (literal(&exprs[*left]), literal(&exprs[*right]))
+                && let Ok(result) = binary_operation(*operator, left, right, IntOverflow::Error, expr.span)
+            {
+                replace_with_literal(expr, result);
+            }
+        }
+        ExprKind::Logical { left, operator, right } => {
+            fold_operand(*left, exprs);
+            fold_operand(*right, exprs);
+            // the result is whichever operand decides it, so a literal left
+            // operand picks one side without evaluating anything
+            if let Some(value) = literal(&exprs[*left]) {
+                let decided = match operator {
+                    LogicalOp::And => !value.is_truthy(),
+                    LogicalOp::Or => value.is_truthy(),
+                };
+                let result = if decided { *left } else { *right };
+                *expr = take(exprs, result);
+            }
+        }
+        ExprKind::Call { callee, arguments } => {
+            fold_operand(*callee, exprs);
+            arguments.iter_mut().for_each(|argument| fold_expression(argument, exprs));
+        }
+        ExprKind::Array(elements) => elements.iter_mut().for_each(|element| fold_expression(element, exprs)),
+        ExprKind::Index { object, index } => {
+            fold_operand(*object, exprs);
+            fold_operand(*index, exprs);
+        }
+        ExprKind::Slice { object, start, end, .. } => {
+            fold_operand(*object, exprs);
+            [start, end].into_iter().flatten().for_each(|bound| fold_operand(*bound, exprs));
+        }
+        ExprKind::SetIndex { object, index, value, .. } => {
+            fold_operand(*object, exprs);
+            fold_operand(*index, exprs);
+            fold_operand(*value, exprs);
+        }
+        ExprKind::Map(entries) => entries.iter_mut().for_each(|(_, value)| fold_expression(value, exprs)),
+        ExprKind::Member { object, .. } => fold_operand(*object, exprs),
+        ExprKind::SetMember { object, value, .. } => {
+            fold_operand(*object, exprs);
+            fold_operand(*value, exprs);
+        }
+        ExprKind::Range { start, end, .. } => {
+            fold_operand(*start, exprs);
+            fold_operand(*end, exprs);
+        }
+        ExprKind::Function(declaration) => fold_function(Rc::make_mut(declaration)),
+    }
+}
+
+fn fold_operand(id: ExprId, exprs: &mut Exprs) {
+    let mut operand = take(exprs, id);
+    fold_expression(&mut operand, exprs);
+    exprs[id] = operand;
+}
+
+fn fold_function(declaration: &mut FunctionDecl) {
+    for param in &mut declaration.params {
+        if let Some(default) = &mut param.default {
+            fold_expression(default, &mut declaration.exprs);
+        }
+    }
+    fold_statements(&mut declaration.body, &mut declaration.exprs);
+}
+
+/// The value of a literal expression.
+fn literal(expr: &Expr) -> Option<Value> {
+    match &expr.kind {
+        ExprKind::Number(n) => Some(Value::Number(*n)),
+        ExprKind::Int(n) => Some(Value::Int(*n)),
+        ExprKind::String(s) => Some(Value::String(s.as_str().into())),
+        ExprKind::Bool(b) => Some(Value::Bool(*b)),
+        ExprKind::Null => Some(Value::Null),
+        _ => None,
+    }
+}
+
+/// Turns `expr` into a literal of `value`, keeping its span so errors that
+/// mention it still point at the original expression.
+fn replace_with_literal(expr: &mut Expr, value: Value) {
+    expr.kind = match value {
+        Value::Number(n) => ExprKind::Number(n),
+        Value::Int(n) => ExprKind::Int(n),
+        Value::String(s) => ExprKind::String(s.to_string()),
+        Value::Bool(b) => ExprKind::Bool(b),
+        Value::Null => ExprKind::Null,
+        other => unreachable!("operations on literals don't produce {}", other.type_name()),
+    };
+}
+
+/// Moves the expression at `id` out of `exprs`, leaving `null` in its
+/// place, so it can be changed while the operands it refers to are too.
+fn take(exprs: &mut Exprs, id: ExprId) -> Expr {
+    let empty = Expr { kind: ExprKind::Null, span: Span::default() };
+    std::mem::replace(&mut exprs[id], empty)
+}
@@ -0,0 +1,1473 @@
+use std::fmt;
+use std::rc::Rc;
+
+use crate::ast::{
+    BinaryOp, ClassDecl, Expr, ExprKind, Exprs, FunctionDecl, LogicalOp, MatchArm, Parameter, Pattern, Program, Stmt, StmtKind,
+    Type, UnaryOp,
+};
+use crate::codes::{self, Code};
+use crate::lexer::{Span, Token, TokenType, escape_string, is_keyword, parse_integer, parse_number};
+use crate::symbol::Symbol;
+use crate::value::{IntOverflow, Value};
+
+/// How many levels expressions, blocks, and patterns may nest inside each
+/// other: parentheses, brackets, braces, and unary operators. The parser
+/// recurses once for each level, so deeper input is rejected here rather
+/// than overflowing its stack.
+const MAX_NESTING: usize = 200;
+
+/// How deep the syntax tree may get, where each link of an operator chain
+/// like `a + b + c`, of a chain of calls, indexes, and members, and each
+/// arm of an `else if` chain is also a level, as the left side or the else
+/// branch holds the rest of the chain. The parser reads those chains in a
+/// loop and the tree-walker runs them in one, but the checker, optimizer,
+/// and compiler walk the tree recursively, so a chain is only rejected
+/// once it would take that deep, far past anything written by hand.
+const MAX_DEPTH: usize = 10_000;
+
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    pub code: &'static Code,
+    pub message: String,
+    pub span: Span,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at line {}, column {}", self.message, self.span.line, self.span.column)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+pub struct Parser {
+    tokens: Vec<Token>,
+    current: usize,
+    // the labels of the loops enclosing the current statement within the
+    // current function, innermost last, `None` for a loop without one, so
+    // `break`/`continue` outside a loop or with an unknown label are
+    // rejected
+    loops: Vec<Option<Symbol>>,
+    // how many class bodies enclose the current code, so `this` outside a
+    // method is rejected
+    class_depth: usize,
+    // how many levels of nesting enclose the current code, up to
+    // `MAX_NESTING`
+    nesting: usize,
+    // how deep in the syntax tree the current code will be, up to
+    // `MAX_DEPTH`
+    depth: usize,
+    // where the expressions of the current function, or of the program
+    // outside any function, are added
+    exprs: Exprs,
+    // what an integer literal out of range becomes
+    int_overflow: IntOverflow,
+}
+
+impl Parser {
+    /// `tokens` must end with an EOF token, as produced by `Lexer::tokenize`.
+    pub fn new(tokens: Vec<Token>) -> Self {
+        Parser {
+            tokens,
+            current: 0,
+            loops: Vec::new(),
+            class_depth: 0,
+            nesting: 0,
+            depth: 0,
+            exprs: Exprs::new(),
+            int_overflow: IntOverflow::default(),
+        }
+    }
+
+    /// Makes integer literals out of range wrap, saturate, fail, or become
+    /// fractional numbers as `int_overflow` says, as arithmetic on integers
+    /// does when the program runs.
+    pub fn with_int_overflow(mut self, int_overflow: IntOverflow) -> Self {
+        self.int_overflow = int_overflow;
+        self
+    }
+
+    pub fn parse(&mut self) -> Result<Program, ParseError> {
+        let mut statements = Vec::new();
+        while !self.is_at_end() {
+            statements.push(self.top_level_declaration()?);
+        }
+        Ok(Program { statements, exprs: std::mem::take(&mut self.exprs) })
+    }
+
+    /// Parses the tokens as a single expression, with nothing after it, for
+    /// input that is a formula rather than a program. The expression comes
+    /// with the arena its operands are in.
+    pub fn parse_expression(&mut self) -> Result<(Expr, Exprs), ParseError> {
+        let expr = self.expression()?;
+        if !self.is_at_end() {
+            return Err(self.error_at_current(&codes::UNEXPECTED_TOKEN, "Expected the end of the expression"));
+        }
+        Ok((expr, std::mem::take(&mut self.exprs)))
+    }
+
+    /// Parses like `parse`, but after an error skips to the next statement
+    /// at the top level and goes on, so every error can be reported at once.
+    /// The statements that parsed are returned along with the errors.
+    pub fn parse_all(&mut self) -> (Program, Vec<ParseError>) {
+        let (program, errors) = self.parse_all_with_starts();
+        (program, errors.into_iter().map(|(_, error)| error).collect())
+    }
+
+    /// Parses like `parse_all`, pairing each error with the byte offset
+    /// where the statement it ended starts, so a caller can tell whether a
+    /// lexer error earlier in the same statement may have caused it.
+    pub(crate) fn parse_all_with_starts(&mut self) -> (Program, Vec<(usize, ParseError)>) {
+        let mut statements = Vec::new();
+        let mut errors = Vec::new();
+        while !self.is_at_end() {
+            let start = self.current;
+            match self.top_level_declaration() {
+                Ok(statement) => statements.push(statement),
+                Err(error) => {
+                    errors.push((self.tokens[start].span.start, error));
+                    self.synchronize(start);
+                }
+            }
+        }
+        (Program { statements, exprs: std::mem::take(&mut self.exprs) }, errors)
+    }
+
+    /// Skips the rest of a statement that failed to parse, which started at
+    /// token `start`: past the braces it left open, then up to the end of a
+    /// statement or the keyword starting the next one.
+    fn synchronize(&mut self, start: usize) {
+        self.loops.clear();
+        self.class_depth = 0;
+        self.nesting = 0;
+        self.depth = 0;
+        // an error at the first token would otherwise never move past it
+        if self.current == start {
+            self.advance();
+        }
+        let mut depth: isize = self.tokens[start..self.current].iter().map(brace_depth).sum();
+        while !self.is_at_end() {
+            let statement_ended = matches!(self.previous().token_type, TokenType::Semicolon | TokenType::RightBrace);
+            let statement_starts = matches!(
+                self.peek().token_type,
+                TokenType::Let
+                    | TokenType::Const
+                    | TokenType::Function
+                    | TokenType::Class
+                    | TokenType::If
+                    | TokenType::While
+                    | TokenType::Do
+                    | TokenType::Match
+                    | TokenType::Try
+                    | TokenType::Throw
+                    | TokenType::For
+                    | TokenType::Return
+                    | TokenType::Import
+                    | TokenType::Export
+            );
+            if depth <= 0 && (statement_ended || statement_starts) {
+                return;
+            }
+            depth += brace_depth(self.advance());
+        }
+    }
+
+    // token helpers
+
+    fn peek(&self) -> &Token {
+        // the lexer always ends the stream with EOF, so stay on it once reached
+        &self.tokens[self.current.min(self.tokens.len() - 1)]
+    }
+
+    fn previous(&self) -> &Token {
+        &self.tokens[self.current - 1]
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.peek().token_type == TokenType::EOF
+    }
+
+    fn check(&self, token_type: &TokenType) -> bool {
+        &self.peek().token_type == token_type
+    }
+
+    fn check_next(&self, token_type: &TokenType) -> bool {
+        self.tokens
+            .get(self.current + 1)
+            .is_some_and(|token| &token.token_type == token_type)
+    }
+
+    fn advance(&mut self) -> &Token {
+        if !self.is_at_end() {
+            self.current += 1;
+        }
+        self.previous()
+    }
+
+    fn match_token(&mut self, token_type: &TokenType) -> bool {
+        if self.check(token_type) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn consume(&mut self, token_type: &TokenType, message: &str) -> Result<&Token, ParseError> {
+        if self.check(token_type) {
+            Ok(self.advance())
+        } else {
+            let code = match token_type {
+                TokenType::Semicolon => &codes::MISSING_SEMICOLON,
+                _ => &codes::UNEXPECTED_TOKEN,
+            };
+            Err(self.error_at_current(code, message))
+        }
+    }
+
+    /// Consumes the name a declaration introduces, like `consume` with
+    /// `Identifier`, except that a keyword in its place is reported as one
+    /// rather than as an unexpected token. `what` says what the name is
+    /// for, e.g. "parameter name".
+    fn consume_name(&mut self, what: &str, message: &str) -> Result<&Token, ParseError> {
+        let token = self.peek();
+        if is_keyword(&token.token_type) {
+            return Err(ParseError {
+                code: &codes::RESERVED_KEYWORD,
+                message: format!("'{}' is a reserved keyword and cannot be used as a {}", token.value, what),
+                span: token.span,
+            });
+        }
+        self.consume(&TokenType::Identifier, message)
+    }
+
+    /// Goes one level deeper into nested code, which fails at the current
+    /// token with `message` once `MAX_NESTING` is reached. Callers call
+    /// `unnest` when they finish; after an error, `synchronize` resets the
+    /// nesting.
+    fn nest(&mut self, message: &str) -> Result<(), ParseError> {
+        if self.nesting >= MAX_NESTING {
+            return Err(self.error_at_current(&codes::TOO_DEEPLY_NESTED, message));
+        }
+        self.deepen()?;
+        self.nesting += 1;
+        Ok(())
+    }
+
+    fn unnest(&mut self) {
+        self.nesting -= 1;
+        self.depth -= 1;
+    }
+
+    /// Goes one level deeper into the syntax tree without nesting, for the
+    /// next link of a chain, which fails once `MAX_DEPTH` is reached. A
+    /// chain like `a + b + c` or `f()()()` is read in a loop rather than by
+    /// recursion, but each link wraps the tree built so far one level
+    /// deeper, and everything that later walks the tree recurses through
+    /// every level. Callers put `depth` back when the chain ends.
+    fn deepen(&mut self) -> Result<(), ParseError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(self.error_at_current(
+                &codes::TOO_DEEPLY_NESTED,
+                "Chain of operators, calls, or else-ifs too long",
+            ));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn error_at_current(&self, code: &'static Code, message: &str) -> ParseError {
+        let token = self.peek();
+        let (found, span) = match token.token_type {
+            // whatever is missing belongs after the last token, not on the
+            // blank lines, spaces or comments that may follow it
+            TokenType::EOF if self.current > 0 => ("end of input".to_string(), self.previous().end()),
+            TokenType::EOF => ("end of input".to_string(), token.span),
+            _ => (format!("'{}'", token.value), token.span),
+        };
+        ParseError {
+            code,
+            message: format!("{}, found {}", message, found),
+            span,
+        }
+    }
+
+    // declarations
+
+    /// Parses a declaration at the top level of a file, the only place an
+    /// `import` or `export` may appear.
+    fn top_level_declaration(&mut self) -> Result<Stmt, ParseError> {
+        if self.check(&TokenType::Import) {
+            self.import_statement()
+        } else if self.check(&TokenType::Export) {
+            self.export_declaration()
+        } else if self.check(&TokenType::Identifier) && self.peek().value == "test" && self.check_next(&TokenType::String) {
+            self.test_block()
+        } else {
+            self.declaration()
+        }
+    }
+
+    /// Parses `import "path";` or `import "path" as alias;`.
+    fn import_statement(&mut self) -> Result<Stmt, ParseError> {
+        let start = self.advance().span;
+        let path = self.consume(&TokenType::String, "Expected a file path after 'import'")?.value.clone();
+        let alias = if self.match_token(&TokenType::As) {
+            Some(self.consume_name("module name", "Expected a module name after 'as'")?.value.clone())
+        } else {
+            None
+        };
+        let end = self.consume(&TokenType::Semicolon, "Expected ';' after import")?.span;
+        Ok(Stmt {
+            kind: StmtKind::Import { path, alias },
+            span: start.to(end),
+        })
+    }
+
+    /// Parses `export` followed by a `let`, `const`, function, or class
+    /// declaration.
+    fn export_declaration(&mut self) -> Result<Stmt, ParseError> {
+        let start = self.advance().span;
+        let exportable = self.check(&TokenType::Let)
+            || self.check(&TokenType::Const)
+            || self.check(&TokenType::Class)
+            // a keyword is let through as a function's name so that it's
+            // reported as one
+            || (self.check(&TokenType::Function)
+                && self.tokens.get(self.current + 1).is_some_and(|token| {
+                    token.token_type == TokenType::Identifier || is_keyword(&token.token_type)
+                }));
+        if !exportable {
+            return Err(self.error_at_current(
+                &codes::UNEXPECTED_TOKEN,
+                "Expected a 'let', 'const', function, or class declaration after 'export'",
+            ));
+        }
+        let declaration = self.declaration()?;
+        Ok(Stmt {
+            span: start.to(declaration.span),
+            kind: StmtKind::Export(Box::new(declaration)),
+        })
+    }
+
+    /// Parses `test "name" { ... }`. `test` is only a keyword here, before
+    /// a string, so it can still name variables.
+    fn test_block(&mut self) -> Result<Stmt, ParseError> {
+        let start = self.advance().span;
+        let name = self.advance().value.clone();
+        let (body, end) = self.block()?;
+        Ok(Stmt {
+            kind: StmtKind::Test { name, body },
+            span: start.to(end),
+        })
+    }
+
+    fn declaration(&mut self) -> Result<Stmt, ParseError> {
+        if self.check(&TokenType::Let) || self.check(&TokenType::Const) {
+            self.let_declaration()
+        } else if self.check(&TokenType::Function) && self.check_next(&TokenType::Identifier) {
+            self.function_declaration()
+        } else if self.check(&TokenType::Class) {
+            self.class_declaration()
+        } else {
+            self.statement()
+        }
+    }
+
+    /// Parses `let name = value;` or `const name = value;`, either with an
+    /// optional `: type` after the name, or a destructuring declaration
+    /// like `let [a, b] = value;`.
+    fn let_declaration(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.advance().clone();
+        let constant = keyword.token_type == TokenType::Const;
+        if self.check(&TokenType::LeftBracket) || self.check(&TokenType::LeftBrace) {
+            let pattern = self.pattern()?;
+            self.consume(&TokenType::Assign, "Expected '=' after destructuring pattern")?;
+            let initializer = self.expression()?;
+            let end = self.consume(&TokenType::Semicolon, "Expected ';' after variable declaration")?.span;
+            return Ok(Stmt {
+                kind: StmtKind::Destructure { pattern, initializer, constant },
+                span: keyword.span.to(end),
+            });
+        }
+        let name_token = self.consume_name("variable name", "Expected variable name")?;
+        let (name, name_span) = (Symbol::intern(&name_token.value), name_token.span);
+        let annotation = self.annotation()?;
+
+        let initializer = if self.match_token(&TokenType::Assign) {
+            Some(self.expression()?)
+        } else if constant {
+            let message = format!("Expected '=' after constant '{}'", name);
+            return Err(self.error_at_current(&codes::UNEXPECTED_TOKEN, &message));
+        } else {
+            None
+        };
+
+        let end = self.consume(&TokenType::Semicolon, "Expected ';' after variable declaration")?.span;
+        Ok(Stmt {
+            kind: StmtKind::Let { name, name_span, annotation, initializer, constant },
+            span: keyword.span.to(end),
+        })
+    }
+
+    /// Parses a name, an array pattern like `[a, [b], ...rest]`, or a map
+    /// pattern like `{ x, y: [first] }`.
+    fn pattern(&mut self) -> Result<Pattern, ParseError> {
+        if self.match_token(&TokenType::LeftBracket) {
+            let mut elements = Vec::new();
+            let mut rest = None;
+            while !self.check(&TokenType::RightBracket) {
+                if self.match_token(&TokenType::Ellipsis) {
+                    let name = self.consume_name("variable name", "Expected a name after '...'")?;
+                    rest = Some((Symbol::intern(&name.value), name.span));
+                    break;
+                }
+                self.nest("Pattern nested too deeply")?;
+                elements.push(self.pattern()?);
+                self.unnest();
+                if !self.match_token(&TokenType::Comma) {
+                    break;
+                }
+            }
+            let message = if rest.is_some() {
+                "Expected ']' after the rest element"
+            } else {
+                "Expected ']' after array pattern"
+            };
+            self.consume(&TokenType::RightBracket, message)?;
+            Ok(Pattern::Array { elements, rest })
+        } else if self.match_token(&TokenType::LeftBrace) {
+            let mut entries = Vec::new();
+            while !self.check(&TokenType::RightBrace) {
+                if !self.check(&TokenType::Identifier) && !self.check(&TokenType::String) {
+                    return Err(self.error_at_current(&codes::UNEXPECTED_TOKEN, "Expected a key in map pattern"));
+                }
+                let key = self.advance().clone();
+                let pattern = if self.match_token(&TokenType::Colon) {
+                    self.nest("Pattern nested too deeply")?;
+                    let pattern = self.pattern()?;
+                    self.unnest();
+                    pattern
+                } else if key.token_type == TokenType::Identifier {
+                    Pattern::Name { name: Symbol::intern(&key.value), span: key.span }
+                } else {
+                    return Err(self.error_at_current(&codes::UNEXPECTED_TOKEN, "Expected ':' after quoted key"));
+                };
+                entries.push((key.value, pattern));
+                if !self.match_token(&TokenType::Comma) {
+                    break;
+                }
+            }
+            self.consume(&TokenType::RightBrace, "Expected '}' after map pattern")?;
+            Ok(Pattern::Map(entries))
+        } else {
+            let name = self.consume_name("variable name", "Expected a name, '[', or '{' in pattern")?;
+            Ok(Pattern::Name { name: Symbol::intern(&name.value), span: name.span })
+        }
+    }
+
+    fn function_declaration(&mut self) -> Result<Stmt, ParseError> {
+        let exported = self.current > 0 && self.tokens[self.current - 1].token_type == TokenType::Export;
+        let keyword = self.advance();
+        let start = keyword.span;
+        let mut doc = keyword.doc.clone();
+        if exported {
+            doc = doc.or_else(|| self.tokens[self.current - 2].doc.clone());
+        }
+        let name = self.consume_name("function name", "Expected function name")?;
+        let (name, name_span) = (Symbol::intern(&name.value), name.span);
+        let declaration = FunctionDecl { doc, ..self.function(name, name_span, start)? };
+        let span = declaration.span;
+        Ok(Stmt {
+            kind: StmtKind::Function(Rc::new(declaration)),
+            span,
+        })
+    }
+
+    /// Parses `class Name { ... }`, whose body is method declarations
+    /// written like functions without the `function` keyword.
+    fn class_declaration(&mut self) -> Result<Stmt, ParseError> {
+        let start = self.advance().span;
+        let name = Symbol::intern(&self.consume_name("class name", "Expected class name")?.value);
+        self.consume(&TokenType::LeftBrace, "Expected '{' after class name")?;
+
+        self.class_depth += 1;
+        let methods = self.methods(&name);
+        self.class_depth -= 1;
+        let methods = methods?;
+
+        let end = self.consume(&TokenType::RightBrace, "Expected '}' after class body")?.span;
+        let span = start.to(end);
+        Ok(Stmt {
+            kind: StmtKind::Class(Rc::new(ClassDecl { name, methods, span })),
+            span,
+        })
+    }
+
+    /// Parses the method declarations of the class `class` up to its
+    /// closing brace.
+    fn methods(&mut self, class: &str) -> Result<Vec<Rc<FunctionDecl>>, ParseError> {
+        let mut methods: Vec<Rc<FunctionDecl>> = Vec::new();
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            let name = self.consume_name("method name", "Expected a method name")?.clone();
+            if methods.iter().any(|method| method.name == name.value) {
+                return Err(ParseError {
+                    code: &codes::UNEXPECTED_TOKEN,
+                    message: format!("Duplicate method '{}' in class '{}'", name.value, class),
+                    span: name.span,
+                });
+            }
+            let declaration = self.function(Symbol::intern(&name.value), name.span, name.span)?;
+            methods.push(Rc::new(FunctionDecl { doc: name.doc, ..declaration }));
+        }
+        Ok(methods)
+    }
+
+    /// Parses the parameters and body of a function whose header (starting
+    /// at `start`) has been consumed up to its name.
+    fn function(&mut self, name: Symbol, name_span: Span, start: Span) -> Result<FunctionDecl, ParseError> {
+        // the function's expressions go in an arena of its own
+        let enclosing_exprs = std::mem::take(&mut self.exprs);
+        let declaration = self.function_parts(name, name_span, start);
+        let exprs = std::mem::replace(&mut self.exprs, enclosing_exprs);
+        Ok(FunctionDecl { exprs, ..declaration? })
+    }
+
+    /// Parses what `function` does, into the current arena.
+    fn function_parts(&mut self, name: Symbol, name_span: Span, start: Span) -> Result<FunctionDecl, ParseError> {
+        self.consume(&TokenType::LeftParen, "Expected '(' after function name")?;
+        let mut params: Vec<Parameter> = Vec::new();
+        if !self.check(&TokenType::RightParen) {
+            loop {
+                let rest = self.match_token(&TokenType::Ellipsis);
+                let param = self.consume_name("parameter name", "Expected parameter name")?;
+                let (name, span) = (Symbol::intern(&param.value), param.span);
+                let annotation = self.annotation()?;
+                let default = if !rest && self.match_token(&TokenType::Assign) {
+                    Some(self.expression()?)
+                } else {
+                    None
+                };
+                let param = Parameter { name, annotation, default, rest, span };
+                if params.iter().any(|existing| existing.name == param.name) {
+                    return Err(ParseError {
+                        code: &codes::DUPLICATE_PARAMETER,
+                        message: format!("Duplicate parameter '{}'", param.name),
+                        span: param.span,
+                    });
+                }
+                let after_default = params.iter().any(|existing| existing.default.is_some());
+                if after_default && param.default.is_none() && !param.rest {
+                    return Err(ParseError {
+                        code: &codes::UNEXPECTED_TOKEN,
+                        message: format!("Parameter '{}' needs a default, since one before it has one", param.name),
+                        span: param.span,
+                    });
+                }
+                params.push(param);
+                if rest {
+                    break;
+                }
+                if !self.match_token(&TokenType::Comma) {
+                    break;
+                }
+            }
+        }
+        let message = match params.last() {
+            Some(param) if param.rest => "Expected ')' after the rest parameter",
+            _ => "Expected ')' after parameters",
+        };
+        self.consume(&TokenType::RightParen, message)?;
+        let return_type = self.annotation()?;
+
+        // a loop around the declaration doesn't make `break` valid inside it
+        let enclosing_loops = std::mem::take(&mut self.loops);
+        let body = self.block();
+        self.loops = enclosing_loops;
+        let (body, end) = body?;
+        Ok(FunctionDecl {
+            name,
+            name_span,
+            params,
+            return_type,
+            body,
+            exprs: Exprs::new(),
+            span: start.to(end),
+            doc: None,
+        })
+    }
+
+    /// Parses an optional `: type` annotation.
+    fn annotation(&mut self) -> Result<Option<Type>, ParseError> {
+        if !self.match_token(&TokenType::Colon) {
+            return Ok(None);
+        }
+        let token = self.peek();
+        let annotation = match (&token.token_type, token.value.as_str()) {
+            (TokenType::Identifier, "number") => Type::Number,
+            (TokenType::Identifier, "string") => Type::String,
+            (TokenType::Identifier, "bool") => Type::Bool,
+            (TokenType::Null, _) => Type::Null,
+            (TokenType::Identifier, "array") => Type::Array,
+            (TokenType::Identifier, "map") => Type::Map,
+            (TokenType::Function, _) => Type::Function,
+            (TokenType::Identifier, "any") => Type::Any,
+            _ => {
+                return Err(self.error_at_current(
+                    &codes::UNEXPECTED_TOKEN,
+                    "Expected a type (number, string, bool, null, array, map, function, or any)",
+                ));
+            }
+        };
+        self.advance();
+        Ok(Some(annotation))
+    }
+
+    // statements
+
+    fn statement(&mut self) -> Result<Stmt, ParseError> {
+        match self.peek().token_type {
+            TokenType::If => self.if_statement(),
+            TokenType::While => self.while_statement(None),
+            TokenType::Do => self.do_while_statement(None),
+            TokenType::Match => self.match_statement(),
+            TokenType::For => self.for_statement(None),
+            TokenType::Identifier if self.check_next(&TokenType::Colon) => self.labelled_statement(),
+            TokenType::Return => self.return_statement(),
+            TokenType::Try => self.try_statement(),
+            TokenType::Throw => self.throw_statement(),
+            TokenType::Break | TokenType::Continue => self.loop_control_statement(),
+            TokenType::Import | TokenType::Export => Err(ParseError {
+                code: &codes::MISPLACED_STATEMENT,
+                message: format!("'{}' is only allowed at the top level of a file", self.peek().value),
+                span: self.peek().span,
+            }),
+            TokenType::LeftBrace => {
+                let (statements, span) = self.block()?;
+                Ok(Stmt { kind: StmtKind::Block(statements), span })
+            }
+            _ => self.expression_statement(),
+        }
+    }
+
+    /// Parses `{ declaration* }`, returning the statements and the span of
+    /// the whole block including its braces.
+    fn block(&mut self) -> Result<(Vec<Stmt>, Span), ParseError> {
+        self.nest("Block nested too deeply")?;
+        let start = self.consume(&TokenType::LeftBrace, "Expected '{'")?.span;
+        let mut statements = Vec::new();
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            statements.push(self.declaration()?);
+        }
+        let end = self.consume(&TokenType::RightBrace, "Expected '}' after block")?.span;
+        self.unnest();
+        Ok((statements, start.to(end)))
+    }
+
+    fn if_statement(&mut self) -> Result<Stmt, ParseError> {
+        let start = self.advance().span;
+        let condition = self.expression()?;
+        // every branch is braced, so an `else` can only ever belong to one if
+        if self.check(&TokenType::If) {
+            return Err(self.if_without_braces());
+        }
+        let (then_branch, mut end) = self.block()?;
+        let mut arms = vec![(start, condition, then_branch)];
+
+        // `else if` is an else holding just the next if of the chain, which
+        // the arms are put into from the last once they've all been read
+        let depth = self.depth;
+        let mut else_branch = None;
+        while self.match_token(&TokenType::Else) {
+            if self.check(&TokenType::If) {
+                self.deepen()?;
+                let start = self.advance().span;
+                let condition = self.expression()?;
+                if self.check(&TokenType::If) {
+                    return Err(self.if_without_braces());
+                }
+                let (then_branch, span) = self.block()?;
+                end = span;
+                arms.push((start, condition, then_branch));
+            } else if self.check(&TokenType::LeftBrace) {
+                let (statements, span) = self.block()?;
+                end = span;
+                else_branch = Some(statements);
+                break;
+            } else {
+                return Err(self.error_at_current(&codes::UNEXPECTED_TOKEN, "Expected '{' or 'if' after 'else'"));
+            }
+        }
+        self.depth = depth;
+
+        let mut statement = None;
+        for (start, condition, then_branch) in arms.into_iter().rev() {
+            let else_branch = match statement {
+                Some(next) => Some(vec![next]),
+                None => else_branch.take(),
+            };
+            statement = Some(Stmt {
+                kind: StmtKind::If { condition, then_branch, else_branch },
+                span: start.to(end),
+            });
+        }
+        Ok(statement.expect("there is at least one arm"))
+    }
+
+    fn if_without_braces(&self) -> ParseError {
+        self.error_at_current(
+            &codes::UNEXPECTED_TOKEN,
+            "Expected '{' after the if condition; put an if inside an if in braces",
+        )
+    }
+
+    /// Parses `label: loop`, giving the loop a label that `break` and
+    /// `continue` in loops nested inside it can name.
+    fn labelled_statement(&mut self) -> Result<Stmt, ParseError> {
+        let name = self.advance().clone();
+        self.advance();
+        let label = Some(Symbol::intern(&name.value));
+        let stmt = match self.peek().token_type {
+            TokenType::While => self.while_statement(label)?,
+            TokenType::Do => self.do_while_statement(label)?,
+            TokenType::For => self.for_statement(label)?,
+            _ => {
+                return Err(ParseError {
+                    code: &codes::MISPLACED_STATEMENT,
+                    message: format!("The label '{}' isn't on a loop; only loops can be labelled", name.value),
+                    span: name.span,
+                });
+            }
+        };
+        Ok(Stmt {
+            span: name.span.to(stmt.span),
+            ..stmt
+        })
+    }
+
+    fn while_statement(&mut self, label: Option<Symbol>) -> Result<Stmt, ParseError> {
+        let start = self.advance().span;
+        let condition = self.expression()?;
+        let (body, end) = self.loop_body(label.clone())?;
+        Ok(Stmt {
+            kind: StmtKind::While { condition, body, label },
+            span: start.to(end),
+        })
+    }
+
+    fn do_while_statement(&mut self, label: Option<Symbol>) -> Result<Stmt, ParseError> {
+        let start = self.advance().span;
+        let (body, _) = self.loop_body(label.clone())?;
+        self.consume(&TokenType::While, "Expected 'while' after do loop body")?;
+        let condition = self.expression()?;
+        let end = self.consume(&TokenType::Semicolon, "Expected ';' after do loop condition")?.span;
+        Ok(Stmt {
+            kind: StmtKind::DoWhile { body, condition, label },
+            span: start.to(end),
+        })
+    }
+
+    /// Parses `match subject { arms }`, where each arm is one or more
+    /// comma-separated values, or `_` for the default arm, then `=>` and a
+    /// block. The default arm, if any, comes last.
+    fn match_statement(&mut self) -> Result<Stmt, ParseError> {
+        let start = self.advance().span;
+        let subject = self.expression()?;
+        self.consume(&TokenType::LeftBrace, "Expected '{' after match subject")?;
+
+        let mut arms = Vec::new();
+        let mut default = None;
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            if default.is_some() {
+                return Err(self.error_at_current(&codes::UNEXPECTED_TOKEN, "Expected '}' after the '_' arm"));
+            }
+            let wildcard = self.check(&TokenType::Identifier) && self.peek().value == "_";
+            if wildcard && self.check_next(&TokenType::FatArrow) {
+                self.advance();
+                self.advance();
+                default = Some(self.block()?.0);
+                continue;
+            }
+            let mut values = vec![self.expression()?];
+            while self.match_token(&TokenType::Comma) {
+                values.push(self.expression()?);
+            }
+            self.consume(&TokenType::FatArrow, "Expected '=>' after match values")?;
+            let (body, end) = self.block()?;
+            let span = values[0].span.to(end);
+            arms.push(MatchArm { values, body, span });
+        }
+        let end = self.consume(&TokenType::RightBrace, "Expected '}' after match arms")?.span;
+
+        Ok(Stmt {
+            kind: StmtKind::Match { subject, arms, default },
+            span: start.to(end),
+        })
+    }
+
+    fn for_statement(&mut self, label: Option<Symbol>) -> Result<Stmt, ParseError> {
+        let start = self.advance().span;
+        if self.check(&TokenType::Identifier) {
+            return self.for_in_statement(start, label);
+        }
+        self.consume(&TokenType::LeftParen, "Expected '(' or a loop variable after 'for'")?;
+
+        // the initializer statements consume their own ';'
+        let initializer = if self.match_token(&TokenType::Semicolon) {
+            None
+        } else if self.check(&TokenType::Let) || self.check(&TokenType::Const) {
+            Some(Box::new(self.let_declaration()?))
+        } else {
+            Some(Box::new(self.expression_statement()?))
+        };
+
+        let condition = if self.check(&TokenType::Semicolon) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.consume(&TokenType::Semicolon, "Expected ';' after loop condition")?;
+
+        let increment = if self.check(&TokenType::RightParen) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.consume(&TokenType::RightParen, "Expected ')' after for clauses")?;
+
+        let (body, end) = self.loop_body(label.clone())?;
+        Ok(Stmt {
+            kind: StmtKind::For { initializer, condition, increment, body, label },
+            span: start.to(end),
+        })
+    }
+
+    /// Parses the rest of `for variable in iterable { ... }`, where the
+    /// iterable may be a `start..end` or `start..=end` range.
+    fn for_in_statement(&mut self, start: Span, label: Option<Symbol>) -> Result<Stmt, ParseError> {
+        let variable = Symbol::intern(&self.advance().value);
+        self.consume(&TokenType::In, "Expected 'in' after loop variable")?;
+
+        let mut iterable = self.expression()?;
+        if let Some(inclusive) = self.range_operator() {
+            let end = self.expression()?;
+            let span = iterable.span.to(end.span);
+            iterable = Expr {
+                kind: ExprKind::Range { start: self.exprs.add(iterable), end: self.exprs.add(end), inclusive },
+                span,
+            };
+        }
+
+        let (body, end) = self.loop_body(label.clone())?;
+        Ok(Stmt {
+            kind: StmtKind::ForIn { variable, iterable, body, label },
+            span: start.to(end),
+        })
+    }
+
+    /// Consumes `..` or `..=`, returning whether the range includes its end.
+    fn range_operator(&mut self) -> Option<bool> {
+        if self.match_token(&TokenType::DotDot) {
+            Some(false)
+        } else if self.match_token(&TokenType::DotDotEqual) {
+            Some(true)
+        } else {
+            None
+        }
+    }
+
+    /// Parses the block of a loop with `label`, inside which `break` and
+    /// `continue` are allowed.
+    fn loop_body(&mut self, label: Option<Symbol>) -> Result<(Vec<Stmt>, Span), ParseError> {
+        self.loops.push(label);
+        let body = self.block();
+        self.loops.pop();
+        body
+    }
+
+    fn loop_control_statement(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.advance().clone();
+        if self.loops.is_empty() {
+            return Err(ParseError {
+                code: &codes::MISPLACED_STATEMENT,
+                message: format!("'{}' outside of a loop", keyword.value),
+                span: keyword.span,
+            });
+        }
+        let label = if self.check(&TokenType::Identifier) {
+            let name = self.advance().clone();
+            let label = Symbol::intern(&name.value);
+            if !self.loops.contains(&Some(label.clone())) {
+                return Err(ParseError {
+                    code: &codes::UNDEFINED_LABEL,
+                    message: format!("No loop around this '{}' is labelled '{}'", keyword.value, name.value),
+                    span: name.span,
+                });
+            }
+            Some(label)
+        } else {
+            None
+        };
+        let kind = if keyword.token_type == TokenType::Break {
+            StmtKind::Break(label)
+        } else {
+            StmtKind::Continue(label)
+        };
+        let end = self.consume(&TokenType::Semicolon, &format!("Expected ';' after '{}'", keyword.value))?.span;
+        Ok(Stmt {
+            kind,
+            span: keyword.span.to(end),
+        })
+    }
+
+    fn return_statement(&mut self) -> Result<Stmt, ParseError> {
+        let start = self.advance().span;
+        let value = if self.check(&TokenType::Semicolon) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        let end = self.consume(&TokenType::Semicolon, "Expected ';' after return value")?.span;
+        Ok(Stmt {
+            kind: StmtKind::Return(value),
+            span: start.to(end),
+        })
+    }
+
+    /// Parses `try { ... } catch (name) { ... }`.
+    fn try_statement(&mut self) -> Result<Stmt, ParseError> {
+        let start = self.advance().span;
+        let (body, _) = self.block()?;
+        self.consume(&TokenType::Catch, "Expected 'catch' after try block")?;
+        self.consume(&TokenType::LeftParen, "Expected '(' after 'catch'")?;
+        let name = Symbol::intern(&self.consume_name("variable name", "Expected a name for the caught error")?.value);
+        self.consume(&TokenType::RightParen, "Expected ')' after the caught error's name")?;
+        let (handler, end) = self.block()?;
+        Ok(Stmt {
+            kind: StmtKind::Try { body, name, handler },
+            span: start.to(end),
+        })
+    }
+
+    fn throw_statement(&mut self) -> Result<Stmt, ParseError> {
+        let start = self.advance().span;
+        let value = self.expression()?;
+        let end = self.consume(&TokenType::Semicolon, "Expected ';' after thrown value")?.span;
+        Ok(Stmt {
+            kind: StmtKind::Throw(value),
+            span: start.to(end),
+        })
+    }
+
+    fn expression_statement(&mut self) -> Result<Stmt, ParseError> {
+        let expr = self.expression()?;
+        let end = self.consume(&TokenType::Semicolon, "Expected ';' after expression")?.span;
+        let span = expr.span.to(end);
+        Ok(Stmt {
+            kind: StmtKind::Expression(expr),
+            span,
+        })
+    }
+
+    /// Parses the rest of `object[index]` or of a slice like `object[1..3]`,
+    /// `object[..2]`, `object[2..]`, `object[..]`, or `object[1..=3]`, after
+    /// the `[`.
+    fn index(&mut self, object: Expr) -> Result<Expr, ParseError> {
+        let object_span = object.span;
+        let object = self.exprs.add(object);
+        let start = if self.check(&TokenType::DotDot) || self.check(&TokenType::DotDotEqual) {
+            None
+        } else {
+            let start = self.expression()?;
+            Some(self.exprs.add(start))
+        };
+        let kind = match self.range_operator() {
+            Some(inclusive) => {
+                // `..=` needs an end to include
+                let end = if self.check(&TokenType::RightBracket) && !inclusive {
+                    None
+                } else {
+                    let end = self.expression()?;
+                    Some(self.exprs.add(end))
+                };
+                ExprKind::Slice { object, start, end, inclusive }
+            }
+            None => ExprKind::Index {
+                object,
+                index: start.expect("the start is only left out before a range operator"),
+            },
+        };
+        let end = self.consume(&TokenType::RightBracket, "Expected ']' after index")?.span;
+        Ok(Expr {
+            kind,
+            span: object_span.to(end),
+        })
+    }
+
+    // expressions, from lowest to highest precedence
+
+    pub fn expression(&mut self) -> Result<Expr, ParseError> {
+        self.nest("Expression nested too deeply")?;
+        let expr = self.assignment()?;
+        self.unnest();
+        Ok(expr)
+    }
+
+    fn assignment(&mut self) -> Result<Expr, ParseError> {
+        let expr = self.pipeline()?;
+
+        let operator = match self.peek().token_type {
+            TokenType::Assign => None,
+            TokenType::PlusAssign => Some(BinaryOp::Add),
+            TokenType::MinusAssign => Some(BinaryOp::Subtract),
+            TokenType::MultiplyAssign => Some(BinaryOp::Multiply),
+            TokenType::DivideAssign => Some(BinaryOp::Divide),
+            TokenType::ModuloAssign => Some(BinaryOp::Modulo),
+            _ => return Ok(expr),
+        };
+        let equals = self.advance().span;
+        self.nest("Expression nested too deeply")?;
+        let value = self.assignment()?;
+        self.unnest();
+        let span = expr.span.to(value.span);
+        let value = self.exprs.add(value);
+        match expr.kind {
+            ExprKind::Variable(name) => Ok(Expr {
+                kind: ExprKind::Assign { name, operator, value },
+                span,
+            }),
+            ExprKind::Index { object, index } => Ok(Expr {
+                kind: ExprKind::SetIndex { object, index, operator, value },
+                span,
+            }),
+            ExprKind::Member { object, name } => Ok(Expr {
+                kind: ExprKind::SetMember { object, name, operator, value },
+                span,
+            }),
+            _ => Err(ParseError {
+                code: &codes::INVALID_ASSIGNMENT_TARGET,
+                message: "Invalid assignment target".to_string(),
+                span: equals,
+            }),
+        }
+    }
+
+    // `x |> f(a)` is parsed as the call `f(x, a)`, so nothing past the
+    // parser needs to know about pipes
+    fn pipeline(&mut self) -> Result<Expr, ParseError> {
+        let depth = self.depth;
+        let mut expr = self.logic_or()?;
+        while self.match_token(&TokenType::PipeGreater) {
+            self.deepen()?;
+            let stage = self.logic_or()?;
+            let span = expr.span.to(stage.span);
+            let ExprKind::Call { callee, mut arguments } = stage.kind else {
+                return Err(self.pipe_without_call(&stage));
+            };
+            arguments.insert(0, expr);
+            expr = Expr {
+                kind: ExprKind::Call { callee, arguments },
+                span,
+            };
+        }
+        self.depth = depth;
+        Ok(expr)
+    }
+
+    /// The error for a `|>` followed by `stage`, which isn't a call. When
+    /// it's a name, or a member like `text.trim`, the call is suggested.
+    fn pipe_without_call(&self, stage: &Expr) -> ParseError {
+        let mut message =
+            "Expected a call after '|>', which passes the value before it as the first argument".to_string();
+        if matches!(stage.kind, ExprKind::Variable(_) | ExprKind::Member { .. }) {
+            message.push_str(&format!("; did you mean `{}()`?", self.source_text(stage.span)));
+        }
+        ParseError {
+            code: &codes::PIPE_WITHOUT_CALL,
+            message,
+            span: stage.span,
+        }
+    }
+
+    fn logic_or(&mut self) -> Result<Expr, ParseError> {
+        let depth = self.depth;
+        let mut expr = self.logic_and()?;
+        while self.match_token(&TokenType::Or) {
+            self.deepen()?;
+            let right = self.logic_and()?;
+            expr = self.logical(expr, LogicalOp::Or, right);
+        }
+        self.depth = depth;
+        Ok(expr)
+    }
+
+    fn logic_and(&mut self) -> Result<Expr, ParseError> {
+        let depth = self.depth;
+        let mut expr = self.equality()?;
+        while self.match_token(&TokenType::And) {
+            self.deepen()?;
+            let right = self.equality()?;
+            expr = self.logical(expr, LogicalOp::And, right);
+        }
+        self.depth = depth;
+        Ok(expr)
+    }
+
+    fn equality(&mut self) -> Result<Expr, ParseError> {
+        let depth = self.depth;
+        let mut expr = self.comparison()?;
+        let mut compared = false;
+        loop {
+            let operator = match self.peek().token_type {
+                TokenType::Equal => BinaryOp::Equal,
+                TokenType::NotEqual => BinaryOp::NotEqual,
+                _ => break,
+            };
+            self.advance();
+            self.deepen()?;
+            let right = self.comparison()?;
+            if compared {
+                return Err(self.chained_comparison(&expr, operator, &right));
+            }
+            compared = true;
+            expr = self.binary(expr, operator, right);
+        }
+        self.depth = depth;
+        Ok(expr)
+    }
+
+    fn comparison(&mut self) -> Result<Expr, ParseError> {
+        let depth = self.depth;
+        let mut expr = self.term()?;
+        let mut compared = false;
+        loop {
+            let operator = match self.peek().token_type {
+                TokenType::Less => BinaryOp::Less,
+                TokenType::LessEqual => BinaryOp::LessEqual,
+                TokenType::Greater => BinaryOp::Greater,
+                TokenType::GreaterEqual => BinaryOp::GreaterEqual,
+                TokenType::In => BinaryOp::In,
+                // `not` is only special before `in`, so it stays usable as a name
+                TokenType::Identifier if self.peek().value == "not" && self.check_next(&TokenType::In) => {
+                    self.advance();
+                    BinaryOp::NotIn
+                }
+                _ => break,
+            };
+            self.advance();
+            self.deepen()?;
+            let right = self.term()?;
+            if compared {
+                return Err(self.chained_comparison(&expr, operator, &right));
+            }
+            compared = true;
+            expr = self.binary(expr, operator, right);
+        }
+        self.depth = depth;
+        Ok(expr)
+    }
+
+    /// The error for `a < b < c`, where `comparison` is `a < b`, which
+    /// suggests `a < b && b < c` instead.
+    fn chained_comparison(&self, comparison: &Expr, operator: BinaryOp, right: &Expr) -> ParseError {
+        let ExprKind::Binary { left, operator: first, right: middle } = &comparison.kind else {
+            unreachable!("only a comparison is chained");
+        };
+        let span = comparison.span.to(right.span);
+        let [left, middle, right] =
+            [&self.exprs[*left], &self.exprs[*middle], right].map(|operand| self.source_text(operand.span));
+        ParseError {
+            code: &codes::CHAINED_COMPARISON,
+            message: format!(
+                "Chained comparisons are not supported; did you mean `{} {} {} && {} {} {}`?",
+                left,
+                first.symbol(),
+                middle,
+                middle,
+                operator.symbol(),
+                right
+            ),
+            span,
+        }
+    }
+
+    // the source text of the tokens in `span`, with whitespace between
+    // them collapsed, and strings, of which only the contents are known,
+    // written back as literals
+    fn source_text(&self, span: Span) -> String {
+        let mut text = String::new();
+        let mut end = None;
+        let tokens = self.tokens.iter().filter(|token| {
+            token.token_type != TokenType::EOF && token.span.start >= span.start && token.span.end <= span.end
+        });
+        for token in tokens {
+            if end.is_some_and(|end| end < token.span.start) {
+                text.push(' ');
+            }
+            match token.token_type {
+                TokenType::String if token.raw.is_none() => text.push_str(&escape_string(&token.value)),
+                _ => text.push_str(token.raw()),
+            }
+            end = Some(token.span.end);
+        }
+        text
+    }
+
+    fn term(&mut self) -> Result<Expr, ParseError> {
+        let depth = self.depth;
+        let mut expr = self.factor()?;
+        loop {
+            let operator = match self.peek().token_type {
+                TokenType::Plus => BinaryOp::Add,
+                TokenType::Minus => BinaryOp::Subtract,
+                _ => break,
+            };
+            self.advance();
+            self.deepen()?;
+            let right = self.factor()?;
+            expr = self.binary(expr, operator, right);
+        }
+        self.depth = depth;
+        Ok(expr)
+    }
+
+    fn factor(&mut self) -> Result<Expr, ParseError> {
+        let depth = self.depth;
+        let mut expr = self.unary()?;
+        loop {
+            let operator = match self.peek().token_type {
+                TokenType::Multiply => BinaryOp::Multiply,
+                TokenType::Divide => BinaryOp::Divide,
+                TokenType::Modulo => BinaryOp::Modulo,
+                _ => break,
+            };
+            self.advance();
+            self.deepen()?;
+            let right = self.unary()?;
+            expr = self.binary(expr, operator, right);
+        }
+        self.depth = depth;
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        // a minus before an integer literal too big to be an integer by
+        // itself belongs to the literal, so the most negative integer can
+        // be written
+        if self.check(&TokenType::Minus)
+            && let Some(literal) = self.tokens.get(self.current + 1)
+            && literal.token_type == TokenType::Number
+            && parse_integer(&literal.value)
+                .is_some_and(|magnitude| !matches!(magnitude, Ok(n) if n <= i64::MAX as u64))
+            && !self.tokens.get(self.current + 2).is_some_and(|next| {
+                matches!(next.token_type, TokenType::LeftParen | TokenType::LeftBracket | TokenType::Dot)
+            })
+        {
+            let (text, span) = (literal.value.clone(), self.peek().span.to(literal.span));
+            let kind = self.number_literal(&text, true, span)?;
+            self.current += 2;
+            return Ok(Expr { kind, span });
+        }
+        let operator = match self.peek().token_type {
+            TokenType::Minus => UnaryOp::Negate,
+            TokenType::Not => UnaryOp::Not,
+            TokenType::TypeOf => UnaryOp::TypeOf,
+            _ => return self.call(),
+        };
+        let start = self.advance().span;
+        self.nest("Expression nested too deeply")?;
+        let operand = self.unary()?;
+        self.unnest();
+        let span = start.to(operand.span);
+        Ok(Expr {
+            kind: ExprKind::Unary { operator, operand: self.exprs.add(operand) },
+            span,
+        })
+    }
+
+    fn call(&mut self) -> Result<Expr, ParseError> {
+        let depth = self.depth;
+        let mut expr = self.primary()?;
+
+        loop {
+            if self.match_token(&TokenType::LeftParen) {
+                self.deepen()?;
+                let arguments = self.arguments(&TokenType::RightParen)?;
+                let end = self.consume(&TokenType::RightParen, "Expected ')' after arguments")?.span;
+                let span = expr.span.to(end);
+                expr = Expr {
+                    kind: ExprKind::Call { callee: self.exprs.add(expr), arguments },
+                    span,
+                };
+            } else if self.match_token(&TokenType::LeftBracket) {
+                self.deepen()?;
+                expr = self.index(expr)?;
+            } else if self.match_token(&TokenType::Dot) {
+                self.deepen()?;
+                let name = self.consume(&TokenType::Identifier, "Expected property name after '.'")?;
+                let span = expr.span.to(name.span);
+                let name = name.value.clone();
+                expr = Expr {
+                    kind: ExprKind::Member { object: self.exprs.add(expr), name },
+                    span,
+                };
+            } else {
+                break;
+            }
+        }
+
+        self.depth = depth;
+        Ok(expr)
+    }
+
+    // the number literal `text`, negated if `negative`, where an integer
+    // out of range becomes what `int_overflow` says
+    fn number_literal(&self, text: &str, negative: bool, span: Span) -> Result<ExprKind, ParseError> {
+        let sign = if negative { "-" } else { "" };
+        let error = |message| ParseError { code: &codes::INVALID_NUMBER, message, span };
+        let Some(number) = parse_number(text) else {
+            return Err(error(format!("Invalid number literal '{}{}'", sign, text)));
+        };
+        let number = if negative { -number } else { number };
+        let Some(magnitude) = parse_integer(text) else {
+            return Ok(ExprKind::Number(number));
+        };
+        let exact = match magnitude {
+            Ok(n) if negative => 0i64.checked_sub_unsigned(n),
+            Ok(n) => i64::try_from(n).ok(),
+            Err(_) => None,
+        };
+        if let Some(n) = exact {
+            return Ok(ExprKind::Int(n));
+        }
+        let (Ok(low) | Err(low)) = magnitude;
+        let wrapped = if negative { (low as i64).wrapping_neg() } else { low as i64 };
+        match self.int_overflow.overflowed(wrapped, number) {
+            Some(Value::Int(n)) => Ok(ExprKind::Int(n)),
+            Some(Value::Number(n)) => Ok(ExprKind::Number(n)),
+            Some(_) => unreachable!("an overflowed integer becomes a number"),
+            None => Err(error(format!(
+                "Integer literal {}{} is out of range; integers go from {} to {}",
+                sign,
+                text,
+                i64::MIN,
+                i64::MAX
+            ))),
+        }
+    }
+
+    /// Parses a comma-separated list of expressions up to (but not
+    /// including) `closing`, allowing a trailing comma.
+    fn arguments(&mut self, closing: &TokenType) -> Result<Vec<Expr>, ParseError> {
+        let mut arguments = Vec::new();
+        while !self.check(closing) {
+            arguments.push(self.expression()?);
+            if !self.match_token(&TokenType::Comma) {
+                break;
+            }
+        }
+        Ok(arguments)
+    }
+
+    fn primary(&mut self) -> Result<Expr, ParseError> {
+        let token = self.peek().clone();
+        let kind = match token.token_type {
+            TokenType::Number => self.number_literal(&token.value, false, token.span)?,
+            TokenType::String => ExprKind::String(token.value),
+            TokenType::True => ExprKind::Bool(true),
+            TokenType::False => ExprKind::Bool(false),
+            TokenType::Null => ExprKind::Null,
+            TokenType::Identifier => ExprKind::Variable(Symbol::intern(&token.value)),
+            TokenType::This if self.class_depth == 0 => {
+                return Err(ParseError {
+                    code: &codes::MISPLACED_STATEMENT,
+                    message: "'this' can only be used inside a class's methods".to_string(),
+                    span: token.span,
+                });
+            }
+            TokenType::This => ExprKind::This,
+            TokenType::LeftBracket => {
+                self.advance();
+                let elements = self.arguments(&TokenType::RightBracket)?;
+                let end = self.consume(&TokenType::RightBracket, "Expected ']' after array elements")?.span;
+                return Ok(Expr {
+                    kind: ExprKind::Array(elements),
+                    span: token.span.to(end),
+                });
+            }
+            TokenType::LeftBrace => return self.map_literal(),
+            TokenType::Function => {
+                self.advance();
+                // a declaration like `function match() {}` ends up here,
+                // since `match` isn't a name
+                if is_keyword(&self.peek().token_type) {
+                    self.consume_name("function name", "Expected function name")?;
+                }
+                let declaration = self.function(Symbol::intern("anonymous"), token.span, token.span)?;
+                let span = declaration.span;
+                return Ok(Expr {
+                    kind: ExprKind::Function(Rc::new(declaration)),
+                    span,
+                });
+            }
+            TokenType::LeftParen => {
+                self.advance();
+                let mut expr = self.expression()?;
+                let end = self.consume(&TokenType::RightParen, "Expected ')' after expression")?.span;
+                // widen the span to cover the parentheses for error reporting
+                expr.span = token.span.to(end);
+                return Ok(expr);
+            }
+            _ => return Err(self.error_at_current(&codes::UNEXPECTED_TOKEN, "Expected expression")),
+        };
+        self.advance();
+        Ok(Expr { kind, span: token.span })
+    }
+
+    fn map_literal(&mut self) -> Result<Expr, ParseError> {
+        let start = self.advance().span;
+        let mut entries = Vec::new();
+        while !self.check(&TokenType::RightBrace) {
+            let key = match self.peek().token_type {
+                TokenType::Identifier | TokenType::String => self.advance().value.clone(),
+                _ => return Err(self.error_at_current(&codes::UNEXPECTED_TOKEN, "Expected map key")),
+            };
+            self.consume(&TokenType::Colon, "Expected ':' after map key")?;
+            let value = self.expression()?;
+            entries.push((key, value));
+            if !self.match_token(&TokenType::Comma) {
+                break;
+            }
+        }
+        let end = self.consume(&TokenType::RightBrace, "Expected '}' after map entries")?.span;
+        Ok(Expr {
+            kind: ExprKind::Map(entries),
+            span: start.to(end),
+        })
+    }
+
+    fn binary(&mut self, left: Expr, operator: BinaryOp, right: Expr) -> Expr {
+        let span = left.span.to(right.span);
+        Expr {
+            kind: ExprKind::Binary { left: self.exprs.add(left), operator, right: self.exprs.add(right) },
+            span,
+        }
+    }
+
+    fn logical(&mut self, left: Expr, operator: LogicalOp, right: Expr) -> Expr {
+        let span = left.span.to(right.span);
+        Expr {
+            kind: ExprKind::Logical { left: self.exprs.add(left), operator, right: self.exprs.add(right) },
+            span,
+        }
+    }
+}
+
+/// How much `token` changes the nesting of braces.
+fn brace_depth(token: &Token) -> isize {
+    match token.token_type {
+        TokenType::LeftBrace => 1,
+        TokenType::RightBrace => -1,
+        _ => 0,
+    }
+}
@@ -0,0 +1,473 @@
+use std::fmt::Write;
+
+use crate::ast::{BinaryOp, Expr, ExprKind, Exprs, FunctionDecl, Pattern, Program, Stmt, StmtKind};
+use crate::lexer::{Span, Token};
+use crate::symbol::Symbol;
+use crate::value::format_number;
+
+/// Renders a program's syntax tree, one node per line, indented under its
+/// parent. Each child is labelled with its role in the parent, and with
+/// `spans` every node ends with its line, column, and byte range.
+///
+/// ```text
+/// Let name=x
+///   initializer: Binary operator=+
+///     left: Int value=1
+///     right: Variable name=y
+/// ```
+pub fn print_ast(program: &Program, spans: bool) -> String {
+    let mut output = String::new();
+    for statement in &program.statements {
+        write_tree(&mut output, &statement_node(statement, &program.exprs), "", 0, spans);
+    }
+    output
+}
+
+/// Renders a program's syntax tree as a JSON array of nodes. Each node is
+/// an object with a `kind`, its attributes, and its children by role; with
+/// `spans` it also has a `span` object of `line`, `column`, `start`, and
+/// `end`.
+pub fn ast_json(program: &Program, spans: bool) -> String {
+    let mut output = String::from("[");
+    for (position, statement) in program.statements.iter().enumerate() {
+        if position > 0 {
+            output.push(',');
+        }
+        write_json(&mut output, &statement_node(statement, &program.exprs), spans);
+    }
+    output.push(']');
+    output
+}
+
+/// Renders tokens as a JSON array with one object per line, each with the
+/// token's `type`, `value`, `line`, `column`, and byte range as `start` and
+/// `end`.
+pub fn tokens_json(tokens: &[Token]) -> String {
+    let objects: Vec<String> = tokens
+        .iter()
+        .map(|token| {
+            format!(
+                "{{\"type\": {}, \"value\": {}, \"line\": {}, \"column\": {}, \"start\": {}, \"end\": {}}}",
+                json_string(&format!("{:?}", token.token_type)),
+                json_string(&token.value),
+                token.line,
+                token.column,
+                token.span.start,
+                token.span.end
+            )
+        })
+        .collect();
+    format!("[\n  {}\n]", objects.join(",\n  "))
+}
+
+/// Quotes `s` as a JSON string.
+pub fn json_string(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// A syntax tree node in a form both renderers can walk.
+struct Node {
+    kind: &'static str,
+    attributes: Vec<(&'static str, Attribute)>,
+    children: Vec<(&'static str, Child)>,
+    // map entries have no span of their own
+    span: Option<Span>,
+}
+
+enum Attribute {
+    // identifiers and operators, shown bare
+    Name(String),
+    // string literals, shown quoted
+    Text(String),
+    Number(f64),
+    Int(i64),
+    Bool(bool),
+}
+
+enum Child {
+    One(Node),
+    Many(Vec<Node>),
+}
+
+impl Node {
+    fn new(kind: &'static str, span: Span) -> Self {
+        Node {
+            kind,
+            attributes: Vec::new(),
+            children: Vec::new(),
+            span: Some(span),
+        }
+    }
+
+    fn attribute(mut self, name: &'static str, value: Attribute) -> Self {
+        self.attributes.push((name, value));
+        self
+    }
+
+    fn name(self, name: &'static str, value: impl Into<String>) -> Self {
+        self.attribute(name, Attribute::Name(value.into()))
+    }
+
+    // the operator of a compound assignment, like `+=`
+    fn compound(self, operator: Option<BinaryOp>) -> Self {
+        match operator {
+            Some(operator) => self.name("operator", format!("{}=", operator.symbol())),
+            None => self,
+        }
+    }
+
+    // `arena` is where the operands of the expressions added are
+    fn child(mut self, role: &'static str, expr: &Expr, arena: &Exprs) -> Self {
+        self.children.push((role, Child::One(expression_node(expr, arena))));
+        self
+    }
+
+    fn optional_child(self, role: &'static str, expr: Option<&Expr>, arena: &Exprs) -> Self {
+        match expr {
+            Some(expr) => self.child(role, expr, arena),
+            None => self,
+        }
+    }
+
+    fn expressions(mut self, role: &'static str, exprs: &[Expr], arena: &Exprs) -> Self {
+        let nodes = exprs.iter().map(|expr| expression_node(expr, arena)).collect();
+        self.children.push((role, Child::Many(nodes)));
+        self
+    }
+
+    fn statements(mut self, role: &'static str, statements: &[Stmt], arena: &Exprs) -> Self {
+        let nodes = statements.iter().map(|statement| statement_node(statement, arena)).collect();
+        self.children.push((role, Child::Many(nodes)));
+        self
+    }
+}
+
+fn statement_node(stmt: &Stmt, exprs: &Exprs) -> Node {
+    let node = |kind| Node::new(kind, stmt.span);
+    match &stmt.kind {
+        StmtKind::Expression(expr) => node("Expression").child("expression", expr, exprs),
+        StmtKind::Let { name, annotation, initializer, constant, .. } => {
+            let mut let_node = node(if *constant { "Const" } else { "Let" }).name("name", name);
+            if let Some(annotation) = annotation {
+                let_node = let_node.name("type", annotation.name());
+            }
+            let_node.optional_child("initializer", initializer.as_ref(), exprs)
+        }
+        StmtKind::Destructure { pattern, initializer, constant } => {
+            let mut let_node = node(if *constant { "Const" } else { "Let" });
+            let_node.children.push(("pattern", Child::One(pattern_node(pattern))));
+            let_node.child("initializer", initializer, exprs)
+        }
+        StmtKind::Block(statements) => node("Block").statements("body", statements, exprs),
+        StmtKind::If { condition, then_branch, else_branch } => {
+            let mut else_branch = else_branch;
+            let mut if_node = node("If").child("condition", condition, exprs).statements("then", then_branch, exprs);
+            // an `else if` chain is listed arm by arm rather than nested
+            let mut else_ifs = Vec::new();
+            while let Some([next]) = else_branch.as_deref()
+                && let StmtKind::If { condition, then_branch, else_branch: next_else } = &next.kind
+                && next.span.end == stmt.span.end
+            {
+                else_ifs.push(
+                    Node::new("ElseIf", next.span)
+                        .child("condition", condition, exprs)
+                        .statements("then", then_branch, exprs),
+                );
+                else_branch = next_else;
+            }
+            if !else_ifs.is_empty() {
+                if_node.children.push(("else_ifs", Child::Many(else_ifs)));
+            }
+            match else_branch {
+                Some(else_branch) => if_node.statements("else", else_branch, exprs),
+                None => if_node,
+            }
+        }
+        StmtKind::While { condition, body, label } => labelled(node("While"), label)
+            .child("condition", condition, exprs)
+            .statements("body", body, exprs),
+        StmtKind::DoWhile { body, condition, label } => labelled(node("DoWhile"), label)
+            .statements("body", body, exprs)
+            .child("condition", condition, exprs),
+        StmtKind::Match { subject, arms, default } => {
+            let arms = arms
+                .iter()
+                .map(|arm| {
+                    Node::new("Arm", arm.span)
+                        .expressions("values", &arm.values, exprs)
+                        .statements("body", &arm.body, exprs)
+                })
+                .collect();
+            let mut match_node = node("Match").child("subject", subject, exprs);
+            match_node.children.push(("arms", Child::Many(arms)));
+            match default {
+                Some(default) => match_node.statements("default", default, exprs),
+                None => match_node,
+            }
+        }
+        StmtKind::For { initializer, condition, increment, body, label } => {
+            let mut for_node = labelled(node("For"), label);
+            if let Some(initializer) = initializer {
+                for_node.children.push(("initializer", Child::One(statement_node(initializer, exprs))));
+            }
+            for_node
+                .optional_child("condition", condition.as_ref(), exprs)
+                .optional_child("increment", increment.as_ref(), exprs)
+                .statements("body", body, exprs)
+        }
+        StmtKind::ForIn { variable, iterable, body, label } => labelled(node("ForIn"), label)
+            .name("variable", variable)
+            .child("iterable", iterable, exprs)
+            .statements("body", body, exprs),
+        StmtKind::Function(declaration) => function_node(declaration),
+        StmtKind::Class(declaration) => {
+            let mut class_node = node("Class").name("name", &declaration.name);
+            let methods = declaration.methods.iter().map(|method| function_node(method)).collect();
+            class_node.children.push(("methods", Child::Many(methods)));
+            class_node
+        }
+        StmtKind::Return(value) => node("Return").optional_child("value", value.as_ref(), exprs),
+        StmtKind::Try { body, name, handler } => node("Try")
+            .name("name", name)
+            .statements("body", body, exprs)
+            .statements("handler", handler, exprs),
+        StmtKind::Throw(value) => node("Throw").child("value", value, exprs),
+        StmtKind::Break(label) => labelled(node("Break"), label),
+        StmtKind::Continue(label) => labelled(node("Continue"), label),
+        StmtKind::Import { path, alias } => {
+            let import_node = node("Import").attribute("path", Attribute::Text(path.clone()));
+            match alias {
+                Some(alias) => import_node.name("alias", alias),
+                None => import_node,
+            }
+        }
+        StmtKind::Export(declaration) => {
+            let mut export_node = node("Export");
+            export_node.children.push(("declaration", Child::One(statement_node(declaration, exprs))));
+            export_node
+        }
+        StmtKind::Test { name, body } => node("Test")
+            .attribute("name", Attribute::Text(name.clone()))
+            .statements("body", body, exprs),
+    }
+}
+
+/// A destructuring pattern, where only the names bound have spans.
+fn pattern_node(pattern: &Pattern) -> Node {
+    let unspanned = |kind| Node {
+        kind,
+        attributes: Vec::new(),
+        children: Vec::new(),
+        span: None,
+    };
+    match pattern {
+        Pattern::Name { name, span } => Node::new("Name", *span).name("name", name),
+        Pattern::Array { elements, rest } => {
+            let mut node = unspanned("ArrayPattern");
+            node.children.push(("elements", Child::Many(elements.iter().map(pattern_node).collect())));
+            match rest {
+                Some((rest, _)) => node.name("rest", rest),
+                None => node,
+            }
+        }
+        Pattern::Map(entries) => {
+            let entries = entries
+                .iter()
+                .map(|(key, pattern)| {
+                    let mut entry = unspanned("Entry").attribute("key", Attribute::Text(key.clone()));
+                    entry.children.push(("pattern", Child::One(pattern_node(pattern))));
+                    entry
+                })
+                .collect();
+            let mut node = unspanned("MapPattern");
+            node.children.push(("entries", Child::Many(entries)));
+            node
+        }
+    }
+}
+
+// a loop, `break`, or `continue` node, with its label if it has one
+fn labelled(node: Node, label: &Option<Symbol>) -> Node {
+    match label {
+        Some(label) => node.name("label", label.to_string()),
+        None => node,
+    }
+}
+
+fn function_node(declaration: &FunctionDecl) -> Node {
+    let mut node = Node::new("Function", declaration.span).name("name", &declaration.name);
+    if let Some(return_type) = declaration.return_type {
+        node = node.name("returns", return_type.name());
+    }
+    if let Some(doc) = &declaration.doc {
+        node = node.attribute("doc", Attribute::Text(doc.clone()));
+    }
+    let params = declaration
+        .params
+        .iter()
+        .map(|param| {
+            let mut param_node = Node::new("Parameter", param.span).name("name", &param.name);
+            if param.rest {
+                param_node = param_node.attribute("rest", Attribute::Bool(true));
+            }
+            if let Some(annotation) = param.annotation {
+                param_node = param_node.name("type", annotation.name());
+            }
+            param_node.optional_child("default", param.default.as_ref(), &declaration.exprs)
+        })
+        .collect();
+    node.children.push(("params", Child::Many(params)));
+    node.statements("body", &declaration.body, &declaration.exprs)
+}
+
+fn expression_node(expr: &Expr, exprs: &Exprs) -> Node {
+    let node = |kind| Node::new(kind, expr.span);
+    match &expr.kind {
+        ExprKind::Number(n) => node("Number").attribute("value", Attribute::Number(*n)),
+        ExprKind::Int(n) => node("Int").attribute("value", Attribute::Int(*n)),
+        ExprKind::String(s) => node("String").attribute("value", Attribute::Text(s.clone())),
+        ExprKind::Bool(b) => node("Bool").attribute("value", Attribute::Bool(*b)),
+        ExprKind::Null => node("Null"),
+        ExprKind::Variable(name) => node("Variable").name("name", name),
+        ExprKind::Assign { name, operator, value } => {
+            node("Assign").name("name", name).compound(*operator).child("value", &exprs[*value], exprs)
+        }
+        ExprKind::Unary { operator, operand } => {
+            node("Unary").name("operator", operator.symbol()).child("operand", &exprs[*operand], exprs)
+        }
+        ExprKind::Binary { left, operator, right } => node("Binary")
+            .name("operator", operator.symbol())
+            .child("left", &exprs[*left], exprs)
+            .child("right", &exprs[*right], exprs),
+        ExprKind::Logical { left, operator, right } => node("Logical")
+            .name("operator", operator.symbol())
+            .child("left", &exprs[*left], exprs)
+            .child("right", &exprs[*right], exprs),
+        ExprKind::Call { callee, arguments } => node("Call")
+            .child("callee", &exprs[*callee], exprs)
+            .expressions("arguments", arguments, exprs),
+        ExprKind::Array(elements) => node("Array").expressions("elements", elements, exprs),
+        ExprKind::Index { object, index } => node("Index")
+            .child("object", &exprs[*object], exprs)
+            .child("index", &exprs[*index], exprs),
+        ExprKind::Slice { object, start, end, inclusive } => node("Slice")
+            .attribute("inclusive", Attribute::Bool(*inclusive))
+            .child("object", &exprs[*object], exprs)
+            .optional_child("start", start.map(|start| &exprs[start]), exprs)
+            .optional_child("end", end.map(|end| &exprs[end]), exprs),
+        ExprKind::SetIndex { object, index, operator, value } => node("SetIndex")
+            .compound(*operator)
+            .child("object", &exprs[*object], exprs)
+            .child("index", &exprs[*index], exprs)
+            .child("value", &exprs[*value], exprs),
+        ExprKind::Map(entries) => {
+            let entries = entries
+                .iter()
+                .map(|(key, value)| {
+                    let entry = Node {
+                        kind: "Entry",
+                        attributes: Vec::new(),
+                        children: Vec::new(),
+                        span: None,
+                    };
+                    entry.attribute("key", Attribute::Text(key.clone())).child("value", value, exprs)
+                })
+                .collect();
+            let mut map = node("Map");
+            map.children.push(("entries", Child::Many(entries)));
+            map
+        }
+        ExprKind::Member { object, name } => node("Member").name("name", name).child("object", &exprs[*object], exprs),
+        ExprKind::SetMember { object, name, operator, value } => node("SetMember")
+            .name("name", name)
+            .compound(*operator)
+            .child("object", &exprs[*object], exprs)
+            .child("value", &exprs[*value], exprs),
+        ExprKind::Range { start, end, inclusive } => node("Range")
+            .attribute("inclusive", Attribute::Bool(*inclusive))
+            .child("start", &exprs[*start], exprs)
+            .child("end", &exprs[*end], exprs),
+        ExprKind::Function(declaration) => function_node(declaration),
+        ExprKind::This => node("This"),
+    }
+}
+
+fn write_tree(output: &mut String, node: &Node, role: &str, depth: usize, spans: bool) {
+    let _ = write!(output, "{:indent$}{}{}", "", role, node.kind, indent = depth * 2);
+    for (name, value) in &node.attributes {
+        let _ = match value {
+            Attribute::Name(value) => write!(output, " {}={}", name, value),
+            Attribute::Text(value) => write!(output, " {}={:?}", name, value),
+            Attribute::Number(value) => write!(output, " {}={}", name, format_number(*value)),
+            Attribute::Int(value) => write!(output, " {}={}", name, value),
+            Attribute::Bool(value) => write!(output, " {}={}", name, value),
+        };
+    }
+    if spans && let Some(span) = node.span {
+        let _ = write!(output, " @ {}:{} ({}..{})", span.line, span.column, span.start, span.end);
+    }
+    output.push('\n');
+    for (child_role, child) in &node.children {
+        match child {
+            Child::One(child) => write_tree(output, child, &format!("{}: ", child_role), depth + 1, spans),
+            Child::Many(children) => {
+                let _ = writeln!(output, "{:indent$}{}:", "", child_role, indent = (depth + 1) * 2);
+                for child in children {
+                    write_tree(output, child, "", depth + 2, spans);
+                }
+            }
+        }
+    }
+}
+
+fn write_json(output: &mut String, node: &Node, spans: bool) {
+    let _ = write!(output, "{{\"kind\":{}", json_string(node.kind));
+    for (name, value) in &node.attributes {
+        let value = match value {
+            Attribute::Name(value) | Attribute::Text(value) => json_string(value),
+            Attribute::Number(value) if value.is_finite() => format_number(*value),
+            // JSON has no infinity, which a literal too big for a number becomes
+            Attribute::Number(_) => "null".to_string(),
+            Attribute::Int(value) => value.to_string(),
+            Attribute::Bool(value) => value.to_string(),
+        };
+        let _ = write!(output, ",{}:{}", json_string(name), value);
+    }
+    for (role, child) in &node.children {
+        let _ = write!(output, ",{}:", json_string(role));
+        match child {
+            Child::One(child) => write_json(output, child, spans),
+            Child::Many(children) => {
+                output.push('[');
+                for (position, child) in children.iter().enumerate() {
+                    if position > 0 {
+                        output.push(',');
+                    }
+                    write_json(output, child, spans);
+                }
+                output.push(']');
+            }
+        }
+    }
+    if spans && let Some(span) = node.span {
+        let _ = write!(
+            output,
+            ",\"span\":{{\"line\":{},\"column\":{},\"start\":{},\"end\":{}}}",
+            span.line, span.column, span.start, span.end
+        );
+    }
+    output.push('}');
+}
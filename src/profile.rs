@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::lexer::Span;
+
+/// The calls made to one function while profiling, from
+/// `Interpreter::take_profile`. Inclusive time counts the functions it
+/// called; exclusive time doesn't. A recursive function's inclusive time
+/// counts each outermost call once, not again for the calls inside it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FnStats {
+    pub name: String,
+    // where a script function is declared; builtins and native functions
+    // have none
+    pub span: Option<Span>,
+    pub calls: u64,
+    pub inclusive: Duration,
+    pub exclusive: Duration,
+}
+
+/// Times calls as they are made, for `Interpreter::with_profiling`.
+#[derive(Debug, Default)]
+pub(crate) struct Profiler {
+    functions: Vec<Profiled>,
+    // a function's index in `functions`, by its name and declaration,
+    // since methods of different classes can share a name
+    indices: HashMap<(String, Option<(usize, usize)>), usize>,
+    // the calls running now, innermost last
+    frames: Vec<Frame>,
+}
+
+#[derive(Debug)]
+struct Profiled {
+    stats: FnStats,
+    // how many calls to it are running now
+    depth: usize,
+}
+
+#[derive(Debug)]
+struct Frame {
+    function: usize,
+    start: Instant,
+    // the time spent in the calls it made
+    children: Duration,
+}
+
+impl Profiler {
+    /// Starts timing a call to the function `name`, declared at `span`.
+    pub(crate) fn enter(&mut self, name: &str, span: Option<Span>) {
+        let key = (name.to_string(), span.map(|span| (span.file, span.start)));
+        let function = *self.indices.entry(key).or_insert_with(|| {
+            self.functions.push(Profiled {
+                stats: FnStats {
+                    name: name.to_string(),
+                    span,
+                    calls: 0,
+                    inclusive: Duration::ZERO,
+                    exclusive: Duration::ZERO,
+                },
+                depth: 0,
+            });
+            self.functions.len() - 1
+        });
+        self.functions[function].depth += 1;
+        self.frames.push(Frame {
+            function,
+            start: Instant::now(),
+            children: Duration::ZERO,
+        });
+    }
+
+    /// Stops timing the innermost call running.
+    pub(crate) fn exit(&mut self) {
+        let Some(frame) = self.frames.pop() else {
+            return;
+        };
+        let elapsed = frame.start.elapsed();
+        let profiled = &mut self.functions[frame.function];
+        profiled.depth -= 1;
+        profiled.stats.calls += 1;
+        profiled.stats.exclusive += elapsed.saturating_sub(frame.children);
+        if profiled.depth == 0 {
+            profiled.stats.inclusive += elapsed;
+        }
+        if let Some(caller) = self.frames.last_mut() {
+            caller.children += elapsed;
+        }
+    }
+
+    /// The functions called since the last `take`, most exclusive time
+    /// first, and starts counting again from nothing.
+    pub(crate) fn take(&mut self) -> Vec<FnStats> {
+        let mut stats: Vec<FnStats> = self
+            .functions
+            .iter_mut()
+            .filter(|profiled| profiled.stats.calls > 0)
+            .map(|profiled| {
+                let stats = profiled.stats.clone();
+                profiled.stats.calls = 0;
+                profiled.stats.inclusive = Duration::ZERO;
+                profiled.stats.exclusive = Duration::ZERO;
+                stats
+            })
+            .collect();
+        stats.sort_by(|a, b| b.exclusive.cmp(&a.exclusive).then_with(|| a.name.cmp(&b.name)));
+        stats
+    }
+}
@@ -0,0 +1,238 @@
+use std::io;
+use std::rc::Rc;
+
+use crate::codes;
+use crate::completion::{self, CompletionKind};
+use crate::error::Error;
+use crate::interpreter::{Completion, Interpreter};
+use crate::lexer::{Lexer, TokenType};
+use crate::source::SourceFile;
+use crate::value::Value;
+
+pub const PROMPT: &str = "> ";
+const CONTINUATION_PROMPT: &str = "... ";
+
+const HELP: &str = "Enter statements to run them; the values of expressions are printed.
+help(f) shows how the function f is called, and its /// doc comment.
+Commands:
+  :tokens <code>  show the tokens of <code>
+  :complete <code>  list the completions for the end of <code>
+  :help           show this message
+  :quit           leave (or press Ctrl-D)
+In a terminal, Ctrl-C drops the entry being typed, and the arrow keys
+move through it and through earlier entries.";
+
+/// Runs an interactive session on `interpreter`'s input and output until
+/// the input ends or `:quit` is entered. Input with unclosed brackets or
+/// strings is kept and continued on the next line, as `EntryBuffer` does,
+/// and each whole entry is run by a `Session`. A terminal gets a line
+/// editor instead, in `editor::run`, which runs its entries the same way.
+pub fn run(interpreter: &mut Interpreter, color: bool) -> io::Result<Completion> {
+    let mut session = Session::new(color);
+    let mut entry = EntryBuffer::default();
+    loop {
+        let Some(line) = interpreter.read_line(entry.prompt())? else {
+            // end the prompt's line so the shell starts on a fresh one
+            interpreter.write_line("")?;
+            return Ok(Completion::Finished);
+        };
+        // a command is one line, whatever brackets are in it
+        let source = match entry.is_continuing() || !is_command(&line) {
+            true => match entry.push_line(&line) {
+                Some(source) => source,
+                None => continue,
+            },
+            false => line,
+        };
+        if let Some(completion) = session.enter(interpreter, &source)? {
+            return Ok(completion);
+        }
+    }
+}
+
+/// Runs the entries of an interactive session, wherever they're read
+/// from, in one interpreter. Every entry runs in the same global scope, so
+/// declarations persist between them. A missing `;` after an expression
+/// is added, non-null values of expression statements are echoed, and
+/// errors are printed without ending the session, colored if `color` is
+/// set. Each entry is its own file, named `<repl:n>` for the nth one, so
+/// an error in a function declared earlier shows where it is.
+#[derive(Debug)]
+pub struct Session {
+    color: bool,
+    // how many entries have been run
+    entries: usize,
+}
+
+impl Session {
+    pub fn new(color: bool) -> Self {
+        Session { color, entries: 0 }
+    }
+
+    /// Runs one whole entry: a command like `:help`, or a program, writing
+    /// what it prints and any error to `interpreter`'s output. Returns how
+    /// the session ends if it should, after `:quit` or a call to `exit()`.
+    pub fn enter(&mut self, interpreter: &mut Interpreter, source: &str) -> io::Result<Option<Completion>> {
+        match source.trim() {
+            "" => return Ok(None),
+            ":quit" => return Ok(Some(Completion::Finished)),
+            ":help" => {
+                interpreter.write_line(HELP)?;
+                return Ok(None);
+            }
+            command if is_command(command) => {
+                run_command(interpreter, command, self.color)?;
+                return Ok(None);
+            }
+            _ => {}
+        }
+
+        let source = with_semicolon(source);
+        self.entries += 1;
+        let output = match interpreter.eval_file(SourceFile::new(format!("<repl:{}>", self.entries), source.as_str())) {
+            Ok(Value::Null) => return Ok(None),
+            Ok(value) => value.to_string(),
+            Err(Error::Runtime(error)) if let Some(code) = error.exit_code() => return Ok(Some(Completion::Exit(code))),
+            Err(error) => {
+                // the error may be in an earlier entry or a file this one
+                // imports
+                let report = interpreter.report(&error);
+                let (name, source) = match interpreter.sources().get(report.span.file) {
+                    Some(file) => (file.name.as_str(), file.contents.as_str()),
+                    None => ("<repl>", source.as_str()),
+                };
+                report.render(source, name, self.color).trim_end().to_string()
+            }
+        };
+        interpreter.write_line(&output)?;
+        Ok(None)
+    }
+}
+
+/// Whether `line` is a REPL command like `:help` rather than code.
+pub fn is_command(line: &str) -> bool {
+    line.trim_start().starts_with(':')
+}
+
+/// Gathers the lines typed at the REPL into whole entries, apart from
+/// where the lines come from, so a line editor can feed it as well as
+/// `run` does.
+#[derive(Debug, Default)]
+pub struct EntryBuffer {
+    // the lines of the unfinished entry, each ending in a newline
+    lines: String,
+}
+
+impl EntryBuffer {
+    /// Adds `line` to the entry, and returns the whole entry, however many
+    /// lines it took, once its brackets and strings are closed.
+    pub fn push_line(&mut self, line: &str) -> Option<String> {
+        self.lines.push_str(line);
+        self.lines.push('\n');
+        if is_incomplete(&self.lines) {
+            return None;
+        }
+        Some(std::mem::take(&mut self.lines))
+    }
+
+    /// Whether an entry has been started and not finished.
+    pub fn is_continuing(&self) -> bool {
+        !self.lines.is_empty()
+    }
+
+    /// The prompt for the next line: `> `, or `... ` to continue an entry.
+    pub fn prompt(&self) -> &'static str {
+        if self.is_continuing() { CONTINUATION_PROMPT } else { PROMPT }
+    }
+}
+
+fn run_command(interpreter: &mut Interpreter, command: &str, color: bool) -> io::Result<()> {
+    let (name, argument) = command.split_once(' ').unwrap_or((command, ""));
+    match name {
+        ":tokens" => match Lexer::new(argument).tokenize() {
+            Ok(tokens) => {
+                for token in tokens.iter().filter(|token| token.token_type != TokenType::EOF) {
+                    interpreter.write_line(&token.to_string())?;
+                }
+                Ok(())
+            }
+            Err(error) => {
+                let report = Error::Lex(error).report();
+                interpreter.write_line(report.render(argument, "<repl>", color).trim_end())
+            }
+        },
+        ":complete" => {
+            let labels: Vec<String> = completions(interpreter, argument, argument.len())
+                .into_iter()
+                .map(|completion| completion.label)
+                .collect();
+            interpreter.write_line(&labels.join("  "))
+        }
+        _ => interpreter.write_line(&format!("Unknown command '{}'; try :help", name)),
+    }
+}
+
+/// The completions at byte `offset` of `line`, as `completion::complete`
+/// gives them, along with the globals earlier entries declared.
+pub fn completions(interpreter: &Interpreter, line: &str, offset: usize) -> Vec<completion::Completion> {
+    completion::complete_with_globals(line, offset, &global_completions(interpreter))
+}
+
+/// The globals earlier entries declared and the host's functions, as
+/// completions, for `completion::complete_with_globals`.
+pub fn global_completions(interpreter: &Interpreter) -> Vec<completion::Completion> {
+    let mut globals = Vec::new();
+    let mut scope = Some(Rc::clone(interpreter.globals()));
+    while let Some(environment) = scope {
+        let environment = environment.borrow();
+        globals.extend(environment.bindings().filter_map(|(name, value)| {
+            let kind = match value {
+                // already among the completions, as builtins
+                Value::Builtin(_) => return None,
+                Value::Function(_) | Value::NativeFunction(_) => CompletionKind::Function,
+                Value::Class(_) => CompletionKind::Class,
+                _ => CompletionKind::Variable,
+            };
+            Some(completion::Completion { label: name.to_string(), kind })
+        }));
+        scope = environment.enclosing().cloned();
+    }
+    globals
+}
+
+/// Whether `source` has more opening brackets than closing ones, ends in
+/// a string, or so far is only comments ending in a `///` doc comment, so
+/// the entry continues on the next line. Input that doesn't lex for any
+/// other reason is complete, so its error is reported rather than waiting
+/// for more.
+pub fn is_incomplete(source: &str) -> bool {
+    let tokens = match Lexer::new(source).tokenize() {
+        Ok(tokens) => tokens,
+        Err(error) => return error.code == &codes::UNTERMINATED_STRING,
+    };
+    // the doc comment is waiting for the declaration it documents
+    let documenting = source.lines().last().is_some_and(|line| line.trim_start().starts_with("///"));
+    if tokens.len() == 1 && documenting {
+        return true;
+    }
+    let depth: isize = tokens
+        .iter()
+        .map(|token| match token.token_type {
+            TokenType::LeftParen | TokenType::LeftBrace | TokenType::LeftBracket => 1,
+            TokenType::RightParen | TokenType::RightBrace | TokenType::RightBracket => -1,
+            _ => 0,
+        })
+        .sum();
+    depth > 0
+}
+
+/// `source` with a `;` added if it only parses with one, so an expression
+/// can be entered without it.
+fn with_semicolon(source: &str) -> String {
+    let completed = format!("{};", source.trim_end());
+    if crate::parse(source).is_err() && crate::parse(&completed).is_ok() {
+        completed
+    } else {
+        source.to_string()
+    }
+}
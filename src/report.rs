@@ -0,0 +1,276 @@
+use std::ffi::OsStr;
+use std::fmt::Write;
+
+use crate::checker::{Diagnostic, Severity};
+use crate::codes::Code;
+use crate::error::Error;
+use crate::lexer::Span;
+
+// how many columns a tab is drawn as, so underlines line up under it
+const TAB_WIDTH: usize = 4;
+
+// ANSI color codes
+const RED: &str = "1;31";
+const YELLOW: &str = "1;33";
+const BLUE: &str = "1;34";
+
+/// Whether reports written to a stream should be colored: only when it is a
+/// terminal, and the `NO_COLOR` environment variable isn't set to anything.
+/// See https://no-color.org.
+pub fn color_enabled(is_terminal: bool) -> bool {
+    color_wanted(is_terminal, std::env::var_os("NO_COLOR").as_deref())
+}
+
+/// `color_enabled` with the value of `NO_COLOR` given rather than read.
+pub fn color_wanted(is_terminal: bool, no_color: Option<&OsStr>) -> bool {
+    is_terminal && no_color.is_none_or(|value| value.is_empty())
+}
+
+/// `text` wrapped in the escape codes for `code`, or as is without `color`.
+fn paint(text: &str, code: &str, color: bool) -> String {
+    if color && !text.is_empty() {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// A part of the source with a message about it, drawn as a `-` underline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+/// An error or warning ready to be shown with the source it points at.
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub severity: Severity,
+    // what kind of problem this is, like "Parse error"
+    pub kind: &'static str,
+    pub code: &'static Code,
+    pub message: String,
+    pub span: Span,
+    pub secondary: Option<Label>,
+    // printed after the source, one per line
+    pub notes: Vec<String>,
+    // how to fix the problem, printed after the notes
+    pub help: Option<String>,
+}
+
+impl Report {
+    /// Renders the report in the style of rustc: a `kind[code]: message`
+    /// header, the `filename:line:column` it happened at, the source lines
+    /// involved with `^` under the span and `-` under the secondary label,
+    /// then the notes and help. Tabs are expanded so the underlines line
+    /// up. With `color`, the header and `^` underline are red for errors
+    /// and yellow for warnings, and the secondary label is blue. A report
+    /// with no span (`Span::default()`) shows no location.
+    ///
+    /// ```text
+    /// Lexer error[E0001]: Unexpected character '@'
+    ///  --> script.slime:1:11
+    ///   |
+    /// 1 | let x = 1 @ 2;
+    ///   |           ^
+    /// ```
+    pub fn render(&self, source: &str, filename: &str, color: bool) -> String {
+        let mut output = String::new();
+        let severity_color = match self.severity {
+            Severity::Error => RED,
+            Severity::Warning => YELLOW,
+        };
+        let header = format!("{}[{}]", self.kind, self.code.id);
+        let _ = writeln!(output, "{}: {}", paint(&header, severity_color, color), self.message);
+
+        if self.span == Span::default() {
+            self.write_notes(&mut output, 1);
+            return output;
+        }
+
+        // a span past the end of `source` came from different source text,
+        // like a function defined in an earlier REPL entry
+        if self.span.end > source.len() {
+            let _ = writeln!(output, " --> {}:{}:{}", filename, self.span.line, self.span.column);
+            self.write_notes(&mut output, 1);
+            return output;
+        }
+
+        let lines: Vec<&str> = source.split('\n').collect();
+        let mut underlines = vec![Underline::new(&lines, self.span, '^', severity_color, "")];
+        if let Some(label) = self.secondary.as_ref().filter(|label| label.span.end <= source.len()) {
+            underlines.push(Underline::new(&lines, label.span, '-', BLUE, &label.message));
+        }
+        let mut shown: Vec<usize> = underlines
+            .iter()
+            .flat_map(|underline| [underline.first_line, underline.last_line])
+            .collect();
+        shown.sort_unstable();
+        shown.dedup();
+
+        let gutter = (shown.last().copied().unwrap_or(0) + 1).to_string().len();
+        let _ = writeln!(output, "{:gutter$}--> {}:{}:{}", "", filename, self.span.line, self.span.column);
+        let _ = writeln!(output, "{:gutter$} |", "");
+        let mut previous = None;
+        for &line in &shown {
+            if previous.is_some_and(|previous| line > previous + 1) {
+                output.push_str("...\n");
+            }
+            previous = Some(line);
+            let text = lines.get(line).copied().unwrap_or("");
+            let _ = writeln!(output, "{:>gutter$} | {}", line + 1, expand_tabs(text).trim_end());
+            for underline in &underlines {
+                if let Some((from, to)) = underline.columns(&lines, line) {
+                    let label = if line == underline.last_line { underline.message } else { "" };
+                    let marks = underline.marker.to_string().repeat(to - from);
+                    let row = format!("{} {}", marks, label);
+                    let row = paint(row.trim_end(), underline.color, color);
+                    let _ = writeln!(output, "{:gutter$} | {:from$}{}", "", "", row);
+                }
+            }
+        }
+        self.write_notes(&mut output, gutter);
+        output
+    }
+
+    /// Writes the notes, then the help, lined up under a gutter `gutter`
+    /// characters wide.
+    fn write_notes(&self, output: &mut String, gutter: usize) {
+        for note in &self.notes {
+            let _ = writeln!(output, "{:gutter$} = note: {}", "", note);
+        }
+        if let Some(help) = &self.help {
+            let _ = writeln!(output, "{:gutter$} = help: {}", "", help);
+        }
+    }
+}
+
+/// Where a span is drawn: the lines it starts and ends on, counted from 0.
+struct Underline<'a> {
+    start: usize,
+    end: usize,
+    first_line: usize,
+    last_line: usize,
+    marker: char,
+    color: &'static str,
+    message: &'a str,
+}
+
+impl<'a> Underline<'a> {
+    fn new(lines: &[&str], span: Span, marker: char, color: &'static str, message: &'a str) -> Self {
+        // a span ending right after a newline ends on the line before it
+        let last = if span.end > span.start { span.end - 1 } else { span.start };
+        Underline {
+            start: span.start,
+            end: span.end.max(span.start),
+            first_line: line_of(lines, span.start),
+            last_line: line_of(lines, last),
+            marker,
+            color,
+            message,
+        }
+    }
+
+    /// The display columns to underline on `line`, if the span covers it.
+    /// Zero-width spans get one mark, and spans running over several lines
+    /// are underlined from their start to the end of the first line, and
+    /// from the first non-blank character to their end on the last.
+    fn columns(&self, lines: &[&str], line: usize) -> Option<(usize, usize)> {
+        if line != self.first_line && line != self.last_line {
+            return None;
+        }
+        let text = lines.get(line).copied().unwrap_or("");
+        let line_start = line_start(lines, line);
+        let from = if line == self.first_line {
+            display_width(text, self.start - line_start)
+        } else {
+            display_width(text, text.len() - text.trim_start().len())
+        };
+        let to = if line == self.last_line {
+            display_width(text, self.end.saturating_sub(line_start))
+        } else {
+            display_width(text, text.trim_end().len())
+        };
+        Some((from, to.max(from + 1)))
+    }
+}
+
+/// The index of the line containing byte `offset`.
+fn line_of(lines: &[&str], offset: usize) -> usize {
+    let mut line_start = 0;
+    for (index, line) in lines.iter().enumerate() {
+        if offset <= line_start + line.len() {
+            return index;
+        }
+        line_start += line.len() + 1;
+    }
+    lines.len().saturating_sub(1)
+}
+
+/// The byte offset where `line` starts.
+fn line_start(lines: &[&str], line: usize) -> usize {
+    lines[..line].iter().map(|line| line.len() + 1).sum()
+}
+
+/// How many columns the first `bytes` bytes of `text` take up once tabs are
+/// expanded.
+fn display_width(text: &str, bytes: usize) -> usize {
+    text.char_indices()
+        .take_while(|(offset, _)| *offset < bytes)
+        .map(|(_, c)| if c == '\t' { TAB_WIDTH } else { 1 })
+        .sum()
+}
+
+fn expand_tabs(text: &str) -> String {
+    text.replace('\t', &" ".repeat(TAB_WIDTH))
+}
+
+impl Error {
+    /// The error as a report to render with its source.
+    pub fn report(&self) -> Report {
+        let report = |kind, code, message: &str, span| Report {
+            severity: Severity::Error,
+            kind,
+            code,
+            message: message.to_string(),
+            span,
+            secondary: None,
+            notes: Vec::new(),
+            help: None,
+        };
+        match self {
+            Error::Lex(error) => Report {
+                secondary: error.secondary.as_deref().cloned(),
+                ..report("Lexer error", error.code, &error.message, error.span)
+            },
+            Error::Parse(error) => report("Parse error", error.code, &error.message, error.span),
+            Error::Import(error) => report("Import error", error.code, &error.message, error.span),
+            Error::Runtime(error) => Report {
+                notes: error.trace_lines(),
+                ..report("Runtime error", error.code, &error.message, error.span)
+            },
+        }
+    }
+}
+
+impl Diagnostic {
+    /// The diagnostic as a report to render with its source.
+    pub fn report(&self) -> Report {
+        Report {
+            severity: self.severity,
+            // codes are numbered by where the problem is found
+            kind: match (self.severity, &self.code.id[..3]) {
+                (Severity::Warning, _) => "Warning",
+                (Severity::Error, "E00") => "Lexer error",
+                (Severity::Error, "E01") => "Parse error",
+                (Severity::Error, _) => "Check error",
+            },
+            code: self.code,
+            message: self.message.clone(),
+            span: self.span,
+            secondary: None,
+            notes: Vec::new(),
+            help: self.suggestion.clone(),
+        }
+    }
+}
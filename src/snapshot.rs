@@ -0,0 +1,373 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use indexmap::IndexMap;
+
+use crate::environment::Environment;
+use crate::interpreter::Interpreter;
+use crate::json::Json;
+use crate::lexer::escape_string;
+use crate::symbol::Symbol;
+use crate::value::{Instance, Value, format_number};
+
+// written in every snapshot; `restore` reads no other version
+const VERSION: f64 = 1.0;
+
+/// Why `Interpreter::snapshot` or `Interpreter::restore` failed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SnapshotError {
+    // a function, class, or module no global holds, so a restore couldn't
+    // find it again, at a path among the globals such as `handlers[2]`
+    Unnamed { path: String, type_name: &'static str },
+    // the snapshot refers to the function, class, or module held by the
+    // global `name`, which the interpreter being restored doesn't have
+    Unlinked { name: String, type_name: String },
+    // the bytes aren't a snapshot, or not one this version can read
+    Invalid(String),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::Unnamed { path, type_name } => {
+                write!(f, "The {} at {} can't be saved, since no global holds it", type_name, path)
+            }
+            SnapshotError::Unlinked { name, type_name } => {
+                write!(f, "The snapshot needs the {} '{}', which isn't defined", type_name, name)
+            }
+            SnapshotError::Invalid(message) => write!(f, "Invalid snapshot: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl Interpreter {
+    /// The global variables, as bytes `restore` can read back, e.g. to keep
+    /// a long-running script's state across a restart.
+    ///
+    /// Nulls, booleans, numbers, strings, arrays, maps, and instances are
+    /// saved whole. An array, map, or instance reached more than once, even
+    /// from inside itself, is saved once, so variables that shared it share
+    /// it again after `restore`. Functions, classes, and modules are saved
+    /// as the name of a global holding them, to be found again in the
+    /// script run before `restore`, and a global holding the definition
+    /// named after it, like a declared function or a builtin, is left out.
+    /// It's an error if a definition isn't held by any global.
+    pub fn snapshot(&self) -> Result<Vec<u8>, SnapshotError> {
+        let mut bindings: Vec<(Symbol, Value)> = self.globals().borrow().bindings().collect();
+        bindings.sort_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+
+        let mut writer = Writer { homes: HashMap::new(), ids: HashMap::new(), queue: Vec::new() };
+        // a definition is saved as the global named after it if there is
+        // one, or else as the first global holding it
+        let definitions = bindings
+            .iter()
+            .filter(|(_, value)| is_definition(value))
+            .filter_map(|(name, value)| Some((address(value)?, name.clone(), value)));
+        for (address, name, value) in definitions.clone() {
+            if definition_name(value) == Some(name.as_str()) {
+                writer.homes.insert(address, name);
+            } else if let Value::Builtin(_) | Value::NativeFunction(_) = value
+                && let Some(own) = definition_name(value)
+                && self.get_global(own).as_ref().and_then(self::address) == Some(address)
+            {
+                // builtins and host functions are found under their own
+                // names in the scope around the globals, unless shadowed
+                writer.homes.entry(address).or_insert(Symbol::from(own));
+            }
+        }
+        for (address, name, _) in definitions {
+            writer.homes.entry(address).or_insert(name);
+        }
+
+        let mut globals = Vec::new();
+        for (name, value) in &bindings {
+            if is_definition(value) && address(value).is_some_and(|address| writer.homes.get(&address) == Some(name)) {
+                continue;
+            }
+            globals.push((name.to_string(), writer.value(value, Place::Global(name.clone()))?));
+        }
+        let mut containers = Vec::new();
+        while containers.len() < writer.queue.len() {
+            containers.push(writer.container(containers.len())?);
+        }
+        let snapshot = Json::object([
+            ("snapshot", Json::Number(VERSION)),
+            ("globals", Json::Object(globals)),
+            ("containers", Json::Array(containers)),
+        ]);
+        Ok(snapshot.to_string().into_bytes())
+    }
+
+    /// Sets the global variables to those saved by `snapshot`, leaving any
+    /// others as they are. The functions, classes, and modules it names are
+    /// the ones held by globals of those names here, so the script they
+    /// come from should be run first. Nothing changes if it fails.
+    pub fn restore(&mut self, bytes: &[u8]) -> Result<(), SnapshotError> {
+        let text = std::str::from_utf8(bytes).map_err(|_| invalid("it isn't UTF-8"))?;
+        let snapshot = Json::parse(text).map_err(SnapshotError::Invalid)?;
+        if snapshot.get("snapshot").and_then(Json::as_number) != Some(VERSION) {
+            return Err(invalid("it isn't a version 1 snapshot"));
+        }
+        let containers = snapshot
+            .get("containers")
+            .and_then(Json::as_array)
+            .ok_or_else(|| invalid("it has no containers"))?;
+        let Some(Json::Object(globals)) = snapshot.get("globals") else {
+            return Err(invalid("it has no globals"));
+        };
+
+        let mut reader = Reader { globals: self.globals(), shells: Vec::new() };
+        // every container is made empty before any is filled, so they can
+        // refer to each other and to themselves
+        for container in containers {
+            let shell = if container.get("array").is_some() {
+                Value::array(Vec::new())
+            } else if container.get("map").is_some() {
+                Value::map(IndexMap::new())
+            } else if let Some(class) = container.get("class") {
+                match reader.value(class)? {
+                    Value::Class(class) => {
+                        Value::Instance(Rc::new(Instance { class, fields: RefCell::new(IndexMap::new()) }))
+                    }
+                    _ => return Err(invalid("an instance's class isn't a class")),
+                }
+            } else {
+                return Err(invalid("a container isn't an array, map, or instance"));
+            };
+            reader.shells.push(shell);
+        }
+        for (container, shell) in containers.iter().zip(&reader.shells) {
+            match shell {
+                Value::Array(elements) => {
+                    let items = container
+                        .get("array")
+                        .and_then(Json::as_array)
+                        .ok_or_else(|| invalid("an array's elements aren't an array"))?;
+                    *elements.borrow_mut() = items.iter().map(|item| reader.value(item)).collect::<Result<_, _>>()?;
+                }
+                Value::Map(entries) => *entries.borrow_mut() = reader.entries(container.get("map"))?,
+                Value::Instance(instance) => *instance.fields.borrow_mut() = reader.entries(container.get("fields"))?,
+                _ => unreachable!("only arrays, maps, and instances are made"),
+            }
+        }
+        let restored = globals
+            .iter()
+            .map(|(name, value)| Ok((name, reader.value(value)?)))
+            .collect::<Result<Vec<_>, SnapshotError>>()?;
+
+        let mut environment = self.globals().borrow_mut();
+        for (name, value) in restored {
+            environment.replace(name.as_str(), value);
+        }
+        Ok(())
+    }
+}
+
+fn invalid(message: &str) -> SnapshotError {
+    SnapshotError::Invalid(message.to_string())
+}
+
+// what identifies an array, map, or instance, or a function, class, or
+// module, whichever variables hold it
+fn address(value: &Value) -> Option<*const ()> {
+    Some(match value {
+        Value::Array(elements) => Rc::as_ptr(elements) as *const (),
+        Value::Map(entries) => Rc::as_ptr(entries) as *const (),
+        Value::Instance(instance) => Rc::as_ptr(instance) as *const (),
+        Value::Function(function) => Rc::as_ptr(function) as *const (),
+        Value::Builtin(builtin) => *builtin as *const _ as *const (),
+        Value::NativeFunction(function) => Rc::as_ptr(function) as *const (),
+        Value::Module(module) => Rc::as_ptr(module) as *const (),
+        Value::Class(class) => Rc::as_ptr(class) as *const (),
+        _ => return None,
+    })
+}
+
+fn is_definition(value: &Value) -> bool {
+    matches!(
+        value,
+        Value::Function(_) | Value::Builtin(_) | Value::NativeFunction(_) | Value::Module(_) | Value::Class(_)
+    )
+}
+
+// the name a definition was given where it was declared or registered;
+// a module's is its file, which no global is called
+fn definition_name(value: &Value) -> Option<&str> {
+    match value {
+        Value::Function(function) => Some(function.declaration.name.as_str()),
+        Value::Builtin(builtin) => Some(builtin.name),
+        Value::NativeFunction(function) => Some(&function.name),
+        Value::Class(class) => Some(class.declaration.name.as_str()),
+        _ => None,
+    }
+}
+
+// where a value was met: a global, or inside the array, map, or instance
+// at the given index of the writer's queue, so its path can be worked out
+// when there's an error without keeping one for every value
+#[derive(Clone)]
+enum Place {
+    Global(Symbol),
+    Element(usize, usize),
+    Entry(usize, String),
+    // an instance's class
+    Class(usize),
+}
+
+struct Writer {
+    // the global each definition is saved as, by its address
+    homes: HashMap<*const (), Symbol>,
+    // the index in the snapshot's containers of each array, map, and
+    // instance met so far, by its address
+    ids: HashMap<*const (), usize>,
+    // those arrays, maps, and instances in the same order, with where each
+    // was first met
+    queue: Vec<(Value, Place)>,
+}
+
+impl Writer {
+    // a container is saved as its index, and written out from the queue
+    // afterwards, so nesting never deepens the recursion
+    fn value(&mut self, value: &Value, place: Place) -> Result<Json, SnapshotError> {
+        Ok(match value {
+            Value::Null => Json::Null,
+            Value::Bool(b) => Json::Bool(*b),
+            // JSON has no infinities or NaN, and its zero has no sign
+            Value::Number(n) if !n.is_finite() => Json::object([("number", format_number(*n).as_str().into())]),
+            Value::Number(n) if *n == 0.0 && n.is_sign_negative() => Json::object([("number", "-0".into())]),
+            Value::Number(n) => Json::Number(*n),
+            Value::Int(n) => Json::Int(*n),
+            Value::String(s) => Json::String(s.to_string()),
+            Value::Array(_) | Value::Map(_) | Value::Instance(_) => {
+                let address = address(value).expect("containers have addresses");
+                let queue = &mut self.queue;
+                let id = *self.ids.entry(address).or_insert_with(|| {
+                    queue.push((value.clone(), place));
+                    queue.len() - 1
+                });
+                Json::object([("ref", id.into())])
+            }
+            _ => {
+                let address = address(value).expect("definitions have addresses");
+                let Some(name) = self.homes.get(&address) else {
+                    return Err(SnapshotError::Unnamed { path: self.path(&place), type_name: value.type_name() });
+                };
+                Json::object([("global", name.as_str().into()), ("type", value.type_name().into())])
+            }
+        })
+    }
+
+    // the container at `id` in the queue
+    fn container(&mut self, id: usize) -> Result<Json, SnapshotError> {
+        Ok(match self.queue[id].0.clone() {
+            Value::Array(elements) => {
+                let items = elements
+                    .borrow()
+                    .iter()
+                    .enumerate()
+                    .map(|(index, element)| self.value(element, Place::Element(id, index)))
+                    .collect::<Result<_, _>>()?;
+                Json::object([("array", Json::Array(items))])
+            }
+            Value::Map(entries) => Json::object([("map", self.entries(&entries.borrow(), id)?)]),
+            Value::Instance(instance) => Json::object([
+                ("class", self.value(&Value::Class(Rc::clone(&instance.class)), Place::Class(id))?),
+                ("fields", self.entries(&instance.fields.borrow(), id)?),
+            ]),
+            _ => unreachable!("only arrays, maps, and instances are queued"),
+        })
+    }
+
+    fn entries(&mut self, entries: &IndexMap<String, Value>, id: usize) -> Result<Json, SnapshotError> {
+        let members = entries
+            .iter()
+            .map(|(key, value)| Ok((key.clone(), self.value(value, Place::Entry(id, key.clone()))?)))
+            .collect::<Result<_, SnapshotError>>()?;
+        Ok(Json::Object(members))
+    }
+
+    // e.g. `handlers[2]`, `config.retry`, or `names["first name"]`
+    fn path(&self, place: &Place) -> String {
+        let mut steps = Vec::new();
+        let mut place = place;
+        loop {
+            let container = match place {
+                Place::Global(name) => {
+                    steps.push(name.to_string());
+                    break;
+                }
+                Place::Element(container, index) => {
+                    steps.push(format!("[{}]", index));
+                    container
+                }
+                Place::Entry(container, key) => {
+                    let is_name = key.starts_with(|c: char| c.is_alphabetic() || c == '_')
+                        && key.chars().all(|c| c.is_alphanumeric() || c == '_');
+                    steps.push(if is_name { format!(".{}", key) } else { format!("[{}]", escape_string(key)) });
+                    container
+                }
+                Place::Class(container) => container,
+            };
+            place = &self.queue[*container].1;
+        }
+        steps.reverse();
+        steps.concat()
+    }
+}
+
+struct Reader<'a> {
+    // where the definitions a snapshot names are looked up
+    globals: &'a RefCell<Environment>,
+    // the snapshot's containers, by index
+    shells: Vec<Value>,
+}
+
+impl Reader<'_> {
+    fn value(&self, json: &Json) -> Result<Value, SnapshotError> {
+        Ok(match json {
+            Json::Null => Value::Null,
+            Json::Bool(b) => Value::Bool(*b),
+            Json::Number(n) => Value::Number(*n),
+            Json::Int(n) => Value::Int(*n),
+            Json::String(s) => Value::String(s.as_str().into()),
+            Json::Object(_) if let Some(id) = json.get("ref") => id
+                .as_number()
+                .filter(|id| id.fract() == 0.0 && *id >= 0.0)
+                .and_then(|id| self.shells.get(id as usize))
+                .cloned()
+                .ok_or_else(|| invalid("a reference is to no container"))?,
+            Json::Object(_) if let Some(name) = json.get("global").and_then(Json::as_str) => {
+                let type_name = json.get("type").and_then(Json::as_str).unwrap_or_default();
+                let value = self.globals.borrow().get(name);
+                match value {
+                    Some(value) if is_definition(&value) && value.type_name() == type_name => value,
+                    _ => {
+                        return Err(SnapshotError::Unlinked {
+                            name: name.to_string(),
+                            type_name: type_name.to_string(),
+                        });
+                    }
+                }
+            }
+            Json::Object(_) if let Some(number) = json.get("number").and_then(Json::as_str) => match number {
+                "nan" => Value::Number(f64::NAN),
+                "inf" => Value::Number(f64::INFINITY),
+                "-inf" => Value::Number(f64::NEG_INFINITY),
+                "-0" => Value::Number(-0.0),
+                _ => return Err(invalid("a number isn't one")),
+            },
+            _ => return Err(invalid("a value isn't one")),
+        })
+    }
+
+    fn entries(&self, json: Option<&Json>) -> Result<IndexMap<String, Value>, SnapshotError> {
+        let Some(Json::Object(members)) = json else {
+            return Err(invalid("a map's entries aren't an object"));
+        };
+        members.iter().map(|(key, value)| Ok((key.clone(), self.value(value)?))).collect()
+    }
+}
@@ -0,0 +1,141 @@
+/// The text of a program or imported file, with the name errors in it are
+/// reported under, like its path or `<stdin>`.
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    pub name: String,
+    pub contents: String,
+}
+
+impl SourceFile {
+    pub fn new(name: impl Into<String>, contents: impl Into<String>) -> Self {
+        SourceFile {
+            name: name.into(),
+            contents: contents.into(),
+        }
+    }
+}
+
+/// The files an interpreter has run code from, so that the `file` of any
+/// span it reports can be turned back into a name and text to render the
+/// span with. Ids start at 1: file 0 is source the map doesn't know about,
+/// like a program lexed on its own with `Lexer::new`.
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    /// Adds `file`, returning the id its spans should carry.
+    pub fn add(&mut self, file: SourceFile) -> usize {
+        self.files.push(file);
+        self.files.len()
+    }
+
+    pub fn get(&self, file: usize) -> Option<&SourceFile> {
+        self.files.get(file.checked_sub(1)?)
+    }
+
+    /// Every file with its id, in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &SourceFile)> {
+        self.files.iter().enumerate().map(|(index, file)| (index + 1, file))
+    }
+}
+
+/// What a column counts from the start of its line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Columns {
+    /// Characters, as spans count them.
+    #[default]
+    Chars,
+    /// UTF-16 code units, as the language server protocol counts them.
+    Utf16,
+}
+
+/// Where each line of a source starts, for converting between byte
+/// offsets and positions in either direction. Lines and columns start at
+/// 1, as in spans. A line ends at a `\n`, and a `\r` just before it belongs
+/// to the line break rather than the line. The last line needn't end with
+/// a line break.
+#[derive(Debug, Clone)]
+pub struct LineIndex<'a> {
+    source: &'a str,
+    // the offset each line starts at
+    starts: Vec<usize>,
+    columns: Columns,
+}
+
+impl<'a> LineIndex<'a> {
+    /// An index of `source` counting columns in characters.
+    pub fn new(source: &'a str) -> Self {
+        let starts = std::iter::once(0).chain(source.match_indices('\n').map(|(i, _)| i + 1)).collect();
+        LineIndex { source, starts, columns: Columns::Chars }
+    }
+
+    pub fn with_columns(mut self, columns: Columns) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// The line and column of the byte at `offset`. An offset inside a
+    /// character is taken as that character's, one inside a `\r\n` as the
+    /// end of its line, and one past the end of the source as the end.
+    pub fn offset_to_position(&self, offset: usize) -> (usize, usize) {
+        let mut offset = offset.min(self.source.len());
+        while !self.source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let line = self.starts.partition_point(|&start| start <= offset) - 1;
+        let mut text = &self.source[self.starts[line]..offset];
+        if self.source[offset..].starts_with('\n') {
+            text = text.strip_suffix('\r').unwrap_or(text);
+        }
+        (line + 1, self.width(text) + 1)
+    }
+
+    /// The offset of the character at `line` and `column`, where the
+    /// column just past a line's last character is its end. `None` if
+    /// there's no such line or column, or if the column falls inside a
+    /// character, as it can between the halves of a UTF-16 surrogate pair.
+    pub fn position_to_offset(&self, line: usize, column: usize) -> Option<usize> {
+        let start = *self.starts.get(line.checked_sub(1)?)?;
+        let text = self.line_text(line - 1);
+        let mut counted = 1;
+        for (i, ch) in text.char_indices() {
+            if counted >= column {
+                return (counted == column).then_some(start + i);
+            }
+            counted += self.width_of(ch);
+        }
+        (counted == column).then_some(start + text.len())
+    }
+
+    // the text of the zero-based `line`, without its line break
+    fn line_text(&self, line: usize) -> &'a str {
+        let start = self.starts[line];
+        match self.starts.get(line + 1) {
+            Some(&next) => {
+                let text = &self.source[start..next - 1];
+                text.strip_suffix('\r').unwrap_or(text)
+            }
+            None => &self.source[start..],
+        }
+    }
+
+    fn width(&self, text: &str) -> usize {
+        match self.columns {
+            Columns::Chars => text.chars().count(),
+            Columns::Utf16 => text.chars().map(char::len_utf16).sum(),
+        }
+    }
+
+    fn width_of(&self, ch: char) -> usize {
+        match self.columns {
+            Columns::Chars => 1,
+            Columns::Utf16 => ch.len_utf16(),
+        }
+    }
+}
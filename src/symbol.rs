@@ -0,0 +1,182 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::rc::{Rc, Weak};
+
+/// An interned identifier: a shared name, so scopes can hash and compare
+/// names by address without touching their text. Every symbol for the same
+/// name shares one copy of it and is equal. Names are interned per thread,
+/// so symbols can't be sent to another one, any more than the values and
+/// syntax trees holding them.
+#[derive(Clone)]
+pub struct Symbol(Rc<str>);
+
+/// The names interned on this thread. The table only points at them, so a
+/// name is freed with the last symbol for it, and interning it again later
+/// makes a new copy.
+#[derive(Default)]
+struct Interner {
+    symbols: HashMap<Box<str>, Weak<str>>,
+    // how many names the table held when it was last cleared of freed
+    // ones, so it's cleared again once it has doubled
+    live: usize,
+}
+
+thread_local! {
+    static INTERNER: RefCell<Interner> = RefCell::default();
+}
+
+impl Interner {
+    /// Forgets the names nothing uses anymore.
+    fn remove_freed(&mut self) {
+        self.symbols.retain(|_, name| name.strong_count() > 0);
+        self.live = self.symbols.len();
+    }
+}
+
+impl Symbol {
+    /// The symbol for `name`, interning it if no symbol for it exists.
+    pub fn intern(name: &str) -> Symbol {
+        INTERNER.with_borrow_mut(|interner| {
+            if let Some(symbol) = interner.symbols.get(name).and_then(Weak::upgrade) {
+                return Symbol(symbol);
+            }
+            if interner.symbols.len() >= 2 * interner.live.max(64) {
+                interner.remove_freed();
+            }
+            let symbol: Rc<str> = name.into();
+            interner.symbols.insert(name.into(), Rc::downgrade(&symbol));
+            Symbol(symbol)
+        })
+    }
+
+    /// The symbol for `name` if one exists, without interning it
+    /// otherwise. No scope can bind a name that has no symbol, so lookups
+    /// use this to avoid keeping every name they're asked about.
+    pub fn lookup(name: &str) -> Option<Symbol> {
+        INTERNER.with_borrow(|interner| interner.symbols.get(name).and_then(Weak::upgrade).map(Symbol))
+    }
+
+    /// How many names are interned on this thread, counting only those
+    /// some symbol still uses.
+    pub fn interned() -> usize {
+        INTERNER.with_borrow_mut(|interner| {
+            interner.remove_freed();
+            interner.live
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Symbol) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Symbol {}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Rc::as_ptr(&self.0).cast::<u8>().hash(state);
+    }
+}
+
+/// A name to look up, given as a symbol or as text. Unlike `Into<Symbol>`,
+/// looking up text doesn't intern it.
+pub trait LookupName {
+    /// The symbol for the name, or `None` if nothing can be bound to it.
+    fn symbol(&self) -> Option<Symbol>;
+}
+
+impl LookupName for Symbol {
+    fn symbol(&self) -> Option<Symbol> {
+        Some(self.clone())
+    }
+}
+
+impl LookupName for &Symbol {
+    fn symbol(&self) -> Option<Symbol> {
+        Some((*self).clone())
+    }
+}
+
+impl LookupName for &str {
+    fn symbol(&self) -> Option<Symbol> {
+        Symbol::lookup(self)
+    }
+}
+
+impl LookupName for &String {
+    fn symbol(&self) -> Option<Symbol> {
+        Symbol::lookup(self)
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(name: &str) -> Self {
+        Symbol::intern(name)
+    }
+}
+
+impl From<&String> for Symbol {
+    fn from(name: &String) -> Self {
+        Symbol::intern(name)
+    }
+}
+
+impl From<&Symbol> for Symbol {
+    fn from(symbol: &Symbol) -> Self {
+        symbol.clone()
+    }
+}
+
+impl From<&Symbol> for String {
+    fn from(symbol: &Symbol) -> Self {
+        symbol.as_str().to_string()
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for Symbol {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// shown as the name, so ASTs print the same as when names were strings
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
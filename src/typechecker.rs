@@ -0,0 +1,424 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::ast::{
+    BinaryOp, Expr, ExprId, ExprKind, Exprs, FunctionDecl, LogicalOp, Program, Stmt, StmtKind, Type, UnaryOp,
+};
+use crate::checker::{Diagnostic, Severity};
+use crate::codes::{self, Code};
+use crate::lexer::Span;
+
+/// Finds values whose type doesn't match an annotation: `let` initializers
+/// and assignments to annotated variables, arguments to functions with
+/// annotated parameters, and returned values of functions with a declared
+/// return type. Also reports operators that can only fail, like
+/// `"a" - 1`.
+///
+/// Types are inferred from literals and operators; anything else that isn't
+/// annotated is `any` and matches every type, so unannotated code never gets
+/// a diagnostic.
+pub fn check(program: &Program) -> Vec<Diagnostic> {
+    let mut checker = TypeChecker {
+        exprs: &program.exprs,
+        scopes: Vec::new(),
+        return_types: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+    checker.scoped(&program.statements);
+    checker.diagnostics
+}
+
+/// What is known about a name: its declared type, and for functions that
+/// can't be reassigned, the declaration its calls are checked against.
+#[derive(Clone)]
+struct Binding {
+    annotation: Type,
+    function: Option<Rc<FunctionDecl>>,
+}
+
+struct TypeChecker<'a> {
+    // the arena of the expressions in the function or program being checked
+    exprs: &'a Exprs,
+    scopes: Vec<HashMap<String, Binding>>,
+    // the declared return type of each function being checked, innermost last
+    return_types: Vec<(String, Type)>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> TypeChecker<'a> {
+    /// Checks `statements` in a new scope. Function declarations are bound
+    /// first, so calls above a declaration are checked too.
+    fn scoped(&mut self, statements: &'a [Stmt]) {
+        self.scopes.push(HashMap::new());
+        for statement in statements {
+            if let StmtKind::Function(declaration) = &statement.without_export().kind {
+                self.declare_function(declaration);
+            }
+        }
+        for statement in statements {
+            self.statement(statement);
+        }
+        self.scopes.pop();
+    }
+
+    fn declare(&mut self, name: &str, annotation: Type, function: Option<Rc<FunctionDecl>>) {
+        let scope = self.scopes.last_mut().expect("there is always a global scope");
+        scope.insert(name.to_string(), Binding { annotation, function });
+    }
+
+    fn declare_function(&mut self, declaration: &Rc<FunctionDecl>) {
+        self.declare(&declaration.name, Type::Function, Some(Rc::clone(declaration)));
+    }
+
+    fn expr(&self, id: ExprId) -> &'a Expr {
+        &self.exprs[id]
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Binding> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    fn mismatch(&mut self, code: &'static Code, message: String, span: Span) {
+        self.diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            code,
+            message,
+            span,
+            suggestion: None,
+        });
+    }
+
+    /// The type an assignment stores: `value`'s for a plain one, or for a
+    /// compound one like `+=`, the type of its operator applied to the
+    /// target's `current` value and `value`.
+    fn compound(&mut self, operator: Option<BinaryOp>, current: Type, value: Type, span: Span) -> Type {
+        let Some(operator) = operator else {
+            return value;
+        };
+        binary_type(operator, current, value).unwrap_or_else(|| {
+            let message = format!("Cannot apply '{}=' to {} and {}", operator.symbol(), current, value);
+            self.mismatch(&codes::INVALID_OPERAND, message, span);
+            Type::Any
+        })
+    }
+
+    fn statement(&mut self, stmt: &'a Stmt) {
+        match &stmt.kind {
+            StmtKind::Expression(expr) => {
+                self.expression(expr);
+            }
+            StmtKind::Let { name, annotation, initializer, constant, .. } => {
+                let annotation = annotation.unwrap_or(Type::Any);
+                // a variable declared without a value holds null until it
+                // is assigned, which the annotation doesn't rule out
+                if let Some(initializer) = initializer {
+                    let found = self.expression(initializer);
+                    if !annotation.accepts(found) {
+                        self.mismatch(
+                            &codes::TYPE_MISMATCH,
+                            format!("'{}' is declared as {} but is given {}", name, annotation, found),
+                            initializer.span,
+                        );
+                    }
+                }
+                // a constant function's calls can be checked like a declaration's
+                let function = match initializer {
+                    Some(Expr { kind: ExprKind::Function(declaration), .. }) if *constant => {
+                        Some(Rc::clone(declaration))
+                    }
+                    _ => None,
+                };
+                self.declare(name, annotation, function);
+            }
+            StmtKind::Destructure { pattern, initializer, .. } => {
+                self.expression(initializer);
+                for (name, _) in pattern.names() {
+                    self.declare(name, Type::Any, None);
+                }
+            }
+            StmtKind::Block(statements) => self.scoped(statements),
+            StmtKind::If { condition, then_branch, else_branch } => {
+                self.expression(condition);
+                self.scoped(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.scoped(else_branch);
+                }
+            }
+            StmtKind::While { condition, body, .. } => {
+                self.expression(condition);
+                self.scoped(body);
+            }
+            StmtKind::DoWhile { body, condition, .. } => {
+                self.scoped(body);
+                self.expression(condition);
+            }
+            StmtKind::Match { subject, arms, default } => {
+                self.expression(subject);
+                for arm in arms {
+                    for value in &arm.values {
+                        self.expression(value);
+                    }
+                    self.scoped(&arm.body);
+                }
+                if let Some(default) = default {
+                    self.scoped(default);
+                }
+            }
+            StmtKind::For { initializer, condition, increment, body, .. } => {
+                self.scopes.push(HashMap::new());
+                if let Some(initializer) = initializer {
+                    self.statement(initializer);
+                }
+                if let Some(condition) = condition {
+                    self.expression(condition);
+                }
+                self.scoped(body);
+                if let Some(increment) = increment {
+                    self.expression(increment);
+                }
+                self.scopes.pop();
+            }
+            StmtKind::ForIn { variable, iterable, body, .. } => {
+                self.expression(iterable);
+                self.scopes.push(HashMap::new());
+                self.declare(variable, Type::Any, None);
+                self.scoped(body);
+                self.scopes.pop();
+            }
+            StmtKind::Function(declaration) => {
+                self.declare_function(declaration);
+                self.function(declaration);
+            }
+            StmtKind::Class(declaration) => {
+                self.declare(&declaration.name, Type::Any, None);
+                for method in &declaration.methods {
+                    self.function(method);
+                }
+            }
+            StmtKind::Return(value) => {
+                let found = match value {
+                    Some(value) => self.expression(value),
+                    None => Type::Null,
+                };
+                if let Some((function, expected)) = self.return_types.last()
+                    && !expected.accepts(found)
+                {
+                    let message = format!("'{}' must return {} but returns {}", function, expected, found);
+                    let span = value.as_ref().map_or(stmt.span, |value| value.span);
+                    self.mismatch(&codes::TYPE_MISMATCH, message, span);
+                }
+            }
+            StmtKind::Try { body, name, handler } => {
+                self.scoped(body);
+                self.scopes.push(HashMap::new());
+                self.declare(name, Type::Any, None);
+                self.scoped(handler);
+                self.scopes.pop();
+            }
+            StmtKind::Throw(value) => {
+                self.expression(value);
+            }
+            StmtKind::Export(declaration) => self.statement(declaration),
+            StmtKind::Test { body, .. } => self.scoped(body),
+            StmtKind::Break(_) | StmtKind::Continue(_) | StmtKind::Import { .. } => {}
+        }
+    }
+
+    fn function(&mut self, declaration: &'a FunctionDecl) {
+        let enclosing_exprs = std::mem::replace(&mut self.exprs, &declaration.exprs);
+        self.scopes.push(HashMap::new());
+        for param in &declaration.params {
+            let annotation = param.annotation.unwrap_or(if param.rest { Type::Array } else { Type::Any });
+            if let Some(default) = &param.default {
+                let found = self.expression(default);
+                if !annotation.accepts(found) {
+                    self.mismatch(
+                        &codes::TYPE_MISMATCH,
+                        format!("Parameter '{}' is declared as {} but defaults to {}", param.name, annotation, found),
+                        default.span,
+                    );
+                }
+            }
+            self.declare(&param.name, annotation, None);
+        }
+        let return_type = declaration.return_type.unwrap_or(Type::Any);
+        self.return_types.push((declaration.name.to_string(), return_type));
+        self.scoped(&declaration.body);
+        self.return_types.pop();
+        self.scopes.pop();
+        self.exprs = enclosing_exprs;
+    }
+
+    /// Checks `expr` and returns the type of its value, `any` if unknown.
+    fn expression(&mut self, expr: &'a Expr) -> Type {
+        match &expr.kind {
+            ExprKind::Number(_) | ExprKind::Int(_) => Type::Number,
+            ExprKind::String(_) => Type::String,
+            ExprKind::Bool(_) => Type::Bool,
+            ExprKind::Null => Type::Null,
+            ExprKind::Variable(name) => self.lookup(name).map_or(Type::Any, |binding| binding.annotation),
+            ExprKind::Assign { name, operator, value } => {
+                let found = self.expression(self.expr(*value));
+                let binding = self.scopes.iter_mut().rev().find_map(|scope| scope.get_mut(name.as_str()));
+                let annotation = match binding {
+                    Some(binding) => {
+                        // calls after this may be to a different function
+                        binding.function = None;
+                        binding.annotation
+                    }
+                    None => Type::Any,
+                };
+                let found = self.compound(*operator, annotation, found, expr.span);
+                if !annotation.accepts(found) {
+                    self.mismatch(
+                        &codes::TYPE_MISMATCH,
+                        format!("'{}' is declared as {} but is assigned {}", name, annotation, found),
+                        self.expr(*value).span,
+                    );
+                }
+                found
+            }
+            ExprKind::Unary { operator, operand } => {
+                let operand = self.expression(self.expr(*operand));
+                match operator {
+                    UnaryOp::Not => Type::Bool,
+                    UnaryOp::TypeOf => Type::String,
+                    UnaryOp::Negate => {
+                        if !Type::Number.accepts(operand) {
+                            let message = format!("Cannot apply '-' to {}", operand);
+                            self.mismatch(&codes::INVALID_OPERAND, message, expr.span);
+                        }
+                        Type::Number
+                    }
+                }
+            }
+            ExprKind::Binary { left, operator, right } => {
+                let left = self.expression(self.expr(*left));
+                let right = self.expression(self.expr(*right));
+                match binary_type(*operator, left, right) {
+                    Some(result) => result,
+                    None => {
+                        self.mismatch(
+                            &codes::INVALID_OPERAND,
+                            format!("Cannot apply '{}' to {} and {}", operator.symbol(), left, right),
+                            expr.span,
+                        );
+                        Type::Any
+                    }
+                }
+            }
+            ExprKind::Logical { left, operator, right } => {
+                let left = self.expression(self.expr(*left));
+                let right = self.expression(self.expr(*right));
+                // the result is one of the operands, so only known when both agree
+                match operator {
+                    LogicalOp::And | LogicalOp::Or if left == right => left,
+                    LogicalOp::And | LogicalOp::Or => Type::Any,
+                }
+            }
+            ExprKind::Call { callee, arguments } => {
+                self.expression(self.expr(*callee));
+                let found: Vec<Type> = arguments.iter().map(|argument| self.expression(argument)).collect();
+                let ExprKind::Variable(name) = &self.expr(*callee).kind else {
+                    return Type::Any;
+                };
+                let Some(declaration) = self.lookup(name).and_then(|binding| binding.function.clone()) else {
+                    return Type::Any;
+                };
+                let params = declaration.params.iter().filter(|param| !param.rest);
+                for (position, (param, argument)) in params.zip(arguments).enumerate() {
+                    let expected = param.annotation.unwrap_or(Type::Any);
+                    if !expected.accepts(found[position]) {
+                        self.mismatch(
+                            &codes::TYPE_MISMATCH,
+                            format!(
+                                "Parameter '{}' of '{}' expects {} but is given {}",
+                                param.name, name, expected, found[position]
+                            ),
+                            argument.span,
+                        );
+                    }
+                }
+                declaration.return_type.unwrap_or(Type::Any)
+            }
+            ExprKind::Array(elements) => {
+                elements.iter().for_each(|element| {
+                    self.expression(element);
+                });
+                Type::Array
+            }
+            ExprKind::Index { object, index } => {
+                self.expression(self.expr(*object));
+                self.expression(self.expr(*index));
+                Type::Any
+            }
+            ExprKind::Slice { object, start, end, .. } => {
+                self.expression(self.expr(*object));
+                for bound in [start, end].into_iter().flatten() {
+                    self.expression(self.expr(*bound));
+                }
+                Type::String
+            }
+            ExprKind::SetIndex { object, index, operator, value } => {
+                self.expression(self.expr(*object));
+                self.expression(self.expr(*index));
+                let found = self.expression(self.expr(*value));
+                self.compound(*operator, Type::Any, found, expr.span)
+            }
+            ExprKind::Map(entries) => {
+                entries.iter().for_each(|(_, value)| {
+                    self.expression(value);
+                });
+                Type::Map
+            }
+            ExprKind::Member { object, .. } => {
+                self.expression(self.expr(*object));
+                Type::Any
+            }
+            ExprKind::SetMember { object, operator, value, .. } => {
+                self.expression(self.expr(*object));
+                let found = self.expression(self.expr(*value));
+                self.compound(*operator, Type::Any, found, expr.span)
+            }
+            ExprKind::Range { start, end, .. } => {
+                self.expression(self.expr(*start));
+                self.expression(self.expr(*end));
+                Type::Any
+            }
+            ExprKind::Function(declaration) => {
+                self.function(declaration);
+                Type::Function
+            }
+            ExprKind::This => Type::Any,
+        }
+    }
+}
+
+/// The type of `left operator right`, or `None` if it can only fail at
+/// runtime. Mirrors the cases `binary_operation` accepts.
+fn binary_type(operator: BinaryOp, left: Type, right: Type) -> Option<Type> {
+    let both = |allowed: &[Type]| {
+        allowed
+            .iter()
+            .any(|allowed| allowed.accepts(left) && allowed.accepts(right))
+    };
+    match operator {
+        BinaryOp::Equal | BinaryOp::NotEqual => Some(Type::Bool),
+        BinaryOp::Add if !both(&[Type::Number, Type::String]) => None,
+        // whichever side is known decides between numbers and strings
+        BinaryOp::Add if left != Type::Any => Some(left),
+        BinaryOp::Add => Some(right),
+        BinaryOp::Subtract | BinaryOp::Multiply | BinaryOp::Divide | BinaryOp::Modulo => {
+            both(&[Type::Number]).then_some(Type::Number)
+        }
+        BinaryOp::Less | BinaryOp::LessEqual | BinaryOp::Greater | BinaryOp::GreaterEqual => {
+            both(&[Type::Number, Type::String]).then_some(Type::Bool)
+        }
+        // arrays hold anything, but map keys and substrings are strings
+        BinaryOp::In | BinaryOp::NotIn => match right {
+            Type::Array | Type::Any => Some(Type::Bool),
+            Type::Map | Type::String => Type::String.accepts(left).then_some(Type::Bool),
+            _ => None,
+        },
+    }
+}
@@ -0,0 +1,687 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::rc::Rc;
+
+use indexmap::IndexMap;
+
+use crate::ast::{ClassDecl, FunctionDecl};
+use crate::builtins::Builtin;
+use crate::codes;
+use crate::environment::Environment;
+use crate::interpreter::RuntimeError;
+use crate::lexer::Span;
+use crate::printer::json_string;
+
+/// A value in a running program. Null, bools, numbers, and strings can't
+/// be changed, so it never matters whether two variables hold the same one.
+/// Arrays, maps, and instances are shared instead: assigning one or passing
+/// it to a function doesn't copy it, so a change made through one variable
+/// shows through every other holding it. `copy()` makes an independent copy
+/// of arrays and maps (see `deep_copy`), and `is_same()` tells sharing
+/// apart from equality (see `is_same`).
+#[derive(Debug, Clone)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    // a number written without a fraction or exponent, or made from such
+    // numbers by `+`, `-`, `*`, and `%`: exact across the range of an
+    // `i64`, and otherwise like any other number, equal to the same number
+    // written with a fraction. What happens past that range is up to
+    // `IntOverflow`.
+    Int(i64),
+    String(Rc<str>),
+    // shared, so every variable holding the array sees changes made through
+    // any of them
+    Array(Rc<RefCell<Vec<Value>>>),
+    // string-keyed, and kept in insertion order so printing and iteration
+    // are the same on every run
+    Map(Rc<RefCell<IndexMap<String, Value>>>),
+    Function(Rc<Function>),
+    Builtin(&'static Builtin),
+    NativeFunction(Rc<NativeFunction>),
+    Module(Rc<Module>),
+    Class(Rc<Class>),
+    Instance(Rc<Instance>),
+}
+
+/// What happens when `+`, `-`, `*`, or negation on integers has a result
+/// outside the range of an `i64`, or an integer literal is outside it:
+/// `9223372036854775807 + 1`, or `9223372036854775808`. The most negative
+/// integer is written `-9223372036854775808`, the minus belonging to the
+/// literal.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IntOverflow {
+    // wrap around, as two's complement arithmetic does
+    Wrap,
+    // stop at the largest or smallest integer
+    Saturate,
+    // fail with `integer-overflow` (E0520), or for a literal, with
+    // `invalid-number` (E0105) before the program runs
+    Error,
+    // give the result as a fractional number instead, like the one the
+    // same arithmetic on `9223372036854775807.0` gives
+    #[default]
+    Promote,
+}
+
+impl IntOverflow {
+    /// What an overflowed result becomes, given it wrapped around and
+    /// worked out as a fractional number. `None` for `Error`.
+    pub(crate) fn overflowed(self, wrapped: i64, promoted: f64) -> Option<Value> {
+        match self {
+            IntOverflow::Wrap => Some(Value::Int(wrapped)),
+            IntOverflow::Saturate => Some(Value::Int(if promoted < 0.0 { i64::MIN } else { i64::MAX })),
+            IntOverflow::Error => None,
+            IntOverflow::Promote => Some(Value::Number(promoted)),
+        }
+    }
+}
+
+/// How many arguments a function accepts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arity {
+    Exact(usize),
+    Range(usize, usize),
+    AtLeast(usize),
+}
+
+impl Arity {
+    pub fn accepts(&self, count: usize) -> bool {
+        match *self {
+            Arity::Exact(n) => count == n,
+            Arity::Range(min, max) => (min..=max).contains(&count),
+            Arity::AtLeast(min) => count >= min,
+        }
+    }
+
+    /// Describes the accepted counts for error messages, e.g. "1 argument",
+    /// "at least 2 arguments", or "any number of arguments".
+    pub fn describe(&self) -> String {
+        let (text, last) = match *self {
+            Arity::AtLeast(0) => ("any number of".to_string(), 0),
+            Arity::Exact(n) => (n.to_string(), n),
+            Arity::Range(min, max) => (format!("{} to {}", min, max), max),
+            Arity::AtLeast(min) => (format!("at least {}", min), min),
+        };
+        if last == 1 {
+            format!("{} argument", text)
+        } else {
+            format!("{} arguments", text)
+        }
+    }
+}
+
+/// A script-defined function together with the environment it was declared
+/// in, so it can see the variables around it when called later.
+pub struct Function {
+    pub declaration: Rc<FunctionDecl>,
+    pub closure: Rc<RefCell<Environment>>,
+}
+
+impl fmt::Debug for Function {
+    // the closure can (indirectly) contain this function, so don't print it
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<function {}>", self.declaration.name)
+    }
+}
+
+/// A file imported with `import "path" as alias;`: the scope its top-level
+/// statements ran in, and the names in it that the file exports.
+pub struct Module {
+    pub name: String,
+    pub environment: Rc<RefCell<Environment>>,
+    pub exports: Vec<String>,
+}
+
+impl fmt::Debug for Module {
+    // the module's functions refer back to its environment
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<module {}>", self.name)
+    }
+}
+
+/// A class declaration together with the environment it was declared in,
+/// which its methods close over.
+pub struct Class {
+    pub declaration: Rc<ClassDecl>,
+    pub closure: Rc<RefCell<Environment>>,
+}
+
+impl Class {
+    /// The method `name` as a function whose `this` is `instance`.
+    pub fn bind(&self, name: &str, instance: &Rc<Instance>) -> Option<Rc<Function>> {
+        let method = self.declaration.method(name)?;
+        let mut scope = Environment::with_enclosing(Rc::clone(&self.closure));
+        scope.define("this", Value::Instance(Rc::clone(instance)));
+        Some(Rc::new(Function {
+            declaration: Rc::clone(method),
+            closure: Rc::new(RefCell::new(scope)),
+        }))
+    }
+}
+
+impl fmt::Debug for Class {
+    // the methods' closure can contain the class itself
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<class {}>", self.declaration.name)
+    }
+}
+
+/// An object made by calling a class. Its fields are shared like a map's
+/// entries, and looked up before its class's methods.
+pub struct Instance {
+    pub class: Rc<Class>,
+    pub fields: RefCell<IndexMap<String, Value>>,
+}
+
+impl fmt::Debug for Instance {
+    // fields can refer back to the instance
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<{} instance>", self.class.declaration.name)
+    }
+}
+
+/// The Rust closure behind a `NativeFunction`.
+pub type NativeFn = dyn Fn(&[Value]) -> Result<Value, RuntimeError>;
+
+/// A host-provided Rust closure, registered with
+/// `Interpreter::register_fn`.
+pub struct NativeFunction {
+    pub name: String,
+    pub arity: Arity,
+    pub function: Box<NativeFn>,
+}
+
+impl fmt::Debug for NativeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<native function {}>", self.name)
+    }
+}
+
+impl Value {
+    pub fn array(elements: Vec<Value>) -> Value {
+        Value::Array(Rc::new(RefCell::new(elements)))
+    }
+
+    pub fn map(entries: IndexMap<String, Value>) -> Value {
+        Value::Map(Rc::new(RefCell::new(entries)))
+    }
+
+    /// A copy in which every array and map, however deeply nested, is new,
+    /// so changing the copy never changes the original. Everything else is
+    /// kept as it is: values that can't be changed, and functions, classes,
+    /// modules, and instances, which are shared. An array or map found more
+    /// than once, even inside itself, is copied once, so the copy shares
+    /// and cycles back in the same places.
+    pub fn deep_copy(&self) -> Value {
+        // the copy of each array and map met so far, by the address of the
+        // original, and the ones whose contents are still to be copied;
+        // working from a list rather than recursing keeps copying a deeply
+        // nested value off the stack
+        let mut copies = HashMap::new();
+        let mut pending = Vec::new();
+        let copy = self.copy_of(&mut copies, &mut pending);
+        while let Some((original, copy)) = pending.pop() {
+            match (original, copy) {
+                (Value::Array(original), Value::Array(copy)) => {
+                    let copied = original
+                        .borrow()
+                        .iter()
+                        .map(|element| element.copy_of(&mut copies, &mut pending))
+                        .collect();
+                    *copy.borrow_mut() = copied;
+                }
+                (Value::Map(original), Value::Map(copy)) => {
+                    let copied = original
+                        .borrow()
+                        .iter()
+                        .map(|(key, value)| (key.clone(), value.copy_of(&mut copies, &mut pending)))
+                        .collect();
+                    *copy.borrow_mut() = copied;
+                }
+                _ => unreachable!("only arrays and maps are pending"),
+            }
+        }
+        copy
+    }
+
+    // the copy of this value, which for an array or map not met before is
+    // a new empty one, recorded in `copies` and left in `pending` to be
+    // filled in, so one that refers back to itself finds its copy
+    fn copy_of(&self, copies: &mut HashMap<*const (), Value>, pending: &mut Vec<(Value, Value)>) -> Value {
+        let (original, copy) = match self {
+            Value::Array(elements) => (Rc::as_ptr(elements) as *const (), Value::array(Vec::new())),
+            Value::Map(entries) => (Rc::as_ptr(entries) as *const (), Value::map(IndexMap::new())),
+            other => return other.clone(),
+        };
+        if let Some(copy) = copies.get(&original) {
+            return copy.clone();
+        }
+        copies.insert(original, copy.clone());
+        pending.push((self.clone(), copy.clone()));
+        copy
+    }
+
+    /// Whether the two are one and the same value, not just equal: the
+    /// same array or map, rather than two with equal contents. For every
+    /// other type this is `==`, which already compares functions, classes,
+    /// modules, and instances by identity.
+    pub fn is_same(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Array(a), Value::Array(b)) => Rc::ptr_eq(a, b),
+            (Value::Map(a), Value::Map(b)) => Rc::ptr_eq(a, b),
+            _ => self == other,
+        }
+    }
+
+    /// Only `false` and `null` are falsey; every other value, including `0`
+    /// and `""`, is truthy.
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, Value::Null | Value::Bool(false))
+    }
+
+    /// The type name used in error messages, and given by `typeof` and
+    /// `type()`, so the two always agree.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::Bool(_) => "bool",
+            Value::Number(_) | Value::Int(_) => "number",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Map(_) => "map",
+            Value::Function(_) | Value::Builtin(_) | Value::NativeFunction(_) => "function",
+            Value::Module(_) => "module",
+            Value::Class(_) => "class",
+            Value::Instance(_) => "instance",
+        }
+    }
+
+    /// The whole number `n`, as an integer if it is in range, like a
+    /// counter a range loop gives.
+    pub(crate) fn whole(n: f64) -> Value {
+        if n >= i64::MIN as f64 && n < i64::MAX as f64 { Value::Int(n as i64) } else { Value::Number(n) }
+    }
+
+    /// The number `self` is, an integer or not, as an `f64`.
+    pub fn as_number(&self) -> Option<f64> {
+        match *self {
+            Value::Number(n) => Some(n),
+            Value::Int(n) => Some(n as f64),
+            _ => None,
+        }
+    }
+}
+
+impl PartialEq for Value {
+    /// Values of different types are never equal; arrays compare element by
+    /// element, maps by their entries regardless of order, and functions by
+    /// identity, as are modules, classes, and instances. Nested arrays and
+    /// maps are compared from a list of pairs still to compare rather than
+    /// by recursing, so neither deep nesting nor a cycle can overflow the
+    /// stack. A pair of containers met again while comparing them, as in
+    /// two arrays that each hold themselves, counts as equal: nothing
+    /// along the way told them apart.
+    fn eq(&self, other: &Value) -> bool {
+        let mut compared = HashSet::new();
+        let mut pending = vec![(self.clone(), other.clone())];
+        while let Some((a, b)) = pending.pop() {
+            match (&a, &b) {
+                (Value::Array(a), Value::Array(b)) => {
+                    if Rc::ptr_eq(a, b) || !compared.insert((Rc::as_ptr(a) as *const (), Rc::as_ptr(b) as *const ())) {
+                        continue;
+                    }
+                    let (a, b) = (a.borrow(), b.borrow());
+                    if a.len() != b.len() {
+                        return false;
+                    }
+                    pending.extend(a.iter().cloned().zip(b.iter().cloned()));
+                }
+                (Value::Map(a), Value::Map(b)) => {
+                    if Rc::ptr_eq(a, b) || !compared.insert((Rc::as_ptr(a) as *const (), Rc::as_ptr(b) as *const ())) {
+                        continue;
+                    }
+                    let (a, b) = (a.borrow(), b.borrow());
+                    if a.len() != b.len() {
+                        return false;
+                    }
+                    for (key, value) in a.iter() {
+                        match b.get(key) {
+                            Some(other) => pending.push((value.clone(), other.clone())),
+                            None => return false,
+                        }
+                    }
+                }
+                _ if !a.shallow_eq(&b) => return false,
+                _ => {}
+            }
+        }
+        true
+    }
+}
+
+impl Value {
+    // `==` for everything but two arrays or two maps, which `eq` compares
+    // itself
+    fn shallow_eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Null, Value::Null) => true,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Number(a), Value::Number(b)) => a == b,
+            (Value::Int(a), Value::Int(b)) => a == b,
+            (Value::Int(a), Value::Number(b)) | (Value::Number(b), Value::Int(a)) => int_equals(*a, *b),
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Function(a), Value::Function(b)) => Rc::ptr_eq(a, b),
+            (Value::Builtin(a), Value::Builtin(b)) => std::ptr::eq(*a, *b),
+            (Value::NativeFunction(a), Value::NativeFunction(b)) => Rc::ptr_eq(a, b),
+            (Value::Module(a), Value::Module(b)) => Rc::ptr_eq(a, b),
+            (Value::Class(a), Value::Class(b)) => Rc::ptr_eq(a, b),
+            (Value::Instance(a), Value::Instance(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+// whether the integer `a` is the number `b`, which comparing them as
+// floats could get wrong, rounding `a` to a neighbour of `b`
+fn int_equals(a: i64, b: f64) -> bool {
+    compare_int(a, b) == Some(Ordering::Equal)
+}
+
+/// How the integer `a` orders against the number `b`, exactly, where
+/// converting `a` to a float could round it onto `b`. `None` if `b` is NaN.
+pub(crate) fn compare_int(a: i64, b: f64) -> Option<Ordering> {
+    if b.is_nan() {
+        None
+    } else if b >= i64::MAX as f64 {
+        // `i64::MAX as f64` is 2^63, one past the largest integer
+        Some(Ordering::Less)
+    } else if b < i64::MIN as f64 {
+        Some(Ordering::Greater)
+    } else {
+        let whole = b.trunc();
+        Some(a.cmp(&(whole as i64)).then(0.0_f64.total_cmp(&(b - whole))))
+    }
+}
+
+/// How `print` and `str()` show a value, and so the REPL and error
+/// messages too. Strings are shown as they are, but quoted inside an array,
+/// map, or instance, where `[1, "2"]` and `[1, 2]` should look different.
+/// Map keys are quoted only if they aren't names. An array, map, or
+/// instance inside itself is shown as `[...]` or `{...}`.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Renderer { pretty: false, depth: 0, open: Vec::new() }.value(f, self, false)
+    }
+}
+
+impl Value {
+    /// Like `to_string`, but with each element of a non-empty array, entry
+    /// of a map, and field of an instance on its own line, indented two
+    /// spaces per level.
+    pub fn pretty(&self) -> String {
+        let mut out = String::new();
+        let _ = Renderer { pretty: true, depth: 0, open: Vec::new() }.value(&mut out, self, false);
+        out
+    }
+}
+
+/// How every number is written, whether printed, converted with `str()`,
+/// or written as JSON: whole numbers without a decimal point, and others
+/// in the fewest digits that read back as the same number. Scientific
+/// notation is kept for magnitudes from 1e21 up and below 1e-7, where
+/// writing out the zeros would take more room than the digits. Negative
+/// zero is written `0`, and the numbers arithmetic can make but literals
+/// can't write are `nan`, `inf`, and `-inf`. `num()` reads every number's
+/// text back as the same number, and a finite one's is also a literal.
+pub fn format_number(n: f64) -> String {
+    if n.is_nan() {
+        "nan".to_string()
+    } else if n.is_infinite() {
+        if n > 0.0 { "inf" } else { "-inf" }.to_string()
+    } else if n == 0.0 {
+        "0".to_string()
+    } else if n.abs() >= 1e21 || n.abs() < 1e-7 {
+        format!("{:e}", n)
+    } else {
+        n.to_string()
+    }
+}
+
+// writes values for `Display` and `Value::pretty`
+struct Renderer {
+    pretty: bool,
+    // how many containers deep the value being written is
+    depth: usize,
+    // the arrays, maps, and instances being written, outermost first
+    open: Vec<*const ()>,
+}
+
+impl Renderer {
+    fn value(&mut self, f: &mut dyn fmt::Write, value: &Value, nested: bool) -> fmt::Result {
+        let address = match value {
+            Value::Null => return write!(f, "null"),
+            Value::Bool(b) => return write!(f, "{}", b),
+            Value::Number(n) => return write!(f, "{}", format_number(*n)),
+            Value::Int(n) => return write!(f, "{}", n),
+            Value::String(s) if nested => return write!(f, "{}", json_string(s)),
+            Value::String(s) => return write!(f, "{}", s),
+            Value::Function(function) => return write!(f, "<function {}>", function.declaration.name),
+            Value::Builtin(builtin) => return write!(f, "<builtin {}>", builtin.name),
+            Value::NativeFunction(function) => return write!(f, "<native function {}>", function.name),
+            Value::Module(module) => return write!(f, "<module {}>", module.name),
+            Value::Class(class) => return write!(f, "<class {}>", class.declaration.name),
+            Value::Array(elements) => Rc::as_ptr(elements) as *const (),
+            Value::Map(entries) => Rc::as_ptr(entries) as *const (),
+            Value::Instance(instance) => Rc::as_ptr(instance) as *const (),
+        };
+        if let Value::Instance(instance) = value {
+            write!(f, "{} ", instance.class.declaration.name)?;
+        }
+        let (open, close) = match value {
+            Value::Array(_) => ('[', ']'),
+            _ => ('{', '}'),
+        };
+        if self.open.contains(&address) {
+            return write!(f, "{}...{}", open, close);
+        }
+        self.open.push(address);
+        match value {
+            Value::Array(elements) => {
+                self.items(f, open, close, elements.borrow().iter().map(|element| (None, element)))?
+            }
+            Value::Map(entries) => self.items(f, open, close, keyed(&entries.borrow()))?,
+            Value::Instance(instance) => self.items(f, open, close, keyed(&instance.fields.borrow()))?,
+            _ => unreachable!("only arrays, maps, and instances are opened"),
+        }
+        self.open.pop();
+        Ok(())
+    }
+
+    fn items<'a>(
+        &mut self,
+        f: &mut dyn fmt::Write,
+        open: char,
+        close: char,
+        items: impl ExactSizeIterator<Item = (Option<&'a str>, &'a Value)>,
+    ) -> fmt::Result {
+        let empty = items.len() == 0;
+        f.write_char(open)?;
+        self.depth += 1;
+        for (i, (key, value)) in items.enumerate() {
+            if i > 0 {
+                f.write_char(',')?;
+            }
+            if self.pretty {
+                write!(f, "\n{}", "  ".repeat(self.depth))?;
+            } else if i > 0 {
+                f.write_char(' ')?;
+            }
+            match key {
+                Some(key) if is_name(key) => write!(f, "{}: ", key)?,
+                Some(key) => write!(f, "{}: ", json_string(key))?,
+                None => {}
+            }
+            self.value(f, value, true)?;
+        }
+        self.depth -= 1;
+        if self.pretty && !empty {
+            write!(f, "\n{}", "  ".repeat(self.depth))?;
+        }
+        f.write_char(close)
+    }
+}
+
+fn keyed(entries: &IndexMap<String, Value>) -> impl ExactSizeIterator<Item = (Option<&str>, &Value)> {
+    entries.iter().map(|(key, value)| (Some(key.as_str()), value))
+}
+
+// whether a map key can be shown without quotes
+fn is_name(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars.next().is_some_and(|first| first.is_alphabetic() || first == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Self {
+        Value::Number(n)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(n: i32) -> Self {
+        Value::Int(n.into())
+    }
+}
+
+impl From<i64> for Value {
+    fn from(n: i64) -> Self {
+        Value::Int(n)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.into())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::String(s.into())
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(elements: Vec<Value>) -> Self {
+        Value::array(elements)
+    }
+}
+
+/// The error for a failed conversion out of a `Value`. Its span is empty;
+/// when returned from a native function, the interpreter fills in the
+/// position of the call.
+fn conversion_error(expected: &str, found: &Value) -> RuntimeError {
+    RuntimeError::with_code(
+        &codes::WRONG_TYPE,
+        format!("Expected {}, got {}", expected, found.type_name()),
+        Span::default(),
+    )
+}
+
+impl TryFrom<Value> for f64 {
+    type Error = RuntimeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Number(n) => Ok(n),
+            Value::Int(n) => Ok(n as f64),
+            other => Err(conversion_error("number", &other)),
+        }
+    }
+}
+
+/// Accepts only whole numbers that fit in an `i64`.
+impl TryFrom<Value> for i64 {
+    type Error = RuntimeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Int(n) => Ok(n),
+            Value::Number(n) if n.fract() == 0.0 && n >= i64::MIN as f64 && n < i64::MAX as f64 => Ok(n as i64),
+            Value::Number(n) => {
+                let message = format!("Expected an integer, got {}", n);
+                Err(RuntimeError::with_code(&codes::INVALID_ARGUMENT, message, Span::default()))
+            }
+            other => Err(conversion_error("integer", &other)),
+        }
+    }
+}
+
+impl TryFrom<Value> for bool {
+    type Error = RuntimeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Bool(b) => Ok(b),
+            other => Err(conversion_error("bool", &other)),
+        }
+    }
+}
+
+impl TryFrom<Value> for String {
+    type Error = RuntimeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::String(s) => Ok(s.to_string()),
+            other => Err(conversion_error("string", &other)),
+        }
+    }
+}
+
+/// Converts `null` to `None` and anything else with `T`'s conversion.
+impl<T: TryFrom<Value, Error = RuntimeError>> TryFrom<Value> for Option<T> {
+    type Error = RuntimeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Null => Ok(None),
+            other => T::try_from(other).map(Some),
+        }
+    }
+}
+
+/// Copies the map's entries, keeping their order.
+impl TryFrom<Value> for IndexMap<String, Value> {
+    type Error = RuntimeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Map(entries) => Ok(entries.borrow().clone()),
+            other => Err(conversion_error("map", &other)),
+        }
+    }
+}
+
+/// Copies the array's elements; the copy doesn't share later changes.
+impl TryFrom<Value> for Vec<Value> {
+    type Error = RuntimeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Array(elements) => Ok(elements.borrow().clone()),
+            other => Err(conversion_error("array", &other)),
+        }
+    }
+}
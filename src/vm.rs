@@ -0,0 +1,343 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use indexmap::IndexMap;
+
+use crate::ast::FunctionDecl;
+use crate::compiler::{self, Chunk, Op};
+use crate::environment::Environment;
+use crate::interpreter::{
+    Flow, Interpreter, RuntimeError, TailCall, adds_entry, binary_operation, compound_operation, get_index, get_member,
+    get_slice, iteration_items, range_bound, set_index, set_member, unary_operation,
+};
+use crate::value::Value;
+
+/// Compiled function bodies, each compiled the first time the function is
+/// called. Entries hold on to their declaration so its address, the key,
+/// can't be reused by a different one.
+#[derive(Debug, Default)]
+pub(crate) struct ChunkCache {
+    chunks: HashMap<*const FunctionDecl, (Rc<FunctionDecl>, Rc<Chunk>)>,
+}
+
+impl ChunkCache {
+    pub(crate) fn get(&mut self, declaration: &Rc<FunctionDecl>) -> Rc<Chunk> {
+        let (_, chunk) = self
+            .chunks
+            .entry(Rc::as_ptr(declaration))
+            .or_insert_with(|| (Rc::clone(declaration), Rc::new(compiler::compile_function(declaration))));
+        Rc::clone(chunk)
+    }
+}
+
+/// A for-in loop in progress.
+enum Iteration {
+    Items(std::vec::IntoIter<Value>),
+    Range { next: f64, end: f64 },
+}
+
+impl Iteration {
+    fn next(&mut self) -> Option<Value> {
+        match self {
+            Iteration::Items(items) => items.next(),
+            Iteration::Range { next, end } => {
+                if *next < *end {
+                    let item = Value::whole(*next);
+                    *next += 1.0;
+                    Some(item)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// A `try` block being run: where its catch block starts, and how much of
+/// the state outside it to keep when an error unwinds to it.
+struct Handler {
+    target: usize,
+    stack: usize,
+    scopes: usize,
+    iterations: usize,
+}
+
+/// Where a chunk's execution is, kept apart from the loop running it so
+/// the loop can be resumed at a catch block.
+struct State {
+    stack: Vec<Value>,
+    // the scopes enclosing the current one, innermost last
+    scopes: Vec<Rc<RefCell<Environment>>>,
+    iterations: Vec<Iteration>,
+    // the `try` blocks being run, innermost last
+    handlers: Vec<Handler>,
+    // the program's result so far
+    result: Value,
+    ip: usize,
+}
+
+fn pop(stack: &mut Vec<Value>) -> Value {
+    stack.pop().expect("the compiler keeps the stack balanced")
+}
+
+fn pop_number(stack: &mut Vec<Value>) -> f64 {
+    match pop(stack) {
+        Value::Number(n) => n,
+        other => unreachable!("range bounds are checked before iterating, got {}", other),
+    }
+}
+
+impl Interpreter {
+    /// Runs `chunk` with `environment` as the current scope, restoring the
+    /// previous scope afterwards even if it fails. Returns the function's
+    /// return value or the tail call it ends with, or the program's result.
+    pub(crate) fn run_chunk(
+        &mut self,
+        chunk: &Chunk,
+        environment: Rc<RefCell<Environment>>,
+    ) -> Result<Flow, RuntimeError> {
+        let previous = std::mem::replace(&mut self.environment, environment);
+        let result = self.execute_chunk(chunk);
+        self.environment = previous;
+        result
+    }
+
+    fn execute_chunk(&mut self, chunk: &Chunk) -> Result<Flow, RuntimeError> {
+        let mut state = State {
+            stack: Vec::new(),
+            scopes: Vec::new(),
+            iterations: Vec::new(),
+            handlers: Vec::new(),
+            result: Value::Null,
+            ip: 0,
+        };
+        loop {
+            match self.run_until_error(chunk, &mut state) {
+                Err(error) if error.catchable() && !state.handlers.is_empty() => {
+                    let handler = state.handlers.pop().expect("checked above");
+                    if state.scopes.len() > handler.scopes {
+                        self.environment = Rc::clone(&state.scopes[handler.scopes]);
+                        state.scopes.truncate(handler.scopes);
+                    }
+                    state.stack.truncate(handler.stack);
+                    state.iterations.truncate(handler.iterations);
+                    state.stack.push(error.caught_value());
+                    state.ip = handler.target;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Runs the chunk from `state.ip` until it returns, halts, or fails.
+    fn run_until_error(&mut self, chunk: &Chunk, state: &mut State) -> Result<Flow, RuntimeError> {
+        let State { stack, scopes, iterations, handlers, result, ip } = state;
+
+        loop {
+            let op = chunk.code[*ip];
+            let span = chunk.spans[*ip];
+            *ip += 1;
+
+            match op {
+                Op::Constant(index) => stack.push(chunk.constants[index].clone()),
+                Op::Null => stack.push(Value::Null),
+                Op::Pop => {
+                    pop(stack);
+                }
+                Op::Duplicate => {
+                    let value = stack.last().expect("the compiler keeps the stack balanced").clone();
+                    stack.push(value);
+                }
+
+                Op::DuplicateTwo => {
+                    let top = stack.len() - 2;
+                    stack.extend_from_within(top..);
+                }
+
+                Op::GetVariable(name) => stack.push(self.get_variable(&chunk.names[name], span)?),
+                Op::SetVariable(name) => {
+                    let value = stack.last().expect("the compiler keeps the stack balanced").clone();
+                    self.assign(&chunk.names[name], value, span)?;
+                }
+                Op::Destructure(pattern) | Op::DestructureConst(pattern) => {
+                    let value = pop(stack);
+                    let constant = matches!(op, Op::DestructureConst(_));
+                    self.destructure(&chunk.patterns[pattern], value, constant, span)?;
+                }
+                Op::Declare(name) => {
+                    let value = pop(stack);
+                    self.declare(&chunk.names[name], value, false, span)?;
+                }
+                Op::DeclareConst(name) => {
+                    let value = pop(stack);
+                    self.declare(&chunk.names[name], value, true, span)?;
+                }
+                Op::Define(name) => {
+                    let value = pop(stack);
+                    self.environment.borrow_mut().define(&chunk.names[name], value);
+                }
+                Op::Closure(function) => stack.push(self.closure(&chunk.functions[function])),
+                Op::Class(class) => stack.push(self.class(&chunk.classes[class])),
+
+                Op::Unary(operator) => {
+                    let operand = pop(stack);
+                    stack.push(unary_operation(operator, operand, self.config.int_overflow, span)?);
+                }
+                Op::Binary(operator) => {
+                    let right = pop(stack);
+                    let left = pop(stack);
+                    let result = binary_operation(operator, left, right, self.config.int_overflow, span)?;
+                    self.allocated(&result, span)?;
+                    stack.push(result);
+                }
+                Op::Compound(operator) => {
+                    let value = pop(stack);
+                    let current = pop(stack);
+                    let result = compound_operation(operator, current, value, self.config.int_overflow, span)?;
+                    self.allocated(&result, span)?;
+                    stack.push(result);
+                }
+
+                Op::Jump(target) => *ip = target,
+                Op::JumpIfBound(name, target) => {
+                    if self.environment.borrow().binds(&chunk.names[name]) {
+                        *ip = target;
+                    }
+                }
+                Op::JumpIfFalse(target) => {
+                    if !pop(stack).is_truthy() {
+                        *ip = target;
+                    }
+                }
+                Op::JumpIfFalseOrPop(target) => {
+                    if stack.last().is_some_and(|value| !value.is_truthy()) {
+                        *ip = target;
+                    } else {
+                        pop(stack);
+                    }
+                }
+                Op::JumpIfTrueOrPop(target) => {
+                    if stack.last().is_some_and(Value::is_truthy) {
+                        *ip = target;
+                    } else {
+                        pop(stack);
+                    }
+                }
+
+                Op::Call(count) => {
+                    let arguments = stack.split_off(stack.len() - count);
+                    let callee = pop(stack);
+                    stack.push(self.call(callee, arguments, span)?);
+                }
+                Op::TailCall(count) => {
+                    let arguments = stack.split_off(stack.len() - count);
+                    return match pop(stack) {
+                        Value::Function(function) => Ok(Flow::TailCall(TailCall { function, arguments, span })),
+                        callee => Ok(Flow::Return(self.call(callee, arguments, span)?)),
+                    };
+                }
+                Op::Array(count) => {
+                    let elements = stack.split_off(stack.len() - count);
+                    let array = Value::array(elements);
+                    self.allocated(&array, span)?;
+                    stack.push(array);
+                }
+                Op::Map(keys) => {
+                    let keys = &chunk.map_keys[keys];
+                    let values = stack.split_off(stack.len() - keys.len());
+                    let entries: IndexMap<String, Value> = keys.iter().cloned().zip(values).collect();
+                    let map = Value::map(entries);
+                    self.allocated(&map, span)?;
+                    stack.push(map);
+                }
+                Op::GetIndex => {
+                    let index = pop(stack);
+                    let object = pop(stack);
+                    stack.push(get_index(&object, &index, span)?);
+                }
+                Op::Slice(inclusive) => {
+                    let end = pop(stack);
+                    let start = pop(stack);
+                    let object = pop(stack);
+                    stack.push(get_slice(&object, &start, &end, inclusive, span)?);
+                }
+                Op::SetIndex => {
+                    let value = pop(stack);
+                    let index = pop(stack);
+                    let object = pop(stack);
+                    self.check_growth(&object, adds_entry(&object, &index) as usize, span)?;
+                    set_index(&object, &index, value.clone(), span)?;
+                    stack.push(value);
+                }
+                Op::GetMember(name) => {
+                    let object = pop(stack);
+                    stack.push(get_member(&object, &chunk.names[name], span)?);
+                }
+                Op::SetMember(name) => {
+                    let value = pop(stack);
+                    let object = pop(stack);
+                    let key = Value::String(chunk.names[name].as_str().into());
+                    self.check_growth(&object, adds_entry(&object, &key) as usize, span)?;
+                    set_member(&object, &chunk.names[name], value.clone(), span)?;
+                    stack.push(value);
+                }
+
+                Op::PushScope => {
+                    let scope = Environment::with_enclosing(Rc::clone(&self.environment));
+                    scopes.push(std::mem::replace(&mut self.environment, Rc::new(RefCell::new(scope))));
+                }
+                Op::PopScope => {
+                    self.environment = scopes.pop().expect("the compiler pairs scope pushes and pops");
+                }
+
+                Op::Iterate => {
+                    let items = iteration_items(pop(stack), span)?;
+                    iterations.push(Iteration::Items(items.into_iter()));
+                }
+                Op::CheckRangeBound => {
+                    let bound = range_bound(pop(stack), span)?;
+                    stack.push(Value::Number(bound));
+                }
+                Op::IterateRange => {
+                    let end = pop_number(stack);
+                    let start = pop_number(stack);
+                    iterations.push(Iteration::Range { next: start, end });
+                }
+                Op::Next(target) => {
+                    let iteration = iterations.last_mut().expect("the compiler starts iterations before Next");
+                    match iteration.next() {
+                        Some(item) => stack.push(item),
+                        None => {
+                            iterations.pop();
+                            *ip = target;
+                        }
+                    }
+                }
+                Op::EndIteration => {
+                    iterations.pop();
+                }
+
+                Op::Import(path) => self.import(&chunk.names[path], span)?,
+                Op::ImportModule(path) => stack.push(self.import_module(&chunk.names[path], span)?),
+                Op::Tick => self.tick(span)?,
+                Op::SetResult => *result = pop(stack),
+                Op::Return => return Ok(Flow::Return(pop(stack))),
+                Op::Halt => return Ok(Flow::Return(result.clone())),
+                Op::Error(code, message) => {
+                    return Err(RuntimeError::with_code(code, chunk.constants[message].to_string(), span));
+                }
+                Op::Throw => return Err(RuntimeError::thrown(pop(stack), span)),
+                Op::Try(target) => handlers.push(Handler {
+                    target,
+                    stack: stack.len(),
+                    scopes: scopes.len(),
+                    iterations: iterations.len(),
+                }),
+                Op::EndTry => {
+                    handlers.pop();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Says when the files a watched program uses change, for `watch`.
+/// `Poller` watches the real files; a test can give `watch` a source of
+/// made-up changes instead.
+pub trait ChangeSource {
+    /// Blocks until at least one of `files` changes, returning the ones
+    /// that did, or `None` once watching should stop.
+    fn wait(&mut self, files: &[PathBuf]) -> Option<Vec<PathBuf>>;
+}
+
+/// Calls `run`, then calls it again each time `changes` reports a change
+/// to any of the files the last call returned, until `changes` says to
+/// stop. `run` is given the files that changed, which is empty the first
+/// time, and returns the files to watch next, so a script that starts or
+/// stops importing a file is watched accordingly.
+pub fn watch(changes: &mut impl ChangeSource, mut run: impl FnMut(&[PathBuf]) -> Vec<PathBuf>) {
+    let mut files = run(&[]);
+    while let Some(changed) = changes.wait(&files) {
+        files = run(&changed);
+    }
+}
+
+// a file's modification time and length, or `None` if it can't be read,
+// such as while an editor is replacing it
+type Stamp = Option<(SystemTime, u64)>;
+
+fn stamp(path: &Path) -> Stamp {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Finds changes by checking the files' modification times and lengths
+/// every `interval`, which works on every platform. A change is only
+/// reported once the files have stayed the same for `settle`, so an editor
+/// saving several times in a row causes one rerun rather than several.
+/// It never stops watching by itself.
+#[derive(Debug)]
+pub struct Poller {
+    interval: Duration,
+    settle: Duration,
+    // how each file was when it was last reported, or when it was first
+    // watched
+    seen: HashMap<PathBuf, Stamp>,
+}
+
+impl Poller {
+    pub fn new(interval: Duration, settle: Duration) -> Self {
+        Poller {
+            interval,
+            settle,
+            seen: HashMap::new(),
+        }
+    }
+}
+
+impl Default for Poller {
+    fn default() -> Self {
+        Poller::new(Duration::from_millis(100), Duration::from_millis(200))
+    }
+}
+
+impl ChangeSource for Poller {
+    fn wait(&mut self, files: &[PathBuf]) -> Option<Vec<PathBuf>> {
+        // files no longer used are forgotten, and new ones are compared
+        // with how they are now
+        self.seen.retain(|path, _| files.contains(path));
+        for file in files {
+            self.seen.entry(file.clone()).or_insert_with(|| stamp(file));
+        }
+        let mut previous: Vec<Stamp> = files.iter().map(|file| self.seen[file]).collect();
+        let mut changed_at: Option<Instant> = None;
+        loop {
+            thread::sleep(self.interval);
+            let current: Vec<Stamp> = files.iter().map(|file| stamp(file)).collect();
+            if current != previous {
+                previous = current;
+                changed_at = Some(Instant::now());
+                continue;
+            }
+            if changed_at.is_none_or(|at| at.elapsed() < self.settle) {
+                continue;
+            }
+            changed_at = None;
+            let changed: Vec<PathBuf> = files
+                .iter()
+                .zip(&current)
+                .filter(|(file, stamp)| self.seen[*file] != **stamp)
+                .map(|(file, _)| file.clone())
+                .collect();
+            for (file, stamp) in files.iter().zip(current) {
+                self.seen.insert(file.clone(), stamp);
+            }
+            // a file saved and then put back as it was hasn't changed
+            if !changed.is_empty() {
+                return Some(changed);
+            }
+        }
+    }
+}
@@ -0,0 +1,161 @@
+mod common;
+
+use common::{error, run};
+
+#[test]
+fn arrays_are_indexed_assigned_and_measured() {
+    assert_eq!(run("let xs = [1, 2, 3]; xs[0] = 10; print(xs[0] + len(xs));"), "13\n");
+    assert_eq!(run("print(len(\"héllo\")); print(len([])); print(len([[1, 2]]));"), "5\n0\n1\n");
+}
+
+#[test]
+fn arrays_are_shared_not_copied() {
+    let source = r#"
+        function set_first(array) { array[0] = "changed"; }
+        let xs = [1, 2];
+        let alias = xs;
+        set_first(xs);
+        print(alias);
+    "#;
+    assert_eq!(run(source), "[\"changed\", 2]\n");
+}
+
+#[test]
+fn negative_indexes_are_errors() {
+    // they don't count from the end
+    let report = error("let xs = [1, 2, 3]; print(xs[-1]);");
+    assert_eq!(report.code.id, "E0503");
+    assert_eq!(report.message, "Index -1 out of bounds for array of length 3");
+    assert_eq!(error("let xs = [1, 2, 3]; xs[-1] = 0;").code.id, "E0503");
+}
+
+#[test]
+fn out_of_bounds_errors_give_the_index_and_length() {
+    let report = error("let xs = [1, 2]; print(xs[5]);");
+    assert_eq!(report.code.id, "E0503");
+    assert_eq!(report.message, "Index 5 out of bounds for array of length 2");
+}
+
+#[test]
+fn assigning_past_the_end_doesnt_grow_the_array() {
+    let report = error("let xs = [1]; xs[1] = 2;");
+    assert_eq!(report.message, "Index 1 out of bounds for array of length 1");
+    let source = r#"
+        let xs = [1];
+        try { xs[3] = 2; } catch (e) { print("refused"); }
+        print(len(xs));
+    "#;
+    assert_eq!(run(source), "refused\n1\n");
+}
+
+#[test]
+fn push_and_pop_change_the_array_everywhere_its_shared() {
+    let source = r#"
+        let xs = [];
+        let alias = xs;
+        push(xs, 1); push(alias, [2]);
+        print(xs);
+        print(pop(alias), pop(xs), xs);
+    "#;
+    assert_eq!(run(source), "[1, [2]]\n[2] 1 []\n");
+    let report = error("pop([]);");
+    assert_eq!(report.code.id, "E0518");
+    assert_eq!(report.message, "pop() called on an empty array");
+}
+
+#[test]
+fn insert_and_remove_shift_the_elements_after() {
+    let source = r#"
+        let xs = [];
+        insert(xs, 0, "b"); insert(xs, 0, "a"); insert(xs, 2, "c");
+        print(xs);
+        print(remove(xs, 1), xs);
+    "#;
+    assert_eq!(run(source), "[\"a\", \"b\", \"c\"]\nb [\"a\", \"c\"]\n");
+    assert_eq!(error("insert([], 1, 0);").message, "insert() index 1 out of bounds for array of length 0");
+    assert_eq!(error("remove([], 0);").message, "remove() index 0 out of bounds for array of length 0");
+    // a negative index is out of bounds, as it is in `xs[-1]`
+    for source in ["remove([1], -1);", "insert([1], -1, 0);", "slice([1], -1, 1);", "remove([1], 1);"] {
+        assert_eq!(error(source).code.id, "E0503", "{}", source);
+    }
+    assert_eq!(error("remove([1, 2], -1);").message, "remove() index -1 out of bounds for array of length 2");
+    assert_eq!(error("insert([], -2, 0);").message, "insert() index -2 out of bounds for array of length 0");
+    let report = error("remove([1], 1.5);");
+    assert_eq!((report.code.id, report.message.as_str()), ("E0517", "remove() expects a whole number, got 1.5"));
+}
+
+#[test]
+fn slice_copies_part_of_the_array() {
+    let source = r#"
+        let xs = [1, 2, 3];
+        let part = slice(xs, 1, 3);
+        part[0] = 20;
+        print(part, xs, slice(xs, 3, 3), slice([], 0, 0));
+    "#;
+    assert_eq!(run(source), "[20, 3] [1, 2, 3] [] []\n");
+    assert_eq!(error("slice([1], 0, 2);").message, "slice() end 2 is out of range for array of length 1");
+    assert_eq!(error("slice([1], 1, 0);").message, "slice() start 1 is after end 0");
+    assert_eq!(error("slice([1], -1, 1);").message, "slice() start -1 is out of range for array of length 1");
+}
+
+#[test]
+fn sort_and_reverse_work_in_place() {
+    let source = r#"
+        let xs = [3, 1, 2];
+        sort(xs);
+        let words = ["b", "a"];
+        sort(words);
+        let empty = [];
+        sort(empty); reverse(empty);
+        reverse(xs);
+        print(xs, words, empty);
+    "#;
+    assert_eq!(run(source), "[3, 2, 1] [\"a\", \"b\"] []\n");
+    assert_eq!(error("sort([1, \"a\"]);").message, "sort() expects only numbers or only strings, got number and string");
+}
+
+#[test]
+fn slices_copy_what_their_range_covers() {
+    let source = r#"
+        let xs = [0, 1, 2, 3, 4];
+        let s = "añ😀bc";
+        print(xs[1..3], xs[..2], xs[3..], xs[..], xs[1..=3], xs[..=0]);
+        print(s[1..3], s[..2], s[3..], s[..], s[1..=2]);
+        let copy = xs[..];
+        push(copy, 5);
+        print(len(xs), len(copy));
+    "#;
+    assert_eq!(
+        run(source),
+        "[1, 2] [0, 1] [3, 4] [0, 1, 2, 3, 4] [1, 2, 3] [0]\nñ😀 añ bc añ😀bc ñ😀\n5 6\n"
+    );
+}
+
+#[test]
+fn empty_slices_are_empty() {
+    let source = r#"
+        let xs = [0, 1, 2];
+        let s = "abc";
+        print(xs[1..1], xs[3..], xs[..0], xs[2..=1]);
+        print(s[1..1] == "", s[3..] == "", len(s[..0]));
+    "#;
+    assert_eq!(run(source), "[] [] [] []\ntrue true 0\n");
+}
+
+#[test]
+fn slices_past_the_ends_or_inverted_are_errors() {
+    for (source, message) in [
+        ("[0, 1, 2][1..4];", "Slice 1..4 out of bounds for array of length 3"),
+        ("[0, 1, 2][..=3];", "Slice ..=3 out of bounds for array of length 3"),
+        ("[0, 1, 2][-1..];", "Slice -1.. out of bounds for array of length 3"),
+        ("\"a😀\"[0..3];", "Slice 0..3 out of bounds for string of length 2"),
+        ("[0, 1, 2][2..1];", "Slice 2..1 of array of length 3 starts after it ends"),
+        ("\"abc\"[3..=1];", "Slice 3..=1 of string of length 3 starts after it ends"),
+    ] {
+        let report = error(source);
+        assert_eq!(report.code.id, "E0503", "{}", source);
+        assert_eq!(report.message, message);
+    }
+    assert_eq!(error("[0][0.5..];").message, "Slice start must be a whole number, got 0.5");
+    assert_eq!(error("5[0..];").message, "Cannot slice number");
+}
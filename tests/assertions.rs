@@ -0,0 +1,41 @@
+mod common;
+
+use std::fs;
+use std::process::Command;
+
+use common::{error, run};
+
+#[test]
+fn passing_assertions_do_nothing() {
+    assert_eq!(run("assert(true); assert(1, \"truthy\"); assert([] == []); print(\"done\");"), "done\n");
+}
+
+#[test]
+fn failed_assertions_say_why_and_where() {
+    let report = error("let x = 1;\nassert(x > 1, \"x must be big\");");
+    assert_eq!(report.code.id, "E0508");
+    assert_eq!(report.message, "Assertion failed: x must be big");
+    assert_eq!((report.span.line, report.span.column), (2, 1));
+
+    // without a message, the condition is quoted
+    assert_eq!(error("let x = 1; assert( x > 1 );").message, "Assertion failed: x > 1");
+    assert_eq!(error("assert(null);").message, "Assertion failed: null");
+}
+
+#[test]
+fn panic_always_fails() {
+    let report = error("if true { panic(\"unreachable \" + str(2)); }");
+    assert_eq!(report.code.id, "E0508");
+    assert_eq!(report.message, "Panic: unreachable 2");
+}
+
+#[test]
+fn a_failed_assertion_fails_the_command_line() {
+    let path = std::env::temp_dir().join(format!("slimescript-assert-{}.slime", std::process::id()));
+    fs::write(&path, "print(\"before\");\nassert(1 == 2);\nprint(\"after\");").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_slimescript")).arg("run").arg(&path).output().unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(output.status.code(), Some(70));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "before\n");
+    assert!(String::from_utf8(output.stderr).unwrap().contains("Assertion failed: 1 == 2"));
+}
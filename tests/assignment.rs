@@ -0,0 +1,68 @@
+mod common;
+
+use common::{error, run};
+
+#[test]
+fn each_operator_updates_a_variable() {
+    let source = r#"
+        let x = 10;
+        x += 5; print(x);
+        x -= 3; print(x);
+        x *= 2; print(x);
+        x /= 8; print(x);
+        x %= 2; print(x);
+        let s = "ab";
+        s += "c";
+        print(s, x += 10, x);
+    "#;
+    assert_eq!(run(source), "15\n12\n24\n3\n1\nabc 11 11\n");
+}
+
+#[test]
+fn elements_members_and_keys_are_updated_in_place() {
+    let source = r#"
+        let arr = [1, 2, 3];
+        let i = 1;
+        arr[i] *= 10;
+        arr[2] -= 1;
+        print(arr);
+        let obj = {count: 3, names: {}};
+        obj.count -= 1;
+        obj.names["ann"] = 1;
+        obj.names["ann"] += 1;
+        print(obj.count, obj.names);
+        let shared = arr;
+        shared[0] += 100;
+        print(arr[0]);
+    "#;
+    assert_eq!(run(source), "[1, 20, 2]\n2 {ann: 2}\n101\n");
+}
+
+#[test]
+fn the_target_is_evaluated_once() {
+    let source = r#"
+        let calls = 0;
+        function f() { calls += 1; return calls - 1; }
+        let arr = [0, 0];
+        arr[f()] += 1;
+        print(arr, calls);
+        let maps = [{n: 1}];
+        function pick() { calls += 1; return maps[0]; }
+        pick().n *= 5;
+        pick()["n"] += 1;
+        print(maps, calls);
+    "#;
+    assert_eq!(run(source), "[1, 0] 1\n[{n: 6}] 3\n");
+}
+
+#[test]
+fn type_errors_name_the_compound_operator() {
+    let report = error("let count = 1;\ncount += \"x\";");
+    assert_eq!(report.code.id, "E0505");
+    assert_eq!(report.message, "Cannot apply '+=' to number and string");
+    assert_eq!((report.span.line, report.span.column), (2, 1));
+    assert_eq!(error("let a = [true]; a[0] *= 2;").message, "Cannot apply '*=' to bool and number");
+    assert_eq!(error("let m = {k: null}; m.k -= 1;").message, "Cannot apply '-=' to null and number");
+    // a missing key has nothing to update
+    assert_eq!(error("let m = {}; m.k += 1;").code.id, "E0504");
+}
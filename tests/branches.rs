@@ -0,0 +1,89 @@
+mod common;
+
+use common::{error, run};
+use slimescript::formatter::format_source;
+use slimescript::parse;
+use slimescript::printer::print_ast;
+
+#[test]
+fn a_chain_runs_the_first_arm_whose_condition_holds() {
+    let source = r#"
+        function grade(score) {
+            if score >= 90 {
+                return "a";
+            } else if score >= 80 {
+                return "b";
+            } else if score >= 70 {
+                return "c";
+            } else {
+                return "f";
+            }
+        }
+        print(grade(95), grade(90), grade(85), grade(70), grade(12));
+        // without a final else nothing runs when no condition holds
+        let seen = "none";
+        if false { seen = "if"; } else if null { seen = "else if"; }
+        print(seen);
+    "#;
+    assert_eq!(run(source), "a a b c f\nnone\n");
+}
+
+#[test]
+fn an_if_directly_inside_an_if_is_rejected() {
+    // braces are what decide which if an else belongs to
+    let report = error("let a = true;\nlet b = false;\nif a if b { print(1); } else { print(2); }");
+    assert_eq!(report.code.id, "E0100");
+    assert_eq!(
+        report.message,
+        "Expected '{' after the if condition; put an if inside an if in braces, found 'if'"
+    );
+    assert_eq!((report.span.line, report.span.column), (3, 6));
+    assert_eq!(run("let a = true; let b = false; if a { if b { print(1); } } else { print(2); }"), "");
+}
+
+#[test]
+fn an_else_takes_a_block_or_another_if() {
+    let report = error("if false { print(0); } else print(1);");
+    assert_eq!(report.message, "Expected '{' or 'if' after 'else', found 'print'");
+    assert_eq!((report.span.line, report.span.column), (1, 29));
+}
+
+#[test]
+fn the_printer_lists_a_chain_arm_by_arm() {
+    let program = parse("if a { x; } else if b { y; } else if c { z; } else { w; }").unwrap();
+    let expected = "\
+If
+  condition: Variable name=a
+  then:
+    Expression
+      expression: Variable name=x
+  else_ifs:
+    ElseIf
+      condition: Variable name=b
+      then:
+        Expression
+          expression: Variable name=y
+    ElseIf
+      condition: Variable name=c
+      then:
+        Expression
+          expression: Variable name=z
+  else:
+    Expression
+      expression: Variable name=w
+";
+    assert_eq!(print_ast(&program, false), expected);
+
+    // an if written inside an else's braces stays nested
+    let nested = print_ast(&parse("if a { x; } else { if b { y; } }").unwrap(), false);
+    assert!(!nested.contains("ElseIf"), "{}", nested);
+    assert!(nested.contains("  else:\n    If\n"), "{}", nested);
+}
+
+#[test]
+fn formatting_keeps_else_if_on_the_closing_brace() {
+    let source = "if a {\nx();\n}\nelse if b { y(); }\nelse\n{ z(); }\n";
+    let formatted = format_source(source).unwrap();
+    assert_eq!(formatted, "if a {\n    x();\n} else if b {\n    y();\n} else {\n    z();\n}\n");
+    assert_eq!(format_source(&formatted).unwrap(), formatted);
+}
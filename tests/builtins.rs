@@ -0,0 +1,60 @@
+mod common;
+
+use std::io::Cursor;
+
+use common::{Captured, error, run};
+use slimescript::Interpreter;
+
+#[test]
+fn len_counts_characters_elements_and_entries() {
+    assert_eq!(run(r#"print(len(""), len("héllo"), len([1, [2, 3]]), len({ "a": 1, "b": 2 }));"#), "0 5 2 2\n");
+    let report = error("len(5);");
+    assert_eq!(report.code.id, "E0505");
+    assert_eq!(report.message, "len() expects a string, array, or map, got number");
+}
+
+#[test]
+fn type_names_the_type_of_its_argument() {
+    let source = r#"print(type(1), type("s"), type(true), type(null), type([]), type({}), type(len), type(type(1)));"#;
+    assert_eq!(run(source), "number string bool null array map function string\n");
+}
+
+#[test]
+fn str_gives_the_printed_form() {
+    assert_eq!(run(r#"print(str(1.5) + "|" + str([1, "a"]) + "|" + str(null) + "|" + str("s"));"#), "1.5|[1, \"a\"]|null|s\n");
+}
+
+#[test]
+fn num_parses_numbers_and_names_itself_when_it_cant() {
+    assert_eq!(run(r#"print(num("42"), num(" -1.5 "), num("1e3"), num(7));"#), "42 -1.5 1000 7\n");
+    let report = error(r#"num("forty");"#);
+    assert_eq!(report.code.id, "E0517");
+    assert_eq!(report.message, "num() could not parse 'forty' as a number");
+    assert_eq!(error("num([]);").message, "num() expects a string, got array");
+}
+
+#[test]
+fn input_prompts_and_reads_lines_from_the_configured_reader() {
+    let output = Captured::default();
+    let mut interpreter = Interpreter::with_io(Cursor::new("ada\r\nlast"), output.clone());
+    interpreter
+        .run(r#"let name = input("name? "); print("hi " + name); print(input("")); print(input("more? "));"#)
+        .unwrap();
+    assert_eq!(output.text(), "name? hi ada\nlast\nmore? null\n");
+    assert_eq!(error("input(1);").message, "input() expects a string prompt, got number");
+}
+
+#[test]
+fn builtins_check_how_many_arguments_they_get() {
+    for (call, message) in [
+        ("len()", "len() expected 1 argument but got 0"),
+        ("type(1, 2)", "type() expected 1 argument but got 2"),
+        ("str()", "str() expected 1 argument but got 0"),
+        ("num(\"1\", 2)", "num() expected 1 argument but got 2"),
+        ("input()", "input() expected 1 argument but got 0"),
+    ] {
+        let report = error(&format!("{};", call));
+        assert_eq!(report.code.id, "E0513", "{}", call);
+        assert_eq!(report.message, message);
+    }
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use common::{error, run};
+
+#[test]
+fn map_filter_and_reduce_chain() {
+    let source = r#"
+        function square(x) { return x * x; }
+        print(reduce(map(filter([1, 2, 3, 4, 5, 6], function(x) { return x % 2 == 0; }), square), function(a, b) { return a + b; }, 0));
+        print(map(["a", "b"], upper), filter([], square), reduce([1, 2, 3], max));
+        print(reduce(["x"], function(a, b) { return a + b; }), reduce([], function(a, b) { return a + b; }, "init"));
+    "#;
+    assert_eq!(run(source), "56\n[\"A\", \"B\"] [] 3\nx init\n");
+}
+
+#[test]
+fn map_and_filter_make_new_arrays() {
+    let source = r#"
+        let xs = [1, 2];
+        let doubled = map(xs, function(x) { return x * 2; });
+        let kept = filter(xs, function(x) { return true; });
+        push(kept, 3);
+        print(xs, doubled, kept);
+    "#;
+    assert_eq!(run(source), "[1, 2] [2, 4] [1, 2, 3]\n");
+}
+
+#[test]
+fn errors_in_callbacks_show_the_builtin_and_the_callback() {
+    let report = error("function broken(x) { return x.missing; }\nmap([{}], broken);");
+    assert_eq!(report.code.id, "E0504");
+    assert_eq!(report.notes, ["in broken() called at line 2, column 1", "in map() called at line 2, column 1"]);
+}
+
+#[test]
+fn reduce_needs_an_initial_value_for_an_empty_array() {
+    let report = error("reduce([], function(a, b) { return a + b; });");
+    assert_eq!(report.code.id, "E0518");
+    assert_eq!(report.message, "reduce() of an empty array needs an initial value");
+    assert_eq!(error("map([1], 2);").message, "map() expects a function, got number");
+}
@@ -0,0 +1,181 @@
+use std::fs;
+
+use slimescript::checker::{Diagnostic, Severity, check_source, check_source_file, remove_allowed};
+use slimescript::codes;
+use slimescript::{CheckOptions, FileStatus, check_files, check_files_with};
+
+/// The codes of the errors checking `source` finds, with their lines.
+fn errors(source: &str) -> Vec<(&'static str, usize)> {
+    check_source(source)
+        .into_iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .map(|diagnostic| (diagnostic.code.id, diagnostic.span.line))
+        .collect()
+}
+
+#[test]
+fn lexer_errors_dont_cascade_into_parse_errors() {
+    assert_eq!(errors("let x = 5 @ 3;\nprint(x);"), [("E0001", 1), ("E0200", 2)]);
+    // an unterminated string is reported at the end of the input
+    assert_eq!(errors("print(1);\nlet greeting = \"hello;\n"), [("E0002", 3)]);
+}
+
+#[test]
+fn parse_errors_elsewhere_are_still_reported() {
+    // in a later statement
+    assert_eq!(errors("let a = 1 @ 2;\nlet = 2;"), [("E0001", 1), ("E0100", 2)]);
+    // before the lexer error in the same statement
+    assert_eq!(errors("let = 5 @ 3;"), [("E0100", 1), ("E0001", 1)]);
+}
+
+/// Where the warnings with `code` that checking `source` finds are, as the
+/// text each underlines.
+fn underlined<'a>(source: &'a str, code: &str) -> Vec<&'a str> {
+    check_source(source)
+        .into_iter()
+        .filter(|diagnostic| diagnostic.code.id == code)
+        .map(|diagnostic| &source[diagnostic.span.start..diagnostic.span.end])
+        .collect()
+}
+
+#[test]
+fn unused_names_are_underlined_alone() {
+    let source = "let total = [\n    1,\n    2,\n];\nfunction helper() {\n    return 1;\n}\nconst limit: number = 3;";
+    assert_eq!(underlined(source, "W0001"), ["total", "limit"]);
+    assert_eq!(underlined(source, "W0002"), ["helper"]);
+}
+
+#[test]
+fn unreachable_code_is_underlined_at_its_first_keyword() {
+    let source = "function f() {\n    return 1;\n    let x = {\n        a: 1,\n    };\n    print(x);\n}\nf();";
+    assert_eq!(underlined(source, "W0003"), ["let"]);
+    let source = "if false {\n    outer: while true {\n        break outer;\n    }\n}";
+    assert_eq!(underlined(source, "W0003"), ["outer"]);
+    let source = "function f() {\n    throw \"stop\";\n    f();\n}";
+    assert_eq!(underlined(source, "W0003"), ["f()"]);
+}
+
+#[test]
+fn names_from_imported_files_are_known_but_typos_are_still_reported() {
+    let directory = std::env::temp_dir().join(format!("slimescript-check-imports-{}", std::process::id()));
+    fs::create_dir_all(directory.join("lib")).unwrap();
+    fs::write(directory.join("lib/utils.slime"), "import \"more.slime\";\nfunction clamp(x) { return x; }\nexport let limit = 3;").unwrap();
+    fs::write(directory.join("lib/more.slime"), "let extra = 1;").unwrap();
+    let main = directory.join("main.slime");
+    let codes = |source: &str| -> Vec<_> {
+        check_source_file(source, &main)
+            .into_iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+            .map(|diagnostic| (diagnostic.code.id, diagnostic.message))
+            .collect()
+    };
+
+    let source = "import \"lib/utils.slime\";\nprint(clamp(limit) + extra);\nprint(clmap(1));";
+    assert_eq!(codes(source), [("E0200", "Undefined variable 'clmap'".to_string())]);
+    // a file that can't be read could declare anything
+    assert_eq!(codes("import \"lib/missing.slime\";\nprint(clmap(1));"), []);
+    // relative to the current directory, the import isn't found
+    assert_eq!(errors(source), []);
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn files_are_checked_in_parallel_and_reported_in_order() {
+    let directory = std::env::temp_dir().join(format!("slimescript-check-files-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    // every seventh file has a parse error on its line `i % 5 + 1`
+    let paths: Vec<_> = (0..40)
+        .map(|i| {
+            let path = directory.join(format!("file{}.slime", i));
+            let mut source = format!("function f{i}(x) {{ return x * {i}; }}\n").repeat(i % 5);
+            source += match i % 7 {
+                0 => "let = 1;\n",
+                _ => "print(1);\n",
+            };
+            fs::write(&path, source).unwrap();
+            path
+        })
+        .collect();
+    let errors = |status: &FileStatus| -> Vec<(&str, usize)> {
+        match status {
+            FileStatus::Checked { diagnostics, .. } => diagnostics
+                .iter()
+                .filter(|diagnostic| diagnostic.severity == Severity::Error)
+                .map(|diagnostic| (diagnostic.code.id, diagnostic.span.line))
+                .collect(),
+            _ => panic!("{:?}", status),
+        }
+    };
+
+    let reports = check_files_with(&paths, &CheckOptions { jobs: 4, ..Default::default() });
+    assert_eq!(reports.iter().map(|report| &report.path).collect::<Vec<_>>(), paths.iter().collect::<Vec<_>>());
+    for (i, report) in reports.iter().enumerate() {
+        let expected: Vec<_> = match i % 7 {
+            0 => vec![("E0100", i % 5 + 1)],
+            _ => vec![],
+        };
+        assert_eq!(errors(&report.status), expected, "file{}", i);
+        assert_eq!(report.failed(), i % 7 == 0);
+    }
+    assert_eq!(reports.iter().filter(|report| report.failed()).count(), 6);
+
+    // one job at a time, nothing after the first broken file is started
+    let options = CheckOptions { jobs: 1, stop_on_error: true, ..Default::default() };
+    let reports = check_files_with(&paths[1..], &options);
+    assert!(reports[..6].iter().all(|report| !report.failed()));
+    assert_eq!(errors(&reports[6].status), [("E0100", 3)]);
+    assert!(reports[7..].iter().all(|report| matches!(report.status, FileStatus::Skipped)));
+
+    let missing = [paths[1].clone(), directory.join("missing.slime")];
+    let reports = check_files(&missing);
+    assert!(matches!(reports[1].status, FileStatus::Unreadable(_)) && reports[1].failed());
+    assert!(!reports[0].failed());
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn assignments_in_conditions_are_warned_about() {
+    let source = "let x = 1;\nif x = 2 { print(x); }\nwhile !(x = 3) {}\nif x == 2 && (x = 4) {}";
+    assert_eq!(underlined(source, "W0005"), ["x = 2", "(x = 3)", "(x = 4)"]);
+    let suggestions: Vec<_> = check_source(source).into_iter().filter_map(|diagnostic| diagnostic.suggestion).collect();
+    assert_eq!(suggestions, ["use `==` to compare"; 3]);
+    // an assignment outside a condition, or a comparison inside one, is fine
+    assert_eq!(underlined("let x = 1;\nlet y = (x = 3);\nif x == 3 { print(y); }", "W0005"), [""; 0]);
+}
+
+#[test]
+fn interpolation_in_strings_is_warned_about() {
+    assert_eq!(underlined("let x = 1;\nprint(\"cost: ${x}\", x);", "W0006"), ["\"cost: ${x}\""]);
+    // a dollar sign or braces alone, or `${` never closed, aren't interpolation
+    assert_eq!(underlined("print(\"$5 {each}\", \"${\", \"} ${\");", "W0006"), [""; 0]);
+}
+
+#[test]
+fn strings_in_a_row_are_warned_about_alongside_the_parse_error() {
+    let source = "print(\"a\" \"b\");";
+    assert_eq!(underlined(source, "W0007"), ["\"a\" \"b\""]);
+    assert_eq!(errors(source), [("E0100", 1)]);
+    assert_eq!(underlined("print(\"a\" + \"b\");", "W0007"), [""; 0]);
+}
+
+#[test]
+fn numbers_against_names_are_warned_about_alongside_the_parse_error() {
+    let diagnostics = check_source("let x = 2;\nprint(3x);");
+    let warning = diagnostics.iter().find(|diagnostic| diagnostic.code.id == "W0008").unwrap();
+    assert_eq!(warning.message, "The number 3 is written right against 'x'");
+    assert_eq!(warning.suggestion.as_deref(), Some("write `3 * x` to multiply them, or put a space between them"));
+    assert_eq!((warning.span.line, warning.span.column), (2, 7));
+    // hex, ranges and an operator in between are fine
+    assert_eq!(underlined("let x = 2;\nprint(3 * x, 0x1f, 0..10, 2.5);", "W0008"), [""; 0]);
+}
+
+#[test]
+fn allowed_warnings_are_dropped_but_errors_are_not() {
+    let mut diagnostics = check_source("let x = 1;\nif x = 2 { print(x, \"${x}\"); }\nprint(y);");
+    let ids = |diagnostics: &[Diagnostic]| -> Vec<&str> {
+        diagnostics.iter().map(|diagnostic| diagnostic.code.id).collect()
+    };
+    assert_eq!(ids(&diagnostics), ["W0005", "W0006", "E0200"]);
+    remove_allowed(&mut diagnostics, &[&codes::ASSIGN_IN_CONDITION, &codes::UNDEFINED_VARIABLE]);
+    assert_eq!(ids(&diagnostics), ["W0006", "E0200"]);
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use common::{error, run};
+
+#[test]
+fn classes_construct_instances_with_init_and_methods() {
+    let source = r#"
+        class Point {
+            init(x, y) { this.x = x; this.y = y; }
+            dist() { return sqrt(this.x * this.x + this.y * this.y); }
+            moved(dx) { return Point(this.x + dx, this.y); }
+        }
+        let p = Point(3, 4);
+        print(p.dist(), p.x, p.moved(3).x, p.x);
+        p.y = 0;
+        print(p.dist());
+        let dist = p.dist;
+        p.x = 5;
+        print(dist());
+        class Empty {}
+        let e = Empty();
+        e.label = "set later";
+        print(e.label);
+    "#;
+    assert_eq!(run(source), "5 3 6 3\n3\n5\nset later\n");
+}
+
+#[test]
+fn init_checks_its_arguments() {
+    let report = error("class Point { init(x, y) { this.x = x; } }\nPoint(1);");
+    assert_eq!(report.code.id, "E0513");
+    assert_eq!(report.message, "Point() expected 2 arguments but got 1");
+    assert_eq!(error("class Empty {}\nEmpty(1);").message, "Empty() expected 0 arguments but got 1");
+}
+
+#[test]
+fn missing_fields_are_errors_not_null() {
+    let report = error("class Point { init() { this.x = 1; } }\nlet p = Point();\nprint(p.y);");
+    assert_eq!(report.code.id, "E0504");
+    assert_eq!(report.message, "Point instance has no field or method 'y'");
+    assert_eq!(report.span.line, 3);
+    assert_eq!(error("class Point {}\nPoint().area();").message, "Point instance has no field or method 'area'");
+}
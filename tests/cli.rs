@@ -0,0 +1,249 @@
+mod common;
+
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Command, Stdio};
+
+use common::{Captured, assert_snapshot, interpreter};
+use slimescript::{Arity, Value};
+
+/// Runs the command line with `arguments` on a long script, reads one line
+/// of what it prints and closes the pipe, as `| head -1` would, returning
+/// that line, its exit status, and what it wrote to stderr.
+fn first_line(arguments: &[&str]) -> (String, bool, String) {
+    let path = std::env::temp_dir().join(format!("slimescript-cli-{}-{}.slime", std::process::id(), arguments.join("_")));
+    fs::write(&path, "let x = 1;\n".repeat(50_000)).unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_slimescript"))
+        .args(arguments)
+        .arg(&path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("the command line starts");
+    let mut line = String::new();
+    BufReader::new(child.stdout.take().unwrap()).read_line(&mut line).unwrap();
+    let mut stderr = String::new();
+    child.stderr.take().unwrap().read_to_string(&mut stderr).unwrap();
+    let status = child.wait().unwrap();
+    fs::remove_file(&path).unwrap();
+    (line, status.success(), stderr)
+}
+
+#[test]
+fn listings_end_quietly_when_the_reader_stops() {
+    let commands: [&[&str]; 4] = [&["--tokens"], &["--tokens", "--json"], &["--ast"], &["disasm"]];
+    for arguments in commands {
+        let (line, success, stderr) = first_line(arguments);
+        assert!(!line.is_empty(), "{:?} printed nothing", arguments);
+        assert!(success, "{:?} failed: {}", arguments, stderr);
+        assert_eq!(stderr, "", "{:?}", arguments);
+    }
+}
+
+/// Runs the command line with `arguments`, giving it `stdin`, and returns
+/// its exit code, stdout, and stderr.
+fn slimescript(arguments: &[&str], stdin: &str) -> (i32, String, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_slimescript"))
+        .args(arguments)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("the command line starts");
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    let text = |bytes: Vec<u8>| String::from_utf8(bytes).unwrap();
+    (output.status.code().expect("it exits"), text(output.stdout), text(output.stderr))
+}
+
+#[test]
+fn scripts_given_as_files_exit_with_what_happened() {
+    let directory = std::env::temp_dir().join(format!("slimescript-cli-exits-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let script = |name: &str, source: &str| {
+        let path = directory.join(name);
+        fs::write(&path, source).unwrap();
+        path.to_str().unwrap().to_string()
+    };
+    let fine = script("fine.slime", "print(1 + 2);");
+    let broken = script("broken.slime", "let = 1;");
+    let failing = script("failing.slime", "print(1);\n[][0];");
+
+    assert_eq!(slimescript(&["--no-color", &fine], ""), (0, "3\n".to_string(), String::new()));
+    let (code, stdout, stderr) = slimescript(&["--no-color", &broken], "");
+    assert_eq!((code, stdout.as_str()), (65, ""));
+    assert!(stderr.contains(&format!("{}:1:5", broken)), "{}", stderr);
+    let (code, stdout, stderr) = slimescript(&["--no-color", "run", &failing], "");
+    assert_eq!((code, stdout.as_str()), (70, "1\n"));
+    assert!(stderr.contains(&format!("{}:2:", failing)), "{}", stderr);
+
+    let missing = directory.join("missing.slime");
+    let (code, stdout, stderr) = slimescript(&[missing.to_str().unwrap()], "");
+    assert_eq!((code, stdout.as_str()), (66, ""));
+    assert!(stderr.contains("missing.slime"), "{}", stderr);
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn exit_sets_the_status_of_the_process() {
+    let source = "function stop() { try { exit(7); } catch (e) { print(\"caught\"); } }
+print(\"before\");
+stop();
+print(1);";
+    assert_eq!(slimescript(&["-"], source), (7, "before\n".to_string(), String::new()));
+    assert_eq!(slimescript(&["-"], "exit(0); print(1);"), (0, String::new(), String::new()));
+    // an exit() that can't be carried out is a runtime error like any other
+    let (code, _, stderr) = slimescript(&["--no-color", "-"], "exit(-1);");
+    assert_eq!(code, 70);
+    assert!(stderr.contains("exit() expects a whole number from 0 to 255"), "{}", stderr);
+}
+
+#[test]
+fn warnings_can_be_allowed_one_by_one_or_all_quieted() {
+    let source = "let x = 1;\nif x = 2 { print(\"${x}\", x); }";
+    let (code, stdout, stderr) = slimescript(&["--no-color", "--check", "-"], source);
+    assert_eq!((code, stdout.as_str()), (0, ""));
+    assert!(stderr.contains("W0005") && stderr.contains("W0006"), "{}", stderr);
+    assert!(stderr.contains("= help: use `==` to compare"), "{}", stderr);
+
+    let (_, _, stderr) = slimescript(&["--no-color", "--check", "--allow", "assign-in-condition", "-"], source);
+    assert!(!stderr.contains("W0005") && stderr.contains("W0006"), "{}", stderr);
+    let (code, stdout, stderr) = slimescript(&["--no-color", "run", "--allow", "W0006", "-"], source);
+    assert_eq!((code, stdout.as_str()), (0, "${x} 2\n"));
+    assert!(stderr.contains("W0005") && !stderr.contains("W0006"), "{}", stderr);
+    assert_eq!(slimescript(&["run", "--quiet", "-"], source), (0, "${x} 2\n".to_string(), String::new()));
+
+    // errors can't be allowed
+    let (code, _, stderr) = slimescript(&["--check", "--allow", "E0200", "-"], source);
+    assert_eq!(code, 64);
+    assert!(stderr.starts_with("Unknown warning 'E0200'"), "{}", stderr);
+}
+
+/// The `--output json` events in `stdout`, read back with `json_parse`,
+/// one line each with the `fields` the event has, or `-` for those it
+/// doesn't.
+fn events(stdout: &str, fields: &[&str]) -> String {
+    let lines: Vec<Value> = stdout.lines().map(Value::from).collect();
+    let fields: Vec<Value> = fields.iter().map(|&field| Value::from(field)).collect();
+    let output = Captured::default();
+    let mut interpreter = interpreter(&output);
+    interpreter.register_fn("events", Arity::Exact(0), move |_| Ok(Value::from(lines.clone())));
+    interpreter.register_fn("fields", Arity::Exact(0), move |_| Ok(Value::from(fields.clone())));
+    let source = "
+        for line in events() {
+            let event = json_parse(line);
+            let shown = [];
+            for field in fields() {
+                if has(event, field) { push(shown, event[field]); } else { push(shown, \"-\"); }
+            }
+            print(join(shown, \" \"));
+        }
+    ";
+    interpreter.run(source).unwrap();
+    output.text()
+}
+
+#[test]
+fn json_output_is_one_event_per_line() {
+    let source = "print(\"hi\", 1);\nlet x = 1;\nif x = 2 { print(\"tab\\there\"); }\n[][0];";
+    let (code, stdout, stderr) = slimescript(&["run", "--output", "json", "-"], source);
+    assert_eq!((code, stderr.as_str()), (70, ""));
+    // the warnings come before the script runs
+    assert_eq!(
+        events(&stdout, &["type", "text", "code", "severity", "line", "column", "exit_code"]),
+        "diagnostic - W0001 warning 2 5 -\n\
+         diagnostic - W0005 warning 3 4 -\n\
+         print hi 1 - - - - -\n\
+         print tab\there - - - - -\n\
+         diagnostic - E0503 error 4 1 -\n\
+         result - - - - - 70\n"
+    );
+    assert!(events(&stdout, &["duration_ms"]).lines().last().unwrap().parse::<f64>().is_ok());
+}
+
+#[test]
+fn an_unreadable_script_is_a_json_event_too() {
+    let missing = std::env::temp_dir().join(format!("slimescript-missing-{}.slime", std::process::id()));
+    let path = missing.to_str().unwrap();
+    let (code, stdout, stderr) = slimescript(&["run", "--output", "json", path], "");
+    assert_eq!((code, stderr.as_str()), (66, ""));
+    assert_eq!(
+        events(&stdout, &["type", "code", "name", "severity", "line", "exit_code"]),
+        "diagnostic E0519 io-failed error null -\nresult - - - - 66\n"
+    );
+    let message = events(&stdout, &["message"]);
+    assert!(message.starts_with(&format!("Could not read {}: ", path)), "{}", message);
+}
+
+#[test]
+fn scripts_can_come_from_stdin() {
+    assert_eq!(slimescript(&["-"], "print(1 + 2);"), (0, "3\n".to_string(), String::new()));
+    // with no arguments and stdin not a terminal, it runs what's piped in
+    assert_eq!(slimescript(&[], "print(\"piped\");"), (0, "piped\n".to_string(), String::new()));
+    let (code, _, stderr) = slimescript(&["--no-color", "-"], "print(1);\n[][0];");
+    assert_eq!(code, 70);
+    assert!(stderr.contains("<stdin>:2:1"), "{}", stderr);
+    let (code, _, stderr) = slimescript(&["--no-color", "-"], "let = 1;");
+    assert_eq!(code, 65);
+    assert!(stderr.contains("<stdin>:1:5"), "{}", stderr);
+}
+
+#[test]
+fn help_succeeds_and_bad_usage_does_not() {
+    for flag in ["--help", "-h"] {
+        let (code, stdout, stderr) = slimescript(&[flag], "");
+        assert_eq!(code, 0, "{}", flag);
+        assert!(stdout.starts_with("Usage: slimescript"), "{}", stdout);
+        assert_eq!(stderr, "");
+    }
+    let (code, stdout, stderr) = slimescript(&["run", "--no-such-flag", "x.slime"], "");
+    assert_eq!((code, stdout.as_str()), (64, ""));
+    assert!(stderr.starts_with("Usage: slimescript"), "{}", stderr);
+}
+
+#[test]
+fn arguments_after_a_double_dash_go_to_the_script() {
+    let source = "print(len(args())); for arg in args() { print(arg); }";
+    let (code, stdout, stderr) = slimescript(&["run", "-", "--", "input.csv", "42", "--vm", "--"], source);
+    assert_eq!((code, stdout.as_str(), stderr.as_str()), (0, "4\ninput.csv\n42\n--vm\n--\n", ""));
+    assert_eq!(slimescript(&["-"], source), (0, "0\n".to_string(), String::new()));
+}
+
+#[test]
+fn integer_overflow_is_chosen_on_the_command_line() {
+    let source = "print(9223372036854775807 + 1);";
+    let printed = |mode: &str| slimescript(&["run", "--int-overflow", mode, "-"], source).1;
+    assert_eq!(printed("promote"), "9223372036854776000\n");
+    assert_eq!(printed("wrap"), "-9223372036854775808\n");
+    assert_eq!(printed("saturate"), "9223372036854775807\n");
+    let (code, stdout, stderr) = slimescript(&["--no-color", "--vm", "--int-overflow", "error", "-"], source);
+    assert_eq!((code, stdout.as_str()), (70, ""));
+    assert!(stderr.contains("E0520") && stderr.contains("9223372036854775807 + 1 is out of range"), "{}", stderr);
+    assert_eq!(slimescript(&["--int-overflow", "clamp", "-"], source).0, 64);
+}
+
+#[test]
+fn doc_prints_a_markdown_page() {
+    let source = "/// Says hi.\nfunction hi(name) {}\n";
+    let page = "# <stdin>\n\n## hi\n\n```\nfunction hi(name)\n```\n\nSays hi.\n";
+    assert_eq!(slimescript(&["doc", "-"], source), (0, page.to_string(), String::new()));
+    assert_eq!(slimescript(&["doc", "--format", "md", "-"], source).1, page);
+    assert_eq!(slimescript(&["doc", "--format", "html", "-"], source).0, 64);
+    assert_eq!(slimescript(&["doc", "-"], "function (").0, 65);
+}
+
+#[test]
+fn ast_dumps_the_tree_without_running_it() {
+    let source = "let total = 0;\nfor x in [1, 2] {\n    total += x * 2;\n}\nprint(total);\n";
+    let dump = |arguments: &[&str]| {
+        let (code, stdout, stderr) = slimescript(arguments, source);
+        assert_eq!((code, stderr.as_str()), (0, ""), "{:?}", arguments);
+        stdout
+    };
+    assert_snapshot("ast.txt", &dump(&["--ast", "-"]));
+    assert_snapshot("ast.json", &dump(&["--ast", "--json", "-"]));
+    assert_snapshot("ast_spans.txt", &dump(&["--ast", "--spans", "-"]));
+    assert_eq!(slimescript(&["--ast", "-"], "let = 1;").0, 65);
+    assert_eq!(slimescript(&["--spans", "-"], source).0, 64);
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use std::collections::HashSet;
+
+use common::error;
+use slimescript::codes;
+
+#[test]
+fn codes_are_unique_and_explained() {
+    let mut ids = HashSet::new();
+    let mut names = HashSet::new();
+    for code in codes::all() {
+        assert!(ids.insert(code.id), "{} is used twice", code.id);
+        assert!(names.insert(code.name), "{} is used twice", code.name);
+        assert!(!code.explanation.is_empty(), "{} has no explanation", code.id);
+        assert_eq!(codes::lookup(code.id), Some(code));
+        assert_eq!(codes::lookup(code.name), Some(code));
+    }
+}
+
+fn code(source: &str) -> &'static str {
+    error(source).code.id
+}
+
+#[test]
+fn language_errors_have_their_own_codes() {
+    assert_eq!(code("function f(a, b) { return a; } f(1);"), "E0513");
+    assert_eq!(code("len();"), "E0513");
+    assert_eq!(code("const limit = 1; limit = 2;"), "E0514");
+    assert_eq!(code("let x = 1; let x = 2;"), "E0515");
+    assert_eq!(code("let end = 2.5; for i in 0..end { }"), "E0516");
+}
+
+#[test]
+fn builtin_errors_have_their_own_codes() {
+    // passing the wrong type
+    assert_eq!(code("len(5);"), "E0505");
+    assert_eq!(code("upper(5);"), "E0505");
+    assert_eq!(code("keys([1]);"), "E0505");
+    // values of the right type that can't be used
+    assert_eq!(code("num(\"ten\");"), "E0517");
+    assert_eq!(code("sqrt(-1);"), "E0517");
+    assert_eq!(code("split(\"a,b\", \"\");"), "E0517");
+    assert_eq!(code("format(\"{} {}\", 1);"), "E0517");
+    assert_eq!(code("json_parse(\"{\");"), "E0517");
+    assert_eq!(code("random(3, 1);"), "E0517");
+    // positions past the end
+    assert_eq!(code("substring(\"abc\", 0, 10);"), "E0503");
+    assert_eq!(code("slice([1, 2], 1, 5);"), "E0503");
+    // taking from nothing
+    assert_eq!(code("pop([]);"), "E0518");
+    assert_eq!(code("reduce([], function(a, b) { return a + b; });"), "E0518");
+}
+
+#[test]
+fn nothing_built_in_uses_the_catch_all_code() {
+    let sources = [
+        "len(5);",
+        "num(\"x\");",
+        "pop([]);",
+        "substring(\"a\", 2, 1);",
+        "format_time(0, \"%\");",
+        "exit(300);",
+        "sleep(-1);",
+        "regex_match(\"(\", \"a\");",
+    ];
+    for source in sources {
+        assert_ne!(code(source), "E0500", "{}", source);
+    }
+}
@@ -0,0 +1,111 @@
+// Each test file is its own crate and uses only some of these.
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use slimescript::report::Report;
+use slimescript::{Backend, Interpreter};
+
+/// A `Write` the tests can read back after the interpreter that owns a
+/// clone of it is done.
+#[derive(Clone, Default)]
+pub struct Captured(Rc<RefCell<Vec<u8>>>);
+
+impl Captured {
+    pub fn text(&self) -> String {
+        String::from_utf8(self.0.borrow().clone()).expect("output is UTF-8")
+    }
+}
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// An interpreter with no input that prints to `output`.
+pub fn interpreter(output: &Captured) -> Interpreter {
+    Interpreter::with_io(io::empty(), output.clone())
+}
+
+// every way a program can be run, each of which must print the same
+const SETUPS: [(&str, Backend, bool); 3] = [
+    ("tree-walker", Backend::TreeWalker, false),
+    ("vm", Backend::Vm, false),
+    ("optimized vm", Backend::Vm, true),
+];
+
+/// What `source` prints and the error that stopped it, if any, run each
+/// way in `SETUPS`, checking that every way agrees.
+pub fn outcome(source: &str) -> (String, Option<Report>) {
+    let mut first: Option<(String, Option<Report>)> = None;
+    for (name, backend, optimize) in SETUPS {
+        let output = Captured::default();
+        let result = interpreter(&output).with_backend(backend).with_constant_folding(optimize).run(source);
+        let this = (output.text(), result.err().map(|error| error.report()));
+        match &first {
+            None => first = Some(this),
+            Some((text, error)) => {
+                assert_eq!(&this.0, text, "the {} printed something else", name);
+                assert_eq!(
+                    this.1.as_ref().map(|report| (report.code.id, &report.message)),
+                    error.as_ref().map(|report| (report.code.id, &report.message)),
+                    "the {} failed differently",
+                    name
+                );
+            }
+        }
+    }
+    first.expect("there is a setup")
+}
+
+/// What `source` prints, which must run without an error.
+pub fn run(source: &str) -> String {
+    match outcome(source) {
+        (output, None) => output,
+        (_, Some(error)) => panic!("{}[{}]: {}", error.kind, error.code.id, error.message),
+    }
+}
+
+/// The error that stops `source`, which must fail.
+pub fn error(source: &str) -> Report {
+    match outcome(source) {
+        (_, Some(error)) => error,
+        (output, None) => panic!("expected an error, but it ran and printed {:?}", output),
+    }
+}
+
+/// Calls `f` on a thread with a stack as big as the command line gives
+/// the interpreter, for programs that build or drop very deep values.
+pub fn on_big_stack<T: Send>(f: impl FnOnce() -> T + Send) -> T {
+    std::thread::scope(|scope| {
+        std::thread::Builder::new()
+            .stack_size(64 * 1024 * 1024)
+            .spawn_scoped(scope, f)
+            .expect("the thread starts")
+            .join()
+            .expect("the thread doesn't panic")
+    })
+}
+
+/// Checks `actual` against the snapshot `tests/snapshots/<name>`. Running
+/// with `UPDATE_SNAPSHOTS=1` writes `actual` there instead, to be reviewed
+/// in the diff.
+pub fn assert_snapshot(name: &str, actual: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots").join(name);
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap_or_else(|_| panic!("{} is missing", path.display()));
+    assert_eq!(actual, expected, "{} changed; rerun with UPDATE_SNAPSHOTS=1 to accept", name);
+}
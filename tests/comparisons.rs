@@ -0,0 +1,42 @@
+mod common;
+
+use common::{error, run};
+
+#[test]
+fn a_chain_of_comparisons_is_rejected_with_the_rewrite() {
+    let report = error("let a = 1; let b = 2; let c = 3;\nprint(a < b < c);");
+    assert_eq!(report.code.id, "E0109");
+    assert_eq!(report.message, "Chained comparisons are not supported; did you mean `a < b && b < c`?");
+    // the whole chain is underlined
+    assert_eq!((report.span.line, report.span.column), (2, 7));
+    assert_eq!(report.span.end - report.span.start, "a < b < c".len());
+
+    let report = error("let a = 1; let b = 1; let c = true;\nif a == b == c { print(1); }");
+    assert_eq!(report.code.id, "E0109");
+    assert_eq!(report.message, "Chained comparisons are not supported; did you mean `a == b && b == c`?");
+    assert_eq!((report.span.line, report.span.column), (2, 4));
+    assert_eq!(report.span.end - report.span.start, "a == b == c".len());
+}
+
+#[test]
+fn the_rewrite_keeps_the_operands_as_written() {
+    let report = error("let x = 5;\nif 1 <= x  >  f(\"a b\", [2]) {}");
+    assert_eq!(
+        report.message,
+        "Chained comparisons are not supported; did you mean `1 <= x && x > f(\"a b\", [2])`?"
+    );
+    assert_eq!(
+        error("let s = 1; print(s != 0 == true);").message,
+        "Chained comparisons are not supported; did you mean `s != 0 && 0 == true`?"
+    );
+}
+
+#[test]
+fn comparisons_kept_apart_still_work() {
+    let source = r#"
+        let a = 1; let b = 2; let c = 3;
+        print(a < b && b < c, (a < b) == (b < c), (a == b) == false);
+        print(a < b == true, 1 + 1 < 3 != false);
+    "#;
+    assert_eq!(run(source), "true true true\ntrue true\n");
+}
@@ -0,0 +1,56 @@
+use std::io;
+
+use slimescript::completion::{Completion, CompletionKind};
+use slimescript::{Arity, Interpreter, Value, complete, repl};
+
+fn labels(completions: Vec<Completion>) -> Vec<String> {
+    completions.into_iter().map(|completion| completion.label).collect()
+}
+
+#[test]
+fn completes_builtins_and_keywords_by_prefix() {
+    let completions = complete("pri", 3);
+    assert_eq!(labels(completions.clone()), ["print"]);
+    assert_eq!(completions[0].kind, CompletionKind::Builtin);
+    assert!(labels(complete("whi", 3)).contains(&"while".to_string()));
+}
+
+#[test]
+fn block_variables_are_only_in_scope_inside_the_block() {
+    let source = "{ let total = 1; to } to";
+    let inside = source.find("to }").unwrap() + 2;
+    assert!(labels(complete(source, inside)).contains(&"total".to_string()));
+    assert!(!labels(complete(source, source.len())).contains(&"total".to_string()));
+}
+
+#[test]
+fn nothing_completes_inside_strings_or_comments() {
+    assert!(complete("print(\"pri", 10).is_empty());
+    assert!(complete("// pri", 6).is_empty());
+}
+
+#[test]
+fn map_keys_complete_after_a_dot() {
+    let source = "let point = {x: 1, y: 2}; point.";
+    assert_eq!(labels(complete(source, source.len())), ["x", "y"]);
+}
+
+#[test]
+fn unfinished_code_still_completes() {
+    let source = "function area(width) { let w = width * ; wi";
+    assert_eq!(labels(complete(source, source.len())), ["width"]);
+}
+
+#[test]
+fn the_repl_completes_earlier_entries_and_host_functions() {
+    let mut interpreter = Interpreter::with_io(io::empty(), io::sink());
+    interpreter.register_fn("host_value", Arity::Exact(0), |_| Ok(Value::Null));
+    interpreter.run("let hotel = 1; class House {}").unwrap();
+    let completions = repl::completions(&interpreter, "ho", 2);
+    let found: Vec<(&str, CompletionKind)> = completions.iter().map(|c| (c.label.as_str(), c.kind)).collect();
+    assert_eq!(
+        found,
+        [("host_value", CompletionKind::Function), ("hotel", CompletionKind::Variable)]
+    );
+    assert_eq!(labels(repl::completions(&interpreter, "Hou", 3)), ["House"]);
+}
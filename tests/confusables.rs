@@ -0,0 +1,116 @@
+use slimescript::checker::{Severity, check_source};
+use slimescript::lexer::{Lexer, LexerConfig};
+
+/// The message the lexer stops `source` with, and the column it points at.
+fn unexpected(source: &str) -> (String, usize) {
+    let error = Lexer::new(source).tokenize().unwrap_err();
+    assert_eq!(error.code.id, "E0001", "{}", source);
+    (error.message, error.span.column)
+}
+
+#[test]
+fn lookalike_characters_are_named_with_what_to_write() {
+    let quote = "string literals must use '\"'";
+    let cases = [
+        ('\u{201C}', "LEFT DOUBLE QUOTATION MARK", quote),
+        ('\u{201D}', "RIGHT DOUBLE QUOTATION MARK", quote),
+        ('\u{201E}', "DOUBLE LOW-9 QUOTATION MARK", quote),
+        ('\u{2018}', "LEFT SINGLE QUOTATION MARK", quote),
+        ('\u{2019}', "RIGHT SINGLE QUOTATION MARK", quote),
+        ('\u{00AB}', "LEFT-POINTING DOUBLE ANGLE QUOTATION MARK", quote),
+        ('\u{00BB}', "RIGHT-POINTING DOUBLE ANGLE QUOTATION MARK", quote),
+        ('\u{2212}', "MINUS SIGN", "write '-' instead"),
+        ('\u{2010}', "HYPHEN", "write '-' instead"),
+        ('\u{2011}', "NON-BREAKING HYPHEN", "write '-' instead"),
+        ('\u{2013}', "EN DASH", "write '-' instead"),
+        ('\u{2014}', "EM DASH", "write '-' instead"),
+        ('\u{00D7}', "MULTIPLICATION SIGN", "write '*' instead"),
+        ('\u{00F7}', "DIVISION SIGN", "write '/' instead"),
+        ('\u{037E}', "GREEK QUESTION MARK", "write ';' instead"),
+        ('\u{2026}', "HORIZONTAL ELLIPSIS", "write '...' instead"),
+    ];
+    for (ch, name, instead) in cases {
+        let message = format!("Unexpected character '{}' (U+{:04X} {}); {}", ch, ch as u32, name, instead);
+        assert_eq!(unexpected(&format!("let x = {}1;", ch)), (message, 9));
+    }
+    // as pasted from a chat app
+    let (message, column) = unexpected("print(“hello”);");
+    assert_eq!(message, "Unexpected character '“' (U+201C LEFT DOUBLE QUOTATION MARK); string literals must use '\"'");
+    assert_eq!(column, 7);
+}
+
+#[test]
+fn invisible_characters_are_shown_escaped() {
+    let cases = [
+        ('\u{200B}', "ZERO WIDTH SPACE"),
+        ('\u{2060}', "WORD JOINER"),
+        ('\u{FEFF}', "ZERO WIDTH NO-BREAK SPACE"),
+    ];
+    for (ch, name) in cases {
+        let message = format!(
+            "Unexpected character '\\u{{{:x}}}' (U+{:04X} {}); it's invisible, so delete it",
+            ch as u32, ch as u32, name
+        );
+        assert_eq!(unexpected(&format!("let{}x = 1;", ch)), (message, 4));
+    }
+}
+
+#[test]
+fn fullwidth_forms_suggest_their_ascii_character() {
+    assert_eq!(
+        unexpected("print（1);").0,
+        "Unexpected character '（' (U+FF08, a fullwidth '('); write '(' instead"
+    );
+    assert_eq!(unexpected("let x ＝ ０;").0, "Unexpected character '＝' (U+FF1D, a fullwidth '='); write '=' instead");
+    assert_eq!(unexpected("x～").0, "Unexpected character '～' (U+FF5E, a fullwidth '~'); write '~' instead");
+}
+
+#[test]
+fn other_characters_get_the_plain_message() {
+    // outside ASCII, with the code point, since the character may not look like what it is
+    assert_eq!(unexpected("let price = €5;"), ("Unexpected character '€' (U+20AC)".to_string(), 13));
+    assert_eq!(unexpected("let x = 1 @ 2;").0, "Unexpected character '@'");
+    assert_eq!(unexpected("let x = 1 \u{7} 2;").0, "Unexpected character '\\u{7}'");
+    // the same characters in strings and comments are left alone
+    assert!(Lexer::new("// ‘fine’ − … \u{200B}\nlet t = \"“ok” ＝ €\";").tokenize().is_ok());
+}
+
+#[test]
+fn no_break_spaces_are_read_as_spaces_with_a_warning() {
+    let source = "let\u{A0}x = 1;\nlet y\u{202F}= \"a\u{A0}b\"; // \u{A0}";
+    let mut lexer = Lexer::new(source);
+    assert!(lexer.tokenize().is_ok());
+    let warnings: Vec<(&str, &str, usize, usize)> = lexer
+        .warnings()
+        .iter()
+        .map(|warning| (warning.code.id, warning.message.as_str(), warning.span.line, warning.span.column))
+        .collect();
+    assert_eq!(
+        warnings,
+        [
+            ("W0009", "Found U+00A0, a no-break space, read as an ordinary space", 1, 4),
+            ("W0009", "Found U+202F, a no-break space, read as an ordinary space", 2, 6),
+        ]
+    );
+    // the checker reports them with a fix
+    let spaces: Vec<_> = check_source(source)
+        .into_iter()
+        .filter(|diagnostic| diagnostic.code.id == "W0009")
+        .map(|diagnostic| (diagnostic.severity, diagnostic.span.line, diagnostic.suggestion))
+        .collect();
+    let fix = Some("replace it with an ordinary space".to_string());
+    assert_eq!(spaces, [(Severity::Warning, 1, fix.clone()), (Severity::Warning, 2, fix)]);
+}
+
+#[test]
+fn no_break_spaces_can_be_rejected() {
+    let config = LexerConfig { reject_no_break_spaces: true, ..Default::default() };
+    let error = Lexer::new("let\u{A0}x = 1;").with_config(config).tokenize().unwrap_err();
+    assert_eq!(
+        error.message,
+        "Unexpected character '\\u{a0}' (U+00A0 NO-BREAK SPACE); write an ordinary space instead"
+    );
+    assert_eq!(error.span.column, 4);
+    // but not in a string
+    assert!(Lexer::new("let s = \"a\u{A0}b\";").with_config(config).tokenize().is_ok());
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use common::{Captured, interpreter};
+use slimescript::{Backend, SourceFile};
+
+#[test]
+fn an_untaken_branch_is_reported_uncovered() {
+    let source = "\
+// rejects big numbers
+function check(n) {
+    if n > 10 {
+        print(\"too big\");
+        return false;
+    }
+    return true;
+}
+
+let ok = check(3);
+";
+    for backend in [Backend::TreeWalker, Backend::Vm] {
+        let mut interpreter = interpreter(&Captured::default()).with_backend(backend).with_coverage(true);
+        interpreter.eval_file(SourceFile::new("main.slime", source)).unwrap();
+        let files = interpreter.coverage().expect("coverage is on");
+        assert_eq!(files.len(), 1);
+        // comments, blank lines, and braces aren't counted
+        assert_eq!(files[0].lines, [(2, true), (3, true), (4, false), (5, false), (7, true), (10, true)], "{:?}", backend);
+        assert_eq!(files[0].covered(), 4);
+    }
+    assert_eq!(interpreter(&Captured::default()).coverage(), None);
+}
+
+#[test]
+fn imported_files_are_covered_too() {
+    let directory = std::env::temp_dir().join(format!("slimescript-coverage-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    fs::write(directory.join("lib.slime"), "function used() {\n    return 1;\n}\nfunction unused() {\n    return 2;\n}").unwrap();
+    let source = "import \"lib.slime\";\nprint(used());";
+
+    let output = Captured::default();
+    let mut interpreter = interpreter(&output).with_script_path(directory.join("main.slime")).with_coverage(true);
+    interpreter.run(source).unwrap();
+    let files = interpreter.coverage().unwrap();
+    let name = |file: &str| Path::new(file).file_name().unwrap().to_str().unwrap().to_string();
+    let lines: Vec<_> = files.iter().map(|file| (name(&file.name), file.lines.clone())).collect();
+    assert_eq!(
+        lines,
+        [
+            ("main.slime".to_string(), vec![(1, true), (2, true)]),
+            ("lib.slime".to_string(), vec![(1, true), (2, true), (4, true), (5, false)]),
+        ]
+    );
+    fs::remove_dir_all(&directory).unwrap();
+}
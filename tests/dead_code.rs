@@ -0,0 +1,106 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use common::{Captured, interpreter, on_big_stack, run};
+use slimescript::optimizer::{eliminate_dead_code, fold_constants};
+use slimescript::parse;
+use slimescript::printer::print_ast;
+
+const DEAD: &str = r#"
+function f(x) {
+    return x;
+    print("after return");
+    let kept = 1;
+}
+if 1 > 2 { print("never"); } else { print("always"); }
+while false { print("loop"); }
+for i in 0..3 {
+    if i == 1 { break; print("after break"); }
+    continue;
+    print("after continue");
+}
+print(f(2));
+"#;
+
+fn eliminated(source: &str) -> String {
+    let mut program = parse(source).unwrap();
+    fold_constants(&mut program);
+    eliminate_dead_code(&mut program);
+    print_ast(&program, false)
+}
+
+#[test]
+fn code_that_cant_run_is_removed_but_declarations_are_kept() {
+    let expected = "\
+Function name=f
+  params:
+    Parameter name=x
+  body:
+    Return
+      value: Variable name=x
+    Let name=kept
+      initializer: Int value=1
+Block
+  body:
+    Expression
+      expression: Call
+        callee: Variable name=print
+        arguments:
+          String value=\"always\"
+ForIn variable=i
+  iterable: Range inclusive=false
+    start: Int value=0
+    end: Int value=3
+  body:
+    If
+      condition: Binary operator===
+        left: Variable name=i
+        right: Int value=1
+      then:
+        Break
+    Continue
+Expression
+  expression: Call
+    callee: Variable name=print
+    arguments:
+      Call
+        callee: Variable name=f
+        arguments:
+          Int value=2
+";
+    assert_eq!(eliminated(DEAD), expected);
+    assert_eq!(run(DEAD), "always\n2\n");
+}
+
+#[test]
+fn eliminating_twice_changes_nothing() {
+    let mut program = parse(DEAD).unwrap();
+    fold_constants(&mut program);
+    eliminate_dead_code(&mut program);
+    let once = print_ast(&program, true);
+    eliminate_dead_code(&mut program);
+    assert_eq!(print_ast(&program, true), once);
+}
+
+#[test]
+fn the_programs_in_the_repository_print_the_same_without_their_dead_code() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut paths = vec![root.join("code.slime")];
+    paths.extend(fs::read_dir(root.join("benches")).unwrap().map(|entry| entry.unwrap().path()));
+    paths.retain(|path| path.extension().is_some_and(|extension| extension == "slime"));
+    assert!(paths.len() > 1);
+    for path in paths {
+        let source = fs::read_to_string(&path).unwrap();
+        // constant folding is what turns the pass on
+        let printed = |optimize| {
+            on_big_stack(|| {
+                let output = Captured::default();
+                let result = interpreter(&output).with_constant_folding(optimize).run(&source);
+                (output.text(), result.map_err(|error| error.to_string()))
+            })
+        };
+        assert_eq!(printed(true), printed(false), "{}", path.display());
+    }
+}
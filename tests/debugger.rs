@@ -0,0 +1,96 @@
+#![cfg(feature = "debugger")]
+mod common;
+
+use common::{Captured, interpreter};
+use slimescript::debugger::{Debugger, Event, PauseReason, Paused};
+use slimescript::{Backend, SourceFile, Value};
+
+const LOOP: &str = "\
+let total = 0;
+for i in 0..5 {
+    let doubled = i * 2;
+    total = total + doubled;
+}
+print(total);
+";
+
+/// Where `event` says the program paused, as its file, line, and why.
+fn stop(event: Event) -> (String, usize, PauseReason) {
+    match event {
+        Event::Paused(Paused { file, span, reason, .. }) => (file, span.line, reason),
+        Event::Finished(result) => panic!("the program finished with {:?}", result),
+    }
+}
+
+#[test]
+fn a_session_breaks_in_a_loop_inspects_it_and_steps_on() {
+    for backend in [Backend::TreeWalker, Backend::Vm] {
+        let output = Captured::default();
+        let mut debugger = Debugger::new(interpreter(&output).with_backend(backend));
+        debugger.set_breakpoint("main.slime", 3);
+
+        let breakpoint = ("main.slime".to_string(), 3, PauseReason::Breakpoint);
+        assert_eq!(stop(debugger.run(SourceFile::new("main.slime", LOOP))), breakpoint);
+        assert_eq!(debugger.inspect("i"), Some(Value::Number(0.0)), "{:?}", backend);
+        assert_eq!(stop(debugger.continue_()), breakpoint);
+        assert_eq!(stop(debugger.continue_()), breakpoint);
+        // the third time round
+        assert_eq!(debugger.inspect("i"), Some(Value::Number(2.0)));
+        assert_eq!(debugger.inspect("total"), Some(Value::Number(2.0)));
+        assert_eq!(debugger.inspect("nope"), None);
+        assert_eq!(debugger.locals(), [("i".to_string(), Value::Number(2.0))]);
+        assert!(debugger.is_paused() && debugger.interpreter().is_none());
+
+        assert_eq!(stop(debugger.step_over()), ("main.slime".to_string(), 4, PauseReason::Step));
+        assert_eq!(
+            debugger.locals(),
+            [("doubled".to_string(), Value::Number(4.0)), ("i".to_string(), Value::Number(2.0))]
+        );
+        // nothing has been printed while the program was paused
+        assert_eq!(output.text(), "");
+
+        assert!(debugger.clear_breakpoint("main.slime", 3));
+        match debugger.continue_() {
+            Event::Finished(result) => assert_eq!(result.unwrap(), Value::Null),
+            event => panic!("expected the program to finish, not {:?}", event),
+        }
+        assert_eq!(output.text(), "20\n");
+        assert!(!debugger.is_paused());
+        assert_eq!(debugger.inspect("total"), Some(Value::Number(20.0)));
+        assert_eq!(debugger.locals(), []);
+    }
+}
+
+#[test]
+fn steps_go_into_and_out_of_calls() {
+    let source = "\
+function twice(n) {
+    let result = n * 2;
+    return result;
+}
+let x = twice(3);
+print(x);
+";
+    let mut debugger = Debugger::new(interpreter(&Captured::default()));
+    debugger.set_breakpoint("main.slime", 5);
+    let paused = match debugger.run(SourceFile::new("main.slime", source)) {
+        Event::Paused(paused) => paused,
+        event => panic!("expected a pause, not {:?}", event),
+    };
+    assert_eq!((paused.line(), paused.depth), (5, 0));
+
+    assert_eq!(stop(debugger.step_into()).1, 2);
+    assert_eq!(debugger.inspect("n"), Some(Value::Number(3.0)));
+    assert_eq!(stop(debugger.step_out()).1, 6);
+    assert_eq!(debugger.inspect("x"), Some(Value::Number(6.0)));
+    assert!(matches!(debugger.step_over(), Event::Finished(Ok(_))));
+    assert!(debugger.into_interpreter().is_some());
+}
+
+#[test]
+fn dropping_a_paused_debugger_drops_its_program() {
+    let mut debugger = Debugger::new(interpreter(&Captured::default()));
+    debugger.set_breakpoint("main.slime", 1);
+    assert!(matches!(debugger.run(SourceFile::new("main.slime", "let x = [1];\n")), Event::Paused(_)));
+    assert!(debugger.into_interpreter().is_none());
+}
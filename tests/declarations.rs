@@ -0,0 +1,89 @@
+mod common;
+
+use common::{error, interpreter, run, Captured};
+use slimescript::checker::{check_source, Severity};
+use slimescript::{Arity, Value, reserved_words};
+
+#[test]
+fn const_cannot_be_assigned() {
+    let report = error("const x = 1;\nx = 2;");
+    assert!(report.message.contains("constant 'x'"), "{}", report.message);
+    assert_eq!((report.span.line, report.span.column), (2, 1));
+}
+
+#[test]
+fn redeclaring_in_the_same_scope_fails_with_both_positions() {
+    let report = error("let x = 1;\nlet x = 2;");
+    assert_eq!(
+        report.message,
+        "'x' is already declared in this scope (previous declaration at line 1, column 1)"
+    );
+    assert_eq!((report.span.line, report.span.column), (2, 1));
+}
+
+#[test]
+fn inner_scopes_can_shadow() {
+    assert_eq!(run("let x = 1; { let x = 2; print(x); } print(x);"), "2\n1\n");
+}
+
+#[test]
+fn programs_can_declare_names_builtins_have() {
+    let source = "
+        let max = 5;
+        const keys = 1;
+        function copy(x) { return \"mine\"; }
+        class now {}
+        print(max, keys, copy([1]), typeof now, min(1, 2));
+    ";
+    assert_eq!(run(source), "5 1 mine class 1\n");
+    let errors: Vec<_> = check_source(source)
+        .into_iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .collect();
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+#[test]
+fn programs_can_shadow_host_functions() {
+    let output = Captured::default();
+    let mut interpreter = interpreter(&output);
+    interpreter.register_fn("answer", Arity::Exact(0), |_| Ok(Value::Number(42.0)));
+    interpreter.run("print(answer());").unwrap();
+    interpreter.run("let answer = 7; print(answer);").unwrap();
+    assert_eq!(output.text(), "42\n7\n");
+    assert!(interpreter.get_global("answer") == Some(Value::Number(7.0)));
+    assert!(interpreter.get_global("len").is_some());
+}
+
+#[test]
+fn keywords_cant_be_declared_as_names() {
+    let cases = [
+        ("let if = 3;", "'if' is a reserved keyword and cannot be used as a variable name", 5),
+        ("const while = 1;", "'while' is a reserved keyword and cannot be used as a variable name", 7),
+        ("let [a, true] = [1, 2];", "'true' is a reserved keyword and cannot be used as a variable name", 9),
+        ("function return() {}", "'return' is a reserved keyword and cannot be used as a function name", 10),
+        ("function match() {}", "'match' is a reserved keyword and cannot be used as a function name", 10),
+        ("function f(a, class) {}", "'class' is a reserved keyword and cannot be used as a parameter name", 15),
+        ("function f(...null) {}", "'null' is a reserved keyword and cannot be used as a parameter name", 15),
+        ("class for {}", "'for' is a reserved keyword and cannot be used as a class name", 7),
+        ("try {} catch (let) {}", "'let' is a reserved keyword and cannot be used as a variable name", 15),
+    ];
+    for (source, message, column) in cases {
+        let report = error(source);
+        assert_eq!((report.code.id, report.message.as_str()), ("E0107", message), "{}", source);
+        assert_eq!((report.span.line, report.span.column), (1, column), "{}", source);
+    }
+    // a keyword inside a name, or in a string, is fine
+    assert_eq!(run("let iff = \"if\"; function fortune(classy) { return classy; } print(fortune(iff));"), "if\n");
+}
+
+#[test]
+fn every_reserved_word_is_rejected_as_a_name() {
+    let words = reserved_words();
+    for word in ["if", "let", "function", "true", "null", "match", "import"] {
+        assert!(words.contains(&word), "{} is missing", word);
+    }
+    for word in words {
+        assert_eq!(error(&format!("let {} = 1;", word)).code.id, "E0107", "{}", word);
+    }
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use common::{error, run};
+
+#[test]
+fn arrays_and_maps_bind_their_parts() {
+    let source = r#"
+        let [a, b] = [1, 2];
+        let { x, y } = { "x": 3, "y": 4, "z": 5 };
+        let { "first name": first, id: number } = { "first name": "ada", "id": 7 };
+        print(a, b, x, y, first, number);
+    "#;
+    assert_eq!(run(source), "1 2 3 4 ada 7\n");
+}
+
+#[test]
+fn patterns_nest() {
+    let source = r#"
+        let [[a], b] = [[1], 2];
+        let { point: [px, py], tags: { main } } = { "point": [3, 4], "tags": { "main": "m" } };
+        print(a, b, px, py, main);
+    "#;
+    assert_eq!(run(source), "1 2 3 4 m\n");
+}
+
+#[test]
+fn the_rest_element_takes_what_is_left() {
+    let source = r#"
+        let [head, ...tail] = [1, 2, 3];
+        print(head, tail);
+        let [only, ...none] = [1];
+        print(only, none);
+        let [...all] = [];
+        print(all);
+    "#;
+    assert_eq!(run(source), "1 [2, 3]\n1 []\n[]\n");
+}
+
+#[test]
+fn extra_items_without_a_rest_element_are_ignored() {
+    assert_eq!(run("let [a] = [1, 2, 3]; print(a);"), "1\n");
+}
+
+#[test]
+fn the_right_side_is_evaluated_once() {
+    let source = r#"
+        let calls = 0;
+        function pair() { calls = calls + 1; return [1, 2]; }
+        let [a, b] = pair();
+        print(calls);
+    "#;
+    assert_eq!(run(source), "1\n");
+}
+
+#[test]
+fn too_short_arrays_are_errors() {
+    let report = error("let [a, b, c] = [1, 2];");
+    assert_eq!(report.code.id, "E0503");
+    assert_eq!(report.message, "Cannot destructure array of length 2 into 3 elements");
+    let report = error("let [[a, b]] = [[1]];");
+    assert_eq!(report.message, "Cannot destructure array of length 1 into 2 elements");
+}
+
+#[test]
+fn wrong_types_and_missing_keys_are_errors() {
+    let report = error("let [a] = { \"a\": 1 };");
+    assert_eq!(report.code.id, "E0505");
+    assert_eq!(report.message, "Cannot destructure map as an array");
+    let report = error("let { a } = [1];");
+    assert_eq!(report.message, "Cannot destructure array as a map");
+    let report = error("let { a, b } = { \"a\": 1 };");
+    assert_eq!(report.code.id, "E0504");
+    assert_eq!(report.message, "Cannot destructure missing key 'b'");
+}
+
+#[test]
+fn const_patterns_bind_constants() {
+    assert_eq!(error("const [a, b] = [1, 2]; b = 3;").code.id, "E0514");
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use std::ffi::OsStr;
+use std::fs;
+use std::process::Command;
+
+use common::{Captured, assert_snapshot, interpreter};
+use slimescript::checker::check_source;
+use slimescript::parse;
+use slimescript::report::color_wanted;
+
+/// Every error `source` has, from checking it or else from running it,
+/// rendered without color as the command line would for `script.slime`.
+fn rendered(source: &str) -> String {
+    let reports: Vec<_> = match parse(source) {
+        Err(error) => vec![error.report()],
+        Ok(_) => {
+            let diagnostics = check_source(source);
+            if diagnostics.is_empty() {
+                let error = interpreter(&Captured::default()).run(source).expect_err("the source fails");
+                vec![error.report()]
+            } else {
+                diagnostics.iter().map(|diagnostic| diagnostic.report()).collect()
+            }
+        }
+    };
+    reports.iter().map(|report| report.render(source, "script.slime", false)).collect()
+}
+
+#[test]
+fn rendered_diagnostics_snapshot() {
+    let cases = [
+        ("an unexpected character", "let x = 1 @ 2;"),
+        ("an unterminated string, pointing at its start", "print(1);\nlet s = \"abc;\nprint(s);"),
+        ("a parse error", "let = 5;"),
+        ("tabs before the span", "if true {\n\tlet\tx = 1 @ 2;\n}"),
+        ("a span over several lines", "let total = len(\n    [1, 2],\n    [3]\n);\nprint(total);"),
+        ("a runtime error with a trace", "function first(xs) {\n    return xs[0];\n}\nprint(first([]));"),
+        ("a warning with help", "let unused = 1;\nif unused = 2 { print(unused); }"),
+        ("lines numbered past 9", &format!("{}[][3];", "print(1);\n".repeat(11))),
+    ];
+    let mut snapshot = String::new();
+    for (name, source) in cases {
+        snapshot.push_str(&format!("== {} ==\n{}\n", name, rendered(source)));
+    }
+    assert_snapshot("diagnostics.txt", &snapshot);
+}
+
+#[test]
+fn color_is_only_added_when_asked_for() {
+    let source = "let x = 1 @ 2;";
+    let report = parse(source).unwrap_err().report();
+    let plain = report.render(source, "script.slime", false);
+    assert!(!plain.contains('\x1b'), "{:?}", plain);
+    let colored = report.render(source, "script.slime", true);
+    assert!(colored.starts_with("\x1b[1;31mLexer error[E0001]\x1b[0m: Unexpected character '@'\n"), "{:?}", colored);
+    assert!(colored.contains("\x1b[1;31m^\x1b[0m"), "{:?}", colored);
+    // without the escape codes it's the same
+    assert_eq!(colored.replace("\x1b[1;31m", "").replace("\x1b[0m", ""), plain);
+
+    let source = "let unused = 1;\nif unused = 2 { print(unused); }";
+    let warning = check_source(source)[0].report().render(source, "script.slime", true);
+    assert!(warning.starts_with("\x1b[1;33mWarning[W0005]\x1b[0m"), "{:?}", warning);
+}
+
+#[test]
+fn no_color_and_pipes_turn_color_off() {
+    assert!(color_wanted(true, None));
+    assert!(color_wanted(true, Some(OsStr::new(""))));
+    assert!(!color_wanted(true, Some(OsStr::new("1"))));
+    assert!(!color_wanted(false, None));
+}
+
+#[test]
+fn the_command_line_writes_no_escape_codes_to_a_pipe() {
+    let path = std::env::temp_dir().join(format!("slimescript-color-{}.slime", std::process::id()));
+    fs::write(&path, "let x = 1 @ 2;").unwrap();
+    for (arguments, no_color) in [(&[][..], None), (&["--no-color"][..], None), (&[][..], Some("1"))] {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_slimescript"));
+        command.args(arguments).arg(&path);
+        if let Some(value) = no_color {
+            command.env("NO_COLOR", value);
+        }
+        let output = command.output().unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.starts_with("Lexer error[E0001]: Unexpected character '@'\n"), "{:?}", stderr);
+        assert!(!stderr.contains('\x1b'), "{:?} wrote {:?}", arguments, stderr);
+    }
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use common::assert_snapshot;
+use slimescript::compiler::compile;
+use slimescript::disassembler::disassemble;
+use slimescript::parse;
+
+const PROGRAM: &str = r#"let day = 60 * 60 * 24;
+function f(x, y = 2) {
+    if x > y && !x { return -x; } else { return x + y; }
+}
+class Point {
+    init(x) { this.x = x; }
+    norm() { return abs(this.x); }
+}
+let total = 0;
+for i in 0..3 {
+    if i == 1 { continue; }
+    total += f(i);
+}
+while total > 0 { total = total - 1; break; }
+try { throw "oops"; } catch (e) { print(e, [1, "a"], { "k": null }); }
+"#;
+
+#[test]
+fn disassembly_snapshot() {
+    let chunk = compile(&parse(PROGRAM).unwrap());
+    assert_snapshot("disassembly.txt", &disassemble(&chunk));
+}
+
+#[test]
+fn disassembling_is_deterministic() {
+    let chunk = compile(&parse(PROGRAM).unwrap());
+    assert_eq!(disassemble(&chunk), disassemble(&compile(&parse(PROGRAM).unwrap())));
+}
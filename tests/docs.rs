@@ -0,0 +1,96 @@
+mod common;
+
+use common::{assert_snapshot, error, run};
+use slimescript::doc::markdown;
+use slimescript::parse;
+
+#[test]
+fn help_shows_the_signature_and_the_doc_comment() {
+    let source = r#"
+        /// Adds two numbers.
+        /// The second defaults to one.
+        function add(a: number, b = 1): number { return a + b; }
+        function bare(...rest) {}
+        help(add);
+        help(bare);
+        class Stack {
+            /// Puts `item` on top.
+            push(item) {}
+        }
+        help(Stack().push);
+        help(len);
+        help(print);
+        help(random);
+    "#;
+    assert_eq!(
+        run(source),
+        "add(a: number, b = 1): number\nAdds two numbers.\nThe second defaults to one.\n\
+         bare(...rest)\n\
+         push(item)\nPuts `item` on top.\n\
+         len, a builtin taking 1 argument\n\
+         print, a builtin taking any number of arguments\n\
+         random, a builtin taking 0 to 2 arguments\n"
+    );
+    assert_eq!(error("help(1);").message, "help() expects a function, got number");
+}
+
+#[test]
+fn only_comments_right_above_a_function_are_its_doc() {
+    let source = r#"
+        /// attached
+        function close() {}
+
+        /// a blank line in between
+
+        function apart() {}
+
+        /// an ordinary comment in between
+        // like this one
+        function interrupted() {}
+
+        //// four slashes are an ordinary comment
+        function slashes() {}
+
+        /// the first paragraph is dropped
+
+        /// only the run just above is kept
+        function runs() {}
+
+        /// not a function
+        let x = 1;
+        function after_a_statement() {}
+        for f in [close, apart, interrupted, slashes, runs, after_a_statement] { help(f); }
+    "#;
+    assert_eq!(
+        run(source),
+        "close()\nattached\napart()\ninterrupted()\nslashes()\nruns()\nonly the run just above is kept\n\
+         after_a_statement()\n"
+    );
+}
+
+#[test]
+fn the_markdown_page_lists_each_top_level_function() {
+    let source = r#"/// Tools for working with text.
+let separator = ", ";
+
+/// Joins `words` with the separator,
+/// skipping empty ones.
+export function join_words(words: array, ...more): string {
+    return join(filter(words, function(w) { return w != ""; }), separator);
+}
+
+function undocumented(x = [1, 2]) {}
+
+class Helper {
+    /// methods aren't listed
+    help() {}
+}
+
+/// Shouts.
+function shout(s) {
+    function inner() {}
+    return upper(s) + "!";
+}
+"#;
+    assert_snapshot("docs.md", &markdown("text.slime", &parse(source).unwrap().statements, source));
+}
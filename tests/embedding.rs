@@ -0,0 +1,175 @@
+mod common;
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use common::{Captured, error, interpreter};
+use slimescript::{Arity, Backend, Completion, RuntimeError, Value};
+
+#[test]
+fn globals_and_functions_survive_between_evals() {
+    let output = Captured::default();
+    let mut interpreter = interpreter(&output);
+    assert_eq!(interpreter.eval("let total = 1; function bump(n) { total = total + n; return total; }").unwrap(), Value::Null);
+    assert_eq!(interpreter.eval("bump(2);").unwrap(), Value::Number(3.0));
+    assert_eq!(interpreter.eval("print(total); total * 10;").unwrap(), Value::Number(30.0));
+    assert_eq!(output.text(), "3\n");
+}
+
+#[test]
+fn a_failed_eval_keeps_what_ran_before_the_error() {
+    let mut interpreter = interpreter(&Captured::default());
+    assert!(interpreter.eval("let kept = 1; [][0]; let lost = 2;").is_err());
+    assert_eq!(interpreter.eval("kept;").unwrap(), Value::Number(1.0));
+    assert_eq!(interpreter.get_global("lost"), None);
+}
+
+#[test]
+fn native_functions_can_keep_state_on_the_host() {
+    for backend in [Backend::TreeWalker, Backend::Vm] {
+        let calls = Rc::new(Cell::new(0));
+        let counted = Rc::clone(&calls);
+        let output = Captured::default();
+        let mut interpreter = interpreter(&output).with_backend(backend);
+        interpreter.register_fn("count", Arity::Range(0, 1), move |args| {
+            let step = match args.first() {
+                Some(value) => i64::try_from(value.clone())?,
+                None => 1,
+            };
+            counted.set(counted.get() + step);
+            Ok(Value::from(counted.get() as f64))
+        });
+        interpreter.run("for i in 0..3 { count(); } print(count(10));").unwrap();
+        assert_eq!(calls.get(), 13);
+        assert_eq!(output.text(), "13\n");
+    }
+}
+
+#[test]
+fn native_functions_show_in_stack_traces_and_check_their_arity() {
+    let mut interpreter = interpreter(&Captured::default());
+    interpreter.register_fn("fail", Arity::Exact(1), |args| {
+        Err(RuntimeError::new(format!("failed with {}", args[0]), Default::default()))
+    });
+    let source = "function outer() { return fail(\"x\"); }\nouter();";
+    let error = interpreter.run(source).unwrap_err().report();
+    assert_eq!(error.code.id, "E0500");
+    assert_eq!(error.message, "failed with x");
+    // the host's error has no position, so it takes the call's
+    assert_eq!((error.span.line, error.span.column), (1, 27));
+    assert_eq!(error.notes, ["in fail() called at line 1, column 27", "in outer() called at line 2, column 1"]);
+
+    let error = interpreter.run("fail();").unwrap_err().report();
+    assert_eq!(error.message, "fail() expected 1 argument but got 0");
+}
+
+#[test]
+fn functions_outlive_the_program_that_declared_them() {
+    for backend in [Backend::TreeWalker, Backend::Vm] {
+        let mut interpreter = interpreter(&Captured::default()).with_backend(backend);
+        let source = "function adder(n) { return function(x) { return x + n * 2; }; } let add = adder(3);";
+        let program = slimescript::parse(source).unwrap();
+        interpreter.eval_statements(&program).unwrap();
+        // each function keeps its own expressions, not the program's
+        drop(program);
+        assert_eq!(interpreter.call_function("add", &[Value::from(36)]).unwrap(), Value::from(42));
+        assert_eq!(interpreter.eval("adder(1)(1);").unwrap(), Value::from(3));
+    }
+}
+
+#[test]
+fn values_convert_to_and_from_rust_types() {
+    assert_eq!(Value::from(1.5), Value::Number(1.5));
+    assert_eq!(Value::from(true), Value::Bool(true));
+    assert_eq!(Value::from("text"), Value::from("text".to_string()));
+    let array = Value::from(vec![Value::from(1), Value::Null]);
+    assert_eq!(Vec::<Value>::try_from(array).unwrap(), [Value::Number(1.0), Value::Null]);
+
+    assert_eq!(f64::try_from(Value::from(2)).unwrap(), 2.0);
+    assert_eq!(String::try_from(Value::from("s")).unwrap(), "s");
+    assert!(bool::try_from(Value::Bool(false)).is_ok_and(|b| !b));
+    assert_eq!(Option::<f64>::try_from(Value::Null).unwrap(), None);
+    assert_eq!(i64::try_from(Value::from(1.5)).unwrap_err().message, "Expected an integer, got 1.5");
+    assert_eq!(String::try_from(Value::from(1)).unwrap_err().message, "Expected string, got number");
+}
+
+#[test]
+fn scripts_can_be_read_as_config_files() {
+    let mut interpreter = interpreter(&Captured::default());
+    let config = r#"
+        let timeout = 30;
+        let name = "worker";
+        let hosts = ["a", "b"];
+        let retries = null;
+        let limits = { "cpu": 2 };
+    "#;
+    interpreter.run(config).unwrap();
+    let get = |name| interpreter.get_global(name).expect("the script defines it");
+    assert_eq!(i64::try_from(get("timeout")).unwrap(), 30);
+    assert_eq!(String::try_from(get("name")).unwrap(), "worker");
+    assert_eq!(Vec::<Value>::try_from(get("hosts")).unwrap().len(), 2);
+    assert_eq!(Option::<i64>::try_from(get("retries")).unwrap(), None);
+    assert_eq!(get("limits").to_string(), "{cpu: 2}");
+    assert_eq!(interpreter.get_global("missing"), None);
+}
+
+#[test]
+fn the_host_can_call_back_into_scripts() {
+    let output = Captured::default();
+    let mut interpreter = interpreter(&output);
+    interpreter.register_fn("twice", Arity::Exact(1), |args| Ok(Value::from(f64::try_from(args[0].clone())? * 2.0)));
+    let script = r#"
+        let seen = [];
+        function on_event(name) { push(seen, name); return len(seen); }
+        function broken() { return [][0]; }
+    "#;
+    interpreter.run(script).unwrap();
+    assert_eq!(interpreter.call_function("on_event", &[Value::from("clicked")]).unwrap(), Value::from(1));
+    assert_eq!(interpreter.call_function("on_event", &[Value::from("closed")]).unwrap(), Value::from(2));
+    assert_eq!(interpreter.get_global("seen").unwrap().to_string(), "[\"clicked\", \"closed\"]");
+    assert_eq!(interpreter.call_function("twice", &[Value::from(4)]).unwrap(), Value::from(8));
+
+    let error = interpreter.call_function("broken", &[]).unwrap_err();
+    assert_eq!(error.code.id, "E0503");
+    assert_eq!(error.trace_lines(), ["in broken() called from the host"]);
+    let error = interpreter.call_function("on_event", &[]).unwrap_err();
+    assert_eq!(error.message, "on_event() expected 1 argument but got 0");
+    let error = interpreter.call_function("nothing", &[]).unwrap_err();
+    assert_eq!(error.message, "Undefined function 'nothing'");
+}
+
+#[test]
+fn the_host_gives_scripts_their_arguments() {
+    for backend in [Backend::TreeWalker, Backend::Vm] {
+        let output = Captured::default();
+        let mut interpreter = interpreter(&output).with_backend(backend).with_args(vec!["a b".to_string(), "2".to_string()]);
+        interpreter.run("let xs = args(); print(xs, len(xs), type(xs[1]));").unwrap();
+        assert_eq!(output.text(), "[\"a b\", \"2\"] 2 string\n");
+    }
+    assert_eq!(error("args(1);").code.id, "E0513");
+}
+
+#[test]
+fn exit_unwinds_everything_and_leaves_the_code_to_the_host() {
+    let source = "
+        function stop() {
+            try { exit(3); } catch (e) { print(\"caught\"); }
+            print(\"after try\");
+        }
+        for i in 0..3 { print(i); if i == 1 { stop(); } }
+        print(\"after loop\");
+    ";
+    for backend in [Backend::TreeWalker, Backend::Vm] {
+        let output = Captured::default();
+        let mut interpreter = interpreter(&output).with_backend(backend);
+        assert_eq!(interpreter.run(source).unwrap(), Completion::Exit(3), "{:?}", backend);
+        assert_eq!(output.text(), "0\n1\n");
+        // the interpreter is still usable, and a program that ends finishes
+        assert_eq!(interpreter.run("exit();").unwrap(), Completion::Exit(0));
+        assert_eq!(interpreter.run("print(\"again\");").unwrap(), Completion::Finished);
+        assert_eq!(output.text(), "0\n1\nagain\n");
+    }
+    let report = error("exit(256);");
+    assert_eq!(report.code.id, "E0517");
+    assert_eq!(report.message, "exit() expects a whole number from 0 to 255, got 256");
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use common::error;
+use slimescript::lexer::{Lexer, Token, TokenType};
+
+fn tokens(source: &str) -> Vec<Token> {
+    Lexer::new(source).tokenize().unwrap()
+}
+
+#[test]
+fn the_end_of_input_is_just_past_the_last_character() {
+    for (source, line, column) in [
+        ("", 1, 1),
+        ("let x = 1;", 1, 11),
+        ("let x = 1;\n", 2, 1),
+        ("let x = 1;   ", 1, 14),
+        ("let x = 1;\n\n\n", 4, 1),
+        ("let x = 1; // done", 1, 19),
+        ("let x = 1;\r\n// done\r\n", 3, 1),
+        ("\tlet s = \"é\";", 1, 14),
+    ] {
+        let end = tokens(source).pop().unwrap();
+        assert_eq!(end.token_type, TokenType::EOF);
+        assert_eq!((end.span.start, end.span.end), (source.len(), source.len()), "{:?}", source);
+        assert_eq!((end.span.line, end.span.column), (line, column), "{:?}", source);
+    }
+}
+
+#[test]
+fn each_token_knows_where_it_ends() {
+    let tokens = tokens("let text = \"two\nlines\";\nprint(text);");
+    let string = &tokens[3];
+    assert_eq!(string.token_type, TokenType::String);
+    assert_eq!((string.line, string.column), (1, 12));
+    assert_eq!((string.end_line, string.end_column), (2, 7));
+    let end = string.end();
+    assert_eq!((end.start, end.end), (string.span.end, string.span.end));
+    assert_eq!((end.line, end.column), (2, 7));
+}
+
+#[test]
+fn a_missing_token_is_reported_after_the_last_one() {
+    // whatever trails the statement, the ';' belongs after the `1`
+    let sources = ["let x = 1", "let x = 1\n", "let x = 1   ", "let x = 1\n\n\n", "let x = 1 // done\n", "let x = 1\r\n"];
+    for source in sources {
+        let report = error(source);
+        assert_eq!(report.message, "Expected ';' after variable declaration, found end of input", "{:?}", source);
+        assert_eq!((report.span.line, report.span.column), (1, 10), "{:?}", source);
+        assert_eq!((report.span.start, report.span.end), (9, 9), "{:?}", source);
+    }
+    // the last token can end on a later line than it starts
+    let report = error("let s = \"a\nb\"");
+    assert_eq!((report.span.line, report.span.column), (2, 3));
+    // comments before the last token don't matter
+    let report = error("// nothing but a comment\n{");
+    assert_eq!(report.message, "Expected '}' after block, found end of input");
+    assert_eq!((report.span.line, report.span.column), (2, 2));
+}
@@ -0,0 +1,62 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use slimescript::environment::Environment;
+use slimescript::symbol::Symbol;
+use slimescript::{Interpreter, Value};
+
+#[test]
+fn looking_names_up_doesnt_intern_them() {
+    let mut globals = Environment::new();
+    globals.define("declared", Value::Number(1.0));
+    let scope = Environment::with_enclosing(Rc::new(RefCell::new(globals)));
+
+    assert_eq!(scope.get("never_declared_anywhere"), None);
+    assert!(!scope.binds("never_bound_here"));
+    assert_eq!(Interpreter::new().get_global("never_a_global"), None);
+    for name in ["never_declared_anywhere", "never_bound_here", "never_a_global"] {
+        assert_eq!(Symbol::lookup(name), None, "{} was interned", name);
+    }
+}
+
+#[test]
+fn names_are_found_as_text_or_symbols() {
+    let mut globals = Environment::new();
+    globals.define("total", Value::Number(3.0));
+    let scope = Environment::with_enclosing(Rc::new(RefCell::new(globals)));
+    let symbol = Symbol::lookup("total").expect("defining interns the name");
+
+    assert_eq!(scope.get("total"), Some(Value::Number(3.0)));
+    assert_eq!(scope.get(symbol), Some(Value::Number(3.0)));
+    assert!(!scope.binds("total"));
+}
+
+#[test]
+fn assigning_to_a_name_never_declared_fails() {
+    let mut scope = Environment::new();
+    assert!(scope.assign("never_assigned_anywhere", Value::Null).is_err());
+    assert_eq!(Symbol::lookup("never_assigned_anywhere"), None);
+}
+
+#[test]
+fn names_are_freed_with_the_last_program_using_them() {
+    let before = Symbol::interned();
+    {
+        let mut interpreter = Interpreter::new();
+        interpreter.run("let short_lived = 1; function helper(argument) { return argument; }").unwrap();
+        assert!(Symbol::lookup("short_lived").is_some());
+        assert!(Symbol::interned() > before);
+        // the same name is the same symbol while it lives
+        assert_eq!(Symbol::intern("short_lived"), Symbol::lookup("short_lived").unwrap());
+    }
+    for name in ["short_lived", "helper", "argument", "len"] {
+        assert_eq!(Symbol::lookup(name), None, "{} is still interned", name);
+    }
+    assert_eq!(Symbol::interned(), before);
+
+    // names made and dropped one by one don't pile up either
+    for i in 0..10_000 {
+        Symbol::intern(&format!("generated_{}", i));
+    }
+    assert_eq!(Symbol::interned(), before);
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use common::{Captured, error, interpreter, run};
+use slimescript::InterpreterConfig;
+
+#[test]
+fn catch_gets_a_map_describing_a_builtin_error() {
+    let source = r#"
+        let zero = 0;
+        try {
+            print(10 / zero);
+            print("not reached");
+        } catch (e) {
+            print(e.message);
+            print(e.code, e.line, e.column);
+        }
+        print("carries on");
+    "#;
+    assert_eq!(run(source), "Division by zero\nE0502 4 19\ncarries on\n");
+}
+
+#[test]
+fn catch_gets_exactly_what_was_thrown() {
+    let source = r#"
+        function check(record) {
+            if !has(record, "id") { throw { "reason": "no id", "record": record }; }
+            return record.id;
+        }
+        let ids = [];
+        let skipped = [];
+        for record in [{ "id": 1 }, { "name": "x" }, { "id": 3 }] {
+            try { push(ids, check(record)); } catch (e) { push(skipped, e.reason); }
+        }
+        print(ids, skipped);
+        try { throw 42; } catch (e) { print(e + 1); }
+    "#;
+    assert_eq!(run(source), "[1, 3] [\"no id\"]\n43\n");
+}
+
+#[test]
+fn catch_can_rethrow() {
+    let source = r#"
+        try {
+            try { throw "inner"; } catch (e) { print("saw " + e); throw e + " again"; }
+        } catch (e) {
+            print(e);
+        }
+    "#;
+    assert_eq!(run(source), "saw inner\ninner again\n");
+
+    let report = error(r#"try { throw "lost"; } catch (e) { throw e; }"#);
+    assert_eq!(report.code.id, "E0509");
+    assert_eq!(report.message, "lost");
+}
+
+#[test]
+fn return_break_and_continue_pass_through_try() {
+    let source = r#"
+        function first_even(items) {
+            for x in items {
+                try {
+                    if x % 2 == 0 { return x; }
+                } catch (e) {
+                    return "caught";
+                }
+            }
+            return null;
+        }
+        print(first_even([1, 3, 4, 5]));
+
+        for i in 0..5 {
+            try {
+                if i == 1 { continue; }
+                if i == 3 { break; }
+                print(i);
+            } catch (e) { print("caught"); }
+        }
+    "#;
+    assert_eq!(run(source), "4\n0\n2\n");
+}
+
+#[test]
+fn errors_from_called_functions_are_caught() {
+    let source = r#"
+        function deep(n) { if n == 0 { return [][0]; } return deep(n - 1); }
+        try { deep(3); } catch (e) { print(e.code); }
+    "#;
+    assert_eq!(run(source), "E0503\n");
+}
+
+#[test]
+fn running_out_of_budget_cant_be_caught() {
+    let output = Captured::default();
+    let config = InterpreterConfig { max_steps: Some(1000), ..Default::default() };
+    let result = interpreter(&output)
+        .with_config(config)
+        .run("try { while true { } } catch (e) { print(\"caught\"); }");
+    assert_eq!(result.err().map(|error| error.report().code.id), Some("E0507"));
+    assert_eq!(output.text(), "");
+}
@@ -0,0 +1,53 @@
+// Run with `cargo test --features ffi`.
+#![cfg(feature = "ffi")]
+
+use std::ffi::{CStr, c_char};
+use std::ptr;
+use std::thread;
+
+use slimescript::ffi::{
+    SlimeResult, SlimeStatus, slime_eval, slime_free, slime_get_global_number, slime_last_error_message,
+    slime_new, slime_string_free,
+};
+
+fn c(s: &str) -> Vec<c_char> {
+    s.bytes().chain([0]).map(|b| b as c_char).collect()
+}
+
+#[test]
+fn evaluates_and_reads_globals() {
+    let handle = slime_new();
+    let mut result = SlimeResult { status: SlimeStatus::Error, value: ptr::null_mut() };
+    let status = unsafe { slime_eval(handle, c("let answer = 6 * 7; answer;").as_ptr(), &mut result) };
+    assert_eq!(status, SlimeStatus::Ok);
+    assert_eq!(unsafe { CStr::from_ptr(result.value) }.to_str(), Ok("42"));
+    unsafe { slime_string_free(result.value) };
+
+    let mut answer = 0.0;
+    let status = unsafe { slime_get_global_number(handle, c("answer").as_ptr(), &mut answer) };
+    assert_eq!((status, answer), (SlimeStatus::Ok, 42.0));
+    unsafe { slime_free(handle) };
+}
+
+#[test]
+fn interpreters_refuse_calls_from_other_threads() {
+    let handle = slime_new();
+    unsafe { slime_eval(handle, c("missing;").as_ptr(), ptr::null_mut()) };
+    let address = handle as usize;
+    thread::spawn(move || {
+        let handle = address as *mut _;
+        let status = unsafe { slime_eval(handle, c("1;").as_ptr(), ptr::null_mut()) };
+        assert_eq!(status, SlimeStatus::WrongThread);
+        let mut out = 0.0;
+        let status = unsafe { slime_get_global_number(handle, c("x").as_ptr(), &mut out) };
+        assert_eq!(status, SlimeStatus::WrongThread);
+        assert!(unsafe { slime_last_error_message(handle) }.is_null());
+    })
+    .join()
+    .unwrap();
+
+    let message = unsafe { slime_last_error_message(handle) };
+    assert!(!message.is_null());
+    unsafe { slime_string_free(message) };
+    unsafe { slime_free(handle) };
+}
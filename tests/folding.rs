@@ -0,0 +1,86 @@
+mod common;
+
+use common::{outcome, run};
+use slimescript::optimizer::fold_constants;
+use slimescript::parse;
+use slimescript::printer::print_ast;
+
+const PROGRAM: &str = r#"
+let day = 60 * 60 * 24;
+let s = "a" + "b" + str(1);
+print(day, s, !(1 < 2));
+function f(x) { return -(2 + 3) * x; }
+print(f(2));
+print(1 / 0);
+"#;
+
+fn folded(source: &str) -> String {
+    let mut program = parse(source).unwrap();
+    fold_constants(&mut program);
+    print_ast(&program, false)
+}
+
+#[test]
+fn operations_on_literals_become_literals() {
+    let expected = "\
+Let name=day
+  initializer: Int value=86400
+Let name=s
+  initializer: Binary operator=+
+    left: String value=\"ab\"
+    right: Call
+      callee: Variable name=str
+      arguments:
+        Int value=1
+Expression
+  expression: Call
+    callee: Variable name=print
+    arguments:
+      Variable name=day
+      Variable name=s
+      Bool value=false
+Function name=f
+  params:
+    Parameter name=x
+  body:
+    Return
+      value: Binary operator=*
+        left: Int value=-5
+        right: Variable name=x
+Expression
+  expression: Call
+    callee: Variable name=print
+    arguments:
+      Call
+        callee: Variable name=f
+        arguments:
+          Int value=2
+Expression
+  expression: Call
+    callee: Variable name=print
+    arguments:
+      Binary operator=/
+        left: Int value=1
+        right: Int value=0
+";
+    assert_eq!(folded(PROGRAM), expected);
+}
+
+#[test]
+fn folding_twice_changes_nothing() {
+    let mut program = parse(PROGRAM).unwrap();
+    fold_constants(&mut program);
+    let once = print_ast(&program, true);
+    fold_constants(&mut program);
+    assert_eq!(print_ast(&program, true), once);
+}
+
+#[test]
+fn folding_doesnt_change_what_programs_do() {
+    // `outcome` runs the program folded and unfolded, which must agree,
+    // including on the division by zero left for runtime
+    let (output, error) = outcome(PROGRAM);
+    assert_eq!(output, "86400 ab1 false\n-10\n");
+    assert_eq!(error.map(|report| report.code.id), Some("E0502"));
+    assert_eq!(run("print(0.1 + 0.2, \"x\" + \"y\" == \"xy\", -(-0));"), "0.30000000000000004 true 0\n");
+}
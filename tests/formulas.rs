@@ -0,0 +1,52 @@
+use slimescript::{CompiledExpr, Error, Value, eval_with_vars};
+
+/// The code and message of the error evaluating `source` with no
+/// variables fails with.
+fn failure(source: &str) -> (&'static str, String) {
+    let report = eval_with_vars(source, &[]).expect_err(source).report();
+    (report.code.id, report.message)
+}
+
+#[test]
+fn formulas_read_the_variables_they_are_given() {
+    let variables = [("price", 2.5.into()), ("qty", 4.into()), ("tax", 0.5.into())];
+    assert_eq!(eval_with_vars("price * qty * (1 + tax)", &variables).unwrap(), Value::Number(15.0));
+    assert_eq!(eval_with_vars("max(sqrt(qty), floor(price)) + abs(-1)", &variables).unwrap(), Value::Number(3.0));
+    assert_eq!(eval_with_vars("qty > 3 && price < 3", &variables).unwrap(), Value::Bool(true));
+    // a variable hides the builtin of the same name
+    assert_eq!(eval_with_vars("max * 2", &[("max", 5.into())]).unwrap(), Value::Number(10.0));
+}
+
+#[test]
+fn names_that_arent_given_are_undefined() {
+    assert_eq!(failure("price * 2"), ("E0501", "Undefined variable 'price'".to_string()));
+    // only the pure math builtins are there
+    assert_eq!(failure("print(1)"), ("E0501", "Undefined variable 'print'".to_string()));
+}
+
+#[test]
+fn formulas_cant_change_anything() {
+    assert_eq!(failure("x = 1"), ("E0108", "The expression can't assign, only read".to_string()));
+    assert_eq!(failure("1 + (m.k = 2)"), ("E0108", "The expression can't assign, only read".to_string()));
+    assert_eq!(failure("function() { return 1; }"), ("E0108", "The expression can't define a function".to_string()));
+    // and are one expression, not statements
+    assert!(matches!(eval_with_vars("let x = 1;", &[]), Err(Error::Parse(_))));
+    assert!(matches!(eval_with_vars("1; 2", &[]), Err(Error::Parse(_))));
+}
+
+#[test]
+fn a_compiled_formula_is_reused_with_different_variables() {
+    let formula = CompiledExpr::compile("base + rate * hours").unwrap();
+    let cases = [(10, 2.0, 3, 16.0), (0, 1.5, 4, 6.0), (-5, 0.0, 100, -5.0)];
+    for (base, rate, hours, expected) in cases {
+        let variables = [("base", base.into()), ("rate", Value::from(rate)), ("hours", hours.into())];
+        assert_eq!(formula.eval(&variables).unwrap(), Value::Number(expected));
+    }
+    // nothing carries over from one evaluation to the next
+    let error = formula.eval(&[("base", 1.into()), ("rate", 1.into())]).unwrap_err();
+    assert_eq!(error.report().message, "Undefined variable 'hours'");
+    for base in 0..1000 {
+        let variables = [("base", base.into()), ("rate", 0.into()), ("hours", 0.into())];
+        assert_eq!(formula.eval(&variables).unwrap(), Value::Number(base as f64));
+    }
+}
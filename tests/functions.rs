@@ -0,0 +1,144 @@
+mod common;
+
+use common::{error, run};
+
+#[test]
+fn functions_dispatch_from_maps_and_arrays() {
+    let source = r#"
+        function add(a, b) { return a + b; }
+        function sub(a, b) { return a - b; }
+        let ops = { "add": add, "sub": sub };
+        print(ops["add"](1, 2));
+        print(ops.sub(5, 3));
+        let table = [add, sub, function(a, b) { return a * b; }];
+        for op in table { print(op(6, 3)); }
+        print(table[len(table) - 1](2, 4));
+    "#;
+    assert_eq!(run(source), "3\n2\n9\n3\n18\n8\n");
+}
+
+#[test]
+fn functions_are_passed_and_returned() {
+    let source = r#"
+        function adder(n) { return function(x) { return x + n; }; }
+        print(adder(2)(3));
+        function twice(f, x) { return f(f(x)); }
+        print(twice(adder(10), 1));
+        print(map([1, 2, 3], adder(1)));
+        let pick = function(which) { return [len, str][which]; };
+        print(pick(0)("four"));
+        print(adder(1)(1) + adder(2)(2));
+    "#;
+    assert_eq!(run(source), "5\n21\n[2, 3, 4]\n4\n6\n");
+}
+
+#[test]
+fn functions_print_with_their_names() {
+    let source = r#"
+        function add(a, b) { return a + b; }
+        print(add);
+        print({ "op": add });
+        print(function(x) { return x; });
+        print(len);
+    "#;
+    assert_eq!(
+        run(source),
+        "<function add>\n{op: <function add>}\n<function anonymous>\n<builtin len>\n"
+    );
+}
+
+#[test]
+fn functions_are_equal_only_to_themselves() {
+    let source = r#"
+        function add(a, b) { return a + b; }
+        function same(a, b) { return a + b; }
+        let alias = add;
+        print(add == alias);
+        print(add == same);
+        function make() { return function() { return 1; }; }
+        print(make() == make());
+        let made = make();
+        print(made == made);
+        print(len == len);
+        print([add] == [alias]);
+    "#;
+    assert_eq!(run(source), "true\nfalse\nfalse\ntrue\ntrue\ntrue\n");
+}
+
+#[test]
+fn defaults_fill_in_omitted_arguments() {
+    let source = r#"
+        function greet(name, greeting = "Hello") { return str(greeting) + ", " + name; }
+        print(greet("ada"));
+        print(greet("ada", "Hi"));
+        print(greet("ada", null));
+    "#;
+    // a null passed on purpose is an argument, not an omission
+    assert_eq!(run(source), "Hello, ada\nHi, ada\nnull, ada\n");
+}
+
+#[test]
+fn defaults_are_evaluated_at_each_call_and_see_earlier_parameters() {
+    let source = r#"
+        function box(width, height = width, label = str(width) + "x" + str(height)) {
+            return label;
+        }
+        print(box(2), box(2, 3), box(2, 3, "custom"));
+        function fresh(items = []) { push(items, 1); return len(items); }
+        print(fresh(), fresh());
+    "#;
+    assert_eq!(run(source), "2x2 2x3 custom\n1 1\n");
+}
+
+#[test]
+fn rest_parameters_collect_extra_arguments() {
+    let source = r#"
+        function sum(...nums) { return reduce(nums, function(a, b) { return a + b; }, 0); }
+        print(sum(), sum(1), sum(1, 2, 3, 4));
+        function tag(name, ...rest) { return [name, rest]; }
+        print(tag("a"), tag("a", 1, 2));
+        function both(a, b = 2, ...rest) { return [a, b, rest]; }
+        print(both(1), both(1, 5, 6, 7));
+    "#;
+    assert_eq!(run(source), "0 1 10\n[\"a\", []] [\"a\", [1, 2]]\n[1, 2, []] [1, 5, [6, 7]]\n");
+}
+
+#[test]
+fn arity_errors_give_the_accepted_range() {
+    let source = "function greet(name, greeting = \"Hello\") { return name; }";
+    let report = error(&format!("{} greet();", source));
+    assert_eq!(report.code.id, "E0513");
+    assert_eq!(report.message, "greet() expected 1 to 2 arguments but got 0");
+    let report = error(&format!("{} greet(1, 2, 3);", source));
+    assert_eq!(report.message, "greet() expected 1 to 2 arguments but got 3");
+    let report = error("function tag(name, ...rest) { return name; } tag();");
+    assert_eq!(report.message, "tag() expected at least 1 argument but got 0");
+}
+
+#[test]
+fn tail_calls_dont_nest() {
+    let source = r#"
+        function is_even(n) { if n == 0 { return true; } return is_odd(n - 1); }
+        function is_odd(n) { if n == 0 { return false; } return is_even(n - 1); }
+        print(is_even(100000), is_odd(100001), is_even(7));
+        function go(i, acc) { if i == 0 { return acc; } return go(i - 1, acc + i); }
+        print(go(200000, 0));
+    "#;
+    assert_eq!(run(source), "true true false\n20000100000\n");
+}
+
+#[test]
+fn tail_calls_leave_only_the_latest_frame_in_traces() {
+    let source = "function down(n) {\n    if n == 0 { return [][0]; }\n    return down(n - 1);\n}\nfunction start() { return 1 + down(5); }\nstart();";
+    let report = error(source);
+    assert_eq!(report.code.id, "E0503");
+    assert_eq!(
+        report.notes,
+        // the calls `down` made to itself in between are gone
+        [
+            "in down() called at line 3, column 12",
+            "in down() called at line 5, column 31",
+            "in start() called at line 6, column 1"
+        ]
+    );
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use std::fs;
+
+use common::{Captured, interpreter};
+use slimescript::lexer::{Lexer, LexerConfig, TokenType, tokens_to_source};
+use slimescript::{Error, InterpreterConfig};
+
+fn hashes(allowed: bool) -> LexerConfig {
+    LexerConfig { hash_comments: allowed, ..Default::default() }
+}
+
+/// What `source` prints when `#` comments are `allowed`, or the message of
+/// the error that stopped it.
+fn run(allowed: bool, source: &str) -> Result<String, String> {
+    let output = Captured::default();
+    let config = InterpreterConfig { lexer: hashes(allowed), ..Default::default() };
+    match interpreter(&output).with_config(config).run(source) {
+        Ok(_) => Ok(output.text()),
+        Err(error) => Err(error.report().message),
+    }
+}
+
+#[test]
+fn hash_comments_are_skipped_when_allowed() {
+    let source = "# settings\nlet retries = 3; # per host\n// the usual kind still works\nprint(retries);#\n";
+    assert_eq!(run(true, source), Ok("3\n".to_string()));
+    // a `#` in a string is text either way
+    let source = "print(\"# not a comment\", \"a#b\");";
+    assert_eq!(run(true, source), Ok("# not a comment a#b\n".to_string()));
+    assert_eq!(run(false, source), Ok("# not a comment a#b\n".to_string()));
+}
+
+#[test]
+fn a_hash_is_an_error_by_default() {
+    let error = Lexer::new("let x = 1;\nlet y = 2; # two").tokenize().unwrap_err();
+    assert_eq!(error.code.id, "E0001");
+    assert_eq!(error.message, "Unexpected character '#'; line comments start with '//'");
+    assert_eq!((error.span.line, error.span.column), (2, 12));
+    assert_eq!(run(false, "# settings\nprint(1);"), Err(error.message.clone()));
+    assert_eq!(LexerConfig::default(), hashes(false));
+}
+
+#[test]
+fn a_shebang_needs_no_option() {
+    let source = "#!/usr/bin/env slimescript\nprint(1);";
+    assert_eq!(run(false, source), Ok("1\n".to_string()));
+    // only as the very first line
+    assert!(run(false, "print(1);\n#!/usr/bin/env slimescript").is_err());
+    assert!(run(false, " #!/usr/bin/env slimescript\nprint(1);").is_err());
+    assert_eq!(run(true, "print(1);\n#!/not/a/shebang"), Ok("1\n".to_string()));
+}
+
+#[test]
+fn kept_comments_include_hash_comments() {
+    let source = "#!/bin/slime\n# top\nlet x = 1; # after\n";
+    let tokens = Lexer::with_comments(source).with_config(hashes(true)).tokenize().unwrap();
+    let comments: Vec<&str> = tokens
+        .iter()
+        .filter(|token| token.token_type == TokenType::Comment)
+        .map(|token| token.value.as_str())
+        .collect();
+    assert_eq!(comments, ["#!/bin/slime", "# top", "# after"]);
+    let tokens = Lexer::with_trivia(source).with_config(hashes(true)).tokenize().unwrap();
+    assert_eq!(tokens_to_source(&tokens), source);
+    // and like any other plain comment, one splits a doc comment from its function
+    let tokens = Lexer::new("/// doc\n# note\nfunction f() {}").with_config(hashes(true)).tokenize().unwrap();
+    assert_eq!(tokens[0].doc, None);
+}
+
+#[test]
+fn imports_are_lexed_the_same_way() {
+    let directory = std::env::temp_dir().join(format!("slimescript-hash-comments-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    fs::write(directory.join("settings.slime"), "# shared\nexport let retries = 3;\n").unwrap();
+    let main = directory.join("main.slime");
+    let source = "import \"settings.slime\" as settings;\nprint(settings.retries);";
+    let output = Captured::default();
+    let config = InterpreterConfig { lexer: hashes(true), ..Default::default() };
+    interpreter(&output).with_config(config).with_script_path(&main).run(source).unwrap();
+    assert_eq!(output.text(), "3\n");
+    let error = interpreter(&Captured::default()).with_script_path(&main).run(source).unwrap_err();
+    assert!(matches!(error, Error::Lex(_)), "{:?}", error);
+    fs::remove_dir_all(&directory).unwrap();
+}
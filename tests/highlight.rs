@@ -0,0 +1,64 @@
+use slimescript::highlight::{HighlightKind, highlight, highlight_html};
+
+/// Each span of `source` as its text and kind, leaving out whitespace
+/// after checking that the spans cover every byte once, in order.
+fn classified(source: &str) -> Vec<(&str, HighlightKind)> {
+    let spans = highlight(source);
+    let mut position = 0;
+    for span in &spans {
+        assert_eq!(span.start, position, "{:?} leaves a gap or overlaps", span);
+        position = span.end;
+    }
+    assert_eq!(position, source.len());
+    spans
+        .into_iter()
+        .filter(|span| span.kind != HighlightKind::Whitespace)
+        .map(|span| (&source[span.start..span.end], span.kind))
+        .collect()
+}
+
+#[test]
+fn every_byte_of_a_program_is_classified() {
+    use HighlightKind::*;
+    let source = "// total\nlet n = 1.5;\nif n >= 1 { print(\"hé\"); }";
+    assert_eq!(
+        classified(source),
+        [
+            ("// total", Comment),
+            ("let", Keyword),
+            ("n", Identifier),
+            ("=", Operator),
+            ("1.5", Number),
+            (";", Punctuation),
+            ("if", Keyword),
+            ("n", Identifier),
+            (">=", Operator),
+            ("1", Number),
+            ("{", Punctuation),
+            ("print", Identifier),
+            ("(", Punctuation),
+            ("\"hé\"", String),
+            (")", Punctuation),
+            (";", Punctuation),
+            ("}", Punctuation),
+        ]
+    );
+}
+
+#[test]
+fn what_the_lexer_rejects_becomes_error_spans() {
+    use HighlightKind::*;
+    assert_eq!(classified("a @@ b"), [("a", Identifier), ("@@", Error), ("b", Identifier)]);
+    // an unterminated string runs to the end and is still a string
+    assert_eq!(classified("x = \"open\nmore"), [("x", Identifier), ("=", Operator), ("\"open\nmore", String)]);
+    assert_eq!(classified(""), []);
+}
+
+#[test]
+fn html_escapes_text_and_leaves_whitespace_bare() {
+    assert_eq!(
+        highlight_html("a < \"&\";"),
+        "<span class=\"identifier\">a</span> <span class=\"operator\">&lt;</span> \
+         <span class=\"string\">&quot;&amp;&quot;</span><span class=\"punctuation\">;</span>"
+    );
+}
@@ -0,0 +1,148 @@
+mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use common::{Captured, interpreter};
+
+/// A fresh directory for one test's files, in `parent`.
+fn directory(parent: PathBuf, name: &str) -> PathBuf {
+    let directory = parent.join(format!("slimescript-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(directory.join("lib")).unwrap();
+    fs::canonicalize(directory).unwrap()
+}
+
+/// Writes files importing each other in a cycle to `directory`, and
+/// returns the message of the error from running `main.slime` there as the
+/// script at `script_path`.
+fn cycle_message(directory: &Path, script_path: &Path) -> String {
+    fs::write(directory.join("main.slime"), "import \"lib/a.slime\";").unwrap();
+    fs::write(directory.join("lib/a.slime"), "import \"b.slime\";").unwrap();
+    fs::write(directory.join("lib/b.slime"), "import \"../main.slime\";").unwrap();
+
+    let source = fs::read_to_string(directory.join("main.slime")).unwrap();
+    let error = interpreter(&Captured::default()).with_script_path(script_path).run(&source).unwrap_err();
+    assert_eq!(error.report().code.id, "E0401");
+    error.report().message
+}
+
+#[test]
+fn import_cycles_outside_the_current_directory_show_full_paths() {
+    let directory = directory(std::env::temp_dir(), "cycle");
+    let shown = |path: &str| directory.join(path).display().to_string();
+    assert_eq!(
+        cycle_message(&directory, &directory.join("main.slime")),
+        format!(
+            "Circular import: {} -> {} -> {} -> {}",
+            shown("main.slime"),
+            shown("lib/a.slime"),
+            shown("lib/b.slime"),
+            shown("main.slime")
+        )
+    );
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn import_cycles_inside_the_current_directory_show_relative_paths() {
+    let directory = directory(PathBuf::from(env!("CARGO_TARGET_TMPDIR")), "cycle");
+    let relative = directory.strip_prefix(fs::canonicalize(".").unwrap()).unwrap();
+    let shown = |path: &str| relative.join(path).display().to_string();
+    // the script is reached by a path through `lib`, which isn't shown
+    let script_path = relative.join("lib/../main.slime");
+    assert_eq!(
+        cycle_message(&directory, &script_path),
+        format!(
+            "Circular import: {} -> {} -> {} -> {}",
+            shown("main.slime"),
+            shown("lib/a.slime"),
+            shown("lib/b.slime"),
+            shown("main.slime")
+        )
+    );
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn imports_are_found_next_to_the_importing_file_and_run_once() {
+    let directory = directory(std::env::temp_dir(), "include");
+    fs::write(directory.join("main.slime"), "import \"lib/a.slime\";\nimport \"lib/b.slime\";\nprint(double(limit), loaded);").unwrap();
+    fs::write(directory.join("lib/a.slime"), "import \"b.slime\";\nfunction double(x) { return x * 2; }").unwrap();
+    fs::write(directory.join("lib/b.slime"), "let loaded = 0;\nloaded += 1;\nlet limit = 21;\nprint(\"b runs\");").unwrap();
+
+    let output = Captured::default();
+    let source = fs::read_to_string(directory.join("main.slime")).unwrap();
+    interpreter(&output).with_script_path(directory.join("main.slime")).run(&source).unwrap();
+    assert_eq!(output.text(), "b runs\n42 1\n");
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn a_missing_import_names_the_file() {
+    let directory = directory(std::env::temp_dir(), "missing");
+    let source = "print(1);\nimport \"lib/nowhere.slime\";";
+    let output = Captured::default();
+    let error = interpreter(&output).with_script_path(directory.join("main.slime")).run(source).unwrap_err().report();
+    assert_eq!(error.code.id, "E0400");
+    let expected = format!("Cannot import '{}': ", directory.join("lib/nowhere.slime").display());
+    assert!(error.message.starts_with(&expected), "{}", error.message);
+    assert_eq!(error.span.line, 2);
+    // imports are loaded before anything runs
+    assert_eq!(output.text(), "");
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn modules_keep_their_names_to_themselves() {
+    let directory = directory(std::env::temp_dir(), "modules");
+    fs::write(
+        directory.join("lib/math_utils.slime"),
+        "print(\"loading\");\nlet calls = 0;\nexport function clamp(x, low, high) { calls += 1; return min(max(x, low), high); }\nexport let name = \"math\";\nexport function count() { return calls; }\nfunction helper() { return 0; }",
+    )
+    .unwrap();
+    let source = r#"
+        import "lib/math_utils.slime" as mu;
+        import "lib/math_utils.slime" as again;
+        function clamp(x) { return "mine"; }
+        let name = "main";
+        print(mu.clamp(5, 0, 1), again.clamp(-5, 0, 1), clamp(5), name, mu.name);
+        print(mu.count(), again.count(), type(mu));
+    "#;
+    let output = Captured::default();
+    let mut interpreter = interpreter(&output).with_script_path(directory.join("main.slime"));
+    interpreter.run(source).unwrap();
+    // both aliases are the one module, loaded once
+    assert_eq!(output.text(), "loading\n1 0 mine main math\n2 2 module\n");
+
+    let error = interpreter.run("mu.helper();").unwrap_err().report();
+    let module = directory.join("lib/math_utils.slime");
+    assert_eq!(error.message, format!("Module '{}' has no export 'helper'", module.display()));
+    assert_eq!(interpreter.get_global("calls"), None);
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn errors_in_an_imported_file_are_shown_in_that_file() {
+    let directory = directory(std::env::temp_dir(), "failing");
+    fs::write(directory.join("lib/halves.slime"), "export function half(x) {\n    return x / twoo;\n}\n").unwrap();
+    let main = directory.join("main.slime");
+    let source = "import \"lib/halves.slime\" as halves;\n\nprint(halves.half(4));";
+    let mut interpreter = interpreter(&Captured::default()).with_script_path(&main);
+    let error = interpreter.run(source).unwrap_err();
+    let report = interpreter.report(&error);
+    let file = interpreter.sources().get(report.span.file).unwrap();
+    let lib = directory.join("lib/halves.slime");
+    assert_eq!(file.name, lib.display().to_string());
+    assert_eq!(
+        report.render(&file.contents, &file.name, false),
+        format!(
+            "Runtime error[E0501]: Undefined variable 'twoo'\n \
+             --> {}:2:16\n  |\n2 |     return x / twoo;\n  |                ^^^^\n  \
+             = note: in half() called at line 3, column 7 of {}\n",
+            lib.display(),
+            main.display()
+        )
+    );
+    fs::remove_dir_all(directory).unwrap();
+}
@@ -0,0 +1,202 @@
+mod common;
+
+use common::{Captured, interpreter};
+use slimescript::report::Report;
+use slimescript::{Backend, IntOverflow, InterpreterConfig, Value};
+
+/// What `source` prints with integer overflow handled as `overflow`, and
+/// the error that stopped it, if any, on each backend, optimized or not,
+/// which must agree.
+fn outcome(overflow: IntOverflow, source: &str) -> (String, Option<Report>) {
+    let mut outcomes = Vec::new();
+    for (backend, optimize) in [(Backend::TreeWalker, false), (Backend::Vm, false), (Backend::Vm, true)] {
+        let output = Captured::default();
+        let result = interpreter(&output)
+            .with_backend(backend)
+            .with_constant_folding(optimize)
+            .with_config(InterpreterConfig { int_overflow: overflow, ..Default::default() })
+            .run(source);
+        outcomes.push((output.text(), result.err().map(|error| error.report())));
+    }
+    let (text, error) = outcomes.remove(0);
+    for (other, other_error) in &outcomes {
+        assert_eq!(&text, other, "the setups printed differently");
+        assert_eq!(error.as_ref().map(|error| &error.message), other_error.as_ref().map(|error| &error.message));
+    }
+    (text, error)
+}
+
+fn run(overflow: IntOverflow, source: &str) -> String {
+    match outcome(overflow, source) {
+        (output, None) => output,
+        (_, Some(error)) => panic!("{}[{}]: {}", error.kind, error.code.id, error.message),
+    }
+}
+
+fn error(overflow: IntOverflow, source: &str) -> Report {
+    outcome(overflow, source).1.expect("the program fails")
+}
+
+const LIMITS: &str = "let top = 9223372036854775807; let bottom = -9223372036854775808;\n";
+
+#[test]
+fn integers_are_exact_across_their_range() {
+    let source = r#"
+        print(top, bottom, top - 1, bottom + 1, typeof top);
+        // past 2^53, where fractional numbers skip odd integers
+        let odd = 9007199254740993;
+        print(odd, odd + 2, odd == 9007199254740992.0, odd - 1 == 9007199254740992.0);
+        print(0x7fffffffffffffff == top, 3037000499 * 3037000499, -3037000499 * 3037000499);
+    "#;
+    assert_eq!(
+        run(IntOverflow::default(), &format!("{}{}", LIMITS, source)),
+        "9223372036854775807 -9223372036854775808 9223372036854775806 -9223372036854775807 number\n\
+         9007199254740993 9007199254740995 false true\n\
+         true 9223372030926249001 -9223372030926249001\n"
+    );
+}
+
+#[test]
+fn integers_and_fractions_are_the_same_numbers() {
+    let source = r#"
+        print(2 == 2.0, [1, {n: 2}] == [1.0, {n: 2.0}], 3 < 3.5, 0.5 + 1);
+        // division gives a fraction, and % keeps the sign of the left side
+        print(7 / 2, 6 / 3, 7 % 3, -7 % 3, 7 % -3, bottom % -1);
+        print(len("abc") * 3, abs(bottom + 1), max(3, top, -1), round(top), floor(2.5));
+        print(json_stringify([top, -0.5]), json_parse("[9223372036854775807]")[0] == top);
+    "#;
+    assert_eq!(
+        run(IntOverflow::Error, &format!("{}{}", LIMITS, source)).replace("9223372036854775807", "MAX"),
+        "true true true 1.5\n3.5 2 1 -1 1 0\n\
+         9 MAX MAX MAX 2\n[MAX,-0.5] true\n"
+    );
+}
+
+#[test]
+fn overflow_becomes_a_fraction_by_default() {
+    let source = r#"
+        print(top + 1 == 9223372036854775808.0, bottom - 1 == -9223372036854775808.0);
+        print(top * 2 == 18446744073709551616.0, -bottom == 9223372036854775808.0, -bottom > top);
+        let numbers = [2.5, top, -bottom, 2];
+        sort(numbers);
+        // compared exactly, though as floats the two would be equal
+        print(top < 9223372036854775808.0, top == 9223372036854775808.0, numbers);
+        print(9223372036854775808, 0x10000000000000000 == 18446744073709551616.0);
+    "#;
+    assert_eq!(
+        run(IntOverflow::Promote, &format!("{}{}", LIMITS, source)),
+        "true true\ntrue true true\n\
+         true false [2, 2.5, 9223372036854775807, 9223372036854776000]\n\
+         9223372036854776000 true\n"
+    );
+    assert_eq!(IntOverflow::default(), IntOverflow::Promote);
+}
+
+#[test]
+fn overflow_can_wrap_around() {
+    let source = r#"
+        print(top + 1 == bottom, bottom - 1 == top, top * 2, bottom * -1 == bottom, -bottom == bottom);
+        let count = top;
+        count += 2;
+        print(count);
+        // literals wrap too, keeping their low 64 bits
+        print(9223372036854775808 == bottom, 18446744073709551617, 0x1ffffffffffffffff, -9223372036854775809 == top);
+    "#;
+    assert_eq!(
+        run(IntOverflow::Wrap, &format!("{}{}", LIMITS, source)),
+        "true true -2 true true\n-9223372036854775807\ntrue 1 -1 true\n"
+    );
+}
+
+#[test]
+fn overflow_can_saturate() {
+    let source = r#"
+        print(top + 1 == top, bottom - 1 == bottom, top * 2 == top, top * -2 == bottom, -bottom == top);
+        print(top + 1 - 1, 100000000000000000000 == top, -100000000000000000000 == bottom);
+    "#;
+    assert_eq!(
+        run(IntOverflow::Saturate, &format!("{}{}", LIMITS, source)),
+        "true true true true true\n9223372036854775806 true true\n"
+    );
+}
+
+#[test]
+fn overflow_can_be_an_error() {
+    let cases = [
+        ("top + 1", "9223372036854775807 + 1"),
+        ("bottom - 1", "-9223372036854775808 - 1"),
+        ("top * 2", "9223372036854775807 * 2"),
+        ("bottom * -1", "-9223372036854775808 * -1"),
+        ("-bottom", "-(-9223372036854775808)"),
+    ];
+    for (expression, operation) in cases {
+        let report = error(IntOverflow::Error, &format!("{}print(1);\nprint({});", LIMITS, expression));
+        assert_eq!(report.code.id, "E0520");
+        assert_eq!(report.message, format!("Integer overflow: {} is out of range", operation));
+        assert_eq!((report.span.line, report.span.column), (3, 7), "{}", expression);
+    }
+    // compound assignment overflows the same way, and the error can be caught
+    let source = "let n = 4611686018427387904;\nn *= 2;";
+    assert_eq!(error(IntOverflow::Error, source).message, "Integer overflow: 4611686018427387904 * 2 is out of range");
+    let source = format!("{}try {{ top += 1; }} catch (e) {{ print(e.code, top); }}", LIMITS);
+    assert_eq!(run(IntOverflow::Error, &source), "E0520 9223372036854775807\n");
+    // a fraction is never an integer, even a whole one
+    assert_eq!(run(IntOverflow::Error, "print(9223372036854775807.0 + 1);"), "9223372036854776000\n");
+}
+
+#[test]
+fn a_literal_out_of_range_is_an_error_before_running() {
+    for (source, literal) in [
+        ("print(1);\nlet big = 9223372036854775808;", "9223372036854775808"),
+        ("print(1);\nlet big = -9223372036854775809;", "-9223372036854775809"),
+        ("print(1);\nlet big = 0x8000000000000000;", "0x8000000000000000"),
+        // in parentheses the minus applies to the literal's value
+        ("print(1);\nlet big = -(9223372036854775808);", "9223372036854775808"),
+    ] {
+        let (output, report) = outcome(IntOverflow::Error, source);
+        let report = report.expect("the literal is rejected");
+        assert_eq!(output, "");
+        assert_eq!(report.code.id, "E0105");
+        assert_eq!(
+            report.message,
+            format!(
+                "Integer literal {} is out of range; integers go from -9223372036854775808 to 9223372036854775807",
+                literal
+            )
+        );
+        let column = source.lines().nth(1).unwrap().find(literal).unwrap() + 1;
+        assert_eq!((report.span.line, report.span.column), (2, column));
+    }
+}
+
+#[test]
+fn the_most_negative_integer_is_written_with_its_minus() {
+    let source = r#"
+        let bottom = -9223372036854775808;
+        print(bottom, bottom == -9223372036854775807 - 1, bottom + 0, [-9223372036854775808], - 9223372036854775808);
+        // but a subtraction is a subtraction
+        print(0 - 9223372036854775807 - 1 == bottom);
+    "#;
+    for overflow in [IntOverflow::Promote, IntOverflow::Wrap, IntOverflow::Saturate, IntOverflow::Error] {
+        assert_eq!(
+            run(overflow, source),
+            "-9223372036854775808 true -9223372036854775808 [-9223372036854775808] -9223372036854775808\ntrue\n",
+            "{:?}",
+            overflow
+        );
+    }
+    let report = error(IntOverflow::Error, "let x = 1;\nprint(x - 9223372036854775808);");
+    assert_eq!(report.code.id, "E0105");
+    assert_eq!((report.span.line, report.span.column), (2, 11));
+}
+
+#[test]
+fn embedders_get_integers_back() {
+    let output = Captured::default();
+    let mut interpreter = interpreter(&output);
+    let sum = interpreter.eval("let n = 2; n * 3 + 1;").unwrap();
+    assert!(matches!(sum, Value::Int(7)), "{:?}", sum);
+    assert_eq!(sum, Value::Number(7.0));
+    assert_eq!(i64::try_from(interpreter.eval("9223372036854775807;").unwrap()).unwrap(), i64::MAX);
+    assert_eq!(Value::from(5), Value::Int(5));
+}
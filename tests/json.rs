@@ -0,0 +1,75 @@
+mod common;
+
+use common::{error, run};
+
+#[test]
+fn nested_json_becomes_maps_and_arrays() {
+    let source = r#"
+        let data = json_parse("{\"name\": \"slime\", \"tags\": [\"a\", null, true], \"size\": {\"w\": 2, \"h\": 1.5}}");
+        print(typeof data, typeof data.tags, data.tags[1], data.tags[2], data.size.w + data.size.h);
+        print(keys(data));
+        print(json_stringify(data));
+    "#;
+    assert_eq!(
+        run(source),
+        "map array null true 3.5\n[\"name\", \"tags\", \"size\"]\n\
+         {\"name\":\"slime\",\"tags\":[\"a\",null,true],\"size\":{\"w\":2,\"h\":1.5}}\n"
+    );
+}
+
+#[test]
+fn what_can_be_written_reads_back_the_same() {
+    let source = r#"
+        let value = [1, -0.25, 1e21, "tab\there", [], {}, {z: [false], a: null}];
+        let text = json_stringify(value);
+        print(text);
+        print(json_parse(text) == value, json_stringify(json_parse(text)) == text);
+    "#;
+    assert_eq!(
+        run(source),
+        "[1,-0.25,1e21,\"tab\\there\",[],{},{\"z\":[false],\"a\":null}]\ntrue true\n"
+    );
+}
+
+#[test]
+fn unicode_survives_both_ways() {
+    let source = r#"
+        let s = json_parse("\"hé 😀 ok\"");
+        print(s, len(s));
+        print(json_stringify("é😀\n"));
+    "#;
+    assert_eq!(run(source), "hé 😀 ok 7\n\"é😀\\n\"\n");
+}
+
+#[test]
+fn a_repeated_key_keeps_its_last_value() {
+    let source = r#"print(json_parse("{\"a\": 1, \"b\": 2, \"a\": {\"c\": 3, \"c\": 4}}"));"#;
+    // where it first appeared, as in JavaScript
+    assert_eq!(run(source), "{a: {c: 4}, b: 2}\n");
+}
+
+#[test]
+fn pretty_output_is_indented() {
+    assert_eq!(
+        run(r#"print(json_stringify({a: [1, 2], b: {}}, true));"#),
+        "{\n  \"a\": [\n    1,\n    2\n  ],\n  \"b\": {}\n}\n"
+    );
+}
+
+#[test]
+fn bad_json_is_reported_where_it_goes_wrong() {
+    let report = error("json_parse(\"{\\n  \\\"a\\\": tru\\n}\");");
+    assert_eq!(report.code.id, "E0517");
+    assert_eq!(report.message, "json_parse(): Expected a value at line 2, column 8 (byte 9)");
+    // and can be caught like any other error
+    assert_eq!(run("try { json_parse(\"[1,]\"); } catch (e) { print(e.code); }"), "E0517\n");
+}
+
+#[test]
+fn values_json_cant_hold_are_errors() {
+    let report = error("function f() {} json_stringify({f: f});");
+    assert_eq!(report.code.id, "E0517");
+    assert_eq!(report.message, "json_stringify(): a function can't be written as JSON");
+    assert_eq!(error("let a = []; push(a, a); json_stringify(a);").code.id, "E0517");
+    assert_eq!(error("json_stringify(1e308 * 10);").message, "json_stringify(): inf can't be written as JSON");
+}
@@ -0,0 +1,175 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::{Captured, error, interpreter, on_big_stack, run};
+use slimescript::checker::check_source;
+use slimescript::report::Report;
+use slimescript::{Backend, InterpreterConfig};
+
+/// The error `source` stops with under `config` on each backend, which
+/// must agree, and the longest either took to stop.
+fn limited(config: impl Fn() -> InterpreterConfig, source: &str) -> (Report, Duration) {
+    let mut reports = Vec::new();
+    let mut longest = Duration::ZERO;
+    for backend in [Backend::TreeWalker, Backend::Vm] {
+        let start = Instant::now();
+        let result = interpreter(&Captured::default()).with_backend(backend).with_config(config()).run(source);
+        longest = longest.max(start.elapsed());
+        reports.push(result.expect_err("the limit stops the program").report());
+    }
+    assert_eq!(reports[0].message, reports[1].message, "the backends stopped differently");
+    (reports.remove(0), longest)
+}
+
+#[test]
+fn infinite_loops_stop_at_the_step_limit() {
+    let config = || InterpreterConfig { max_steps: Some(10_000), ..Default::default() };
+    let (report, _) = limited(config, "let i = 0; while true { i = i + 1; }");
+    assert_eq!(report.code.id, "E0507");
+    assert_eq!(report.message, "Execution budget exceeded: more than 10000 steps");
+    assert_eq!((report.span.line, report.span.column), (1, 12));
+}
+
+#[test]
+fn infinite_loops_stop_at_the_time_limit() {
+    let config = || InterpreterConfig { max_duration: Some(Duration::from_millis(50)), ..Default::default() };
+    let (report, took) = limited(config, "let i = 0; while true { i = i + 1; }");
+    assert_eq!(report.code.id, "E0507");
+    assert_eq!(report.message, "Execution budget exceeded: ran longer than 50ms");
+    assert!(took < Duration::from_secs(5), "took {:?}", took);
+}
+
+#[test]
+fn slow_builtins_dont_outrun_the_time_limit() {
+    // each call takes milliseconds, so waiting for the step count to reach
+    // the next check of the clock would run far past the limit
+    let config = || InterpreterConfig { max_duration: Some(Duration::from_millis(100)), ..Default::default() };
+    let source = "let s = \"xy\"; for i in 0..20 { s = s + s; } while true { len(split(s, \"y\")); }";
+    let (report, took) = limited(config, source);
+    assert_eq!(report.code.id, "E0507");
+    assert!(took < Duration::from_secs(10), "took {:?}", took);
+}
+
+const COUNTDOWN: &str = "function f(n) { if n == 0 { return 0; } return 1 + f(n - 1); }";
+
+#[test]
+fn long_flat_chains_parse() {
+    let terms = vec!["1"; 9000].join(" + ");
+    assert_eq!(on_big_stack(|| run(&format!("print({});", terms))), "9000\n");
+    assert!(on_big_stack(|| check_source(&format!("print({});", terms))).is_empty());
+    let terms = vec!["x"; 9000].join(" && ");
+    assert_eq!(on_big_stack(|| run(&format!("let x = 1; print({} || 2);", terms))), "1\n");
+    let calls = "f".to_string() + &"()".repeat(9000);
+    assert_eq!(on_big_stack(|| run(&format!("function f() {{ return f; }} print(typeof({}));", calls))), "function\n");
+    let links = "m".to_string() + &".m[0]".repeat(4500);
+    assert_eq!(on_big_stack(|| run(&format!("let m = {{}}; m.m = [m]; print({} == m);", links))), "true\n");
+}
+
+#[test]
+fn long_else_if_chains_parse() {
+    let mut source = String::from("let x = 8999;\nif x == 0 { print(0); }");
+    for arm in 1..9000 {
+        source += &format!(" else if x == {} {{ print({}); }}", arm, arm);
+    }
+    source += " else { print(\"none\"); }";
+    assert_eq!(on_big_stack(|| run(&source)), "8999\n");
+}
+
+#[test]
+fn chains_too_long_to_walk_are_errors() {
+    let terms = vec!["1"; 20_000].join(" + ");
+    let report = on_big_stack(|| error(&format!("print({});", terms)));
+    assert_eq!(report.code.id, "E0106");
+    assert_eq!(report.message, "Chain of operators, calls, or else-ifs too long, found '1'");
+}
+
+#[test]
+fn real_nesting_is_still_limited() {
+    // the statement and the call to `print` are two levels already
+    let source = format!("print({}1{});", "(".repeat(199), ")".repeat(199));
+    assert_eq!(on_big_stack(|| error(&source)).code.id, "E0106");
+    let source = format!("print({}1{});", "(".repeat(198), ")".repeat(198));
+    assert_eq!(on_big_stack(|| run(&source)), "1\n");
+}
+
+#[test]
+fn calls_nest_to_the_fixed_limit_without_a_stack_size() {
+    assert_eq!(on_big_stack(|| run(&format!("{} print(f(150));", COUNTDOWN))), "150\n");
+    let error = on_big_stack(|| error(&format!("{} print(f(250));", COUNTDOWN)));
+    assert_eq!(error.code.id, "E0506");
+    assert!(error.notes.iter().any(|note| note.starts_with("(the call above repeated")));
+}
+
+#[test]
+fn calls_nest_as_deep_as_the_stack_allows() {
+    let config = || InterpreterConfig { stack_size: Some(64 * 1024 * 1024), ..Default::default() };
+    let output = on_big_stack(|| {
+        let output = Captured::default();
+        interpreter(&output)
+            .with_config(config())
+            .run(&format!("{} print(f(250));", COUNTDOWN))
+            .map(|_| output.text())
+            .map_err(|error| error.report().message)
+    });
+    assert_eq!(output, Ok("250\n".to_string()));
+
+    let code = on_big_stack(|| {
+        let output = Captured::default();
+        let result = interpreter(&output).with_config(config()).run(&format!("{} print(f(100000000));", COUNTDOWN));
+        result.err().map(|error| error.report().code.id)
+    });
+    assert_eq!(code, Some("E0506"));
+}
+
+#[test]
+fn doubling_a_string_stops_at_the_string_limit() {
+    let config = || InterpreterConfig { max_string_len: Some(1 << 20), ..Default::default() };
+    let (report, _) = limited(config, "let s = \"x\";\nwhile (true) { s = s + s; }");
+    assert_eq!(report.code.id, "E0511");
+    assert_eq!(report.message, "allocation limit exceeded: a string of 2097152 bytes is longer than 1048576");
+    assert_eq!((report.span.line, report.span.column), (2, 20));
+    // a builtin whose result would be too long fails before building it
+    let (report, _) = limited(config, "let s = \"x\"; for i in 0..11 { s = s + s; } replace(s, \"x\", s);");
+    assert_eq!(report.message, "allocation limit exceeded: a string of 4194304 bytes is longer than 1048576");
+}
+
+#[test]
+fn growing_an_array_stops_at_the_array_limit() {
+    let config = || InterpreterConfig { max_array_len: Some(1000), ..Default::default() };
+    let (report, _) = limited(config, "let xs = []; while true { push(xs, 0); }");
+    assert_eq!(report.code.id, "E0511");
+    assert_eq!(report.message, "allocation limit exceeded: an array of 1001 elements is longer than 1000");
+    let (report, _) = limited(config, "let xs = []; for i in 0..1000 { push(xs, i); } insert(xs, 0, -1);");
+    assert_eq!(report.message, "allocation limit exceeded: an array of 1001 elements is longer than 1000");
+    // and so does a builtin returning a longer array
+    let (report, _) = limited(config, "let s = \"x,\"; for i in 0..10 { s = s + s; } split(s, \",\");");
+    assert_eq!(report.message, "allocation limit exceeded: an array of 1025 elements is longer than 1000");
+}
+
+#[test]
+fn holding_on_to_values_stops_at_the_total_limit() {
+    let config = || InterpreterConfig { max_total_values: Some(10_000), ..Default::default() };
+    let (report, _) = limited(config, "let kept = []; while true { push(kept, [1, 2, 3]); }");
+    assert_eq!(report.code.id, "E0511");
+    assert_eq!(report.message, "allocation limit exceeded: more than 10000 values alive at once");
+    let (report, _) = limited(config, "let m = {}; let i = 0; while true { m[str(i)] = i; i = i + 1; }");
+    assert_eq!(report.message, "allocation limit exceeded: more than 10000 values alive at once");
+
+    // values that are dropped again don't add up
+    let source = "let total = 0;
+        for i in 0..100000 { let pair = [i, {i: i}]; total = total + len(pair); }
+        print(total);";
+    for backend in [Backend::TreeWalker, Backend::Vm] {
+        let output = Captured::default();
+        interpreter(&output).with_backend(backend).with_config(config()).run(source).unwrap();
+        assert_eq!(output.text(), "200000\n");
+    }
+}
+
+#[test]
+fn programs_are_unlimited_by_default() {
+    let source = "let s = \"x\"; for i in 0..22 { s = s + s; } let xs = []; for i in 0..200000 { push(xs, [i]); }
+        print(len(s), len(xs));";
+    assert_eq!(run(source), "4194304 200000\n");
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use common::{error, run};
+
+// `touch` counts its calls, so a test can tell whether an operand ran
+const COUNTER: &str = r#"
+    let calls = 0;
+    function touch(value) { calls = calls + 1; return value; }
+"#;
+
+fn run_counted(source: &str) -> String {
+    run(&format!("{}{}", COUNTER, source))
+}
+
+#[test]
+fn and_skips_its_right_side_when_the_left_is_falsy() {
+    let source = r#"
+        print(false && touch(true));
+        print(null && touch(true));
+        print(calls);
+        let x = null;
+        print(x != null && x.field);
+    "#;
+    assert_eq!(run_counted(source), "false\nnull\n0\nfalse\n");
+}
+
+#[test]
+fn or_skips_its_right_side_when_the_left_is_truthy() {
+    let source = r#"
+        let cached = "hit";
+        print(cached || touch("computed"));
+        print(true || touch(false));
+        print(calls);
+    "#;
+    assert_eq!(run_counted(source), "hit\ntrue\n0\n");
+}
+
+#[test]
+fn the_right_side_runs_when_it_decides() {
+    let source = r#"
+        print(true && touch("right"));
+        print(null || touch("fallback"));
+        print(false || touch(false));
+        print(calls);
+    "#;
+    assert_eq!(run_counted(source), "right\nfallback\nfalse\n3\n");
+}
+
+#[test]
+fn chains_stop_at_the_first_deciding_operand() {
+    let source = r#"
+        print(touch(1) && touch(null) && touch(3));
+        print(calls);
+        print(touch(false) || touch(0) || touch(5));
+        print(calls);
+    "#;
+    assert_eq!(run_counted(source), "null\n2\n0\n4\n");
+}
+
+#[test]
+fn only_null_and_false_are_falsy() {
+    let source = r#"print(0 && "zero"); print("" && "empty"); print([] && "array");"#;
+    assert_eq!(run(source), "zero\nempty\narray\n");
+}
+
+#[test]
+fn in_looks_through_arrays_map_keys_and_strings() {
+    let source = r#"
+        let names = ["ada", [1, 2], { "k": null }];
+        let ages = { "ada": 36, "alan": null };
+        print("ada" in names, [1, 2] in names, { "k": null } in names, "bob" in names, 1 in names);
+        print("alan" in ages, "bob" in ages);
+        print("ñ😀" in "añ😀b", "" in "abc", "x" in "");
+        print("bob" not in names, "ada" not in names, "z" not in "abc", !("a" in "abc"));
+        let allowed = ["ada"];
+        if "ada" in allowed && "bob" not in allowed { print("yes"); }
+    "#;
+    assert_eq!(
+        run(source),
+        "true true true false false\ntrue false\ntrue true false\ntrue false true false\nyes\n"
+    );
+}
+
+#[test]
+fn in_names_both_types_when_it_cant_look() {
+    for (source, message) in [
+        ("1 in 2;", "Cannot apply 'in' to number and number"),
+        ("1 not in \"a1\";", "Cannot apply 'not in' to number and string"),
+        ("1 in { \"1\": true };", "Cannot apply 'in' to number and map"),
+        ("null not in null;", "Cannot apply 'not in' to null and null"),
+    ] {
+        let report = error(source);
+        assert_eq!(report.code.id, "E0505", "{}", source);
+        assert_eq!(report.message, message);
+    }
+}
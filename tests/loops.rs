@@ -0,0 +1,263 @@
+mod common;
+
+use common::{error, run};
+
+#[test]
+fn break_stops_a_search_at_the_first_match() {
+    let source = r#"
+        let names = ["ada", "grace", "linus", "grace"];
+        let found = null;
+        let i = 0;
+        while i < len(names) {
+            if names[i] == "grace" { found = i; break; }
+            i = i + 1;
+        }
+        print(found);
+        print(i);
+    "#;
+    assert_eq!(run(source), "1\n1\n");
+}
+
+#[test]
+fn continue_skips_the_rest_of_the_body() {
+    let source = r#"
+        let evens = [];
+        for (let i = 0; i < 8; i = i + 1) {
+            if i % 2 == 1 { continue; }
+            push(evens, i);
+        }
+        print(evens);
+
+        let n = 0;
+        let odds = 0;
+        while n < 5 {
+            n = n + 1;
+            if n % 2 == 0 { continue; }
+            odds = odds + 1;
+        }
+        print(odds);
+    "#;
+    // `continue` in a C-style for still runs the increment, or this would never end
+    assert_eq!(run(source), "[0, 2, 4, 6]\n3\n");
+}
+
+#[test]
+fn break_only_leaves_the_innermost_loop() {
+    let source = r#"
+        let pairs = 0;
+        for (let i = 0; i < 3; i = i + 1) {
+            for (let j = 0; j < 3; j = j + 1) {
+                if j == 1 { break; }
+                pairs = pairs + 1;
+            }
+        }
+        print(pairs);
+    "#;
+    assert_eq!(run(source), "3\n");
+}
+
+#[test]
+fn labels_pick_which_loop_to_leave() {
+    let source = r#"
+        let seen = [];
+        outer: for (let i = 0; i < 3; i = i + 1) {
+            for (let j = 0; j < 3; j = j + 1) {
+                if j == 1 { continue outer; }
+                if i == 2 { break outer; }
+                push(seen, [i, j]);
+            }
+        }
+        print(seen);
+    "#;
+    assert_eq!(run(source), "[[0, 0], [1, 0]]\n");
+}
+
+#[test]
+fn return_leaves_every_loop_in_the_function() {
+    let source = r#"
+        function first_negative(rows) {
+            for (let i = 0; i < len(rows); i = i + 1) {
+                let j = 0;
+                while true {
+                    if j == len(rows[i]) { break; }
+                    if rows[i][j] < 0 { return [i, j]; }
+                    j = j + 1;
+                }
+            }
+            return null;
+        }
+        print(first_negative([[1, 2], [3, -4, 5]]));
+        print(first_negative([[1]]));
+    "#;
+    assert_eq!(run(source), "[1, 1]\nnull\n");
+}
+
+#[test]
+fn break_inside_a_called_function_doesnt_leave_the_callers_loop() {
+    let source = r#"
+        function count_to(limit) {
+            let n = 0;
+            while true { if n == limit { break; } n = n + 1; }
+            return n;
+        }
+        let total = 0;
+        for (let i = 0; i < 3; i = i + 1) { total = total + count_to(i); }
+        print(total);
+    "#;
+    assert_eq!(run(source), "3\n");
+}
+
+#[test]
+fn for_in_visits_arrays_maps_strings_and_ranges() {
+    let source = r#"
+        for x in [1, "two", null] { print(x); }
+        for key in { "a": 1, "b": 2 } { print(key); }
+        for ch in "hé!" { print(ch); }
+        for i in 0..3 { print(i); }
+        for i in 0..=2 { print(i); }
+        for i in 3..3 { print("never"); }
+    "#;
+    assert_eq!(run(source), "1\ntwo\nnull\na\nb\nh\né\n!\n0\n1\n2\n0\n1\n2\n");
+}
+
+#[test]
+fn for_in_defines_the_variable_fresh_each_time() {
+    let source = r#"
+        let readers = [];
+        for i in 0..3 { push(readers, function() { return i; }); }
+        for read in readers { print(read()); }
+        let i = "outside";
+        for i in [1] { }
+        print(i);
+    "#;
+    assert_eq!(run(source), "0\n1\n2\noutside\n");
+}
+
+#[test]
+fn for_in_iterates_over_a_snapshot() {
+    let source = r#"
+        let items = [1, 2, 3];
+        for x in items {
+            push(items, x * 10);
+            items[0] = "changed";
+            print(x);
+        }
+        print(items);
+
+        let m = { "a": 1 };
+        for key in m { m.b = 2; print(key); }
+        print(len(keys(m)));
+    "#;
+    assert_eq!(run(source), "1\n2\n3\n[\"changed\", 2, 3, 10, 20, 30]\na\n2\n");
+}
+
+#[test]
+fn break_and_continue_work_inside_for_in() {
+    let source = r#"
+        for i in 0..10 {
+            if i == 4 { break; }
+            if i % 2 == 0 { continue; }
+            print(i);
+        }
+        for ch in "abc" { for i in 0..5 { if i == 1 { break; } print(ch); } }
+    "#;
+    assert_eq!(run(source), "1\n3\na\nb\nc\n");
+}
+
+#[test]
+fn for_in_over_other_values_is_an_error() {
+    assert_eq!(error("for x in 5 { }").code.id, "E0505");
+    assert_eq!(error("for x in 0..1.5 { }").code.id, "E0516");
+}
+
+#[test]
+fn do_while_runs_its_body_once_when_the_condition_is_false() {
+    let source = r#"
+        let runs = 0;
+        do { runs = runs + 1; } while (false);
+        print(runs);
+        let n = 0;
+        do { n = n + 1; } while (n < 3);
+        print(n);
+    "#;
+    assert_eq!(run(source), "1\n3\n");
+}
+
+#[test]
+fn continue_in_do_while_goes_to_the_condition() {
+    let source = r#"
+        let n = 0;
+        let checks = 0;
+        function again() { checks = checks + 1; return n < 5; }
+        do {
+            n = n + 1;
+            if n % 2 == 0 { continue; }
+            if n == 5 { break; }
+            print(n);
+        } while (again());
+        print(checks);
+    "#;
+    // the condition is checked after passes 1 to 4; pass 5 breaks before it
+    assert_eq!(run(source), "1\n3\n4\n");
+}
+
+#[test]
+fn a_labelled_break_leaves_every_loop_up_to_the_label() {
+    let source = r#"
+        let grid = [[1, 2, 3], [4, 5, 6], [7, 8, 9]];
+        let found = null;
+        search: for row in grid {
+            for cell in row {
+                while true {
+                    if cell == 5 { found = cell; break search; }
+                    break;
+                }
+            }
+        }
+        print(found);
+    "#;
+    assert_eq!(run(source), "5\n");
+}
+
+#[test]
+fn a_labelled_continue_goes_on_to_the_outer_loops_next_iteration() {
+    let source = r#"
+        let kept = [];
+        rows: for (let i = 0; i < 3; i = i + 1) {
+            let j = 0;
+            while true {
+                j = j + 1;
+                if j > i { continue rows; }
+                push(kept, [i, j]);
+            }
+        }
+        print(kept);
+        // the innermost loop with the label wins, so only `y == 1` is skipped
+        let n = 0;
+        o: for x in 0..2 { o: for y in 0..3 { if y == 1 { continue o; } n = n + 1; } }
+        print(n);
+    "#;
+    assert_eq!(run(source), "[[1, 1], [2, 1], [2, 2]]\n4\n");
+}
+
+#[test]
+fn an_unknown_label_is_an_error_at_its_name() {
+    let report = error("outer: while true {\n    for x in [1] { break outr; }\n}");
+    assert_eq!(report.code.id, "E0111");
+    assert_eq!(report.message, "No loop around this 'break' is labelled 'outr'");
+    assert_eq!((report.span.line, report.span.column), (2, 26));
+    assert_eq!((report.span.start, report.span.end), (45, 49));
+    // a label from outside the function doesn't count
+    let report = error("outer: while true { function f() { while true { continue outer; } } }");
+    assert_eq!(report.code.id, "E0111");
+    assert_eq!(report.span.column, 58);
+}
+
+#[test]
+fn a_label_on_anything_but_a_loop_is_an_error_at_the_label() {
+    let report = error("let x = 1;\nchecks: if x { }");
+    assert_eq!(report.code.id, "E0104");
+    assert_eq!(report.message, "The label 'checks' isn't on a loop; only loops can be labelled");
+    assert_eq!((report.span.line, report.span.column), (2, 1));
+    assert_eq!((report.span.start, report.span.end), (11, 17));
+}
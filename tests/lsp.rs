@@ -0,0 +1,70 @@
+use slimescript::lsp::serve;
+
+/// The messages the server sends back for `requests`, each framed as a
+/// client would send it, followed by `shutdown` and `exit`.
+fn session(requests: &[String]) -> Vec<String> {
+    let mut input = String::new();
+    let closing = [
+        r#"{"jsonrpc":"2.0","id":99,"method":"shutdown"}"#.to_string(),
+        r#"{"jsonrpc":"2.0","method":"exit"}"#.to_string(),
+    ];
+    for message in requests.iter().chain(&closing) {
+        input.push_str(&format!("Content-Length: {}\r\n\r\n{}", message.len(), message));
+    }
+    let mut output = Vec::new();
+    assert!(serve(input.as_bytes(), &mut output).unwrap(), "the client shut the server down");
+
+    let mut output = String::from_utf8(output).unwrap();
+    let mut replies = Vec::new();
+    while let Some(rest) = output.strip_prefix("Content-Length: ") {
+        let (length, rest) = rest.split_once("\r\n\r\n").unwrap();
+        let length: usize = length.parse().unwrap();
+        replies.push(rest[..length].to_string());
+        output = rest[length..].to_string();
+    }
+    assert_eq!(output, "");
+    assert_eq!(replies.pop().unwrap(), r#"{"jsonrpc":"2.0","id":99,"result":null}"#);
+    replies
+}
+
+fn hover(id: usize, line: usize, character: usize) -> String {
+    format!(
+        r#"{{"jsonrpc":"2.0","id":{},"method":"textDocument/hover","params":{{"textDocument":{{"uri":"untitled:a"}},"position":{{"line":{},"character":{}}}}}}}"#,
+        id, line, character
+    )
+}
+
+#[test]
+fn a_session_reports_diagnostics_and_hovers_in_utf16_positions() {
+    // the emoji is four bytes but two UTF-16 units, which LSP counts in
+    let source = "/// Adds one.\nfunction inc(x) { return x + 1; }\nlet s = \"😀\"; print(inc(len(s)), nope);";
+    let open = format!(
+        r#"{{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{{"textDocument":{{"uri":"untitled:a","languageId":"slimescript","version":1,"text":{:?}}}}}}}"#,
+        source
+    );
+    let replies = session(&[
+        r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"capabilities":{}}}"#.to_string(),
+        open,
+        hover(2, 2, 21),
+        hover(3, 2, 25),
+        hover(4, 2, 3),
+    ]);
+    assert_eq!(replies.len(), 5, "{:#?}", replies);
+
+    assert!(replies[0].starts_with(r#"{"jsonrpc":"2.0","id":1,"result":{"capabilities":{"#), "{}", replies[0]);
+    assert!(replies[0].contains(r#""hoverProvider":true"#), "{}", replies[0]);
+    assert_eq!(
+        replies[1],
+        r#"{"jsonrpc":"2.0","method":"textDocument/publishDiagnostics","params":{"uri":"untitled:a","diagnostics":[{"range":{"start":{"line":2,"character":33},"end":{"line":2,"character":37}},"severity":1,"code":"E0200","source":"slimescript","message":"Undefined variable 'nope'"}]}}"#
+    );
+    assert_eq!(
+        replies[2],
+        r#"{"jsonrpc":"2.0","id":2,"result":{"contents":{"kind":"markdown","value":"```\nfunction inc(x)\n```\n\nAdds one."},"range":{"start":{"line":2,"character":20},"end":{"line":2,"character":23}}}}"#
+    );
+    assert_eq!(
+        replies[3],
+        r#"{"jsonrpc":"2.0","id":3,"result":{"contents":{"kind":"markdown","value":"`len`, a builtin taking 1 argument"},"range":{"start":{"line":2,"character":24},"end":{"line":2,"character":27}}}}"#
+    );
+    // `let` is a keyword, not a name
+    assert_eq!(replies[4], r#"{"jsonrpc":"2.0","id":4,"result":null}"#);
+}
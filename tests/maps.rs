@@ -0,0 +1,54 @@
+mod common;
+
+use common::run;
+
+const INSERTIONS: &str = r#"
+    let m = { "zebra": 1, "apple": 2, "mango": 3 };
+    m["banana"] = 4;
+    m.cherry = 5;
+    m["apple"] = 20;
+"#;
+
+#[test]
+fn maps_keep_insertion_order_everywhere() {
+    let source = format!(
+        "{}{}",
+        INSERTIONS,
+        r#"
+        print(m);
+        print(keys(m));
+        for key in m { print(key); }
+        print(json_stringify(m));
+        print(str(m));
+    "#
+    );
+    assert_eq!(
+        run(&source),
+        "{zebra: 1, apple: 20, mango: 3, banana: 4, cherry: 5}\n\
+         [\"zebra\", \"apple\", \"mango\", \"banana\", \"cherry\"]\n\
+         zebra\napple\nmango\nbanana\ncherry\n\
+         {\"zebra\":1,\"apple\":20,\"mango\":3,\"banana\":4,\"cherry\":5}\n\
+         {zebra: 1, apple: 20, mango: 3, banana: 4, cherry: 5}\n"
+    );
+}
+
+#[test]
+fn printed_maps_are_identical_across_runs() {
+    // every std hash map gets its own hash keys, even within one process,
+    // so a map backed by one would print in a different order each time
+    let source = format!("{}{}", INSERTIONS, "let big = {}; for i in 0..200 { big[str(i * 7919 % 1000)] = i; } print(big); print(m);");
+    let first = run(&source);
+    for _ in 0..20 {
+        assert_eq!(run(&source), first);
+    }
+}
+
+#[test]
+fn map_equality_ignores_order() {
+    let source = r#"
+        print({ "a": 1, "b": [1, { "c": 2, "d": 3 }] } == { "b": [1, { "d": 3, "c": 2 }], "a": 1 });
+        print({ "a": 1, "b": 2 } == { "a": 1, "b": 3 });
+        print({ "a": 1 } == { "a": 1, "b": 2 });
+    "#;
+    assert_eq!(run(source), "true\nfalse\nfalse\n");
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use common::run;
+
+#[test]
+fn match_picks_the_arm_equal_to_the_subject() {
+    let source = r#"
+        function describe(x) {
+            match x {
+                1 => { return "one"; }
+                "a" => { return "letter a"; }
+                _ => { return "something else"; }
+            }
+        }
+        print(describe(1));
+        print(describe("a"));
+        print(describe(2));
+        print(describe("1"));
+    "#;
+    // equality is the language's `==`, so "1" doesn't match 1
+    assert_eq!(run(source), "one\nletter a\nsomething else\nsomething else\n");
+}
+
+#[test]
+fn an_arm_can_list_several_values() {
+    let source = r#"
+        for day in ["sat", "mon", "sun"] {
+            match day {
+                "sat", "sun" => { print("weekend"); }
+                _ => { print("weekday"); }
+            }
+        }
+        for n in 0..5 {
+            match n { 1, 3 => { print("odd"); } 0, 2, 4 => { print("even"); } }
+        }
+    "#;
+    assert_eq!(run(source), "weekend\nweekday\nweekend\neven\nodd\neven\nodd\neven\n");
+}
+
+#[test]
+fn without_a_default_an_unmatched_subject_does_nothing() {
+    let source = r#"
+        match 7 { 1 => { print("one"); } "7" => { print("string"); } }
+        print("after");
+    "#;
+    assert_eq!(run(source), "after\n");
+}
+
+#[test]
+fn only_the_first_matching_arm_runs() {
+    let source = r#"
+        let calls = 0;
+        function subject() { calls = calls + 1; return 2; }
+        match subject() {
+            2 => { print("first"); }
+            2 => { print("second"); }
+            _ => { print("default"); }
+        }
+        print(calls);
+    "#;
+    // no fallthrough, and the subject is evaluated once
+    assert_eq!(run(source), "first\n1\n");
+}
+
+#[test]
+fn break_in_an_arm_leaves_the_enclosing_loop() {
+    let source = r#"
+        for n in 0..10 {
+            match n { 2 => { continue; } 4 => { break; } }
+            print(n);
+        }
+    "#;
+    assert_eq!(run(source), "0\n1\n3\n");
+}
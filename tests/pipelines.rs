@@ -0,0 +1,76 @@
+mod common;
+
+use common::{error, run};
+use slimescript::lexer::{Lexer, TokenType};
+
+#[test]
+fn each_stage_gets_the_value_as_its_first_argument() {
+    let source = r#"
+        function is_valid(line) { return len(line) > 0; }
+        let lines = ["  ant ", "", " bee"];
+        print(lines |> filter(is_valid) |> map(trim) |> join(", "));
+        // the same as calling them inside out
+        print(join(map(filter(lines, is_valid), trim), ", "));
+        function between(x, low, high) { return low <= x && x <= high; }
+        print(5 |> between(1, 10), 5 |> between(6, 10));
+    "#;
+    assert_eq!(run(source), "ant, bee\nant, bee\ntrue false\n");
+}
+
+#[test]
+fn builtins_methods_and_anything_called_can_be_piped_into() {
+    let source = r#"
+        print("slime" |> upper(), [1, 2, 3] |> len());
+        class Counter {
+            init() { this.total = 0; }
+            add(n, times) { this.total += n * times; return this; }
+        }
+        let counter = Counter();
+        print((2 |> counter.add(3)).total);
+        let handlers = {shout: function(s) { return s + "!"; }};
+        print("hi" |> handlers.shout() |> handlers["shout"]());
+        print(3 |> (function(n) { return n * n; })());
+    "#;
+    assert_eq!(run(source), "SLIME 3\n6\nhi!!\n9\n");
+}
+
+#[test]
+fn a_pipe_binds_looser_than_everything_but_assignment() {
+    let source = r#"
+        function double(n) { return n * 2; }
+        print(1 + 2 |> double(), 1 < 2 |> str(), false || true |> str());
+        let x = 4 |> double() |> double();
+        print(x);
+    "#;
+    assert_eq!(run(source), "6 true true\n16\n");
+}
+
+#[test]
+fn the_right_side_must_be_a_call() {
+    let report = error("let lines = [];\nlet n = lines |> len;");
+    assert_eq!(report.code.id, "E0110");
+    assert_eq!(
+        report.message,
+        "Expected a call after '|>', which passes the value before it as the first argument; did you mean `len()`?"
+    );
+    assert_eq!((report.span.line, report.span.column), (2, 18));
+    let report = error("let m = {};\nprint([1] |> m.size);");
+    assert!(report.message.ends_with("did you mean `m.size()`?"), "{}", report.message);
+    // only a name or a member gets a suggestion
+    let report = error("print(1 |> 2 + 3);");
+    assert_eq!(report.code.id, "E0110");
+    assert!(!report.message.contains("did you mean"), "{}", report.message);
+    assert_eq!(report.span.column, 12);
+}
+
+#[test]
+fn a_pipe_is_one_token_apart_from_or() {
+    let types = |source: &str| -> Vec<TokenType> {
+        Lexer::new(source).tokenize().unwrap().into_iter().map(|token| token.token_type).collect()
+    };
+    assert_eq!(types("a |> b"), [TokenType::Identifier, TokenType::PipeGreater, TokenType::Identifier, TokenType::EOF]);
+    assert_eq!(types("a ||> b")[1..3], [TokenType::Or, TokenType::Greater]);
+    // a `|` on its own is still no operator
+    let error = Lexer::new("a | > b").tokenize().unwrap_err();
+    assert_eq!((error.span.line, error.span.column), (1, 3));
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use std::io::Cursor;
+
+use common::{Captured, assert_snapshot, error, run};
+use slimescript::{Interpreter, repl};
+
+const VALUES: &str = r#"
+    class Point { init(x, y) { this.x = x; this.y = y; } }
+    function add(a, b) { return a + b; }
+    let values = [
+        null, true, 0, -0, 1.5, 1e21, 0.1 + 0.2, 2e-8,
+        "top", "", "quo\"te\n",
+        [], [1, "a", null, [2, [3]]],
+        {}, { "name": "ada", "two words": 1, "_x1": [], "1st": {} },
+        Point(1, "b"),
+        add, len, function(x) { return x; },
+    ];
+    let cycle = [1]; push(cycle, cycle);
+    let owner = { "name": "m" }; owner.self = owner; owner.list = [owner];
+    let shared = [1];
+    let twice = [shared, shared];
+    let node = Point(0, 0); node.next = node;
+"#;
+
+#[test]
+fn print_str_and_pretty_snapshot() {
+    let source = format!(
+        "{}{}",
+        VALUES,
+        r#"
+        print("-- print");
+        for value in values { print(value); }
+        print(cycle); print(owner); print(twice); print(node);
+        print("-- str");
+        print(str(values)); print(str(cycle)); print(str(owner));
+        print("-- pretty");
+        print(pretty(values)); print(pretty(owner)); print(pretty([[], {}]));
+        "#
+    );
+    assert_snapshot("values.txt", &run(&source));
+}
+
+const ECHOED: &str = "let owner = { \"name\": \"m\" }; owner.self = owner;\nowner\n\"top\"\n[\"in\"]\n";
+
+#[test]
+fn repl_echo_and_error_messages_use_the_same_rendering() {
+    let output = Captured::default();
+    let mut interpreter = Interpreter::with_io(Cursor::new(ECHOED.to_string()), output.clone());
+    repl::run(&mut interpreter, false).unwrap();
+    let echoed: Vec<_> = output.text().split("> ").map(str::to_string).filter(|line| !line.is_empty()).collect();
+    // the first entry echoes the value of its assignment
+    let owner = "{name: \"m\", self: {...}}\n";
+    assert_eq!(echoed, [owner, owner, "top\n", "[\"in\"]\n", "\n"]);
+
+    let report = error("let bound = [1, \"a\"]; for i in 0..bound { }");
+    assert_eq!(report.message, "Range bounds must be whole numbers, got [1, \"a\"]");
+    let report = error("let cycle = [1]; push(cycle, cycle); for i in 0..cycle { }");
+    assert_eq!(report.message, "Range bounds must be whole numbers, got [1, [...]]");
+}
+
+#[test]
+fn format_renders_each_value_as_str_does() {
+    let source = format!(
+        "{}{}",
+        VALUES,
+        r#"
+        for value in values { assert(format("{}", value) == str(value), str(value)); }
+        print(format("x = {}, y = {}", 1, [2, "b"]));
+        print(format("{{}} {{{}}} }}", "kept"));
+        print(format("none"));
+        "#
+    );
+    assert_eq!(run(&source), "x = 1, y = [2, \"b\"]\n{} {kept} }\nnone\n");
+}
+
+#[test]
+fn format_needs_a_value_for_each_placeholder() {
+    assert_eq!(error("format(\"{} {}\", 1);").message, "format() has 2 placeholders but got 1 value");
+    assert_eq!(error("format(\"{}\", 1, 2);").message, "format() has 1 placeholder but got 2 values");
+    assert_eq!(
+        error("format(\"a { b\");").message,
+        "format() found an unmatched '{'; use '{{' for a literal brace"
+    );
+}
+
+#[test]
+fn print_joins_its_arguments_with_spaces() {
+    assert_eq!(run("print(); print(1); print(\"a\", null, [1], \"\", 2);"), "\n1\na null [1]  2\n");
+}
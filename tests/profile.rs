@@ -0,0 +1,46 @@
+mod common;
+
+use common::{Captured, interpreter};
+use slimescript::Backend;
+
+const PROGRAM: &str = "
+function fib(n) { if n < 2 { return n; } return fib(n - 1) + fib(n - 2); }
+function busy() { let total = 0; for i in 0..2000 { total = total + i; } return total; }
+function main() { busy(); return fib(10) + len([1, 2]); }
+main();
+";
+
+#[test]
+fn calls_are_counted_and_timed_per_function() {
+    for backend in [Backend::TreeWalker, Backend::Vm] {
+        let mut interpreter = interpreter(&Captured::default()).with_backend(backend).with_profiling(true);
+        interpreter.run(PROGRAM).unwrap();
+        let profile = interpreter.take_profile();
+
+        let mut calls: Vec<_> = profile.iter().map(|stats| (stats.name.as_str(), stats.calls)).collect();
+        calls.sort();
+        assert_eq!(calls, [("busy", 1), ("fib", 177), ("len", 1), ("main", 1)], "{:?}", backend);
+        // the functions that spent longest in themselves come first
+        assert!(profile.windows(2).all(|pair| pair[0].exclusive >= pair[1].exclusive), "{:?}", profile);
+
+        let stats = |name| profile.iter().find(|stats| stats.name == name).unwrap();
+        let (main, fib, busy) = (stats("main"), stats("fib"), stats("busy"));
+        assert!(main.exclusive <= main.inclusive);
+        // fib's inclusive time counts its outermost call, not every level
+        // of the recursion again, so it fits inside main's
+        assert!(fib.inclusive + busy.inclusive <= main.inclusive, "{:?}", profile);
+        assert!(stats("len").span.is_none() && fib.span.is_some());
+
+        // taking the profile starts it again
+        assert_eq!(interpreter.take_profile(), []);
+        interpreter.run("fib(2);").unwrap();
+        assert_eq!(interpreter.take_profile().iter().map(|stats| stats.calls).collect::<Vec<_>>(), [3]);
+    }
+}
+
+#[test]
+fn nothing_is_recorded_without_profiling() {
+    let mut interpreter = interpreter(&Captured::default());
+    interpreter.run(PROGRAM).unwrap();
+    assert_eq!(interpreter.take_profile(), []);
+}
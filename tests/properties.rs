@@ -0,0 +1,294 @@
+//! Property tests run by `cargo test`, each the same check as the fuzz
+//! target of the same name in `fuzz/`, over fewer and less clever inputs
+//! but on every run.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use proptest::prelude::*;
+use slimescript::formatter::format_source;
+use slimescript::highlight::highlight;
+use slimescript::lexer::{
+    Lexer, LexerConfig, Span, TextEdit, Token, TokenType, escape_string, parse_number, tokens_to_source, unescape_string,
+};
+use slimescript::source::{Columns, LineIndex};
+use slimescript::value::format_number;
+use slimescript::{Interpreter, Value};
+
+/// Text made mostly of the characters the lexer treats specially, so that
+/// inputs hit numbers, strings, comments, and escapes far more often than
+/// arbitrary text would, mixed with arbitrary text.
+fn source() -> impl Strategy<Value = String> {
+    prop_oneof![
+        3 => "[a-z0-9_ .eExXbo\"'\\\\/*#{}()\\[\\];:,+\\-=<>!&|\n\r\té€😀\u{0}\u{a0}]{0,40}",
+        1 => any::<String>(),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn tokenize(source in source()) {
+        match Lexer::new(&source).tokenize() {
+            Ok(tokens) => {
+                prop_assert!(tokens.last().is_some_and(|token| token.token_type == TokenType::EOF));
+                check_tokens(&source, &tokens);
+            }
+            Err(error) => check_span(&source, error.span),
+        }
+
+        let mut lexer = Lexer::with_comments(&source);
+        let (tokens, errors) = lexer.tokenize_all();
+        check_tokens(&source, &tokens);
+        for error in errors.iter().chain(lexer.warnings()) {
+            check_span(&source, error.span);
+        }
+
+        let mut previous_end = 0;
+        for span in highlight(&source) {
+            prop_assert!(span.start == previous_end && span.start < span.end, "{:?} leaves a gap or overlaps", span);
+            prop_assert!(source.is_char_boundary(span.end));
+            previous_end = span.end;
+        }
+        prop_assert_eq!(previous_end, source.len());
+    }
+}
+
+proptest! {
+    #[test]
+    fn relex(old in source(), start: usize, end: usize, new_text in source()) {
+        let mut start = start % (old.len() + 1);
+        let mut end = end % (old.len() + 1);
+        if start > end {
+            (start, end) = (end, start);
+        }
+        // rather than throw the input away, move each end back to a character
+        while !old.is_char_boundary(start) {
+            start -= 1;
+        }
+        while !old.is_char_boundary(end) {
+            end -= 1;
+        }
+        let new = format!("{}{}{}", &old[..start], new_text, &old[end..]);
+        let edit = TextEdit { range: start..end, new_text };
+
+        let old_tokens = Lexer::new(&old).tokenize_all().0;
+        let relexed = Lexer::new(&new).relex(&old_tokens, edit.clone());
+        prop_assert_eq!(describe(&relexed), describe(&Lexer::new(&new).tokenize_all().0));
+
+        let old_tokens = Lexer::with_comments(&old).tokenize_all().0;
+        let relexed = Lexer::with_comments(&new).relex(&old_tokens, edit);
+        prop_assert_eq!(describe(&relexed), describe(&Lexer::with_comments(&new).tokenize_all().0));
+    }
+}
+
+/// Any float, with whole numbers, which print differently, as likely.
+fn number() -> impl Strategy<Value = f64> {
+    prop_oneof![
+        any::<u64>().prop_map(f64::from_bits),
+        any::<i64>().prop_map(|n| n as f64),
+        (-1e6..1e6f64).prop_map(f64::trunc),
+    ]
+}
+
+proptest! {
+    // a larger sample than the rest, as serialization relies on it
+    #![proptest_config(ProptestConfig::with_cases(2000))]
+
+    #[test]
+    fn numbers(n in number()) {
+        let text = format_number(n);
+        if !n.is_finite() {
+            prop_assert!(["nan", "inf", "-inf"].contains(&text.as_str()), "{} printed as {}", n, text);
+            return Ok(());
+        }
+        let magnitude = text.strip_prefix('-').unwrap_or(&text);
+        prop_assert_eq!(parse_number(magnitude), Some(n.abs()), "{} printed as {}", n, text);
+
+        let tokens = Lexer::new(magnitude).tokenize().expect("printed numbers lex");
+        prop_assert_eq!(tokens.len(), 2, "{} lexes as more than one token", magnitude);
+        prop_assert_eq!(&tokens[0].token_type, &TokenType::Number);
+        prop_assert_eq!(&tokens[0].value, magnitude);
+
+        // and what scripts rely on, `num(str(x)) == x`
+        let mut interpreter = Interpreter::new();
+        let shown = interpreter.call_function("str", &[Value::Number(n)]).unwrap();
+        let read = interpreter.call_function("num", &[shown]).unwrap();
+        prop_assert!(matches!(read, Value::Number(back) if back == n), "{} read back as {}", n, read);
+    }
+}
+
+proptest! {
+    #[test]
+    fn line_index(source in source()) {
+        for columns in [Columns::Chars, Columns::Utf16] {
+            let lines = LineIndex::new(&source).with_columns(columns);
+            for offset in (0..=source.len()).filter(|&offset| source.is_char_boundary(offset)) {
+                let (line, column) = lines.offset_to_position(offset);
+                let back = lines.position_to_offset(line, column);
+                // the `\n` of a `\r\n` is at the same position as its `\r`
+                let crlf = source[offset..].starts_with('\n') && source[..offset].ends_with('\r');
+                let expected = if crlf { offset - 1 } else { offset };
+                prop_assert_eq!(back, Some(expected), "offset {} is at {}:{}", offset, line, column);
+            }
+            prop_assert_eq!(lines.position_to_offset(0, 1), None);
+            prop_assert_eq!(lines.position_to_offset(lines.line_count() + 1, 1), None);
+        }
+
+        let lines = LineIndex::new(&source);
+        let (tokens, _) = Lexer::with_comments(&source).tokenize_all();
+        for token in tokens {
+            let position = lines.offset_to_position(token.span.start);
+            prop_assert_eq!(position, (token.span.line, token.span.column), "{:?}", token);
+        }
+    }
+}
+
+/// Text made of pieces that each lex, so that unlike `source` it nearly
+/// always lexes as a whole: names, number and string literals in their
+/// various spellings, operators, comments, and whitespace.
+fn pieces() -> impl Strategy<Value = String> {
+    let piece = prop_oneof![
+        "[a-zA-Z_][a-zA-Z0-9_]{0,6}",
+        "[0-9]{1,4}(\\.[0-9]{1,3})?([eE][+-]?[0-9]{1,2})?|0x[0-9a-fA-F]{1,4}|0b[01]{1,6}|[0-9]{1,3}_[0-9]{3}",
+        "\"([a-z é😀]|\\\\[nrt\"\\\\]|\\\\u\\{[0-9a-c][0-9a-f]{0,3}\\})*\"",
+        "[-+*/%=<>!&|.,;:(){}\\[\\]]|==|!=|<=|>=|&&|\\|\\||=>|\\.\\.=?|\\.\\.\\.",
+        "//[a-z =;é]*\n|///[a-z é]*\n",
+        "[ \t\n]|\r\n",
+    ];
+    prop::collection::vec(piece, 0..30).prop_map(|pieces| pieces.join(" "))
+}
+
+proptest! {
+    #[test]
+    fn round_trip(source in prop_oneof![source(), pieces()]) {
+        check_round_trip(&source, LexerConfig::default());
+        check_round_trip(&source, LexerConfig { hash_comments: true, ..LexerConfig::default() });
+    }
+}
+
+proptest! {
+    #[test]
+    fn format(source in prop_oneof![source(), pieces()]) {
+        check_format(&source);
+    }
+}
+
+#[test]
+fn every_program_in_the_repository_round_trips() {
+    let mut programs = Vec::new();
+    find_programs(Path::new(env!("CARGO_MANIFEST_DIR")), &mut programs);
+    assert!(!programs.is_empty());
+    for path in programs {
+        let source = fs::read_to_string(&path).unwrap();
+        let tokens = Lexer::with_trivia(&source).tokenize().unwrap_or_else(|error| panic!("{}: {}", path.display(), error));
+        assert_eq!(tokens_to_source(&tokens), source, "{}", path.display());
+        check_round_trip(&source, LexerConfig::default());
+        check_format(&source);
+    }
+}
+
+/// Checks that formatting `source` keeps its tokens, comments included,
+/// and that formatting the result again changes nothing.
+fn check_format(source: &str) {
+    let Ok(formatted) = format_source(source) else {
+        return;
+    };
+    let tokens = |source: &str| -> Vec<(TokenType, String)> {
+        let tokens = Lexer::with_comments(source).tokenize().unwrap_or_else(|error| panic!("{}\n{}", error, source));
+        tokens.into_iter().map(|token| (token.token_type, token.value)).collect()
+    };
+    assert_eq!(tokens(&formatted), tokens(source), "formatting changed the tokens of:\n{}", source);
+    assert_eq!(format_source(&formatted).unwrap(), formatted, "formatting twice changed:\n{}", source);
+}
+
+// the `.slime` files under `dir`, apart from build output
+fn find_programs(dir: &Path, programs: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() && !path.ends_with("target") && !path.ends_with(".git") {
+            find_programs(&path, programs);
+        } else if path.extension().is_some_and(|extension| extension == "slime") {
+            programs.push(path);
+        }
+    }
+}
+
+fn check_round_trip(source: &str, config: LexerConfig) {
+    let Ok(tokens) = Lexer::with_trivia(source).with_config(config).tokenize() else {
+        assert!(Lexer::new(source).with_config(config).tokenize().is_err());
+        return;
+    };
+    assert_eq!(tokens_to_source(&tokens), source);
+    for token in &tokens {
+        assert_eq!(token.raw(), &source[token.span.start..token.span.end], "{}", token);
+    }
+
+    let plain = Lexer::new(source).with_config(config).tokenize().expect("the lexer keeping trivia took it");
+    let kept: Vec<_> = tokens
+        .iter()
+        .filter(|token| !matches!(token.token_type, TokenType::Whitespace | TokenType::Comment))
+        .collect();
+    assert_eq!(kept.len(), plain.len());
+    for (kept, plain) in kept.iter().zip(&plain) {
+        assert_eq!((&kept.token_type, &kept.value, kept.span), (&plain.token_type, &plain.value, plain.span));
+    }
+}
+
+proptest! {
+    #[test]
+    fn escape(text in prop_oneof![source(), pieces()]) {
+        let literal = escape_string(&text);
+        prop_assert!(literal.chars().all(|c| c == ' ' || c.is_ascii_graphic()), "{}", literal);
+        prop_assert_eq!(lex_literal(&literal), Some(text.clone()));
+        prop_assert_eq!(unescape_string(&literal), Ok(text.clone()));
+
+        match unescape_string(&text) {
+            Ok(value) => prop_assert_eq!(lex_literal(&text), Some(value)),
+            Err(error) => {
+                prop_assert!(lex_literal(&text).is_none(), "{}", error);
+                prop_assert!((error.range.start < error.range.end || text.is_empty()) && error.range.end <= text.len(), "{}", error);
+                prop_assert!(text.is_char_boundary(error.range.start) && text.is_char_boundary(error.range.end));
+            }
+        }
+    }
+}
+
+// the value of `source` lexed as a single string literal and nothing else
+fn lex_literal(source: &str) -> Option<String> {
+    let tokens = Lexer::new(source).tokenize().ok()?;
+    match &tokens[..] {
+        [string, eof] if string.token_type == TokenType::String && eof.token_type == TokenType::EOF => {
+            (string.span.start == 0 && string.span.end == source.len()).then(|| string.value.clone())
+        }
+        _ => None,
+    }
+}
+
+/// The tokens' types, values, and positions, where they end included,
+/// which `Token` can't compare itself.
+fn describe(tokens: &[Token]) -> Vec<String> {
+    tokens
+        .iter()
+        .map(|token| format!("{} at {:?} to {:?}", token, token.span, token.end()))
+        .collect()
+}
+
+fn check_tokens(source: &str, tokens: &[Token]) {
+    let mut previous_end = 0;
+    for token in tokens {
+        check_span(source, token.span);
+        assert!(token.span.start >= previous_end, "{:?} overlaps the token before it", token);
+        let start = Span { end: token.span.start, ..token.span };
+        assert_eq!((token.line, token.column), start.end_position(source), "{:?} starts elsewhere", token);
+        assert_eq!((token.end_line, token.end_column), token.span.end_position(source), "{:?} ends elsewhere", token);
+        if token.token_type == TokenType::EOF {
+            assert_eq!(token.span.start..token.span.end, source.len()..source.len());
+        }
+        previous_end = token.span.end;
+    }
+}
+
+fn check_span(source: &str, span: Span) {
+    assert!(span.start <= span.end && span.end <= source.len(), "{:?} is outside the input", span);
+    assert!(source.is_char_boundary(span.start) && source.is_char_boundary(span.end));
+}
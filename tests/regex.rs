@@ -0,0 +1,64 @@
+mod common;
+
+use std::rc::Rc;
+
+use common::{error, run};
+use slimescript::builtins::RegexCache;
+
+#[test]
+fn matches_are_found_with_their_groups() {
+    let source = r#"
+        let date = "(\\d{4})-(\\d\\d)(-(\\d\\d))?";
+        print(regex_match(date, "due 2024-03"), regex_match("^\\d+$", "12a"));
+        print(regex_find("\\d+", "a 12 b 345"), regex_find("x", "abc"));
+        print(regex_find_all("\\d+", "a 12 b 345 ü 6"));
+        print(regex_captures(date, "on 2024-03, then"), regex_captures(date, "none"));
+        print(regex_captures("(?<word>é+)(!)", "ééé!"));
+    "#;
+    assert_eq!(
+        run(source),
+        "true false\n12 null\n[\"12\", \"345\", \"6\"]\n\
+         [\"2024-03\", \"2024\", \"03\", null, null] null\n[\"ééé!\", \"ééé\", \"!\"]\n"
+    );
+}
+
+#[test]
+fn replacements_refer_to_groups() {
+    let source = r#"
+        print(regex_replace("(\\w+)@(\\w+)", "ann@home, bo@work", "$2:$1"));
+        print(regex_replace("(?<n>\\d)", "a1b2", "<${n}$0>"));
+        print(regex_replace("o", "foo", "$$"), regex_replace("z", "foo", "y"));
+    "#;
+    assert_eq!(run(source), "home:ann, work:bo\na<11>b<22>\nf$$ foo\n");
+}
+
+#[test]
+fn an_invalid_pattern_is_an_error_at_the_call() {
+    let report = error("let ok = true;\nlet m = regex_find_all(\"a(b\", \"ab\");");
+    assert_eq!(report.code.id, "E0517");
+    assert!(report.message.starts_with("regex_find_all() was given an invalid pattern: "), "{}", report.message);
+    // regex's own explanation is kept
+    assert!(report.message.contains("unclosed group"), "{}", report.message);
+    assert_eq!((report.span.line, report.span.column), (2, 9));
+}
+
+#[test]
+fn compiled_patterns_are_reused_until_pushed_out() {
+    let mut cache = RegexCache::default();
+    let first = cache.get("a+b").unwrap();
+    assert!(Rc::ptr_eq(&first, &cache.get("a+b").unwrap()));
+    assert!(cache.get("(").is_err());
+
+    // using a pattern again keeps it while 31 others come and go
+    for i in 0..100 {
+        cache.get(&format!("x{}", i)).unwrap();
+        if i % 20 == 0 {
+            assert!(Rc::ptr_eq(&first, &cache.get("a+b").unwrap()));
+        }
+    }
+    // but 32 new ones push it out
+    for i in 0..32 {
+        cache.get(&format!("y{}", i)).unwrap();
+    }
+    assert!(!Rc::ptr_eq(&first, &cache.get("a+b").unwrap()));
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use std::io::Cursor;
+
+use common::Captured;
+use slimescript::repl::{self, EntryBuffer, Session};
+use slimescript::{Completion, Interpreter};
+
+#[test]
+fn entries_continue_until_brackets_close() {
+    let mut entry = EntryBuffer::default();
+    assert_eq!(entry.prompt(), "> ");
+    assert_eq!(entry.push_line("function f(a) {"), None);
+    assert!(entry.is_continuing());
+    assert_eq!(entry.prompt(), "... ");
+    assert_eq!(entry.push_line("  return [a,"), None);
+    assert_eq!(entry.push_line("    a];"), None);
+    assert_eq!(entry.push_line("}").as_deref(), Some("function f(a) {\n  return [a,\n    a];\n}\n"));
+    assert!(!entry.is_continuing());
+}
+
+#[test]
+fn entries_continue_open_strings_and_doc_comments() {
+    assert!(repl::is_incomplete("let s = \"one"));
+    assert!(repl::is_incomplete("/// Adds one."));
+    assert!(!repl::is_incomplete("let s = \"one\";"));
+    assert!(!repl::is_incomplete("// a comment"));
+}
+
+#[test]
+fn extra_closing_brackets_and_bad_input_end_the_entry() {
+    assert!(!repl::is_incomplete("}"));
+    assert!(!repl::is_incomplete("let x = (1));"));
+    assert!(!repl::is_incomplete("let x = @ {"));
+    assert!(!repl::is_incomplete("print(\"{\")"));
+}
+
+fn session(input: &str) -> (String, Completion) {
+    let output = Captured::default();
+    let mut interpreter = Interpreter::with_io(Cursor::new(input.to_string()), output.clone());
+    let completion = repl::run(&mut interpreter, false).unwrap();
+    (output.text(), completion)
+}
+
+#[test]
+fn sessions_run_whole_entries_and_echo_values() {
+    let (output, completion) = session("let x = 1\nfunction f(a) {\n  return a + x;\n}\nf(41)\n:quit\nprint(1)\n");
+    assert_eq!(output, "> > ... ... > 42\n> ");
+    assert_eq!(completion, Completion::Finished);
+}
+
+#[test]
+fn sessions_keep_going_after_errors_and_end_with_exit() {
+    let (output, completion) = session("undefined_name\n1 + 1\nexit(3)\n");
+    assert!(output.contains("Undefined variable 'undefined_name'"), "{}", output);
+    assert!(output.contains("> 2\n"), "{}", output);
+    assert_eq!(completion, Completion::Exit(3));
+}
+
+#[test]
+fn commands_are_one_line_whatever_they_contain() {
+    let (output, _) = session(":tokens {\n");
+    assert!(output.contains("LeftBrace"), "{}", output);
+}
+
+#[test]
+fn a_session_runs_entries_from_anywhere() {
+    let output = Captured::default();
+    let mut interpreter = Interpreter::with_io(Cursor::new(String::new()), output.clone());
+    let mut session = Session::new(false);
+    assert_eq!(session.enter(&mut interpreter, "let a = [1,\n 2]").unwrap(), None);
+    assert_eq!(session.enter(&mut interpreter, "a").unwrap(), None);
+    assert_eq!(session.enter(&mut interpreter, ":quit").unwrap(), Some(Completion::Finished));
+    assert_eq!(output.text(), "[1, 2]\n");
+}
+
+#[test]
+fn a_session_colors_errors_only_when_asked_to() {
+    for color in [false, true] {
+        let output = Captured::default();
+        let mut interpreter = Interpreter::with_io(Cursor::new(String::new()), output.clone());
+        Session::new(color).enter(&mut interpreter, "[][0]").unwrap();
+        let text = output.text();
+        assert!(text.contains("Index 0 out of bounds"), "{}", text);
+        assert_eq!(text.contains("\x1b[1;31m"), color, "{:?}", text);
+    }
+}
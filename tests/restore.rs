@@ -0,0 +1,140 @@
+mod common;
+
+use common::{Captured, interpreter};
+use slimescript::{Interpreter, SnapshotError, Value};
+
+// what both sides of a restart declare
+const DEFINITIONS: &str = r#"
+    class Point {
+        init(x, y) { this.x = x; this.y = y; }
+        sum() { return this.x + this.y; }
+    }
+    function double(n) { return n * 2; }
+"#;
+
+/// A fresh interpreter printing to `output` that has run `sources` in turn.
+fn started(output: &Captured, sources: &[&str]) -> Interpreter {
+    let mut interpreter = interpreter(output);
+    for source in sources {
+        interpreter.run(source).unwrap();
+    }
+    interpreter
+}
+
+/// What `check` prints in a fresh interpreter restored from the globals
+/// `source` leaves, as if the process had restarted in between.
+fn after_restart(source: &str, check: &str) -> String {
+    let bytes = started(&Captured::default(), &[DEFINITIONS, source]).snapshot().unwrap();
+    let output = Captured::default();
+    let mut restarted = started(&output, &[DEFINITIONS]);
+    restarted.restore(&bytes).unwrap();
+    restarted.run(check).unwrap();
+    output.text()
+}
+
+#[test]
+fn shared_arrays_maps_and_instances_stay_shared() {
+    let source = r#"
+        let list = [1, 2];
+        let same = list;
+        let nested = {inner: list, again: [list]};
+        let node = {name: "loop"};
+        node.next = node;
+        let point = Point(1, 2);
+        let points = [point, point];
+    "#;
+    let check = r#"
+        push(same, 3);
+        print(list, nested.inner, nested.again[0]);
+        print(is_same(node.next, node), node.next.next.name);
+        points[0].x = 10;
+        print(point.x, points[1].sum(), is_same(points[0], points[1]));
+        // equal but separate containers stay separate
+        print(is_same(list, [1, 2, 3]));
+    "#;
+    assert_eq!(
+        after_restart(source, check),
+        "[1, 2, 3] [1, 2, 3] [1, 2, 3]\ntrue loop\n10 12 true\nfalse\n"
+    );
+}
+
+#[test]
+fn every_kind_of_value_comes_back() {
+    let source = r#"
+        let nothing = null;
+        let flags = [true, false];
+        let numbers = [0, -0.0, 2.5, 9223372036854775807, 1e308 * 10, -1e308 * 10, 1e308 * 10 - 1e308 * 10];
+        let text = "tab\tquote\" snowman ☃";
+        let map = {"two words": 1, "": [], nested: {deep: {}}};
+        let point = Point(3, 4);
+        let twice = double;
+        let root = sqrt;
+        let shape = Point;
+        let handlers = {on_point: double};
+    "#;
+    let check = r#"
+        print(nothing, flags, text);
+        print(numbers);
+        print(map, typeof map.nested.deep);
+        print(point.sum(), typeof point, twice(4), root(16), shape(1, 1).sum(), handlers.on_point(5));
+        print(is_same(twice, double), is_same(root, sqrt), is_same(shape, Point));
+    "#;
+    assert_eq!(
+        after_restart(source, check),
+        "null [true, false] tab\tquote\" snowman ☃\n\
+         [0, 0, 2.5, 9223372036854775807, inf, -inf, nan]\n\
+         {\"two words\": 1, \"\": [], nested: {deep: {}}} map\n\
+         7 instance 8 4 2 10\n\
+         true true true\n"
+    );
+    // zero keeps its sign, which printing hides
+    let mut restarted = started(&Captured::default(), &[]);
+    restarted.restore(&started(&Captured::default(), &["let zero = -0.0;"]).snapshot().unwrap()).unwrap();
+    assert!(matches!(restarted.get_global("zero"), Some(Value::Number(n)) if n == 0.0 && n.is_sign_negative()));
+}
+
+#[test]
+fn definitions_are_saved_by_name_and_linked_again() {
+    let mut interpreter = started(&Captured::default(), &[DEFINITIONS, "let twice = double; let count = 1;"]);
+    let bytes = interpreter.snapshot().unwrap();
+    let text = String::from_utf8(bytes.clone()).unwrap();
+    // a declared function is left to the script, and another global holding
+    // it is saved as its name
+    assert!(!text.contains("\"double\":"), "{}", text);
+    assert!(text.contains("\"twice\":{\"global\":\"double\",\"type\":\"function\"}"), "{}", text);
+
+    // the restored global is whatever the new script calls `double`
+    let output = Captured::default();
+    let mut restarted = started(&output, &["function double(n) { return n + n + 1; }"]);
+    restarted.restore(&bytes).unwrap();
+    restarted.run("print(twice(2), count);").unwrap();
+    assert_eq!(output.text(), "5 1\n");
+
+    // a function no global holds can't be found again
+    interpreter.run("let handlers = [double, function(x) { return x; }];").unwrap();
+    assert_eq!(
+        interpreter.snapshot(),
+        Err(SnapshotError::Unnamed { path: "handlers[1]".to_string(), type_name: "function" })
+    );
+    interpreter.run("handlers = {\"on save\": {run: function() {}}};").unwrap();
+    let error = interpreter.snapshot().unwrap_err();
+    assert_eq!(error.to_string(), "The function at handlers[\"on save\"].run can't be saved, since no global holds it");
+}
+
+#[test]
+fn a_failed_restore_changes_nothing() {
+    let bytes = started(&Captured::default(), &[DEFINITIONS, "let twice = double; let count = 2;"]).snapshot().unwrap();
+    let output = Captured::default();
+    let mut restarted = started(&output, &["let count = 1;"]);
+    assert_eq!(
+        restarted.restore(&bytes),
+        Err(SnapshotError::Unlinked { name: "double".to_string(), type_name: "function".to_string() })
+    );
+    for bytes in [&b"not json"[..], b"{\"snapshot\":2,\"globals\":{},\"containers\":[]}", b"\xff"] {
+        assert!(matches!(restarted.restore(bytes), Err(SnapshotError::Invalid(_))), "{:?}", bytes);
+    }
+    let dangling = b"{\"snapshot\":1,\"globals\":{\"count\":{\"ref\":3}},\"containers\":[]}";
+    assert_eq!(restarted.restore(dangling), Err(SnapshotError::Invalid("a reference is to no container".to_string())));
+    assert_eq!(restarted.get_global("count"), Some(Value::from(1)));
+    assert_eq!(restarted.get_global("twice"), None);
+}
@@ -0,0 +1,113 @@
+mod common;
+
+use std::fs;
+use std::process::Command;
+
+use common::{Captured, interpreter};
+use slimescript::{Backend, SourceFile, TestResult};
+
+const TESTS: &str = r#"
+let runs = 0;
+function add(a, b) { return a + b; }
+test "addition works" { assert(add(1, 2) == 3); }
+test "wrong sum" { assert(add(1, 1) == 3, "one and one"); }
+test "crashes" { let items = []; print(items[1]); }
+test "counts" { runs = runs + 1; assert(runs == 1); }
+"#;
+
+/// The name of each test in `source` with the code it failed with, if it
+/// did, run by each backend, which must agree.
+fn results(source: &str) -> Vec<(String, Option<&'static str>)> {
+    let summarize = |results: Vec<TestResult>| {
+        results
+            .into_iter()
+            .map(|result| (result.name, result.error.map(|error| error.code.id)))
+            .collect::<Vec<_>>()
+    };
+    let output = Captured::default();
+    let walked = interpreter(&output).run_tests(SourceFile::new("tests.slime", source)).unwrap();
+    let compiled = interpreter(&output)
+        .with_backend(Backend::Vm)
+        .run_tests(SourceFile::new("tests.slime", source))
+        .unwrap();
+    let walked = summarize(walked);
+    assert_eq!(walked, summarize(compiled), "the vm ran the tests differently");
+    walked
+}
+
+#[test]
+fn every_test_runs_and_reports_how_it_went() {
+    let expected = [
+        ("addition works", None),
+        ("wrong sum", Some("E0508")),
+        ("crashes", Some("E0503")),
+        ("counts", None),
+    ];
+    let expected: Vec<_> = expected.iter().map(|(name, code)| (name.to_string(), *code)).collect();
+    assert_eq!(results(TESTS), expected);
+}
+
+#[test]
+fn tests_get_their_own_scope_over_the_globals() {
+    let source = r#"
+        let shared = 0;
+        test "first" { let mine = 1; shared = shared + 1; }
+        test "second" { print(mine); }
+        test "third" { assert(shared == 0, "assignments to globals are undone"); }
+    "#;
+    let codes: Vec<_> = results(source).into_iter().map(|(_, code)| code).collect();
+    assert_eq!(codes, [None, Some("E0501"), None]);
+}
+
+#[test]
+fn each_test_starts_from_the_globals_the_top_level_left() {
+    let source = r#"
+        let count = 1;
+        let items = [1, 2];
+        let same = items;
+        function bump() { count = count + 1; return count; }
+        test "a changes everything" {
+            assert(bump() == 2);
+            push(items, 3);
+            items[0] = 9;
+            same = [];
+        }
+        test "b sees the originals" {
+            assert(count == 1 && bump() == 2, "count was reset");
+            assert(items == [1, 2] && same == [1, 2], "arrays were reset");
+            assert(is_same(items, same), "globals that shared an array still do");
+        }
+    "#;
+    let codes: Vec<_> = results(source).into_iter().map(|(_, code)| code).collect();
+    assert_eq!(codes, [None, None]);
+}
+
+#[test]
+fn errors_outside_the_tests_stop_the_run() {
+    let output = Captured::default();
+    let result = interpreter(&output).run_tests(SourceFile::new("t.slime", "test \"never\" { } [][0];"));
+    assert_eq!(result.err().map(|error| error.report().code.id), Some("E0503"));
+}
+
+#[test]
+fn the_command_line_summarizes_and_fails_if_any_test_fails() {
+    let path = std::env::temp_dir().join(format!("slimescript-runner-{}.slime", std::process::id()));
+    fs::write(&path, TESTS).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_slimescript")).arg("test").arg(&path).output().unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout.contains("test addition works ... ok\n"), "{}", stdout);
+    assert!(stdout.contains("test wrong sum ... FAILED\n"), "{}", stdout);
+    assert!(stdout.contains("Assertion failed: one and one"), "{}", stdout);
+    assert!(stdout.contains(":5:20\n"), "the failure has no position: {}", stdout);
+    assert!(stdout.contains("test result: FAILED. 2 passed; 2 failed;"), "{}", stdout);
+
+    let path = std::env::temp_dir().join(format!("slimescript-runner-ok-{}.slime", std::process::id()));
+    fs::write(&path, "test \"fine\" { assert(true); }").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_slimescript")).arg("test").arg(&path).output().unwrap();
+    fs::remove_file(&path).unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout).unwrap().contains("test result: ok. 1 passed; 0 failed;"));
+}
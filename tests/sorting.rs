@@ -0,0 +1,104 @@
+mod common;
+
+use common::{error, run};
+
+const PLAYERS: &str = r#"
+    let players = [
+        { "name": "ada", "score": 30 },
+        { "name": "bob", "score": 10 },
+        { "name": "cy", "score": 20 },
+        { "name": "dee", "score": 10 },
+        { "name": "eve", "score": 20 },
+    ];
+    function names() { return map(players, function(p) { return p.name; }); }
+"#;
+
+fn run_with_players(source: &str) -> String {
+    run(&format!("{}{}", PLAYERS, source))
+}
+
+#[test]
+fn plain_sort_orders_numbers_and_strings() {
+    let source = r#"
+        let numbers = [3, -1, 2.5, 0];
+        sort(numbers);
+        let words = ["pear", "Apple", "fig"];
+        sort(words);
+        print(numbers, words);
+    "#;
+    assert_eq!(run(source), "[-1, 0, 2.5, 3] [\"Apple\", \"fig\", \"pear\"]\n");
+    assert_eq!(error("sort([1, \"a\"]);").code.id, "E0505");
+}
+
+#[test]
+fn a_comparator_sorts_maps_by_a_field() {
+    let source = r#"
+        sort(players, function(a, b) { return b.score - a.score; });
+        print(names());
+    "#;
+    assert_eq!(run_with_players(source), "[\"ada\", \"cy\", \"eve\", \"bob\", \"dee\"]\n");
+}
+
+#[test]
+fn sorts_are_stable() {
+    // bob and dee tie, as do cy and eve, and each pair keeps its order
+    let source = r#"
+        sort(players, function(a, b) { return a.score - b.score; });
+        print(names());
+        sort(players, function(a, b) { return 0; });
+        print(names());
+        sort_by_key(players, function(p) { return p.score; });
+        print(names());
+    "#;
+    let expected = "[\"bob\", \"dee\", \"cy\", \"eve\", \"ada\"]\n";
+    assert_eq!(run_with_players(source), expected.repeat(3));
+}
+
+#[test]
+fn sort_by_key_calls_the_key_function_once_per_element() {
+    let source = r#"
+        let calls = 0;
+        sort_by_key(players, function(p) { calls = calls + 1; return p.name; });
+        print(names(), calls);
+    "#;
+    assert_eq!(run_with_players(source), "[\"ada\", \"bob\", \"cy\", \"dee\", \"eve\"] 5\n");
+}
+
+#[test]
+fn errors_in_the_comparator_show_the_sort_frame() {
+    let report = error(&format!(
+        "{}{}",
+        PLAYERS,
+        "function broken(a, b) { return a.score - b.missing; }\nsort(players, broken);"
+    ));
+    assert_eq!(report.code.id, "E0504");
+    assert_eq!(report.notes.len(), 2);
+    assert!(report.notes[0].starts_with("in broken() called at"), "{:?}", report.notes);
+    assert!(report.notes[1].starts_with("in sort() called at"), "{:?}", report.notes);
+}
+
+#[test]
+fn an_error_mid_sort_leaves_the_array_as_it_was() {
+    let source = r#"
+        let compared = 0;
+        let outcome = "sorted";
+        try {
+            sort(players, function(a, b) {
+                compared = compared + 1;
+                if compared == 3 { throw "stop"; }
+                return a.score - b.score;
+            });
+        } catch (e) {
+            outcome = e;
+        }
+        print(outcome, compared, names());
+    "#;
+    assert_eq!(run_with_players(source), "stop 3 [\"ada\", \"bob\", \"cy\", \"dee\", \"eve\"]\n");
+}
+
+#[test]
+fn comparators_must_return_numbers() {
+    let report = error("sort([2, 1], function(a, b) { return a < b; });");
+    assert_eq!(report.code.id, "E0505");
+    assert_eq!(report.message, "sort() comparator must return a number, got bool");
+}
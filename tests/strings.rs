@@ -0,0 +1,77 @@
+mod common;
+
+use common::{error, run};
+
+#[test]
+fn string_builtins_work_on_characters_not_bytes() {
+    let source = r#"
+        let s = "  Grüße, 世界 😀  ";
+        print(upper(trim(s)), lower("ÀÉÎ"));
+        print(chars("añ😀"), len(chars("añ😀")));
+        print(substring("añ😀b", 1, 3), substring("日本語", 2, 3), substring("é", 0, 0) == "");
+        print(split("α,β,,γ", ","), join(["α", 1, null], "·"));
+        print(contains("naïve", "ï"), contains("naïve", "i"), replace("café café", "é", "e"));
+    "#;
+    assert_eq!(
+        run(source),
+        "GRÜSSE, 世界 😀 àéî\n[\"a\", \"ñ\", \"😀\"] 3\nñ😀 語 true\n[\"α\", \"β\", \"\", \"γ\"] α·1·null\ntrue false cafe cafe\n"
+    );
+}
+
+#[test]
+fn substring_bounds_count_characters() {
+    // four characters in eleven bytes
+    let report = error(r#"substring("añ😀b", 0, 5);"#);
+    assert_eq!(report.code.id, "E0503");
+    assert_eq!(report.message, "substring() end 5 is out of range for string of length 4");
+    assert_eq!(error(r#"substring("añ😀b", 3, 2);"#).message, "substring() start 3 is after end 2");
+    assert_eq!(error(r#"substring("añ", -1, 1);"#).message, "substring() expects a non-negative whole number, got -1");
+}
+
+#[test]
+fn string_builtins_name_themselves_in_type_errors() {
+    assert_eq!(error("upper(1);").message, "upper() expects a string, got number");
+    assert_eq!(error("join(\"ab\", \",\");").message, "join() expects an array, got string");
+    assert_eq!(error("split(\"ab\", \"\");").message, "split() separator must not be empty; use chars() instead");
+    assert_eq!(error("replace(\"ab\", \"\", \"x\");").message, "replace() pattern must not be empty");
+}
+
+#[test]
+fn strings_index_by_character() {
+    assert_eq!(run(r#"let s = "a😀b"; print(s[0], s[1], s[2], len(s[1]));"#), "a 😀 b 1\n");
+    assert_eq!(run(r#"print("a😀b"[1] == "😀", "日本語"[1..3], "a😀b"[1..]);"#), "true 本語 😀b\n");
+
+    let report = error(r#"let s = "a😀b"; print(s[3]);"#);
+    assert_eq!(report.code.id, "E0503");
+    assert_eq!(report.message, "Index 3 out of bounds for string of length 3");
+    assert_eq!((report.span.line, report.span.column), (1, 22));
+    assert_eq!(error(r#""😀😀"[-1];"#).message, "Index -1 out of bounds for string of length 2");
+}
+
+#[test]
+fn strings_cant_be_assigned_into() {
+    let report = error(r#"let s = "a😀b"; s[1] = "x";"#);
+    assert_eq!(report.code.id, "E0505");
+    assert_eq!(report.message, "Cannot assign to an index of a string: strings are immutable");
+}
+
+#[test]
+fn concatenating_pieces_builds_what_adding_them_would() {
+    // the report is compared with the one built by `+` after every line
+    let source = r#"
+        let added = "";
+        let pieces = [];
+        let same = true;
+        for i in 0..200 {
+            let line = "line " + str(i) + "\n";
+            if i % 3 == 0 { line = "ünï " + line; }
+            added = added + line;
+            push(pieces, line);
+            same = same && concat(pieces) == added;
+        }
+        print(same, len(concat(pieces)), concat(pieces) == join(pieces, ""));
+        print(concat([]) == "", concat(["a", 1, null, true, [2]]));
+    "#;
+    assert_eq!(run(source), "true 1958 true\ntrue a1nulltrue[2]\n");
+    assert_eq!(error("concat(\"abc\");").message, "concat() expects an array, got string");
+}
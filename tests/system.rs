@@ -0,0 +1,178 @@
+mod common;
+
+use std::fs;
+use std::rc::Rc;
+use std::time::Duration;
+
+use common::{Captured, interpreter};
+use slimescript::builtins::BuiltinSet;
+use slimescript::checker::check_with_builtins;
+use slimescript::clock::FakeClock;
+use slimescript::report::Report;
+use slimescript::{Backend, InterpreterConfig, parse};
+
+/// What `source` prints under `config` and the error that stopped it, if
+/// any, on each backend, which must agree.
+fn outcome_with(config: impl Fn() -> InterpreterConfig, source: &str) -> (String, Option<Report>) {
+    let mut outcomes = Vec::new();
+    for backend in [Backend::TreeWalker, Backend::Vm] {
+        let output = Captured::default();
+        let result = interpreter(&output).with_backend(backend).with_config(config()).run(source);
+        outcomes.push((output.text(), result.err().map(|error| error.report())));
+    }
+    let (text, error) = outcomes.remove(0);
+    assert_eq!(text, outcomes[0].0, "the backends printed differently");
+    assert_eq!(error.as_ref().map(|error| &error.message), outcomes[0].1.as_ref().map(|error| &error.message));
+    (text, error)
+}
+
+/// What `source` prints under `config`, which must run without an error.
+fn run_with(config: impl Fn() -> InterpreterConfig, source: &str) -> String {
+    match outcome_with(config, source) {
+        (output, None) => output,
+        (_, Some(error)) => panic!("{}[{}]: {}", error.kind, error.code.id, error.message),
+    }
+}
+
+/// The error that stops `source` under `config`, which must fail.
+fn error_with(config: impl Fn() -> InterpreterConfig, source: &str) -> Report {
+    outcome_with(config, source).1.expect("the program fails")
+}
+
+#[test]
+fn scripts_allowed_the_filesystem_can_read_and_write_files() {
+    let directory = std::env::temp_dir().join(format!("slimescript-fs-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let path = directory.join("notes.txt");
+    let missing = directory.join("missing.txt");
+    let source = format!(
+        r#"
+            let path = "{}";
+            print(file_exists("{}"));
+            write_file(path, "first\nsecond");
+            print(file_exists(path), split(read_file(path), "\n"));
+        "#,
+        path.display(),
+        missing.display()
+    );
+    let allowed = || InterpreterConfig { allow_fs: true, ..Default::default() };
+    assert_eq!(run_with(allowed, &source), "false\ntrue [\"first\", \"second\"]\n");
+    assert_eq!(fs::read_to_string(&path).unwrap(), "first\nsecond");
+
+    let error = error_with(allowed, &format!("read_file(\"{}\");", missing.display()));
+    assert_eq!(error.code.id, "E0519");
+    assert!(error.message.starts_with("read_file() could not read '"), "{}", error.message);
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn the_filesystem_is_off_limits_by_default() {
+    let path = std::env::temp_dir().join(format!("slimescript-denied-{}.txt", std::process::id()));
+    for call in ["read_file(path)", "write_file(path, \"x\")", "file_exists(path)"] {
+        let source = format!("let path = \"{}\";\n{};", path.display(), call);
+        let error = error_with(InterpreterConfig::default, &source);
+        assert_eq!((error.code.id, error.message.as_str()), ("E0510", "filesystem access disabled"), "{}", call);
+    }
+    assert!(!path.exists(), "write_file() wrote while denied");
+}
+
+#[test]
+fn environment_variables_are_off_limits_by_default() {
+    let source = "print(type(env(\"PATH\")), env(\"SLIMESCRIPT_SURELY_UNSET_VARIABLE\"));";
+    let error = error_with(InterpreterConfig::default, source);
+    assert_eq!((error.code.id, error.message.as_str()), ("E0510", "environment access disabled"));
+    let allowed = || InterpreterConfig { allow_env: true, ..Default::default() };
+    assert_eq!(run_with(allowed, source), "string null\n");
+}
+
+#[test]
+fn time_builtins_read_the_configured_clock() {
+    // 2024-02-29 12:34:56 UTC
+    let fake = || InterpreterConfig {
+        clock: Some(Rc::new(FakeClock::starting_at(Duration::from_secs(1_709_210_096)))),
+        ..Default::default()
+    };
+    let source = r#"
+        let start = clock();
+        sleep(1.5);
+        sleep(0);
+        print(start, clock() - start, now());
+        print(format_time(now(), "%Y-%m-%d %H:%M:%S (100%%)"));
+        print(format_time(0, "%Y/%m/%d"), format_time(-1, "%Y-%m-%d %H:%M:%S"));
+    "#;
+    let expected = "0 1.5 1709210097.5\n2024-02-29 12:34:57 (100%)\n1970/01/01 1969-12-31 23:59:59\n";
+    assert_eq!(run_with(fake, source), expected);
+
+    let error = error_with(fake, "sleep(-1);");
+    assert_eq!((error.code.id, error.message.as_str()), ("E0517", "sleep() expects a non-negative number of seconds, got -1"));
+
+    // the first and last seconds with a four-digit year
+    let source = "print(format_time(-62167219200, \"%Y-%m-%d\"), format_time(253402300799.5, \"%Y-%m-%d %H:%M:%S\"));";
+    assert_eq!(run_with(fake, source), "0000-01-01 9999-12-31 23:59:59\n");
+    let times = [
+        ("253402300800", "253402300800"),
+        ("-1e300", "-1e300"),
+        ("1e308 * 10", "inf"),
+        ("1e308 * 10 - 1e308 * 10", "nan"),
+    ];
+    for (time, shown) in times {
+        let error = error_with(fake, &format!("format_time({}, \"%Y\");", time));
+        assert_eq!(error.code.id, "E0517");
+        assert!(error.message.ends_with(&format!(", got {}", shown)), "{}", error.message);
+    }
+}
+
+#[test]
+fn sleeping_past_the_time_limit_stops_the_program() {
+    let limited = || InterpreterConfig {
+        max_duration: Some(Duration::from_millis(50)),
+        clock: Some(Rc::new(FakeClock::default())),
+        ..Default::default()
+    };
+    let error = error_with(limited, "print(1);\nsleep(60);\nprint(2);");
+    assert_eq!(error.code.id, "E0507");
+    assert_eq!(error.message, "Execution budget exceeded: ran longer than 50ms");
+    assert_eq!(error.span.line, 2);
+}
+
+#[test]
+fn denied_builtins_fail_where_they_are_called() {
+    let builtins = BuiltinSet::default().deny("input").deny("sleep");
+    let sandbox = || InterpreterConfig { builtins: builtins.clone(), ..Default::default() };
+    let error = error_with(sandbox, "print(\"ready\");\nlet name = input(\"Name: \");");
+    assert_eq!(error.code.id, "E0510");
+    assert_eq!(error.message, "builtin 'input' is not available in this environment");
+    assert_eq!((error.span.line, error.span.column), (2, 12));
+    // the name is still defined, so a script can find out what it may use
+    let source = "try { sleep(1); } catch (e) { print(e.code, e.message); }\nprint(typeof sleep, max(1, 2));";
+    assert_eq!(
+        run_with(sandbox, source),
+        "E0510 builtin 'sleep' is not available in this environment\nfunction 2\n"
+    );
+    // unlike a name that isn't defined at all
+    assert_eq!(error_with(sandbox, "nope();").code.id, "E0501");
+
+    let builtins = BuiltinSet::default().allow_only_pure_math().allow("print");
+    let math = || InterpreterConfig { builtins: builtins.clone(), ..Default::default() };
+    assert_eq!(run_with(math, "print(sqrt(16), abs(-2));"), "4 2\n");
+    assert_eq!(error_with(math, "print(len([]));").message, "builtin 'len' is not available in this environment");
+}
+
+#[test]
+#[should_panic(expected = "can't deny 'read_fil', which isn't a builtin")]
+fn denying_a_name_that_isnt_a_builtin_panics() {
+    let _ = BuiltinSet::default().deny("read_file").deny("read_fil");
+}
+
+#[test]
+fn the_checker_warns_about_denied_builtins_before_running() {
+    let builtins = BuiltinSet::default().deny("input");
+    let program = parse("let a = input(\"?\");\nfunction f(input) { return input; }\nprint(a, f(1));").unwrap();
+    let warnings: Vec<_> = check_with_builtins(&program, &builtins)
+        .into_iter()
+        .map(|diagnostic| (diagnostic.code.id, diagnostic.span.line, diagnostic.message))
+        .collect();
+    // a parameter of the same name isn't the builtin
+    assert_eq!(warnings, [("W0004", 1, "Builtin 'input' is not available in this environment".to_string())]);
+    assert_eq!(check_with_builtins(&program, &BuiltinSet::default()), []);
+}
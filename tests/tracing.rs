@@ -0,0 +1,56 @@
+mod common;
+
+use common::{Captured, interpreter};
+use slimescript::{InterpreterConfig, SourceFile, Trace};
+
+const BRANCHING: &str = "\
+let x = 2;
+if x > 1 {
+    print(\"big\");
+} else {
+    print(\"small\");
+}
+let label = \"\";
+for i in 0..2 {
+    x = x + i;
+}
+";
+
+/// What running `source` as `main.slime` prints and traces, with the
+/// trace stopped after `limit` lines, if given.
+fn traced(source: &str, limit: Option<usize>) -> (String, String) {
+    let (output, trace) = (Captured::default(), Captured::default());
+    let sink = match limit {
+        Some(limit) => Trace::new(trace.clone()).with_limit(limit),
+        None => Trace::new(trace.clone()),
+    };
+    let config = InterpreterConfig { trace: Some(sink), ..Default::default() };
+    interpreter(&output).with_config(config).eval_file(SourceFile::new("main.slime", source)).unwrap();
+    (output.text(), trace.text())
+}
+
+#[test]
+fn each_statement_run_is_traced_with_what_it_stored() {
+    let (output, trace) = traced(BRANCHING, None);
+    // the trace goes to its own sink, not among what the program prints
+    assert_eq!(output, "big\n");
+    assert_eq!(
+        trace,
+        "\
+main.slime:1:1: let x = 2; -> 2
+main.slime:2:1: if x > 1 {
+main.slime:3:5: print(\"big\");
+main.slime:7:1: let label = \"\"; -> \"\"
+main.slime:8:1: for i in 0..2 {
+main.slime:9:5: x = x + i; -> 2
+main.slime:9:5: x = x + i; -> 3
+"
+    );
+}
+
+#[test]
+fn a_limit_stops_the_trace_but_not_the_program() {
+    let (output, trace) = traced(BRANCHING, Some(2));
+    assert_eq!(output, "big\n");
+    assert_eq!(trace, "main.slime:1:1: let x = 2; -> 2\nmain.slime:2:1: if x > 1 {\ntrace stopped after 2 lines\n");
+}
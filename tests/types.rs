@@ -0,0 +1,56 @@
+use slimescript::checker::check_source;
+use slimescript::{parse, typechecker};
+
+/// The type mismatches in `source`, as their lines and messages.
+fn mismatches(source: &str) -> Vec<(usize, String)> {
+    typechecker::check(&parse(source).unwrap())
+        .into_iter()
+        .map(|diagnostic| {
+            assert_eq!(diagnostic.code.id, "E0300");
+            (diagnostic.span.line, diagnostic.message)
+        })
+        .collect()
+}
+
+#[test]
+fn arguments_of_the_wrong_type_are_reported() {
+    let source = "function area(width: number, height: number): number {\n    return width * height;\n}\nprint(area(2, \"3\"));\nprint(area(true, 3));";
+    assert_eq!(
+        mismatches(source),
+        [
+            (4, "Parameter 'height' of 'area' expects number but is given string".to_string()),
+            (5, "Parameter 'width' of 'area' expects number but is given bool".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn returned_values_of_the_wrong_type_are_reported() {
+    let source = "function name(): string {\n    return 1;\n}\nfunction nothing(): number {\n    return;\n}\nprint(name(), nothing());";
+    assert_eq!(
+        mismatches(source),
+        [
+            (2, "'name' must return string but returns number".to_string()),
+            (5, "'nothing' must return number but returns null".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn a_well_typed_program_gets_no_diagnostics() {
+    let source = r#"
+        function greet(name: string, times: number): string {
+            let greeting: string = "hi " + name;
+            if times > 1 {
+                return greeting + "!";
+            }
+            return greeting;
+        }
+        let count: number = 2;
+        count += 1;
+        let untyped = greet("ada", count);
+        print(greet(untyped, 1));
+    "#;
+    assert_eq!(mismatches(source), []);
+    assert!(check_source(source).is_empty(), "{:?}", check_source(source));
+}
@@ -0,0 +1,116 @@
+mod common;
+
+use std::fs;
+
+use common::{Captured, error, interpreter, on_big_stack, run};
+use slimescript::builtins::BuiltinSet;
+use slimescript::{Arity, Backend, InterpreterConfig, Value};
+
+#[test]
+fn arrays_and_maps_compare_by_contents() {
+    assert_eq!(run("print([1, [2, {x: 3}]] == [1, [2, {x: 3}]]);"), "true\n");
+    assert_eq!(run("print([1, [2]] == [1, [3]], [1] == [1, 1]);"), "false false\n");
+    assert_eq!(run("print({a: 1, b: 2} == {b: 2, a: 1}, {a: 1} == {a: 1, b: 2});"), "true false\n");
+    assert_eq!(run("print([1] == {}, null == false, 1 == \"1\");"), "false false false\n");
+}
+
+#[test]
+fn cyclic_values_compare_without_overflowing() {
+    let source = "
+        let a = []; push(a, a);
+        let b = []; push(b, b);
+        let m = {}; m.self = m;
+        let n = {}; n.self = n;
+        let c = [1]; push(c, c);
+        let d = [2]; push(d, d);
+        print(a == b, m == n, c == d, a == m);
+    ";
+    assert_eq!(run(source), "true true false false\n");
+}
+
+#[test]
+fn deeply_nested_values_compare_without_overflowing() {
+    let source = "
+        let a = []; let b = [];
+        for i in 0..100000 { a = [a]; b = [b]; }
+        print(a == b);
+        b = [b];
+        print(a == b);
+    ";
+    assert_eq!(on_big_stack(|| run(source)), "true\nfalse\n");
+}
+
+#[test]
+fn copy_makes_independent_arrays_and_maps() {
+    let source = "
+        let a = [1, {x: [2]}];
+        let b = copy(a);
+        b[1].x[0] = 9;
+        push(b, 3);
+        print(a, b, a == copy(a), is_same(a, copy(a)));
+    ";
+    assert_eq!(run(source), "[1, {x: [2]}] [1, {x: [9]}, 3] true false\n");
+}
+
+#[test]
+fn copy_keeps_sharing_and_cycles() {
+    let source = "
+        let shared = [1];
+        let a = [shared, shared];
+        push(a, a);
+        let b = copy(a);
+        print(is_same(b[0], b[1]), is_same(b[0], shared), is_same(b[2], b), is_same(b[2], a));
+        print(b);
+    ";
+    assert_eq!(run(source), "true false true false\n[[1], [1], [...]]\n");
+}
+
+#[test]
+fn copy_of_deeply_nested_value_does_not_overflow() {
+    let source = "
+        let a = [];
+        for i in 0..100000 { a = [a]; }
+        let b = copy(a);
+        print(a == b, is_same(a, b));
+    ";
+    assert_eq!(on_big_stack(|| run(source)), "true false\n");
+}
+
+#[test]
+fn typeof_names_every_kind_of_value() {
+    let directory = std::env::temp_dir().join(format!("slimescript-typeof-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    fs::write(directory.join("lib.slime"), "export let x = 1;").unwrap();
+    let source = r#"
+        import "lib.slime" as lib;
+        class Point { init() { this.x = 0; } }
+        function f() {}
+        let values = [null, true, 1.5, "s", [], {}, f, function() {}, len, host, lib, Point, Point()];
+        let names = [];
+        for value in values { push(names, typeof value); }
+        print(join(names, " "));
+        print(typeof typeof 1, typeof(1) == type(1));
+    "#;
+    for backend in [Backend::TreeWalker, Backend::Vm] {
+        let output = Captured::default();
+        let mut interpreter = interpreter(&output).with_backend(backend).with_script_path(directory.join("main.slime"));
+        interpreter.register_fn("host", Arity::Exact(0), |_| Ok(Value::Null));
+        interpreter.run(source).unwrap();
+        assert_eq!(
+            output.text(),
+            "null bool number string array map function function function function module class instance\nstring true\n"
+        );
+    }
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn typeof_needs_no_builtins_and_agrees_with_errors() {
+    let config = InterpreterConfig { builtins: BuiltinSet::default().allow_only_pure_math(), ..Default::default() };
+    let mut interpreter = interpreter(&Captured::default()).with_config(config);
+    interpreter.run("let kinds = typeof [] + \" \" + typeof sqrt;").unwrap();
+    assert_eq!(interpreter.get_global("kinds"), Some(Value::from("array function")));
+    // errors describe operands by the same names
+    assert_eq!(error("class C {} C() - 1;").message, "Cannot apply '-' to instance and number");
+    assert_eq!(error("let f = function() {}; f + [];").message, "Cannot apply '+' to function and array");
+}
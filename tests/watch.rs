@@ -0,0 +1,75 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use slimescript::watch::{ChangeSource, Poller, watch};
+
+/// Reports made-up changes in order, remembering which files it was asked
+/// to watch each time, then says to stop.
+struct Script {
+    changes: VecDeque<Vec<PathBuf>>,
+    watched: Vec<Vec<PathBuf>>,
+}
+
+impl ChangeSource for Script {
+    fn wait(&mut self, files: &[PathBuf]) -> Option<Vec<PathBuf>> {
+        self.watched.push(files.to_vec());
+        self.changes.pop_front()
+    }
+}
+
+#[test]
+fn each_change_reruns_the_script_and_watches_what_it_now_imports() {
+    let (main, lib) = (PathBuf::from("main.slime"), PathBuf::from("lib.slime"));
+    let mut script = Script {
+        changes: VecDeque::from([vec![main.clone()], vec![lib.clone()]]),
+        watched: Vec::new(),
+    };
+    let mut runs = Vec::new();
+    watch(&mut script, |changed| {
+        runs.push(changed.to_vec());
+        // the second run finds that main.slime has started importing lib.slime
+        match runs.len() {
+            1 => vec![main.clone()],
+            _ => vec![main.clone(), lib.clone()],
+        }
+    });
+    assert_eq!(runs, [vec![], vec![main.clone()], vec![lib.clone()]]);
+    assert_eq!(script.watched, [vec![main.clone()], vec![main.clone(), lib.clone()], vec![main, lib]]);
+}
+
+#[test]
+fn the_poller_reports_a_burst_of_saves_once_they_settle() {
+    let directory = std::env::temp_dir().join(format!("slimescript-watch-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let (main, lib) = (directory.join("main.slime"), directory.join("lib.slime"));
+    fs::write(&main, "").unwrap();
+    fs::write(&lib, "").unwrap();
+    let files = [main.clone(), lib.clone()];
+    let settle = Duration::from_millis(300);
+    let mut poller = Poller::new(Duration::from_millis(5), settle);
+
+    // the length changes with each save, so they're seen even where
+    // modification times are coarse
+    let saving = thread::spawn({
+        let main = main.clone();
+        move || {
+            for save in 1..=3 {
+                thread::sleep(Duration::from_millis(20));
+                fs::write(&main, "x".repeat(save)).unwrap();
+            }
+            Instant::now()
+        }
+    });
+    assert_eq!(poller.wait(&files), Some(vec![main.clone()]));
+    let last_save = saving.join().unwrap();
+    assert!(last_save.elapsed() >= settle, "reported before the saves settled");
+    assert_eq!(fs::read_to_string(&main).unwrap(), "xxx");
+
+    // removing a file is a change too
+    fs::remove_file(&lib).unwrap();
+    assert_eq!(poller.wait(&files), Some(vec![lib]));
+    fs::remove_dir_all(&directory).unwrap();
+}
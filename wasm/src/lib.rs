@@ -0,0 +1,117 @@
+//! JavaScript bindings for running slimescript in a browser, e.g. for a
+//! playground. Errors come back as `{ code, message, line, column }`
+//! objects, where `code` is like "E0200".
+
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+use std::time::Duration;
+
+use js_sys::{Date, Object, Reflect};
+use slimescript::clock::Clock;
+use slimescript::lexer::Lexer;
+use slimescript::{Error, Interpreter, InterpreterConfig, highlight, printer};
+use wasm_bindgen::prelude::*;
+
+/// How many steps a program may take before `run` stops it, so a runaway
+/// loop can't hang the page. Time limits need a clock the interpreter
+/// can't read on wasm32-unknown-unknown.
+const MAX_STEPS: u64 = 10_000_000;
+
+/// Runs `source` in a fresh interpreter, returning
+/// `{ output, error }`: everything it printed, and the error that stopped
+/// it or `null`.
+#[wasm_bindgen]
+pub fn run(source: &str) -> JsValue {
+    let output = SharedBuffer::default();
+    let config = InterpreterConfig {
+        max_steps: Some(MAX_STEPS),
+        clock: Some(Rc::new(JsClock)),
+        ..InterpreterConfig::default()
+    };
+    let mut interpreter = Interpreter::with_io(io::empty(), output.clone()).with_config(config);
+    let error = match interpreter.run(source) {
+        Ok(_) => JsValue::NULL,
+        Err(error) => error_object(&error),
+    };
+
+    let result = Object::new();
+    let printed = String::from_utf8_lossy(&output.0.borrow()).into_owned();
+    set(&result, "output", &JsValue::from_str(&printed));
+    set(&result, "error", &error);
+    result.into()
+}
+
+/// The tokens of `source` as the JSON `--tokens --json` prints. Throws the
+/// error object if it doesn't lex.
+#[wasm_bindgen]
+pub fn tokenize_json(source: &str) -> Result<String, JsValue> {
+    let tokens = Lexer::new(source)
+        .tokenize()
+        .map_err(|error| error_object(&Error::Lex(error)))?;
+    Ok(printer::tokens_json(&tokens))
+}
+
+/// The syntax tree of `source` as the JSON `--ast --json --spans` prints.
+/// Throws the error object if it doesn't parse.
+#[wasm_bindgen]
+pub fn parse_json(source: &str) -> Result<String, JsValue> {
+    let program = slimescript::parse(source).map_err(|error| error_object(&error))?;
+    Ok(printer::ast_json(&program, true))
+}
+
+/// `source` as HTML with each token in a span whose class is its kind,
+/// like `keyword` or `string`, for the editor to show under the text.
+#[wasm_bindgen]
+pub fn highlight_html(source: &str) -> String {
+    highlight::highlight_html(source)
+}
+
+fn error_object(error: &Error) -> JsValue {
+    let report = error.report();
+    let object = Object::new();
+    set(&object, "code", &JsValue::from_str(report.code.id));
+    set(&object, "message", &JsValue::from_str(&report.message));
+    set(&object, "line", &JsValue::from(report.span.line as u32));
+    set(&object, "column", &JsValue::from(report.span.column as u32));
+    object.into()
+}
+
+fn set(object: &Object, key: &str, value: &JsValue) {
+    Reflect::set(object, &JsValue::from_str(key), value).expect("setting a property on a plain object succeeds");
+}
+
+/// Where `print` writes to, kept by `run` to read back afterwards.
+#[derive(Clone, Default)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The browser's clock, since `std::time` panics on wasm32-unknown-unknown.
+#[derive(Debug)]
+struct JsClock;
+
+impl Clock for JsClock {
+    fn monotonic(&self) -> Duration {
+        self.now()
+    }
+
+    fn now(&self) -> Duration {
+        Duration::from_secs_f64(Date::now() / 1000.0)
+    }
+
+    // a page can't block its thread, so this spins until the time is up
+    fn sleep(&self, duration: Duration) {
+        let end = Date::now() + duration.as_secs_f64() * 1000.0;
+        while Date::now() < end {}
+    }
+}
@@ -0,0 +1,54 @@
+//! Run with `cargo test --target wasm32-unknown-unknown` from `wasm/`,
+//! which needs `wasm-bindgen-test-runner` from `wasm-bindgen-cli` and
+//! Node.js.
+
+use js_sys::Reflect;
+use slimescript_wasm::{highlight_html, parse_json, run, tokenize_json};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+fn get(object: &JsValue, key: &str) -> JsValue {
+    Reflect::get(object, &JsValue::from_str(key)).expect("the property can be read")
+}
+
+#[wasm_bindgen_test]
+fn run_captures_what_a_program_prints() {
+    let result = run("let greeting = \"hi\"; print(greeting); print(1 + 2);");
+    assert_eq!(get(&result, "output").as_string().as_deref(), Some("hi\n3\n"));
+    assert!(get(&result, "error").is_null());
+}
+
+#[wasm_bindgen_test]
+fn run_reports_errors_with_their_code_and_position() {
+    let result = run("print(\"before\");\nprint(missing);");
+    assert_eq!(get(&result, "output").as_string().as_deref(), Some("before\n"));
+    let error = get(&result, "error");
+    assert_eq!(get(&error, "code").as_string().as_deref(), Some("E0501"));
+    assert_eq!(get(&error, "line").as_f64(), Some(2.0));
+    assert_eq!(get(&error, "column").as_f64(), Some(7.0));
+}
+
+#[wasm_bindgen_test]
+fn run_stops_runaway_loops() {
+    let result = run("while true { }");
+    assert!(!get(&result, "error").is_null());
+}
+
+#[wasm_bindgen_test]
+fn time_builtins_read_the_browser_clock() {
+    let result = run("print(now() > 0); sleep(0.001);");
+    assert_eq!(get(&result, "output").as_string().as_deref(), Some("true\n"));
+}
+
+#[wasm_bindgen_test]
+fn tokens_and_trees_come_back_as_json() {
+    assert!(tokenize_json("let x = 1;").unwrap().starts_with('['));
+    assert!(parse_json("let x = 1;").unwrap().starts_with('['));
+    let error = parse_json("let = 1;").unwrap_err();
+    assert_eq!(get(&error, "code").as_string().as_deref(), Some("E0100"));
+}
+
+#[wasm_bindgen_test]
+fn highlighting_wraps_tokens_in_spans() {
+    assert!(highlight_html("let x = 1;").contains("<span class=\"keyword\">let</span>"));
+}